@@ -281,8 +281,10 @@ fn bench_memory_usage(c: &mut Criterion) {
                         limits: Limits {
                             max_depth: 10000,
                             max_anchors: 10000,
+                            max_anchor_name_len: 1024,
                             max_document_size: 100_000_000, // 100MB
                             max_string_length: 10_000_000,
+                            max_scalar_len: 10_000_000,
                             max_alias_depth: 100,
                             max_collection_size: 100000,
                             max_complexity_score: 1_000_000,
@@ -322,6 +322,8 @@ fn bench_comment_construction_performance(c: &mut Criterion) {
                 value,
                 comments,
                 style: Style::default(),
+                keys: Default::default(),
+                items: Vec::new(),
             };
 
             std::hint::black_box(commented);
@@ -340,6 +342,8 @@ fn bench_comment_construction_performance(c: &mut Criterion) {
             value,
             comments,
             style: Style::default(),
+            keys: Default::default(),
+            items: Vec::new(),
         };
 
         b.iter(|| {
@@ -0,0 +1,177 @@
+#![allow(clippy::uninlined_format_args)]
+#![allow(clippy::needless_raw_string_hashes)]
+
+//! Parsing throughput compared with `serde_yaml` and `yaml-rust2`
+//!
+//! Run with `cargo bench --features bench --bench comparison`. Besides the
+//! generated corpora below, every `.yaml`/`.yml` file under `benches/corpus/`
+//! is benchmarked; `scripts/download-bench-corpus.sh` fills that directory
+//! with public YAML files.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rust_yaml::Yaml;
+use serde::Deserialize;
+use std::fmt::Write;
+use std::path::Path;
+
+/// A small application config, the most common YAML workload
+fn small_config() -> String {
+    r#"
+server:
+  host: 0.0.0.0
+  port: 8080
+  tls: {enabled: true, cert: /etc/tls/cert.pem}
+database:
+  url: postgres://db:5432/app
+  pool: 16
+features: [auth, metrics, tracing]
+log_level: info
+"#
+    .to_string()
+}
+
+/// A bundle of Kubernetes-style manifests separated by `---`
+fn large_manifests(count: usize) -> String {
+    let mut out = String::new();
+    for i in 0..count {
+        write!(
+            out,
+            r#"---
+apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: service-{i}
+  labels: {{app: service-{i}, tier: backend}}
+spec:
+  replicas: 3
+  template:
+    spec:
+      containers:
+        - name: app
+          image: "registry.example.com/service-{i}:1.{i}.0"
+          ports:
+            - containerPort: 8080
+          env:
+            - name: RUST_LOG
+              value: info
+          resources:
+            limits: {{cpu: 500m, memory: 256Mi}}
+"#
+        )
+        .unwrap();
+    }
+    out
+}
+
+/// Mappings nested `depth` levels deep
+fn deep_nesting(depth: usize) -> String {
+    let mut out = String::new();
+    for level in 0..depth {
+        writeln!(out, "{}level{}:", "  ".repeat(level), level).unwrap();
+    }
+    writeln!(out, "{}leaf: value", "  ".repeat(depth)).unwrap();
+    out
+}
+
+/// Many aliases referring to a few anchored mappings
+fn alias_heavy(count: usize) -> String {
+    let mut out = String::from(
+        "defaults: &defaults {timeout: 30, retries: 3}\nlimits: &limits [1, 2, 3]\nitems:\n",
+    );
+    for i in 0..count {
+        writeln!(
+            out,
+            "  - {{name: item{i}, config: *defaults, limits: *limits}}"
+        )
+        .unwrap();
+    }
+    out
+}
+
+/// Files downloaded into `benches/corpus/`, if any
+fn downloaded_corpus() -> Vec<(String, String)> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("benches/corpus");
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            matches!(
+                path.extension().and_then(|e| e.to_str()),
+                Some("yaml" | "yml")
+            )
+        })
+        .filter_map(|path| {
+            let name = path.file_name()?.to_string_lossy().into_owned();
+            let text = std::fs::read_to_string(&path).ok()?;
+            // Skip files that any of the libraries rejects, so every
+            // benchmark measures the same successful work
+            parses_everywhere(&text).then_some((name, text))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+fn parse_rust_yaml(input: &str) -> usize {
+    Yaml::new().load_all_str(input).map_or(0, |docs| docs.len())
+}
+
+fn parse_serde_yaml(input: &str) -> usize {
+    serde_yaml::Deserializer::from_str(input)
+        .map(serde_yaml::Value::deserialize)
+        .take_while(Result::is_ok)
+        .count()
+}
+
+fn parse_yaml_rust2(input: &str) -> usize {
+    yaml_rust2::YamlLoader::load_from_str(input).map_or(0, |docs| docs.len())
+}
+
+fn parses_everywhere(input: &str) -> bool {
+    Yaml::new().load_all_str(input).is_ok()
+        && serde_yaml::Deserializer::from_str(input)
+            .all(|doc| serde_yaml::Value::deserialize(doc).is_ok())
+        && yaml_rust2::YamlLoader::load_from_str(input).is_ok()
+}
+
+fn bench_corpus(c: &mut Criterion, group_name: &str, inputs: &[(String, String)]) {
+    let mut group = c.benchmark_group(group_name);
+    for (name, input) in inputs {
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::new("rust-yaml", name), input, |b, input| {
+            b.iter(|| parse_rust_yaml(std::hint::black_box(input)));
+        });
+        group.bench_with_input(BenchmarkId::new("serde_yaml", name), input, |b, input| {
+            b.iter(|| parse_serde_yaml(std::hint::black_box(input)));
+        });
+        group.bench_with_input(BenchmarkId::new("yaml-rust2", name), input, |b, input| {
+            b.iter(|| parse_yaml_rust2(std::hint::black_box(input)));
+        });
+    }
+    group.finish();
+}
+
+fn bench_generated(c: &mut Criterion) {
+    let inputs = vec![
+        ("small_config".to_string(), small_config()),
+        ("manifests_100".to_string(), large_manifests(100)),
+        ("deep_nesting_100".to_string(), deep_nesting(100)),
+        ("alias_heavy_1000".to_string(), alias_heavy(1000)),
+    ];
+    for (name, input) in &inputs {
+        assert!(parses_everywhere(input), "{name} does not parse everywhere");
+    }
+    bench_corpus(c, "comparison", &inputs);
+}
+
+fn bench_downloaded(c: &mut Criterion) {
+    let inputs = downloaded_corpus();
+    if !inputs.is_empty() {
+        bench_corpus(c, "comparison_corpus", &inputs);
+    }
+}
+
+criterion_group!(benches, bench_generated, bench_downloaded);
+criterion_main!(benches);
@@ -1,8 +1,8 @@
 #![allow(clippy::uninlined_format_args)]
 #![allow(clippy::needless_raw_string_hashes)]
 
-use criterion::{criterion_group, criterion_main, Criterion};
-use rust_yaml::Yaml;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use rust_yaml::{BasicComposer, BasicParser, BasicScanner, Composer, Limits, Scanner, Yaml};
 
 fn bench_simple_scalars(c: &mut Criterion) {
     let yaml = Yaml::new();
@@ -142,6 +142,64 @@ fn bench_large_sequence(c: &mut Criterion) {
     });
 }
 
+fn bench_large_document(c: &mut Criterion) {
+    let yaml = Yaml::new();
+
+    // Composer throughput on a large block document of nested records
+    let mut large_doc = String::from("records:\n");
+    for i in 0..2000 {
+        large_doc.push_str(&format!(
+            "  - id: {i}\n    name: record {i}\n    tags: [a, b, c]\n    meta:\n      active: true\n      score: {i}.5\n"
+        ));
+    }
+
+    let mut group = c.benchmark_group("parse_large_document");
+    group.throughput(Throughput::Bytes(large_doc.len() as u64));
+    group.bench_function("block_records", |b| {
+        b.iter(|| yaml.load_str(std::hint::black_box(&large_doc)).unwrap());
+    });
+    // The composer alone, over events parsed ahead of time
+    group.bench_function("compose_block_records", |b| {
+        b.iter_batched(
+            || {
+                let parser = BasicParser::new_eager(large_doc.clone());
+                BasicComposer::from_parser(parser, Limits::default())
+            },
+            |mut composer| composer.compose_document().unwrap(),
+            BatchSize::SmallInput,
+        );
+    });
+    group.finish();
+}
+
+fn bench_manifest_scanning(c: &mut Criterion) {
+    let mut manifests = String::new();
+    for i in 0..200 {
+        manifests.push_str(&format!(
+            "---\napiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: service-{i}\n  \
+             labels:\n    app.kubernetes.io/name: service-{i} # owning team\nspec:\n  \
+             replicas: 3\n  template:\n    spec:\n      containers:\n        - name: app\n          \
+             image: \"registry.example.com/service-{i}:1.2.{i}\"\n          \
+             args: [--port, \"8080\"]\n          resources: {{limits: {{cpu: 500m}}}}\n"
+        ));
+    }
+    // A single character outside ASCII makes the scanner decode the input
+    let unicode = format!("{manifests}# café\n");
+
+    let mut group = c.benchmark_group("scan_manifests");
+    group.throughput(Throughput::Bytes(manifests.len() as u64));
+    for (name, input) in [("ascii", &manifests), ("unicode", &unicode)] {
+        group.bench_function(name, |b| {
+            // Creating the scanner is where the input is decoded
+            b.iter(|| {
+                let mut scanner = BasicScanner::new(std::hint::black_box(input).clone());
+                while scanner.get_token().unwrap().is_some() {}
+            });
+        });
+    }
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_simple_scalars,
@@ -149,6 +207,8 @@ criterion_group!(
     bench_block_collections,
     bench_nested_structures,
     bench_multi_document,
-    bench_large_sequence
+    bench_large_sequence,
+    bench_large_document,
+    bench_manifest_scanning
 );
 criterion_main!(benches);
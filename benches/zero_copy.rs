@@ -208,7 +208,7 @@ fn benchmark_memory_allocations(c: &mut Criterion) {
             let mut composer = BasicComposer::new(yaml.to_string());
             let result = composer.compose_document().unwrap().unwrap();
             // Force evaluation to ensure all allocations happen
-            if let Value::Mapping(map) = result {
+            if let Value::Mapping(map) = &result {
                 for (k, v) in map {
                     std::hint::black_box(k);
                     std::hint::black_box(v);
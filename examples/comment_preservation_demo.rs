@@ -342,6 +342,8 @@ environments:
         value: manual_value,
         comments: manual_comments,
         style: Style::default(),
+        keys: Default::default(),
+        items: Vec::new(),
     };
 
     println!(
@@ -66,7 +66,7 @@ sequence: !!seq
             println!("✅ FULL YAML 1.2: Parsed complex document");
             println!("   Parse time: {:?}", start.elapsed());
 
-            if let Value::Mapping(map) = value {
+            if let Value::Mapping(map) = &value {
                 println!("   Features supported:");
                 println!("   • Tag directives: %TAG, %YAML");
                 println!("   • Explicit types: !!str, !!int, !!float, !!bool, !!null");
@@ -24,6 +24,9 @@ config:
         use_zero_copy: true,
         max_depth: 10,
         collect_stats: true,
+        reject_duplicate_keys: false,
+        strict_escapes: false,
+        max_scalar_len: 1024 * 1024,
     };
 
     // Create streaming parser
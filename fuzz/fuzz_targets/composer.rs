@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_yaml::{LoaderType, Yaml};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        let _ = Yaml::new().load_all_str(input);
+        let _ = Yaml::with_loader(LoaderType::RoundTrip).load_str_with_comments(input);
+    }
+});
@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_yaml::{BasicParser, Parser};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        let mut parser = BasicParser::new(input.to_string());
+        while let Ok(Some(_)) = parser.get_event() {}
+        rust_yaml::fuzz_util::exercise_streaming(input);
+    }
+});
@@ -0,0 +1,46 @@
+#!/usr/bin/env bash
+# Fill benches/corpus/ with public YAML files for the comparison benchmark
+#
+# Usage: scripts/download-bench-corpus.sh [max-files-per-source]
+#
+# Each source repository is shallow-cloned into a temporary directory and its
+# YAML files are copied flat into benches/corpus/, prefixed with the source
+# name. The corpus is git-ignored; run `cargo bench --features bench --bench
+# comparison` afterwards.
+
+set -euo pipefail
+
+MAX_FILES="${1:-200}"
+ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")/.." && pwd)"
+CORPUS="$ROOT/benches/corpus"
+WORK="$(mktemp -d)"
+trap 'rm -rf "$WORK"' EXIT
+
+# name repository-url
+SOURCES=(
+  "k8s-examples https://github.com/kubernetes/examples.git"
+  "yaml-test-suite https://github.com/yaml/yaml-test-suite.git"
+  "github-workflows https://github.com/actions/starter-workflows.git"
+)
+
+mkdir -p "$CORPUS"
+
+for source in "${SOURCES[@]}"; do
+  read -r name url <<<"$source"
+  echo "Fetching $name from $url"
+  git clone --quiet --depth 1 "$url" "$WORK/$name"
+
+  count=0
+  while IFS= read -r -d '' file; do
+    relative="${file#"$WORK/$name/"}"
+    cp "$file" "$CORPUS/$name-${relative//\//_}"
+    count=$((count + 1))
+    if [ "$count" -ge "$MAX_FILES" ]; then
+      break
+    fi
+  done < <(find "$WORK/$name" -type f \( -name '*.yaml' -o -name '*.yml' \) -not -path '*/.git/*' -print0 | sort -z)
+
+  echo "  copied $count files"
+done
+
+echo "Corpus ready in $CORPUS"
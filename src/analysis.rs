@@ -0,0 +1,572 @@
+//! Structure analysis for editor tooling
+//!
+//! The functions here compute the outline and folding regions of a YAML
+//! text from its event stream, which is what a language server needs to
+//! answer `textDocument/documentSymbol` and `textDocument/foldingRange`:
+//!
+//! ```
+//! use rust_yaml::analysis::{document_symbols, folding_ranges};
+//!
+//! let text = "spec:\n  containers:\n    - image: nginx\n";
+//! let paths: Vec<String> = document_symbols(text)
+//!     .iter()
+//!     .map(|symbol| symbol.path.to_string())
+//!     .collect();
+//! assert_eq!(paths, ["spec", "spec.containers", "spec.containers[0].image"]);
+//! assert_eq!(folding_ranges(text)[0].start_line, 1);
+//! ```
+//!
+//! Both functions are tolerant of invalid input: analysis stops at the first
+//! parse error and reports the structure seen up to that point, with any
+//! unclosed collection extending to the end of the text.
+
+use crate::composer::resolve_scalar;
+use crate::parser::{BasicParser, Event, EventType, Parser, ScalarStyle};
+use crate::{Path, PathSegment, Position, Value};
+
+/// A region of the source text; `end` is exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span {
+    /// The first position of the region
+    pub start: Position,
+    /// The position just past the region
+    pub end: Position,
+}
+
+/// What kind of node a [`Symbol`]'s key holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolKind {
+    /// A nested mapping
+    Mapping,
+    /// A sequence
+    Sequence,
+    /// A scalar
+    Scalar,
+    /// An alias to an anchored node
+    Alias,
+}
+
+/// A mapping key of the document outline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    /// The key as displayed in an outline
+    pub name: String,
+    /// The path of the key's value from the document root
+    pub path: Path,
+    /// The kind of the key's value
+    pub kind: SymbolKind,
+    /// The index of the document in the stream, starting at 0
+    pub document: usize,
+    /// The key together with its value
+    pub range: Span,
+    /// The key alone
+    pub selection_range: Span,
+}
+
+/// What a [`FoldingRange`] folds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FoldingKind {
+    /// A multi-line collection or block scalar
+    Region,
+    /// A run of comment lines
+    Comment,
+}
+
+/// A range of lines an editor can collapse
+///
+/// Lines are 1-based like [`Position::line`]; both ends are inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FoldingRange {
+    /// The first line of the range
+    pub start_line: usize,
+    /// The last line of the range
+    pub end_line: usize,
+    /// What the range folds
+    pub kind: FoldingKind,
+}
+
+/// The mapping keys of every document in `input`, in source order
+///
+/// Every key in the stream is reported with the path of its value, so keys
+/// nested under sequence items carry the item index, as in
+/// `spec.containers[0].image`.
+pub fn document_symbols(input: &str) -> Vec<Symbol> {
+    let analysis = Analysis::new(input);
+    let mut symbols = Vec::new();
+    for (document, root) in analysis.documents.iter().enumerate() {
+        analysis.collect_symbols(root, &mut Path::new(), document, &mut symbols);
+    }
+    symbols
+}
+
+/// The foldable regions of `input`, ordered by their first line
+///
+/// Multi-line collections fold from the line of their key or sequence entry,
+/// block scalars from their indicator line, and runs of two or more comment
+/// lines fold as [`FoldingKind::Comment`]. Document roots are not folded.
+pub fn folding_ranges(input: &str) -> Vec<FoldingRange> {
+    let analysis = Analysis::new(input);
+    let mut ranges = Vec::new();
+    for root in &analysis.documents {
+        for child in &root.children {
+            analysis.collect_folds(child, &mut ranges);
+        }
+    }
+    comment_folds(input, &mut ranges);
+    ranges.sort_by_key(|range| (range.start_line, std::cmp::Reverse(range.end_line)));
+    ranges.dedup();
+    ranges
+}
+
+/// A node of the document tree with its byte range in the source
+#[derive(Debug)]
+struct Node {
+    kind: NodeKind,
+    start: usize,
+    end: usize,
+    children: Vec<Child>,
+}
+
+#[derive(Debug)]
+enum NodeKind {
+    Mapping,
+    Sequence,
+    Scalar(String, ScalarStyle),
+    Alias(String),
+}
+
+/// An entry of a collection; sequence items have no key
+#[derive(Debug)]
+struct Child {
+    key: Option<Node>,
+    value: Node,
+}
+
+struct Analysis<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+    documents: Vec<Node>,
+}
+
+impl<'a> Analysis<'a> {
+    fn new(text: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        let mut parser = BasicParser::new(text.to_string());
+        let mut events = Vec::new();
+        // Keep whatever was parsed before an error
+        while let Ok(Some(event)) = parser.get_event() {
+            events.push(event);
+        }
+
+        let mut analysis = Self {
+            text,
+            line_starts,
+            documents: Vec::new(),
+        };
+        let mut builder = TreeBuilder {
+            text,
+            events: &events,
+            cursor: 0,
+        };
+        while let Some(event) = events.get(builder.cursor) {
+            builder.cursor += 1;
+            if let EventType::DocumentStart { .. } = event.event_type {
+                if let Some(root) = builder.node() {
+                    analysis.documents.push(root);
+                }
+            }
+        }
+        analysis
+    }
+
+    fn position(&self, index: usize) -> Position {
+        let index = index.min(self.text.len());
+        let line = self.line_starts.partition_point(|&start| start <= index);
+        let line_start = self.line_starts[line - 1];
+        let column = self.text[line_start..index].chars().count() + 1;
+        Position::at(line, column, index)
+    }
+
+    fn span(&self, start: usize, end: usize) -> Span {
+        Span {
+            start: self.position(start),
+            end: self.position(end),
+        }
+    }
+
+    fn line(&self, index: usize) -> usize {
+        self.position(index).line
+    }
+
+    fn collect_symbols(
+        &self,
+        node: &Node,
+        path: &mut Path,
+        document: usize,
+        out: &mut Vec<Symbol>,
+    ) {
+        for (index, child) in node.children.iter().enumerate() {
+            let Some(key) = &child.key else {
+                path.push(PathSegment::Index(index));
+                self.collect_symbols(&child.value, path, document, out);
+                path.pop();
+                continue;
+            };
+            let (name, key_value) = match &key.kind {
+                NodeKind::Scalar(value, style) => {
+                    (value.clone(), resolve_scalar(value.clone(), *style))
+                }
+                NodeKind::Alias(anchor) => {
+                    (format!("*{anchor}"), Value::String(format!("*{anchor}")))
+                }
+                NodeKind::Mapping | NodeKind::Sequence => {
+                    let source = self.text[key.start..key.end].to_string();
+                    (source.clone(), Value::String(source))
+                }
+            };
+            let key_end = self.key_end(key);
+            path.push(PathSegment::Key(key_value));
+            out.push(Symbol {
+                name,
+                path: path.clone(),
+                kind: match child.value.kind {
+                    NodeKind::Mapping => SymbolKind::Mapping,
+                    NodeKind::Sequence => SymbolKind::Sequence,
+                    NodeKind::Scalar(..) => SymbolKind::Scalar,
+                    NodeKind::Alias(_) => SymbolKind::Alias,
+                },
+                document,
+                range: self.span(key.start, child.value.end.max(key_end)),
+                selection_range: self.span(key.start, key_end),
+            });
+            self.collect_symbols(&child.value, path, document, out);
+            path.pop();
+        }
+    }
+
+    /// Where a key's source text ends, found by scanning the source since
+    /// the key's events do not record it
+    fn key_end(&self, key: &Node) -> usize {
+        if !matches!(key.kind, NodeKind::Scalar(..) | NodeKind::Alias(_)) {
+            return key.end;
+        }
+        let bytes = self.text.as_bytes();
+        let mut i = key.start;
+        // Step over properties such as `&anchor` and `!tag` before the key
+        while i < bytes.len() && matches!(bytes[i], b'&' | b'!') {
+            while i < bytes.len() && !bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            while i < bytes.len() && matches!(bytes[i], b' ' | b'\t') {
+                i += 1;
+            }
+        }
+        match bytes.get(i) {
+            Some(&quote @ (b'"' | b'\'')) => {
+                i += 1;
+                while i < bytes.len() {
+                    match bytes[i] {
+                        b'\\' if quote == b'"' => i += 2,
+                        b'\'' if quote == b'\'' && bytes.get(i + 1) == Some(&b'\'') => i += 2,
+                        byte if byte == quote => return i + 1,
+                        _ => i += 1,
+                    }
+                }
+                bytes.len()
+            }
+            _ => {
+                let start = i;
+                while i < bytes.len() {
+                    match bytes[i] {
+                        b'\n' | b',' | b']' | b'}' => break,
+                        b':' if bytes.get(i + 1).is_none_or(|next| {
+                            next.is_ascii_whitespace() || b",]}".contains(next)
+                        }) =>
+                        {
+                            break
+                        }
+                        _ => i += 1,
+                    }
+                }
+                start + self.text[start..i].trim_end().len()
+            }
+        }
+    }
+
+    fn collect_folds(&self, child: &Child, out: &mut Vec<FoldingRange>) {
+        let value = &child.value;
+        let foldable = match &value.kind {
+            NodeKind::Mapping | NodeKind::Sequence => true,
+            NodeKind::Scalar(_, style) => {
+                matches!(style, ScalarStyle::Literal | ScalarStyle::Folded)
+            }
+            NodeKind::Alias(_) => false,
+        };
+        if foldable {
+            let start = child.key.as_ref().map_or(value.start, |key| key.start);
+            let (start_line, end_line) = (self.line(start), self.line(value.end));
+            if end_line > start_line {
+                out.push(FoldingRange {
+                    start_line,
+                    end_line,
+                    kind: FoldingKind::Region,
+                });
+            }
+        }
+        for grandchild in &value.children {
+            self.collect_folds(grandchild, out);
+        }
+    }
+}
+
+/// Turns the flat event stream into a tree of nodes with source ranges
+struct TreeBuilder<'a> {
+    text: &'a str,
+    events: &'a [Event],
+    cursor: usize,
+}
+
+impl TreeBuilder<'_> {
+    fn node(&mut self) -> Option<Node> {
+        let event = self.events.get(self.cursor)?;
+        let start = event.position.index.min(self.text.len());
+        let (kind, children) = match &event.event_type {
+            EventType::Scalar { value, style, .. } => {
+                self.cursor += 1;
+                (NodeKind::Scalar(value.clone(), *style), Vec::new())
+            }
+            EventType::Alias { anchor } => {
+                self.cursor += 1;
+                (NodeKind::Alias(anchor.clone()), Vec::new())
+            }
+            EventType::SequenceStart { .. } => {
+                self.cursor += 1;
+                let mut children = Vec::new();
+                while !self.at(|event| matches!(event, EventType::SequenceEnd)) {
+                    let Some(value) = self.node() else { break };
+                    children.push(Child { key: None, value });
+                }
+                self.close(|event| matches!(event, EventType::SequenceEnd));
+                (NodeKind::Sequence, children)
+            }
+            EventType::MappingStart { .. } => {
+                self.cursor += 1;
+                let mut children = Vec::new();
+                while !self.at(|event| matches!(event, EventType::MappingEnd)) {
+                    let Some(key) = self.node() else { break };
+                    let value = self.node().unwrap_or(Node {
+                        kind: NodeKind::Scalar(String::new(), ScalarStyle::Plain),
+                        start: key.end,
+                        end: key.end,
+                        children: Vec::new(),
+                    });
+                    children.push(Child {
+                        key: Some(key),
+                        value,
+                    });
+                }
+                self.close(|event| matches!(event, EventType::MappingEnd));
+                (NodeKind::Mapping, children)
+            }
+            _ => return None,
+        };
+        let end = self.boundary(start);
+        Some(Node {
+            kind,
+            start,
+            end,
+            children,
+        })
+    }
+
+    /// Check whether the next event matches, treating the end of the events
+    /// and document boundaries as a match so truncated collections close
+    fn at(&self, matches: impl Fn(&EventType) -> bool) -> bool {
+        self.events.get(self.cursor).is_none_or(|event| {
+            matches(&event.event_type)
+                || matches!(
+                    event.event_type,
+                    EventType::DocumentStart { .. }
+                        | EventType::DocumentEnd { .. }
+                        | EventType::StreamEnd
+                )
+        })
+    }
+
+    /// Consume the end event of a collection, leaving a document boundary
+    /// that closed it early for the caller
+    fn close(&mut self, matches: impl Fn(&EventType) -> bool) {
+        if self
+            .events
+            .get(self.cursor)
+            .is_some_and(|event| matches(&event.event_type))
+        {
+            self.cursor += 1;
+        }
+    }
+
+    /// Where a node that started at `start` ends: at the next event, less
+    /// the whitespace and whole comment lines before it
+    fn boundary(&self, start: usize) -> usize {
+        let next = self
+            .events
+            .get(self.cursor)
+            .map_or(self.text.len(), |event| event.position.index)
+            .clamp(start, self.text.len());
+        let mut end = next;
+        loop {
+            end = start + self.text[start..end].trim_end().len();
+            let line_start = self.text[start..end]
+                .rfind('\n')
+                .map_or(start, |i| start + i + 1);
+            if line_start > start && self.text[line_start..end].trim_start().starts_with('#') {
+                end = line_start;
+            } else {
+                return end;
+            }
+        }
+    }
+}
+
+/// Add a fold for every run of two or more comment-only lines
+fn comment_folds(text: &str, out: &mut Vec<FoldingRange>) {
+    let mut run_start = None;
+    for (index, line) in text.lines().chain(std::iter::once("")).enumerate() {
+        let line_number = index + 1;
+        if line.trim_start().starts_with('#') {
+            run_start.get_or_insert(line_number);
+        } else if let Some(start_line) = run_start.take() {
+            if line_number - 1 > start_line {
+                out.push(FoldingRange {
+                    start_line,
+                    end_line: line_number - 1,
+                    kind: FoldingKind::Comment,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(text: &str) -> Vec<String> {
+        document_symbols(text)
+            .iter()
+            .map(|symbol| symbol.path.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_symbol_paths_and_kinds() {
+        let text = "apiVersion: v1\nspec:\n  ports: [80, 443]\n  containers:\n    - name: app\n      image: nginx\n";
+        assert_eq!(
+            paths(text),
+            [
+                "apiVersion",
+                "spec",
+                "spec.ports",
+                "spec.containers",
+                "spec.containers[0].name",
+                "spec.containers[0].image",
+            ]
+        );
+        let kinds: Vec<_> = document_symbols(text).iter().map(|s| s.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                SymbolKind::Scalar,
+                SymbolKind::Mapping,
+                SymbolKind::Sequence,
+                SymbolKind::Sequence,
+                SymbolKind::Scalar,
+                SymbolKind::Scalar,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_symbol_spans() {
+        let text =
+            "server:\n  host: localhost # the host\n  port: 8080\n\n# trailing\nname: \"x\"\n";
+        let symbols = document_symbols(text);
+
+        let server = &symbols[0];
+        assert_eq!(server.name, "server");
+        assert_eq!(server.selection_range.start, Position::at(1, 1, 0));
+        assert_eq!(server.selection_range.end, Position::at(1, 7, 6));
+        assert_eq!(server.range.start.line, 1);
+        assert_eq!(server.range.end, Position::at(3, 13, 49));
+
+        let port = &symbols[2];
+        assert_eq!(port.name, "port");
+        assert_eq!(port.selection_range.start, Position::at(3, 3, 39));
+        assert_eq!(port.selection_range.end, Position::at(3, 7, 43));
+
+        let name = &symbols[3];
+        assert_eq!(
+            &text[name.range.start.index..name.range.end.index],
+            "name: \"x\""
+        );
+    }
+
+    #[test]
+    fn test_symbols_per_document() {
+        let text = "a: 1\n---\nb:\n  c: 2\n";
+        let symbols = document_symbols(text);
+        let located: Vec<_> = symbols
+            .iter()
+            .map(|symbol| (symbol.document, symbol.path.to_string()))
+            .collect();
+        assert_eq!(
+            located,
+            [
+                (0, "a".to_string()),
+                (1, "b".to_string()),
+                (1, "b.c".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_folding_ranges() {
+        let text = "# header\n# more\nspec:\n  script: |\n    one\n    two\n  items:\n    - a\n    - b\nflat: 1\n";
+        assert_eq!(
+            folding_ranges(text),
+            [
+                FoldingRange {
+                    start_line: 1,
+                    end_line: 2,
+                    kind: FoldingKind::Comment
+                },
+                FoldingRange {
+                    start_line: 3,
+                    end_line: 9,
+                    kind: FoldingKind::Region
+                },
+                FoldingRange {
+                    start_line: 4,
+                    end_line: 6,
+                    kind: FoldingKind::Region
+                },
+                FoldingRange {
+                    start_line: 7,
+                    end_line: 9,
+                    kind: FoldingKind::Region
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_input_keeps_prefix() {
+        let text = "ok: 1\nnested:\n  inner: 2\nbad: [unclosed\n";
+        let found = paths(text);
+        assert!(found.starts_with(&["ok".to_string()]), "{found:?}");
+        assert!(document_symbols("").is_empty());
+        assert!(folding_ranges("").is_empty());
+    }
+}
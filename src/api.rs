@@ -0,0 +1,69 @@
+//! Top-level convenience functions in the style of `serde_yaml`
+//!
+//! These load and dump [`Value`]s with the default [`Yaml`] configuration, for
+//! code that does not need to configure a loader:
+//!
+//! ```
+//! let value = rust_yaml::from_str("name: demo\nreplicas: 3\n").unwrap();
+//! assert_eq!(value.get_str("replicas"), Some(&rust_yaml::Value::Int(3)));
+//!
+//! let text = rust_yaml::to_string(&value).unwrap();
+//! assert_eq!(rust_yaml::from_str(&text).unwrap(), value);
+//! ```
+//!
+//! Use [`Yaml::with_config`] for anything beyond the defaults, such as
+//! round-trip loading or custom limits.
+
+use crate::{Result, Value, Yaml};
+use std::io::{Read, Write};
+
+/// Load the first document of a YAML string
+pub fn from_str(input: &str) -> Result<Value> {
+    Yaml::new().load_str(input)
+}
+
+/// Load the first document of a YAML stream read from `reader`
+pub fn from_reader<R: Read>(reader: R) -> Result<Value> {
+    Yaml::new().load(reader)
+}
+
+/// Load the first document of YAML bytes, detecting UTF-8, UTF-16 or UTF-32
+pub fn from_slice(input: &[u8]) -> Result<Value> {
+    Yaml::new().load_bytes(input)
+}
+
+/// Dump a value as a YAML string
+pub fn to_string(value: &Value) -> Result<String> {
+    Yaml::new().dump_str(value)
+}
+
+/// Dump a value as YAML to `writer`
+pub fn to_writer<W: Write>(writer: W, value: &Value) -> Result<()> {
+    Yaml::new().dump(value, writer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_through_functions() {
+        let value = from_str("a: 1\nb: [true, null]\n").unwrap();
+        assert_eq!(from_slice(b"a: 1\nb: [true, null]\n").unwrap(), value);
+        assert_eq!(
+            from_reader("a: 1\nb: [true, null]\n".as_bytes()).unwrap(),
+            value
+        );
+
+        let text = to_string(&value).unwrap();
+        let mut buffer = Vec::new();
+        to_writer(&mut buffer, &value).unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), text);
+        assert_eq!(from_str(&text).unwrap(), value);
+    }
+
+    #[test]
+    fn test_from_slice_rejects_invalid_utf8() {
+        assert!(from_slice(&[b'a', b':', b' ', 0xFF]).is_err());
+    }
+}
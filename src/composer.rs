@@ -1,33 +1,45 @@
 //! YAML composer for converting events to nodes
 
+use crate::document::TagDirective;
 #[cfg(test)]
 use crate::scanner::Scanner;
-use crate::tag::TagResolver;
+use crate::tag::{Tag, TagHandlers, TagKind, TagResolver, UnknownHandle};
 use crate::{
-    parser::EventType, BasicParser, Error, Limits, Parser, Position, ResourceTracker, Result, Value,
+    error::ErrorContext, parser::EventType, BasicParser, Error, Limits, LoaderType, Parser, Path,
+    PathSegment, Position, ResourceHook, ResourceStats, ResourceTracker, Result, Value,
 };
 use indexmap::IndexMap;
 use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Whether `value` is a sequence or mapping, possibly with an application
+/// tag, rather than a scalar
+fn is_complex(value: &Value) -> bool {
+    match value {
+        Value::Sequence(_) | Value::Mapping(_) => true,
+        Value::Tagged(tagged) => is_complex(&tagged.value),
+        _ => false,
+    }
+}
 
 /// Calculate complexity score for a value (for resource limiting)
 fn calculate_value_complexity(value: &Value) -> Result<usize> {
-    let mut complexity = 1usize;
-
-    match value {
-        Value::Sequence(seq) => {
-            complexity = complexity.saturating_add(seq.len());
-            for item in seq {
-                complexity = complexity.saturating_add(calculate_value_complexity(item)?);
+    let mut complexity = 0usize;
+    let mut stack = vec![value];
+
+    while let Some(value) = stack.pop() {
+        complexity = complexity.saturating_add(1);
+        match value {
+            Value::Sequence(seq) => {
+                complexity = complexity.saturating_add(seq.len());
+                stack.extend(seq);
             }
-        }
-        Value::Mapping(map) => {
-            complexity = complexity.saturating_add(map.len().saturating_mul(2));
-            for (key, val) in map {
-                complexity = complexity.saturating_add(calculate_value_complexity(key)?);
-                complexity = complexity.saturating_add(calculate_value_complexity(val)?);
+            Value::Mapping(map) => {
+                complexity = complexity.saturating_add(map.len().saturating_mul(2));
+                stack.extend(map.iter().flat_map(|(key, val)| [key, val]));
             }
+            _ => {} // Scalars have complexity 1
         }
-        _ => {} // Scalars have complexity 1
     }
 
     Ok(complexity)
@@ -35,33 +47,353 @@ fn calculate_value_complexity(value: &Value) -> Result<usize> {
 
 /// Calculate the maximum nesting depth of a value structure
 fn calculate_structure_depth(value: &Value) -> usize {
+    let mut max_depth = 0;
+    let mut stack = vec![(value, 1)];
+
+    while let Some((value, depth)) = stack.pop() {
+        max_depth = max_depth.max(depth);
+        match value {
+            Value::Sequence(seq) => stack.extend(seq.iter().map(|item| (item, depth + 1))),
+            Value::Mapping(map) => stack.extend(map.values().map(|val| (val, depth + 1))),
+            _ => {} // Scalars have depth 1
+        }
+    }
+
+    max_depth
+}
+
+/// Resolve an untagged scalar to its implicit type
+pub(crate) fn resolve_scalar(value: String, style: crate::parser::ScalarStyle) -> Value {
+    // If explicitly quoted (single or double quotes), always treat as string
+    match style {
+        crate::parser::ScalarStyle::SingleQuoted | crate::parser::ScalarStyle::DoubleQuoted => {
+            return Value::String(value);
+        }
+        _ => {
+            // Continue with implicit type resolution for plain, literal, and folded styles
+        }
+    }
+
+    // For now, do basic type resolution; an empty plain scalar is an empty node
+    if value.is_empty() {
+        if style == crate::parser::ScalarStyle::Plain {
+            return Value::Null;
+        }
+        return Value::String(value);
+    }
+
+    // Try integer parsing
+    if let Some(int_value) = parse_int(&value) {
+        return Value::Int(int_value);
+    }
+
+    // Try float parsing
+    if let Ok(float_value) = value.parse::<f64>() {
+        return Value::Float(float_value);
+    }
+    if let Some(float_value) = special_float(&value) {
+        return Value::Float(float_value);
+    }
+
+    // Try boolean parsing
+    if let Some(b) = bool_word(&value) {
+        return Value::Bool(b);
+    }
+    if matches!(value.to_lowercase().as_str(), "null" | "~") {
+        return Value::Null;
+    }
+
+    // Default to string
+    Value::String(value)
+}
+
+/// Parse the YAML 1.1 boolean words (`true`, `yes`, `on` and their negations)
+/// in any letter case
+pub(crate) fn bool_word(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "on" => Some(true),
+        "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// Parse a decimal integer, or a hexadecimal (`0xFF`) or octal (`0o755`)
+/// integer as written in the YAML 1.2 core schema
+pub(crate) fn parse_int(value: &str) -> Option<i64> {
+    if let Ok(int_value) = value.parse::<i64>() {
+        return Some(int_value);
+    }
+    let (digits, radix) = if let Some(digits) = value.strip_prefix("0x") {
+        (digits, 16)
+    } else if let Some(digits) = value.strip_prefix("0o") {
+        (digits, 8)
+    } else {
+        return None;
+    };
+    if digits.starts_with(['+', '-']) {
+        return None;
+    }
+    i64::from_str_radix(digits, radix).ok()
+}
+
+/// Parse the YAML spellings of infinity and NaN (`.inf`, `-.inf`, `.nan`)
+pub(crate) fn special_float(value: &str) -> Option<f64> {
     match value {
-        Value::Sequence(seq) => {
-            if seq.is_empty() {
-                1
-            } else {
-                1 + seq.iter().map(calculate_structure_depth).max().unwrap_or(0)
-            }
+        ".inf" | ".Inf" | ".INF" | "+.inf" | "+.Inf" | "+.INF" => Some(f64::INFINITY),
+        "-.inf" | "-.Inf" | "-.INF" => Some(f64::NEG_INFINITY),
+        ".nan" | ".NaN" | ".NAN" => Some(f64::NAN),
+        _ => None,
+    }
+}
+
+/// Resolve an untagged scalar by the YAML 1.2.2 core schema alone
+///
+/// Unlike [`resolve_scalar`], only `true`/`false` and `null`/`~` in their
+/// lower, title and upper case spellings are booleans and null, and numbers
+/// must match the core schema patterns, so `yes`, `off`, `nan` or `1_000`
+/// are strings.
+pub(crate) fn resolve_core_scalar(value: String, style: crate::parser::ScalarStyle) -> Value {
+    if matches!(
+        style,
+        crate::parser::ScalarStyle::SingleQuoted | crate::parser::ScalarStyle::DoubleQuoted
+    ) {
+        return Value::String(value);
+    }
+    match value.as_str() {
+        "" if style == crate::parser::ScalarStyle::Plain => return Value::Null,
+        "null" | "Null" | "NULL" | "~" => return Value::Null,
+        "true" | "True" | "TRUE" => return Value::Bool(true),
+        "false" | "False" | "FALSE" => return Value::Bool(false),
+        _ => {}
+    }
+    if let Some(int_value) = core_int(&value) {
+        return Value::Int(int_value);
+    }
+    if let Some(float_value) = core_float(&value) {
+        return Value::Float(float_value);
+    }
+    Value::String(value)
+}
+
+/// Parse an integer matching the core schema: decimal digits with an
+/// optional sign, `0o` followed by octal digits or `0x` by hex digits
+fn core_int(value: &str) -> Option<i64> {
+    let (digits, radix) = if let Some(digits) = value.strip_prefix("0o") {
+        (digits, 8)
+    } else if let Some(digits) = value.strip_prefix("0x") {
+        (digits, 16)
+    } else {
+        let digits = value.strip_prefix(['+', '-']).unwrap_or(value);
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
         }
-        Value::Mapping(map) => {
-            if map.is_empty() {
-                1
-            } else {
-                1 + map
-                    .values()
-                    .map(calculate_structure_depth)
-                    .max()
-                    .unwrap_or(0)
-            }
+        return value.parse().ok();
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+    i64::from_str_radix(digits, radix).ok()
+}
+
+/// Parse a float matching the core schema, such as `1.5`, `-.5e3` or `.inf`
+fn core_float(value: &str) -> Option<f64> {
+    if let Some(special) = special_float(value) {
+        return Some(special);
+    }
+    let digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    let unsigned = value.strip_prefix(['+', '-']).unwrap_or(value);
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (unsigned, None),
+    };
+    let mantissa_valid = match mantissa.split_once('.') {
+        Some((whole, fraction)) => {
+            digits(whole) && digits(fraction) && !(whole.is_empty() && fraction.is_empty())
+        }
+        None => !mantissa.is_empty() && digits(mantissa),
+    };
+    let exponent_valid = exponent.is_none_or(|exponent| {
+        let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+        !exponent.is_empty() && digits(exponent)
+    });
+    if mantissa_valid && exponent_valid {
+        value.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// The error for an alias at `position` of `input` whose anchor is not
+/// defined before it
+pub(crate) fn unknown_anchor(input: &str, position: Position, anchor: &str) -> Error {
+    let context = ErrorContext::from_input(input, &position, 2).with_suggestion(format!(
+        "define the anchor with '&{anchor}' on a node before this alias"
+    ));
+    Error::construction_with_context(position, format!("Unknown anchor '{anchor}'"), context)
+}
+
+/// The error for an alias at `position` of `input` inside the node its
+/// anchor is defined on
+pub(crate) fn cyclic_alias(input: &str, position: Position, anchor: &str) -> Error {
+    let context = ErrorContext::from_input(input, &position, 2).with_suggestion(
+        "an alias cannot refer to a node that contains it; copy the content instead".to_string(),
+    );
+    Error::construction_with_context(
+        position,
+        format!("Cyclic alias reference detected: '{anchor}'"),
+        context,
+    )
+}
+
+/// The error for the value of a merge key (`<<`) at `position` of `input`
+/// that is not a mapping or a sequence of mappings
+pub(crate) fn invalid_merge(input: &str, position: Position, message: &str) -> Error {
+    let context = ErrorContext::from_input(input, &position, 2)
+        .with_suggestion("merge a mapping, an alias of one, or a sequence of them".to_string());
+    Error::construction_with_context(position, message, context)
+}
+
+/// Override order for merge keys (`<<`) with several source mappings
+///
+/// Keys written explicitly in the merging mapping always take precedence
+/// over merged keys; this only decides which merge source wins when the
+/// sources themselves disagree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergePrecedence {
+    /// YAML 1.1 merge key semantics: earlier sources override later ones
+    #[default]
+    Spec,
+    /// Later sources override earlier ones, as ruamel-style loaders expect
+    LastWins,
+}
+
+/// What to do when a document defines the same anchor name twice
+///
+/// YAML allows redefinition (an alias refers to the most recent definition),
+/// but in hand-written files it is usually a copy-paste mistake.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnchorRedefinition {
+    /// Accept the redefinition silently
+    #[default]
+    Allow,
+    /// Reject the document with a construction error
+    Error,
+    /// Accept the redefinition and record a warning naming both definitions
+    Warn,
+}
+
+/// Which documents of a stream an anchor can be aliased from
+///
+/// The YAML spec scopes anchors to the document defining them, but some
+/// generators write streams whose later documents alias nodes anchored in
+/// earlier ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnchorScope {
+    /// Anchors are forgotten at the end of each document, as the spec says
+    #[default]
+    Document,
+    /// Anchors stay visible to the following documents until the end of the
+    /// stream; a later definition of the same name replaces the earlier one
+    Stream,
+}
+
+/// What an empty plain scalar, such as the value of `key:`, is loaded as
+///
+/// Empty quoted scalars (`key: ""`) always load as empty strings, and
+/// `key: null` and `key: ~` as null.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmptyScalar {
+    /// Null, as the YAML core schema resolves an empty node
+    #[default]
+    Null,
+    /// An empty string, as some loading paths of earlier releases did
+    EmptyString,
+}
+
+impl EmptyScalar {
+    /// Resolve an untagged scalar, loading an empty plain scalar as this
+    /// policy says
+    pub(crate) fn resolve(self, value: String, style: crate::parser::ScalarStyle) -> Value {
+        self.resolve_for(LoaderType::Safe, value, style)
+    }
+
+    /// Resolve an untagged scalar as `loader_type` types it, loading an
+    /// empty plain scalar as this policy says
+    pub(crate) fn resolve_for(
+        self,
+        loader_type: LoaderType,
+        value: String,
+        style: crate::parser::ScalarStyle,
+    ) -> Value {
+        if loader_type == LoaderType::Base
+            || (self == Self::EmptyString
+                && value.is_empty()
+                && style == crate::parser::ScalarStyle::Plain)
+        {
+            return Value::String(value);
+        }
+        if loader_type == LoaderType::Strict {
+            return resolve_core_scalar(value, style);
+        }
+        resolve_scalar(value, style)
+    }
+}
+
+/// How the scalar keys of mappings are canonicalized while loading
+///
+/// The keys `1`, `1.0` and `"1"` load as different values by default, so a
+/// lookup must use the type the document happened to write. Data read as
+/// JSON, whose keys are always strings, can be given string keys instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyCoercion {
+    /// Keys keep the type they are resolved to
+    #[default]
+    None,
+    /// Integer, float, boolean and null keys are loaded as their canonical
+    /// text, such as `"1"`, `"1.0"`, `"true"` and `"null"`; collection keys
+    /// and tagged keys are left as they are
+    StringifyScalars,
+}
+
+impl KeyCoercion {
+    /// `key` canonicalized as this policy says
+    pub(crate) fn apply(self, key: Value) -> Value {
+        if self == Self::None {
+            return key;
+        }
+        match key {
+            Value::Null => Value::String("null".to_string()),
+            Value::Bool(b) => Value::String(b.to_string()),
+            Value::Int(i) => Value::String(i.to_string()),
+            Value::Float(f) => Value::String(canonical_float(f)),
+            key => key,
+        }
+    }
+}
+
+/// The text the core schema writes for `f`, which keeps the decimal point of
+/// whole numbers so `1.0` and `1` stay apart
+pub(crate) fn canonical_float(f: f64) -> String {
+    if f.is_nan() {
+        ".nan".to_string()
+    } else if f.is_infinite() {
+        if f.is_sign_positive() {
+            ".inf"
+        } else {
+            "-.inf"
         }
-        _ => 1, // Scalars have depth 1
+        .to_string()
+    } else if f.fract() == 0.0 {
+        format!("{f:.1}")
+    } else {
+        f.to_string()
     }
 }
 
 /// Trait for YAML composers that convert event streams to node structures
 pub trait Composer {
     /// Check if there are more documents available
-    fn check_document(&self) -> bool;
+    fn check_document(&mut self) -> bool;
 
     /// Compose the next document
     ///
@@ -76,6 +408,121 @@ pub trait Composer {
     fn reset(&mut self);
 }
 
+/// The tags and mapping keys a load accepts, following its [`LoaderType`]
+/// and its `reject_` options
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Acceptance {
+    pub(crate) loader_type: LoaderType,
+    pub(crate) reject_unknown_tags: bool,
+    pub(crate) reject_complex_keys: bool,
+}
+
+impl Default for Acceptance {
+    fn default() -> Self {
+        Self {
+            loader_type: LoaderType::Safe,
+            reject_unknown_tags: false,
+            reject_complex_keys: false,
+        }
+    }
+}
+
+impl Acceptance {
+    /// Check that `tag`, found at `position` of `input`, is accepted
+    pub(crate) fn check_tag(
+        self,
+        tag: &Tag,
+        resolver: &TagResolver,
+        input: &str,
+        position: Position,
+    ) -> Result<()> {
+        if self.loader_type == LoaderType::Full || resolver.has_handler(tag) {
+            return Ok(());
+        }
+        let core = matches!(
+            tag.kind,
+            TagKind::Null
+                | TagKind::Bool
+                | TagKind::Int
+                | TagKind::Float
+                | TagKind::Str
+                | TagKind::Seq
+                | TagKind::Map
+        );
+        if self.loader_type == LoaderType::Strict && !core {
+            return Err(self.tag_error(
+                input,
+                position,
+                format!("Tag '{}' is not in the YAML 1.2 core schema", tag.original),
+            ));
+        }
+        if self.reject_unknown_tags && matches!(tag.kind, TagKind::Custom(_)) {
+            return Err(self.tag_error(input, position, format!("Unknown tag '{}'", tag.original)));
+        }
+        Ok(())
+    }
+
+    /// Check that `key`, composed up to `position` of `input`, is accepted
+    /// as a mapping key
+    pub(crate) fn check_key(self, key: &Value, input: &str, position: Position) -> Result<()> {
+        if !self.reject_complex_keys || self.loader_type == LoaderType::Full || !is_complex(key) {
+            return Ok(());
+        }
+        let context = ErrorContext::from_input(input, &position, 2)
+            .with_suggestion("use a scalar key, or load with LoaderType::Full".to_string());
+        Err(Error::construction_with_context(
+            position,
+            format!(
+                "Complex mapping key {} is not allowed",
+                key.display_truncated(1, 3)
+            ),
+            context,
+        ))
+    }
+
+    /// The error for a tag at `position` of `input` that is not accepted
+    pub(crate) fn tag_error(self, input: &str, position: Position, message: String) -> Error {
+        let suggestion = match self.loader_type {
+            LoaderType::Strict => {
+                "use a core schema tag (!!null, !!bool, !!int, !!float, !!str, !!seq or !!map) \
+                 or register a handler for this tag"
+            }
+            _ => "register a handler for this tag, or load with LoaderType::Full to keep it",
+        };
+        let context =
+            ErrorContext::from_input(input, &position, 2).with_suggestion(suggestion.to_string());
+        Error::construction_with_context(position, message, context)
+    }
+}
+
+/// A sequence or mapping being composed by [`BasicComposer`]
+#[derive(Debug)]
+struct Frame {
+    anchor: Option<String>,
+    tag: Option<String>,
+    position: Position,
+    /// Whether the collection is inside a mapping key
+    in_key: bool,
+    content: FrameContent,
+}
+
+/// The entries of a collection composed so far
+#[derive(Debug)]
+enum FrameContent {
+    Sequence(Vec<Value>),
+    /// A mapping, with the key whose value is composed next
+    Mapping(IndexMap<Value, Value>, Option<Value>),
+}
+
+/// What the events of the next node start
+#[derive(Debug)]
+enum Composed {
+    Node(Value),
+    Collection(Frame),
+    /// No node follows in the current collection or document
+    End,
+}
+
 /// A basic composer implementation for converting events to nodes
 #[derive(Debug)]
 pub struct BasicComposer {
@@ -87,6 +534,20 @@ pub struct BasicComposer {
     alias_expansion_stack: Vec<String>,
     current_depth: usize,
     tag_resolver: TagResolver,
+    merge_precedence: MergePrecedence,
+    version: Option<(u8, u8)>,
+    tag_directives: Vec<TagDirective>,
+    explicit_end: bool,
+    path: Path,
+    composing_key: bool,
+    anchor_paths: HashMap<String, Path>,
+    anchor_redefinition: AnchorRedefinition,
+    anchor_scope: AnchorScope,
+    anchor_positions: HashMap<String, Position>,
+    warnings: Vec<String>,
+    empty_scalar: EmptyScalar,
+    key_coercion: KeyCoercion,
+    acceptance: Acceptance,
 }
 
 impl BasicComposer {
@@ -99,16 +560,7 @@ impl BasicComposer {
     /// Create a new composer with custom limits
     #[must_use]
     pub fn with_limits(input: String, limits: Limits) -> Self {
-        Self {
-            parser: BasicParser::with_limits(input, limits.clone()),
-            position: Position::new(),
-            anchors: HashMap::new(),
-            limits,
-            resource_tracker: ResourceTracker::new(),
-            alias_expansion_stack: Vec::new(),
-            current_depth: 0,
-            tag_resolver: TagResolver::new(),
-        }
+        Self::from_parser(BasicParser::with_limits(input, limits.clone()), limits)
     }
 
     /// Create a new composer with eager parsing (for compatibility)
@@ -120,8 +572,20 @@ impl BasicComposer {
     /// Create a new composer with eager parsing and custom limits
     #[must_use]
     pub fn new_eager_with_limits(input: String, limits: Limits) -> Self {
+        Self::from_parser(
+            BasicParser::new_eager_with_limits(input, limits.clone()),
+            limits,
+        )
+    }
+
+    /// Create a composer over an existing parser, such as one built from a
+    /// scanner with [`BasicScanner::set_strict_escapes`] enabled
+    ///
+    /// [`BasicScanner::set_strict_escapes`]: crate::BasicScanner::set_strict_escapes
+    #[must_use]
+    pub fn from_parser(parser: BasicParser, limits: Limits) -> Self {
         Self {
-            parser: BasicParser::new_eager_with_limits(input, limits.clone()),
+            parser,
             position: Position::new(),
             anchors: HashMap::new(),
             limits,
@@ -129,301 +593,566 @@ impl BasicComposer {
             alias_expansion_stack: Vec::new(),
             current_depth: 0,
             tag_resolver: TagResolver::new(),
+            merge_precedence: MergePrecedence::default(),
+            version: None,
+            tag_directives: Vec::new(),
+            explicit_end: false,
+            path: Path::new(),
+            composing_key: false,
+            anchor_paths: HashMap::new(),
+            anchor_redefinition: AnchorRedefinition::default(),
+            anchor_scope: AnchorScope::default(),
+            anchor_positions: HashMap::new(),
+            warnings: Vec::new(),
+            empty_scalar: EmptyScalar::default(),
+            key_coercion: KeyCoercion::default(),
+            acceptance: Acceptance::default(),
         }
     }
 
-    /// Compose a node from events (recursive)
-    fn compose_node(&mut self) -> Result<Option<Value>> {
-        if !self.parser.check_event() {
-            return Ok(None);
-        }
+    /// Set which values are constructed, as described for each
+    /// [`LoaderType`]
+    pub fn set_loader_type(&mut self, loader_type: LoaderType) {
+        self.acceptance.loader_type = loader_type;
+    }
 
-        let Some(event) = self.parser.get_event()? else {
-            return Ok(None);
-        };
+    /// Set whether a tag that is neither a YAML tag nor constructed by a
+    /// registered handler is an error rather than kept on a
+    /// [`Value::Tagged`]; [`LoaderType::Full`] keeps such tags regardless
+    pub fn set_reject_unknown_tags(&mut self, reject: bool) {
+        self.acceptance.reject_unknown_tags = reject;
+    }
 
-        self.position = event.position;
+    /// Set whether a sequence or mapping used as a mapping key is an error;
+    /// [`LoaderType::Full`] accepts such keys regardless
+    pub fn set_reject_complex_keys(&mut self, reject: bool) {
+        self.acceptance.reject_complex_keys = reject;
+    }
 
-        match event.event_type {
-            EventType::StreamStart | EventType::StreamEnd => {
-                // Skip stream boundaries, these don't produce nodes
-                self.compose_node()
-            }
+    /// Set the override order used when resolving merge keys
+    pub fn set_merge_precedence(&mut self, merge_precedence: MergePrecedence) {
+        self.merge_precedence = merge_precedence;
+    }
 
-            EventType::DocumentStart { .. } => {
-                // Skip document start events, these should be handled by compose_document
-                self.compose_node()
-            }
+    /// Get the override order used when resolving merge keys
+    pub const fn merge_precedence(&self) -> MergePrecedence {
+        self.merge_precedence
+    }
 
-            EventType::DocumentEnd { .. } => {
-                // Document end, return None to indicate end of document
-                Ok(None)
-            }
+    /// Set how a redefined anchor name is handled
+    pub fn set_anchor_redefinition(&mut self, anchor_redefinition: AnchorRedefinition) {
+        self.anchor_redefinition = anchor_redefinition;
+    }
 
-            EventType::Scalar {
-                value,
-                anchor,
-                tag,
-                style,
-                ..
-            } => {
-                let scalar_value = if let Some(tag_str) = tag {
-                    // Apply tag if present
-                    self.compose_tagged_scalar(value, tag_str)?
-                } else {
-                    // Use implicit typing
-                    self.compose_scalar(value, style)?
-                };
+    /// Set whether anchors stay visible to the following documents
+    pub fn set_anchor_scope(&mut self, anchor_scope: AnchorScope) {
+        self.anchor_scope = anchor_scope;
+    }
 
-                // Store anchor if present
-                if let Some(anchor_name) = anchor {
-                    self.resource_tracker.add_anchor(&self.limits)?;
-                    self.anchors.insert(anchor_name, scalar_value.clone());
-                }
+    /// Set what an empty plain scalar is loaded as
+    pub fn set_empty_scalar(&mut self, empty_scalar: EmptyScalar) {
+        self.empty_scalar = empty_scalar;
+    }
 
-                Ok(Some(scalar_value))
-            }
+    /// Set how the scalar keys of mappings are canonicalized
+    pub fn set_key_coercion(&mut self, key_coercion: KeyCoercion) {
+        self.key_coercion = key_coercion;
+    }
 
-            EventType::SequenceStart { anchor, .. } => {
-                let sequence = self.compose_sequence()?;
+    /// Set the hook called with the resource usage counters as they grow
+    pub fn set_resource_hook(&mut self, hook: Option<Arc<dyn ResourceHook>>) {
+        self.resource_tracker.set_hook(hook);
+    }
 
-                // Store anchor if present
-                if let Some(anchor_name) = anchor {
-                    if let Some(ref seq) = sequence {
-                        self.resource_tracker.add_anchor(&self.limits)?;
-                        self.anchors.insert(anchor_name, seq.clone());
-                    }
-                }
+    /// Set the handlers that construct values with custom tags
+    pub fn set_tag_handlers(&mut self, handlers: TagHandlers) {
+        self.tag_resolver.set_handlers(handlers);
+    }
 
-                Ok(sequence)
-            }
+    /// Set how named tag handles without a `%TAG` directive are resolved
+    pub fn set_unknown_handle(&mut self, unknown_handle: UnknownHandle) {
+        self.tag_resolver.set_unknown_handle(unknown_handle);
+    }
 
-            EventType::MappingStart { anchor, .. } => {
-                let mapping = self.compose_mapping()?;
+    /// Warnings raised while composing the last document
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
 
-                // Store anchor if present
-                if let Some(anchor_name) = anchor {
-                    if let Some(ref map) = mapping {
-                        self.resource_tracker.add_anchor(&self.limits)?;
-                        self.anchors.insert(anchor_name, map.clone());
-                    }
-                }
+    /// The parser this composer reads events from
+    pub const fn parser(&self) -> &BasicParser {
+        &self.parser
+    }
 
-                Ok(mapping)
-            }
+    /// Resources used by everything composed so far
+    pub fn resource_stats(&self) -> ResourceStats {
+        self.resource_tracker.stats()
+    }
 
-            EventType::SequenceEnd | EventType::MappingEnd => {
-                // These collection end events should normally be handled by their respective compose methods.
-                // However, if we encounter them here, it means we're in an unexpected state.
-                // This can happen when the parser generates a flattened structure instead of proper nesting.
-                // Return None to indicate we've reached the end of the current node.
-                Ok(None)
-            }
+    /// The `%YAML` version of the last composed document
+    pub const fn document_version(&self) -> Option<(u8, u8)> {
+        self.version
+    }
 
-            EventType::Alias { anchor } => {
-                // Check for cyclic references
-                if self.alias_expansion_stack.contains(&anchor) {
-                    return Err(Error::construction(
-                        event.position,
-                        format!("Cyclic alias reference detected: '{anchor}'"),
-                    ));
-                }
+    /// Whether the last composed document was closed with a `...` marker
+    pub const fn document_explicit_end(&self) -> bool {
+        self.explicit_end
+    }
 
-                // Check alias expansion depth limit BEFORE pushing
-                if self.alias_expansion_stack.len() >= self.limits.max_alias_depth {
-                    return Err(Error::construction(
-                        event.position,
-                        format!(
-                            "Maximum alias expansion depth {} exceeded",
-                            self.limits.max_alias_depth
-                        ),
-                    ));
+    /// The `%TAG` directives of the last composed document
+    pub fn tag_directives(&self) -> &[TagDirective] {
+        &self.tag_directives
+    }
+
+    /// Where each anchor of the last composed document is defined
+    pub const fn anchor_paths(&self) -> &HashMap<String, Path> {
+        &self.anchor_paths
+    }
+
+    /// Apply the redefinition policy to an anchor defined at the current
+    /// position and remember where it is, unless it sits inside a key
+    fn define_anchor(&mut self, anchor: &str) -> Result<()> {
+        let position = self.position;
+        if let Some(previous) = self.anchor_positions.insert(anchor.to_string(), position) {
+            let message = format!(
+                "Anchor '{anchor}' at {position} redefines the anchor defined at {previous}"
+            );
+            match self.anchor_redefinition {
+                AnchorRedefinition::Allow => {}
+                AnchorRedefinition::Error => {
+                    let context = ErrorContext::from_input(self.parser.input(), &position, 2)
+                        .with_suggestion(
+                            "rename one of the anchors; aliases after this one refer to it"
+                                .to_string(),
+                        );
+                    return Err(Error::construction_with_context(position, message, context));
                 }
+                AnchorRedefinition::Warn => self.warnings.push(message),
+            }
+        }
+        if !self.composing_key {
+            self.anchor_paths
+                .insert(anchor.to_string(), self.path.clone());
+        }
+        Ok(())
+    }
 
-                // Track alias expansion depth
-                self.resource_tracker.enter_alias(&self.limits)?;
-                self.alias_expansion_stack.push(anchor.clone());
-
-                // Resolve alias to the anchored value
-                let result = match self.anchors.get(&anchor) {
-                    Some(value) => {
-                        // Check if the resolved value's structure depth would exceed alias depth limit
-                        let structure_depth = calculate_structure_depth(value);
-                        if structure_depth > self.limits.max_alias_depth {
-                            return Err(Error::construction(
-                                event.position,
-                                format!(
-                                    "Alias '{}' creates structure with depth {} exceeding max_alias_depth {}",
-                                    anchor, structure_depth, self.limits.max_alias_depth
-                                ),
-                            ));
-                        }
+    /// Apply the directives of a document starting at `position`
+    fn start_document(
+        &mut self,
+        version: Option<(u8, u8)>,
+        tags: &[(String, String)],
+        position: Position,
+    ) {
+        let warnings = self.parser.take_directive_warnings(position);
+        self.warnings.extend(warnings);
+
+        // Clear previous document's tag directives
+        self.tag_resolver.clear_directives();
+        self.version = version;
+        self.tag_directives.clear();
+
+        // Add new tag directives from this document
+        for (handle, prefix) in tags {
+            self.tag_resolver
+                .add_directive(handle.clone(), prefix.clone());
+            self.tag_directives.push(TagDirective {
+                handle: handle.clone(),
+                prefix: prefix.clone(),
+            });
+        }
+    }
+
+    /// Compose a node from events
+    ///
+    /// Open collections are kept on an explicit stack rather than the call
+    /// stack, so the nesting a document may have is bounded by
+    /// [`Limits::max_depth`] alone.
+    fn compose_node(&mut self) -> Result<Option<Value>> {
+        let in_key = self.composing_key;
+        let result = self.compose_nested();
+        self.composing_key = in_key;
+        result
+    }
 
-                        // Add complexity score for alias expansion
-                        self.resource_tracker
-                            .add_complexity(&self.limits, calculate_value_complexity(value)?)?;
-                        Ok(Some(value.clone()))
+    /// Compose the next node and everything nested in it, leaving the
+    /// composing key state changed
+    fn compose_nested(&mut self) -> Result<Option<Value>> {
+        let mut stack: Vec<Frame> = Vec::new();
+        loop {
+            let composed = match stack.last() {
+                Some(frame) if self.collection_ends(frame)? => match stack.pop() {
+                    Some(frame) => Composed::Node(self.finish_collection(frame)?),
+                    None => Composed::End,
+                },
+                Some(frame) => {
+                    self.composing_key = frame.in_key;
+                    match &frame.content {
+                        FrameContent::Sequence(items) => {
+                            self.path.push(PathSegment::Index(items.len()));
+                        }
+                        FrameContent::Mapping(_, Some(key)) => {
+                            self.path.push(PathSegment::Key(key.clone()));
+                        }
+                        FrameContent::Mapping(_, None) => self.composing_key = true,
                     }
-                    None => Err(Error::construction(
-                        event.position,
-                        format!("Unknown anchor '{anchor}'"),
-                    )),
-                };
+                    self.read_node()?
+                }
+                None => self.read_node()?,
+            };
 
-                // Clean up tracking
-                self.alias_expansion_stack.pop();
-                self.resource_tracker.exit_alias();
+            let mut node = match composed {
+                Composed::Node(value) => Some(value),
+                Composed::Collection(frame) => {
+                    stack.push(frame);
+                    continue;
+                }
+                Composed::End => None,
+            };
 
-                result
+            // Hand the node to the collection holding it, finishing the
+            // collections it completes
+            loop {
+                let Some(frame) = stack.last_mut() else {
+                    return Ok(node);
+                };
+                match &mut frame.content {
+                    FrameContent::Sequence(items) => {
+                        self.path.pop();
+                        if let Some(item) = node {
+                            self.resource_tracker.add_collection_item(&self.limits)?;
+                            self.resource_tracker.add_complexity(&self.limits, 1)?;
+                            items.push(item);
+                            break;
+                        }
+                    }
+                    FrameContent::Mapping(mapping, pending) => match pending.take() {
+                        Some(key) => {
+                            self.path.pop();
+                            let value = node.unwrap_or(Value::Null);
+
+                            // Check for merge key (YAML 1.2 specification)
+                            if let Value::String(key_str) = &key {
+                                if key_str == "<<" {
+                                    // Handle merge key - the value is already resolved
+                                    self.process_merge_key(mapping, &value)?;
+                                    break;
+                                }
+                            }
+
+                            self.resource_tracker.add_collection_item(&self.limits)?;
+                            self.resource_tracker.add_complexity(&self.limits, 2)?; // Key-value pair
+                            mapping.insert(key, value);
+                            break;
+                        }
+                        None if node.is_some() => {
+                            if let Some(key) = &node {
+                                self.acceptance.check_key(
+                                    key,
+                                    self.parser.input(),
+                                    self.position,
+                                )?;
+                            }
+                            *pending = node.map(|key| self.key_coercion.apply(key));
+                            break;
+                        }
+                        None => {}
+                    },
+                }
+                // No node came, so the collection ends here
+                if let Some(frame) = stack.pop() {
+                    node = Some(self.finish_collection(frame)?);
+                }
             }
         }
     }
 
-    /// Compose a scalar value
-    fn compose_scalar(&self, value: String, style: crate::parser::ScalarStyle) -> Result<Value> {
-        // If explicitly quoted (single or double quotes), always treat as string
-        match style {
-            crate::parser::ScalarStyle::SingleQuoted | crate::parser::ScalarStyle::DoubleQuoted => {
-                return Ok(Value::String(value));
-            }
-            _ => {
-                // Continue with implicit type resolution for plain, literal, and folded styles
+    /// Whether the collection of `frame` ends before its next node,
+    /// consuming its end event
+    fn collection_ends(&mut self, frame: &Frame) -> Result<bool> {
+        if !self.parser.check_event() {
+            return Ok(true);
+        }
+        let Some(event) = self.parser.peek_event() else {
+            return Ok(false);
+        };
+        match (&event.event_type, &frame.content) {
+            (EventType::SequenceEnd, FrameContent::Sequence(_))
+            | (EventType::MappingEnd, FrameContent::Mapping(..)) => {
+                // Consume the end event
+                self.parser.get_event()?;
+                Ok(true)
             }
+            // Don't consume these - let compose_document handle them
+            (
+                EventType::DocumentEnd { .. }
+                | EventType::DocumentStart { .. }
+                | EventType::StreamEnd,
+                _,
+            ) => Ok(true),
+            _ => Ok(false),
         }
+    }
 
-        // For now, do basic type resolution
-        if value.is_empty() {
-            return Ok(Value::String(value));
-        }
+    /// Read the events of the next scalar or alias, or the start of the
+    /// next collection
+    fn read_node(&mut self) -> Result<Composed> {
+        loop {
+            if !self.parser.check_event() {
+                return Ok(Composed::End);
+            }
 
-        // Try integer parsing
-        if let Ok(int_value) = value.parse::<i64>() {
-            return Ok(Value::Int(int_value));
-        }
+            let Some(event) = self.parser.get_event()? else {
+                return Ok(Composed::End);
+            };
 
-        // Try float parsing
-        if let Ok(float_value) = value.parse::<f64>() {
-            return Ok(Value::Float(float_value));
-        }
+            self.position = event.position;
 
-        // Try boolean parsing
-        match value.to_lowercase().as_str() {
-            "true" | "yes" | "on" => return Ok(Value::Bool(true)),
-            "false" | "no" | "off" => return Ok(Value::Bool(false)),
-            "null" | "~" => return Ok(Value::Null),
-            _ => {}
-        }
+            match event.event_type {
+                EventType::StreamStart | EventType::StreamEnd => {
+                    // Skip stream boundaries, these don't produce nodes
+                }
 
-        // Default to string
-        Ok(Value::String(value))
-    }
+                EventType::DocumentStart { version, tags, .. } => {
+                    // A lazy parser only produces the start here, after
+                    // compose_document looked for it
+                    self.start_document(version, &tags, event.position);
+                }
 
-    /// Compose a tagged scalar value
-    fn compose_tagged_scalar(&mut self, value: String, tag_str: String) -> Result<Value> {
-        // Resolve the tag (TagResolver should handle already-resolved URIs)
-        let tag = self.tag_resolver.resolve(&tag_str)?;
+                // These end the current node: a document end, or the end
+                // of a collection of another kind when the parser generates
+                // a flattened structure instead of proper nesting
+                EventType::DocumentEnd { .. } | EventType::SequenceEnd | EventType::MappingEnd => {
+                    return Ok(Composed::End);
+                }
 
-        // Apply the tag to the value
-        self.tag_resolver.apply_tag(&tag, &value)
-    }
+                EventType::Scalar {
+                    value,
+                    anchor,
+                    tag,
+                    style,
+                    ..
+                } => {
+                    let scalar_value = if let Some(tag_str) = tag {
+                        // Apply tag if present
+                        self.compose_tagged_scalar(value, tag_str)?
+                    } else {
+                        // Use implicit typing
+                        self.empty_scalar
+                            .resolve_for(self.acceptance.loader_type, value, style)
+                    };
 
-    /// Compose a sequence
-    fn compose_sequence(&mut self) -> Result<Option<Value>> {
-        // Track depth
-        self.current_depth += 1;
-        self.resource_tracker
-            .check_depth(&self.limits, self.current_depth)?;
+                    // Store anchor if present
+                    if let Some(anchor_name) = anchor {
+                        self.resource_tracker.add_anchor(&self.limits)?;
+                        self.define_anchor(&anchor_name)?;
+                        self.anchors.insert(anchor_name, scalar_value.clone());
+                    }
 
-        let mut sequence = Vec::new();
-
-        while self.parser.check_event() {
-            // Peek at the next event to see if we're at the end
-            if let Ok(Some(event)) = self.parser.peek_event() {
-                if matches!(event.event_type, EventType::SequenceEnd) {
-                    // Consume the end event
-                    self.parser.get_event()?;
-                    break;
-                } else if matches!(
-                    event.event_type,
-                    EventType::DocumentEnd { .. }
-                        | EventType::DocumentStart { .. }
-                        | EventType::StreamEnd
-                ) {
-                    // Don't consume these - let compose_document handle them
-                    break;
+                    return Ok(Composed::Node(scalar_value));
                 }
-            }
 
-            // Compose the next element
-            if let Some(node) = self.compose_node()? {
-                self.resource_tracker.add_collection_item(&self.limits)?;
-                self.resource_tracker.add_complexity(&self.limits, 1)?;
-                sequence.push(node);
-            } else {
-                // If compose_node returns None, we might have hit a document boundary
-                break;
+                EventType::SequenceStart { anchor, tag, .. } => {
+                    return self.open_collection(anchor, tag, FrameContent::Sequence(Vec::new()));
+                }
+
+                EventType::MappingStart { anchor, tag, .. } => {
+                    return self.open_collection(
+                        anchor,
+                        tag,
+                        FrameContent::Mapping(IndexMap::new(), None),
+                    );
+                }
+
+                EventType::Alias { anchor } => {
+                    return self
+                        .resolve_alias(anchor, event.position)
+                        .map(Composed::Node);
+                }
             }
         }
-
-        self.current_depth -= 1;
-        Ok(Some(Value::Sequence(sequence)))
     }
 
-    /// Compose a mapping
-    fn compose_mapping(&mut self) -> Result<Option<Value>> {
+    /// Start composing a collection at the current position
+    fn open_collection(
+        &mut self,
+        anchor: Option<String>,
+        tag: Option<String>,
+        content: FrameContent,
+    ) -> Result<Composed> {
+        if let Some(anchor_name) = &anchor {
+            self.define_anchor(anchor_name)?;
+        }
+
         // Track depth
         self.current_depth += 1;
         self.resource_tracker
             .check_depth(&self.limits, self.current_depth)?;
 
-        let mut mapping = IndexMap::new();
-
-        while self.parser.check_event() {
-            // Peek at the next event to see if we're at the end
-            if let Ok(Some(event)) = self.parser.peek_event() {
-                if matches!(event.event_type, EventType::MappingEnd) {
-                    // Consume the end event
-                    self.parser.get_event()?;
-                    break;
-                } else if matches!(
-                    event.event_type,
-                    EventType::DocumentEnd { .. }
-                        | EventType::DocumentStart { .. }
-                        | EventType::StreamEnd
-                ) {
-                    // Don't consume these - let compose_document handle them
-                    break;
-                }
+        Ok(Composed::Collection(Frame {
+            anchor,
+            tag,
+            position: self.position,
+            in_key: self.composing_key,
+            content,
+        }))
+    }
+
+    /// Build the value of a collection whose entries are all composed,
+    /// applying its tag and storing its anchor
+    fn finish_collection(&mut self, frame: Frame) -> Result<Value> {
+        self.current_depth -= 1;
+
+        let value = match frame.content {
+            FrameContent::Sequence(items) => Value::Sequence(items),
+            #[cfg_attr(not(feature = "sort-keys"), allow(unused_mut))]
+            FrameContent::Mapping(mut mapping, _) => {
+                #[cfg(feature = "sort-keys")]
+                mapping.sort_keys();
+                Value::Mapping(mapping)
             }
+        };
+        let value = match frame.tag {
+            Some(tag_str) => self.compose_tagged_collection(value, &tag_str, frame.position)?,
+            None => value,
+        };
 
-            // Compose key
-            let Some(key) = self.compose_node()? else {
-                break;
-            };
+        // Store anchor if present
+        if let Some(anchor_name) = frame.anchor {
+            self.resource_tracker.add_anchor(&self.limits)?;
+            self.anchors.insert(anchor_name, value.clone());
+        }
+
+        Ok(value)
+    }
 
-            // Compose value
-            let value = self.compose_node()?.unwrap_or(Value::Null);
+    /// The value of the anchor an alias at `position` refers to
+    fn resolve_alias(&mut self, anchor: String, position: Position) -> Result<Value> {
+        // Check for cyclic references
+        if self.alias_expansion_stack.contains(&anchor) {
+            return Err(cyclic_alias(self.parser.input(), position, &anchor));
+        }
 
-            // Check for merge key (YAML 1.2 specification)
-            if let Value::String(key_str) = &key {
-                if key_str == "<<" {
-                    // Handle merge key - the value should already be resolved by compose_node()
-                    self.process_merge_key(&mut mapping, &value)?;
-                    continue;
+        // Check alias expansion depth limit BEFORE pushing
+        if self.alias_expansion_stack.len() >= self.limits.max_alias_depth {
+            return Err(Error::construction(
+                position,
+                format!(
+                    "Maximum alias expansion depth {} exceeded",
+                    self.limits.max_alias_depth
+                ),
+            ));
+        }
+
+        // Track alias expansion depth
+        self.resource_tracker.enter_alias(&self.limits)?;
+        self.alias_expansion_stack.push(anchor.clone());
+
+        // Resolve alias to the anchored value
+        let result = match self.anchors.get(&anchor) {
+            Some(value) => {
+                // Check if the resolved value's structure depth would exceed alias depth limit
+                let structure_depth = calculate_structure_depth(value);
+                if structure_depth > self.limits.max_alias_depth {
+                    return Err(Error::construction(
+                        position,
+                        format!(
+                            "Alias '{}' creates structure with depth {} exceeding max_alias_depth {}",
+                            anchor, structure_depth, self.limits.max_alias_depth
+                        ),
+                    ));
                 }
+
+                // Add complexity score for alias expansion
+                self.resource_tracker
+                    .add_complexity(&self.limits, calculate_value_complexity(value)?)?;
+                Ok(value.clone())
             }
+            None => Err(unknown_anchor(self.parser.input(), position, &anchor)),
+        };
+
+        // Clean up tracking
+        self.alias_expansion_stack.pop();
+        self.resource_tracker.exit_alias();
+
+        result
+    }
+
+    /// Compose a tagged scalar value
+    fn compose_tagged_scalar(&mut self, value: String, tag_str: String) -> Result<Value> {
+        // Resolve the tag (TagResolver should handle already-resolved URIs)
+        let tag = self.tag_resolver.resolve_at(&tag_str, self.position)?;
+        self.acceptance
+            .check_tag(&tag, &self.tag_resolver, self.parser.input(), self.position)?;
 
-            self.resource_tracker.add_collection_item(&self.limits)?;
-            self.resource_tracker.add_complexity(&self.limits, 2)?; // Key-value pair
-            mapping.insert(key, value);
+        if self.acceptance.loader_type == LoaderType::Strict && !self.tag_resolver.has_handler(&tag)
+        {
+            return self.construct_core_scalar(&tag, value);
         }
 
-        self.current_depth -= 1;
-        Ok(Some(Value::Mapping(mapping)))
+        // Apply the tag to the value
+        self.tag_resolver.apply_tag(&tag, &value)
+    }
+
+    /// Apply the tag of a sequence or mapping to its composed value
+    fn compose_tagged_collection(
+        &self,
+        value: Value,
+        tag_str: &str,
+        position: Position,
+    ) -> Result<Value> {
+        let tag = self.tag_resolver.resolve_at(tag_str, position)?;
+        self.acceptance
+            .check_tag(&tag, &self.tag_resolver, self.parser.input(), position)?;
+        let expected = match value {
+            Value::Sequence(_) => TagKind::Seq,
+            _ => TagKind::Map,
+        };
+        if self.acceptance.loader_type == LoaderType::Strict
+            && !matches!(tag.kind, TagKind::Custom(_))
+            && tag.kind != expected
+        {
+            return Err(self.acceptance.tag_error(
+                self.parser.input(),
+                position,
+                format!(
+                    "Tag '{}' does not match the collection it is on",
+                    tag.original
+                ),
+            ));
+        }
+        Ok(self.tag_resolver.apply_collection_tag(&tag, value))
+    }
+
+    /// Construct a scalar with a core schema tag, which its text must match
+    fn construct_core_scalar(&self, tag: &Tag, value: String) -> Result<Value> {
+        let constructed = match tag.kind {
+            TagKind::Str => return Ok(Value::String(value)),
+            TagKind::Int => core_int(&value).map(Value::Int),
+            TagKind::Float => core_float(&value).map(Value::Float),
+            TagKind::Null | TagKind::Bool => {
+                match resolve_core_scalar(value.clone(), crate::parser::ScalarStyle::Plain) {
+                    Value::Null if tag.kind == TagKind::Null => Some(Value::Null),
+                    Value::Bool(b) if tag.kind == TagKind::Bool => Some(Value::Bool(b)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        constructed.ok_or_else(|| {
+            self.acceptance.tag_error(
+                self.parser.input(),
+                self.position,
+                format!(
+                    "'{}' is not a valid value for tag '{}' in the YAML 1.2 core schema",
+                    value, tag.original
+                ),
+            )
+        })
     }
 
     /// Process a merge key by merging values into the current mapping
-    /// The `merge_value` should already be resolved by `compose_node()`
+    /// The `merge_value` should already be resolved by `compose_nested()`
     fn process_merge_key(
         &self,
         mapping: &mut IndexMap<Value, Value>,
@@ -438,25 +1167,34 @@ impl BasicComposer {
                 }
             }
 
-            // Sequence of mappings to merge (in order)
+            // Sequence of mappings to merge, in precedence order
             Value::Sequence(sources) => {
+                let mut source_maps = Vec::with_capacity(sources.len());
                 for source in sources {
                     if let Value::Mapping(source_map) = source {
-                        for (key, value) in source_map {
-                            // Only insert if key doesn't already exist
-                            mapping.entry(key.clone()).or_insert_with(|| value.clone());
-                        }
+                        source_maps.push(source_map);
                     } else {
-                        return Err(Error::construction(
+                        return Err(invalid_merge(
+                            self.parser.input(),
                             self.position,
                             "Merge key sequence can only contain mappings",
                         ));
                     }
                 }
+                if self.merge_precedence == MergePrecedence::LastWins {
+                    source_maps.reverse();
+                }
+                for source_map in source_maps {
+                    for (key, value) in source_map {
+                        // Only insert if key doesn't already exist
+                        mapping.entry(key.clone()).or_insert_with(|| value.clone());
+                    }
+                }
             }
 
             _ => {
-                return Err(Error::construction(
+                return Err(invalid_merge(
+                    self.parser.input(),
                     self.position,
                     "Merge key value must be a mapping or sequence of mappings",
                 ));
@@ -468,12 +1206,12 @@ impl BasicComposer {
 }
 
 impl Composer for BasicComposer {
-    fn check_document(&self) -> bool {
+    fn check_document(&mut self) -> bool {
         // Check if there are events that could form a document
-        if let Ok(Some(event)) = self.parser.peek_event() {
-            !matches!(event.event_type, EventType::StreamEnd)
-        } else {
-            false
+        match self.parser.peek_event() {
+            Some(event) => !matches!(event.event_type, EventType::StreamEnd),
+            // A pending error is reported by `compose_document`
+            None => self.parser.check_event(),
         }
     }
 
@@ -483,20 +1221,22 @@ impl Composer for BasicComposer {
             return Err(error);
         }
 
-        // Process document start events and extract tag directives
-        while let Ok(Some(event)) = self.parser.peek_event() {
-            if let EventType::DocumentStart { tags, .. } = &event.event_type {
-                // Clear previous document's tag directives
-                self.tag_resolver.clear_directives();
-
-                // Add new tag directives from this document
-                for (handle, prefix) in tags {
-                    self.tag_resolver
-                        .add_directive(handle.clone(), prefix.clone());
-                }
+        self.version = None;
+        self.tag_directives.clear();
+        self.explicit_end = false;
+        self.path = Path::new();
+        if self.anchor_scope == AnchorScope::Document {
+            self.anchors.clear();
+        }
+        self.anchor_paths.clear();
+        self.anchor_positions.clear();
+        self.warnings.clear();
 
-                self.parser.get_event()?; // consume the DocumentStart
-            } else if matches!(event.event_type, EventType::DocumentStart { .. }) {
+        // Process document start events and extract tag directives
+        while let Some(event) = self.parser.peek_event() {
+            if let EventType::DocumentStart { version, tags, .. } = &event.event_type {
+                let (version, tags, position) = (*version, tags.clone(), event.position);
+                self.start_document(version, &tags, position);
                 self.parser.get_event()?; // consume the DocumentStart
             } else {
                 break;
@@ -506,9 +1246,10 @@ impl Composer for BasicComposer {
         // Compose the actual document content
         let document = self.compose_node()?;
 
-        // Skip any document end event
-        while let Ok(Some(event)) = self.parser.peek_event() {
-            if matches!(event.event_type, EventType::DocumentEnd { .. }) {
+        // Skip any document end event, noting whether it was written out
+        while let Some(event) = self.parser.peek_event() {
+            if let EventType::DocumentEnd { implicit } = event.event_type {
+                self.explicit_end |= !implicit;
                 self.parser.get_event()?; // consume the DocumentEnd
             } else {
                 break;
@@ -528,7 +1269,15 @@ impl Composer for BasicComposer {
         self.resource_tracker.reset();
         self.alias_expansion_stack.clear();
         self.current_depth = 0;
-        self.tag_resolver = TagResolver::new();
+        self.tag_resolver.clear_directives();
+        self.version = None;
+        self.tag_directives.clear();
+        self.explicit_end = false;
+        self.path = Path::new();
+        self.composing_key = false;
+        self.anchor_paths.clear();
+        self.anchor_positions.clear();
+        self.warnings.clear();
     }
 }
 
@@ -559,6 +1308,24 @@ mod tests {
         assert_eq!(document, Value::Int(42));
     }
 
+    #[test]
+    fn test_hex_and_octal_composition() {
+        let mut composer =
+            BasicComposer::new_eager("[0xFF, 0o755, 0x, 0o8, 0x-1, 1.2.3]".to_string());
+        let document = composer.compose_document().unwrap().unwrap();
+        assert_eq!(
+            document,
+            Value::Sequence(vec![
+                Value::Int(255),
+                Value::Int(493),
+                Value::String("0x".to_string()),
+                Value::String("0o8".to_string()),
+                Value::String("0x-1".to_string()),
+                Value::String("1.2.3".to_string()),
+            ])
+        );
+    }
+
     #[test]
     fn test_boolean_composition() {
         let mut composer = BasicComposer::new_eager("true".to_string());
@@ -688,6 +1455,51 @@ test:
         }
     }
 
+    #[test]
+    fn test_merge_precedence() {
+        let yaml = "a: &a\n  x: 1\n  y: 1\nb: &b\n  x: 2\n  z: 2\nc:\n  <<: [*a, *b]\n  y: 3\n";
+        let merged = |precedence| {
+            let mut composer = BasicComposer::new_eager(yaml.to_string());
+            composer.set_merge_precedence(precedence);
+            let document = composer.compose_document().unwrap().unwrap();
+            document.get_str("c").unwrap().clone()
+        };
+
+        let spec = merged(MergePrecedence::Spec);
+        assert_eq!(spec.get_str("x"), Some(&Value::Int(1)));
+        assert_eq!(spec.get_str("y"), Some(&Value::Int(3)));
+        assert_eq!(spec.get_str("z"), Some(&Value::Int(2)));
+
+        let last_wins = merged(MergePrecedence::LastWins);
+        assert_eq!(last_wins.get_str("x"), Some(&Value::Int(2)));
+        assert_eq!(last_wins.get_str("y"), Some(&Value::Int(3)));
+    }
+
+    #[test]
+    fn test_alias_and_merge_errors_quote_source() {
+        let compose = |input: &str| {
+            let mut composer = BasicComposer::new_eager(input.to_string());
+            composer.set_anchor_redefinition(AnchorRedefinition::Error);
+            composer.compose_document().unwrap_err()
+        };
+
+        let error = compose("base: 1\nref: *missing\n");
+        assert_eq!(error.position().map(|p| p.line), Some(2));
+        let message = error.to_string();
+        assert!(message.contains("Unknown anchor 'missing'"), "{message}");
+        assert!(message.contains("   2 | ref: *missing"), "{message}");
+        assert!(message.contains("'&missing'"), "{message}");
+
+        let message = compose("a: &x 1\nb: &x 2\n").to_string();
+        assert!(message.contains("redefines the anchor"), "{message}");
+        assert!(message.contains("   2 | b: &x 2"), "{message}");
+
+        let message = compose("a:\n  <<: 5\n").to_string();
+        assert!(message.contains("Merge key value must be"), "{message}");
+        assert!(message.contains("   2 |   <<: 5"), "{message}");
+        assert!(message.contains("Suggestion: merge a mapping"), "{message}");
+    }
+
     #[test]
     fn test_debug_alias_tokens() {
         let yaml_content = r"
@@ -827,4 +1639,97 @@ ref: *base
             panic!("Document should be a mapping, got: {:?}", document);
         }
     }
+
+    #[test]
+    fn test_anchored_key_alias_resolution() {
+        let compose = |input: &str| {
+            BasicComposer::new_eager(input.to_string())
+                .compose_document()
+                .unwrap()
+                .unwrap()
+        };
+        let string = |value: &str| Value::String(value.to_string());
+
+        // An anchor on the key's line names the key, not the mapping
+        let mut expected = IndexMap::new();
+        expected.insert(string("key"), string("value"));
+        expected.insert(string("other"), string("key"));
+        assert_eq!(
+            compose("&a key: value\nother: *a\n"),
+            Value::Mapping(expected.clone())
+        );
+        assert_eq!(
+            compose("{&a key: value, other: *a}"),
+            Value::Mapping(expected)
+        );
+
+        // An aliased key reuses the anchored node, including a tagged one
+        let mut expected = IndexMap::new();
+        expected.insert(string("1"), string("v"));
+        expected.insert(string("w"), string("1"));
+        assert_eq!(compose("&k !!str 1: v\nw: *k\n"), Value::Mapping(expected));
+
+        let mut expected = IndexMap::new();
+        expected.insert(string("x"), Value::Int(1));
+        expected.insert(string("y"), string("x"));
+        assert_eq!(
+            compose("- &a x: 1\n  y: *a\n"),
+            Value::Sequence(vec![Value::Mapping(expected)])
+        );
+
+        // Anchors inside flow collections and on a mapping on its own line
+        assert_eq!(
+            compose("[&x 1, *x]"),
+            Value::Sequence(vec![Value::Int(1), Value::Int(1)])
+        );
+        let mut inner = IndexMap::new();
+        inner.insert(string("a"), Value::Int(1));
+        let mut expected = IndexMap::new();
+        expected.insert(string("m"), Value::Mapping(inner.clone()));
+        expected.insert(string("n"), Value::Mapping(inner));
+        assert_eq!(compose("m: &m\n  a: 1\nn: *m\n"), Value::Mapping(expected));
+    }
+
+    #[test]
+    fn test_directive_warnings_per_document() {
+        let input = "%YAML 1.3\n---\na: 1\n...\n%FOO bar\n%TAG !e! tag:e.com:\n%TAG !e! tag:f.com:\n---\nb: 2\n";
+        let mut scanner = crate::BasicScanner::new(input.to_string());
+        scanner.set_invalid_directive(crate::InvalidDirective::Warn);
+        let mut composer =
+            BasicComposer::from_parser(BasicParser::from_scanner_eager(scanner), Limits::default());
+
+        assert!(composer.compose_document().unwrap().is_some());
+        assert_eq!(composer.document_version(), Some((1, 3)));
+        assert_eq!(composer.warnings().len(), 1);
+        assert!(composer.warnings()[0].contains("1.3 at line 1"));
+
+        assert!(composer.compose_document().unwrap().is_some());
+        assert_eq!(composer.document_version(), None);
+        assert_eq!(composer.warnings().len(), 2);
+        assert!(composer.warnings()[0].contains("'%FOO' at line 5"));
+        assert!(composer.warnings()[1].contains("'!e!' at line 7"));
+        assert_eq!(composer.tag_directives()[0].prefix, "tag:e.com:");
+    }
+
+    #[test]
+    fn test_deep_nesting_does_not_recurse() {
+        // Far deeper than composing on the call stack of a test thread allows
+        let depth = 50_000;
+        let input = format!("{}1{}", "[{k: ".repeat(depth), "}]".repeat(depth));
+        let mut composer = BasicComposer::new_eager_with_limits(input.clone(), Limits::unlimited());
+        let mut node = &composer.compose_document().unwrap().unwrap();
+        for _ in 0..depth {
+            node = &node[0];
+            assert!(node.is_mapping());
+            node = &node["k"];
+        }
+        assert_eq!(node, &Value::Int(1));
+
+        let limits = Limits {
+            max_depth: depth,
+            ..Limits::unlimited()
+        };
+        let mut composer = BasicComposer::new_eager_with_limits(input, limits);
+        assert!(composer.compose_document().is_err());
+    }
 }
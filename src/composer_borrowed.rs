@@ -3,13 +3,16 @@
 //! This module provides a composer that minimizes allocations by using
 //! borrowed data structures where possible.
 
+use crate::composer::{cyclic_alias, unknown_anchor};
 use crate::{
     parser::{EventType, ScalarStyle},
     value_borrowed::BorrowedValue,
-    BasicParser, Error, Limits, Parser, Position, ResourceTracker, Result,
+    BasicParser, EmptyScalar, Error, Limits, Parser, Position, ResourceHook, ResourceTracker,
+    Result,
 };
 use indexmap::IndexMap;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 /// Calculate the maximum nesting depth of a borrowed value structure
 fn calculate_borrowed_structure_depth(value: &BorrowedValue) -> usize {
@@ -43,7 +46,7 @@ fn calculate_borrowed_structure_depth(value: &BorrowedValue) -> usize {
 /// Trait for zero-copy YAML composers
 pub trait BorrowedComposer<'a> {
     /// Check if there are more documents available
-    fn check_document(&self) -> bool;
+    fn check_document(&mut self) -> bool;
 
     /// Compose the next document with minimal allocations
     fn compose_document(&mut self) -> Result<Option<BorrowedValue<'a>>>;
@@ -67,6 +70,7 @@ pub struct ZeroCopyComposer<'a> {
     current_depth: usize,
     /// Reference to the input string for borrowing
     input: &'a str,
+    empty_scalar: EmptyScalar,
 }
 
 impl<'a> ZeroCopyComposer<'a> {
@@ -86,9 +90,20 @@ impl<'a> ZeroCopyComposer<'a> {
             alias_expansion_stack: Vec::new(),
             current_depth: 0,
             input,
+            empty_scalar: EmptyScalar::default(),
         }
     }
 
+    /// Set what an empty plain scalar is loaded as
+    pub fn set_empty_scalar(&mut self, empty_scalar: EmptyScalar) {
+        self.empty_scalar = empty_scalar;
+    }
+
+    /// Set the hook called with the resource usage counters as they grow
+    pub fn set_resource_hook(&mut self, hook: Option<Arc<dyn ResourceHook>>) {
+        self.resource_tracker.set_hook(hook);
+    }
+
     /// Compose a node from events with minimal allocations
     fn compose_node(&mut self) -> Result<Option<BorrowedValue<'a>>> {
         if !self.parser.check_event() {
@@ -162,9 +177,10 @@ impl<'a> ZeroCopyComposer<'a> {
                 // Check for cyclic references
                 let anchor_str = anchor.as_str();
                 if self.alias_expansion_stack.iter().any(|&a| a == anchor_str) {
-                    return Err(Error::construction(
+                    return Err(cyclic_alias(
+                        self.parser.input(),
                         event.position,
-                        format!("Cyclic alias reference detected: '{}'", anchor_str),
+                        anchor_str,
                     ));
                 }
 
@@ -200,9 +216,10 @@ impl<'a> ZeroCopyComposer<'a> {
                         // Only clone if we absolutely need to
                         Ok(Some(value.clone_if_needed()))
                     }
-                    None => Err(Error::construction(
+                    None => Err(unknown_anchor(
+                        self.parser.input(),
                         event.position,
-                        format!("Unknown anchor '{}'", anchor_str),
+                        anchor_str,
                     )),
                 };
 
@@ -230,11 +247,14 @@ impl<'a> ZeroCopyComposer<'a> {
 
         // Type resolution for unquoted scalars
         if value.is_empty() {
-            return Ok(BorrowedValue::owned_string(value.to_string()));
+            return Ok(match self.empty_scalar {
+                EmptyScalar::Null => BorrowedValue::Null,
+                EmptyScalar::EmptyString => BorrowedValue::owned_string(String::new()),
+            });
         }
 
         // Try integer parsing
-        if let Ok(int_value) = value.parse::<i64>() {
+        if let Some(int_value) = crate::composer::parse_int(value) {
             return Ok(BorrowedValue::Int(int_value));
         }
 
@@ -242,6 +262,9 @@ impl<'a> ZeroCopyComposer<'a> {
         if let Ok(float_value) = value.parse::<f64>() {
             return Ok(BorrowedValue::Float(float_value));
         }
+        if let Some(float_value) = crate::composer::special_float(value) {
+            return Ok(BorrowedValue::Float(float_value));
+        }
 
         // Try boolean parsing
         match value.to_lowercase().as_str() {
@@ -265,7 +288,7 @@ impl<'a> ZeroCopyComposer<'a> {
         let mut sequence = Vec::new();
 
         while self.parser.check_event() {
-            if let Ok(Some(event)) = self.parser.peek_event() {
+            if let Some(event) = self.parser.peek_event() {
                 if matches!(event.event_type, EventType::SequenceEnd) {
                     self.parser.get_event()?;
                     break;
@@ -301,7 +324,7 @@ impl<'a> ZeroCopyComposer<'a> {
         let mut mapping = IndexMap::new();
 
         while self.parser.check_event() {
-            if let Ok(Some(event)) = self.parser.peek_event() {
+            if let Some(event) = self.parser.peek_event() {
                 if matches!(event.event_type, EventType::MappingEnd) {
                     self.parser.get_event()?;
                     break;
@@ -333,11 +356,11 @@ impl<'a> ZeroCopyComposer<'a> {
 }
 
 impl<'a> BorrowedComposer<'a> for ZeroCopyComposer<'a> {
-    fn check_document(&self) -> bool {
-        if let Ok(Some(event)) = self.parser.peek_event() {
-            !matches!(event.event_type, EventType::StreamEnd)
-        } else {
-            false
+    fn check_document(&mut self) -> bool {
+        match self.parser.peek_event() {
+            Some(event) => !matches!(event.event_type, EventType::StreamEnd),
+            // A pending error is reported by `compose_document`
+            None => self.parser.check_event(),
         }
     }
 
@@ -347,7 +370,7 @@ impl<'a> BorrowedComposer<'a> for ZeroCopyComposer<'a> {
         }
 
         // Skip any leading document start events
-        while let Ok(Some(event)) = self.parser.peek_event() {
+        while let Some(event) = self.parser.peek_event() {
             if matches!(event.event_type, EventType::DocumentStart { .. }) {
                 self.parser.get_event()?;
             } else {
@@ -358,7 +381,7 @@ impl<'a> BorrowedComposer<'a> for ZeroCopyComposer<'a> {
         let document = self.compose_node()?;
 
         // Skip any document end event
-        while let Ok(Some(event)) = self.parser.peek_event() {
+        while let Some(event) = self.parser.peek_event() {
             if matches!(event.event_type, EventType::DocumentEnd { .. }) {
                 self.parser.get_event()?;
             } else {
@@ -419,6 +442,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_zero_copy_empty_scalars() {
+        let compose = |empty_scalar| {
+            let mut composer = ZeroCopyComposer::new("a:\nb: ''\n");
+            composer.set_empty_scalar(empty_scalar);
+            match composer.compose_document().unwrap().unwrap() {
+                BorrowedValue::Mapping(map) => map.into_values().collect::<Vec<_>>(),
+                other => panic!("Expected mapping, got {other:?}"),
+            }
+        };
+
+        let empty = BorrowedValue::owned_string(String::new());
+        assert_eq!(
+            compose(EmptyScalar::Null),
+            vec![BorrowedValue::Null, empty.clone()]
+        );
+        assert_eq!(
+            compose(EmptyScalar::EmptyString),
+            vec![empty.clone(), empty]
+        );
+    }
+
     #[test]
     fn test_zero_copy_mapping() {
         let input = r#"{"key": "value"}"#;
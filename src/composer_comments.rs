@@ -1,17 +1,25 @@
 //! Comment-preserving YAML composer
 
+use crate::composer::{cyclic_alias, unknown_anchor, Acceptance, KeyCoercion};
 use crate::{
-    parser::EventType, BasicParser, BasicScanner, CommentedValue, Comments, Error, Limits, Parser,
-    Position, ResourceTracker, Result, Scanner, Style, TokenType, Value,
+    composer::{bool_word, parse_int},
+    parser::{EventType, ScalarStyle},
+    tag::{TagHandlers, TagResolver, UnknownHandle},
+    BasicParser, BasicScanner, BlockScalarStyle, Chomping, CommentedValue, Comments, EmptyScalar,
+    Error, Limits, LoaderType, MergePrecedence, NullStyle, Parser, Position, QuoteStyle,
+    ResourceHook, ResourceTracker, Result, Scanner, Style, TokenType, Value,
 };
 use indexmap::IndexMap;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
 /// A composer that preserves comments during parsing
 #[derive(Debug)]
 pub struct CommentPreservingComposer {
     parser: BasicParser,
     scanner: BasicScanner,
+    /// The input text, used to recover how plain scalars were written
+    source: String,
     limits: Limits,
     resource_tracker: ResourceTracker,
     anchors: HashMap<String, CommentedValue>,
@@ -19,8 +27,27 @@ pub struct CommentPreservingComposer {
     alias_expansion_stack: Vec<String>,
     /// Map of positions to comments (position -> comment text)
     comment_map: HashMap<Position, String>,
+    /// Lines (1-based) that hold nothing but a comment
+    comment_lines: HashSet<usize>,
+    /// Lines (1-based) that hold nothing but whitespace
+    blank_lines: HashSet<usize>,
     /// Stack of pending comments that might belong to the next value
     pending_comments: Vec<String>,
+    /// Keep `<<` entries as ordinary keys instead of resolving them
+    preserve_merge_keys: bool,
+    /// Override order used when resolving merge keys
+    merge_precedence: MergePrecedence,
+    /// What an empty plain scalar is loaded as
+    empty_scalar: EmptyScalar,
+    key_coercion: KeyCoercion,
+    /// `%YAML` directive of the document being composed
+    yaml_version: Option<(u8, u8)>,
+    /// `%TAG` directives of the document being composed
+    tag_directives: Vec<(String, String)>,
+    /// Constructs the values of explicitly tagged scalars
+    tag_resolver: TagResolver,
+    /// The tags and mapping keys accepted
+    acceptance: Acceptance,
 }
 
 impl CommentPreservingComposer {
@@ -31,23 +58,97 @@ impl CommentPreservingComposer {
 
     /// Create a new comment-preserving composer with limits
     pub fn with_limits(input: String, limits: Limits) -> Self {
+        let comment_lines = input
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| line.trim_start().starts_with('#'))
+            .map(|(index, _)| index + 1)
+            .collect();
+        let blank_lines = input
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| line.trim().is_empty())
+            .map(|(index, _)| index + 1)
+            .collect();
+
         // Use comment-preserving scanner
         let scanner = BasicScanner::new_with_comments_and_limits(input.clone(), limits.clone());
-        let parser = BasicParser::new_eager_with_limits(input, limits.clone());
+        let parser = BasicParser::new_eager_with_limits(input.clone(), limits.clone());
 
         Self {
             parser,
             scanner,
+            source: input,
             limits,
             resource_tracker: ResourceTracker::new(),
             anchors: HashMap::new(),
             current_depth: 0,
             alias_expansion_stack: Vec::new(),
             comment_map: HashMap::new(),
+            comment_lines,
+            blank_lines,
             pending_comments: Vec::new(),
+            preserve_merge_keys: false,
+            merge_precedence: MergePrecedence::default(),
+            empty_scalar: EmptyScalar::default(),
+            key_coercion: KeyCoercion::default(),
+            yaml_version: None,
+            tag_directives: Vec::new(),
+            tag_resolver: TagResolver::new(),
+            acceptance: Acceptance {
+                loader_type: LoaderType::RoundTrip,
+                ..Acceptance::default()
+            },
         }
     }
 
+    /// Keep merge keys (`<<`) unresolved so they can be re-emitted
+    pub fn set_preserve_merge_keys(&mut self, preserve: bool) {
+        self.preserve_merge_keys = preserve;
+    }
+
+    /// Set the override order used when resolving merge keys
+    pub fn set_merge_precedence(&mut self, merge_precedence: MergePrecedence) {
+        self.merge_precedence = merge_precedence;
+    }
+
+    /// Set what an empty plain scalar is loaded as
+    pub fn set_empty_scalar(&mut self, empty_scalar: EmptyScalar) {
+        self.empty_scalar = empty_scalar;
+    }
+
+    /// Set how the scalar keys of mappings are canonicalized
+    pub fn set_key_coercion(&mut self, key_coercion: KeyCoercion) {
+        self.key_coercion = key_coercion;
+    }
+
+    /// Set the hook called with the resource usage counters as they grow
+    pub fn set_resource_hook(&mut self, hook: Option<Arc<dyn ResourceHook>>) {
+        self.resource_tracker.set_hook(hook);
+    }
+
+    /// Set the handlers that construct values with custom tags
+    pub fn set_tag_handlers(&mut self, handlers: TagHandlers) {
+        self.tag_resolver.set_handlers(handlers);
+    }
+
+    /// Set how named tag handles without a `%TAG` directive are resolved
+    pub fn set_unknown_handle(&mut self, unknown_handle: UnknownHandle) {
+        self.tag_resolver.set_unknown_handle(unknown_handle);
+    }
+
+    /// Set whether a tag that is neither a YAML tag nor constructed by a
+    /// registered handler is an error rather than kept on a
+    /// [`Value::Tagged`]
+    pub fn set_reject_unknown_tags(&mut self, reject: bool) {
+        self.acceptance.reject_unknown_tags = reject;
+    }
+
+    /// Set whether a sequence or mapping used as a mapping key is an error
+    pub fn set_reject_complex_keys(&mut self, reject: bool) {
+        self.acceptance.reject_complex_keys = reject;
+    }
+
     /// Extract comments from the scanner and build a position map
     fn extract_comments(&mut self) -> Result<()> {
         // Scan all tokens to extract comments
@@ -76,9 +177,12 @@ impl CommentPreservingComposer {
             // Comments on the same line after the value (trailing)
             if comment_pos.line == position.line && comment_pos.column > position.column {
                 comments.set_trailing(comment_text.clone());
+                comments.trailing_column = Some(comment_pos.column);
             }
-            // Comments on lines before the value (leading)
-            else if (-3..0).contains(&line_diff) {
+            // Comments on lines before the value (leading); the comment at
+            // the end of an earlier line belongs to that line's node
+            else if (-3..0).contains(&line_diff) && self.comment_lines.contains(&comment_pos.line)
+            {
                 // Allow up to 3 lines before as leading comments
                 comments.add_leading(comment_text.clone());
             }
@@ -95,6 +199,41 @@ impl CommentPreservingComposer {
         comments
     }
 
+    /// Comments belonging to a mapping key on `line`: the run of comment-only
+    /// lines directly above it and the comment at the end of its line
+    fn key_comments(&self, line: usize) -> Comments {
+        let comment_on = |line: usize| {
+            self.comment_map
+                .iter()
+                .find(|(position, _)| position.line == line)
+        };
+
+        let mut comments = Comments::new();
+        let mut above = line;
+        while above > 1 && self.comment_lines.contains(&(above - 1)) {
+            above -= 1;
+        }
+        comments.leading = (above..line)
+            .filter_map(|line| comment_on(line).map(|(_, text)| text.clone()))
+            .collect();
+        if !self.comment_lines.contains(&line) {
+            if let Some((position, text)) = comment_on(line) {
+                comments.trailing = Some(text.clone());
+                comments.trailing_column = Some(position.column);
+            }
+        }
+        comments.blank_lines = self.blank_lines_above(above);
+        comments
+    }
+
+    /// The number of blank lines directly above `line`
+    fn blank_lines_above(&self, line: usize) -> usize {
+        (1..line)
+            .rev()
+            .take_while(|line| self.blank_lines.contains(line))
+            .count()
+    }
+
     /// Compose a single document with comment preservation
     pub fn compose_document(&mut self) -> Result<Option<CommentedValue>> {
         // First, extract all comments from the scanner
@@ -106,8 +245,18 @@ impl CommentPreservingComposer {
         self.alias_expansion_stack.clear();
         self.resource_tracker.reset();
 
-        // Compose the document
-        self.compose_node()
+        // Compose the document, recording its directives on the root value
+        self.yaml_version = None;
+        self.tag_directives.clear();
+        let mut document = self.compose_node()?;
+        if let Some(root) = document.as_mut() {
+            root.style.yaml_version = self.yaml_version;
+            root.style.tag_directives = std::mem::take(&mut self.tag_directives);
+            root.style.explicit_end = self.parser.peek_event().is_some_and(|event| {
+                matches!(event.event_type, EventType::DocumentEnd { implicit: false })
+            });
+        }
+        Ok(document)
     }
 
     /// Compose a single node (value) with comments
@@ -134,15 +283,33 @@ impl CommentPreservingComposer {
 
         let position = event.position;
         let result = match event.event_type {
-            EventType::Scalar { value, anchor, .. } => self.compose_scalar(value, anchor, position),
-            EventType::SequenceStart { anchor, .. } => self.compose_sequence(anchor, position),
-            EventType::MappingStart { anchor, .. } => self.compose_mapping(anchor, position),
+            EventType::Scalar {
+                value,
+                anchor,
+                tag,
+                style,
+                ..
+            } => self.compose_scalar(value, anchor, tag, style, position),
+            EventType::SequenceStart { anchor, tag, .. } => {
+                self.compose_sequence(anchor, tag, position)
+            }
+            EventType::MappingStart { anchor, tag, .. } => {
+                self.compose_mapping(anchor, tag, position)
+            }
             EventType::Alias { anchor } => self.compose_alias(anchor, position),
             EventType::StreamStart | EventType::StreamEnd => {
                 // Skip structural events and try next
                 self.compose_node()
             }
-            EventType::DocumentStart { .. } | EventType::DocumentEnd { .. } => {
+            EventType::DocumentStart { version, tags, .. } => {
+                // Record directives and continue with the document content
+                if version.is_some() {
+                    self.yaml_version = version;
+                }
+                self.tag_directives.extend(tags);
+                self.compose_node()
+            }
+            EventType::DocumentEnd { .. } => {
                 // Skip document markers and try next
                 self.compose_node()
             }
@@ -162,16 +329,60 @@ impl CommentPreservingComposer {
         &mut self,
         value: String,
         anchor: Option<String>,
+        tag: Option<String>,
+        style: ScalarStyle,
         position: Position,
     ) -> Result<Option<CommentedValue>> {
-        // Resolve the scalar type properly
-        let resolved_value = self.resolve_scalar_type(value);
+        // Resolve the scalar type properly; an empty plain scalar is an empty
+        // node and a quoted or block scalar is a string
+        let resolved_value = if let Some(tag) = &tag {
+            let resolved = self.tag_resolver.resolve_at(tag, position)?;
+            self.acceptance
+                .check_tag(&resolved, &self.tag_resolver, &self.source, position)?;
+            self.tag_resolver.apply_tag(&resolved, &value)?
+        } else if value.is_empty() && style == ScalarStyle::Plain {
+            self.empty_scalar.resolve(value, style)
+        } else if style == ScalarStyle::Plain {
+            self.resolve_scalar_type(value)
+        } else {
+            Value::String(value)
+        };
 
-        let commented_value = CommentedValue {
+        let mut commented_value = CommentedValue {
             value: resolved_value,
             comments: self.get_comments_for_position(position),
             style: Style::default(),
+            keys: IndexMap::new(),
+            items: Vec::new(),
         };
+        // An application tag is kept by the value itself
+        if !commented_value.value.is_tagged() {
+            commented_value.style.tag = tag;
+        }
+        if style == ScalarStyle::Plain {
+            let text = self.plain_text_at(position);
+            match commented_value.value {
+                Value::Null => {
+                    commented_value.style.null_style = Some(match text {
+                        "~" => NullStyle::Tilde,
+                        "null" | "Null" | "NULL" => NullStyle::Null,
+                        _ => NullStyle::Empty,
+                    });
+                }
+                Value::Bool(b) if bool_word(text) == Some(b) => {
+                    commented_value.style.literal = Some(text.to_string());
+                }
+                // Keep notations such as `0o755` or `0xFF`
+                Value::Int(i) if parse_int(text) == Some(i) && text != i.to_string() => {
+                    commented_value.style.literal = Some(text.to_string());
+                }
+                _ => {}
+            }
+        }
+        if matches!(style, ScalarStyle::Literal | ScalarStyle::Folded) {
+            commented_value.style.block_scalar =
+                Some(self.block_scalar_style_at(position, style == ScalarStyle::Folded));
+        }
 
         // Store anchor if present
         if let Some(anchor_name) = anchor {
@@ -181,6 +392,44 @@ impl CommentPreservingComposer {
         Ok(Some(commented_value))
     }
 
+    /// The source text of the plain scalar starting at `position`, up to the
+    /// next space or flow indicator; empty for a node with no text of its own
+    fn plain_text_at(&self, position: Position) -> &str {
+        self.source
+            .get(position.index..)
+            .and_then(|rest| {
+                rest.split(|c: char| c.is_whitespace() || matches!(c, ',' | ']' | '}'))
+                    .next()
+            })
+            .unwrap_or("")
+    }
+
+    /// The block scalar style written at `position`, with the indicators of
+    /// its header such as `|2-`
+    fn block_scalar_style_at(&self, position: Position, folded: bool) -> BlockScalarStyle {
+        let mut style = if folded {
+            BlockScalarStyle::folded()
+        } else {
+            BlockScalarStyle::literal()
+        };
+        let header = self
+            .source
+            .get(position.index..)
+            .and_then(|rest| rest.strip_prefix(['|', '>']))
+            .unwrap_or("");
+        for ch in header
+            .chars()
+            .take_while(|c| matches!(c, '1'..='9' | '+' | '-'))
+        {
+            match ch {
+                '+' => style.chomping = Some(Chomping::Keep),
+                '-' => style.chomping = Some(Chomping::Strip),
+                digit => style.indent = digit.to_digit(10).map(|digit| digit as u8),
+            }
+        }
+        style
+    }
+
     /// Resolve scalar type from string value
     fn resolve_scalar_type(&self, value: String) -> Value {
         // Empty string
@@ -189,7 +438,7 @@ impl CommentPreservingComposer {
         }
 
         // Try integer parsing
-        if let Ok(int_value) = value.parse::<i64>() {
+        if let Some(int_value) = parse_int(&value) {
             return Value::Int(int_value);
         }
 
@@ -197,6 +446,9 @@ impl CommentPreservingComposer {
         if let Ok(float_value) = value.parse::<f64>() {
             return Value::Float(float_value);
         }
+        if let Some(float_value) = crate::composer::special_float(&value) {
+            return Value::Float(float_value);
+        }
 
         // Try boolean parsing
         match value.to_lowercase().as_str() {
@@ -214,21 +466,34 @@ impl CommentPreservingComposer {
     fn compose_sequence(
         &mut self,
         anchor: Option<String>,
+        tag: Option<String>,
         position: Position,
     ) -> Result<Option<CommentedValue>> {
         let mut sequence = Vec::new();
+        let mut items: Vec<CommentedValue> = Vec::new();
         let mut inner_comments = Vec::new();
 
         // Collect sequence items
-        while let Some(item_event) = self.parser.peek_event()? {
+        while let Some(item_event) = self.parser.peek_event() {
             if matches!(item_event.event_type, EventType::SequenceEnd) {
                 self.parser.get_event()?; // consume SequenceEnd
                 break;
             }
-
-            if let Some(item) = self.compose_node()? {
+            let item_line = item_event.position.line;
+
+            if let Some(mut item) = self.compose_node()? {
+                // An item owns the comments above and after its first line,
+                // like a mapping key does
+                let inner = std::mem::take(&mut item.comments.inner);
+                item.comments = self.key_comments(item_line);
+                item.comments.inner = inner;
+                let kept = items
+                    .last()
+                    .map_or(0, |previous| kept_breaks(&previous.value));
+                item.comments.blank_lines = item.comments.blank_lines.saturating_sub(kept);
                 self.collect_item_comments(&item, &mut inner_comments);
-                sequence.push(item.value);
+                sequence.push(item.value.clone());
+                items.push(item);
             }
         }
 
@@ -236,9 +501,11 @@ impl CommentPreservingComposer {
         comments.inner = inner_comments;
 
         let commented_value = CommentedValue {
-            value: Value::Sequence(sequence),
+            value: self.apply_collection_tag(tag, Value::Sequence(sequence), position)?,
             comments,
             style: Style::default(),
+            keys: IndexMap::new(),
+            items,
         };
 
         // Store anchor if present
@@ -253,29 +520,55 @@ impl CommentPreservingComposer {
     fn compose_mapping(
         &mut self,
         anchor: Option<String>,
+        tag: Option<String>,
         position: Position,
     ) -> Result<Option<CommentedValue>> {
         let mut mapping = IndexMap::new();
+        let mut keys = IndexMap::new();
         let mut inner_comments = Vec::new();
 
         // Collect mapping items
-        while let Some(event) = self.parser.peek_event()? {
+        while let Some(event) = self.parser.peek_event() {
             if matches!(event.event_type, EventType::MappingEnd) {
                 self.parser.get_event()?; // consume MappingEnd
                 break;
             }
+            let key_line = event.position.line;
+            let key_position = event.position;
+            let key_quote_style = match event.event_type {
+                EventType::Scalar {
+                    style: ScalarStyle::SingleQuoted,
+                    ..
+                } => Some(QuoteStyle::Single),
+                EventType::Scalar {
+                    style: ScalarStyle::DoubleQuoted,
+                    ..
+                } => Some(QuoteStyle::Double),
+                _ => None,
+            };
 
             // Get key
             let (key, key_comments) = match self.compose_node()? {
                 Some(key_commented) => (key_commented.value, key_commented.comments),
                 None => break,
             };
+            self.acceptance
+                .check_key(&key, &self.source, key_position)?;
+            let key = self.key_coercion.apply(key);
 
             // Get value
-            let (value, value_comments) = match self.compose_node()? {
-                Some(value_commented) => (value_commented.value, value_commented.comments),
-                None => (Value::Null, Comments::new()),
-            };
+            let value_commented = self.compose_node()?.unwrap_or_else(|| {
+                let mut empty = CommentedValue::new(Value::Null);
+                empty.set_null_style(NullStyle::Empty);
+                empty
+            });
+            let CommentedValue {
+                value,
+                comments: value_comments,
+                style: value_style,
+                keys: value_keys,
+                items: value_items,
+            } = value_commented;
 
             // Collect comments from key-value pairs
             self.collect_comments(&key_comments, &mut inner_comments);
@@ -283,12 +576,28 @@ impl CommentPreservingComposer {
 
             // Handle merge keys
             if let Value::String(key_str) = &key {
-                if key_str == "<<" {
+                if key_str == "<<" && !self.preserve_merge_keys {
                     self.process_merge_key(&mut mapping, &value)?;
                     continue;
                 }
             }
 
+            let mut key_entry = CommentedValue::new(key.clone());
+            key_entry.comments = self.key_comments(key_line);
+            key_entry.comments.blank_lines = key_entry.comments.blank_lines.saturating_sub(
+                mapping
+                    .last()
+                    .map_or(0, |(_, previous)| kept_breaks(previous)),
+            );
+            key_entry.style.quote_style = key_quote_style;
+            key_entry.style.null_style = value_style.null_style;
+            key_entry.style.literal = value_style.literal;
+            key_entry.style.block_scalar = value_style.block_scalar;
+            key_entry.style.tag = value_style.tag;
+            key_entry.keys = value_keys;
+            key_entry.items = value_items;
+            keys.insert(key.clone(), key_entry);
+
             mapping.insert(key, value);
         }
 
@@ -296,9 +605,11 @@ impl CommentPreservingComposer {
         comments.inner.extend(inner_comments);
 
         let commented_value = CommentedValue {
-            value: Value::Mapping(mapping),
+            value: self.apply_collection_tag(tag, Value::Mapping(mapping), position)?,
             comments,
             style: Style::default(),
+            keys,
+            items: Vec::new(),
         };
 
         // Store anchor if present
@@ -309,6 +620,24 @@ impl CommentPreservingComposer {
         Ok(Some(commented_value))
     }
 
+    /// Apply the tag of a sequence or mapping to its composed value
+    fn apply_collection_tag(
+        &self,
+        tag: Option<String>,
+        value: Value,
+        position: Position,
+    ) -> Result<Value> {
+        match tag {
+            Some(tag) => {
+                let tag = self.tag_resolver.resolve_at(&tag, position)?;
+                self.acceptance
+                    .check_tag(&tag, &self.tag_resolver, &self.source, position)?;
+                Ok(self.tag_resolver.apply_collection_tag(&tag, value))
+            }
+            None => Ok(value),
+        }
+    }
+
     /// Compose an alias reference
     fn compose_alias(
         &mut self,
@@ -317,20 +646,14 @@ impl CommentPreservingComposer {
     ) -> Result<Option<CommentedValue>> {
         // Prevent cyclic references
         if self.alias_expansion_stack.contains(&anchor) {
-            return Err(Error::parse(
-                position,
-                format!("Cyclic alias reference detected: '{}'", anchor),
-            ));
+            return Err(cyclic_alias(self.parser.input(), position, &anchor));
         }
 
         self.alias_expansion_stack.push(anchor.clone());
 
         let result = match self.anchors.get(&anchor) {
             Some(value) => Ok(Some(value.clone())),
-            None => Err(Error::parse(
-                position,
-                format!("Unknown anchor '{}'", anchor),
-            )),
+            None => Err(unknown_anchor(self.parser.input(), position, &anchor)),
         };
 
         self.alias_expansion_stack.pop();
@@ -374,11 +697,19 @@ impl CommentPreservingComposer {
                 }
             }
             Value::Sequence(sources) => {
-                for source in sources {
-                    if let Value::Mapping(source_map) = source {
-                        for (key, value) in source_map {
-                            mapping.entry(key.clone()).or_insert_with(|| value.clone());
-                        }
+                let mut source_maps: Vec<_> = sources
+                    .iter()
+                    .filter_map(|source| match source {
+                        Value::Mapping(source_map) => Some(source_map),
+                        _ => None,
+                    })
+                    .collect();
+                if self.merge_precedence == MergePrecedence::LastWins {
+                    source_maps.reverse();
+                }
+                for source_map in source_maps {
+                    for (key, value) in source_map {
+                        mapping.entry(key.clone()).or_insert_with(|| value.clone());
                     }
                 }
             }
@@ -390,6 +721,20 @@ impl CommentPreservingComposer {
     }
 }
 
+/// The line breaks past the first that end the last scalar of `value`,
+/// which a block scalar kept with `|+` or `>+` writes as blank lines of its
+/// own
+fn kept_breaks(value: &Value) -> usize {
+    match value {
+        Value::String(s) => s.len() - s.trim_end_matches('\n').len(),
+        Value::Sequence(seq) => seq.last().map_or(0, kept_breaks),
+        Value::Mapping(map) => map.last().map_or(0, |(_, value)| kept_breaks(value)),
+        Value::Tagged(tagged) => kept_breaks(&tagged.value),
+        _ => 0,
+    }
+    .saturating_sub(1)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -414,4 +759,107 @@ nested:
         // Should have preserved some comments
         println!("Preserved comments: {:?}", commented_value.comments);
     }
+
+    #[test]
+    fn test_key_entries_carry_comments_and_style() {
+        let yaml =
+            "server:\n  # Listen port\n  # (TCP)\n  port: 8080  # default\n\n  'host': localhost\n";
+        let mut composer = CommentPreservingComposer::new(yaml.to_string());
+        let document = composer.compose_document().unwrap().unwrap();
+
+        let server = document.key_entry(&Value::from("server")).unwrap();
+        let port = server.key_entry(&Value::from("port")).unwrap();
+        assert_eq!(port.comments.leading, vec!["Listen port", "(TCP)"]);
+        assert_eq!(port.comments.trailing.as_deref(), Some("default"));
+
+        let host = server.key_entry(&Value::from("host")).unwrap();
+        assert!(host.comments.leading.is_empty());
+        assert_eq!(host.quote_style(), Some(&QuoteStyle::Single));
+    }
+
+    #[test]
+    fn test_records_null_styles() {
+        let yaml = "a: ~\nb: null\nc:\nd: [~]\n";
+        let mut composer = CommentPreservingComposer::new(yaml.to_string());
+        let document = composer.compose_document().unwrap().unwrap();
+
+        let null_style = |key: &str| {
+            document
+                .key_entry(&Value::from(key))
+                .and_then(CommentedValue::null_style)
+        };
+        assert_eq!(null_style("a"), Some(NullStyle::Tilde));
+        assert_eq!(null_style("b"), Some(NullStyle::Null));
+        assert_eq!(null_style("c"), Some(NullStyle::Empty));
+
+        let d = document.key_entry(&Value::from("d")).unwrap();
+        assert_eq!(d.items[0].null_style(), Some(NullStyle::Tilde));
+    }
+
+    #[test]
+    fn test_records_bool_literals() {
+        let yaml = "a: yes\nb: Off\nc: true\nd: [on]\n";
+        let mut composer = CommentPreservingComposer::new(yaml.to_string());
+        let document = composer.compose_document().unwrap().unwrap();
+
+        let literal = |key: &str| {
+            document
+                .key_entry(&Value::from(key))
+                .and_then(|entry| entry.style.literal.as_deref())
+        };
+        assert_eq!(literal("a"), Some("yes"));
+        assert_eq!(literal("b"), Some("Off"));
+        assert_eq!(literal("c"), Some("true"));
+
+        let d = document.key_entry(&Value::from("d")).unwrap();
+        assert_eq!(d.items[0].style.literal.as_deref(), Some("on"));
+    }
+
+    #[test]
+    fn test_records_integer_notation() {
+        let yaml = "mode: 0o755\nmask: 0xFF\ncount: 42\n";
+        let mut composer = CommentPreservingComposer::new(yaml.to_string());
+        let document = composer.compose_document().unwrap().unwrap();
+
+        assert_eq!(document.value.get_str("mode"), Some(&Value::Int(0o755)));
+        let literal = |key: &str| {
+            document
+                .key_entry(&Value::from(key))
+                .and_then(|entry| entry.style.literal.as_deref())
+        };
+        assert_eq!(literal("mode"), Some("0o755"));
+        assert_eq!(literal("mask"), Some("0xFF"));
+        assert_eq!(literal("count"), None);
+    }
+
+    #[test]
+    fn test_records_directives() {
+        let yaml = "%YAML 1.2\n%TAG !e! tag:example.com,2024:\n---\nkey: value\n";
+        let mut composer = CommentPreservingComposer::new(yaml.to_string());
+        let document = composer.compose_document().unwrap().unwrap();
+
+        assert_eq!(document.style.yaml_version, Some((1, 2)));
+        assert_eq!(
+            document.style.tag_directives,
+            vec![("!e!".to_string(), "tag:example.com,2024:".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_preserve_merge_keys() {
+        let yaml = "base: &base\n  a: 1\nitem:\n  <<: *base\n  b: 2\n";
+
+        let mut composer = CommentPreservingComposer::new(yaml.to_string());
+        composer.set_preserve_merge_keys(true);
+        let document = composer.compose_document().unwrap().unwrap().value;
+        let item = document.get_str("item").unwrap();
+        assert_eq!(item.get_str("<<"), document.get_str("base"));
+        assert_eq!(item.get_str("a"), None);
+
+        let mut composer = CommentPreservingComposer::new(yaml.to_string());
+        let document = composer.compose_document().unwrap().unwrap().value;
+        let item = document.get_str("item").unwrap();
+        assert_eq!(item.get_str("<<"), None);
+        assert_eq!(item.get_str("a"), Some(&Value::Int(1)));
+    }
 }
@@ -3,10 +3,11 @@
 //! This module provides an optimized composer implementation that
 //! minimizes memory allocations and unnecessary cloning operations.
 
+use crate::composer::{cyclic_alias, invalid_merge, unknown_anchor};
 use crate::{
     parser::{EventType, ScalarStyle},
     zero_copy_value::OptimizedValue,
-    BasicParser, Error, Limits, Parser, Position, ResourceTracker, Result,
+    BasicParser, EmptyScalar, Error, Limits, Parser, Position, ResourceTracker, Result,
 };
 use indexmap::IndexMap;
 use std::collections::HashMap;
@@ -44,7 +45,7 @@ fn calculate_optimized_structure_depth(value: &OptimizedValue) -> usize {
 /// Trait for optimized composers
 pub trait OptimizedComposer {
     /// Check if there are more documents available
-    fn check_document(&self) -> bool;
+    fn check_document(&mut self) -> bool;
 
     /// Compose the next document with minimal allocations
     fn compose_document(&mut self) -> Result<Option<OptimizedValue>>;
@@ -66,6 +67,7 @@ pub struct ReducedAllocComposer {
     resource_tracker: ResourceTracker,
     alias_expansion_stack: Vec<String>,
     current_depth: usize,
+    empty_scalar: EmptyScalar,
 }
 
 impl ReducedAllocComposer {
@@ -84,9 +86,15 @@ impl ReducedAllocComposer {
             resource_tracker: ResourceTracker::new(),
             alias_expansion_stack: Vec::new(),
             current_depth: 0,
+            empty_scalar: EmptyScalar::default(),
         }
     }
 
+    /// Set what an empty plain scalar is loaded as
+    pub fn set_empty_scalar(&mut self, empty_scalar: EmptyScalar) {
+        self.empty_scalar = empty_scalar;
+    }
+
     /// Compose a node from events with reduced allocations
     fn compose_node(&mut self) -> Result<Option<OptimizedValue>> {
         if !self.parser.check_event() {
@@ -157,10 +165,7 @@ impl ReducedAllocComposer {
             EventType::Alias { anchor } => {
                 // Check for cyclic references
                 if self.alias_expansion_stack.contains(&anchor) {
-                    return Err(Error::construction(
-                        event.position,
-                        format!("Cyclic alias reference detected: '{}'", anchor),
-                    ));
+                    return Err(cyclic_alias(self.parser.input(), event.position, &anchor));
                 }
 
                 // Check alias expansion depth limit BEFORE pushing
@@ -199,10 +204,7 @@ impl ReducedAllocComposer {
                             .add_complexity(&self.limits, calculate_complexity(&cloned)?)?;
                         Ok(Some(cloned))
                     }
-                    None => Err(Error::construction(
-                        event.position,
-                        format!("Unknown anchor '{}'", anchor),
-                    )),
+                    None => Err(unknown_anchor(self.parser.input(), event.position, &anchor)),
                 };
 
                 // Clean up tracking
@@ -230,11 +232,14 @@ impl ReducedAllocComposer {
 
         // Type resolution for unquoted scalars
         if value.is_empty() {
-            return Ok(OptimizedValue::string(value));
+            return Ok(match self.empty_scalar {
+                EmptyScalar::Null => OptimizedValue::Null,
+                EmptyScalar::EmptyString => OptimizedValue::string(value),
+            });
         }
 
         // Try integer parsing
-        if let Ok(int_value) = value.parse::<i64>() {
+        if let Some(int_value) = crate::composer::parse_int(&value) {
             return Ok(OptimizedValue::Int(int_value));
         }
 
@@ -242,6 +247,9 @@ impl ReducedAllocComposer {
         if let Ok(float_value) = value.parse::<f64>() {
             return Ok(OptimizedValue::Float(float_value));
         }
+        if let Some(float_value) = crate::composer::special_float(&value) {
+            return Ok(OptimizedValue::Float(float_value));
+        }
 
         // Try boolean parsing
         match value.to_lowercase().as_str() {
@@ -264,7 +272,7 @@ impl ReducedAllocComposer {
         let mut sequence = Vec::new();
 
         while self.parser.check_event() {
-            if let Ok(Some(event)) = self.parser.peek_event() {
+            if let Some(event) = self.parser.peek_event() {
                 if matches!(event.event_type, EventType::SequenceEnd) {
                     self.parser.get_event()?;
                     break;
@@ -300,7 +308,7 @@ impl ReducedAllocComposer {
         let mut mapping = IndexMap::new();
 
         while self.parser.check_event() {
-            if let Ok(Some(event)) = self.parser.peek_event() {
+            if let Some(event) = self.parser.peek_event() {
                 if matches!(event.event_type, EventType::MappingEnd) {
                     self.parser.get_event()?;
                     break;
@@ -363,7 +371,8 @@ impl ReducedAllocComposer {
                             mapping.entry(key.clone()).or_insert_with(|| value.clone());
                         }
                     } else {
-                        return Err(Error::construction(
+                        return Err(invalid_merge(
+                            self.parser.input(),
                             self.position,
                             "Merge key sequence can only contain mappings",
                         ));
@@ -372,7 +381,8 @@ impl ReducedAllocComposer {
             }
 
             _ => {
-                return Err(Error::construction(
+                return Err(invalid_merge(
+                    self.parser.input(),
                     self.position,
                     "Merge key value must be a mapping or sequence of mappings",
                 ));
@@ -384,11 +394,11 @@ impl ReducedAllocComposer {
 }
 
 impl OptimizedComposer for ReducedAllocComposer {
-    fn check_document(&self) -> bool {
-        if let Ok(Some(event)) = self.parser.peek_event() {
-            !matches!(event.event_type, EventType::StreamEnd)
-        } else {
-            false
+    fn check_document(&mut self) -> bool {
+        match self.parser.peek_event() {
+            Some(event) => !matches!(event.event_type, EventType::StreamEnd),
+            // A pending error is reported by `compose_document`
+            None => self.parser.check_event(),
         }
     }
 
@@ -398,7 +408,7 @@ impl OptimizedComposer for ReducedAllocComposer {
         }
 
         // Skip any leading document start events
-        while let Ok(Some(event)) = self.parser.peek_event() {
+        while let Some(event) = self.parser.peek_event() {
             if matches!(event.event_type, EventType::DocumentStart { .. }) {
                 self.parser.get_event()?;
             } else {
@@ -409,7 +419,7 @@ impl OptimizedComposer for ReducedAllocComposer {
         let document = self.compose_node()?;
 
         // Skip any document end event
-        while let Ok(Some(event)) = self.parser.peek_event() {
+        while let Some(event) = self.parser.peek_event() {
             if matches!(event.event_type, EventType::DocumentEnd { .. }) {
                 self.parser.get_event()?;
             } else {
@@ -480,6 +490,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_optimized_empty_scalars() {
+        let compose = |empty_scalar| {
+            let mut composer = ReducedAllocComposer::new("a:\nb: ''\n".to_string());
+            composer.set_empty_scalar(empty_scalar);
+            match composer.compose_document().unwrap().unwrap() {
+                OptimizedValue::Mapping(map) => map.values().cloned().collect::<Vec<_>>(),
+                other => panic!("Expected mapping, got {other:?}"),
+            }
+        };
+
+        let empty = OptimizedValue::string(String::new());
+        assert_eq!(
+            compose(EmptyScalar::Null),
+            vec![OptimizedValue::Null, empty.clone()]
+        );
+        assert_eq!(
+            compose(EmptyScalar::EmptyString),
+            vec![empty.clone(), empty]
+        );
+    }
+
     #[test]
     fn test_anchor_rc_sharing() {
         let yaml = r#"
@@ -0,0 +1,536 @@
+//! Composer producing shared, alias-preserving node trees
+//!
+//! This composer mirrors [`BasicComposer`](crate::BasicComposer) but builds
+//! [`SharedNode`] trees. With [`AliasResolution::Shared`] an alias resolves
+//! to the very node its anchor was attached to instead of a deep copy, and
+//! with [`AliasResolution::Graph`] an alias may also refer to an enclosing
+//! anchored collection, producing a cyclic graph.
+
+use crate::composer::{
+    cyclic_alias, invalid_merge, unknown_anchor, AnchorScope, EmptyScalar, KeyCoercion,
+};
+use crate::tag::{TagHandlers, TagResolver, UnknownHandle};
+use crate::value_shared::{AliasResolution, SharedNode, SharedValue};
+use crate::{
+    parser::EventType, BasicParser, Error, Limits, MergePrecedence, Parser, Position, ResourceHook,
+    ResourceTracker, Result, Value,
+};
+use indexmap::IndexMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Count the nodes reachable from a shared node (for resource limiting)
+fn node_complexity(node: &SharedNode) -> usize {
+    let value = node.borrow();
+    match &*value {
+        SharedValue::Sequence(seq) => seq
+            .iter()
+            .fold(1usize.saturating_add(seq.len()), |acc, item| {
+                acc.saturating_add(node_complexity(item))
+            }),
+        SharedValue::Mapping(map) => map.values().fold(
+            1usize.saturating_add(map.len().saturating_mul(2)),
+            |acc, item| acc.saturating_add(node_complexity(item)),
+        ),
+        _ => 1,
+    }
+}
+
+/// Calculate the maximum nesting depth of a shared node, ignoring back edges
+fn node_depth(node: &SharedNode, path: &mut HashSet<usize>) -> usize {
+    if !path.insert(node.id()) {
+        return 0;
+    }
+    let depth = match &*node.borrow() {
+        SharedValue::Sequence(seq) => {
+            1 + seq
+                .iter()
+                .map(|child| node_depth(child, path))
+                .max()
+                .unwrap_or(0)
+        }
+        SharedValue::Mapping(map) => {
+            1 + map
+                .values()
+                .map(|child| node_depth(child, path))
+                .max()
+                .unwrap_or(0)
+        }
+        _ => 1,
+    };
+    path.remove(&node.id());
+    depth
+}
+
+/// A composer that converts events into [`SharedNode`] trees
+#[derive(Debug)]
+pub struct SharedComposer {
+    parser: BasicParser,
+    position: Position,
+    anchors: HashMap<String, SharedNode>,
+    open_anchors: Vec<String>,
+    limits: Limits,
+    resource_tracker: ResourceTracker,
+    current_depth: usize,
+    tag_resolver: TagResolver,
+    alias_resolution: AliasResolution,
+    merge_precedence: MergePrecedence,
+    empty_scalar: EmptyScalar,
+    key_coercion: KeyCoercion,
+    anchor_scope: AnchorScope,
+}
+
+impl SharedComposer {
+    /// Create a new composer that shares aliased nodes
+    #[must_use]
+    pub fn new(input: String) -> Self {
+        Self::with_limits(input, Limits::default(), AliasResolution::Shared)
+    }
+
+    /// Create a new composer with custom limits and alias resolution
+    #[must_use]
+    pub fn with_limits(input: String, limits: Limits, alias_resolution: AliasResolution) -> Self {
+        Self {
+            parser: BasicParser::new_eager_with_limits(input, limits.clone()),
+            position: Position::new(),
+            anchors: HashMap::new(),
+            open_anchors: Vec::new(),
+            limits,
+            resource_tracker: ResourceTracker::new(),
+            current_depth: 0,
+            tag_resolver: TagResolver::new(),
+            alias_resolution,
+            merge_precedence: MergePrecedence::default(),
+            empty_scalar: EmptyScalar::default(),
+            key_coercion: KeyCoercion::default(),
+            anchor_scope: AnchorScope::default(),
+        }
+    }
+
+    /// Set the override order used when resolving merge keys
+    pub fn set_merge_precedence(&mut self, merge_precedence: MergePrecedence) {
+        self.merge_precedence = merge_precedence;
+    }
+
+    /// Set what an empty plain scalar is loaded as
+    pub fn set_empty_scalar(&mut self, empty_scalar: EmptyScalar) {
+        self.empty_scalar = empty_scalar;
+    }
+
+    /// Set how the scalar keys of mappings are canonicalized
+    pub fn set_key_coercion(&mut self, key_coercion: KeyCoercion) {
+        self.key_coercion = key_coercion;
+    }
+
+    /// Set whether anchors stay visible to the following documents
+    pub fn set_anchor_scope(&mut self, anchor_scope: AnchorScope) {
+        self.anchor_scope = anchor_scope;
+    }
+
+    /// Set the hook called with the resource usage counters as they grow
+    pub fn set_resource_hook(&mut self, hook: Option<Arc<dyn ResourceHook>>) {
+        self.resource_tracker.set_hook(hook);
+    }
+
+    /// Set the handlers that construct values with custom tags
+    pub fn set_tag_handlers(&mut self, handlers: TagHandlers) {
+        self.tag_resolver.set_handlers(handlers);
+    }
+
+    /// Set how named tag handles without a `%TAG` directive are resolved
+    pub fn set_unknown_handle(&mut self, unknown_handle: UnknownHandle) {
+        self.tag_resolver.set_unknown_handle(unknown_handle);
+    }
+
+    /// Get the alias resolution mode used by this composer
+    pub const fn alias_resolution(&self) -> AliasResolution {
+        self.alias_resolution
+    }
+
+    /// Check if there are more documents available
+    pub fn check_document(&mut self) -> bool {
+        match self.parser.peek_event() {
+            Some(event) => !matches!(event.event_type, EventType::StreamEnd),
+            // A pending error is reported by `compose_document`
+            None => self.parser.check_event(),
+        }
+    }
+
+    /// Compose the next document
+    ///
+    /// # Errors
+    /// Returns an error if parsing or composition fails
+    pub fn compose_document(&mut self) -> Result<Option<SharedNode>> {
+        if let Some(error) = self.parser.take_scanning_error() {
+            return Err(error);
+        }
+
+        if self.anchor_scope == AnchorScope::Document {
+            self.anchors.clear();
+        }
+
+        while let Some(event) = self.parser.peek_event() {
+            if let EventType::DocumentStart { tags, .. } = &event.event_type {
+                self.tag_resolver.clear_directives();
+                for (handle, prefix) in tags {
+                    self.tag_resolver
+                        .add_directive(handle.clone(), prefix.clone());
+                }
+                self.parser.get_event()?;
+            } else {
+                break;
+            }
+        }
+
+        let document = self.compose_node()?;
+
+        while let Some(event) = self.parser.peek_event() {
+            if matches!(event.event_type, EventType::DocumentEnd { .. }) {
+                self.parser.get_event()?;
+            } else {
+                break;
+            }
+        }
+
+        Ok(document)
+    }
+
+    /// Get the current position in the stream
+    pub const fn position(&self) -> Position {
+        self.position
+    }
+
+    /// Reset the composer state
+    pub fn reset(&mut self) {
+        self.position = Position::new();
+        self.anchors.clear();
+        self.open_anchors.clear();
+        self.resource_tracker.reset();
+        self.current_depth = 0;
+        self.tag_resolver.clear_directives();
+    }
+
+    /// Compose a node from events (recursive)
+    fn compose_node(&mut self) -> Result<Option<SharedNode>> {
+        if !self.parser.check_event() {
+            return Ok(None);
+        }
+
+        let Some(event) = self.parser.get_event()? else {
+            return Ok(None);
+        };
+
+        self.position = event.position;
+
+        match event.event_type {
+            EventType::StreamStart | EventType::DocumentStart { .. } => self.compose_node(),
+
+            EventType::StreamEnd
+            | EventType::DocumentEnd { .. }
+            | EventType::SequenceEnd
+            | EventType::MappingEnd => Ok(None),
+
+            EventType::Scalar {
+                value,
+                anchor,
+                tag,
+                style,
+                ..
+            } => {
+                let scalar = if let Some(tag_str) = tag {
+                    let tag = self.tag_resolver.resolve_at(&tag_str, self.position)?;
+                    self.tag_resolver.apply_tag(&tag, &value)?
+                } else {
+                    self.empty_scalar.resolve(value, style)
+                };
+                let node = SharedNode::from_value(&scalar);
+                self.register_anchor(anchor, &node)?;
+                Ok(Some(node))
+            }
+
+            EventType::SequenceStart { anchor, .. } => {
+                let node = SharedNode::new(SharedValue::Sequence(Vec::new()));
+                self.compose_anchored(anchor, &node, Self::compose_sequence)?;
+                Ok(Some(node))
+            }
+
+            EventType::MappingStart { anchor, .. } => {
+                let node = SharedNode::new(SharedValue::Mapping(IndexMap::new()));
+                self.compose_anchored(anchor, &node, Self::compose_mapping)?;
+                Ok(Some(node))
+            }
+
+            EventType::Alias { anchor } => {
+                self.resource_tracker.enter_alias(&self.limits)?;
+                let result = self.resolve_alias(&anchor, event.position);
+                self.resource_tracker.exit_alias();
+                result.map(Some)
+            }
+        }
+    }
+
+    /// Record an anchored node so later aliases can refer to it
+    fn register_anchor(&mut self, anchor: Option<String>, node: &SharedNode) -> Result<()> {
+        if let Some(anchor_name) = anchor {
+            self.resource_tracker.add_anchor(&self.limits)?;
+            self.anchors.insert(anchor_name, node.clone());
+        }
+        Ok(())
+    }
+
+    /// Fill a collection node, making its anchor visible to its own children
+    /// only in graph mode
+    fn compose_anchored(
+        &mut self,
+        anchor: Option<String>,
+        node: &SharedNode,
+        compose: fn(&mut Self) -> Result<SharedValue>,
+    ) -> Result<()> {
+        let Some(anchor_name) = anchor else {
+            node.replace(compose(self)?);
+            return Ok(());
+        };
+
+        if self.alias_resolution == AliasResolution::Graph {
+            self.register_anchor(Some(anchor_name), node)?;
+            node.replace(compose(self)?);
+        } else {
+            self.open_anchors.push(anchor_name.clone());
+            let value = compose(self);
+            self.open_anchors.pop();
+            node.replace(value?);
+            self.register_anchor(Some(anchor_name), node)?;
+        }
+        Ok(())
+    }
+
+    /// Resolve an alias according to the configured alias resolution
+    fn resolve_alias(&mut self, anchor: &str, position: Position) -> Result<SharedNode> {
+        let Some(node) = self.anchors.get(anchor) else {
+            if self.open_anchors.iter().any(|open| open == anchor) {
+                return Err(cyclic_alias(self.parser.input(), position, anchor));
+            }
+            return Err(unknown_anchor(self.parser.input(), position, anchor));
+        };
+
+        let structure_depth = node_depth(node, &mut HashSet::new());
+        if structure_depth > self.limits.max_alias_depth {
+            return Err(Error::construction(
+                position,
+                format!(
+                    "Alias '{}' creates structure with depth {} exceeding max_alias_depth {}",
+                    anchor, structure_depth, self.limits.max_alias_depth
+                ),
+            ));
+        }
+
+        match self.alias_resolution {
+            AliasResolution::Copy => {
+                let node = node.clone();
+                self.resource_tracker
+                    .add_complexity(&self.limits, node_complexity(&node))?;
+                Ok(node.deep_copy())
+            }
+            AliasResolution::Shared | AliasResolution::Graph => {
+                let node = node.clone();
+                self.resource_tracker.add_complexity(&self.limits, 1)?;
+                Ok(node)
+            }
+        }
+    }
+
+    /// Check whether the next event closes the current collection
+    fn at_collection_end(&mut self, end: fn(&EventType) -> bool) -> Result<bool> {
+        if let Some(event) = self.parser.peek_event() {
+            if end(&event.event_type) {
+                self.parser.get_event()?;
+                return Ok(true);
+            }
+            if matches!(
+                event.event_type,
+                EventType::DocumentEnd { .. }
+                    | EventType::DocumentStart { .. }
+                    | EventType::StreamEnd
+            ) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Compose a sequence
+    fn compose_sequence(&mut self) -> Result<SharedValue> {
+        self.current_depth += 1;
+        self.resource_tracker
+            .check_depth(&self.limits, self.current_depth)?;
+
+        let mut sequence = Vec::new();
+        while self.parser.check_event() {
+            if self.at_collection_end(|event| matches!(event, EventType::SequenceEnd))? {
+                break;
+            }
+            let Some(node) = self.compose_node()? else {
+                break;
+            };
+            self.resource_tracker.add_collection_item(&self.limits)?;
+            self.resource_tracker.add_complexity(&self.limits, 1)?;
+            sequence.push(node);
+        }
+
+        self.current_depth -= 1;
+        Ok(SharedValue::Sequence(sequence))
+    }
+
+    /// Compose a mapping
+    fn compose_mapping(&mut self) -> Result<SharedValue> {
+        self.current_depth += 1;
+        self.resource_tracker
+            .check_depth(&self.limits, self.current_depth)?;
+
+        let mut mapping = IndexMap::new();
+        while self.parser.check_event() {
+            if self.at_collection_end(|event| matches!(event, EventType::MappingEnd))? {
+                break;
+            }
+            let Some(key) = self.compose_node()? else {
+                break;
+            };
+            let value = self
+                .compose_node()?
+                .unwrap_or_else(|| SharedNode::new(SharedValue::Null));
+            let key = self.key_coercion.apply(key.try_to_value()?);
+
+            if matches!(&key, Value::String(key_str) if key_str == "<<") {
+                self.process_merge_key(&mut mapping, &value)?;
+                continue;
+            }
+
+            self.resource_tracker.add_collection_item(&self.limits)?;
+            self.resource_tracker.add_complexity(&self.limits, 2)?;
+            mapping.insert(key, value);
+        }
+
+        self.current_depth -= 1;
+        Ok(SharedValue::Mapping(mapping))
+    }
+
+    /// Merge the entries of `merge_value` into `mapping`; explicit keys win
+    fn process_merge_key(
+        &self,
+        mapping: &mut IndexMap<Value, SharedNode>,
+        merge_value: &SharedNode,
+    ) -> Result<()> {
+        let merge_value = merge_value.borrow();
+        match &*merge_value {
+            SharedValue::Mapping(source_map) => {
+                for (key, value) in source_map {
+                    mapping.entry(key.clone()).or_insert_with(|| value.clone());
+                }
+            }
+            SharedValue::Sequence(sources) => {
+                let mut sources: Vec<_> = sources.iter().collect();
+                if self.merge_precedence == MergePrecedence::LastWins {
+                    sources.reverse();
+                }
+                for source in sources {
+                    let source = source.borrow();
+                    let SharedValue::Mapping(source_map) = &*source else {
+                        return Err(invalid_merge(
+                            self.parser.input(),
+                            self.position,
+                            "Merge key sequence can only contain mappings",
+                        ));
+                    };
+                    for (key, value) in source_map {
+                        mapping.entry(key.clone()).or_insert_with(|| value.clone());
+                    }
+                }
+            }
+            _ => {
+                return Err(invalid_merge(
+                    self.parser.input(),
+                    self.position,
+                    "Merge key value must be a mapping or sequence of mappings",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "base: &base\n  name: shared\nfirst: *base\nsecond: *base\n";
+
+    fn compose(input: &str, alias_resolution: AliasResolution) -> SharedNode {
+        let mut composer =
+            SharedComposer::with_limits(input.to_string(), Limits::default(), alias_resolution);
+        composer.compose_document().unwrap().unwrap()
+    }
+
+    fn child(node: &SharedNode, key: &str) -> SharedNode {
+        node.borrow().get_str(key).unwrap()
+    }
+
+    #[test]
+    fn test_shared_aliases_point_to_anchor() {
+        let root = compose(INPUT, AliasResolution::Shared);
+        let base = child(&root, "base");
+        assert!(base.ptr_eq(&child(&root, "first")));
+        assert!(base.ptr_eq(&child(&root, "second")));
+
+        child(&root, "first").replace(SharedValue::String("changed".to_string()));
+        assert_eq!(
+            child(&root, "second").to_value(),
+            Value::String("changed".to_string())
+        );
+    }
+
+    #[test]
+    fn test_copy_aliases_are_independent() {
+        let root = compose(INPUT, AliasResolution::Copy);
+        let base = child(&root, "base");
+        let first = child(&root, "first");
+        assert!(!base.ptr_eq(&first));
+        assert_eq!(base.to_value(), first.to_value());
+
+        first.replace(SharedValue::Null);
+        assert_ne!(child(&root, "base").to_value(), Value::Null);
+    }
+
+    #[test]
+    fn test_matches_plain_composition() {
+        let input = "defaults: &d\n  a: 1\n  b: [x, y]\nitem:\n  <<: *d\n  b: 2\nflag: yes\n";
+        let shared = compose(input, AliasResolution::Shared);
+        let expected = crate::Yaml::new().load_str(input).unwrap();
+        assert_eq!(shared.to_value(), expected);
+    }
+
+    #[test]
+    fn test_unknown_anchor() {
+        let mut composer = SharedComposer::new("value: *missing".to_string());
+        assert!(composer.compose_document().is_err());
+    }
+
+    #[test]
+    fn test_cyclic_alias_rejected_outside_graph_mode() {
+        let input = "a: &x {b: *x}";
+        for mode in [AliasResolution::Copy, AliasResolution::Shared] {
+            let mut composer =
+                SharedComposer::with_limits(input.to_string(), Limits::default(), mode);
+            let error = composer.compose_document().unwrap_err();
+            assert!(error.to_string().contains("Cyclic alias"));
+        }
+    }
+
+    #[test]
+    fn test_graph_mode_builds_cycle() {
+        let root = compose("a: &x {b: *x}", AliasResolution::Graph);
+        let a = child(&root, "a");
+        assert!(child(&a, "b").ptr_eq(&a));
+        assert!(root.is_cyclic());
+        assert!(root.try_to_value().is_err());
+    }
+}
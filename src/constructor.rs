@@ -1,8 +1,8 @@
 //! YAML constructor for building Rust objects
 
 use crate::{
-    BasicComposer, CommentPreservingComposer, CommentedValue, Composer, Error, Limits, Position,
-    Result, Value,
+    BasicComposer, CommentPreservingComposer, CommentedValue, Composer, Document, Error, Limits,
+    Position, Result, Value,
 };
 
 /// Trait for YAML constructors that convert document nodes to Rust objects
@@ -11,7 +11,7 @@ pub trait Constructor {
     fn construct(&mut self) -> Result<Option<Value>>;
 
     /// Check if there are more values to construct
-    fn check_data(&self) -> bool;
+    fn check_data(&mut self) -> bool;
 
     /// Reset the constructor state
     fn reset(&mut self);
@@ -79,104 +79,113 @@ impl SafeConstructor {
         }
     }
 
-    /// Validate and potentially transform a value for safety
-    fn validate_value(&self, value: Value) -> Result<Value> {
-        match value {
-            // Basic scalar types are always safe
-            Value::Null | Value::Bool(_) | Value::Int(_) | Value::Float(_) | Value::String(_) => {
-                Ok(value)
+    /// The composer this constructor reads documents from
+    pub const fn composer(&self) -> &BasicComposer {
+        &self.composer
+    }
+
+    /// Construct the next document along with its directives and anchors
+    pub fn construct_document(&mut self) -> Result<Option<Document>> {
+        let Some(value) = self.construct()? else {
+            return Ok(None);
+        };
+        Ok(Some(Document {
+            value,
+            version: self.composer.document_version(),
+            directives: self.composer.tag_directives().to_vec(),
+            explicit_end: self.composer.document_explicit_end(),
+            anchors: self.composer.anchor_paths().clone(),
+            warnings: self.composer.warnings().to_vec(),
+        }))
+    }
+
+    /// Check that every collection in a value is within the size limit
+    ///
+    /// Values are walked from an explicit stack, so checking a deeply nested
+    /// document cannot overflow the call stack.
+    fn validate_value(&self, value: &Value) -> Result<()> {
+        let mut stack = vec![value];
+        while let Some(value) = stack.pop() {
+            match value {
+                // Sequences are safe if all elements are safe
+                Value::Sequence(seq) => {
+                    // Check collection size limit
+                    if seq.len() > self.limits.max_collection_size {
+                        return Err(Error::limit_exceeded(format!(
+                            "Sequence size {} exceeds max_collection_size limit of {}",
+                            seq.len(),
+                            self.limits.max_collection_size
+                        )));
+                    }
+                    stack.extend(seq.iter().rev());
+                }
+
+                Value::Tagged(tagged) => stack.push(&tagged.value),
+
+                // Mappings are safe if all keys and values are safe
+                Value::Mapping(map) => {
+                    // Check collection size limit
+                    if map.len() > self.limits.max_collection_size {
+                        return Err(Error::limit_exceeded(format!(
+                            "Mapping size {} exceeds max_collection_size limit of {}",
+                            map.len(),
+                            self.limits.max_collection_size
+                        )));
+                    }
+                    stack.extend(map.iter().rev().flat_map(|(key, val)| [val, key]));
+                }
+
+                // Basic scalar types are always safe
+                _ => {}
             }
+        }
+        Ok(())
+    }
+
+    /// Apply additional safety checks, walking the value like
+    /// [`validate_value`](Self::validate_value)
+    fn apply_safety_rules(&self, value: &Value) -> Result<()> {
+        let mut stack = vec![value];
+        while let Some(value) = stack.pop() {
+            match value {
+                // Limit string length to prevent memory exhaustion
+                Value::String(s) if s.len() > self.limits.max_string_length => {
+                    return Err(Error::limit_exceeded(format!(
+                        "String too long: {} bytes (max: {})",
+                        s.len(),
+                        self.limits.max_string_length
+                    )));
+                }
 
-            // Sequences are safe if all elements are safe
-            Value::Sequence(seq) => {
-                // Check collection size limit
-                if seq.len() > self.limits.max_collection_size {
+                // Limit sequence length
+                Value::Sequence(seq) if seq.len() > self.limits.max_collection_size => {
                     return Err(Error::limit_exceeded(format!(
-                        "Sequence size {} exceeds max_collection_size limit of {}",
+                        "Sequence too long: {} elements (max: {})",
                         seq.len(),
                         self.limits.max_collection_size
                     )));
                 }
-                let mut safe_seq = Vec::with_capacity(seq.len());
-                for item in seq {
-                    safe_seq.push(self.validate_value(item)?);
-                }
-                Ok(Value::Sequence(safe_seq))
-            }
 
-            // Mappings are safe if all keys and values are safe
-            Value::Mapping(map) => {
-                // Check collection size limit
-                if map.len() > self.limits.max_collection_size {
+                // Limit mapping size
+                Value::Mapping(map) if map.len() > self.limits.max_collection_size => {
                     return Err(Error::limit_exceeded(format!(
-                        "Mapping size {} exceeds max_collection_size limit of {}",
+                        "Mapping too large: {} entries (max: {})",
                         map.len(),
                         self.limits.max_collection_size
                     )));
                 }
-                let mut safe_map = indexmap::IndexMap::new();
-                for (key, val) in map {
-                    let safe_key = self.validate_value(key)?;
-                    let safe_val = self.validate_value(val)?;
-                    safe_map.insert(safe_key, safe_val);
-                }
-                Ok(Value::Mapping(safe_map))
-            }
-        }
-    }
-
-    /// Apply additional safety checks and transformations
-    fn apply_safety_rules(&self, value: Value) -> Result<Value> {
-        match value {
-            // Limit string length to prevent memory exhaustion
-            Value::String(ref s) if s.len() > self.limits.max_string_length => {
-                Err(Error::limit_exceeded(format!(
-                    "String too long: {} bytes (max: {})",
-                    s.len(),
-                    self.limits.max_string_length
-                )))
-            }
 
-            // Limit sequence length
-            Value::Sequence(ref seq) if seq.len() > self.limits.max_collection_size => {
-                Err(Error::limit_exceeded(format!(
-                    "Sequence too long: {} elements (max: {})",
-                    seq.len(),
-                    self.limits.max_collection_size
-                )))
-            }
-
-            // Limit mapping size
-            Value::Mapping(ref map) if map.len() > self.limits.max_collection_size => {
-                Err(Error::limit_exceeded(format!(
-                    "Mapping too large: {} entries (max: {})",
-                    map.len(),
-                    self.limits.max_collection_size
-                )))
-            }
-
-            // Recursively apply rules
-            Value::Sequence(seq) => {
-                let mut safe_seq = Vec::with_capacity(seq.len());
-                for item in seq {
-                    safe_seq.push(self.apply_safety_rules(item)?);
+                // Apply the rules to the entries
+                Value::Sequence(seq) => stack.extend(seq.iter().rev()),
+                Value::Mapping(map) => {
+                    stack.extend(map.iter().rev().flat_map(|(key, val)| [val, key]));
                 }
-                Ok(Value::Sequence(safe_seq))
-            }
 
-            Value::Mapping(map) => {
-                let mut safe_map = indexmap::IndexMap::new();
-                for (key, val) in map {
-                    let safe_key = self.apply_safety_rules(key)?;
-                    let safe_val = self.apply_safety_rules(val)?;
-                    safe_map.insert(safe_key, safe_val);
-                }
-                Ok(Value::Mapping(safe_map))
+                // Other types are fine as-is
+                _ => {}
             }
-
-            // Other types are fine as-is
-            _ => Ok(value),
         }
+        Ok(())
     }
 }
 
@@ -195,13 +204,13 @@ impl Constructor for SafeConstructor {
         };
 
         // Validate and apply safety rules
-        let validated = self.validate_value(document)?;
-        let safe_value = self.apply_safety_rules(validated)?;
+        self.validate_value(&document)?;
+        self.apply_safety_rules(&document)?;
 
-        Ok(Some(safe_value))
+        Ok(Some(document))
     }
 
-    fn check_data(&self) -> bool {
+    fn check_data(&mut self) -> bool {
         self.composer.check_document()
     }
 
@@ -238,6 +247,17 @@ impl RoundTripConstructor {
         }
     }
 
+    /// Create constructor from existing composer with custom limits
+    pub fn from_composer_with_limits(composer: CommentPreservingComposer, limits: Limits) -> Self {
+        let position = Position::start();
+
+        Self {
+            composer,
+            position,
+            limits,
+        }
+    }
+
     /// Parse the input and build CommentedValue tree
     fn parse_with_comments(&mut self) -> Result<Option<CommentedValue>> {
         // Use the comment-preserving composer directly
@@ -302,7 +322,7 @@ mod tests {
         let mut constructor = SafeConstructor::new(yaml_content.to_string());
         let result = constructor.construct().unwrap().unwrap();
 
-        if let Value::Mapping(map) = result {
+        if let Value::Mapping(map) = &result {
             if let Some(Value::Sequence(users)) = map.get(&Value::String("users".to_string())) {
                 assert_eq!(users.len(), 1);
                 if let Value::Mapping(ref user) = users[0] {
@@ -323,7 +343,7 @@ mod tests {
 
     #[test]
     fn test_check_data() {
-        let constructor = SafeConstructor::new("42".to_string());
+        let mut constructor = SafeConstructor::new("42".to_string());
         assert!(constructor.check_data());
     }
 
@@ -333,7 +353,7 @@ mod tests {
         let mut constructor = SafeConstructor::new(yaml_content.to_string());
         let result = constructor.construct().unwrap().unwrap();
 
-        if let Value::Mapping(map) = result {
+        if let Value::Mapping(map) = &result {
             assert_eq!(
                 map.get(&Value::String("string".to_string())),
                 Some(&Value::String("hello".to_string()))
@@ -368,7 +388,7 @@ mod tests {
         match result {
             Ok(Some(value)) => {
                 // Should get a mapping with a string value
-                if let Value::Mapping(map) = value {
+                if let Value::Mapping(map) = &value {
                     if let Some(Value::String(s)) = map.get(&Value::String("value".to_string())) {
                         assert_eq!(s.len(), 1000);
                     }
@@ -0,0 +1,54 @@
+//! A loaded document together with its directives and anchors
+
+use crate::{Path, Value};
+use std::collections::HashMap;
+
+/// A `%TAG` directive of a document
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TagDirective {
+    /// The tag handle, such as `!e!`
+    pub handle: String,
+    /// The prefix the handle expands to, such as `tag:example.com,2024:`
+    pub prefix: String,
+}
+
+/// A document's value with the metadata that loading otherwise discards
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    /// The document's content
+    pub value: Value,
+    /// The `%YAML` directive version, if the document has one
+    pub version: Option<(u8, u8)>,
+    /// The `%TAG` directives of the document, in source order
+    pub directives: Vec<TagDirective>,
+    /// Whether the document was closed with a `...` marker
+    pub explicit_end: bool,
+    /// Where each anchor of the document is defined
+    ///
+    /// Anchors on mapping keys are not listed, as a key has no path of its
+    /// own. A redefined anchor maps to its last definition.
+    pub anchors: HashMap<String, Path>,
+    /// Diagnostics raised while loading, such as redefined anchors under
+    /// [`crate::AnchorRedefinition::Warn`] and ignored directives under
+    /// [`crate::InvalidDirective::Warn`]
+    pub warnings: Vec<String>,
+}
+
+impl Document {
+    /// A document holding `value` and no metadata
+    pub fn new(value: Value) -> Self {
+        Self {
+            value,
+            version: None,
+            directives: Vec::new(),
+            explicit_end: false,
+            anchors: HashMap::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// The node an anchor is defined on
+    pub fn anchored(&self, anchor: &str) -> Option<&Value> {
+        self.anchors.get(anchor)?.resolve(&self.value)
+    }
+}
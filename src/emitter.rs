@@ -1,8 +1,21 @@
 //! YAML emitter for generating text output
 
-use crate::{CommentedValue, Comments, Error, IndentStyle, QuoteStyle, Result, Value};
-use std::collections::HashMap;
-use std::io::Write;
+use crate::composer::{bool_word, parse_int, resolve_scalar};
+use crate::fingerprint::collection_hashes;
+use crate::parser::ScalarStyle;
+use crate::redact::redact;
+use crate::schema_docs::document;
+use crate::tag::is_shorthand_suffix;
+use crate::{
+    BlockScalarStyle, BoolStyle, Chomping, CommentedValue, Comments, Error, FlowStyle, IndentStyle,
+    NullStyle, Path, PathSegment, QuoteStyle, RedactionRules, Result, Schema, SharedNode,
+    SharedValue, Style, StyleMap, TagEmission, TaggedValue, Value,
+};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 /// Trait for YAML emitters that generate text output from values
 pub trait Emitter {
@@ -24,6 +37,116 @@ pub trait Emitter {
     fn reset(&mut self);
 }
 
+/// Chooses the names of the anchors the emitter adds for repeated collections
+///
+/// `path` leads to the first occurrence of the collection and `index` counts
+/// the anchors named so far in the emission. Returning `None` keeps the
+/// default `anchorN` name. The emitter replaces characters other than
+/// letters, digits, `_` and `-` with `_` and adds a suffix to names already
+/// taken, so a namer that only looks at `path` gives the same anchors on
+/// every dump of the same value.
+pub trait AnchorNamer: fmt::Debug + Send + Sync {
+    /// The name for the anchor of the collection at `path`
+    fn anchor_name(&self, path: &Path, index: usize) -> Option<String>;
+}
+
+/// Names anchors after the key path of their collection, such as
+/// `defaults_ports` or `jobs_0_env`, and the root collection `root`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PathAnchorNamer;
+
+impl AnchorNamer for PathAnchorNamer {
+    fn anchor_name(&self, path: &Path, _index: usize) -> Option<String> {
+        if path.is_root() {
+            return Some("root".to_string());
+        }
+        let segments: Vec<String> = path
+            .segments()
+            .iter()
+            .map(|segment| match segment {
+                PathSegment::Key(Value::String(key)) => key.clone(),
+                PathSegment::Key(key) => key.to_string(),
+                PathSegment::Index(index) => index.to_string(),
+            })
+            .collect();
+        Some(segments.join("_"))
+    }
+}
+
+/// Chooses the handles of the `%TAG` directives the emitter adds for tag
+/// prefixes shared by many tags
+///
+/// `prefix` is the URI prefix the handle stands for, such as
+/// `tag:example.com,2024:`, and `index` counts the directives added so far in
+/// the emission. The name is written between `!`s; returning `None` keeps the
+/// default, the first word of the prefix such as `example`. The emitter
+/// keeps only letters, digits and `-`, and adds a suffix to names already
+/// taken.
+pub trait TagHandleNamer: fmt::Debug + Send + Sync {
+    /// The name of the handle for `prefix`
+    fn handle_name(&self, prefix: &str, index: usize) -> Option<String>;
+}
+
+/// The default handle name for a tag prefix: the first word of its authority
+fn default_handle_name(prefix: &str) -> String {
+    let authority = prefix
+        .strip_prefix("tag:")
+        .or_else(|| prefix.split_once("://").map(|(_, rest)| rest))
+        .unwrap_or(prefix);
+    let name: String = authority
+        .chars()
+        .take_while(char::is_ascii_alphanumeric)
+        .collect();
+    if name.is_empty() {
+        "t".to_string()
+    } else {
+        name.to_ascii_lowercase()
+    }
+}
+
+/// The prefix a tag is shortened with: the URI up to its last `:` or `/`,
+/// when what follows can be written after a handle
+fn tag_prefix(tag: &str) -> Option<&str> {
+    if tag.starts_with('!') || tag.starts_with(CORE_TAG_PREFIX) {
+        return None;
+    }
+    let end = tag.rfind([':', '/'])? + 1;
+    is_shorthand_suffix(&tag[end..]).then(|| &tag[..end])
+}
+
+/// Count the tags of `value` by prefix, in the order first found
+fn count_tag_prefixes<'a>(value: &'a Value, counts: &mut indexmap::IndexMap<&'a str, usize>) {
+    let mut stack = vec![value];
+    while let Some(value) = stack.pop() {
+        match value {
+            Value::Tagged(tagged) => {
+                if let Some(prefix) = tag_prefix(&tagged.tag) {
+                    *counts.entry(prefix).or_insert(0) += 1;
+                }
+                stack.push(&tagged.value);
+            }
+            Value::Sequence(seq) => stack.extend(seq.iter().rev()),
+            Value::Mapping(map) => {
+                stack.extend(map.iter().rev().flat_map(|(key, value)| [value, key]));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Replace the characters the scanner does not read as part of an anchor name
+fn sanitize_anchor_name(name: &str) -> String {
+    name.chars()
+        .map(|ch| {
+            if ch.is_alphanumeric() || ch == '_' || ch == '-' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
 /// Information about shared values for anchor/alias emission
 #[derive(Debug, Clone)]
 struct ValueInfo {
@@ -31,16 +154,323 @@ struct ValueInfo {
     first_occurrence: bool,
 }
 
+/// Anchors of the collections repeated in the value being written
+#[derive(Debug, Default)]
+struct SharedValues {
+    anchors: Vec<ValueInfo>,
+    /// Index in `anchors` of each occurrence of a repeated collection, keyed
+    /// by the address of its node in the value being written
+    nodes: HashMap<usize, usize>,
+}
+
+impl SharedValues {
+    fn get(&self, value: &Value) -> Option<&ValueInfo> {
+        self.anchors.get(*self.nodes.get(&node_address(value))?)
+    }
+
+    fn get_mut(&mut self, value: &Value) -> Option<&mut ValueInfo> {
+        self.anchors.get_mut(*self.nodes.get(&node_address(value))?)
+    }
+
+    fn contains_key(&self, value: &Value) -> bool {
+        self.nodes.contains_key(&node_address(value))
+    }
+
+    fn clear(&mut self) {
+        self.anchors.clear();
+        self.nodes.clear();
+    }
+}
+
+/// The address of a node, which identifies it while its value is borrowed
+fn node_address(value: &Value) -> usize {
+    std::ptr::from_ref(value) as usize
+}
+
+/// A distinct collection found by [`BasicEmitter::analyze_shared_values`]
+struct FoundCollection<'a> {
+    /// The node it was first found at
+    value: &'a Value,
+    /// Number of times it was found
+    count: usize,
+    /// The collection it was first found in, with the step from there to
+    /// it; a collection key takes no step
+    parent: Option<(usize, Option<FoundStep<'a>>)>,
+}
+
+/// A step from a collection to one of its children
+#[derive(Clone, Copy)]
+enum FoundStep<'a> {
+    Index(usize),
+    Key(&'a Value),
+}
+
+/// What is left to write of a value in flow style
+enum FlowStep<'a> {
+    Node(&'a Value),
+    Sequence(&'a [Value]),
+    Mapping(&'a indexmap::IndexMap<Value, Value>),
+    /// The node of a tagged value, after its tag
+    Content(&'a TaggedValue),
+    Text(&'static str),
+}
+
+/// The entries of a block collection still to be written
+enum BlockEntries<'a> {
+    Sequence(std::iter::Enumerate<std::slice::Iter<'a, Value>>),
+    Mapping(indexmap::map::Iter<'a, Value, Value>),
+}
+
+/// An entry of a block collection
+enum BlockEntry<'a> {
+    Item(usize, &'a Value),
+    Pair(&'a Value, &'a Value),
+}
+
+impl<'a> BlockEntries<'a> {
+    /// The entries of a collection, or of the collection a tagged value holds
+    fn of(value: &'a Value) -> Option<Self> {
+        let value = match value {
+            Value::Tagged(tagged) => &tagged.value,
+            value => value,
+        };
+        match value {
+            Value::Sequence(seq) => Some(Self::Sequence(seq.iter().enumerate())),
+            Value::Mapping(map) => Some(Self::Mapping(map.iter())),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Iterator for BlockEntries<'a> {
+    type Item = BlockEntry<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Sequence(items) => items
+                .next()
+                .map(|(index, item)| BlockEntry::Item(index, item)),
+            Self::Mapping(pairs) => pairs
+                .next()
+                .map(|(key, value)| BlockEntry::Pair(key, value)),
+        }
+    }
+}
+
+/// Anchor bookkeeping while emitting a shared node graph
+#[derive(Debug, Default)]
+struct SharedEmitState {
+    /// Number of references to each node, keyed by node id
+    references: HashMap<usize, usize>,
+    /// Anchor names of nodes that have already been emitted
+    anchors: HashMap<usize, String>,
+    /// Path of the node being emitted
+    path: Path,
+}
+
+/// Count references to every node reachable from `node`, visiting each once
+fn count_node_references(node: &SharedNode, references: &mut HashMap<usize, usize>) {
+    let count = references.entry(node.id()).or_insert(0);
+    *count += 1;
+    if *count > 1 {
+        return;
+    }
+    match &*node.borrow() {
+        SharedValue::Sequence(seq) => {
+            for item in seq {
+                count_node_references(item, references);
+            }
+        }
+        SharedValue::Mapping(map) => {
+            for value in map.values() {
+                count_node_references(value, references);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Split the content of a folded block scalar into the lines to write, so
+/// that reading them back folds to `body` again
+///
+/// Two text lines that do not start with whitespace fold into one, so the
+/// line break between them is written as an extra empty line.
+fn unfold_block_lines(body: &str) -> Vec<&str> {
+    let mut lines = Vec::new();
+    // Whether the previous text line started with whitespace
+    let mut previous: Option<bool> = None;
+    let mut empty_lines = 0;
+
+    for line in body.split('\n') {
+        if line.is_empty() {
+            empty_lines += 1;
+            continue;
+        }
+        let spaced = line.starts_with([' ', '\t']);
+        let written_empty_lines = match previous {
+            Some(false) if !spaced => empty_lines + 1,
+            _ => empty_lines,
+        };
+        lines.extend(std::iter::repeat_n("", written_empty_lines));
+        lines.push(line);
+        previous = Some(spaced);
+        empty_lines = 0;
+    }
+
+    lines
+}
+
+/// Whether a YAML 1.1 parser resolves the plain scalar `s` to a boolean or a
+/// number that the 1.2 core schema reads as a string: the single letter
+/// booleans, binary integers and numbers with `_` separators
+fn is_yaml_1_1_scalar(s: &str) -> bool {
+    if matches!(s, "y" | "Y" | "n" | "N") {
+        return true;
+    }
+    let number = s.strip_prefix(['-', '+']).unwrap_or(s);
+    if let Some(bits) = number.strip_prefix("0b") {
+        return !bits.is_empty() && bits.chars().all(|c| matches!(c, '0' | '1' | '_'));
+    }
+    number.contains('_')
+        && number.starts_with(|c: char| c.is_ascii_digit())
+        && number
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '_' | '.'))
+}
+
+/// Fewest nodes a repeated subtree needs to be aliased by default, so empty
+/// collections are written out again
+const DEFAULT_ALIAS_THRESHOLD: usize = 2;
+
+/// Number of nodes in `value`: the value itself and, for collections, every
+/// key and value they contain
+fn node_count(value: &Value) -> usize {
+    let mut count = 0;
+    let mut stack = vec![value];
+    while let Some(value) = stack.pop() {
+        count += 1;
+        match value {
+            Value::Sequence(seq) => stack.extend(seq),
+            Value::Mapping(map) => stack.extend(map.iter().flat_map(|(key, value)| [key, value])),
+            _ => {}
+        }
+    }
+    count
+}
+
+/// Whether `value` is written as a collection: a sequence or mapping, or one
+/// with an application tag
+fn is_collection(value: &Value) -> bool {
+    match value {
+        Value::Sequence(_) | Value::Mapping(_) => true,
+        Value::Tagged(tagged) => matches!(tagged.value, Value::Sequence(_) | Value::Mapping(_)),
+        _ => false,
+    }
+}
+
+/// Whether the composer resolves the plain scalar `s` to this same string
+/// rather than to a bool, number or null
+fn is_plain_string(s: &str) -> bool {
+    matches!(
+        resolve_scalar(s.to_string(), ScalarStyle::Plain),
+        Value::String(ref resolved) if resolved == s
+    )
+}
+
+/// Prefix of the core schema tags, written with the `!!` handle
+const CORE_TAG_PREFIX: &str = "tag:yaml.org,2002:";
+
+/// Writer counting the characters written on the current line into a
+/// column shared with the emitter, which aligns trailing comments with it
+struct ColumnWriter<W> {
+    inner: W,
+    column: Arc<AtomicUsize>,
+}
+
+impl<W: Write> ColumnWriter<W> {
+    fn new(inner: W, column: &Arc<AtomicUsize>) -> Self {
+        column.store(0, Ordering::Relaxed);
+        Self {
+            inner,
+            column: Arc::clone(column),
+        }
+    }
+}
+
+impl<W: Write> Write for ColumnWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        // Continuation bytes of UTF-8 do not start a character
+        let chars = |bytes: &[u8]| bytes.iter().filter(|&&b| b & 0xC0 != 0x80).count();
+        let buf = &buf[..written];
+        match buf.iter().rposition(|&b| b == b'\n') {
+            Some(newline) => self
+                .column
+                .store(chars(&buf[newline + 1..]), Ordering::Relaxed),
+            None => {
+                self.column.fetch_add(chars(buf), Ordering::Relaxed);
+            }
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
 /// Basic emitter implementation that generates clean YAML
 #[derive(Debug)]
 pub struct BasicEmitter {
     indent: usize,
     current_indent: usize,
-    shared_values: HashMap<Value, ValueInfo>,
+    shared_values: SharedValues,
     anchor_counter: usize,
     indent_style: IndentStyle,
     yaml_version: Option<(u8, u8)>,
+    /// Version the current emission targets, from the emitter or the
+    /// document's `%YAML` directive
+    target_version: Option<(u8, u8)>,
     tag_directives: Vec<(String, String)>,
+    /// Comments already written in the current commented emission, so a
+    /// comment recorded both on a key and on its parent is written once
+    emitted_comments: HashSet<String>,
+    /// How null values are written
+    null_style: NullStyle,
+    /// How booleans are written
+    bool_style: BoolStyle,
+    /// Warnings about the last emission
+    warnings: Vec<String>,
+    /// How collections are written
+    flow_style: FlowStyle,
+    /// Line width that [`FlowStyle::Auto`] keeps inline collections within
+    width: Option<usize>,
+    /// Fewest nodes a repeated subtree needs to be written with an anchor
+    alias_threshold: Option<usize>,
+    /// Chooses anchor names; `None` numbers them
+    anchor_namer: Option<Arc<dyn AnchorNamer>>,
+    /// Anchor names taken in the current emission
+    anchor_names: HashSet<String>,
+    /// Which scalars are written with an explicit tag
+    tag_emission: TagEmission,
+    /// Fewest tags sharing a prefix for a `%TAG` directive to be added
+    tag_prefix_threshold: Option<usize>,
+    /// Chooses the handles of added `%TAG` directives; `None` uses defaults
+    tag_handle_namer: Option<Arc<dyn TagHandleNamer>>,
+    /// `%TAG` directives in effect for the current emission
+    tag_handles: Vec<(String, String)>,
+    /// Styles set for nodes of plain values by path
+    styles: StyleMap,
+    /// Path of the node being written, tracked while `styles` is not empty
+    path: Path,
+    /// Column trailing comments start at; `Some(0)` keeps their source column
+    align_trailing_comments: Option<usize>,
+    /// Characters written on the current line of a commented emission
+    column: Arc<AtomicUsize>,
+    /// Hides the values of matching keys in what is written
+    redaction: Option<RedactionRules>,
+    /// Orders and describes the properties of mappings in what is written
+    schema: Option<Schema>,
 }
 
 #[allow(dead_code)]
@@ -50,11 +480,31 @@ impl BasicEmitter {
         Self {
             indent: 2,
             current_indent: 0,
-            shared_values: HashMap::new(),
+            shared_values: SharedValues::default(),
             anchor_counter: 0,
             indent_style: IndentStyle::default(),
             yaml_version: None,
+            target_version: None,
             tag_directives: Vec::new(),
+            emitted_comments: HashSet::new(),
+            null_style: NullStyle::default(),
+            bool_style: BoolStyle::default(),
+            warnings: Vec::new(),
+            flow_style: FlowStyle::Block,
+            width: Some(80),
+            alias_threshold: Some(DEFAULT_ALIAS_THRESHOLD),
+            anchor_namer: None,
+            anchor_names: HashSet::new(),
+            tag_emission: TagEmission::Never,
+            tag_prefix_threshold: None,
+            tag_handle_namer: None,
+            tag_handles: Vec::new(),
+            styles: StyleMap::new(),
+            path: Path::new(),
+            align_trailing_comments: None,
+            column: Arc::new(AtomicUsize::new(0)),
+            redaction: None,
+            schema: None,
         }
     }
 
@@ -63,11 +513,31 @@ impl BasicEmitter {
         Self {
             indent,
             current_indent: 0,
-            shared_values: HashMap::new(),
+            shared_values: SharedValues::default(),
             anchor_counter: 0,
             indent_style: IndentStyle::Spaces(indent),
             yaml_version: None,
+            target_version: None,
             tag_directives: Vec::new(),
+            emitted_comments: HashSet::new(),
+            null_style: NullStyle::default(),
+            bool_style: BoolStyle::default(),
+            warnings: Vec::new(),
+            flow_style: FlowStyle::Block,
+            width: Some(80),
+            alias_threshold: Some(DEFAULT_ALIAS_THRESHOLD),
+            anchor_namer: None,
+            anchor_names: HashSet::new(),
+            tag_emission: TagEmission::Never,
+            tag_prefix_threshold: None,
+            tag_handle_namer: None,
+            tag_handles: Vec::new(),
+            styles: StyleMap::new(),
+            path: Path::new(),
+            align_trailing_comments: None,
+            column: Arc::new(AtomicUsize::new(0)),
+            redaction: None,
+            schema: None,
         }
     }
 
@@ -80,19 +550,197 @@ impl BasicEmitter {
         Self {
             indent,
             current_indent: 0,
-            shared_values: HashMap::new(),
+            shared_values: SharedValues::default(),
             anchor_counter: 0,
             indent_style,
             yaml_version: None,
+            target_version: None,
             tag_directives: Vec::new(),
+            emitted_comments: HashSet::new(),
+            null_style: NullStyle::default(),
+            bool_style: BoolStyle::default(),
+            warnings: Vec::new(),
+            flow_style: FlowStyle::Block,
+            width: Some(80),
+            alias_threshold: Some(DEFAULT_ALIAS_THRESHOLD),
+            anchor_namer: None,
+            anchor_names: HashSet::new(),
+            tag_emission: TagEmission::Never,
+            tag_prefix_threshold: None,
+            tag_handle_namer: None,
+            tag_handles: Vec::new(),
+            styles: StyleMap::new(),
+            path: Path::new(),
+            align_trailing_comments: None,
+            column: Arc::new(AtomicUsize::new(0)),
+            redaction: None,
+            schema: None,
         }
     }
 
     /// Set the YAML version directive
+    ///
+    /// Plain scalars that a parser of the given version would read as
+    /// something other than a string are quoted, such as `y` and `1_000` for
+    /// YAML 1.1.
     pub fn set_yaml_version(&mut self, major: u8, minor: u8) {
         self.yaml_version = Some((major, minor));
     }
 
+    /// The YAML version directive set with [`Self::set_yaml_version`]
+    pub const fn yaml_version(&self) -> Option<(u8, u8)> {
+        self.yaml_version
+    }
+
+    /// Set how null values are written
+    ///
+    /// Null styles recorded on a round-trip value take precedence.
+    pub const fn set_null_style(&mut self, null_style: NullStyle) {
+        self.null_style = null_style;
+    }
+
+    /// Set how booleans are written
+    ///
+    /// Boolean literals recorded on a round-trip value take precedence.
+    pub const fn set_bool_style(&mut self, bool_style: BoolStyle) {
+        self.bool_style = bool_style;
+    }
+
+    /// Set the column, counted from 1, that trailing comments are aligned at
+    ///
+    /// `None` writes each comment one space after its value, and `Some(0)`
+    /// keeps the column a round-trip comment had in its source, so comments
+    /// lined up by hand stay lined up. Values reaching past the column are
+    /// followed by a single space.
+    pub const fn set_align_trailing_comments(&mut self, column: Option<usize>) {
+        self.align_trailing_comments = column;
+    }
+
+    /// Set the rules hiding the values of keys such as `password` in what
+    /// is written, as [`redact`](crate::redact) does; `None` writes values
+    /// as they are
+    ///
+    /// This applies to [`Emitter::emit`] and [`Emitter::emit_commented`];
+    /// shared node graphs are written as they are.
+    pub fn set_redaction(&mut self, redaction: Option<RedactionRules>) {
+        self.redaction = redaction;
+    }
+
+    /// Set a schema to write mappings by, with their properties in the
+    /// schema's order and each property's description as a comment above
+    /// its first occurrence; `None` writes mappings as they are
+    ///
+    /// See [`schema_docs`](crate::schema_docs) for the order used. Values
+    /// are written as by [`Emitter::emit_commented`] while a schema is set.
+    pub fn set_schema(&mut self, schema: Option<Schema>) {
+        self.schema = schema;
+    }
+
+    /// `commented` with the values of matching keys hidden and its mappings
+    /// laid out by the schema, when either is set
+    fn prepared(&self, commented: &CommentedValue) -> Option<CommentedValue> {
+        if self.redaction.is_none() && self.schema.is_none() {
+            return None;
+        }
+        let mut prepared = match &self.schema {
+            Some(schema) => document(commented, schema),
+            None => commented.clone(),
+        };
+        if let Some(rules) = &self.redaction {
+            rules.apply(&mut prepared.value);
+        }
+        Some(prepared)
+    }
+
+    /// Set how collections are written
+    ///
+    /// [`FlowStyle::Flow`] writes repeated collections out in full rather
+    /// than with anchors and aliases, and [`FlowStyle::Auto`] only inlines
+    /// collections that are not repeated.
+    pub const fn set_flow_style(&mut self, flow_style: FlowStyle) {
+        self.flow_style = flow_style;
+    }
+
+    /// Set the line width that [`FlowStyle::Auto`] keeps inline collections
+    /// within; `None` inlines them regardless of length
+    pub const fn set_width(&mut self, width: Option<usize>) {
+        self.width = width;
+    }
+
+    /// Set the fewest nodes, counting the collection itself and everything
+    /// in it, that a repeated subtree needs before it is written once with an
+    /// anchor and then as aliases; `None` writes every repeat out in full
+    ///
+    /// Scalars are never anchored. This applies to [`Emitter::emit`] and
+    /// [`Emitter::emit_commented`]; shared node graphs keep their anchors.
+    pub const fn set_alias_threshold(&mut self, threshold: Option<usize>) {
+        self.alias_threshold = threshold;
+    }
+
+    /// Set how the anchors added for repeated collections are named; `None`
+    /// numbers them `anchor0`, `anchor1` and so on
+    pub fn set_anchor_namer(&mut self, anchor_namer: Option<Arc<dyn AnchorNamer>>) {
+        self.anchor_namer = anchor_namer;
+    }
+
+    /// Set which scalars are written with an explicit tag, such as `!!int 1`
+    ///
+    /// Core schema tags are only written on scalars; application tags held by
+    /// [`Value::Tagged`] are always written.
+    pub const fn set_tag_emission(&mut self, tag_emission: TagEmission) {
+        self.tag_emission = tag_emission;
+    }
+
+    /// Set the fewest tags that must share a URI prefix, such as
+    /// `tag:example.com,2024:`, before a `%TAG` directive is added for it and
+    /// the tags are written in short form such as `!example!widget`; `None`,
+    /// the default, adds no directives
+    ///
+    /// Directives added with [`Self::add_tag_directive`] or recorded on a
+    /// round-trip value shorten tags regardless. This applies to
+    /// [`Emitter::emit`] and [`Emitter::emit_commented`].
+    pub const fn set_tag_prefix_threshold(&mut self, threshold: Option<usize>) {
+        self.tag_prefix_threshold = threshold;
+    }
+
+    /// Set how the handles of added `%TAG` directives are named; `None` names
+    /// them after their prefix, such as `!example!`
+    pub fn set_tag_handle_namer(&mut self, namer: Option<Arc<dyn TagHandleNamer>>) {
+        self.tag_handle_namer = namer;
+    }
+
+    /// Set the styles of nodes at given paths, applied by [`Emitter::emit`]
+    ///
+    /// A collection written in flow style is written in flow style
+    /// throughout, so styles set inside it have no effect.
+    pub fn set_styles(&mut self, styles: StyleMap) {
+        self.styles = styles;
+    }
+
+    /// Warnings about the last emission, such as a YAML 1.1 boolean style
+    /// chosen for a YAML 1.2 document
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Reset the warnings for a new emission and check the configured styles
+    /// against the document's target version
+    fn check_styles(&mut self, style: Option<&Style>) {
+        self.warnings.clear();
+        let version = self
+            .yaml_version
+            .or_else(|| style.and_then(|style| style.yaml_version));
+        self.target_version = version;
+        if version >= Some((1, 2)) && self.bool_style != BoolStyle::TrueFalse {
+            self.warnings.push(format!(
+                "boolean style `{}/{}` is not a boolean in YAML 1.2; parsers of the \
+                 target version read it as a string",
+                self.bool_style.literal(true),
+                self.bool_style.literal(false)
+            ));
+        }
+    }
+
     /// Add a TAG directive
     pub fn add_tag_directive(&mut self, handle: String, prefix: String) {
         self.tag_directives.push((handle, prefix));
@@ -105,23 +753,50 @@ impl BasicEmitter {
     }
 
     /// Emit directives to the writer
-    fn emit_directives<W: Write>(&self, writer: &mut W) -> Result<()> {
+    ///
+    /// Directives configured on the emitter take precedence; those recorded on
+    /// a document's root `style` are emitted too so they survive a round-trip.
+    /// With a tag prefix threshold set, directives are added for the prefixes
+    /// shared by enough tags of `value`.
+    fn emit_directives<W: Write>(
+        &mut self,
+        style: Option<&Style>,
+        value: Option<&Value>,
+        writer: &mut W,
+    ) -> Result<()> {
+        let yaml_version = self
+            .yaml_version
+            .or_else(|| style.and_then(|style| style.yaml_version));
+        let mut tag_directives = self.tag_directives.clone();
+        for (handle, prefix) in style.map_or(&[][..], |style| &style.tag_directives[..]) {
+            if !tag_directives
+                .iter()
+                .any(|(existing, _)| existing == handle)
+            {
+                tag_directives.push((handle.clone(), prefix.clone()));
+            }
+        }
+        if let (Some(threshold), Some(value)) = (self.tag_prefix_threshold, value) {
+            self.add_tag_prefix_directives(value, threshold, &mut tag_directives);
+        }
+        self.tag_handles = tag_directives.clone();
+
         // Emit YAML version directive if set
-        if let Some((major, minor)) = self.yaml_version {
+        if let Some((major, minor)) = yaml_version {
             writeln!(writer, "%YAML {}.{}", major, minor).map_err(|e| Error::Emission {
                 message: format!("Failed to write YAML directive: {}", e),
             })?;
         }
 
         // Emit TAG directives
-        for (handle, prefix) in &self.tag_directives {
+        for (handle, prefix) in &tag_directives {
             writeln!(writer, "%TAG {} {}", handle, prefix).map_err(|e| Error::Emission {
                 message: format!("Failed to write TAG directive: {}", e),
             })?;
         }
 
         // If we emitted any directives, emit document start marker
-        if self.yaml_version.is_some() || !self.tag_directives.is_empty() {
+        if yaml_version.is_some() || !tag_directives.is_empty() {
             writeln!(writer, "---").map_err(|e| Error::Emission {
                 message: format!("Failed to write document start marker: {}", e),
             })?;
@@ -130,61 +805,223 @@ impl BasicEmitter {
         Ok(())
     }
 
+    /// Add a directive for every tag prefix of `value` shared by at least
+    /// `threshold` tags and not already covered by one
+    fn add_tag_prefix_directives(
+        &self,
+        value: &Value,
+        threshold: usize,
+        tag_directives: &mut Vec<(String, String)>,
+    ) {
+        let mut counts = indexmap::IndexMap::new();
+        count_tag_prefixes(value, &mut counts);
+        let mut index = 0;
+        for (prefix, count) in counts {
+            if count < threshold.max(1)
+                || tag_directives
+                    .iter()
+                    .any(|(_, existing)| existing == prefix)
+            {
+                continue;
+            }
+            let name = self
+                .tag_handle_namer
+                .as_ref()
+                .and_then(|namer| namer.handle_name(prefix, index))
+                .map(|name| {
+                    name.chars()
+                        .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+                        .collect::<String>()
+                })
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| default_handle_name(prefix));
+            let mut handle = format!("!{name}!");
+            let mut n = 2;
+            while tag_directives
+                .iter()
+                .any(|(existing, _)| *existing == handle)
+            {
+                handle = format!("!{name}{n}!");
+                n += 1;
+            }
+            tag_directives.push((handle, prefix.to_string()));
+            index += 1;
+        }
+    }
+
+    /// The shorthand form of a resolved tag, using the `%TAG` directives in
+    /// effect, `!!` for the core schema tags and the verbatim `!<...>` form
+    /// for other URIs
+    fn tag_shorthand(&self, tag: &str) -> String {
+        let shortest = self
+            .tag_handles
+            .iter()
+            .filter_map(|(handle, prefix)| {
+                tag.strip_prefix(prefix.as_str())
+                    .filter(|suffix| is_shorthand_suffix(suffix))
+                    .map(|suffix| (handle, suffix))
+            })
+            .min_by_key(|(_, suffix)| suffix.len());
+        if let Some((handle, suffix)) = shortest {
+            return format!("{handle}{suffix}");
+        }
+        let redefined = |handle: &str| self.tag_handles.iter().any(|(h, _)| h == handle);
+        match tag.strip_prefix(CORE_TAG_PREFIX) {
+            Some(suffix) if !redefined("!!") => format!("!!{suffix}"),
+            _ if tag.starts_with('!') && !redefined("!") => tag.to_string(),
+            _ => format!("!<{tag}>"),
+        }
+    }
+
     /// Analyze the value tree to identify shared values that need anchors
+    ///
+    /// Collections are matched by content hash before being compared, and
+    /// the tree is walked from an explicit stack, so values of any depth
+    /// are analyzed in time linear in their size.
     fn analyze_shared_values(&mut self, value: &Value) {
-        let mut value_counts = HashMap::new();
-        self.count_value_occurrences(value, &mut value_counts);
+        let Some(threshold) = self.alias_threshold else {
+            return;
+        };
 
-        // Generate anchors for values that occur more than once and are complex
-        for (val, count) in value_counts {
-            if count > 1 && self.is_complex_value(&val) {
-                let anchor_name = format!("anchor{}", self.anchor_counter);
-                self.anchor_counter += 1;
-                self.shared_values.insert(
-                    val,
-                    ValueInfo {
-                        anchor_name,
-                        first_occurrence: true,
-                    },
-                );
+        let mut hashes = HashMap::new();
+        collection_hashes(value, |node, hash| {
+            hashes.insert(node_address(node), hash);
+        });
+        let hash_of = |node: &Value| hashes.get(&node_address(node)).copied().unwrap_or(0);
+
+        // Distinct collections in the order first found, so anchor names are
+        // stable, with every node found at and the collections hidden in
+        // tagged values
+        let mut found: Vec<FoundCollection<'_>> = Vec::new();
+        let mut by_hash: HashMap<u64, Vec<usize>> = HashMap::new();
+        let mut occurrences = Vec::new();
+        let mut tagged = Vec::new();
+        let mut stack = vec![(value, None)];
+        while let Some((node, parent)) = stack.pop() {
+            match node {
+                Value::Sequence(_) | Value::Mapping(_) => {}
+                Value::Tagged(inner) => {
+                    tagged.push(&inner.value);
+                    continue;
+                }
+                _ => continue,
+            }
+            let candidates = by_hash.entry(hash_of(node)).or_default();
+            if let Some(&index) = candidates.iter().find(|&&index| found[index].value == node) {
+                // A repeat is either an alias or a copy of a subtree that is
+                // too small to alias, so what it contains is only counted once
+                found[index].count += 1;
+                occurrences.push((node_address(node), index));
+                continue;
+            }
+            let index = found.len();
+            candidates.push(index);
+            occurrences.push((node_address(node), index));
+            found.push(FoundCollection {
+                value: node,
+                count: 1,
+                parent,
+            });
+            match node {
+                Value::Sequence(seq) => {
+                    for (i, item) in seq.iter().enumerate().rev() {
+                        stack.push((item, Some((index, Some(FoundStep::Index(i))))));
+                    }
+                }
+                Value::Mapping(map) => {
+                    for (key, val) in map.iter().rev() {
+                        stack.push((val, Some((index, Some(FoundStep::Key(key))))));
+                        // A collection key has no path of its own; name it
+                        // after its mapping
+                        stack.push((key, Some((index, None))));
+                    }
+                }
+                _ => {}
             }
         }
-    }
 
-    /// Recursively count occurrences of each value
-    fn count_value_occurrences(&self, value: &Value, counts: &mut HashMap<Value, usize>) {
-        // Only track complex values (sequences and mappings)
-        if self.is_complex_value(value) {
-            *counts.entry(value.clone()).or_insert(0) += 1;
+        // Generate anchors for complex values that occur more than once and
+        // are large enough to be worth an alias
+        let mut anchors = vec![None; found.len()];
+        for (index, collection) in found.iter().enumerate() {
+            if collection.count > 1 && node_count(collection.value) >= threshold {
+                let path = Self::found_path(&found, index);
+                let anchor_name = self.next_anchor_name(&path);
+                anchors[index] = Some(self.shared_values.anchors.len());
+                self.shared_values.anchors.push(ValueInfo {
+                    anchor_name,
+                    first_occurrence: true,
+                });
+            }
+        }
+        for (address, index) in occurrences {
+            if let Some(anchor) = anchors[index] {
+                self.shared_values.nodes.insert(address, anchor);
+            }
         }
 
-        // Recurse into child values
-        match value {
-            Value::Sequence(seq) => {
-                for item in seq {
-                    self.count_value_occurrences(item, counts);
+        // Tagged values are not aliased, but a repeated collection inside
+        // one is still written with the anchor or alias of its content
+        let mut stack = tagged;
+        while let Some(node) = stack.pop() {
+            match node {
+                Value::Sequence(seq) => stack.extend(seq),
+                Value::Mapping(map) => stack.extend(map.iter().flat_map(|(k, v)| [k, v])),
+                Value::Tagged(inner) => {
+                    stack.push(&inner.value);
+                    continue;
                 }
+                _ => continue,
             }
-            Value::Mapping(map) => {
-                for (key, val) in map {
-                    self.count_value_occurrences(key, counts);
-                    self.count_value_occurrences(val, counts);
-                }
+            let anchor = by_hash.get(&hash_of(node)).and_then(|candidates| {
+                candidates
+                    .iter()
+                    .filter_map(|&index| Some((anchors[index]?, found[index].value)))
+                    .find(|&(_, value)| value == node)
+            });
+            if let Some((anchor, _)) = anchor {
+                self.shared_values.nodes.insert(node_address(node), anchor);
             }
-            _ => {}
         }
     }
 
-    /// Check if a value is complex enough to warrant anchor/alias handling
-    const fn is_complex_value(&self, value: &Value) -> bool {
-        matches!(value, Value::Sequence(_) | Value::Mapping(_))
+    /// Path of the collection first found as `found[index]`
+    fn found_path(found: &[FoundCollection<'_>], index: usize) -> Path {
+        let mut steps = Vec::new();
+        let mut parent = found[index].parent;
+        while let Some((index, step)) = parent {
+            steps.extend(step);
+            parent = found[index].parent;
+        }
+        let mut path = Path::new();
+        for step in steps.into_iter().rev() {
+            path.push(match step {
+                FoundStep::Index(index) => PathSegment::Index(index),
+                FoundStep::Key(key) => PathSegment::Key(key.clone()),
+            });
+        }
+        path
     }
 
-    /// Generate next anchor name
-    fn next_anchor_name(&mut self) -> String {
-        let name = format!("anchor{}", self.anchor_counter);
+    /// Generate the next anchor name, for the collection at `path`
+    fn next_anchor_name(&mut self, path: &Path) -> String {
+        let index = self.anchor_counter;
         self.anchor_counter += 1;
-        name
+        let name = self
+            .anchor_namer
+            .as_ref()
+            .and_then(|namer| namer.anchor_name(path, index))
+            .map(|name| sanitize_anchor_name(&name))
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| format!("anchor{index}"));
+
+        let mut unique = name.clone();
+        let mut suffix = 2;
+        while !self.anchor_names.insert(unique.clone()) {
+            unique = format!("{name}_{suffix}");
+            suffix += 1;
+        }
+        unique
     }
 
     /// Update the indent style (useful for round-trip preservation)
@@ -224,9 +1061,28 @@ impl BasicEmitter {
         Ok(())
     }
 
-    /// Write a trailing comment on the same line
-    fn emit_trailing_comment<W: Write>(&self, comment: &str, writer: &mut W) -> Result<()> {
-        write!(writer, " # {}", comment)?;
+    /// Write the blank lines recorded above an entry that is not the first
+    /// of its collection
+    fn emit_blank_lines<W: Write>(&self, comments: &Comments, writer: &mut W) -> Result<()> {
+        for _ in 0..comments.blank_lines {
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    /// Write the trailing comment of `comments` on the same line, aligned as
+    /// set with [`Self::set_align_trailing_comments`]
+    fn emit_trailing_comment<W: Write>(&self, comments: &Comments, writer: &mut W) -> Result<()> {
+        let Some(comment) = &comments.trailing else {
+            return Ok(());
+        };
+        let target = match self.align_trailing_comments {
+            Some(0) => comments.trailing_column,
+            column => column,
+        };
+        let written = self.column.load(Ordering::Relaxed);
+        let padding = target.map_or(1, |column| column.saturating_sub(written + 1).max(1));
+        write!(writer, "{:padding$}# {}", "", comment)?;
         Ok(())
     }
 
@@ -240,6 +1096,44 @@ impl BasicEmitter {
         Ok(())
     }
 
+    /// The tag to write before the scalar `value`, in shorthand form where
+    /// one exists
+    fn scalar_tag(&self, value: &Value, style: Option<&Style>) -> Option<String> {
+        if let Value::Tagged(tagged) = value {
+            return Some(self.tag_shorthand(&tagged.tag));
+        }
+        let core = match value {
+            Value::Null => "tag:yaml.org,2002:null",
+            Value::Bool(_) => "tag:yaml.org,2002:bool",
+            Value::Int(_) => "tag:yaml.org,2002:int",
+            Value::Float(_) => "tag:yaml.org,2002:float",
+            Value::String(_) => "tag:yaml.org,2002:str",
+            _ => return None,
+        };
+        // A recorded tag of another type, such as `!!binary` on the decoded
+        // string, no longer describes the value
+        let recorded = style
+            .and_then(|style| style.tag.as_deref())
+            .is_some_and(|tag| tag == core);
+        let tagged = match self.tag_emission {
+            TagEmission::Never => false,
+            TagEmission::NonDefault => {
+                recorded || matches!(value, Value::String(s) if !is_plain_string(s))
+            }
+            TagEmission::Always => true,
+        };
+        tagged.then(|| self.tag_shorthand(core))
+    }
+
+    /// A block indicator (`:` or `-`) followed by the tag of the scalar
+    /// `value` when it is written with one
+    fn tagged_indicator(&self, indicator: &str, value: &Value, style: Option<&Style>) -> String {
+        match self.scalar_tag(value, style) {
+            Some(tag) => format!("{indicator} {tag}"),
+            None => indicator.to_string(),
+        }
+    }
+
     /// Emit a scalar value
     fn emit_scalar<W: Write>(&self, value: &Value, writer: &mut W) -> Result<()> {
         self.emit_scalar_with_comments(value, None, writer)
@@ -256,11 +1150,14 @@ impl BasicEmitter {
     }
 
     /// Emit a scalar value with optional comments and style information
+    ///
+    /// A recorded null style or literal in `style` takes precedence over the
+    /// emitter's configured styles.
     fn emit_scalar_with_comments_and_style<W: Write>(
         &self,
         value: &Value,
         comments: Option<&Comments>,
-        quote_style: Option<&QuoteStyle>,
+        style: Option<&Style>,
         writer: &mut W,
     ) -> Result<()> {
         // Emit leading comments
@@ -268,11 +1165,56 @@ impl BasicEmitter {
             self.emit_leading_comments(&comments.leading, writer)?;
         }
 
-        // Emit the scalar value
+        if let Some(tag) = self.scalar_tag(value, style) {
+            write!(writer, "{tag} ")?;
+        }
+        self.emit_scalar_body(value, style, writer)?;
+
+        // Emit trailing comment
+        if let Some(comments) = comments {
+            self.emit_trailing_comment(comments, writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Emit a scalar without its tag; the node of a tagged value is written
+    /// in flow style
+    fn emit_scalar_body<W: Write>(
+        &self,
+        value: &Value,
+        style: Option<&Style>,
+        writer: &mut W,
+    ) -> Result<()> {
         match value {
-            Value::Null => write!(writer, "null")?,
-            Value::Bool(b) => write!(writer, "{}", b)?,
-            Value::Int(i) => write!(writer, "{}", i)?,
+            Value::Null => {
+                let null_style = style
+                    .and_then(|style| style.null_style)
+                    .unwrap_or(self.null_style);
+                // An empty node is not allowed here, so fall back to `null`
+                match null_style {
+                    NullStyle::Empty => write!(writer, "{}", NullStyle::Null.literal())?,
+                    null_style => write!(writer, "{}", null_style.literal())?,
+                }
+            }
+            Value::Bool(b) => {
+                match style
+                    .and_then(|style| style.literal.as_deref())
+                    .filter(|literal| bool_word(literal) == Some(*b))
+                {
+                    Some(literal) => write!(writer, "{}", literal)?,
+                    None => write!(writer, "{}", self.bool_style.literal(*b))?,
+                }
+            }
+            Value::Int(i) => {
+                match style
+                    .and_then(|style| style.literal.as_deref())
+                    .filter(|literal| parse_int(literal) == Some(*i))
+                {
+                    Some(literal) => write!(writer, "{}", literal)?,
+                    None => write!(writer, "{}", i)?,
+                }
+            }
             Value::Float(f) => {
                 // Handle special float values
                 if f.is_nan() {
@@ -293,18 +1235,145 @@ impl BasicEmitter {
                 }
             }
             Value::String(s) => {
+                let quote_style = style.and_then(|style| style.quote_style.as_ref());
                 self.emit_string_with_style(s, quote_style, writer)?;
             }
+            Value::Tagged(tagged) => match &tagged.value {
+                Value::Sequence(_) | Value::Mapping(_) => {
+                    self.emit_flow_node(&tagged.value, writer)?;
+                }
+                Value::Tagged(inner) => {
+                    write!(writer, "{} ", self.tag_shorthand(&inner.tag))?;
+                    self.emit_scalar_body(&tagged.value, style, writer)?;
+                }
+                scalar => self.emit_scalar_body(scalar, style, writer)?,
+            },
             _ => return Err(Error::emission("Non-scalar passed to emit_scalar")),
         }
+        Ok(())
+    }
+
+    /// Emit a scalar after a block indicator (`:` or `-`), leaving out the
+    /// separating space when a null is written as an empty node
+    fn emit_entry_scalar<W: Write>(
+        &self,
+        indicator: &str,
+        value: &Value,
+        style: Option<&Style>,
+        writer: &mut W,
+    ) -> Result<()> {
+        let null_style = style
+            .and_then(|style| style.null_style)
+            .unwrap_or(self.null_style);
+        // A tagged null is written as `null` after its tag
+        let tagged = self.tagged_indicator(indicator, value, style);
+        if value.is_null() && null_style == NullStyle::Empty && tagged == indicator {
+            write!(writer, "{indicator}")?;
+            return Ok(());
+        }
+        if let Some((s, block)) = self.block_scalar_for(value, style) {
+            return self.emit_block_scalar(&tagged, s, block, None, writer);
+        }
+        write!(writer, "{indicator} ")?;
+        self.emit_scalar_with_comments_and_style(value, None, style, writer)
+    }
 
-        // Emit trailing comment
+    /// The block scalar style to write `value` with, when it is a string
+    /// recorded as a block scalar and one can represent it
+    ///
+    /// Recorded indicators are kept while they still fit the string; an
+    /// indentation indicator is added when the content starts with a space
+    /// and the chomping follows the string's trailing line breaks.
+    fn block_scalar_for<'a>(
+        &self,
+        value: &'a Value,
+        style: Option<&Style>,
+    ) -> Option<(&'a str, BlockScalarStyle)> {
+        let Value::String(s) = value else {
+            return None;
+        };
+        let recorded = style?.block_scalar?;
+        // Block scalar content is indented with spaces only
+        if self.indent_style == IndentStyle::Tabs {
+            return None;
+        }
+
+        let body = s.trim_end_matches('\n');
+        if body.is_empty()
+            || body.chars().any(|c| {
+                (c.is_control() && c != '\n' && c != '\t')
+                    || matches!(c, '\u{feff}' | '\u{85}' | '\u{2028}' | '\u{2029}')
+            })
+        {
+            return None;
+        }
+
+        let breaks = s.len() - body.len();
+        let chomping = recorded
+            .chomping
+            .filter(|chomping| chomping.fits(breaks))
+            .unwrap_or(Chomping::for_trailing_breaks(breaks));
+        let starts_with_space = body
+            .split('\n')
+            .find(|line| !line.is_empty())
+            .is_some_and(|line| line.starts_with(' '));
+        let indent = recorded
+            .indent
+            .or_else(|| starts_with_space.then(|| self.indent.clamp(1, 9) as u8));
+
+        Some((
+            s,
+            BlockScalarStyle {
+                folded: recorded.folded,
+                indent,
+                chomping: Some(chomping),
+            },
+        ))
+    }
+
+    /// Write `s` as a block scalar after `indicator`, with the trailing
+    /// comment of `comments` on the header line
+    ///
+    /// The line break after the last line is left to whatever follows, as
+    /// for other scalars.
+    fn emit_block_scalar<W: Write>(
+        &self,
+        indicator: &str,
+        s: &str,
+        block: BlockScalarStyle,
+        comments: Option<&Comments>,
+        writer: &mut W,
+    ) -> Result<()> {
+        let chomping = block.chomping.unwrap_or_default();
+        let header = if block.folded { '>' } else { '|' };
+        write!(writer, "{indicator} {header}")?;
+        if let Some(indent) = block.indent {
+            write!(writer, "{indent}")?;
+        }
+        write!(writer, "{}", chomping.indicator())?;
         if let Some(comments) = comments {
-            if let Some(ref trailing) = comments.trailing {
-                self.emit_trailing_comment(trailing, writer)?;
+            self.emit_trailing_comment(comments, writer)?;
+        }
+
+        let content_indent =
+            self.current_indent + block.indent.map_or(self.indent.max(1), usize::from);
+        let body = s.trim_end_matches('\n');
+        let lines = if block.folded {
+            unfold_block_lines(body)
+        } else {
+            body.split('\n').collect()
+        };
+        for line in lines {
+            writeln!(writer)?;
+            if !line.is_empty() {
+                write!(writer, "{:content_indent$}{line}", "")?;
             }
         }
 
+        // Trailing empty lines kept by `+`
+        for _ in 1..s.len() - body.len() {
+            writeln!(writer)?;
+        }
         Ok(())
     }
 
@@ -342,17 +1411,21 @@ impl BasicEmitter {
             return true;
         }
 
-        // String needs quoting if it could be interpreted as another type
-        if s == "null"
-            || s == "~"
-            || s == "true"
-            || s == "false"
-            || s == "yes"
-            || s == "no"
-            || s == "on"
-            || s == "off"
-            || s.parse::<i64>().is_ok()
-            || s.parse::<f64>().is_ok()
+        // String needs quoting if the composer would resolve the plain
+        // scalar to anything other than this same string (bool, int, float,
+        // null); asking the resolver keeps both sides in step
+        if !is_plain_string(s) {
+            return true;
+        }
+
+        // YAML 1.1 parsers resolve more plain scalars than the 1.2 core schema
+        if self.target_version.is_some_and(|version| version < (1, 2)) && is_yaml_1_1_scalar(s) {
+            return true;
+        }
+
+        // Leading indicators would start an anchor, alias, tag, block scalar,
+        // directive, explicit key or reserved syntax; `...` ends a document
+        if s.starts_with(['&', '*', '!', '|', '>', '%', '@', '`', '?', ',']) || s.starts_with("...")
         {
             return true;
         }
@@ -378,8 +1451,10 @@ impl BasicEmitter {
             || s.contains(']')
             || s.contains('{')
             || s.contains('}')
-            || s.starts_with(' ')
-            || s.ends_with(' ')
+            || s.contains(',')
+            || s.starts_with(char::is_whitespace)
+            || s.ends_with(char::is_whitespace)
+            || s.chars().any(|c| c.is_control() || c == '\u{feff}')
         {
             return true;
         }
@@ -397,7 +1472,15 @@ impl BasicEmitter {
                 '\n' => write!(writer, "\\n")?,
                 '\r' => write!(writer, "\\r")?,
                 '\t' => write!(writer, "\\t")?,
-                c if c.is_control() => write!(writer, "\\u{:04x}", c as u32)?,
+                '\0' => write!(writer, "\\0")?,
+                '\x07' => write!(writer, "\\a")?,
+                '\x08' => write!(writer, "\\b")?,
+                '\x0B' => write!(writer, "\\v")?,
+                '\x0C' => write!(writer, "\\f")?,
+                '\x1B' => write!(writer, "\\e")?,
+                c if c.is_control() || c == '\u{feff}' => {
+                    write!(writer, "\\u{:04x}", c as u32)?;
+                }
                 c => write!(writer, "{}", c)?,
             }
         }
@@ -429,30 +1512,74 @@ impl BasicEmitter {
             write!(writer, "[]")?;
             return Ok(());
         }
+        self.emit_block(BlockEntries::Sequence(seq.iter().enumerate()), writer)
+    }
 
-        for (index, item) in seq.iter().enumerate() {
-            if index > 0 {
+    /// Write the entries of a block collection and of the collections nested
+    /// in them, from an explicit stack so nesting of any depth is written
+    /// without recursing
+    fn emit_block<W: Write>(&mut self, entries: BlockEntries<'_>, writer: &mut W) -> Result<()> {
+        // Each collection being written, with whether an entry of it is
+        // written yet
+        let mut stack = vec![(entries, false)];
+        while let Some((entries, started)) = stack.last_mut() {
+            let Some(entry) = entries.next() else {
+                stack.pop();
+                // Back out of the entry that held the finished collection
+                if !stack.is_empty() {
+                    self.current_indent -= self.indent;
+                    self.leave();
+                }
+                continue;
+            };
+            if std::mem::replace(started, true) {
                 writeln!(writer)?;
             }
             self.write_indent(writer)?;
-            write!(writer, "- ")?;
-
-            match item {
-                Value::Sequence(_) | Value::Mapping(_) => {
+            let nested = match entry {
+                BlockEntry::Item(index, item) => self.emit_block_item(index, item, writer)?,
+                BlockEntry::Pair(key, value) => self.emit_block_pair(key, value, writer)?,
+            };
+            match nested.and_then(BlockEntries::of) {
+                Some(entries) => {
                     writeln!(writer)?; // Add newline before nested structure
                     self.current_indent += self.indent;
-                    self.emit_value(item, writer)?;
-                    self.current_indent -= self.indent;
-                }
-                _ => {
-                    self.emit_scalar(item, writer)?;
+                    stack.push((entries, false));
                 }
+                None => self.leave(),
             }
         }
-
         Ok(())
     }
 
+    /// Write a sequence entry up to the collection it holds, which is
+    /// returned when it is still to be written on the next lines
+    fn emit_block_item<'a, W: Write>(
+        &mut self,
+        index: usize,
+        item: &'a Value,
+        writer: &mut W,
+    ) -> Result<Option<&'a Value>> {
+        self.enter(|| PathSegment::Index(index));
+
+        match item {
+            _ if is_collection(item) => {
+                if let Some(flow) = self.auto_flow(item, "- ".len()) {
+                    write!(writer, "- {flow}")?;
+                } else {
+                    let (header, nested) = self.collection_header(item);
+                    write!(writer, "- {header}")?;
+                    return Ok(nested.then_some(item));
+                }
+            }
+            _ => {
+                let style = self.node_scalar_style();
+                self.emit_entry_scalar("-", item, style.as_ref(), writer)?;
+            }
+        }
+        Ok(None)
+    }
+
     /// Emit a mapping with an anchor
     fn emit_mapping_with_anchor<W: Write>(
         &mut self,
@@ -470,46 +1597,7 @@ impl BasicEmitter {
         // key1: value1
         // key2: value2
         writeln!(writer, "&{}", anchor)?;
-
-        let mut first = true;
-        for (key, value) in map {
-            if !first {
-                writeln!(writer)?;
-            }
-            first = false;
-
-            self.write_indent(writer)?;
-
-            // Handle both simple and complex keys
-            let is_complex_key = matches!(key, Value::Sequence(_) | Value::Mapping(_));
-
-            if is_complex_key {
-                // Complex key - emit with explicit key marker
-                write!(writer, "? ")?;
-                self.emit_value(key, writer)?;
-                writeln!(writer)?;
-                self.write_indent(writer)?;
-            } else {
-                // Simple key
-                self.emit_scalar(key, writer)?;
-            }
-
-            write!(writer, ": ")?;
-
-            match value {
-                Value::Sequence(_) | Value::Mapping(_) => {
-                    writeln!(writer)?; // Add newline before nested structure
-                    self.current_indent += self.indent;
-                    self.emit_value(value, writer)?;
-                    self.current_indent -= self.indent;
-                }
-                _ => {
-                    self.emit_scalar(value, writer)?;
-                }
-            }
-        }
-
-        Ok(())
+        self.emit_mapping(map, writer)
     }
 
     /// Emit a mapping (dictionary/object)
@@ -522,58 +1610,151 @@ impl BasicEmitter {
             write!(writer, "{{}}")?;
             return Ok(());
         }
+        self.emit_block(BlockEntries::Mapping(map.iter()), writer)
+    }
 
-        let mut first = true;
-        for (key, value) in map {
-            if !first {
-                writeln!(writer)?;
+    /// Write a mapping entry up to the collection its value is, which is
+    /// returned when it is still to be written on the next lines
+    fn emit_block_pair<'a, W: Write>(
+        &mut self,
+        key: &Value,
+        value: &'a Value,
+        writer: &mut W,
+    ) -> Result<Option<&'a Value>> {
+        // Handle both simple and complex keys
+        let mut inline = None;
+        if is_collection(key) {
+            // Complex key - emit with explicit key marker and flow style
+            // to avoid ambiguity
+            write!(writer, "? ")?;
+            self.emit_flow_node(key, writer)?;
+            writeln!(writer)?;
+            self.write_indent(writer)?;
+            self.enter(|| PathSegment::Key(key.clone()));
+        } else {
+            // Simple key, measured in case the value is written inline
+            let mut key_text = Vec::new();
+            self.emit_scalar(key, &mut key_text)?;
+            writer.write_all(&key_text)?;
+
+            self.enter(|| PathSegment::Key(key.clone()));
+            let key_width = String::from_utf8_lossy(&key_text).chars().count();
+            inline = self.auto_flow(value, key_width + ": ".len());
+        }
+
+        match value {
+            _ if inline.is_some() => {
+                write!(writer, ": {}", inline.unwrap_or_default())?;
+            }
+            _ if is_collection(value) => {
+                let (header, nested) = self.collection_header(value);
+                write!(writer, ": {header}")?;
+                return Ok(nested.then_some(value));
             }
-            first = false;
+            _ => {
+                let style = self.node_scalar_style();
+                self.emit_entry_scalar(":", value, style.as_ref(), writer)?;
+            }
+        }
+        Ok(None)
+    }
 
-            self.write_indent(writer)?;
+    /// Step into a child of the node being written, for the styles set by
+    /// path
+    fn enter(&mut self, segment: impl FnOnce() -> PathSegment) {
+        if !self.styles.is_empty() {
+            self.path.push(segment());
+        }
+    }
 
-            // Handle both simple and complex keys
-            let is_complex_key = matches!(key, Value::Sequence(_) | Value::Mapping(_));
+    /// Step back out of a child entered with [`Self::enter`]
+    fn leave(&mut self) {
+        if !self.styles.is_empty() {
+            self.path.pop();
+        }
+    }
 
-            if is_complex_key {
-                // Complex key - emit with explicit key marker and flow style
-                write!(writer, "? ")?;
-                match key {
-                    Value::Mapping(map) => {
-                        // Emit mapping in flow style to avoid ambiguity
-                        self.emit_mapping_flow_style(map, writer)?;
-                    }
-                    Value::Sequence(seq) => {
-                        // Emit sequence in flow style
-                        self.emit_sequence_flow_style(seq, writer)?;
-                    }
-                    _ => {
-                        self.emit_value(key, writer)?;
-                    }
-                }
-                writeln!(writer)?;
-                self.write_indent(writer)?;
-            } else {
-                // Simple key
-                self.emit_scalar(key, writer)?;
+    /// The collection style of the node being written
+    fn node_flow_style(&self) -> FlowStyle {
+        self.styles
+            .flow_style(&self.path)
+            .unwrap_or(self.flow_style)
+    }
+
+    /// The style set for the scalar being written
+    fn node_scalar_style(&self) -> Option<Style> {
+        self.styles.scalar_style(&self.path)
+    }
+
+    /// The inline flow form of `value` when it is written that way: a
+    /// collection set to [`FlowStyle::Flow`], or with [`FlowStyle::Auto`] a
+    /// non-empty collection of scalars that fits within the width after
+    /// `prefix_width` characters of the line; repeated collections keep their
+    /// anchors
+    fn auto_flow(&self, value: &Value, prefix_width: usize) -> Option<String> {
+        if self.shared_values.contains_key(value) {
+            return None;
+        }
+        match self.node_flow_style() {
+            FlowStyle::Block => return None,
+            FlowStyle::Flow => {
+                let mut flow = Vec::new();
+                self.emit_flow_node(value, &mut flow).ok()?;
+                return String::from_utf8(flow).ok();
+            }
+            FlowStyle::Auto => {}
+        }
+        let is_scalar = |value: &Value| !matches!(value, Value::Sequence(_) | Value::Mapping(_));
+        let mut flow = Vec::new();
+        match value {
+            Value::Sequence(seq) if !seq.is_empty() && seq.iter().all(is_scalar) => {
+                self.emit_sequence_flow_style(seq, &mut flow).ok()?;
+            }
+            Value::Mapping(map)
+                if !map.is_empty() && map.iter().all(|(k, v)| is_scalar(k) && is_scalar(v)) =>
+            {
+                self.emit_mapping_flow_style(map, &mut flow).ok()?;
             }
+            _ => return None,
+        }
 
-            write!(writer, ": ")?;
+        let flow = String::from_utf8(flow).ok()?;
+        let line_width = self.current_indent + prefix_width + flow.chars().count();
+        self.width
+            .is_none_or(|width| line_width <= width)
+            .then_some(flow)
+    }
 
-            match value {
-                Value::Sequence(_) | Value::Mapping(_) => {
-                    writeln!(writer)?; // Add newline before nested structure
-                    self.current_indent += self.indent;
-                    self.emit_value(value, writer)?;
-                    self.current_indent -= self.indent;
-                }
-                _ => {
-                    self.emit_scalar(value, writer)?;
+    /// What follows the indicator of a key or sequence entry whose value is
+    /// a collection: the alias or empty collection that is the whole value,
+    /// or the anchor of a collection written on the next lines, together
+    /// with whether those lines are still to be written
+    fn collection_header(&mut self, value: &Value) -> (String, bool) {
+        if let Value::Tagged(tagged) = value {
+            let tag = self.tag_shorthand(&tagged.tag);
+            return match &tagged.value {
+                Value::Sequence(seq) if seq.is_empty() => (format!("{tag} []"), false),
+                Value::Mapping(map) if map.is_empty() => (format!("{tag} {{}}"), false),
+                _ => (tag, true),
+            };
+        }
+        let empty = match value {
+            Value::Sequence(seq) if seq.is_empty() => Some("[]"),
+            Value::Mapping(map) if map.is_empty() => Some("{}"),
+            _ => None,
+        };
+        match (self.shared_values.get_mut(value), empty) {
+            (Some(info), _) if !info.first_occurrence => (format!("*{}", info.anchor_name), false),
+            (Some(info), empty) => {
+                info.first_occurrence = false;
+                match empty {
+                    Some(empty) => (format!("&{} {empty}", info.anchor_name), false),
+                    None => (format!("&{}", info.anchor_name), true),
                 }
             }
+            (None, Some(empty)) => (empty.to_string(), false),
+            (None, None) => (String::new(), true),
         }
-
-        Ok(())
     }
 
     /// Emit a mapping in flow style for complex keys
@@ -582,69 +1763,69 @@ impl BasicEmitter {
         map: &indexmap::IndexMap<Value, Value>,
         writer: &mut W,
     ) -> Result<()> {
-        write!(writer, "{{")?;
-        let mut first = true;
-        for (key, value) in map {
-            if !first {
-                write!(writer, ", ")?;
-            }
-            first = false;
-
-            // Emit key (handle nested complex values)
-            match key {
-                Value::Mapping(nested_map) => {
-                    self.emit_mapping_flow_style(nested_map, writer)?;
-                }
-                Value::Sequence(nested_seq) => {
-                    self.emit_sequence_flow_style(nested_seq, writer)?;
-                }
-                _ => {
-                    self.emit_scalar(key, writer)?;
-                }
-            }
-
-            write!(writer, ": ")?;
-
-            // Emit value (handle nested complex values)
-            match value {
-                Value::Mapping(nested_map) => {
-                    self.emit_mapping_flow_style(nested_map, writer)?;
-                }
-                Value::Sequence(nested_seq) => {
-                    self.emit_sequence_flow_style(nested_seq, writer)?;
-                }
-                _ => {
-                    self.emit_scalar(value, writer)?;
-                }
-            }
-        }
-        write!(writer, "}}")?;
-        Ok(())
+        self.emit_flow(FlowStep::Mapping(map), writer)
     }
 
     /// Emit a sequence in flow style for complex keys
     fn emit_sequence_flow_style<W: Write>(&self, seq: &[Value], writer: &mut W) -> Result<()> {
-        write!(writer, "[")?;
-        let mut first = true;
-        for item in seq {
-            if !first {
-                write!(writer, ", ")?;
-            }
-            first = false;
-            // Handle nested complex values
-            match item {
-                Value::Mapping(nested_map) => {
-                    self.emit_mapping_flow_style(nested_map, writer)?;
+        self.emit_flow(FlowStep::Sequence(seq), writer)
+    }
+
+    /// Emit any value in flow style
+    fn emit_flow_node<W: Write>(&self, value: &Value, writer: &mut W) -> Result<()> {
+        self.emit_flow(FlowStep::Node(value), writer)
+    }
+
+    /// Write `step` and everything it leads to, from an explicit stack so
+    /// nested collections of any depth are written without recursing
+    fn emit_flow<W: Write>(&self, step: FlowStep<'_>, writer: &mut W) -> Result<()> {
+        let mut steps = vec![step];
+        while let Some(step) = steps.pop() {
+            match step {
+                FlowStep::Node(Value::Sequence(seq)) => steps.push(FlowStep::Sequence(seq)),
+                FlowStep::Node(Value::Mapping(map)) => steps.push(FlowStep::Mapping(map)),
+                FlowStep::Node(Value::Tagged(tagged)) => {
+                    write!(writer, "{} ", self.tag_shorthand(&tagged.tag))?;
+                    steps.push(FlowStep::Content(tagged));
                 }
-                Value::Sequence(nested_seq) => {
-                    self.emit_sequence_flow_style(nested_seq, writer)?;
+                FlowStep::Node(scalar) => self.emit_scalar(scalar, writer)?,
+                FlowStep::Sequence(seq) => {
+                    write!(writer, "[")?;
+                    steps.push(FlowStep::Text("]"));
+                    for (index, item) in seq.iter().enumerate().rev() {
+                        steps.push(FlowStep::Node(item));
+                        if index > 0 {
+                            steps.push(FlowStep::Text(", "));
+                        }
+                    }
                 }
-                _ => {
-                    self.emit_scalar(item, writer)?;
+                FlowStep::Mapping(map) => {
+                    write!(writer, "{{")?;
+                    steps.push(FlowStep::Text("}"));
+                    for (index, (key, value)) in map.iter().enumerate().rev() {
+                        steps.extend([
+                            FlowStep::Node(value),
+                            FlowStep::Text(": "),
+                            FlowStep::Node(key),
+                        ]);
+                        if index > 0 {
+                            steps.push(FlowStep::Text(", "));
+                        }
+                    }
                 }
+                FlowStep::Content(tagged) => match &tagged.value {
+                    Value::Sequence(_) | Value::Mapping(_) => {
+                        steps.push(FlowStep::Node(&tagged.value));
+                    }
+                    Value::Tagged(inner) => {
+                        write!(writer, "{} ", self.tag_shorthand(&inner.tag))?;
+                        steps.push(FlowStep::Content(inner));
+                    }
+                    scalar => self.emit_scalar_body(scalar, None, writer)?,
+                },
+                FlowStep::Text(text) => write!(writer, "{text}")?,
             }
         }
-        write!(writer, "]")?;
         Ok(())
     }
 
@@ -677,6 +1858,7 @@ impl BasicEmitter {
             match value {
                 Value::Sequence(seq) => self.emit_sequence(seq, writer),
                 Value::Mapping(map) => self.emit_mapping(map, writer),
+                _ if is_collection(value) => self.emit_root_tagged(value, None, writer),
                 _ => self.emit_scalar(value, writer),
             }?;
         }
@@ -684,14 +1866,37 @@ impl BasicEmitter {
     }
 
     /// Emit any value (old method for backwards compatibility)
+    ///
+    /// The tag of a tagged collection is left to the header written before
+    /// it.
     fn emit_value_simple<W: Write>(&mut self, value: &Value, writer: &mut W) -> Result<()> {
         match value {
             Value::Sequence(seq) => self.emit_sequence(seq, writer),
             Value::Mapping(map) => self.emit_mapping(map, writer),
+            Value::Tagged(tagged) if is_collection(value) => {
+                self.emit_value_simple(&tagged.value, writer)
+            }
             _ => self.emit_scalar(value, writer),
         }
     }
 
+    /// Emit a tagged collection that starts the document: the tag on a line
+    /// of its own, then the collection
+    fn emit_root_tagged<W: Write>(
+        &mut self,
+        value: &Value,
+        recorded: Option<&CommentedValue>,
+        writer: &mut W,
+    ) -> Result<()> {
+        let (header, nested) = self.collection_header(value);
+        write!(writer, "{header}")?;
+        if nested {
+            writeln!(writer)?;
+            self.emit_recorded_collection(value, recorded, writer)?;
+        }
+        Ok(())
+    }
+
     /// Emit a commented value with full comment and style support
     fn emit_commented_value<W: Write>(
         &mut self,
@@ -704,26 +1909,41 @@ impl BasicEmitter {
             None
         };
 
-        let quote_style = commented.quote_style();
-
         match &commented.value {
-            Value::Sequence(_) | Value::Mapping(_) => {
+            value if is_collection(value) => {
                 // For collections, emit leading comments first
                 if let Some(comments) = comments {
                     self.emit_leading_comments(&comments.leading, writer)?;
+                    self.emitted_comments
+                        .extend(comments.leading.iter().cloned());
+                }
+                if value.is_tagged() {
+                    self.emit_root_tagged(value, Some(commented), writer)?;
+                } else {
+                    self.emit_recorded_collection(value, Some(commented), writer)?;
                 }
-                self.emit_value(&commented.value, writer)?;
 
-                // Emit inner comments for collections
+                // Emit inner comments not already attached to a key
                 if let Some(comments) = comments {
-                    if !comments.inner.is_empty() {
-                        self.emit_inner_comments(&comments.inner, writer)?;
+                    let inner: Vec<String> = comments
+                        .inner
+                        .iter()
+                        .filter(|comment| !self.emitted_comments.contains(*comment))
+                        .cloned()
+                        .collect();
+                    if !inner.is_empty() {
+                        self.emit_inner_comments(&inner, writer)?;
                     }
                 }
 
-                // Trailing comments for collections go on a new line
+                // Trailing comments for collections go on a new line, unless
+                // already written after a key
                 if let Some(comments) = comments {
-                    if let Some(ref trailing) = comments.trailing {
+                    if let Some(trailing) = comments
+                        .trailing
+                        .as_ref()
+                        .filter(|comment| !self.emitted_comments.contains(*comment))
+                    {
                         writeln!(writer)?;
                         self.write_indent(writer)?;
                         writeln!(writer, "# {}", trailing)?;
@@ -735,7 +1955,7 @@ impl BasicEmitter {
                 self.emit_scalar_with_comments_and_style(
                     &commented.value,
                     comments,
-                    quote_style,
+                    Some(&commented.style),
                     writer,
                 )?;
             }
@@ -744,14 +1964,393 @@ impl BasicEmitter {
         Ok(())
     }
 
+    /// Emit a block mapping, re-attaching the comments and quote styles
+    /// recorded for its keys
+    fn emit_mapping_with_keys<W: Write>(
+        &mut self,
+        map: &indexmap::IndexMap<Value, Value>,
+        keys: &indexmap::IndexMap<Value, CommentedValue>,
+        writer: &mut W,
+    ) -> Result<()> {
+        if map.is_empty() {
+            write!(writer, "{{}}")?;
+            return Ok(());
+        }
+
+        for (index, (key, value)) in map.iter().enumerate() {
+            if index > 0 {
+                writeln!(writer)?;
+            }
+            let entry = keys.get(key);
+
+            if let Some(entry) = entry {
+                if index > 0 {
+                    self.emit_blank_lines(&entry.comments, writer)?;
+                }
+                let leading: Vec<String> = entry
+                    .comments
+                    .leading
+                    .iter()
+                    .filter(|comment| !self.emitted_comments.contains(*comment))
+                    .cloned()
+                    .collect();
+                self.emit_leading_comments(&leading, writer)?;
+                self.emitted_comments.extend(leading);
+            }
+
+            self.write_indent(writer)?;
+            match key {
+                _ if is_collection(key) => {
+                    write!(writer, "? ")?;
+                    self.emit_flow_node(key, writer)?;
+                    writeln!(writer)?;
+                    self.write_indent(writer)?;
+                }
+                _ => {
+                    // Only the quote style of an entry's style describes the key
+                    let key_style = entry.map(|entry| Style {
+                        quote_style: entry.style.quote_style.clone(),
+                        ..Style::new()
+                    });
+                    self.emit_scalar_with_comments_and_style(
+                        key,
+                        None,
+                        key_style.as_ref(),
+                        writer,
+                    )?;
+                }
+            }
+
+            let trailing = entry.map(|entry| &entry.comments).filter(|comments| {
+                comments
+                    .trailing
+                    .as_ref()
+                    .is_some_and(|comment| !self.emitted_comments.contains(comment))
+            });
+            match value {
+                _ if is_collection(value) => {
+                    let (header, nested) = self.collection_header(value);
+                    write!(writer, ":")?;
+                    if !header.is_empty() {
+                        write!(writer, " {header}")?;
+                    }
+                    if let Some(trailing) = trailing {
+                        self.emit_trailing_comment(trailing, writer)?;
+                        // Keys on the same line share the comment
+                        self.emitted_comments.extend(trailing.trailing.clone());
+                    }
+                    if nested {
+                        writeln!(writer)?;
+                        self.current_indent += self.indent;
+                        self.emit_recorded_collection(value, entry, writer)?;
+                        self.current_indent -= self.indent;
+                    }
+                }
+                _ => {
+                    let value_style = entry.map(|entry| Style {
+                        quote_style: None,
+                        ..entry.style.clone()
+                    });
+                    // A comment after a block scalar belongs on its header line
+                    if let Some((s, block)) = self.block_scalar_for(value, value_style.as_ref()) {
+                        let indicator = self.tagged_indicator(":", value, value_style.as_ref());
+                        self.emit_block_scalar(&indicator, s, block, trailing, writer)?;
+                    } else {
+                        self.emit_entry_scalar(":", value, value_style.as_ref(), writer)?;
+                        if let Some(trailing) = trailing {
+                            self.emit_trailing_comment(trailing, writer)?;
+                        }
+                    }
+                }
+            }
+            self.emitted_comments
+                .extend(trailing.and_then(|comments| comments.trailing.clone()));
+        }
+
+        Ok(())
+    }
+
+    /// Emit a block sequence, reusing the recorded style of items that are
+    /// unchanged
+    fn emit_sequence_with_items<W: Write>(
+        &mut self,
+        seq: &[Value],
+        items: &[CommentedValue],
+        writer: &mut W,
+    ) -> Result<()> {
+        if seq.is_empty() {
+            write!(writer, "[]")?;
+            return Ok(());
+        }
+
+        for (index, item) in seq.iter().enumerate() {
+            if index > 0 {
+                writeln!(writer)?;
+            }
+            let recorded = items.get(index).filter(|recorded| recorded.value == *item);
+            if let Some(recorded) = recorded {
+                if index > 0 {
+                    self.emit_blank_lines(&recorded.comments, writer)?;
+                }
+                let leading: Vec<String> = recorded
+                    .comments
+                    .leading
+                    .iter()
+                    .filter(|comment| !self.emitted_comments.contains(*comment))
+                    .cloned()
+                    .collect();
+                self.emit_leading_comments(&leading, writer)?;
+                self.emitted_comments.extend(leading);
+            }
+
+            let trailing = recorded
+                .map(|recorded| &recorded.comments)
+                .filter(|comments| {
+                    comments
+                        .trailing
+                        .as_ref()
+                        .is_some_and(|comment| !self.emitted_comments.contains(comment))
+                });
+            let style = recorded.map(|recorded| &recorded.style);
+            self.write_indent(writer)?;
+            match item {
+                _ if is_collection(item) => {
+                    let (header, nested) = self.collection_header(item);
+                    write!(writer, "- {header}")?;
+                    if nested {
+                        // The comment after the item's first line is written
+                        // after its first key or item
+                        writeln!(writer)?;
+                        self.current_indent += self.indent;
+                        self.emit_recorded_collection(item, recorded, writer)?;
+                        self.current_indent -= self.indent;
+                        continue;
+                    }
+                    if let Some(trailing) = trailing {
+                        self.emit_trailing_comment(trailing, writer)?;
+                    }
+                }
+                _ => {
+                    // A comment after a block scalar belongs on its header line
+                    if let Some((s, block)) = self.block_scalar_for(item, style) {
+                        let indicator = self.tagged_indicator("-", item, style);
+                        self.emit_block_scalar(&indicator, s, block, trailing, writer)?;
+                    } else {
+                        self.emit_entry_scalar("-", item, style, writer)?;
+                        if let Some(trailing) = trailing {
+                            self.emit_trailing_comment(trailing, writer)?;
+                        }
+                    }
+                }
+            }
+            self.emitted_comments
+                .extend(trailing.and_then(|comments| comments.trailing.clone()));
+        }
+
+        Ok(())
+    }
+
+    /// Emit a nested collection using the keys or items recorded for it
+    fn emit_recorded_collection<W: Write>(
+        &mut self,
+        value: &Value,
+        recorded: Option<&CommentedValue>,
+        writer: &mut W,
+    ) -> Result<()> {
+        match (value, recorded) {
+            // The tag of a tagged collection is already written
+            (Value::Tagged(tagged), recorded) => {
+                self.emit_recorded_collection(&tagged.value, recorded, writer)
+            }
+            // The anchor of a repeated collection is already written
+            _ if self.shared_values.contains_key(value) => self.emit_value_simple(value, writer),
+            (Value::Mapping(map), Some(recorded)) if !recorded.keys.is_empty() => {
+                self.emit_mapping_with_keys(map, &recorded.keys, writer)
+            }
+            (Value::Sequence(seq), Some(recorded)) if !recorded.items.is_empty() => {
+                self.emit_sequence_with_items(seq, &recorded.items, writer)
+            }
+            _ => self.emit_value(value, writer),
+        }
+    }
+
     /// Emit a CommentedValue with comment preservation (public API)
     pub fn emit_commented_value_public<W: Write>(
         &mut self,
         commented: &CommentedValue,
         writer: W,
     ) -> Result<()> {
-        let mut writer = writer;
-        self.emit_commented_value(commented, &mut writer)
+        let prepared = self.prepared(commented);
+        let commented = prepared.as_ref().unwrap_or(commented);
+        let mut writer = ColumnWriter::new(writer, &self.column);
+        self.emitted_comments.clear();
+        // Anchors found for an earlier value are keyed by its nodes
+        self.shared_values.clear();
+        self.check_styles(Some(&commented.style));
+        self.emit_directives(Some(&commented.style), Some(&commented.value), &mut writer)?;
+        self.emit_commented_value(commented, &mut writer)?;
+
+        // The output is left open, but a final block scalar needs its line break
+        let closed = commented.comments.trailing.is_none()
+            && matches!(commented.value, Value::Sequence(_) | Value::Mapping(_))
+            && self.ends_with_block_scalar(&commented.value, Some(commented));
+        if closed || commented.style.explicit_end {
+            writeln!(writer)?;
+        }
+        if commented.style.explicit_end {
+            write!(writer, "...")?;
+        }
+        Ok(())
+    }
+
+    /// Whether the last scalar written for `value` inside a collection is a
+    /// block scalar ending in a line break
+    fn ends_with_block_scalar(&self, value: &Value, recorded: Option<&CommentedValue>) -> bool {
+        match value {
+            Value::Mapping(map) => map.last().is_some_and(|(key, value)| {
+                self.ends_with_block_scalar(value, recorded.and_then(|entry| entry.keys.get(key)))
+            }),
+            Value::Sequence(seq) => seq.last().is_some_and(|item| {
+                let recorded = recorded
+                    .and_then(|recorded| recorded.items.get(seq.len() - 1))
+                    .filter(|recorded| recorded.value == *item);
+                self.ends_with_block_scalar(item, recorded)
+            }),
+            _ => self
+                .block_scalar_for(value, recorded.map(|recorded| &recorded.style))
+                .is_some_and(|(s, _)| s.ends_with('\n')),
+        }
+    }
+
+    /// Emit a shared node graph
+    ///
+    /// Nodes referenced more than once, including through cycles, are
+    /// written once with an anchor and referred to by alias afterwards.
+    pub fn emit_shared<W: Write>(&mut self, node: &SharedNode, mut writer: W) -> Result<()> {
+        self.current_indent = 0;
+        self.anchor_counter = 0;
+        self.anchor_names.clear();
+
+        self.check_styles(None);
+
+        self.emit_directives(None, None, &mut writer)?;
+
+        let mut state = SharedEmitState::default();
+        count_node_references(node, &mut state.references);
+
+        self.emit_shared_node(node, &mut state, false, &mut writer)?;
+        writeln!(writer)?;
+        Ok(())
+    }
+
+    /// Emit one node of a shared graph; `nested` nodes follow a `-` or `key:`
+    fn emit_shared_node<W: Write>(
+        &mut self,
+        node: &SharedNode,
+        state: &mut SharedEmitState,
+        nested: bool,
+        writer: &mut W,
+    ) -> Result<()> {
+        let id = node.id();
+        let separator = if nested { " " } else { "" };
+
+        if let Some(name) = state.anchors.get(&id) {
+            write!(writer, "{separator}*{name}")?;
+            return Ok(());
+        }
+
+        let anchor = if state.references.get(&id).copied().unwrap_or(0) > 1 {
+            let name = self.next_anchor_name(&state.path);
+            state.anchors.insert(id, name.clone());
+            Some(name)
+        } else {
+            None
+        };
+
+        let value = node.borrow();
+        let is_block = match &*value {
+            SharedValue::Sequence(seq) => !seq.is_empty(),
+            SharedValue::Mapping(map) => !map.is_empty(),
+            _ => false,
+        };
+
+        if !is_block {
+            if nested
+                && anchor.is_none()
+                && matches!(&*value, SharedValue::Null)
+                && self.null_style == NullStyle::Empty
+            {
+                return Ok(());
+            }
+            write!(writer, "{separator}")?;
+            if let Some(name) = &anchor {
+                write!(writer, "&{name} ")?;
+            }
+            match &*value {
+                SharedValue::Sequence(_) => write!(writer, "[]")?,
+                SharedValue::Mapping(_) => write!(writer, "{{}}")?,
+                scalar => self.emit_scalar(&scalar.to_value(), writer)?,
+            }
+            return Ok(());
+        }
+
+        if let Some(name) = &anchor {
+            write!(writer, "{separator}&{name}")?;
+        }
+        if nested || anchor.is_some() {
+            writeln!(writer)?;
+        }
+        if nested {
+            self.current_indent += self.indent;
+        }
+
+        match &*value {
+            SharedValue::Sequence(seq) => {
+                for (index, item) in seq.iter().enumerate() {
+                    if index > 0 {
+                        writeln!(writer)?;
+                    }
+                    self.write_indent(writer)?;
+                    write!(writer, "-")?;
+                    state.path.push(PathSegment::Index(index));
+                    self.emit_shared_node(item, state, true, writer)?;
+                    state.path.pop();
+                }
+            }
+            SharedValue::Mapping(map) => {
+                for (index, (key, item)) in map.iter().enumerate() {
+                    if index > 0 {
+                        writeln!(writer)?;
+                    }
+                    self.write_indent(writer)?;
+                    match key {
+                        Value::Mapping(key_map) => {
+                            write!(writer, "? ")?;
+                            self.emit_mapping_flow_style(key_map, writer)?;
+                            writeln!(writer)?;
+                            self.write_indent(writer)?;
+                        }
+                        Value::Sequence(key_seq) => {
+                            write!(writer, "? ")?;
+                            self.emit_sequence_flow_style(key_seq, writer)?;
+                            writeln!(writer)?;
+                            self.write_indent(writer)?;
+                        }
+                        _ => self.emit_scalar(key, writer)?,
+                    }
+                    write!(writer, ":")?;
+                    state.path.push(PathSegment::Key(key.clone()));
+                    self.emit_shared_node(item, state, true, writer)?;
+                    state.path.pop();
+                }
+            }
+            _ => {}
+        }
+
+        if nested {
+            self.current_indent -= self.indent;
+        }
+        Ok(())
     }
 }
 
@@ -763,36 +2362,66 @@ impl Default for BasicEmitter {
 
 impl Emitter for BasicEmitter {
     fn emit<W: Write>(&mut self, value: &Value, mut writer: W) -> Result<()> {
+        if self.schema.is_some() {
+            return self.emit_commented(&CommentedValue::new(value.clone()), writer);
+        }
+        let redacted = self.redaction.as_ref().map(|rules| redact(value, rules));
+        let value = redacted.as_ref().unwrap_or(value);
+
         // Reset state
         self.current_indent = 0;
         self.shared_values.clear();
         self.anchor_counter = 0;
+        self.anchor_names.clear();
+        self.path = Path::new();
 
         // Emit directives if any
-        self.emit_directives(&mut writer)?;
+        self.check_styles(None);
+        self.emit_directives(None, Some(value), &mut writer)?;
 
         // Analyze for shared values first
         self.analyze_shared_values(value);
 
-        // For top-level sequences, add a leading newline for proper formatting
-        if matches!(value, Value::Sequence(_)) {
-            writeln!(writer)?;
-        }
+        let flow = self.node_flow_style() == FlowStyle::Flow;
+        match value {
+            Value::Mapping(map) if flow => {
+                self.emit_mapping_flow_style(map, &mut writer)?;
+            }
+            Value::Sequence(seq) if flow => {
+                self.emit_sequence_flow_style(seq, &mut writer)?;
+            }
+            Value::Tagged(_) if flow => {
+                self.emit_flow_node(value, &mut writer)?;
+            }
+            _ => {
+                // For top-level sequences, add a leading newline for proper formatting
+                if matches!(value, Value::Sequence(_)) {
+                    writeln!(writer)?;
+                }
 
-        // Emit the value
-        self.emit_value(value, &mut writer)?;
+                // Emit the value
+                self.emit_value(value, &mut writer)?;
+            }
+        }
         writeln!(writer)?; // Add final newline
         Ok(())
     }
 
-    fn emit_commented<W: Write>(&mut self, value: &CommentedValue, mut writer: W) -> Result<()> {
+    fn emit_commented<W: Write>(&mut self, value: &CommentedValue, writer: W) -> Result<()> {
+        let prepared = self.prepared(value);
+        let value = prepared.as_ref().unwrap_or(value);
+        let mut writer = ColumnWriter::new(writer, &self.column);
+
         // Reset state
         self.current_indent = 0;
         self.shared_values.clear();
         self.anchor_counter = 0;
+        self.anchor_names.clear();
+        self.emitted_comments.clear();
 
         // Emit directives if any
-        self.emit_directives(&mut writer)?;
+        self.check_styles(Some(&value.style));
+        self.emit_directives(Some(&value.style), Some(&value.value), &mut writer)?;
 
         // Analyze for shared values first
         self.analyze_shared_values(&value.value);
@@ -807,8 +2436,12 @@ impl Emitter for BasicEmitter {
         &mut self,
         value: &CommentedValue,
         indent_style: &IndentStyle,
-        mut writer: W,
+        writer: W,
     ) -> Result<()> {
+        let prepared = self.prepared(value);
+        let value = prepared.as_ref().unwrap_or(value);
+        let mut writer = ColumnWriter::new(writer, &self.column);
+
         // Store current style and temporarily update
         let original_style = self.indent_style.clone();
         self.set_indent_style(indent_style.clone());
@@ -817,9 +2450,12 @@ impl Emitter for BasicEmitter {
         self.current_indent = 0;
         self.shared_values.clear();
         self.anchor_counter = 0;
+        self.anchor_names.clear();
+        self.emitted_comments.clear();
 
         // Emit directives if any
-        self.emit_directives(&mut writer)?;
+        self.check_styles(Some(&value.style));
+        self.emit_directives(Some(&value.style), Some(&value.value), &mut writer)?;
 
         // Analyze for shared values first
         self.analyze_shared_values(&value.value);
@@ -840,6 +2476,7 @@ impl Emitter for BasicEmitter {
         self.current_indent = 0;
         self.shared_values.clear();
         self.anchor_counter = 0;
+        self.anchor_names.clear();
         // Note: We don't reset directives here as they might need to persist
     }
 }
@@ -880,6 +2517,124 @@ mod tests {
         assert_eq!(String::from_utf8(output).unwrap(), "\"true\"\n");
     }
 
+    #[test]
+    fn test_emit_quotes_type_ambiguous_strings() {
+        let emitter = BasicEmitter::new();
+        for s in [
+            "true", "Yes", "OFF", "null", "NULL", "~", "123", "-5", "+7", "1e3", "0.5", ".inf",
+            "-.Inf", ".NaN", "nan", "*alias", "&anchor", "!tag", "|", ">", "%x", "@x", "`x", "? x",
+            ", x", "...", "0x1F",
+        ] {
+            assert!(emitter.needs_quoting(s), "{s:?} should be quoted");
+        }
+        for s in ["hello", "hello world", "yes please", "dot.ted"] {
+            assert!(!emitter.needs_quoting(s), "{s:?} should stay plain");
+        }
+    }
+
+    #[test]
+    fn test_emit_null_styles() {
+        let mut map = IndexMap::new();
+        map.insert(Value::from("key"), Value::Null);
+        map.insert(
+            Value::from("list"),
+            Value::Sequence(vec![Value::Null, Value::Int(1)]),
+        );
+        let value = Value::Mapping(map);
+
+        let emit = |null_style| {
+            let mut emitter = BasicEmitter::new();
+            emitter.set_null_style(null_style);
+            let mut output = Vec::new();
+            emitter.emit(&value, &mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        assert_eq!(
+            emit(NullStyle::Null),
+            "key: null\nlist: \n  - null\n  - 1\n"
+        );
+        assert_eq!(emit(NullStyle::Tilde), "key: ~\nlist: \n  - ~\n  - 1\n");
+        assert_eq!(emit(NullStyle::Empty), "key:\nlist: \n  -\n  - 1\n");
+
+        // An empty document is not a null node, so `Empty` falls back to `null`
+        let mut emitter = BasicEmitter::new();
+        emitter.set_null_style(NullStyle::Empty);
+        let mut output = Vec::new();
+        emitter.emit(&Value::Null, &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "null\n");
+    }
+
+    #[test]
+    fn test_emit_bool_styles() {
+        let value = Value::Sequence(vec![Value::Bool(true), Value::Bool(false)]);
+        let emit = |emitter: &mut BasicEmitter| {
+            let mut output = Vec::new();
+            emitter.emit(&value, &mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        let mut emitter = BasicEmitter::new();
+        emitter.set_bool_style(BoolStyle::YesNo);
+        assert_eq!(emit(&mut emitter), "\n- yes\n- no\n");
+        assert!(emitter.warnings().is_empty());
+
+        emitter.set_bool_style(BoolStyle::OnOff);
+        emitter.set_yaml_version(1, 2);
+        assert!(emit(&mut emitter).ends_with("- on\n- off\n"));
+        assert_eq!(emitter.warnings().len(), 1);
+        assert!(emitter.warnings()[0].contains("on/off"));
+
+        emitter.set_bool_style(BoolStyle::TrueFalse);
+        assert!(emit(&mut emitter).ends_with("- true\n- false\n"));
+        assert!(emitter.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_emit_flow_styles() {
+        let value = Value::mapping_with(vec![
+            (
+                Value::from("ports"),
+                Value::Sequence(vec![Value::Int(80), Value::Int(443)]),
+            ),
+            (
+                Value::from("env"),
+                Value::mapping_with(vec![(Value::from("debug"), Value::Bool(true))]),
+            ),
+            (
+                Value::from("nested"),
+                Value::Sequence(vec![Value::Sequence(vec![Value::Int(1)])]),
+            ),
+        ]);
+        let emit = |emitter: &mut BasicEmitter| {
+            let mut output = Vec::new();
+            emitter.emit(&value, &mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        let mut emitter = BasicEmitter::new();
+        assert!(emit(&mut emitter).starts_with("ports: \n  - 80\n  - 443\n"));
+
+        emitter.set_flow_style(FlowStyle::Auto);
+        assert_eq!(
+            emit(&mut emitter),
+            "ports: [80, 443]\nenv: {debug: true}\nnested: \n  - [1]\n"
+        );
+
+        // Leaf collections that would overflow the width stay in block style
+        emitter.set_width(Some(12));
+        assert_eq!(
+            emit(&mut emitter),
+            "ports: \n  - 80\n  - 443\nenv: \n  debug: true\nnested: \n  - [1]\n"
+        );
+
+        emitter.set_flow_style(FlowStyle::Flow);
+        assert_eq!(
+            emit(&mut emitter),
+            "{ports: [80, 443], env: {debug: true}, nested: [[1]]}\n"
+        );
+    }
+
     #[test]
     fn test_emit_sequence() {
         let mut emitter = BasicEmitter::new();
@@ -931,4 +2686,276 @@ mod tests {
         assert!(result.contains("- 1"));
         assert!(result.contains("- 2"));
     }
+
+    #[test]
+    fn test_emit_alias_threshold() {
+        let inner = Value::Sequence(vec![Value::Int(1), Value::Int(2)]);
+        let outer = Value::mapping_with(vec![(Value::from("items"), inner.clone())]);
+        let value = Value::mapping_with(vec![
+            (Value::from("a"), outer.clone()),
+            (Value::from("b"), outer),
+            (Value::from("c"), inner),
+            (Value::from("d"), Value::Sequence(vec![])),
+            (Value::from("e"), Value::Sequence(vec![])),
+        ]);
+        let emit = |emitter: &mut BasicEmitter| {
+            let mut output = Vec::new();
+            emitter.emit(&value, &mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        // The sequence repeated inside the repeated mapping is only counted
+        // once, and empty collections are below the threshold
+        let mut emitter = BasicEmitter::new();
+        assert_eq!(
+            emit(&mut emitter),
+            "a: &anchor0\n  items: &anchor1\n    - 1\n    - 2\nb: *anchor0\nc: *anchor1\nd: []\ne: []\n"
+        );
+
+        emitter.set_alias_threshold(Some(6));
+        assert!(!emit(&mut emitter).contains('&'));
+
+        emitter.set_alias_threshold(Some(1));
+        assert!(emit(&mut emitter).contains("d: &anchor2 []\ne: *anchor2"));
+
+        emitter.set_alias_threshold(None);
+        assert!(!emit(&mut emitter).contains('&'));
+    }
+
+    #[test]
+    fn test_emit_anchor_namer() {
+        /// Names every anchor `my anchor`, leaving the emitter to make the
+        /// names valid and unique
+        #[derive(Debug)]
+        struct SameName;
+
+        impl AnchorNamer for SameName {
+            fn anchor_name(&self, _path: &Path, _index: usize) -> Option<String> {
+                Some("my anchor".to_string())
+            }
+        }
+
+        let ports = Value::Sequence(vec![Value::Int(80), Value::Int(443)]);
+        let env = Value::mapping_with(vec![(Value::from("LEVEL"), Value::from("info"))]);
+        let value = Value::mapping_with(vec![
+            (
+                Value::from("defaults"),
+                Value::mapping_with(vec![
+                    (Value::from("ports"), ports.clone()),
+                    (Value::from("env"), env.clone()),
+                ]),
+            ),
+            (Value::from("web"), ports),
+            (Value::from("jobs"), Value::Sequence(vec![env])),
+        ]);
+        let emit = |emitter: &mut BasicEmitter| {
+            let mut output = Vec::new();
+            emitter.emit(&value, &mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        let mut emitter = BasicEmitter::new();
+        emitter.set_anchor_namer(Some(Arc::new(PathAnchorNamer)));
+        let output = emit(&mut emitter);
+        assert!(output.contains("ports: &defaults_ports\n"));
+        assert!(output.contains("web: *defaults_ports\n"));
+        assert!(output.contains("- *defaults_env\n"));
+        assert_eq!(emit(&mut emitter), output);
+
+        emitter.set_anchor_namer(Some(Arc::new(SameName)));
+        let output = emit(&mut emitter);
+        assert!(output.contains("ports: &my_anchor\n"));
+        assert!(output.contains("env: &my_anchor_2\n"));
+    }
+
+    #[test]
+    fn test_emit_shared_reintroduces_anchors() {
+        let shared = SharedNode::new(SharedValue::Sequence(vec![SharedNode::new(
+            SharedValue::Int(1),
+        )]));
+        let mut map = IndexMap::new();
+        map.insert(Value::String("first".to_string()), shared.clone());
+        map.insert(Value::String("second".to_string()), shared);
+        let root = SharedNode::new(SharedValue::Mapping(map));
+
+        let mut emitter = BasicEmitter::new();
+        let mut output = Vec::new();
+        emitter.emit_shared(&root, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "first: &anchor0\n  - 1\nsecond: *anchor0\n"
+        );
+    }
+
+    #[test]
+    fn test_emit_shared_cycle() {
+        let node = SharedNode::new(SharedValue::Mapping(IndexMap::new()));
+        if let SharedValue::Mapping(map) = &mut *node.borrow_mut() {
+            map.insert(
+                Value::String("name".to_string()),
+                SharedNode::new(SharedValue::Int(1)),
+            );
+            map.insert(Value::String("self".to_string()), node.clone());
+        }
+
+        let mut emitter = BasicEmitter::new();
+        let mut output = Vec::new();
+        emitter.emit_shared(&node, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "&anchor0\nname: 1\nself: *anchor0\n"
+        );
+
+        emitter.set_anchor_namer(Some(Arc::new(PathAnchorNamer)));
+        let mut output = Vec::new();
+        emitter.emit_shared(&node, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "&root\nname: 1\nself: *root\n"
+        );
+    }
+
+    #[test]
+    fn test_emit_commented_directives() {
+        let mut commented = CommentedValue::new(Value::String("value".to_string()));
+        commented.style.yaml_version = Some((1, 2));
+        commented
+            .style
+            .tag_directives
+            .push(("!e!".to_string(), "tag:example.com,2024:".to_string()));
+
+        let mut emitter = BasicEmitter::new();
+        let mut output = Vec::new();
+        emitter.emit_commented(&commented, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "%YAML 1.2\n%TAG !e! tag:example.com,2024:\n---\nvalue\n"
+        );
+
+        // Tags under a recorded prefix are written with its handle
+        commented.value = Value::tagged("tag:example.com,2024:widget", Value::from("w"));
+        let mut output = Vec::new();
+        emitter.emit_commented(&commented, &mut output).unwrap();
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .ends_with("---\n!e!widget w\n"));
+    }
+
+    #[test]
+    fn test_emit_block_scalar_indicators() {
+        let emit = |s: &str, block: BlockScalarStyle| {
+            let mut entry = CommentedValue::new(Value::from("key"));
+            entry.style.block_scalar = Some(block);
+            let mut commented = CommentedValue::new(Value::mapping_with(vec![(
+                Value::from("key"),
+                Value::from(s),
+            )]));
+            commented.keys.insert(Value::from("key"), entry);
+
+            let mut output = Vec::new();
+            BasicEmitter::new()
+                .emit_commented(&commented, &mut output)
+                .unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        let literal = BlockScalarStyle::literal();
+        assert_eq!(emit("a\nb\n", literal), "key: |\n  a\n  b\n");
+        assert_eq!(emit("a\nb", literal), "key: |-\n  a\n  b\n");
+        assert_eq!(emit("a\n\n", literal), "key: |+\n  a\n\n");
+        assert_eq!(emit("  a\nb\n", literal), "key: |2\n    a\n  b\n");
+
+        // Recorded indicators are kept while they fit the string
+        let recorded = BlockScalarStyle {
+            indent: Some(4),
+            chomping: Some(Chomping::Keep),
+            ..literal
+        };
+        assert_eq!(emit("a\n", recorded), "key: |4+\n    a\n");
+        assert_eq!(emit("a", recorded), "key: |4-\n    a\n");
+
+        let folded = BlockScalarStyle::folded();
+        assert_eq!(
+            emit("a b\nc\n  d\n", folded),
+            "key: >\n  a b\n\n  c\n    d\n"
+        );
+
+        // Strings a block scalar cannot hold keep their quotes
+        assert_eq!(emit("a\rb\n", literal), "key: \"a\\rb\\n\"\n");
+    }
+
+    #[test]
+    fn test_emit_tag_emission() {
+        let value = Value::mapping_with(vec![
+            (Value::from("n"), Value::Null),
+            (Value::from("b"), Value::Bool(true)),
+            (Value::from("i"), Value::Int(1)),
+            (Value::from("f"), Value::Float(1.5)),
+            (Value::from("s"), Value::from("1")),
+            (Value::from("l"), Value::Sequence(vec![Value::from("x")])),
+        ]);
+        let emit = |emitter: &mut BasicEmitter| {
+            let mut output = Vec::new();
+            emitter.emit(&value, &mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        let mut emitter = BasicEmitter::new();
+        emitter.set_tag_emission(TagEmission::NonDefault);
+        assert_eq!(
+            emit(&mut emitter),
+            "n: null\nb: true\ni: 1\nf: 1.5\ns: !!str \"1\"\nl: \n  - x\n"
+        );
+
+        emitter.set_tag_emission(TagEmission::Always);
+        assert_eq!(
+            emit(&mut emitter),
+            "!!str n: !!null null\n!!str b: !!bool true\n!!str i: !!int 1\n\
+             !!str f: !!float 1.5\n!!str s: !!str \"1\"\n!!str l: \n  - !!str x\n"
+        );
+    }
+
+    #[test]
+    fn test_emit_with_styles() {
+        use crate::parser::ScalarStyle;
+
+        let value = Value::mapping_with(vec![
+            (
+                Value::from("metadata"),
+                Value::mapping_with(vec![(
+                    Value::from("labels"),
+                    Value::mapping_with(vec![(Value::from("app"), Value::from("web"))]),
+                )]),
+            ),
+            (
+                Value::from("args"),
+                Value::Sequence(vec![Value::from("a"), Value::from("b")]),
+            ),
+        ]);
+        let mut styles = StyleMap::new();
+        styles.set("metadata.labels", FlowStyle::Flow).unwrap();
+        styles.set("args[1]", ScalarStyle::SingleQuoted).unwrap();
+
+        let mut emitter = BasicEmitter::new();
+        emitter.set_styles(styles);
+        let mut output = Vec::new();
+        emitter.emit(&value, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "metadata: \n  labels: {app: web}\nargs: \n  - a\n  - 'b'\n"
+        );
+
+        // A block hint keeps a collection out of the automatic flow style
+        let mut styles = StyleMap::new();
+        styles.set("args", FlowStyle::Block).unwrap();
+        let mut emitter = BasicEmitter::new();
+        emitter.set_flow_style(FlowStyle::Auto);
+        emitter.set_styles(styles);
+        let mut output = Vec::new();
+        emitter.emit(&value, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "metadata: \n  labels: {app: web}\nargs: \n  - a\n  - b\n"
+        );
+    }
 }
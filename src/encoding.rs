@@ -0,0 +1,275 @@
+//! Character encoding detection for byte input
+//!
+//! YAML streams may be UTF-8, UTF-16 or UTF-32. [`decode`] detects the
+//! encoding the way section 5.2 of the YAML 1.2 specification describes,
+//! from a byte order mark or, without one, from the pattern of null bytes
+//! around the first character, which the spec requires to be ASCII:
+//!
+//! ```
+//! use rust_yaml::encoding::{decode, Encoding};
+//!
+//! let bytes: Vec<u8> = [0xFF, 0xFE]
+//!     .into_iter()
+//!     .chain("a: 1\n".encode_utf16().flat_map(u16::to_le_bytes))
+//!     .collect();
+//! let decoded = decode(&bytes).unwrap();
+//! assert_eq!(decoded.encoding, Encoding::Utf16Le);
+//! assert!(decoded.bom);
+//! assert_eq!(decoded.text, "a: 1\n");
+//! ```
+
+use crate::{Error, Position, Result};
+use std::fmt;
+
+/// A Unicode encoding a YAML stream can use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Encoding {
+    /// UTF-8
+    #[default]
+    Utf8,
+    /// UTF-16, little endian
+    Utf16Le,
+    /// UTF-16, big endian
+    Utf16Be,
+    /// UTF-32, little endian
+    Utf32Le,
+    /// UTF-32, big endian
+    Utf32Be,
+}
+
+impl Encoding {
+    /// The number of bytes in one code unit
+    pub const fn unit_width(self) -> usize {
+        match self {
+            Self::Utf8 => 1,
+            Self::Utf16Le | Self::Utf16Be => 2,
+            Self::Utf32Le | Self::Utf32Be => 4,
+        }
+    }
+
+    /// The byte order mark of this encoding
+    pub const fn bom(self) -> &'static [u8] {
+        match self {
+            Self::Utf8 => &[0xEF, 0xBB, 0xBF],
+            Self::Utf16Le => &[0xFF, 0xFE],
+            Self::Utf16Be => &[0xFE, 0xFF],
+            Self::Utf32Le => &[0xFF, 0xFE, 0x00, 0x00],
+            Self::Utf32Be => &[0x00, 0x00, 0xFE, 0xFF],
+        }
+    }
+
+    /// Encode `text` in this encoding, without a byte order mark
+    pub fn encode(self, text: &str) -> Vec<u8> {
+        match self {
+            Self::Utf8 => text.as_bytes().to_vec(),
+            Self::Utf16Le => text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+            Self::Utf16Be => text.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+            Self::Utf32Le => text
+                .chars()
+                .flat_map(|c| u32::from(c).to_le_bytes())
+                .collect(),
+            Self::Utf32Be => text
+                .chars()
+                .flat_map(|c| u32::from(c).to_be_bytes())
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Utf8 => "UTF-8",
+            Self::Utf16Le => "UTF-16LE",
+            Self::Utf16Be => "UTF-16BE",
+            Self::Utf32Le => "UTF-32LE",
+            Self::Utf32Be => "UTF-32BE",
+        })
+    }
+}
+
+/// Detect the encoding of `bytes`, returning it with the length of its byte
+/// order mark (0 when there is none)
+pub fn detect(bytes: &[u8]) -> (Encoding, usize) {
+    // The UTF-32LE mark starts with the UTF-16LE one, so it is tried first
+    for encoding in [
+        Encoding::Utf32Be,
+        Encoding::Utf32Le,
+        Encoding::Utf16Be,
+        Encoding::Utf16Le,
+        Encoding::Utf8,
+    ] {
+        if bytes.starts_with(encoding.bom()) {
+            return (encoding, encoding.bom().len());
+        }
+    }
+    let encoding = match bytes {
+        [0, 0, 0, _, ..] => Encoding::Utf32Be,
+        [_, 0, 0, 0, ..] => Encoding::Utf32Le,
+        [0, _, ..] => Encoding::Utf16Be,
+        [_, 0, ..] => Encoding::Utf16Le,
+        _ => Encoding::Utf8,
+    };
+    (encoding, 0)
+}
+
+/// Text decoded from bytes, remembering how it was encoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded {
+    /// The decoded text, without the byte order mark
+    pub text: String,
+    /// The encoding the bytes used
+    pub encoding: Encoding,
+    /// Whether the bytes began with a byte order mark
+    pub bom: bool,
+}
+
+impl Decoded {
+    /// Translate a position in [`Decoded::text`] into the original bytes
+    ///
+    /// Lines and columns count characters, so they are the same in every
+    /// encoding; only the byte index changes.
+    pub fn original_position(&self, position: Position) -> Position {
+        let mut index = position.index.min(self.text.len());
+        while !self.text.is_char_boundary(index) {
+            index -= 1;
+        }
+        let before = &self.text[..index];
+        let units = match self.encoding {
+            Encoding::Utf8 => before.len(),
+            Encoding::Utf16Le | Encoding::Utf16Be => before.encode_utf16().count(),
+            Encoding::Utf32Le | Encoding::Utf32Be => before.chars().count(),
+        };
+        let bom = if self.bom {
+            self.encoding.bom().len()
+        } else {
+            0
+        };
+        Position::at(
+            position.line,
+            position.column,
+            bom + units * self.encoding.unit_width(),
+        )
+    }
+}
+
+/// Detect the encoding of `bytes` and decode them
+///
+/// Invalid sequences are reported as [`Error::Utf8`] with the byte offset of
+/// the offending code unit.
+pub fn decode(bytes: &[u8]) -> Result<Decoded> {
+    let (encoding, bom_len) = detect(bytes);
+    let body = &bytes[bom_len..];
+    let invalid = |offset: usize| Error::Utf8 {
+        message: format!("invalid {encoding} sequence at byte {}", bom_len + offset),
+    };
+
+    let trailing = body.len() % encoding.unit_width();
+    if trailing != 0 {
+        return Err(invalid(body.len() - trailing));
+    }
+    let text = match encoding {
+        Encoding::Utf8 => match std::str::from_utf8(body) {
+            Ok(text) => text.to_string(),
+            Err(error) => return Err(invalid(error.valid_up_to())),
+        },
+        Encoding::Utf16Le | Encoding::Utf16Be => {
+            let units = body.chunks_exact(2).map(|pair| {
+                let pair = [pair[0], pair[1]];
+                if encoding == Encoding::Utf16Le {
+                    u16::from_le_bytes(pair)
+                } else {
+                    u16::from_be_bytes(pair)
+                }
+            });
+            let mut text = String::with_capacity(body.len() / 2);
+            let mut offset = 0;
+            for ch in char::decode_utf16(units) {
+                let ch = ch.map_err(|_| invalid(offset))?;
+                offset += ch.len_utf16() * 2;
+                text.push(ch);
+            }
+            text
+        }
+        Encoding::Utf32Le | Encoding::Utf32Be => body
+            .chunks_exact(4)
+            .enumerate()
+            .map(|(i, quad)| {
+                let quad = [quad[0], quad[1], quad[2], quad[3]];
+                let code = if encoding == Encoding::Utf32Le {
+                    u32::from_le_bytes(quad)
+                } else {
+                    u32::from_be_bytes(quad)
+                };
+                char::from_u32(code).ok_or_else(|| invalid(i * 4))
+            })
+            .collect::<Result<String>>()?,
+    };
+
+    Ok(Decoded {
+        text,
+        encoding,
+        bom: bom_len > 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_bom(encoding: Encoding, text: &str) -> Vec<u8> {
+        let mut bytes = encoding.bom().to_vec();
+        bytes.extend(encoding.encode(text));
+        bytes
+    }
+
+    #[test]
+    fn test_detect_with_and_without_bom() {
+        for encoding in [
+            Encoding::Utf8,
+            Encoding::Utf16Le,
+            Encoding::Utf16Be,
+            Encoding::Utf32Le,
+            Encoding::Utf32Be,
+        ] {
+            let text = "key: välue ✓\n";
+            let decoded = decode(&with_bom(encoding, text)).unwrap();
+            assert_eq!(
+                (decoded.encoding, decoded.bom, decoded.text.as_str()),
+                (encoding, true, text)
+            );
+
+            let decoded = decode(&encoding.encode(text)).unwrap();
+            assert_eq!(
+                (decoded.encoding, decoded.bom, decoded.text.as_str()),
+                (encoding, false, text)
+            );
+        }
+        assert_eq!(detect(b""), (Encoding::Utf8, 0));
+    }
+
+    #[test]
+    fn test_invalid_sequences() {
+        // Unpaired high surrogate after "a"
+        let bytes = [0xFF, 0xFE, b'a', 0, 0x00, 0xD8, b'b', 0];
+        let error = decode(&bytes).unwrap_err();
+        assert!(error.to_string().contains("UTF-16LE sequence at byte 4"));
+
+        assert!(decode(&[b'a', 0, b'b']).is_err());
+        assert!(decode(&[0, 0, 0, b'a', 0, 0x11, 0, 0]).is_err());
+        assert!(decode(&[b'a', 0xFF]).is_err());
+    }
+
+    #[test]
+    fn test_original_position() {
+        let decoded = decode(&with_bom(Encoding::Utf16Be, "é: 1\nb")).unwrap();
+        let position = Position::at(2, 1, "é: 1\n".len());
+        assert_eq!(decoded.original_position(position), Position::at(2, 1, 12));
+
+        let decoded = decode(&Encoding::Utf32Le.encode("ab")).unwrap();
+        assert_eq!(
+            decoded.original_position(Position::at(1, 2, 1)),
+            Position::at(1, 2, 4)
+        );
+    }
+}
@@ -470,6 +470,101 @@ impl Error {
         }
     }
 
+    /// Rewrite every position carried by this error, including those of
+    /// nested errors
+    pub(crate) fn map_positions(self, f: &impl Fn(Position) -> Position) -> Self {
+        match self {
+            Self::Parse {
+                position,
+                message,
+                context,
+            } => Self::Parse {
+                position: f(position),
+                message,
+                context,
+            },
+            Self::Scan {
+                position,
+                message,
+                context,
+            } => Self::Scan {
+                position: f(position),
+                message,
+                context,
+            },
+            Self::Construction {
+                position,
+                message,
+                context,
+            } => Self::Construction {
+                position: f(position),
+                message,
+                context,
+            },
+            Self::Type {
+                expected,
+                found,
+                position,
+                context,
+            } => Self::Type {
+                expected,
+                found,
+                position: f(position),
+                context,
+            },
+            Self::Value {
+                position,
+                message,
+                context,
+            } => Self::Value {
+                position: f(position),
+                message,
+                context,
+            },
+            Self::Indentation {
+                position,
+                expected,
+                found,
+                context,
+            } => Self::Indentation {
+                position: f(position),
+                expected,
+                found,
+                context,
+            },
+            Self::InvalidCharacter {
+                position,
+                character,
+                context_description,
+                context,
+            } => Self::InvalidCharacter {
+                position: f(position),
+                character,
+                context_description,
+                context,
+            },
+            Self::UnclosedDelimiter {
+                start_position,
+                current_position,
+                delimiter_type,
+                context,
+            } => Self::UnclosedDelimiter {
+                start_position: f(start_position),
+                current_position: f(current_position),
+                delimiter_type,
+                context,
+            },
+            Self::Multiple { errors, message } => Self::Multiple {
+                errors: errors
+                    .into_iter()
+                    .map(|error| error.map_positions(f))
+                    .collect(),
+                message,
+            },
+            other => other,
+        }
+    }
+
     /// Get the context associated with this error, if any
     pub const fn context(&self) -> Option<&ErrorContext> {
         match self {
@@ -557,111 +652,66 @@ impl Error {
     }
 }
 
-impl fmt::Display for Error {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl Error {
+    /// The one-line description of this error, without its position or
+    /// source context
+    pub fn summary(&self) -> String {
         match self {
-            Self::Parse {
-                position,
-                message,
-                context,
-            } => self.format_with_context(f, position, message, context.as_ref()),
-            Self::Scan {
-                position,
-                message,
-                context,
-            } => self.format_with_context(
-                f,
-                position,
-                &format!("Scan error: {}", message),
-                context.as_ref(),
-            ),
-            Self::Construction {
-                position,
-                message,
-                context,
-            } => self.format_with_context(
-                f,
-                position,
-                &format!("Construction error: {}", message),
-                context.as_ref(),
-            ),
+            Self::Parse { message, .. } => message.clone(),
+            Self::Scan { message, .. } => format!("Scan error: {}", message),
+            Self::Construction { message, .. } => format!("Construction error: {}", message),
             Self::Type {
-                expected,
-                found,
-                position,
-                context,
-            } => {
-                let msg = format!("Type error: expected {}, found {}", expected, found);
-                self.format_with_context(f, position, &msg, context.as_ref())
-            }
-            Self::Value {
-                position,
-                message,
-                context,
-            } => self.format_with_context(
-                f,
-                position,
-                &format!("Value error: {}", message),
-                context.as_ref(),
-            ),
+                expected, found, ..
+            } => format!("Type error: expected {}, found {}", expected, found),
+            Self::Value { message, .. } => format!("Value error: {}", message),
             Self::Indentation {
-                position,
-                expected,
-                found,
-                context,
-            } => {
-                let msg = format!(
-                    "Indentation error: expected {} spaces, found {}",
-                    expected, found
-                );
-                self.format_with_context(f, position, &msg, context.as_ref())
-            }
+                expected, found, ..
+            } => format!(
+                "Indentation error: expected {} spaces, found {}",
+                expected, found
+            ),
             Self::InvalidCharacter {
-                position,
                 character,
                 context_description,
-                context,
-            } => {
-                let msg = format!(
-                    "Invalid character '{}' in {}",
-                    character, context_description
-                );
-                self.format_with_context(f, position, &msg, context.as_ref())
-            }
+                ..
+            } => format!(
+                "Invalid character '{}' in {}",
+                character, context_description
+            ),
             Self::UnclosedDelimiter {
                 start_position,
-                current_position,
                 delimiter_type,
-                context,
-            } => {
-                let msg = format!(
-                    "Unclosed {} starting at line {}, column {}",
-                    delimiter_type, start_position.line, start_position.column
-                );
-                self.format_with_context(f, current_position, &msg, context.as_ref())
-            }
-            Self::Multiple { errors, message } => {
-                writeln!(f, "Multiple errors: {}", message)?;
+                ..
+            } => format!(
+                "Unclosed {} starting at line {}, column {}",
+                delimiter_type, start_position.line, start_position.column
+            ),
+            Self::Multiple { message, .. } => format!("Multiple errors: {}", message),
+            Self::Emission { message } => format!("Emission error: {}", message),
+            Self::Io { kind, message } => format!("IO error ({:?}): {}", kind, message),
+            Self::Utf8 { message } => format!("UTF-8 error: {}", message),
+            Self::Config { message } => format!("Configuration error: {}", message),
+            Self::LimitExceeded { message } => format!("Resource limit exceeded: {}", message),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Multiple { errors, .. } => {
+                writeln!(f, "{}", self.summary())?;
                 for (i, error) in errors.iter().enumerate() {
                     writeln!(f, "  {}. {}", i + 1, error)?;
                 }
                 Ok(())
             }
-            Self::Emission { message } => {
-                write!(f, "Emission error: {}", message)
-            }
-            Self::Io { kind, message } => {
-                write!(f, "IO error ({:?}): {}", kind, message)
-            }
-            Self::Utf8 { message } => {
-                write!(f, "UTF-8 error: {}", message)
-            }
-            Self::Config { message } => {
-                write!(f, "Configuration error: {}", message)
-            }
-            Self::LimitExceeded { message } => {
-                write!(f, "Resource limit exceeded: {}", message)
-            }
+            _ => match self.position() {
+                Some(position) => {
+                    self.format_with_context(f, position, &self.summary(), self.context())
+                }
+                None => f.write_str(&self.summary()),
+            },
         }
     }
 }
@@ -0,0 +1,941 @@
+//! C ABI compatible with libyaml's event parser (`ffi` feature)
+//!
+//! Exports the libyaml parsing entry points with the same names, return
+//! conventions (`1` on success, `0` on failure) and event layout, so C and
+//! C++ programs written against `yaml.h` can link against this crate
+//! instead:
+//!
+//! ```text
+//! yaml_parser_t parser;
+//! yaml_event_t event;
+//!
+//! yaml_parser_initialize(&parser);
+//! yaml_parser_set_input_string(&parser, input, length);
+//! do {
+//!     if (!yaml_parser_parse(&parser, &event)) {
+//!         fprintf(stderr, "%s at line %zu\n", parser.problem, parser.problem_mark.line + 1);
+//!         break;
+//!     }
+//!     /* ... */
+//!     done = (event.type == YAML_STREAM_END_EVENT);
+//!     yaml_event_delete(&event);
+//! } while (!done);
+//! yaml_parser_delete(&parser);
+//! ```
+//!
+//! `yaml_parser_t` matches libyaml's layout exactly up to and including
+//! `context_mark`: the error fields (`error`, `problem`, `problem_offset`,
+//! `problem_value`, `problem_mark`, `context`, `context_mark`) have libyaml's
+//! types, order and offsets, so code that reads them works unchanged.
+//! libyaml's private fields after them are replaced by a single `internal`
+//! handle, which makes the struct smaller than libyaml's; memory sized for
+//! libyaml's struct is therefore large enough. Marks are zero-based like
+//! libyaml's. Only the UTF-8 string input source is
+//! supported; file and custom read handlers are not.
+
+// Every export crosses the C boundary and dereferences caller pointers.
+#![allow(unsafe_code)]
+#![allow(non_camel_case_types)]
+
+use crate::parser::{BasicParser, EventType, Parser, ScalarStyle};
+use crate::{Error, Position};
+use std::ffi::{c_char, c_int, c_uchar, c_void, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+/// libyaml character type (UTF-8 code unit)
+pub type yaml_char_t = c_uchar;
+
+/// Input encoding of a stream
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum yaml_encoding_t {
+    /// Let the parser choose
+    YAML_ANY_ENCODING = 0,
+    /// UTF-8
+    YAML_UTF8_ENCODING = 1,
+    /// UTF-16 little endian
+    YAML_UTF16LE_ENCODING = 2,
+    /// UTF-16 big endian
+    YAML_UTF16BE_ENCODING = 3,
+}
+
+/// Error category reported in `yaml_parser_t::error`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum yaml_error_type_t {
+    /// No error
+    YAML_NO_ERROR = 0,
+    /// Allocation failure
+    YAML_MEMORY_ERROR = 1,
+    /// Invalid input bytes
+    YAML_READER_ERROR = 2,
+    /// Tokenization failure
+    YAML_SCANNER_ERROR = 3,
+    /// Grammar failure
+    YAML_PARSER_ERROR = 4,
+    /// Node composition failure
+    YAML_COMPOSER_ERROR = 5,
+    /// Output failure
+    YAML_WRITER_ERROR = 6,
+    /// Emission failure
+    YAML_EMITTER_ERROR = 7,
+}
+
+/// Scalar presentation style
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum yaml_scalar_style_t {
+    /// Let the emitter choose
+    YAML_ANY_SCALAR_STYLE = 0,
+    /// Plain
+    YAML_PLAIN_SCALAR_STYLE = 1,
+    /// Single quoted
+    YAML_SINGLE_QUOTED_SCALAR_STYLE = 2,
+    /// Double quoted
+    YAML_DOUBLE_QUOTED_SCALAR_STYLE = 3,
+    /// Literal block (`|`)
+    YAML_LITERAL_SCALAR_STYLE = 4,
+    /// Folded block (`>`)
+    YAML_FOLDED_SCALAR_STYLE = 5,
+}
+
+/// Sequence presentation style
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum yaml_sequence_style_t {
+    /// Let the emitter choose
+    YAML_ANY_SEQUENCE_STYLE = 0,
+    /// Block sequence
+    YAML_BLOCK_SEQUENCE_STYLE = 1,
+    /// Flow sequence (`[...]`)
+    YAML_FLOW_SEQUENCE_STYLE = 2,
+}
+
+/// Mapping presentation style
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum yaml_mapping_style_t {
+    /// Let the emitter choose
+    YAML_ANY_MAPPING_STYLE = 0,
+    /// Block mapping
+    YAML_BLOCK_MAPPING_STYLE = 1,
+    /// Flow mapping (`{...}`)
+    YAML_FLOW_MAPPING_STYLE = 2,
+}
+
+/// Event kind stored in `yaml_event_t::type_`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum yaml_event_type_t {
+    /// Empty event
+    YAML_NO_EVENT = 0,
+    /// Stream start
+    YAML_STREAM_START_EVENT = 1,
+    /// Stream end
+    YAML_STREAM_END_EVENT = 2,
+    /// Document start
+    YAML_DOCUMENT_START_EVENT = 3,
+    /// Document end
+    YAML_DOCUMENT_END_EVENT = 4,
+    /// Alias
+    YAML_ALIAS_EVENT = 5,
+    /// Scalar
+    YAML_SCALAR_EVENT = 6,
+    /// Sequence start
+    YAML_SEQUENCE_START_EVENT = 7,
+    /// Sequence end
+    YAML_SEQUENCE_END_EVENT = 8,
+    /// Mapping start
+    YAML_MAPPING_START_EVENT = 9,
+    /// Mapping end
+    YAML_MAPPING_END_EVENT = 10,
+}
+
+/// Zero-based location in the input
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct yaml_mark_t {
+    /// Byte offset
+    pub index: usize,
+    /// Line, starting at 0
+    pub line: usize,
+    /// Column, starting at 0
+    pub column: usize,
+}
+
+impl From<Position> for yaml_mark_t {
+    fn from(position: Position) -> Self {
+        Self {
+            index: position.index,
+            line: position.line.saturating_sub(1),
+            column: position.column.saturating_sub(1),
+        }
+    }
+}
+
+/// `%YAML` directive
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct yaml_version_directive_t {
+    /// Major version
+    pub major: c_int,
+    /// Minor version
+    pub minor: c_int,
+}
+
+/// `%TAG` directive
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct yaml_tag_directive_t {
+    /// Tag handle, e.g. `!e!`
+    pub handle: *mut yaml_char_t,
+    /// Tag prefix
+    pub prefix: *mut yaml_char_t,
+}
+
+/// Stream start payload
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct yaml_stream_start_data_t {
+    /// Stream encoding
+    pub encoding: yaml_encoding_t,
+}
+
+/// Half-open range of tag directives
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct yaml_tag_directives_t {
+    /// First directive
+    pub start: *mut yaml_tag_directive_t,
+    /// One past the last directive
+    pub end: *mut yaml_tag_directive_t,
+}
+
+/// Document start payload
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct yaml_document_start_data_t {
+    /// `%YAML` directive, or null
+    pub version_directive: *mut yaml_version_directive_t,
+    /// `%TAG` directives
+    pub tag_directives: yaml_tag_directives_t,
+    /// Whether `---` was omitted
+    pub implicit: c_int,
+}
+
+/// Document end payload
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct yaml_document_end_data_t {
+    /// Whether `...` was omitted
+    pub implicit: c_int,
+}
+
+/// Alias payload
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct yaml_alias_data_t {
+    /// Referenced anchor name
+    pub anchor: *mut yaml_char_t,
+}
+
+/// Scalar payload
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct yaml_scalar_data_t {
+    /// Anchor name, or null
+    pub anchor: *mut yaml_char_t,
+    /// Tag, or null
+    pub tag: *mut yaml_char_t,
+    /// NUL-terminated value
+    pub value: *mut yaml_char_t,
+    /// Value length in bytes, excluding the terminator
+    pub length: usize,
+    /// Whether the tag may be omitted for the plain style
+    pub plain_implicit: c_int,
+    /// Whether the tag may be omitted for quoted styles
+    pub quoted_implicit: c_int,
+    /// Presentation style
+    pub style: yaml_scalar_style_t,
+}
+
+/// Sequence start payload
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct yaml_sequence_start_data_t {
+    /// Anchor name, or null
+    pub anchor: *mut yaml_char_t,
+    /// Tag, or null
+    pub tag: *mut yaml_char_t,
+    /// Whether the tag may be omitted
+    pub implicit: c_int,
+    /// Presentation style
+    pub style: yaml_sequence_style_t,
+}
+
+/// Mapping start payload
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct yaml_mapping_start_data_t {
+    /// Anchor name, or null
+    pub anchor: *mut yaml_char_t,
+    /// Tag, or null
+    pub tag: *mut yaml_char_t,
+    /// Whether the tag may be omitted
+    pub implicit: c_int,
+    /// Presentation style
+    pub style: yaml_mapping_style_t,
+}
+
+/// Per-kind event payload
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union yaml_event_data_t {
+    /// `YAML_STREAM_START_EVENT`
+    pub stream_start: yaml_stream_start_data_t,
+    /// `YAML_DOCUMENT_START_EVENT`
+    pub document_start: yaml_document_start_data_t,
+    /// `YAML_DOCUMENT_END_EVENT`
+    pub document_end: yaml_document_end_data_t,
+    /// `YAML_ALIAS_EVENT`
+    pub alias: yaml_alias_data_t,
+    /// `YAML_SCALAR_EVENT`
+    pub scalar: yaml_scalar_data_t,
+    /// `YAML_SEQUENCE_START_EVENT`
+    pub sequence_start: yaml_sequence_start_data_t,
+    /// `YAML_MAPPING_START_EVENT`
+    pub mapping_start: yaml_mapping_start_data_t,
+}
+
+/// A parsing event, laid out like libyaml's `yaml_event_t`
+#[repr(C)]
+pub struct yaml_event_t {
+    /// Event kind (`type` in C)
+    pub type_: yaml_event_type_t,
+    /// Payload for `type_`
+    pub data: yaml_event_data_t,
+    /// Where the event starts
+    pub start_mark: yaml_mark_t,
+    /// Where the event ends
+    pub end_mark: yaml_mark_t,
+}
+
+/// Parser object; callers allocate it and pass it to `yaml_parser_initialize`
+///
+/// The fields before `internal` match libyaml's `yaml_parser_t`.
+#[repr(C)]
+pub struct yaml_parser_t {
+    /// Error category of the last failure
+    pub error: yaml_error_type_t,
+    /// NUL-terminated description of the last failure, or null
+    pub problem: *const c_char,
+    /// Byte offset of the problem (reader errors)
+    pub problem_offset: usize,
+    /// Offending byte (reader errors), or -1
+    pub problem_value: c_int,
+    /// Location of the problem
+    pub problem_mark: yaml_mark_t,
+    /// NUL-terminated description of the enclosing construct, or null
+    pub context: *const c_char,
+    /// Location of the enclosing construct
+    pub context_mark: yaml_mark_t,
+    /// Opaque parser state owned by this library, in place of libyaml's
+    /// private fields
+    pub internal: *mut c_void,
+}
+
+/// State behind `yaml_parser_t::internal`
+#[derive(Default)]
+struct ParserHandle {
+    input: Option<Vec<u8>>,
+    parser: Option<BasicParser>,
+    problem: Option<CString>,
+    stream_ended: bool,
+}
+
+/// Version string of this implementation
+#[no_mangle]
+pub extern "C" fn yaml_get_version_string() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+/// Version numbers of this implementation
+///
+/// # Safety
+///
+/// Each pointer must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn yaml_get_version(major: *mut c_int, minor: *mut c_int, patch: *mut c_int) {
+    let mut parts = env!("CARGO_PKG_VERSION")
+        .split('.')
+        .map(|part| part.parse::<c_int>().unwrap_or(0));
+    for out in [major, minor, patch] {
+        let part = parts.next().unwrap_or(0);
+        if !out.is_null() {
+            *out = part;
+        }
+    }
+}
+
+/// Initialize a parser; returns `1` on success and `0` on failure
+///
+/// # Safety
+///
+/// `parser` must point to writable memory for a `yaml_parser_t`.
+#[no_mangle]
+pub unsafe extern "C" fn yaml_parser_initialize(parser: *mut yaml_parser_t) -> c_int {
+    if parser.is_null() {
+        return 0;
+    }
+    ptr::write(
+        parser,
+        yaml_parser_t {
+            error: yaml_error_type_t::YAML_NO_ERROR,
+            problem: ptr::null(),
+            problem_offset: 0,
+            problem_value: -1,
+            problem_mark: yaml_mark_t::default(),
+            context: ptr::null(),
+            context_mark: yaml_mark_t::default(),
+            internal: Box::into_raw(Box::<ParserHandle>::default()).cast(),
+        },
+    );
+    1
+}
+
+/// Release everything owned by an initialized parser
+///
+/// # Safety
+///
+/// `parser` must have been initialized with `yaml_parser_initialize` and
+/// not deleted since.
+#[no_mangle]
+pub unsafe extern "C" fn yaml_parser_delete(parser: *mut yaml_parser_t) {
+    if parser.is_null() || (*parser).internal.is_null() {
+        return;
+    }
+    drop(Box::from_raw((*parser).internal.cast::<ParserHandle>()));
+    (*parser).internal = ptr::null_mut();
+    (*parser).problem = ptr::null();
+}
+
+/// Set a UTF-8 input buffer; the bytes are copied
+///
+/// # Safety
+///
+/// `parser` must be initialized and `input` valid for `size` bytes reads.
+#[no_mangle]
+pub unsafe extern "C" fn yaml_parser_set_input_string(
+    parser: *mut yaml_parser_t,
+    input: *const c_uchar,
+    size: usize,
+) {
+    let Some(handle) = handle(parser) else {
+        return;
+    };
+    let bytes = if input.is_null() || size == 0 {
+        Vec::new()
+    } else {
+        std::slice::from_raw_parts(input, size).to_vec()
+    };
+    handle.input = Some(bytes);
+    handle.parser = None;
+    handle.stream_ended = false;
+}
+
+/// Set the input encoding; only UTF-8 is supported
+///
+/// # Safety
+///
+/// `parser` must be initialized.
+#[no_mangle]
+pub unsafe extern "C" fn yaml_parser_set_encoding(
+    parser: *mut yaml_parser_t,
+    encoding: yaml_encoding_t,
+) {
+    if encoding == yaml_encoding_t::YAML_UTF16LE_ENCODING
+        || encoding == yaml_encoding_t::YAML_UTF16BE_ENCODING
+    {
+        fail(
+            parser,
+            yaml_error_type_t::YAML_READER_ERROR,
+            "only UTF-8 input is supported",
+            Position::start(),
+        );
+    }
+}
+
+/// Produce the next event; returns `1` on success and `0` on failure
+///
+/// After `YAML_STREAM_END_EVENT` further calls yield `YAML_NO_EVENT`. On
+/// failure `parser.error`, `parser.problem` and `parser.problem_mark`
+/// describe the problem.
+///
+/// # Safety
+///
+/// `parser` must be initialized and `event` valid for writes. The event
+/// must be released with `yaml_event_delete`.
+#[no_mangle]
+pub unsafe extern "C" fn yaml_parser_parse(
+    parser: *mut yaml_parser_t,
+    event: *mut yaml_event_t,
+) -> c_int {
+    if event.is_null() {
+        return 0;
+    }
+    ptr::write(event, empty_event());
+    if parser.is_null() || (*parser).error != yaml_error_type_t::YAML_NO_ERROR {
+        return 0;
+    }
+    let Some(handle) = handle(parser) else {
+        return 0;
+    };
+    if handle.stream_ended {
+        return 1;
+    }
+
+    if handle.parser.is_none() {
+        let Some(bytes) = handle.input.take() else {
+            return fail(
+                parser,
+                yaml_error_type_t::YAML_READER_ERROR,
+                "no input set",
+                Position::start(),
+            );
+        };
+        match String::from_utf8(bytes) {
+            Ok(input) => handle.parser = Some(BasicParser::new(input)),
+            Err(error) => {
+                let offset = error.utf8_error().valid_up_to();
+                (*parser).problem_offset = offset;
+                (*parser).problem_value = c_int::from(error.as_bytes()[offset]);
+                return fail(
+                    parser,
+                    yaml_error_type_t::YAML_READER_ERROR,
+                    "invalid UTF-8 octet",
+                    Position::start(),
+                );
+            }
+        }
+    }
+
+    let Some(inner) = handle.parser.as_mut() else {
+        return 0;
+    };
+    let next = catch_unwind(AssertUnwindSafe(|| inner.get_event()));
+    match next {
+        Ok(Ok(Some(next))) => {
+            handle.stream_ended = matches!(next.event_type, EventType::StreamEnd);
+            ptr::write(event, to_c_event(next.event_type, next.position.into()));
+            1
+        }
+        Ok(Ok(None)) => {
+            handle.stream_ended = true;
+            1
+        }
+        Ok(Err(error)) => {
+            let kind = match error {
+                Error::Scan { .. } => yaml_error_type_t::YAML_SCANNER_ERROR,
+                Error::Utf8 { .. } | Error::Io { .. } => yaml_error_type_t::YAML_READER_ERROR,
+                _ => yaml_error_type_t::YAML_PARSER_ERROR,
+            };
+            let position = error.position().copied().unwrap_or_default();
+            fail(parser, kind, &error.to_string(), position)
+        }
+        Err(_) => fail(
+            parser,
+            yaml_error_type_t::YAML_PARSER_ERROR,
+            "internal parser error",
+            Position::start(),
+        ),
+    }
+}
+
+/// Release the strings and directives owned by an event
+///
+/// # Safety
+///
+/// `event` must be null or an event filled by `yaml_parser_parse` that has
+/// not been deleted yet.
+#[no_mangle]
+pub unsafe extern "C" fn yaml_event_delete(event: *mut yaml_event_t) {
+    if event.is_null() {
+        return;
+    }
+    let data = (*event).data;
+    match (*event).type_ {
+        yaml_event_type_t::YAML_DOCUMENT_START_EVENT => {
+            let data = data.document_start;
+            if !data.version_directive.is_null() {
+                drop(Box::from_raw(data.version_directive));
+            }
+            let start = data.tag_directives.start;
+            if !start.is_null() {
+                let len = data.tag_directives.end.offset_from(start) as usize;
+                let directives = Box::from_raw(ptr::slice_from_raw_parts_mut(start, len));
+                for directive in directives.iter() {
+                    free_string(directive.handle);
+                    free_string(directive.prefix);
+                }
+            }
+        }
+        yaml_event_type_t::YAML_ALIAS_EVENT => free_string(data.alias.anchor),
+        yaml_event_type_t::YAML_SCALAR_EVENT => {
+            free_string(data.scalar.anchor);
+            free_string(data.scalar.tag);
+            free_value(data.scalar.value, data.scalar.length);
+        }
+        yaml_event_type_t::YAML_SEQUENCE_START_EVENT => {
+            free_string(data.sequence_start.anchor);
+            free_string(data.sequence_start.tag);
+        }
+        yaml_event_type_t::YAML_MAPPING_START_EVENT => {
+            free_string(data.mapping_start.anchor);
+            free_string(data.mapping_start.tag);
+        }
+        _ => {}
+    }
+    ptr::write(event, empty_event());
+}
+
+unsafe fn handle<'a>(parser: *mut yaml_parser_t) -> Option<&'a mut ParserHandle> {
+    if parser.is_null() {
+        return None;
+    }
+    (*parser).internal.cast::<ParserHandle>().as_mut()
+}
+
+/// Record an error on the parser and return libyaml's failure code
+unsafe fn fail(
+    parser: *mut yaml_parser_t,
+    kind: yaml_error_type_t,
+    message: &str,
+    position: Position,
+) -> c_int {
+    let Some(handle) = handle(parser) else {
+        return 0;
+    };
+    let problem = handle
+        .problem
+        .insert(CString::new(message.replace('\0', " ")).unwrap_or_default());
+    (*parser).error = kind;
+    (*parser).problem = problem.as_ptr();
+    (*parser).problem_mark = position.into();
+    0
+}
+
+fn empty_event() -> yaml_event_t {
+    yaml_event_t {
+        type_: yaml_event_type_t::YAML_NO_EVENT,
+        data: yaml_event_data_t {
+            stream_start: yaml_stream_start_data_t {
+                encoding: yaml_encoding_t::YAML_ANY_ENCODING,
+            },
+        },
+        start_mark: yaml_mark_t::default(),
+        end_mark: yaml_mark_t::default(),
+    }
+}
+
+fn to_c_event(event: EventType, mark: yaml_mark_t) -> yaml_event_t {
+    let (type_, data) = match event {
+        EventType::StreamStart => (
+            yaml_event_type_t::YAML_STREAM_START_EVENT,
+            yaml_event_data_t {
+                stream_start: yaml_stream_start_data_t {
+                    encoding: yaml_encoding_t::YAML_UTF8_ENCODING,
+                },
+            },
+        ),
+        EventType::StreamEnd => (yaml_event_type_t::YAML_STREAM_END_EVENT, empty_event().data),
+        EventType::DocumentStart {
+            version,
+            tags,
+            implicit,
+        } => {
+            let version_directive = version.map_or(ptr::null_mut(), |(major, minor)| {
+                Box::into_raw(Box::new(yaml_version_directive_t {
+                    major: c_int::from(major),
+                    minor: c_int::from(minor),
+                }))
+            });
+            let tag_directives = if tags.is_empty() {
+                yaml_tag_directives_t {
+                    start: ptr::null_mut(),
+                    end: ptr::null_mut(),
+                }
+            } else {
+                let directives: Box<[yaml_tag_directive_t]> = tags
+                    .into_iter()
+                    .map(|(handle, prefix)| yaml_tag_directive_t {
+                        handle: into_c_string(handle),
+                        prefix: into_c_string(prefix),
+                    })
+                    .collect();
+                let len = directives.len();
+                let start = Box::into_raw(directives).cast::<yaml_tag_directive_t>();
+                yaml_tag_directives_t {
+                    start,
+                    end: start.wrapping_add(len),
+                }
+            };
+            (
+                yaml_event_type_t::YAML_DOCUMENT_START_EVENT,
+                yaml_event_data_t {
+                    document_start: yaml_document_start_data_t {
+                        version_directive,
+                        tag_directives,
+                        implicit: c_int::from(implicit),
+                    },
+                },
+            )
+        }
+        EventType::DocumentEnd { implicit } => (
+            yaml_event_type_t::YAML_DOCUMENT_END_EVENT,
+            yaml_event_data_t {
+                document_end: yaml_document_end_data_t {
+                    implicit: c_int::from(implicit),
+                },
+            },
+        ),
+        EventType::Alias { anchor } => (
+            yaml_event_type_t::YAML_ALIAS_EVENT,
+            yaml_event_data_t {
+                alias: yaml_alias_data_t {
+                    anchor: into_c_string(anchor),
+                },
+            },
+        ),
+        EventType::Scalar {
+            anchor,
+            tag,
+            value,
+            plain_implicit,
+            quoted_implicit,
+            style,
+        } => {
+            let (value, length) = into_c_value(value);
+            (
+                yaml_event_type_t::YAML_SCALAR_EVENT,
+                yaml_event_data_t {
+                    scalar: yaml_scalar_data_t {
+                        anchor: optional_c_string(anchor),
+                        tag: optional_c_string(tag),
+                        value,
+                        length,
+                        plain_implicit: c_int::from(plain_implicit),
+                        quoted_implicit: c_int::from(quoted_implicit),
+                        style: match style {
+                            ScalarStyle::Plain => yaml_scalar_style_t::YAML_PLAIN_SCALAR_STYLE,
+                            ScalarStyle::SingleQuoted => {
+                                yaml_scalar_style_t::YAML_SINGLE_QUOTED_SCALAR_STYLE
+                            }
+                            ScalarStyle::DoubleQuoted => {
+                                yaml_scalar_style_t::YAML_DOUBLE_QUOTED_SCALAR_STYLE
+                            }
+                            ScalarStyle::Literal => yaml_scalar_style_t::YAML_LITERAL_SCALAR_STYLE,
+                            ScalarStyle::Folded => yaml_scalar_style_t::YAML_FOLDED_SCALAR_STYLE,
+                        },
+                    },
+                },
+            )
+        }
+        EventType::SequenceStart {
+            anchor,
+            tag,
+            flow_style,
+        } => (
+            yaml_event_type_t::YAML_SEQUENCE_START_EVENT,
+            yaml_event_data_t {
+                sequence_start: yaml_sequence_start_data_t {
+                    implicit: c_int::from(tag.is_none()),
+                    anchor: optional_c_string(anchor),
+                    tag: optional_c_string(tag),
+                    style: if flow_style {
+                        yaml_sequence_style_t::YAML_FLOW_SEQUENCE_STYLE
+                    } else {
+                        yaml_sequence_style_t::YAML_BLOCK_SEQUENCE_STYLE
+                    },
+                },
+            },
+        ),
+        EventType::SequenceEnd => (
+            yaml_event_type_t::YAML_SEQUENCE_END_EVENT,
+            empty_event().data,
+        ),
+        EventType::MappingStart {
+            anchor,
+            tag,
+            flow_style,
+        } => (
+            yaml_event_type_t::YAML_MAPPING_START_EVENT,
+            yaml_event_data_t {
+                mapping_start: yaml_mapping_start_data_t {
+                    implicit: c_int::from(tag.is_none()),
+                    anchor: optional_c_string(anchor),
+                    tag: optional_c_string(tag),
+                    style: if flow_style {
+                        yaml_mapping_style_t::YAML_FLOW_MAPPING_STYLE
+                    } else {
+                        yaml_mapping_style_t::YAML_BLOCK_MAPPING_STYLE
+                    },
+                },
+            },
+        ),
+        EventType::MappingEnd => (
+            yaml_event_type_t::YAML_MAPPING_END_EVENT,
+            empty_event().data,
+        ),
+    };
+    yaml_event_t {
+        type_,
+        data,
+        start_mark: mark,
+        end_mark: mark,
+    }
+}
+
+/// Hand a scalar value to C as a NUL-terminated buffer plus its length
+///
+/// Interior NUL bytes (legal in double-quoted scalars) are kept; `length`
+/// covers the whole value, as in libyaml.
+fn into_c_value(value: String) -> (*mut yaml_char_t, usize) {
+    let mut bytes = value.into_bytes();
+    let length = bytes.len();
+    bytes.push(0);
+    (
+        Box::into_raw(bytes.into_boxed_slice()).cast::<yaml_char_t>(),
+        length,
+    )
+}
+
+/// Free a buffer created by `into_c_value`
+unsafe fn free_value(value: *mut yaml_char_t, length: usize) {
+    if !value.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            value,
+            length + 1,
+        )));
+    }
+}
+
+/// Hand a name (anchor, tag, directive) to C as a NUL-terminated string
+fn into_c_string(value: String) -> *mut yaml_char_t {
+    CString::new(value.replace('\0', ""))
+        .unwrap_or_default()
+        .into_raw()
+        .cast()
+}
+
+fn optional_c_string(value: Option<String>) -> *mut yaml_char_t {
+    value.map_or(ptr::null_mut(), into_c_string)
+}
+
+/// Free a string created by `into_c_string`
+unsafe fn free_string(value: *mut yaml_char_t) {
+    if !value.is_null() {
+        drop(CString::from_raw(value.cast()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+    use std::mem::MaybeUninit;
+
+    unsafe fn parse_types(input: &str) -> (Vec<yaml_event_type_t>, Vec<String>) {
+        let mut parser = MaybeUninit::<yaml_parser_t>::uninit();
+        assert_eq!(yaml_parser_initialize(parser.as_mut_ptr()), 1);
+        let parser = parser.as_mut_ptr();
+        yaml_parser_set_input_string(parser, input.as_ptr(), input.len());
+
+        let mut types = Vec::new();
+        let mut scalars = Vec::new();
+        let mut event = MaybeUninit::<yaml_event_t>::uninit();
+        loop {
+            assert_eq!(yaml_parser_parse(parser, event.as_mut_ptr()), 1);
+            let current = &*event.as_ptr();
+            types.push(current.type_);
+            if current.type_ == yaml_event_type_t::YAML_SCALAR_EVENT {
+                let value = CStr::from_ptr(current.data.scalar.value.cast());
+                scalars.push(value.to_str().unwrap().to_string());
+            }
+            let done = current.type_ == yaml_event_type_t::YAML_STREAM_END_EVENT;
+            yaml_event_delete(event.as_mut_ptr());
+            if done {
+                break;
+            }
+        }
+        yaml_parser_delete(parser);
+        (types, scalars)
+    }
+
+    #[test]
+    fn test_event_sequence() {
+        use yaml_event_type_t::*;
+        let (types, scalars) = unsafe { parse_types("key: [1, two]\n") };
+        assert_eq!(
+            types,
+            vec![
+                YAML_STREAM_START_EVENT,
+                YAML_DOCUMENT_START_EVENT,
+                YAML_MAPPING_START_EVENT,
+                YAML_SCALAR_EVENT,
+                YAML_SEQUENCE_START_EVENT,
+                YAML_SCALAR_EVENT,
+                YAML_SCALAR_EVENT,
+                YAML_SEQUENCE_END_EVENT,
+                YAML_MAPPING_END_EVENT,
+                YAML_DOCUMENT_END_EVENT,
+                YAML_STREAM_END_EVENT,
+            ]
+        );
+        assert_eq!(scalars, vec!["key", "1", "two"]);
+    }
+
+    #[test]
+    fn test_parser_layout_matches_libyaml() {
+        use std::mem::{offset_of, size_of};
+        let mark = size_of::<yaml_mark_t>();
+        let word = size_of::<usize>();
+        // libyaml: int error; const char *problem; size_t problem_offset;
+        // int problem_value; yaml_mark_t problem_mark; const char *context;
+        // yaml_mark_t context_mark;
+        assert_eq!(mark, 3 * word);
+        assert_eq!(offset_of!(yaml_parser_t, error), 0);
+        assert_eq!(offset_of!(yaml_parser_t, problem), word);
+        assert_eq!(offset_of!(yaml_parser_t, problem_offset), 2 * word);
+        assert_eq!(offset_of!(yaml_parser_t, problem_value), 3 * word);
+        assert_eq!(offset_of!(yaml_parser_t, problem_mark), 4 * word);
+        assert_eq!(offset_of!(yaml_parser_t, context), 4 * word + mark);
+        assert_eq!(offset_of!(yaml_parser_t, context_mark), 5 * word + mark);
+        assert_eq!(offset_of!(yaml_parser_t, internal), 5 * word + 2 * mark);
+    }
+
+    #[test]
+    fn test_error_reporting() {
+        unsafe {
+            let mut parser = MaybeUninit::<yaml_parser_t>::uninit();
+            yaml_parser_initialize(parser.as_mut_ptr());
+            let parser = parser.as_mut_ptr();
+            let input = "a: [\n";
+            yaml_parser_set_input_string(parser, input.as_ptr(), input.len());
+
+            let mut event = MaybeUninit::<yaml_event_t>::uninit();
+            let mut failed = false;
+            for _ in 0..32 {
+                if yaml_parser_parse(parser, event.as_mut_ptr()) == 0 {
+                    failed = true;
+                    break;
+                }
+                yaml_event_delete(event.as_mut_ptr());
+            }
+            assert!(failed);
+            assert_ne!((*parser).error, yaml_error_type_t::YAML_NO_ERROR);
+            assert!(!(*parser).problem.is_null());
+            yaml_parser_delete(parser);
+        }
+    }
+}
@@ -0,0 +1,392 @@
+//! Stable content hashes of values and streams
+//!
+//! [`Value::content_hash`] hashes what a value holds, not how it was
+//! written, and [`Yaml::fingerprint`](crate::Yaml::fingerprint) hashes the
+//! documents of a text without composing them, so a service watching a
+//! config file can tell a semantic change from a reformatting:
+//!
+//! ```
+//! use rust_yaml::Yaml;
+//!
+//! let yaml = Yaml::new();
+//! let before = yaml.fingerprint("port: 80\nhost: example.com\n").unwrap();
+//! let reformatted = yaml.fingerprint("{ host: 'example.com', port: 80 }").unwrap();
+//! let changed = yaml.fingerprint("port: 8080\nhost: example.com\n").unwrap();
+//! assert_eq!(before, reformatted);
+//! assert_ne!(before, changed);
+//! ```
+//!
+//! The hashes use 64-bit FNV-1a over a fixed encoding of the content, so
+//! they are the same across process runs, platforms and releases of the
+//! standard library, and can be stored in a cache.
+
+use crate::handler::DocumentHandler;
+use crate::{Path, PathSegment, Value};
+use std::ops::ControlFlow;
+
+/// 64-bit FNV-1a over bytes written in a platform independent order
+#[derive(Debug, Clone, Copy)]
+struct ContentHasher(u64);
+
+impl ContentHasher {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    /// Start a hash with the byte telling the kind of node hashed
+    const fn new(kind: u8) -> Self {
+        Self(Self::OFFSET_BASIS).write(&[kind])
+    }
+
+    const fn write(mut self, bytes: &[u8]) -> Self {
+        let mut i = 0;
+        while i < bytes.len() {
+            self.0 ^= bytes[i] as u64;
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+            i += 1;
+        }
+        self
+    }
+
+    const fn write_u64(self, value: u64) -> Self {
+        self.write(&value.to_le_bytes())
+    }
+
+    /// Write a length prefixed string, so that adjacent strings cannot run
+    /// into each other
+    const fn write_str(self, value: &str) -> Self {
+        self.write_u64(value.len() as u64).write(value.as_bytes())
+    }
+
+    const fn finish(self) -> u64 {
+        self.0
+    }
+}
+
+// The first byte of every hash, telling the kind of node
+const NULL: u8 = 0;
+const BOOL: u8 = 1;
+const INT: u8 = 2;
+const FLOAT: u8 = 3;
+const STRING: u8 = 4;
+const SEQUENCE: u8 = 5;
+const MAPPING: u8 = 6;
+const ENTRY: u8 = 7;
+const TAGGED: u8 = 8;
+const ALIAS: u8 = 9;
+
+/// Hash a scalar value; collections are hashed by their callers from the
+/// hashes of their children
+fn scalar_hash(value: &Value) -> u64 {
+    match value {
+        Value::Bool(b) => ContentHasher::new(BOOL).write(&[u8::from(*b)]),
+        Value::Int(i) => ContentHasher::new(INT).write(&i.to_le_bytes()),
+        Value::Float(f) => {
+            // NaNs and zeros compare equal whatever their bits, so they hash
+            // the same too
+            let bits = if f.is_nan() {
+                u64::MAX
+            } else if *f == 0.0 {
+                0
+            } else {
+                f.to_bits()
+            };
+            ContentHasher::new(FLOAT).write_u64(bits)
+        }
+        Value::String(s) => ContentHasher::new(STRING).write_str(s),
+        _ => ContentHasher::new(NULL),
+    }
+    .finish()
+}
+
+fn entry_hash(key: u64, value: u64) -> u64 {
+    ContentHasher::new(ENTRY)
+        .write_u64(key)
+        .write_u64(value)
+        .finish()
+}
+
+/// Hash `value` from an explicit stack, so deep values cannot overflow it,
+/// calling `visit` with every sequence and mapping and its hash, children
+/// first
+fn value_hash(value: &Value, ordered: bool, mut visit: impl FnMut(&Value, u64)) -> u64 {
+    // Collections are entered to push their children, and left once the
+    // hashes of all of them are on `hashes`
+    let mut steps = vec![(value, false)];
+    let mut hashes = Vec::new();
+    while let Some((value, left)) = steps.pop() {
+        if !left {
+            match value {
+                Value::Sequence(seq) => {
+                    steps.push((value, true));
+                    steps.extend(seq.iter().rev().map(|item| (item, false)));
+                }
+                Value::Mapping(map) => {
+                    steps.push((value, true));
+                    steps.extend(
+                        map.iter()
+                            .rev()
+                            .flat_map(|(key, value)| [(value, false), (key, false)]),
+                    );
+                }
+                Value::Tagged(tagged) => {
+                    steps.push((value, true));
+                    steps.push((&tagged.value, false));
+                }
+                scalar => hashes.push(scalar_hash(scalar)),
+            }
+            continue;
+        }
+
+        let hash = match value {
+            Value::Sequence(seq) => {
+                let items = hashes.drain(hashes.len() - seq.len()..);
+                let hash = items
+                    .fold(ContentHasher::new(SEQUENCE), ContentHasher::write_u64)
+                    .write_u64(seq.len() as u64)
+                    .finish();
+                visit(value, hash);
+                hash
+            }
+            Value::Mapping(map) => {
+                let children = hashes.split_off(hashes.len() - 2 * map.len());
+                let entries = children
+                    .chunks(2)
+                    .map(|entry| entry_hash(entry[0], entry[1]));
+                let mapping = if ordered {
+                    entries.fold(ContentHasher::new(MAPPING), ContentHasher::write_u64)
+                } else {
+                    ContentHasher::new(MAPPING).write_u64(entries.fold(0, u64::wrapping_add))
+                };
+                let hash = mapping.write_u64(map.len() as u64).finish();
+                visit(value, hash);
+                hash
+            }
+            Value::Tagged(tagged) => ContentHasher::new(TAGGED)
+                .write_str(&tagged.tag)
+                .write_u64(hashes.pop().unwrap_or_default())
+                .finish(),
+            scalar => scalar_hash(scalar),
+        };
+        hashes.push(hash);
+    }
+    hashes.pop().unwrap_or_default()
+}
+
+/// Call `visit` with every sequence and mapping in `value`, children first,
+/// and its [`Value::content_hash_unordered`]
+pub(crate) fn collection_hashes(value: &Value, visit: impl FnMut(&Value, u64)) {
+    value_hash(value, false, visit);
+}
+
+impl Value {
+    /// A hash of the content of this value that stays the same across
+    /// process runs, for caching and change detection
+    ///
+    /// Equal values hash the same, with the order of sequence items and of
+    /// mapping entries taken into account; see
+    /// [`content_hash_unordered`](Self::content_hash_unordered) to ignore the
+    /// order of mapping entries. Comments and styles are not part of a value
+    /// and do not contribute.
+    pub fn content_hash(&self) -> u64 {
+        value_hash(self, true, |_, _| {})
+    }
+
+    /// Like [`content_hash`](Self::content_hash), but the same for mappings
+    /// holding the same entries in a different order
+    ///
+    /// This is what [`Yaml::fingerprint`](crate::Yaml::fingerprint) hashes,
+    /// since reordering the keys of a mapping does not change what it means.
+    pub fn content_hash_unordered(&self) -> u64 {
+        value_hash(self, false, |_, _| {})
+    }
+}
+
+/// A collection being hashed
+#[derive(Debug)]
+enum Frame {
+    /// A sequence, with the hash of its items so far and their count
+    Sequence(ContentHasher, u64),
+    /// A mapping, with the sum of its entry hashes so far and their count
+    Mapping(u64, u64),
+}
+
+/// Handler computing [`Value::content_hash_unordered`] of the documents of
+/// a stream, as a sequence of them, from its events
+#[derive(Debug)]
+pub(crate) struct Fingerprint {
+    frames: Vec<Frame>,
+    documents: ContentHasher,
+    document_count: u64,
+}
+
+impl Fingerprint {
+    pub(crate) const fn new() -> Self {
+        Self {
+            frames: Vec::new(),
+            documents: ContentHasher::new(SEQUENCE),
+            document_count: 0,
+        }
+    }
+
+    /// The hash of the stream; one without documents loads as a single null
+    /// document
+    pub(crate) fn finish(mut self) -> u64 {
+        if self.document_count == 0 {
+            self.add(&Path::new(), ContentHasher::new(NULL).finish());
+        }
+        self.documents.write_u64(self.document_count).finish()
+    }
+
+    /// Add the hash of a complete node at `path` to its collection, or to
+    /// the documents for a root node
+    fn add(&mut self, path: &Path, hash: u64) {
+        match self.frames.last_mut() {
+            Some(Frame::Sequence(hasher, count)) => {
+                *hasher = hasher.write_u64(hash);
+                *count += 1;
+            }
+            Some(Frame::Mapping(sum, count)) => {
+                let key = match path.segments().last() {
+                    Some(PathSegment::Key(key)) => key.content_hash_unordered(),
+                    _ => ContentHasher::new(NULL).finish(),
+                };
+                *sum = sum.wrapping_add(entry_hash(key, hash));
+                *count += 1;
+            }
+            None => {
+                self.documents = self.documents.write_u64(hash);
+                self.document_count += 1;
+            }
+        }
+    }
+
+    fn end(&mut self, path: &Path) -> ControlFlow<()> {
+        let hash = match self.frames.pop() {
+            Some(Frame::Sequence(hasher, count)) => hasher.write_u64(count),
+            Some(Frame::Mapping(sum, count)) => {
+                ContentHasher::new(MAPPING).write_u64(sum).write_u64(count)
+            }
+            None => return ControlFlow::Continue(()),
+        };
+        self.add(path, hash.finish());
+        ControlFlow::Continue(())
+    }
+}
+
+impl DocumentHandler for Fingerprint {
+    fn on_scalar(&mut self, path: &Path, value: &Value) -> ControlFlow<()> {
+        self.add(path, value_hash(value, false, |_, _| {}));
+        ControlFlow::Continue(())
+    }
+
+    fn on_mapping_start(&mut self, path: &Path) -> ControlFlow<()> {
+        self.frames.push(Frame::Mapping(0, 0));
+        ControlFlow::Continue(())
+    }
+
+    fn on_mapping_end(&mut self, path: &Path) -> ControlFlow<()> {
+        self.end(path)
+    }
+
+    fn on_sequence_start(&mut self, path: &Path) -> ControlFlow<()> {
+        self.frames
+            .push(Frame::Sequence(ContentHasher::new(SEQUENCE), 0));
+        ControlFlow::Continue(())
+    }
+
+    fn on_sequence_end(&mut self, path: &Path) -> ControlFlow<()> {
+        self.end(path)
+    }
+
+    fn on_alias(&mut self, path: &Path, anchor: &str) -> ControlFlow<()> {
+        self.add(path, ContentHasher::new(ALIAS).write_str(anchor).finish());
+        ControlFlow::Continue(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{yaml, Yaml};
+
+    #[test]
+    fn test_content_hash_is_stable() {
+        // Fixed values guard against the encoding changing by accident,
+        // which would invalidate hashes stored by users
+        assert_eq!(Value::Null.content_hash(), 0xaf63_bd4c_8601_b7df);
+        assert_eq!(
+            Value::from("a").content_hash(),
+            Value::from("a").content_hash()
+        );
+        assert_ne!(
+            Value::from(1).content_hash(),
+            Value::from("1").content_hash()
+        );
+        assert_ne!(
+            Value::from(1).content_hash(),
+            Value::from(1.0).content_hash()
+        );
+        assert_eq!(
+            Value::from(0.0).content_hash(),
+            Value::from(-0.0).content_hash()
+        );
+    }
+
+    #[test]
+    fn test_content_hash_order() {
+        let seq = yaml!([1, 2]);
+        assert_ne!(seq.content_hash(), yaml!([2, 1]).content_hash());
+        assert_ne!(
+            seq.content_hash_unordered(),
+            yaml!([2, 1]).content_hash_unordered()
+        );
+        assert_ne!(
+            yaml!([[1], 2]).content_hash(),
+            yaml!([1, [2]]).content_hash()
+        );
+
+        let map = yaml!({"a": 1, "b": 2});
+        let reordered = yaml!({"b": 2, "a": 1});
+        assert_ne!(map.content_hash(), reordered.content_hash());
+        assert_eq!(
+            map.content_hash_unordered(),
+            reordered.content_hash_unordered()
+        );
+        assert_ne!(
+            map.content_hash_unordered(),
+            yaml!({"a": 2, "b": 1}).content_hash_unordered()
+        );
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let yaml = Yaml::new();
+        let fingerprint = |input: &str| yaml.fingerprint(input).unwrap();
+        let input = "# settings\nserver:\n  host: example.com\n  ports: [80, 443]\nname: web\n";
+
+        assert_eq!(
+            fingerprint(input),
+            Value::Sequence(yaml.load_all_str(input).unwrap()).content_hash_unordered()
+        );
+        assert_eq!(
+            fingerprint(input),
+            fingerprint("name: 'web'\nserver: {ports: [80, 443], host: \"example.com\"}\n")
+        );
+        assert_ne!(
+            fingerprint(input),
+            fingerprint("server:\n  host: example.com\n  ports: [443, 80]\nname: web\n")
+        );
+        assert_ne!(
+            fingerprint(input),
+            fingerprint("server:\n  host: example.com\n  ports: [80, 443]\nname: '1'\n")
+        );
+        assert_ne!(fingerprint("a: 1"), fingerprint("a: '1'"));
+        assert_ne!(fingerprint("a: 1"), fingerprint("a: 1\n---\na: 1\n"));
+        assert_eq!(fingerprint(""), fingerprint("# only a comment\n"));
+        assert_eq!(
+            fingerprint("base: &b x\nuse: *b\n"),
+            fingerprint("base: x\nuse: x\n")
+        );
+        assert!(yaml.fingerprint("a: [1").is_err());
+    }
+}
@@ -0,0 +1,133 @@
+//! Round-trip checks and helpers shared by the `fuzz/` targets
+//!
+//! The `exercise_*` and `assert_*` helpers accept arbitrary input, ignore
+//! ordinary parse errors and panic only when an invariant is broken, which is
+//! what a fuzzer reports.
+
+use crate::parser::{Parser, StreamingConfig, StreamingParser};
+use crate::streaming_enhanced::{stream_from_string, StreamConfig};
+use crate::{BasicScanner, Error, Result, Scanner, Value, Yaml};
+
+/// Upper bound on tokens/events pulled from one input, so a parser that
+/// stops making progress shows up as a failure rather than a hang
+const MAX_STEPS: usize = 1 << 20;
+
+/// Check that `input` survives a load → dump → load cycle unchanged
+///
+/// Returns the load error if `input` is not valid YAML, and an emission error
+/// if the dumped text fails to load or loads to different documents.
+pub fn roundtrip_check(input: &str) -> Result<()> {
+    let documents = Yaml::new().load_all_str(input)?;
+    check_documents(&documents)
+}
+
+/// Check that `load(dump(value)) == value`
+pub fn roundtrip_check_value(value: &Value) -> Result<()> {
+    check_documents(std::slice::from_ref(value))
+}
+
+/// Like [`roundtrip_check`], but ignores invalid input and panics on a
+/// mismatch, as fuzz targets expect
+pub fn assert_roundtrip(input: &str) {
+    let Ok(documents) = Yaml::new().load_all_str(input) else {
+        return;
+    };
+    if let Err(error) = check_documents(&documents) {
+        panic!("{error}\n input: {input:?}");
+    }
+}
+
+fn check_documents(documents: &[Value]) -> Result<()> {
+    let yaml = Yaml::new();
+    let dumped = yaml.dump_all_str(documents)?;
+    let reloaded = yaml.load_all_str(&dumped).map_err(|error| {
+        Error::emission(format!(
+            "dumped YAML does not load: {error}\n dumped: {dumped:?}"
+        ))
+    })?;
+    if reloaded != documents {
+        return Err(Error::emission(format!(
+            "round-trip mismatch\n dumped: {dumped:?}\n before: {documents:?}\n after: {reloaded:?}"
+        )));
+    }
+    Ok(())
+}
+
+/// Drain every token from the scanner
+pub fn exercise_scanner(input: &str) {
+    let mut scanner = BasicScanner::new(input.to_string());
+    for _ in 0..MAX_STEPS {
+        match scanner.get_token() {
+            Ok(Some(_)) => {}
+            Ok(None) | Err(_) => return,
+        }
+    }
+    panic!("scanner did not finish within {MAX_STEPS} tokens");
+}
+
+/// Drain every event from the streaming parsers, which slice the input by
+/// hand and are the most likely to index out of bounds
+pub fn exercise_streaming(input: &str) {
+    let mut parser = StreamingParser::new(input.to_string(), StreamingConfig::default());
+    drain(&mut parser);
+    let mut parser = StreamingParser::new_zero_copy(input, StreamingConfig::default());
+    drain(&mut parser);
+
+    let mut parser = stream_from_string(input.to_string(), StreamConfig::default());
+    for _ in 0..MAX_STEPS {
+        match parser.parse_next() {
+            Ok(true) => while parser.next_event().is_some() {},
+            Ok(false) | Err(_) => return,
+        }
+    }
+    panic!("enhanced streaming parser did not finish within {MAX_STEPS} steps");
+}
+
+fn drain(parser: &mut impl Parser) {
+    for _ in 0..MAX_STEPS {
+        match parser.get_event() {
+            Ok(Some(_)) => {}
+            Ok(None) | Err(_) => return,
+        }
+    }
+    panic!("parser did not finish within {MAX_STEPS} events");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_accepts_valid_and_invalid_input() {
+        assert_roundtrip("a: 1\nb: [x, y]\nc: {d: 2.5}\n");
+        assert_roundtrip("---\n- 1\n---\n- 2\n");
+        assert_roundtrip("key: [unclosed");
+    }
+
+    #[test]
+    fn test_roundtrip_check_reports_invalid_input() {
+        assert!(roundtrip_check("a: 1\n").is_ok());
+        assert!(roundtrip_check("key: *missing").is_err());
+    }
+
+    #[test]
+    fn test_roundtrip_check_value_ambiguous_strings() {
+        for s in [
+            "Yes", "TRUE", "Null", ".inf", ".nan", "*ref", "&anchor", "!tag", "a, b", "\u{1}",
+        ] {
+            let value = Value::String(s.to_string());
+            assert!(roundtrip_check_value(&value).is_ok(), "{s:?}");
+        }
+        for f in [f64::INFINITY, f64::NEG_INFINITY, f64::NAN] {
+            assert!(roundtrip_check_value(&Value::Float(f)).is_ok(), "{f}");
+        }
+    }
+
+    #[test]
+    fn test_exercise_helpers_on_truncated_input() {
+        for input in ["", "a: 1\n", "- [1, 2]\n", "key: \"unterminated", "é: ü\n"] {
+            exercise_scanner(input);
+            exercise_streaming(input);
+        }
+    }
+}
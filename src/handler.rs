@@ -0,0 +1,556 @@
+//! Callback-based loading that builds no value tree
+//!
+//! [`Yaml::load_with_handler`](crate::Yaml::load_with_handler) walks the
+//! nodes of a stream depth-first and reports each of them to a
+//! [`DocumentHandler`] together with its [`Path`]. Only the scalars are turned
+//! into [`Value`]s, so a few fields can be picked out of a huge document
+//! without holding the whole of it in memory:
+//!
+//! ```
+//! use rust_yaml::{DocumentHandler, Path, Value, Yaml};
+//! use std::ops::ControlFlow;
+//!
+//! #[derive(Default)]
+//! struct Image(Option<Value>);
+//!
+//! impl DocumentHandler for Image {
+//!     fn on_scalar(&mut self, path: &Path, value: &Value) -> ControlFlow<()> {
+//!         if path.to_string() == "spec.containers[0].image" {
+//!             self.0 = Some(value.clone());
+//!             return ControlFlow::Break(());
+//!         }
+//!         ControlFlow::Continue(())
+//!     }
+//! }
+//!
+//! let mut image = Image::default();
+//! let input = "spec:\n  containers:\n    - image: nginx\n";
+//! Yaml::new().load_with_handler(input, &mut image).unwrap();
+//! assert_eq!(image.0, Some(Value::from("nginx")));
+//! ```
+
+use crate::composer::EmptyScalar;
+use crate::parser::{Event, EventType, Parser};
+use crate::tag::TagResolver;
+use crate::{BasicParser, Error, Path, PathSegment, Position, Result, Value};
+use indexmap::IndexMap;
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+
+/// Receiver of the nodes of a stream, in document order
+///
+/// Every method has a default that ignores the node, so a handler only
+/// implements the callbacks it needs. Returning [`ControlFlow::Break`] from
+/// any of them stops loading without an error.
+///
+/// Paths start again from the root in each document. Mapping keys are not
+/// reported themselves; they appear in the paths of the values under them.
+/// Merge keys (`<<`) are reported like any other key, as resolving them would
+/// need the merged nodes.
+pub trait DocumentHandler {
+    /// Called before the nodes of the document at `index` (counting from 0)
+    fn on_document_start(&mut self, index: usize) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called after the last node of the document at `index`
+    fn on_document_end(&mut self, index: usize) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called with a scalar, resolved as the loader would, or with the scalar
+    /// an alias refers to
+    fn on_scalar(&mut self, path: &Path, value: &Value) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called before the entries of a mapping
+    fn on_mapping_start(&mut self, path: &Path) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called after the entries of a mapping
+    fn on_mapping_end(&mut self, path: &Path) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called before the items of a sequence
+    fn on_sequence_start(&mut self, path: &Path) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called after the items of a sequence
+    fn on_sequence_end(&mut self, path: &Path) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    /// Called with an alias of a sequence or mapping, which is not replayed
+    /// since no tree is kept to replay it from
+    fn on_alias(&mut self, path: &Path, anchor: &str) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+}
+
+/// Handler reading the node at one path of the first document, which
+/// breaks as soon as that node has been read in full
+#[derive(Debug)]
+pub(crate) struct PathProbe<'a> {
+    target: &'a Path,
+    /// Collections of the target node being filled, outermost first
+    building: Vec<Value>,
+    found: Option<Value>,
+    /// Whether the target depends on an alias of a collection or a merge
+    /// key, which need the whole document to resolve
+    needs_document: bool,
+}
+
+impl<'a> PathProbe<'a> {
+    pub(crate) const fn new(target: &'a Path) -> Self {
+        Self {
+            target,
+            building: Vec::new(),
+            found: None,
+            needs_document: false,
+        }
+    }
+
+    /// Whether the node could not be read from the events alone, so the
+    /// whole document must be composed to find it
+    pub(crate) const fn needs_document(&self) -> bool {
+        self.needs_document
+    }
+
+    /// The node found, if the document has one at the target path
+    pub(crate) fn into_found(self) -> Option<Value> {
+        self.found
+    }
+
+    /// Whether `path` is the target or lies inside it
+    fn within(&self, path: &Path) -> bool {
+        path.segments().starts_with(self.target.segments())
+    }
+
+    /// Whether `path` is the value of a merge key in the target or in one of
+    /// the mappings on the way to it
+    fn merges_on_route(&self, path: &Path) -> bool {
+        let Some((PathSegment::Key(Value::String(key)), parent)) = path.segments().split_last()
+        else {
+            return false;
+        };
+        key == "<<"
+            && (parent.starts_with(self.target.segments())
+                || self.target.segments().starts_with(parent))
+    }
+
+    /// Add a finished node of the target, breaking when it is the target
+    fn complete(&mut self, path: &Path, value: Value) -> ControlFlow<()> {
+        if path.segments().len() == self.target.segments().len() {
+            self.found = Some(value);
+            return ControlFlow::Break(());
+        }
+        match (self.building.last_mut(), path.segments().last()) {
+            (Some(Value::Mapping(map)), Some(PathSegment::Key(key))) => {
+                map.insert(key.clone(), value);
+            }
+            (Some(Value::Sequence(seq)), _) => seq.push(value),
+            _ => {}
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn start(&mut self, path: &Path, collection: Value) -> ControlFlow<()> {
+        if self.merges_on_route(path) {
+            self.needs_document = true;
+            return ControlFlow::Break(());
+        }
+        if self.within(path) {
+            self.building.push(collection);
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn end(&mut self, path: &Path) -> ControlFlow<()> {
+        match self.within(path).then(|| self.building.pop()).flatten() {
+            Some(collection) => self.complete(path, collection),
+            None => ControlFlow::Continue(()),
+        }
+    }
+}
+
+impl DocumentHandler for PathProbe<'_> {
+    fn on_document_end(&mut self, index: usize) -> ControlFlow<()> {
+        ControlFlow::Break(())
+    }
+
+    fn on_scalar(&mut self, path: &Path, value: &Value) -> ControlFlow<()> {
+        if self.merges_on_route(path) {
+            self.needs_document = true;
+            return ControlFlow::Break(());
+        }
+        if self.within(path) {
+            return self.complete(path, value.clone());
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn on_mapping_start(&mut self, path: &Path) -> ControlFlow<()> {
+        self.start(path, Value::mapping())
+    }
+
+    fn on_mapping_end(&mut self, path: &Path) -> ControlFlow<()> {
+        self.end(path)
+    }
+
+    fn on_sequence_start(&mut self, path: &Path) -> ControlFlow<()> {
+        self.start(path, Value::sequence())
+    }
+
+    fn on_sequence_end(&mut self, path: &Path) -> ControlFlow<()> {
+        self.end(path)
+    }
+
+    fn on_alias(&mut self, path: &Path, anchor: &str) -> ControlFlow<()> {
+        let on_route = self.target.segments().starts_with(path.segments());
+        if on_route || self.within(path) || self.merges_on_route(path) {
+            self.needs_document = true;
+            return ControlFlow::Break(());
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// The collection a node being reported belongs to
+#[derive(Debug)]
+enum Frame {
+    /// A sequence, with the index of its next item
+    Sequence(usize),
+    /// A mapping, with whether its next node is a key
+    Mapping(bool),
+}
+
+/// Drives a [`DocumentHandler`] from the events of a parser
+#[derive(Debug)]
+pub(crate) struct HandlerDriver {
+    parser: BasicParser,
+    tag_resolver: TagResolver,
+    empty_scalar: EmptyScalar,
+    path: Path,
+    frames: Vec<Frame>,
+    /// Anchored scalars of the current document, so aliases of them can be
+    /// reported as scalars
+    scalar_anchors: HashMap<String, Value>,
+    /// Index of the current document, or of the next one between documents
+    document: usize,
+    in_document: bool,
+}
+
+impl HandlerDriver {
+    /// Create a driver over the events of `parser`
+    pub(crate) fn new(
+        parser: BasicParser,
+        tag_resolver: TagResolver,
+        empty_scalar: EmptyScalar,
+    ) -> Self {
+        Self {
+            parser,
+            tag_resolver,
+            empty_scalar,
+            path: Path::new(),
+            frames: Vec::new(),
+            scalar_anchors: HashMap::new(),
+            document: 0,
+            in_document: false,
+        }
+    }
+
+    /// Report every node of the stream to `handler`, until the stream ends or
+    /// the handler breaks
+    pub(crate) fn run<H: DocumentHandler + ?Sized>(&mut self, handler: &mut H) -> Result<()> {
+        if let Some(error) = self.parser.take_scanning_error() {
+            return Err(error);
+        }
+        while let Some(event) = self.parser.get_event()? {
+            if self.handle(event, handler)?.is_break() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn handle<H: DocumentHandler + ?Sized>(
+        &mut self,
+        event: Event,
+        handler: &mut H,
+    ) -> Result<ControlFlow<()>> {
+        match event.event_type {
+            EventType::StreamStart => Ok(ControlFlow::Continue(())),
+            EventType::DocumentEnd { .. } | EventType::StreamEnd => {
+                Ok(self.finish_document(handler))
+            }
+            EventType::DocumentStart { tags, .. } => {
+                if self.finish_document(handler).is_break() {
+                    return Ok(ControlFlow::Break(()));
+                }
+                self.in_document = true;
+                self.tag_resolver.clear_directives();
+                for (handle, prefix) in tags {
+                    self.tag_resolver.add_directive(handle, prefix);
+                }
+                self.scalar_anchors.clear();
+                Ok(handler.on_document_start(self.document))
+            }
+            EventType::SequenceEnd | EventType::MappingEnd => Ok(self.finish_collection(handler)),
+            _ if matches!(self.frames.last(), Some(Frame::Mapping(true))) => {
+                let key = self.compose_key(event)?;
+                self.path.push(PathSegment::Key(key));
+                if let Some(Frame::Mapping(next_is_key)) = self.frames.last_mut() {
+                    *next_is_key = false;
+                }
+                Ok(ControlFlow::Continue(()))
+            }
+            event_type => {
+                if let Some(Frame::Sequence(index)) = self.frames.last() {
+                    self.path.push(PathSegment::Index(*index));
+                }
+                match event_type {
+                    EventType::SequenceStart { .. } => {
+                        self.frames.push(Frame::Sequence(0));
+                        Ok(handler.on_sequence_start(&self.path))
+                    }
+                    EventType::MappingStart { .. } => {
+                        self.frames.push(Frame::Mapping(true));
+                        Ok(handler.on_mapping_start(&self.path))
+                    }
+                    EventType::Alias { anchor } => {
+                        let flow = match self.scalar_anchors.get(&anchor) {
+                            Some(value) => handler.on_scalar(&self.path, value),
+                            None => handler.on_alias(&self.path, &anchor),
+                        };
+                        self.finish_node();
+                        Ok(flow)
+                    }
+                    scalar => {
+                        let value = self.compose_scalar(scalar, event.position)?;
+                        let flow = handler.on_scalar(&self.path, &value);
+                        self.finish_node();
+                        Ok(flow)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Report the end of the innermost open collection
+    fn finish_collection<H: DocumentHandler + ?Sized>(
+        &mut self,
+        handler: &mut H,
+    ) -> ControlFlow<()> {
+        if let Some(Frame::Mapping(false)) = self.frames.last() {
+            // A key whose value never came
+            self.path.pop();
+        }
+        let flow = match self.frames.pop() {
+            Some(Frame::Sequence(_)) => handler.on_sequence_end(&self.path),
+            Some(Frame::Mapping(_)) => handler.on_mapping_end(&self.path),
+            None => ControlFlow::Continue(()),
+        };
+        self.finish_node();
+        flow
+    }
+
+    /// Report the end of the current document and of the collections still
+    /// open in it, whose end events the parser leaves out at the end of the
+    /// stream
+    fn finish_document<H: DocumentHandler + ?Sized>(&mut self, handler: &mut H) -> ControlFlow<()> {
+        if !self.in_document {
+            return ControlFlow::Continue(());
+        }
+        while !self.frames.is_empty() {
+            self.finish_collection(handler)?;
+        }
+        self.in_document = false;
+        self.path = Path::new();
+        self.document += 1;
+        handler.on_document_end(self.document - 1)
+    }
+
+    /// Step past a node that has been reported in full
+    fn finish_node(&mut self) {
+        match self.frames.last_mut() {
+            Some(Frame::Sequence(index)) => {
+                *index += 1;
+                self.path.pop();
+            }
+            Some(Frame::Mapping(next_is_key)) => {
+                *next_is_key = true;
+                self.path.pop();
+            }
+            None => {}
+        }
+    }
+
+    /// Resolve a scalar event, remembering it if it is anchored
+    fn compose_scalar(&mut self, event_type: EventType, position: Position) -> Result<Value> {
+        let EventType::Scalar {
+            anchor,
+            tag,
+            value,
+            style,
+            ..
+        } = event_type
+        else {
+            return Ok(Value::Null);
+        };
+        let value = match tag {
+            Some(tag) => {
+                let tag = self.tag_resolver.resolve_at(&tag, position)?;
+                self.tag_resolver.apply_tag(&tag, &value)?
+            }
+            None => self.empty_scalar.resolve(value, style),
+        };
+        if let Some(anchor) = anchor {
+            self.scalar_anchors.insert(anchor, value.clone());
+        }
+        Ok(value)
+    }
+
+    /// Compose a mapping key in full, since it is part of the paths below it
+    fn compose_key(&mut self, event: Event) -> Result<Value> {
+        match event.event_type {
+            EventType::SequenceStart { .. } => {
+                let mut sequence = Vec::new();
+                while let Some(event) = self.parser.get_event()? {
+                    if matches!(event.event_type, EventType::SequenceEnd) {
+                        break;
+                    }
+                    sequence.push(self.compose_key(event)?);
+                }
+                Ok(Value::Sequence(sequence))
+            }
+            EventType::MappingStart { .. } => {
+                let mut mapping = IndexMap::new();
+                while let Some(event) = self.parser.get_event()? {
+                    if matches!(event.event_type, EventType::MappingEnd) {
+                        break;
+                    }
+                    let key = self.compose_key(event)?;
+                    let value = match self.parser.get_event()? {
+                        Some(event) => self.compose_key(event)?,
+                        None => Value::Null,
+                    };
+                    mapping.insert(key, value);
+                }
+                Ok(Value::Mapping(mapping))
+            }
+            EventType::Alias { anchor } => match self.scalar_anchors.get(&anchor) {
+                Some(value) => Ok(value.clone()),
+                None => Err(Error::construction(
+                    event.position,
+                    format!("Alias '{anchor}' used in a key is not of a scalar"),
+                )),
+            },
+            scalar => self.compose_scalar(scalar, event.position),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Yaml;
+
+    /// Records every callback as a line, breaking at a given scalar
+    #[derive(Default)]
+    struct Recorder {
+        lines: Vec<String>,
+        stop_at: Option<Value>,
+    }
+
+    impl DocumentHandler for Recorder {
+        fn on_document_start(&mut self, index: usize) -> ControlFlow<()> {
+            self.lines.push(format!("doc {index}"));
+            ControlFlow::Continue(())
+        }
+
+        fn on_scalar(&mut self, path: &Path, value: &Value) -> ControlFlow<()> {
+            self.lines.push(format!("{path} = {value}"));
+            if self.stop_at.as_ref() == Some(value) {
+                return ControlFlow::Break(());
+            }
+            ControlFlow::Continue(())
+        }
+
+        fn on_mapping_start(&mut self, path: &Path) -> ControlFlow<()> {
+            self.lines.push(format!("{path} {{"));
+            ControlFlow::Continue(())
+        }
+
+        fn on_mapping_end(&mut self, path: &Path) -> ControlFlow<()> {
+            self.lines.push(format!("{path} }}"));
+            ControlFlow::Continue(())
+        }
+
+        fn on_sequence_start(&mut self, path: &Path) -> ControlFlow<()> {
+            self.lines.push(format!("{path} ["));
+            ControlFlow::Continue(())
+        }
+
+        fn on_sequence_end(&mut self, path: &Path) -> ControlFlow<()> {
+            self.lines.push(format!("{path} ]"));
+            ControlFlow::Continue(())
+        }
+
+        fn on_alias(&mut self, path: &Path, anchor: &str) -> ControlFlow<()> {
+            self.lines.push(format!("{path} = *{anchor}"));
+            ControlFlow::Continue(())
+        }
+    }
+
+    #[test]
+    fn test_load_with_handler() {
+        let input = "name: &n web\nports: [80, 443]\nmeta: &m {a: 1}\nalias: *n\ncopy: *m\n\
+                     ? [x, y]\n: pair\n---\n- true\n";
+        let mut recorder = Recorder::default();
+        Yaml::new().load_with_handler(input, &mut recorder).unwrap();
+        assert_eq!(
+            recorder.lines,
+            vec![
+                "doc 0",
+                " {",
+                "name = \"web\"",
+                "ports [",
+                "ports[0] = 80",
+                "ports[1] = 443",
+                "ports ]",
+                "meta {",
+                "meta.a = 1",
+                "meta }",
+                "alias = \"web\"",
+                "copy = *m",
+                "[\"x\", \"y\"] = \"pair\"",
+                " }",
+                "doc 1",
+                " [",
+                "[0] = true",
+                " ]",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_load_with_handler_stops_early() {
+        let mut recorder = Recorder {
+            stop_at: Some(Value::Int(2)),
+            ..Recorder::default()
+        };
+        Yaml::new()
+            .load_with_handler("a: 1\nb: 2\nc: 3\n", &mut recorder)
+            .unwrap();
+        assert_eq!(recorder.lines, vec!["doc 0", " {", "a = 1", "b = 2"]);
+
+        let mut recorder = Recorder::default();
+        assert!(Yaml::new()
+            .load_with_handler("a:\n  - b\n c: d\n", &mut recorder)
+            .is_err());
+    }
+}
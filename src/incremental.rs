@@ -0,0 +1,425 @@
+//! Incremental re-parsing for editors
+//!
+//! [`IncrementalParser`] keeps a YAML stream in sync with text edits. The
+//! stream is cut into chunks along lines that start at column 0: each
+//! document, and within a block mapping or block sequence document each
+//! top-level entry or item together with the comments above it. After an
+//! edit only the chunks that overlap it are parsed again; the values of all
+//! other chunks are reused.
+//!
+//! ```
+//! use rust_yaml::incremental::IncrementalParser;
+//! use rust_yaml::Value;
+//!
+//! let mut parser = IncrementalParser::new("name: web\nreplicas: 2\n")?;
+//! let start = parser.text().find('2').unwrap();
+//! parser.edit(start..start + 1, "5")?;
+//! assert_eq!(parser.documents()[0]["replicas"], Value::Int(5));
+//! # Ok::<(), rust_yaml::Error>(())
+//! ```
+//!
+//! Documents that cannot be split safely, such as ones with anchors, aliases,
+//! directives or top-level flow collections, are parsed as a whole; the
+//! result is always the same as loading the full text.
+
+use crate::{Error, Result, Value, Yaml};
+use indexmap::IndexMap;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// How a chunk's value contributes to its document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum ChunkKind {
+    /// Top-level entries of a block mapping document
+    Entries,
+    /// Top-level items of a block sequence document
+    Items,
+    /// A whole document
+    Document,
+}
+
+/// The chunks of one document, in source order
+type DocumentChunks = Vec<(Range<usize>, ChunkKind)>;
+
+#[derive(Debug, Clone)]
+struct Chunk {
+    range: Range<usize>,
+    kind: ChunkKind,
+    value: Value,
+}
+
+/// Parser that re-parses only the parts of a stream touched by an edit
+#[derive(Debug, Clone)]
+pub struct IncrementalParser {
+    yaml: Yaml,
+    text: String,
+    /// Chunks of each document, in order
+    documents: Vec<Vec<Chunk>>,
+    values: Vec<Value>,
+    last_reparsed_bytes: usize,
+}
+
+impl IncrementalParser {
+    /// Parse `text` with the default configuration
+    pub fn new(text: impl Into<String>) -> Result<Self> {
+        Self::with_yaml(Yaml::new(), text)
+    }
+
+    /// Parse `text`, loading every chunk with `yaml`
+    pub fn with_yaml(yaml: Yaml, text: impl Into<String>) -> Result<Self> {
+        let mut parser = Self {
+            yaml,
+            text: text.into(),
+            documents: Vec::new(),
+            values: Vec::new(),
+            last_reparsed_bytes: 0,
+        };
+        parser.reparse(&HashMap::new())?;
+        Ok(parser)
+    }
+
+    /// The current text
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The documents of the text as of the last successful parse
+    pub fn documents(&self) -> &[Value] {
+        &self.values
+    }
+
+    /// Number of bytes parsed again by the last edit (or by construction)
+    pub const fn last_reparsed_bytes(&self) -> usize {
+        self.last_reparsed_bytes
+    }
+
+    /// Replace the bytes in `range` with `replacement` and update the
+    /// documents
+    ///
+    /// The text is changed even if the result does not parse; the error is
+    /// returned and [`documents`](Self::documents) keeps the last good
+    /// result.
+    ///
+    /// # Errors
+    /// Returns an error if `range` is out of bounds or not on character
+    /// boundaries (leaving the text unchanged), or if the edited text is not
+    /// valid YAML.
+    pub fn edit(&mut self, range: Range<usize>, replacement: &str) -> Result<()> {
+        if range.start > range.end
+            || range.end > self.text.len()
+            || !self.text.is_char_boundary(range.start)
+            || !self.text.is_char_boundary(range.end)
+        {
+            return Err(Error::config_error(format!(
+                "edit range {}..{} is not a valid range of the {}-byte text",
+                range.start,
+                range.end,
+                self.text.len()
+            )));
+        }
+
+        // Chunks entirely before or after the edit keep their text, so their
+        // values can be found again at their shifted positions
+        let inserted_end = range.start + replacement.len();
+        let mut reusable = HashMap::new();
+        for chunk in self.documents.drain(..).flatten() {
+            let start = if chunk.range.end <= range.start {
+                chunk.range.start
+            } else if chunk.range.start >= range.end {
+                chunk.range.start - range.end + inserted_end
+            } else {
+                continue;
+            };
+            let shifted = start..start + chunk.range.len();
+            reusable.insert((shifted, chunk.kind), chunk.value);
+        }
+
+        self.text.replace_range(range, replacement);
+        self.reparse(&reusable)
+    }
+
+    fn reparse(&mut self, reusable: &HashMap<(Range<usize>, ChunkKind), Value>) -> Result<()> {
+        self.last_reparsed_bytes = 0;
+        let Some(layout) = split_stream(&self.text) else {
+            // Not splittable at all: parse the stream as one piece
+            self.last_reparsed_bytes = self.text.len();
+            self.values = self.yaml.load_all_str(&self.text)?;
+            return Ok(());
+        };
+
+        let mut documents = Vec::with_capacity(layout.len());
+        for chunks in layout {
+            let mut parsed = Vec::with_capacity(chunks.len());
+            for (range, kind) in chunks {
+                let value = match reusable.get(&(range.clone(), kind)) {
+                    Some(value) => value.clone(),
+                    None => {
+                        self.last_reparsed_bytes += range.len();
+                        match self.parse_chunk(&self.text[range.clone()], kind) {
+                            Some(value) => value,
+                            // A chunk that does not parse on its own: report
+                            // the error (with correct positions) from the
+                            // full text
+                            None => return self.reparse_all(),
+                        }
+                    }
+                };
+                parsed.push(Chunk { range, kind, value });
+            }
+            documents.push(parsed);
+        }
+
+        self.values = documents.iter().map(|chunks| assemble(chunks)).collect();
+        if self.values.is_empty() {
+            self.values.push(Value::Null);
+        }
+        self.documents = documents;
+        Ok(())
+    }
+
+    /// Parse the whole text after a chunk failed on its own
+    fn reparse_all(&mut self) -> Result<()> {
+        self.last_reparsed_bytes = self.text.len();
+        self.documents.clear();
+        self.values = self.yaml.load_all_str(&self.text)?;
+        Ok(())
+    }
+
+    /// Parse one chunk, or `None` if it is not a valid piece of its kind
+    fn parse_chunk(&self, text: &str, kind: ChunkKind) -> Option<Value> {
+        let mut documents = self.yaml.load_all_str(text).ok()?;
+        if documents.len() != 1 {
+            return None;
+        }
+        let value = documents.pop()?;
+        let valid = match kind {
+            ChunkKind::Entries => value.is_mapping(),
+            ChunkKind::Items => value.is_sequence(),
+            ChunkKind::Document => true,
+        };
+        valid.then_some(value)
+    }
+}
+
+/// Build a document's value from its chunks
+fn assemble(chunks: &[Chunk]) -> Value {
+    match chunks {
+        [] => Value::Null,
+        [Chunk {
+            kind: ChunkKind::Document,
+            value,
+            ..
+        }] => value.clone(),
+        [first, ..] if first.kind == ChunkKind::Items => Value::Sequence(
+            chunks
+                .iter()
+                .filter_map(|chunk| chunk.value.as_sequence())
+                .flatten()
+                .cloned()
+                .collect(),
+        ),
+        _ => {
+            let mut map = IndexMap::new();
+            for chunk in chunks {
+                if let Some(entries) = chunk.value.as_mapping() {
+                    for (key, value) in entries {
+                        map.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+            #[cfg(feature = "sort-keys")]
+            map.sort_keys();
+            Value::Mapping(map)
+        }
+    }
+}
+
+/// Byte range and content of each line, without the line break
+fn lines(text: &str) -> impl Iterator<Item = (Range<usize>, &str)> {
+    let mut offset = 0;
+    text.split_inclusive('\n').map(move |line| {
+        let start = offset;
+        offset += line.len();
+        let content = line.trim_end_matches(['\n', '\r']);
+        (start..offset, content)
+    })
+}
+
+fn is_marker(line: &str, marker: &str) -> bool {
+    line.strip_prefix(marker)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t']))
+}
+
+fn is_blank_or_comment(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.is_empty() || trimmed.starts_with('#')
+}
+
+/// Cut a stream into documents and chunks, or `None` if it uses features
+/// that need the whole stream (directives, document end markers)
+fn split_stream(text: &str) -> Option<Vec<DocumentChunks>> {
+    let mut documents = Vec::new();
+    let mut start = 0;
+    let mut explicit = false;
+    for (range, line) in lines(text) {
+        if line.starts_with('%') || is_marker(line, "...") {
+            return None;
+        }
+        if is_marker(line, "---") {
+            if explicit || !text[start..range.start].lines().all(is_blank_or_comment) {
+                documents.push(split_document(text, start..range.start, explicit));
+            }
+            start = range.start;
+            explicit = true;
+        }
+    }
+    if explicit || !text[start..].lines().all(is_blank_or_comment) {
+        documents.push(split_document(text, start..text.len(), explicit));
+    }
+    Some(documents)
+}
+
+/// Cut one document into top-level chunks, falling back to a single
+/// [`ChunkKind::Document`] chunk when that is not safe
+fn split_document(
+    text: &str,
+    range: Range<usize>,
+    explicit: bool,
+) -> Vec<(Range<usize>, ChunkKind)> {
+    let whole = vec![(range.clone(), ChunkKind::Document)];
+    let document = &text[range.clone()];
+    if document.contains(['&', '*']) {
+        return whole;
+    }
+
+    let mut content = range.start;
+    if explicit {
+        // Only a bare `---` (or one followed by a comment) may be split off
+        let (marker, line) = lines(document).next().unwrap_or((0..0, ""));
+        if !line[3..].trim_start().is_empty() && !line[3..].trim_start().starts_with('#') {
+            return whole;
+        }
+        content += marker.end;
+    }
+
+    let mut kind = None;
+    let mut starts = Vec::new();
+    for (line_range, line) in lines(&text[content..range.end]) {
+        if is_blank_or_comment(line) || line.starts_with([' ', '\t']) {
+            continue;
+        }
+        let item = line == "-" || line.starts_with("- ");
+        let line_kind = *kind.get_or_insert(if item {
+            ChunkKind::Items
+        } else {
+            ChunkKind::Entries
+        });
+        match line_kind {
+            ChunkKind::Items if item => starts.push(content + line_range.start),
+            // A block sequence may sit at column 0 under a top-level key
+            ChunkKind::Entries if item => {}
+            ChunkKind::Entries
+                if !line.starts_with([
+                    '[', '{', '?', ':', '|', '>', '!', '@', '`', ',', ']', '}', '-',
+                ]) =>
+            {
+                starts.push(content + line_range.start);
+            }
+            _ => return whole,
+        }
+    }
+
+    let Some(kind) = kind else {
+        return whole;
+    };
+    // Each chunk runs from the end of the previous one, so comments and
+    // blank lines belong to the entry below them
+    let mut chunks = Vec::with_capacity(starts.len());
+    let mut chunk_start = range.start;
+    for end in starts.iter().skip(1).copied() {
+        chunks.push((chunk_start..end, kind));
+        chunk_start = end;
+    }
+    chunks.push((chunk_start..range.end, kind));
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(parser: &IncrementalParser) {
+        assert_eq!(
+            parser.documents(),
+            Yaml::new().load_all_str(parser.text()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_edit_reparses_only_the_touched_entry() {
+        let text = "# config\nname: web\nports:\n  - 80\n  - 443\nenv:\n  debug: true\n";
+        let mut parser = IncrementalParser::new(text).unwrap();
+        check(&parser);
+
+        let at = parser.text().find("443").unwrap();
+        parser.edit(at..at + 3, "8443").unwrap();
+        check(&parser);
+        assert_eq!(
+            parser.last_reparsed_bytes(),
+            "ports:\n  - 80\n  - 8443\n".len()
+        );
+
+        let end = parser.text().len();
+        parser.edit(end..end, "extra: [1, 2]\n").unwrap();
+        check(&parser);
+        assert_eq!(parser.documents()[0]["extra"][1], Value::Int(2));
+    }
+
+    #[test]
+    fn test_sequences_and_multiple_documents() {
+        let mut parser = IncrementalParser::new("- a\n- b\n---\nkey: 1\n--- |\n  text\n").unwrap();
+        check(&parser);
+        assert_eq!(parser.documents().len(), 3);
+
+        let at = parser.text().find("- b").unwrap();
+        parser.edit(at + 2..at + 3, "changed").unwrap();
+        check(&parser);
+        assert_eq!(parser.last_reparsed_bytes(), "- changed\n".len());
+
+        // Adding a document separator splits a document
+        let at = parser.text().find("key").unwrap();
+        parser.edit(at..at, "other: 2\n---\n").unwrap();
+        check(&parser);
+        assert_eq!(parser.documents().len(), 4);
+    }
+
+    #[test]
+    fn test_fallbacks_match_full_parse() {
+        for text in [
+            "base: &b {x: 1}\nother: *b\n",
+            "%YAML 1.2\n---\na: 1\n...\n",
+            "? complex\n: value\n",
+            "[1, 2]\n",
+            "",
+            "# only a comment\n",
+        ] {
+            let mut parser = IncrementalParser::new(text).unwrap();
+            check(&parser);
+            parser.edit(0..0, "\n").unwrap();
+            check(&parser);
+        }
+    }
+
+    #[test]
+    fn test_invalid_edits() {
+        let mut parser = IncrementalParser::new("a: é\n").unwrap();
+        assert!(parser.edit(4..5, "x").is_err());
+        assert!(parser.edit(3..99, "x").is_err());
+        assert_eq!(parser.text(), "a: é\n");
+
+        let before = parser.documents().to_vec();
+        assert!(parser.edit(0..0, "b: *missing\n").is_err());
+        assert_eq!(parser.text(), "b: *missing\na: é\n");
+        assert_eq!(parser.documents(), before.as_slice());
+    }
+}
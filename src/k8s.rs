@@ -0,0 +1,163 @@
+//! Helpers for Kubernetes-style manifest bundles
+//!
+//! Manifest bundles are multi-document streams where each document is an
+//! object identified by its `kind` and `metadata.name`. These helpers work on
+//! plain text and [`Value`]s and need no client libraries.
+
+use crate::{Result, Value, Yaml};
+
+/// A document of a manifest bundle, with the fields that identify it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    /// The `kind` of the object, such as `Deployment`
+    pub kind: Option<String>,
+    /// The `metadata.name` of the object
+    pub name: Option<String>,
+    /// The `metadata.namespace` of the object
+    pub namespace: Option<String>,
+    /// The whole document
+    pub value: Value,
+}
+
+impl Manifest {
+    /// Extract the identifying fields of a loaded document
+    pub fn from_value(value: Value) -> Self {
+        let text = |value: &Value| value.as_str().map(str::to_string);
+        let metadata = &value["metadata"];
+        Self {
+            kind: text(&value["kind"]),
+            name: text(&metadata["name"]),
+            namespace: text(&metadata["namespace"]),
+            value,
+        }
+    }
+}
+
+/// Whether `line` is the document marker `marker` (`---` or `...`), alone or
+/// followed by whitespace
+fn is_marker(line: &str, marker: &str) -> bool {
+    line.strip_prefix(marker)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t', '\r']))
+}
+
+/// Whether `line` holds nothing but whitespace or a comment
+fn is_blank(line: &str) -> bool {
+    let line = line.trim_start();
+    line.is_empty() || line.starts_with('#')
+}
+
+/// Split a multi-document stream into the text of each document, without
+/// parsing it
+///
+/// Documents are cut at `---` and `...` lines. Each slice keeps its `---`
+/// line and the directives and comments before it, so it loads on its own;
+/// `...` lines are left out. Documents with nothing but comments or blank
+/// lines are skipped, as `kubectl` does.
+pub fn split_documents(input: &str) -> Vec<&str> {
+    let mut documents = Vec::new();
+    let mut start = 0;
+    // Whether the current document has had content other than directives,
+    // comments and blank lines
+    let mut has_content = false;
+    // Whether the current document has its `---` line, after which a `%`
+    // line is content rather than a directive
+    let mut started = false;
+
+    let mut offset = 0;
+    for line in input.split_inclusive('\n') {
+        let text = line.trim_end_matches(['\n', '\r']);
+        if is_marker(text, "---") {
+            if has_content {
+                documents.push(&input[start..offset]);
+                start = offset;
+            } else if started {
+                // Drop the empty document before this one
+                start = offset;
+            }
+            started = true;
+            // Content on the marker line, such as `--- !Tag` or `--- value`
+            has_content = !is_blank(&text[3..]);
+        } else if is_marker(text, "...") {
+            if has_content {
+                documents.push(&input[start..offset]);
+            }
+            start = offset + line.len();
+            has_content = false;
+            started = false;
+        } else if !is_blank(text) && (started || !text.starts_with('%')) {
+            has_content = true;
+        }
+        offset += line.len();
+    }
+    if has_content {
+        documents.push(&input[start..]);
+    }
+    documents
+}
+
+/// Load every document of a manifest bundle with its `kind` and
+/// `metadata.name` extracted
+///
+/// Empty documents, which load as null, are skipped.
+///
+/// # Errors
+/// Returns an error if the stream is not valid YAML.
+pub fn load_manifests(input: &str) -> Result<Vec<Manifest>> {
+    Ok(Yaml::new()
+        .load_all_str(input)?
+        .into_iter()
+        .filter(|value| !value.is_null())
+        .map(Manifest::from_value)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUNDLE: &str = "# bundle\n---\napiVersion: v1\nkind: Service\nmetadata:\n  name: web\n  \
+                          namespace: prod\n---\n# empty\n---\nkind: Deployment\nmetadata:\n  \
+                          name: web\n...\n%YAML 1.2\n---\nkind: ConfigMap\n";
+
+    #[test]
+    fn test_split_documents() {
+        assert_eq!(
+            split_documents(BUNDLE),
+            vec![
+                "# bundle\n---\napiVersion: v1\nkind: Service\nmetadata:\n  name: web\n  namespace: prod\n",
+                "---\nkind: Deployment\nmetadata:\n  name: web\n",
+                "%YAML 1.2\n---\nkind: ConfigMap\n",
+            ]
+        );
+        assert_eq!(
+            split_documents("a: 1\n---\nb: 2"),
+            vec!["a: 1\n", "---\nb: 2"]
+        );
+        assert_eq!(split_documents("--- !Tag\n"), vec!["--- !Tag\n"]);
+        assert!(split_documents("# nothing\n---\n\n").is_empty());
+    }
+
+    #[test]
+    fn test_load_manifests() {
+        let manifests = load_manifests(BUNDLE).unwrap();
+        let ids: Vec<_> = manifests
+            .iter()
+            .map(|manifest| {
+                (
+                    manifest.kind.as_deref(),
+                    manifest.name.as_deref(),
+                    manifest.namespace.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                (Some("Service"), Some("web"), Some("prod")),
+                (Some("Deployment"), Some("web"), None),
+                (Some("ConfigMap"), None, None),
+            ]
+        );
+        assert_eq!(manifests[0].value["apiVersion"], Value::from("v1"));
+    }
+}
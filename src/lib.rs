@@ -73,60 +73,111 @@
 #![allow(clippy::while_let_on_iterator)]
 #![allow(clippy::collapsible_if)]
 
+pub mod analysis;
+pub mod api;
 pub mod composer;
 pub mod composer_borrowed;
 pub mod composer_comments;
 pub mod composer_optimized;
+pub mod composer_shared;
 pub mod constructor;
+pub mod document;
 pub mod emitter;
+pub mod encoding;
 pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fingerprint;
+pub mod fuzz_util;
+pub mod handler;
+pub mod incremental;
+#[cfg(feature = "k8s")]
+pub mod k8s;
 pub mod limits;
 pub mod parser;
+pub mod patch;
+pub mod path;
 pub mod position;
 pub mod profiling;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod redact;
+pub mod report;
 pub mod representer;
 pub mod resolver;
 pub mod scanner;
 pub mod schema;
+pub mod schema_docs;
 pub mod serializer;
 #[cfg(feature = "async")]
 pub mod streaming_async;
 pub mod streaming_enhanced;
+pub mod style_map;
 pub mod tag;
+pub mod template;
 pub mod value;
 pub mod value_borrowed;
+pub mod value_shared;
+pub mod walk;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 pub mod yaml;
 pub mod zero_copy_value;
 pub mod zerocopy;
 
 // Re-exports for convenience
+pub use api::{from_reader, from_slice, from_str, to_string, to_writer};
+pub use document::{Document, TagDirective};
+pub use encoding::Encoding;
 pub use error::{Error, Result};
-pub use limits::{Limits, ResourceStats, ResourceTracker};
+pub use fuzz_util::{roundtrip_check, roundtrip_check_value};
+pub use handler::DocumentHandler;
+pub use limits::{Limits, ResourceHook, ResourceStats, ResourceTracker};
+pub use patch::PatchOp;
+pub use path::{Path, PathSegment};
 pub use position::Position;
+pub use profiling::{DumpStats, ParseStats};
+pub use redact::{redact, RedactionRules};
+pub use report::ErrorReport;
 pub use scanner::QuoteStyle;
 pub use schema::{
-    Schema, SchemaRule, SchemaValidator, ValidationError, ValidationResult, ValueType,
+    FormatKind, Schema, SchemaRule, SchemaValidator, ValidationError, ValidationResult, ValueType,
+};
+pub use schema_docs::generate_template;
+pub use style_map::{NodeStyle, StyleMap};
+pub use tag::UnknownHandle;
+pub use template::{MaskedTemplate, TemplateSpan};
+pub use value::{
+    BlockScalarStyle, BoolStyle, Chomping, CommentedValue, Comments, FinalNewline, FlowStyle,
+    IndentStyle, LineEnding, NullStyle, Style, TagEmission, TaggedValue, TruncatedDisplay, Value,
 };
-pub use value::{CommentedValue, Comments, IndentStyle, Style, Value};
 pub use value_borrowed::BorrowedValue;
+pub use value_shared::{AliasResolution, SharedNode, SharedValue};
+pub use walk::{Visit, Visitor, Walk};
 pub use yaml::{LoaderType, Yaml, YamlConfig};
 pub use zero_copy_value::OptimizedValue;
 
 // Re-export commonly used types from components
-pub use composer::{BasicComposer, Composer};
+pub use composer::{
+    AnchorRedefinition, AnchorScope, BasicComposer, Composer, EmptyScalar, KeyCoercion,
+    MergePrecedence,
+};
 pub use composer_borrowed::{BorrowedComposer, ZeroCopyComposer};
 pub use composer_comments::CommentPreservingComposer;
 pub use composer_optimized::{OptimizedComposer, ReducedAllocComposer};
+pub use composer_shared::SharedComposer;
 pub use constructor::{
     CommentPreservingConstructor, Constructor, RoundTripConstructor, SafeConstructor,
 };
-pub use emitter::{BasicEmitter, Emitter};
+pub use emitter::{AnchorNamer, BasicEmitter, Emitter, PathAnchorNamer, TagHandleNamer};
 pub use parser::{
     BasicParser, Event, EventType, Parser, StreamingConfig, StreamingParser, StreamingStats,
 };
 pub use representer::{Representer, SafeRepresenter};
 pub use resolver::{BasicResolver, Resolver};
-pub use scanner::{BasicScanner, Scanner, Token, TokenType};
+pub use scanner::{
+    tokenize, BasicScanner, InvalidDirective, Scanner, Token, TokenCategory, TokenType, Tokens,
+};
 pub use serializer::{BasicSerializer, Serializer};
 pub use streaming_enhanced::{
     stream_from_file, stream_from_string, StreamConfig, StreamingYamlParser,
@@ -136,6 +187,8 @@ pub use zerocopy::{ScannerStats, TokenPool, ZeroScanner, ZeroString, ZeroToken,
 
 #[cfg(feature = "serde")]
 pub mod serde_integration;
+#[cfg(feature = "serde")]
+pub use serde_integration::from_value;
 
 #[cfg(test)]
 mod tests {
@@ -288,15 +341,8 @@ prod: *base
         assert!(folded_scalar.is_some(), "Should find folded scalar");
 
         if let EventType::Scalar { value, .. } = &folded_scalar.unwrap().event_type {
-            // Folded scalars should have spaces instead of newlines
-            assert!(
-                !value.contains('\n'),
-                "Folded scalar should not preserve newlines"
-            );
-            assert!(
-                value.contains("This text will be folded into a single line"),
-                "Should fold the text"
-            );
+            // Folded scalars join lines with spaces and clip to one final line break
+            assert_eq!(value, "This text will be folded into a single line\n");
         }
     }
 
@@ -1,19 +1,29 @@
 //! Resource limits for secure YAML processing
 
 use crate::{Error, Result};
+use std::fmt;
+use std::sync::Arc;
 use std::time::Duration;
 
 /// Resource limits configuration for YAML processing
 #[derive(Debug, Clone)]
 pub struct Limits {
     /// Maximum nesting depth for collections
+    ///
+    /// Loading does not recurse per level, so this may be raised well past
+    /// the default for deep machine-generated documents.
     pub max_depth: usize,
     /// Maximum number of anchors in a document
     pub max_anchors: usize,
+    /// Maximum length of an anchor or alias name in characters
+    pub max_anchor_name_len: usize,
     /// Maximum document size in bytes
     pub max_document_size: usize,
     /// Maximum string length in characters
     pub max_string_length: usize,
+    /// Maximum length of a single scalar in bytes, checked by the scanners
+    /// while the scalar is read
+    pub max_scalar_len: usize,
     /// Maximum alias expansion depth
     pub max_alias_depth: usize,
     /// Maximum number of items in a collection
@@ -29,8 +39,10 @@ impl Default for Limits {
         Self {
             max_depth: 1000,
             max_anchors: 10_000,
+            max_anchor_name_len: 1024,
             max_document_size: 100 * 1024 * 1024, // 100MB
             max_string_length: 10 * 1024 * 1024,  // 10MB
+            max_scalar_len: 10 * 1024 * 1024,
             max_alias_depth: 100,
             max_collection_size: 1_000_000,
             max_complexity_score: 1_000_000,
@@ -45,8 +57,10 @@ impl Limits {
         Self {
             max_depth: 50,
             max_anchors: 100,
+            max_anchor_name_len: 256,
             max_document_size: 1024 * 1024, // 1MB
             max_string_length: 64 * 1024,   // 64KB
+            max_scalar_len: 64 * 1024,
             max_alias_depth: 5,
             max_collection_size: 10_000,
             max_complexity_score: 10_000,
@@ -54,13 +68,32 @@ impl Limits {
         }
     }
 
+    /// Creates tight limits for small payloads from untrusted sources, such
+    /// as request bodies or user-submitted snippets
+    pub fn untrusted_input() -> Self {
+        Self {
+            max_depth: 32,
+            max_anchors: 32,
+            max_anchor_name_len: 64,
+            max_document_size: 256 * 1024, // 256KB
+            max_string_length: 16 * 1024,  // 16KB
+            max_scalar_len: 16 * 1024,
+            max_alias_depth: 3,
+            max_collection_size: 4096,
+            max_complexity_score: 4096,
+            timeout: Some(Duration::from_secs(1)),
+        }
+    }
+
     /// Creates permissive limits for trusted input
     pub fn permissive() -> Self {
         Self {
             max_depth: 10_000,
             max_anchors: 100_000,
+            max_anchor_name_len: 64 * 1024,
             max_document_size: 1024 * 1024 * 1024, // 1GB
             max_string_length: 100 * 1024 * 1024,  // 100MB
+            max_scalar_len: 100 * 1024 * 1024,
             max_alias_depth: 1000,
             max_collection_size: 10_000_000,
             max_complexity_score: 100_000_000,
@@ -73,8 +106,10 @@ impl Limits {
         Self {
             max_depth: usize::MAX,
             max_anchors: usize::MAX,
+            max_anchor_name_len: usize::MAX,
             max_document_size: usize::MAX,
             max_string_length: usize::MAX,
+            max_scalar_len: usize::MAX,
             max_alias_depth: usize::MAX,
             max_collection_size: usize::MAX,
             max_complexity_score: usize::MAX,
@@ -83,6 +118,45 @@ impl Limits {
     }
 }
 
+/// Custom accounting hook for a [`ResourceTracker`]
+///
+/// Each method is called with the updated counters after the built-in
+/// [`Limits`] check passed; returning an error aborts the load with it. This
+/// lets embedders enforce their own quotas, such as per-tenant budgets shared
+/// across loads, without changing the composer. All methods default to
+/// accepting the usage.
+pub trait ResourceHook: fmt::Debug + Send + Sync {
+    /// Called when a collection is entered at a new depth
+    fn on_depth(&self, stats: &ResourceStats) -> Result<()> {
+        let _ = stats;
+        Ok(())
+    }
+
+    /// Called when an anchor is defined
+    fn on_anchor(&self, stats: &ResourceStats) -> Result<()> {
+        let _ = stats;
+        Ok(())
+    }
+
+    /// Called when an alias is expanded
+    fn on_alias(&self, stats: &ResourceStats) -> Result<()> {
+        let _ = stats;
+        Ok(())
+    }
+
+    /// Called when an item is added to a collection
+    fn on_collection_item(&self, stats: &ResourceStats) -> Result<()> {
+        let _ = stats;
+        Ok(())
+    }
+
+    /// Called when the complexity score grows
+    fn on_complexity(&self, stats: &ResourceStats) -> Result<()> {
+        let _ = stats;
+        Ok(())
+    }
+}
+
 /// Tracks resource usage during parsing
 #[derive(Debug, Clone, Default)]
 pub struct ResourceTracker {
@@ -91,8 +165,10 @@ pub struct ResourceTracker {
     anchor_count: usize,
     bytes_processed: usize,
     alias_depth: usize,
+    alias_expansions: usize,
     complexity_score: usize,
     collection_items: usize,
+    hook: Option<Arc<dyn ResourceHook>>,
 }
 
 impl ResourceTracker {
@@ -101,6 +177,30 @@ impl ResourceTracker {
         Self::default()
     }
 
+    /// Creates a resource tracker that reports to `hook`
+    pub fn with_hook(hook: Arc<dyn ResourceHook>) -> Self {
+        Self {
+            hook: Some(hook),
+            ..Self::default()
+        }
+    }
+
+    /// Sets the hook called with the counters as they grow
+    pub fn set_hook(&mut self, hook: Option<Arc<dyn ResourceHook>>) {
+        self.hook = hook;
+    }
+
+    /// Runs `call` on the hook, if any, with the current counters
+    fn notify(
+        &self,
+        call: impl FnOnce(&dyn ResourceHook, &ResourceStats) -> Result<()>,
+    ) -> Result<()> {
+        match &self.hook {
+            Some(hook) => call(hook.as_ref(), &self.stats()),
+            None => Ok(()),
+        }
+    }
+
     /// Checks if depth limit is exceeded
     pub fn check_depth(&mut self, limits: &Limits, depth: usize) -> Result<()> {
         self.current_depth = depth;
@@ -112,7 +212,7 @@ impl ResourceTracker {
                 limits.max_depth
             )));
         }
-        Ok(())
+        self.notify(|hook, stats| hook.on_depth(stats))
     }
 
     /// Increments and checks anchor count
@@ -124,7 +224,7 @@ impl ResourceTracker {
                 limits.max_anchors
             )));
         }
-        Ok(())
+        self.notify(|hook, stats| hook.on_anchor(stats))
     }
 
     /// Tracks bytes processed
@@ -159,7 +259,8 @@ impl ResourceTracker {
             )));
         }
         self.alias_depth += 1;
-        Ok(())
+        self.alias_expansions += 1;
+        self.notify(|hook, stats| hook.on_alias(stats))
     }
 
     /// Exits alias expansion
@@ -178,7 +279,7 @@ impl ResourceTracker {
                 limits.max_collection_size
             )));
         }
-        Ok(())
+        self.notify(|hook, stats| hook.on_collection_item(stats))
     }
 
     /// Adds to complexity score
@@ -190,12 +291,15 @@ impl ResourceTracker {
                 limits.max_complexity_score
             )));
         }
-        Ok(())
+        self.notify(|hook, stats| hook.on_complexity(stats))
     }
 
-    /// Resets the tracker for a new document
+    /// Resets the counters for a new document, keeping the hook
     pub fn reset(&mut self) {
-        *self = Self::new();
+        *self = Self {
+            hook: self.hook.take(),
+            ..Self::new()
+        };
     }
 
     /// Gets current statistics
@@ -203,6 +307,7 @@ impl ResourceTracker {
         ResourceStats {
             max_depth: self.max_depth_seen,
             anchor_count: self.anchor_count,
+            alias_expansions: self.alias_expansions,
             bytes_processed: self.bytes_processed,
             complexity_score: self.complexity_score,
             collection_items: self.collection_items,
@@ -211,12 +316,14 @@ impl ResourceTracker {
 }
 
 /// Resource usage statistics
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct ResourceStats {
     /// Maximum depth reached during processing
     pub max_depth: usize,
     /// Total number of anchors encountered
     pub anchor_count: usize,
+    /// Total number of aliases expanded
+    pub alias_expansions: usize,
     /// Total bytes processed
     pub bytes_processed: usize,
     /// Total complexity score
@@ -244,6 +351,15 @@ mod tests {
         assert!(limits.timeout.is_some());
     }
 
+    #[test]
+    fn test_untrusted_input_limits() {
+        let limits = Limits::untrusted_input();
+        let strict = Limits::strict();
+        assert!(limits.max_depth < strict.max_depth);
+        assert!(limits.max_document_size < strict.max_document_size);
+        assert!(limits.timeout.is_some());
+    }
+
     #[test]
     fn test_resource_tracker() {
         let limits = Limits::strict();
@@ -274,5 +390,36 @@ mod tests {
         // Test exiting aliases
         tracker.exit_alias();
         assert!(tracker.enter_alias(&limits).is_ok());
+        assert_eq!(tracker.stats().alias_expansions, 6);
+    }
+
+    /// Allows a fixed number of anchors across every tracker it is shared by
+    #[derive(Debug)]
+    struct AnchorQuota(std::sync::atomic::AtomicUsize);
+
+    impl ResourceHook for AnchorQuota {
+        fn on_anchor(&self, _stats: &ResourceStats) -> Result<()> {
+            let left = self.0.load(std::sync::atomic::Ordering::Relaxed);
+            if left == 0 {
+                return Err(Error::limit_exceeded("anchor quota used up"));
+            }
+            self.0.store(left - 1, std::sync::atomic::Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_resource_hook() {
+        let limits = Limits::default();
+        let quota = Arc::new(AnchorQuota(3.into()));
+        let mut first = ResourceTracker::with_hook(quota.clone());
+        let mut second = ResourceTracker::with_hook(quota);
+
+        assert!(first.add_anchor(&limits).is_ok());
+        assert!(first.add_anchor(&limits).is_ok());
+        first.reset();
+        assert!(second.add_anchor(&limits).is_ok());
+        assert!(first.add_anchor(&limits).is_err());
+        assert!(second.check_depth(&limits, 3).is_ok());
     }
 }
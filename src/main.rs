@@ -1,28 +1,361 @@
-use rust_yaml::Yaml;
-
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let yaml_content = r"
-name: 'rust-yaml'
-version: '0.1.0'
-features:
-  - fast
-  - safe
-  - reliable
-config:
-  debug: true
-  max_depth: 100
+//! `rust-yaml` command line tool
+//!
+//! Built with the `cli` feature:
+//!
+//! ```text
+//! rust-yaml validate <file> [--schema <schema.yaml>]
+//! rust-yaml convert [<file>] [--to json|yaml]
+//! rust-yaml format [<file>] [--check | --in-place]
+//! rust-yaml lint [<file>] [--max-line-length <n>]
+//! rust-yaml diff <old> <new>
+//! ```
+//!
+//! A file argument of `-` (or no file where optional) reads standard input.
+
+use rust_yaml::{LoaderType, Schema, Value, Yaml, YamlConfig};
+use std::fmt::Write as _;
+use std::io::Read;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: rust-yaml <command> [options]
+
+Commands:
+  validate <file> [--schema <schema>]   Check that every document parses (and matches a schema)
+  convert [<file>] [--to json|yaml]     Convert YAML to JSON or normalized YAML
+  format [<file>] [--check|--in-place]  Reformat a document, preserving comments
+  lint [<file>] [--max-line-length <n>] Report style problems
+  diff <old> <new>                      Print a JSON Patch turning <old> into <new>
 ";
 
+type CliResult = Result<ExitCode, String>;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some((command, rest)) = args.split_first() else {
+        eprint!("{USAGE}");
+        return ExitCode::from(2);
+    };
+
+    let result = match command.as_str() {
+        "validate" => validate(rest),
+        "convert" => convert(rest),
+        "format" => format(rest),
+        "lint" => lint(rest),
+        "diff" => diff(rest),
+        "-h" | "--help" | "help" => {
+            print!("{USAGE}");
+            Ok(ExitCode::SUCCESS)
+        }
+        other => Err(format!("unknown command '{other}'\n\n{USAGE}")),
+    };
+
+    result.unwrap_or_else(|message| {
+        eprintln!("rust-yaml: {message}");
+        ExitCode::from(2)
+    })
+}
+
+/// Positional arguments and `--flag [value]` options of a subcommand
+struct Args {
+    positional: Vec<String>,
+    options: Vec<(String, Option<String>)>,
+}
+
+impl Args {
+    /// Split `args`, treating the options in `with_value` as taking a value
+    fn parse(args: &[String], with_value: &[&str]) -> Result<Self, String> {
+        let mut positional = Vec::new();
+        let mut options = Vec::new();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if arg.starts_with("--") {
+                let value = if with_value.contains(&arg.as_str()) {
+                    Some(
+                        iter.next()
+                            .cloned()
+                            .ok_or_else(|| format!("option '{arg}' requires a value"))?,
+                    )
+                } else {
+                    None
+                };
+                options.push((arg.clone(), value));
+            } else {
+                positional.push(arg.clone());
+            }
+        }
+        Ok(Self {
+            positional,
+            options,
+        })
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(option, _)| option == name)
+    }
+
+    fn value(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .find(|(option, _)| option == name)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    /// The single optional input path, defaulting to standard input
+    fn input_path(&self) -> Result<&str, String> {
+        match self.positional.as_slice() {
+            [] => Ok("-"),
+            [path] => Ok(path),
+            _ => Err("expected at most one input file".to_string()),
+        }
+    }
+}
+
+fn read_input(path: &str) -> Result<String, String> {
+    if path == "-" {
+        let mut buffer = String::new();
+        std::io::stdin()
+            .read_to_string(&mut buffer)
+            .map_err(|e| format!("failed to read standard input: {e}"))?;
+        Ok(buffer)
+    } else {
+        std::fs::read_to_string(path).map_err(|e| format!("failed to read '{path}': {e}"))
+    }
+}
+
+fn validate(args: &[String]) -> CliResult {
+    let args = Args::parse(args, &["--schema"])?;
+    let [path] = args.positional.as_slice() else {
+        return Err("validate expects exactly one file".to_string());
+    };
+    let input = read_input(path)?;
+    let yaml = Yaml::new();
+
+    let result = match args.value("--schema") {
+        Some(schema_path) => {
+            let schema_value = yaml
+                .load_str(&read_input(schema_path)?)
+                .map_err(|e| format!("{schema_path}: {e}"))?;
+            let schema =
+                Schema::from_value(&schema_value).map_err(|e| format!("{schema_path}: {e}"))?;
+            yaml.load_all_str_with_schema(&input, &schema).map(|_| ())
+        }
+        None => yaml.load_all_str(&input).map(|_| ()),
+    };
+
+    match result {
+        Ok(()) => {
+            println!("{path}: ok");
+            Ok(ExitCode::SUCCESS)
+        }
+        Err(error) => {
+            println!("{path}: {error}");
+            Ok(ExitCode::FAILURE)
+        }
+    }
+}
+
+fn convert(args: &[String]) -> CliResult {
+    let args = Args::parse(args, &["--to"])?;
+    let path = args.input_path()?;
+    let yaml = Yaml::new();
+    let documents = yaml
+        .load_all_str(&read_input(path)?)
+        .map_err(|e| format!("{path}: {e}"))?;
+
+    match args.value("--to").unwrap_or("json") {
+        "json" => {
+            for document in &documents {
+                let mut output = String::new();
+                write_json(document, 0, &mut output);
+                println!("{output}");
+            }
+        }
+        "yaml" => {
+            let output = yaml.dump_all_str(&documents).map_err(|e| e.to_string())?;
+            print!("{output}");
+        }
+        other => return Err(format!("unsupported output format '{other}'")),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn format(args: &[String]) -> CliResult {
+    let args = Args::parse(args, &[])?;
+    let path = args.input_path()?;
+    let input = read_input(path)?;
+
+    let yaml = Yaml::with_config(YamlConfig {
+        loader_type: LoaderType::RoundTrip,
+        preserve_comments: true,
+        preserve_merge_keys: true,
+        ..YamlConfig::default()
+    });
+    let to_message = |e: rust_yaml::Error| format!("{path}: {e}");
+    let documents = yaml
+        .load_all_str_with_comments(&input)
+        .map_err(to_message)?;
+    let mut output = yaml
+        .dump_all_str_with_comments(&documents)
+        .map_err(to_message)?;
+    if !output.ends_with('\n') {
+        output.push('\n');
+    }
+
+    if args.flag("--check") {
+        if output == input {
+            return Ok(ExitCode::SUCCESS);
+        }
+        println!("{path}: would be reformatted");
+        return Ok(ExitCode::FAILURE);
+    }
+    if args.flag("--in-place") {
+        if path == "-" {
+            return Err("--in-place requires a file".to_string());
+        }
+        std::fs::write(path, output).map_err(|e| format!("failed to write '{path}': {e}"))?;
+    } else {
+        print!("{output}");
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn lint(args: &[String]) -> CliResult {
+    let args = Args::parse(args, &["--max-line-length"])?;
+    let path = args.input_path()?;
+    let max_line_length = match args.value("--max-line-length") {
+        Some(value) => value
+            .parse::<usize>()
+            .map_err(|_| format!("invalid line length '{value}'"))?,
+        None => 120,
+    };
+    let input = read_input(path)?;
+
+    let mut problems = Vec::new();
+    for (index, line) in input.lines().enumerate() {
+        let line_number = index + 1;
+        let indentation = &line[..line.len() - line.trim_start().len()];
+        if indentation.contains('\t') {
+            problems.push((line_number, 1, "tab character in indentation".to_string()));
+        }
+        if line.ends_with([' ', '\t']) {
+            let column = line.trim_end().chars().count() + 1;
+            problems.push((line_number, column, "trailing whitespace".to_string()));
+        }
+        let length = line.chars().count();
+        if length > max_line_length {
+            problems.push((
+                line_number,
+                max_line_length + 1,
+                format!("line too long ({length} > {max_line_length} characters)"),
+            ));
+        }
+    }
+    if !input.is_empty() && !input.ends_with('\n') {
+        let line_count = input.lines().count();
+        problems.push((line_count, 1, "missing newline at end of file".to_string()));
+    }
+    if let Err(error) = Yaml::new().load_all_str(&input) {
+        let position = error.position().copied().unwrap_or_default();
+        problems.push((position.line, position.column, error.to_string()));
+    }
+
+    problems.sort_by_key(|(line, column, _)| (*line, *column));
+    for (line, column, message) in &problems {
+        println!("{path}:{line}:{column}: {message}");
+    }
+    Ok(if problems.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn diff(args: &[String]) -> CliResult {
+    let args = Args::parse(args, &[])?;
+    let [old_path, new_path] = args.positional.as_slice() else {
+        return Err("diff expects two files".to_string());
+    };
     let yaml = Yaml::new();
-    let parsed = yaml.load_str(yaml_content)?;
+    let old = yaml
+        .load_str(&read_input(old_path)?)
+        .map_err(|e| format!("{old_path}: {e}"))?;
+    let new = yaml
+        .load_str(&read_input(new_path)?)
+        .map_err(|e| format!("{new_path}: {e}"))?;
 
-    println!("Parsed YAML: {parsed:#?}");
+    let patch = old.diff_as_json_patch(&new);
+    if patch.is_empty() {
+        return Ok(ExitCode::SUCCESS);
+    }
+    let patch = Value::Sequence(patch.iter().map(|op| op.to_value()).collect());
+    print!("{}", yaml.dump_str(&patch).map_err(|e| e.to_string())?);
+    Ok(ExitCode::FAILURE)
+}
 
-    // Test dumping
-    let mut output = Vec::new();
-    yaml.dump(&parsed, &mut output)?;
-    let output_str = String::from_utf8_lossy(&output);
-    println!("Dumped YAML:\n{output_str}");
+/// Write `value` as pretty-printed JSON; non-string keys are stringified
+fn write_json(value: &Value, indent: usize, out: &mut String) {
+    let pad = "  ".repeat(indent + 1);
+    let close = "  ".repeat(indent);
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Int(i) => {
+            let _ = write!(out, "{i}");
+        }
+        Value::Float(f) if f.is_finite() => {
+            let _ = write!(out, "{f:?}");
+        }
+        Value::Float(_) => out.push_str("null"),
+        Value::String(s) => write_json_string(s, out),
+        Value::Sequence(seq) if seq.is_empty() => out.push_str("[]"),
+        Value::Sequence(seq) => {
+            out.push_str("[\n");
+            for (index, item) in seq.iter().enumerate() {
+                if index > 0 {
+                    out.push_str(",\n");
+                }
+                out.push_str(&pad);
+                write_json(item, indent + 1, out);
+            }
+            let _ = write!(out, "\n{close}]");
+        }
+        Value::Mapping(map) if map.is_empty() => out.push_str("{}"),
+        Value::Mapping(map) => {
+            out.push_str("{\n");
+            for (index, (key, item)) in map.iter().enumerate() {
+                if index > 0 {
+                    out.push_str(",\n");
+                }
+                out.push_str(&pad);
+                match key {
+                    Value::String(s) => write_json_string(s, out),
+                    other => write_json_string(&other.to_string(), out),
+                }
+                out.push_str(": ");
+                write_json(item, indent + 1, out);
+            }
+            let _ = write!(out, "\n{close}}}");
+        }
+        // JSON has no tags, so a tagged value is written as what it holds
+        Value::Tagged(tagged) => write_json(&tagged.value, indent, out),
+    }
+}
 
-    Ok(())
+fn write_json_string(s: &str, out: &mut String) {
+    out.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
 }
@@ -236,6 +236,81 @@ impl Event {
             EventType::DocumentStart { .. } | EventType::DocumentEnd { .. }
         )
     }
+
+    /// Write the event in the notation of the YAML test suite's
+    /// `test.event` files, such as `+MAP {} &a <tag:yaml.org,2002:map>` or
+    /// `=VAL 'text`
+    ///
+    /// Scalar values escape backslashes and the null, backspace, tab, line
+    /// feed and carriage return characters.
+    pub fn to_test_format(&self) -> String {
+        let properties = |anchor: &Option<String>, tag: &Option<String>| {
+            let mut properties = String::new();
+            if let Some(anchor) = anchor {
+                properties.push_str(&format!(" &{anchor}"));
+            }
+            if let Some(tag) = tag {
+                properties.push_str(&format!(" <{tag}>"));
+            }
+            properties
+        };
+
+        match &self.event_type {
+            EventType::StreamStart => "+STR".to_string(),
+            EventType::StreamEnd => "-STR".to_string(),
+            EventType::DocumentStart { implicit: true, .. } => "+DOC".to_string(),
+            EventType::DocumentStart { .. } => "+DOC ---".to_string(),
+            EventType::DocumentEnd { implicit: true } => "-DOC".to_string(),
+            EventType::DocumentEnd { .. } => "-DOC ...".to_string(),
+            EventType::MappingStart {
+                anchor,
+                tag,
+                flow_style,
+            } => {
+                let flow = if *flow_style { " {}" } else { "" };
+                format!("+MAP{flow}{}", properties(anchor, tag))
+            }
+            EventType::MappingEnd => "-MAP".to_string(),
+            EventType::SequenceStart {
+                anchor,
+                tag,
+                flow_style,
+            } => {
+                let flow = if *flow_style { " []" } else { "" };
+                format!("+SEQ{flow}{}", properties(anchor, tag))
+            }
+            EventType::SequenceEnd => "-SEQ".to_string(),
+            EventType::Scalar {
+                anchor,
+                tag,
+                value,
+                style,
+                ..
+            } => {
+                let indicator = match style {
+                    ScalarStyle::Plain => ':',
+                    ScalarStyle::SingleQuoted => '\'',
+                    ScalarStyle::DoubleQuoted => '"',
+                    ScalarStyle::Literal => '|',
+                    ScalarStyle::Folded => '>',
+                };
+                let mut escaped = String::with_capacity(value.len());
+                for ch in value.chars() {
+                    match ch {
+                        '\\' => escaped.push_str("\\\\"),
+                        '\0' => escaped.push_str("\\0"),
+                        '\u{8}' => escaped.push_str("\\b"),
+                        '\t' => escaped.push_str("\\t"),
+                        '\n' => escaped.push_str("\\n"),
+                        '\r' => escaped.push_str("\\r"),
+                        _ => escaped.push(ch),
+                    }
+                }
+                format!("=VAL{} {indicator}{escaped}", properties(anchor, tag))
+            }
+            EventType::Alias { anchor } => format!("=ALI *{anchor}"),
+        }
+    }
 }
 
 impl fmt::Display for Event {
@@ -341,6 +416,53 @@ mod tests {
         assert!(!doc_start.is_collection_start());
     }
 
+    #[test]
+    fn test_event_test_format() {
+        let pos = Position::start();
+        let anchor = Some("a".to_string());
+        let tag = Some("tag:yaml.org,2002:str".to_string());
+
+        let events = [
+            (Event::stream_start(pos), "+STR"),
+            (Event::document_start(pos, None, vec![], false), "+DOC ---"),
+            (Event::mapping_start(pos, None, None, true), "+MAP {}"),
+            (
+                Event::sequence_start(pos, anchor.clone(), None, false),
+                "+SEQ &a",
+            ),
+            (
+                Event::scalar(
+                    pos,
+                    anchor.clone(),
+                    tag,
+                    "a\\b\tc\n".to_string(),
+                    false,
+                    true,
+                    ScalarStyle::DoubleQuoted,
+                ),
+                "=VAL &a <tag:yaml.org,2002:str> \"a\\\\b\\tc\\n",
+            ),
+            (
+                Event::scalar(
+                    pos,
+                    None,
+                    None,
+                    String::new(),
+                    true,
+                    false,
+                    ScalarStyle::Plain,
+                ),
+                "=VAL :",
+            ),
+            (Event::alias(pos, "a".to_string()), "=ALI *a"),
+            (Event::document_end(pos, true), "-DOC"),
+            (Event::stream_end(pos), "-STR"),
+        ];
+        for (event, expected) in events {
+            assert_eq!(event.to_test_format(), expected);
+        }
+    }
+
     #[test]
     fn test_event_display() {
         let pos = Position::start();
@@ -13,12 +13,21 @@ pub use streaming::*;
 // pub use optimizations::*;
 
 /// Trait for YAML parsers that convert token streams to events
+///
+/// Like PyYAML's parser, an implementation parses ahead into a current event
+/// slot that [`Parser::peek_event`] fills and [`Parser::get_event`] empties,
+/// so loops that look at the next event before consuming it do not parse or
+/// copy it twice.
 pub trait Parser {
-    /// Check if there are more events available
-    fn check_event(&self) -> bool;
+    /// Check if there are more events available, or an error to report
+    fn check_event(&mut self) -> bool;
 
     /// Peek at the next event without consuming it
-    fn peek_event(&self) -> Result<Option<&Event>>;
+    ///
+    /// Returns `None` at the end of the events and when parsing the next
+    /// event fails; the error is then returned by the next
+    /// [`Parser::get_event`].
+    fn peek_event(&mut self) -> Option<&Event>;
 
     /// Get the next event, consuming it
     fn get_event(&mut self) -> Result<Option<Event>>;
@@ -31,17 +40,31 @@ pub trait Parser {
 }
 
 /// Basic parser implementation that converts tokens to events
+///
+/// The tokens come from a [`BasicScanner`] unless the parser is created
+/// over another [`Scanner`] with [`BasicParser::with_scanner`], such as the
+/// [`ZeroScanner`](crate::ZeroScanner).
 #[derive(Debug)]
-pub struct BasicParser {
-    scanner: BasicScanner,
+pub struct BasicParser<S = BasicScanner> {
+    scanner: S,
+    /// Every event produced so far; consumed events keep their kind for the
+    /// look-back checks while their contents move out
     events: Vec<Event>,
     event_index: usize,
+    /// The next event, parsed ahead by [`Parser::peek_event`]
+    current_event: Option<Event>,
     state: ParserState,
     state_stack: Vec<ParserState>,
+    /// Where each open flow collection starts, innermost last
+    flow_starts: Vec<Position>,
     position: Position,
     pending_anchor: Option<String>,
     pending_tag: Option<String>,
     last_token_type: Option<TokenType>,
+    last_token_line: usize,
+    /// Whether the key of the current block mapping entry has been parsed,
+    /// so an explicit `? key` without a `:` value can be told apart
+    key_complete: bool,
     scanning_error: Option<Error>,
     yaml_version: Option<(u8, u8)>,
     tag_directives: Vec<(String, String)>,
@@ -78,24 +101,7 @@ impl BasicParser {
 
     /// Create a new streaming parser with custom limits
     pub fn with_limits(input: String, limits: Limits) -> Self {
-        let scanner = BasicScanner::with_limits(input, limits);
-        let position = scanner.position();
-
-        Self {
-            scanner,
-            events: Vec::new(),
-            event_index: 0,
-            state: ParserState::StreamStart,
-            state_stack: Vec::new(),
-            position,
-            pending_anchor: None,
-            pending_tag: None,
-            last_token_type: None,
-            scanning_error: None,
-            yaml_version: None,
-            tag_directives: Vec::new(),
-            tag_resolver: TagResolver::new(),
-        }
+        Self::with_scanner(BasicScanner::with_limits(input, limits))
     }
 
     /// Create a new parser with eager parsing (for compatibility)
@@ -105,65 +111,62 @@ impl BasicParser {
 
     /// Create a new parser with eager parsing and custom limits
     pub fn new_eager_with_limits(input: String, limits: Limits) -> Self {
-        let mut scanner = BasicScanner::new_eager_with_limits(input, limits);
-        let position = scanner.position();
+        Self::from_scanner_eager(BasicScanner::with_limits(input, limits))
+    }
+
+    /// Create a parser with eager parsing over an existing scanner, such as
+    /// one with [`BasicScanner::set_strict_escapes`] enabled
+    pub fn from_scanner_eager(mut scanner: BasicScanner) -> Self {
+        scanner.scan_eagerly();
 
         // Check if there were any scanning errors and store them
         let scanning_error = scanner.take_scanning_error();
 
-        let mut parser = Self {
-            scanner,
-            events: Vec::new(),
-            event_index: 0,
-            state: ParserState::StreamStart,
-            state_stack: Vec::new(),
-            position,
-            pending_anchor: None,
-            pending_tag: None,
-            last_token_type: None,
-            scanning_error: None,
-            yaml_version: None,
-            tag_directives: Vec::new(),
-            tag_resolver: TagResolver::new(),
-        };
+        let mut parser = Self::with_scanner(scanner);
 
         // If there was a scanning error, store it for later propagation
         if let Some(error) = scanning_error {
             parser.scanning_error = Some(error);
         } else {
             // Parse all events immediately only if there were no scanning errors
-            parser.parse_all().unwrap_or(());
+            if let Err(error) = parser.parse_all() {
+                parser.scanning_error = Some(error);
+            }
         }
 
         parser
     }
 
-    /// Create parser from existing scanner
-    pub fn from_scanner(scanner: BasicScanner) -> Self {
-        let position = scanner.position();
+    /// Create a parser with eager parsing, returning the first scanning or
+    /// parsing error instead of reporting it from [`Parser::get_event`]
+    pub fn try_new_eager(input: String) -> Result<Self> {
+        Self::try_new_eager_with_limits(input, Limits::default())
+    }
 
-        let mut parser = Self {
-            scanner,
-            events: Vec::new(),
-            event_index: 0,
-            state: ParserState::StreamStart,
-            state_stack: Vec::new(),
-            position,
-            pending_anchor: None,
-            pending_tag: None,
-            last_token_type: None,
-            scanning_error: None,
-            yaml_version: None,
-            tag_directives: Vec::new(),
-            tag_resolver: TagResolver::new(),
-        };
+    /// Create a parser with eager parsing and custom limits, returning the
+    /// first scanning or parsing error
+    pub fn try_new_eager_with_limits(input: String, limits: Limits) -> Result<Self> {
+        let mut parser = Self::new_eager_with_limits(input, limits);
+        match parser.take_scanning_error() {
+            Some(error) => Err(error),
+            None => Ok(parser),
+        }
+    }
 
-        parser.parse_all().unwrap_or(());
+    /// Create parser from existing scanner
+    pub fn from_scanner(scanner: BasicScanner) -> Self {
+        let mut parser = Self::with_scanner(scanner);
+        if let Err(error) = parser.parse_all() {
+            parser.scanning_error = Some(error);
+        }
         parser
     }
 
     /// Parse all tokens into events
     fn parse_all(&mut self) -> Result<()> {
+        // An eagerly scanned input yields about one event per token
+        self.events.reserve(self.scanner.tokens_scanned());
+
         while self.scanner.check_token() {
             let token = match self.scanner.get_token()? {
                 Some(token) => token,
@@ -189,6 +192,44 @@ impl BasicParser {
         Ok(())
     }
 
+    /// Take the warnings about the directives of the document starting at
+    /// `position`
+    pub(crate) fn take_directive_warnings(&mut self, position: Position) -> Vec<String> {
+        self.scanner.take_warnings_before(position)
+    }
+
+    /// Number of tokens scanned so far
+    pub fn tokens_scanned(&self) -> usize {
+        self.scanner.tokens_scanned()
+    }
+}
+
+impl<S: Scanner> BasicParser<S> {
+    /// Create a parser that parses lazily from the tokens of `scanner`
+    pub fn with_scanner(scanner: S) -> Self {
+        let position = scanner.position();
+
+        Self {
+            scanner,
+            events: Vec::new(),
+            event_index: 0,
+            current_event: None,
+            state: ParserState::StreamStart,
+            state_stack: Vec::new(),
+            flow_starts: Vec::new(),
+            position,
+            pending_anchor: None,
+            pending_tag: None,
+            last_token_type: None,
+            last_token_line: 0,
+            key_complete: false,
+            scanning_error: None,
+            yaml_version: None,
+            tag_directives: Vec::new(),
+            tag_resolver: TagResolver::new(),
+        }
+    }
+
     /// Create implicit document start event with directives
     fn create_implicit_document_start(&mut self, position: Position) -> Event {
         let event = Event::document_start(
@@ -214,19 +255,22 @@ impl BasicParser {
                         "Close the sequence with proper indentation or closing bracket".to_string(),
                     );
                 Err(Error::unclosed_delimiter_with_context(
-                    self.position,
+                    self.open_collection_start(),
                     self.position,
                     "sequence",
                     context,
                 ))
             }
-            ParserState::BlockMapping | ParserState::FlowMapping => {
+            ParserState::BlockMapping
+            | ParserState::FlowMapping
+            | ParserState::FlowMappingKey
+            | ParserState::FlowMappingValue => {
                 let context = ErrorContext::from_input(self.scanner.input(), &self.position, 2)
                     .with_suggestion(
                         "Close the mapping with proper indentation or closing brace".to_string(),
                     );
                 Err(Error::unclosed_delimiter_with_context(
-                    self.position,
+                    self.open_collection_start(),
                     self.position,
                     "mapping",
                     context,
@@ -244,6 +288,20 @@ impl BasicParser {
         }
     }
 
+    /// Where the innermost open flow collection starts, or the current
+    /// position when only block collections are open
+    fn open_collection_start(&self) -> Position {
+        match self.state {
+            ParserState::FlowSequence
+            | ParserState::FlowMapping
+            | ParserState::FlowMappingKey
+            | ParserState::FlowMappingValue => {
+                self.flow_starts.last().copied().unwrap_or(self.position)
+            }
+            _ => self.position,
+        }
+    }
+
     /// Generate the next event by processing the next token
     fn generate_next_event(&mut self) -> Result<()> {
         if let Some(token) = self.scanner.get_token()? {
@@ -295,13 +353,26 @@ impl BasicParser {
             }
         };
 
-        match &token.token_type {
+        match token.token_type {
             TokenType::StreamStart => {
                 self.events.push(Event::stream_start(token.start_position));
                 self.state = ParserState::ImplicitDocumentStart;
             }
 
             TokenType::StreamEnd => {
+                // A flow collection must be closed before the input ends
+                if matches!(
+                    self.state,
+                    ParserState::FlowSequence
+                        | ParserState::FlowMapping
+                        | ParserState::FlowMappingKey
+                        | ParserState::FlowMappingValue
+                ) {
+                    return self.validate_final_state();
+                }
+                if matches!(self.state, ParserState::BlockSequence) {
+                    self.complete_empty_entry(token.start_position);
+                }
                 // Close any open document
                 if matches!(
                     self.state,
@@ -316,15 +387,14 @@ impl BasicParser {
 
             TokenType::YamlDirective(major, minor) => {
                 // Store YAML version directive
-                self.yaml_version = Some((*major, *minor));
+                self.yaml_version = Some((major, minor));
                 // Stay in stream state waiting for document
             }
 
             TokenType::TagDirective(handle, prefix) => {
                 // Store tag directive and update tag resolver
                 self.tag_directives.push((handle.clone(), prefix.clone()));
-                self.tag_resolver
-                    .add_directive(handle.clone(), prefix.clone());
+                self.tag_resolver.add_directive(handle, prefix);
                 // Stay in stream state waiting for document
             }
 
@@ -365,10 +435,13 @@ impl BasicParser {
                     self.events.push(event);
                 }
 
-                // If we're starting a sequence within a mapping context, push the current state
+                // If we're starting a sequence within a mapping or sequence context, push the
+                // current state
                 if matches!(
                     self.state,
-                    ParserState::BlockMappingValue | ParserState::BlockMappingKey
+                    ParserState::BlockMappingValue
+                        | ParserState::BlockMappingKey
+                        | ParserState::BlockSequence
                 ) {
                     self.state_stack.push(self.state);
                 }
@@ -459,10 +532,12 @@ impl BasicParser {
                         self.events.push(event);
                     }
 
-                    // If we're in a mapping value or sequence context, push state to stack
+                    // If we're in a mapping key, value or sequence context, push state to stack
                     if matches!(
                         self.state,
-                        ParserState::BlockMappingValue | ParserState::BlockSequence
+                        ParserState::BlockMappingKey
+                            | ParserState::BlockMappingValue
+                            | ParserState::BlockSequence
                     ) {
                         self.state_stack.push(self.state);
                     }
@@ -519,6 +594,12 @@ impl BasicParser {
                     self.pending_tag.take(),
                     true,
                 ));
+                self.flow_starts.push(token.start_position);
+                // Remember the enclosing context so it can be resumed at the end
+                self.state_stack.push(match self.state {
+                    ParserState::ImplicitDocumentStart => ParserState::DocumentContent,
+                    state => state,
+                });
                 self.state = ParserState::FlowSequence;
             }
 
@@ -537,12 +618,22 @@ impl BasicParser {
                     self.pending_tag.take(),
                     true,
                 ));
+                self.flow_starts.push(token.start_position);
+                // Remember the enclosing context so it can be resumed at the end
+                self.state_stack.push(match self.state {
+                    ParserState::ImplicitDocumentStart => ParserState::DocumentContent,
+                    state => state,
+                });
                 self.state = ParserState::FlowMapping;
             }
 
             TokenType::FlowSequenceEnd => {
                 self.events.push(Event::sequence_end(token.start_position));
-                self.state = ParserState::DocumentContent;
+                self.flow_starts.pop();
+                self.state = self
+                    .state_stack
+                    .pop()
+                    .unwrap_or(ParserState::DocumentContent);
 
                 // Handle state transitions for mapping key/value processing
                 self.handle_node_completion();
@@ -550,7 +641,11 @@ impl BasicParser {
 
             TokenType::FlowMappingEnd => {
                 self.events.push(Event::mapping_end(token.start_position));
-                self.state = ParserState::DocumentContent;
+                self.flow_starts.pop();
+                self.state = self
+                    .state_stack
+                    .pop()
+                    .unwrap_or(ParserState::DocumentContent);
 
                 // Handle state transitions for mapping key/value processing
                 self.handle_node_completion();
@@ -560,6 +655,7 @@ impl BasicParser {
                 // Determine what we're ending based on current state
                 match self.state {
                     ParserState::BlockSequence => {
+                        self.complete_empty_entry(token.start_position);
                         self.events.push(Event::sequence_end(token.start_position));
                         // Pop previous state from stack if available
                         if let Some(prev_state) = self.state_stack.pop() {
@@ -573,12 +669,26 @@ impl BasicParser {
                     ParserState::BlockMapping
                     | ParserState::BlockMappingKey
                     | ParserState::BlockMappingValue => {
+                        // The last value of the mapping was left empty, as in
+                        // `a:\n  b:\nc: 1`
+                        if matches!(self.state, ParserState::BlockMappingValue)
+                            && matches!(
+                                self.last_token_type,
+                                Some(TokenType::Value | TokenType::Anchor(_) | TokenType::Tag(_))
+                            )
+                        {
+                            self.push_empty_scalar(token.start_position);
+                        }
+                        self.complete_explicit_key(token.start_position);
                         self.events.push(Event::mapping_end(token.start_position));
                         // Pop previous state from stack if available
                         if let Some(prev_state) = self.state_stack.pop() {
                             self.state = prev_state;
-                            // If we popped back to a mapping value state, complete it
-                            if matches!(self.state, ParserState::BlockMappingValue) {
+                            // If we popped back to a mapping key or value state, complete it
+                            if matches!(
+                                self.state,
+                                ParserState::BlockMappingKey | ParserState::BlockMappingValue
+                            ) {
                                 self.handle_node_completion();
                             }
                         } else {
@@ -624,6 +734,24 @@ impl BasicParser {
                     self.state = ParserState::DocumentContent;
                 }
 
+                // A key on a later line than the previous `:` means that value was
+                // left empty, as in `a:\nb: 1`
+                if matches!(self.state, ParserState::BlockMappingValue)
+                    && matches!(
+                        self.last_token_type,
+                        Some(TokenType::Value | TokenType::Anchor(_) | TokenType::Tag(_))
+                    )
+                    && token.start_position.line > self.last_token_line
+                    && matches!(
+                        self.scanner.peek_token(),
+                        Ok(Some(next)) if matches!(next.token_type, TokenType::Value)
+                    )
+                {
+                    self.push_empty_scalar(token.start_position);
+                    self.handle_node_completion();
+                }
+                self.complete_explicit_key(token.start_position);
+
                 // Check if we're in a sequence and the next token is Value (indicating a mapping key)
                 if matches!(self.state, ParserState::BlockSequence) {
                     if let Ok(Some(next_token)) = self.scanner.peek_token() {
@@ -653,7 +781,7 @@ impl BasicParser {
                     token.start_position,
                     self.pending_anchor.take(), // Use pending anchor
                     self.pending_tag.take(),    // Use pending tag
-                    value.clone(),
+                    value,
                     style == ScalarStyle::Plain,
                     style != ScalarStyle::Plain,
                     style,
@@ -678,7 +806,7 @@ impl BasicParser {
                     token.start_position,
                     self.pending_anchor.take(), // Use pending anchor
                     self.pending_tag.take(),    // Use pending tag
-                    value.clone(),
+                    value,
                     false, // Not plain
                     true,  // Quoted style
                     ScalarStyle::Literal,
@@ -703,7 +831,7 @@ impl BasicParser {
                     token.start_position,
                     self.pending_anchor.take(), // Use pending anchor
                     self.pending_tag.take(),    // Use pending tag
-                    value.clone(),
+                    value,
                     false, // Not plain
                     true,  // Quoted style
                     ScalarStyle::Folded,
@@ -718,8 +846,9 @@ impl BasicParser {
                 // We need to ensure proper state management for nested structures
                 match self.state {
                     ParserState::BlockSequence => {
-                        // We're already in a sequence, this is a new item
-                        // No event needed, but we should be ready for the next item
+                        // We're already in a sequence, this is a new item; the
+                        // previous item was left empty, as in `-\n- a`
+                        self.complete_empty_entry(token.start_position);
                     }
                     ParserState::BlockMapping | ParserState::BlockMappingValue => {
                         // If we encounter a BlockEntry while in a mapping,
@@ -755,6 +884,11 @@ impl BasicParser {
                 // Key-value separator in mappings
                 match self.state {
                     ParserState::BlockMappingKey => {
+                        // An explicit key left empty, as in `?\n: value`
+                        if !self.key_complete {
+                            self.push_empty_scalar(token.start_position);
+                        }
+                        self.key_complete = false;
                         self.state = ParserState::BlockMappingValue;
                     }
                     ParserState::FlowMappingKey => {
@@ -773,7 +907,7 @@ impl BasicParser {
 
             TokenType::Anchor(name) => {
                 // Store the anchor name to be used with the next node
-                self.pending_anchor = Some(name.clone());
+                self.pending_anchor = Some(name);
             }
 
             TokenType::Alias(name) => {
@@ -787,25 +921,20 @@ impl BasicParser {
                     self.state = ParserState::DocumentContent;
                 }
 
+                self.complete_explicit_key(token.start_position);
+
                 // Generate alias event
-                self.events
-                    .push(Event::alias(token.start_position, name.clone()));
+                self.events.push(Event::alias(token.start_position, name));
 
                 // Handle state transitions for mapping key/value processing
                 self.handle_node_completion();
             }
 
             TokenType::Tag(tag) => {
-                // Resolve and normalize the tag before storing
-                match self.tag_resolver.resolve(&tag) {
-                    Ok(resolved_tag) => {
-                        self.pending_tag = Some(resolved_tag.uri);
-                    }
-                    Err(_) => {
-                        // If tag resolution fails, store the original tag
-                        self.pending_tag = Some(tag.clone());
-                    }
-                }
+                // Expand the handle before storing; a named handle without a
+                // directive is left for the composer to resolve
+                let expanded = self.tag_resolver.expand(&tag, token.start_position)?;
+                self.pending_tag = Some(expanded.unwrap_or(tag));
             }
 
             // TODO: Implement these when we add support for advanced features
@@ -815,6 +944,8 @@ impl BasicParser {
 
             // Complex key marker
             TokenType::Key => {
+                self.complete_explicit_key(token.start_position);
+                self.key_complete = false;
                 match self.state {
                     ParserState::ImplicitDocumentStart => {
                         // Start implicit document and mapping
@@ -903,6 +1034,7 @@ impl BasicParser {
 
         // Update the last token type for next iteration
         self.last_token_type = token_type_for_tracking;
+        self.last_token_line = token.end_position.line;
 
         Ok(())
     }
@@ -914,7 +1046,7 @@ impl BasicParser {
             ParserState::BlockMappingKey => {
                 // After processing a key, we stay in BlockMappingKey state
                 // The Value token (:) will transition us to BlockMappingValue
-                // No state change needed here
+                self.key_complete = true;
             }
             ParserState::FlowMappingKey => {
                 // After processing a key in flow mapping, we stay in FlowMappingKey state
@@ -924,6 +1056,7 @@ impl BasicParser {
             ParserState::BlockMappingValue => {
                 // After processing a value, we go back to waiting for the next key
                 self.state = ParserState::BlockMappingKey;
+                self.key_complete = false;
             }
             ParserState::FlowMappingValue => {
                 // After processing a value in flow mapping, we go back to waiting for the next key
@@ -934,6 +1067,40 @@ impl BasicParser {
             }
         }
     }
+
+    /// Emit the empty plain scalar of a key or value left out of a mapping
+    /// entry, carrying any pending anchor and tag
+    fn push_empty_scalar(&mut self, position: Position) {
+        self.events.push(Event::scalar(
+            position,
+            self.pending_anchor.take(),
+            self.pending_tag.take(),
+            String::new(),
+            true,
+            false,
+            ScalarStyle::Plain,
+        ));
+    }
+
+    /// Give a block sequence entry with no content an empty node, as the
+    /// first entry of `-\n- a` or the last one of `- a\n-`
+    fn complete_empty_entry(&mut self, position: Position) {
+        if matches!(
+            self.last_token_type,
+            Some(TokenType::BlockEntry | TokenType::Anchor(_) | TokenType::Tag(_))
+        ) {
+            self.push_empty_scalar(position);
+        }
+    }
+
+    /// Give an explicit `? key` that has no `:` value an empty value before
+    /// the next entry or the end of its mapping, as in `? a\nb: 1`
+    fn complete_explicit_key(&mut self, position: Position) {
+        if matches!(self.state, ParserState::BlockMappingKey) && self.key_complete {
+            self.push_empty_scalar(position);
+            self.key_complete = false;
+        }
+    }
 }
 
 impl Default for BasicParser {
@@ -942,48 +1109,48 @@ impl Default for BasicParser {
     }
 }
 
-impl Parser for BasicParser {
-    fn check_event(&self) -> bool {
-        // For streaming: check if we have cached events or can generate more
-        self.event_index < self.events.len() || self.scanner.check_token()
+impl<S: Scanner> Parser for BasicParser<S> {
+    fn check_event(&mut self) -> bool {
+        // A pending error is reported by the next `get_event`
+        self.peek_event().is_some()
+            || self.scanning_error.is_some()
+            || self.scanner.has_scanning_error()
     }
 
-    fn peek_event(&self) -> Result<Option<&Event>> {
-        // Peek at cached events only (don't generate new ones)
-        Ok(self.events.get(self.event_index))
+    fn peek_event(&mut self) -> Option<&Event> {
+        if self.current_event.is_none() && self.scanning_error.is_none() {
+            match self.next_event() {
+                Ok(event) => self.current_event = event,
+                Err(error) => self.scanning_error = Some(error),
+            }
+        }
+        self.current_event.as_ref()
     }
 
     fn get_event(&mut self) -> Result<Option<Event>> {
-        // Generate next events until we have one available
-        // Some tokens (like directives) don't generate events
-        while self.event_index >= self.events.len() && self.scanner.check_token() {
-            let events_before = self.events.len();
-            self.generate_next_event()?;
-
-            // If no event was generated and we still have tokens, continue
-            if self.events.len() == events_before && self.scanner.check_token() {
-                continue;
-            }
-            break;
+        // An error found while scanning or parsing ahead of the events is
+        // reported before any of them
+        if let Some(error) = self.take_scanning_error() {
+            return Err(error);
         }
-
-        if self.event_index < self.events.len() {
-            let event = self.events[self.event_index].clone();
-            self.event_index += 1;
-            Ok(Some(event))
-        } else {
-            Ok(None)
+        match self.current_event.take() {
+            Some(event) => Ok(Some(event)),
+            None => self.next_event(),
         }
     }
 
     fn reset(&mut self) {
+        self.events.clear();
         self.event_index = 0;
+        self.current_event = None;
         self.scanner.reset();
         self.state_stack.clear();
+        self.flow_starts.clear();
         self.position = Position::start();
         self.pending_anchor = None;
         self.pending_tag = None;
         self.last_token_type = None;
+        self.last_token_line = 0;
     }
 
     fn position(&self) -> Position {
@@ -991,18 +1158,140 @@ impl Parser for BasicParser {
     }
 }
 
-impl BasicParser {
-    /// Check if there was a scanning error
-    #[allow(clippy::missing_const_for_fn)]
+impl<S: Scanner> BasicParser<S> {
+    /// Parse the event after those already returned, moving it out of the
+    /// event history
+    fn next_event(&mut self) -> Result<Option<Event>> {
+        if let Some(error) = self.take_scanning_error() {
+            return Err(error);
+        }
+
+        // Generate next events until we have one available
+        // Some tokens (like directives) don't generate events
+        while self.event_index >= self.events.len() && self.scanner.check_token() {
+            let events_before = self.events.len();
+            self.generate_next_event()?;
+
+            // If no event was generated and we still have tokens, continue
+            if self.events.len() == events_before && self.scanner.check_token() {
+                continue;
+            }
+            break;
+        }
+
+        let Some(event) = self.events.get_mut(self.event_index) else {
+            return Ok(None);
+        };
+        self.event_index += 1;
+        Ok(Some(take_event(event)))
+    }
+
+    /// Take the error found while scanning or parsing ahead of the events,
+    /// which [`Parser::get_event`] otherwise reports
     pub fn take_scanning_error(&mut self) -> Option<Error> {
-        self.scanning_error.take()
+        self.scanning_error
+            .take()
+            .or_else(|| self.scanner.take_scanning_error())
+    }
+
+    /// The input text being parsed, for errors that quote it
+    pub fn input(&self) -> &str {
+        self.scanner.input()
+    }
+
+    /// The scanner the tokens come from
+    pub fn scanner(&self) -> &S {
+        &self.scanner
+    }
+
+    /// Number of events produced so far
+    pub fn events_parsed(&self) -> usize {
+        self.events.len()
     }
 }
 
+/// Move the contents out of `event`, leaving an event of the same kind and
+/// position behind without allocating
+fn take_event(event: &mut Event) -> Event {
+    let husk = match &event.event_type {
+        EventType::DocumentStart {
+            version, implicit, ..
+        } => EventType::DocumentStart {
+            version: *version,
+            tags: Vec::new(),
+            implicit: *implicit,
+        },
+        EventType::Scalar {
+            plain_implicit,
+            quoted_implicit,
+            style,
+            ..
+        } => EventType::Scalar {
+            anchor: None,
+            tag: None,
+            value: String::new(),
+            plain_implicit: *plain_implicit,
+            quoted_implicit: *quoted_implicit,
+            style: *style,
+        },
+        EventType::SequenceStart { flow_style, .. } => EventType::SequenceStart {
+            anchor: None,
+            tag: None,
+            flow_style: *flow_style,
+        },
+        EventType::MappingStart { flow_style, .. } => EventType::MappingStart {
+            anchor: None,
+            tag: None,
+            flow_style: *flow_style,
+        },
+        EventType::Alias { .. } => EventType::Alias {
+            anchor: String::new(),
+        },
+        event_type => event_type.clone(),
+    };
+    let position = event.position;
+    std::mem::replace(
+        event,
+        Event {
+            event_type: husk,
+            position,
+        },
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_peek_event_fills_current_event() {
+        for mut parser in [
+            BasicParser::new("a: [x, &y z]\n".to_string()),
+            BasicParser::new_eager("a: [x, &y z]\n".to_string()),
+        ] {
+            let mut events = Vec::new();
+            while parser.check_event() {
+                let peeked = parser.peek_event().cloned();
+                let event = parser.get_event().unwrap();
+                assert_eq!(peeked, event);
+                events.extend(event);
+            }
+            assert!(events.iter().any(|event| matches!(
+                &event.event_type,
+                EventType::Scalar { anchor: Some(anchor), value, .. } if anchor == "y" && value == "z"
+            )));
+            assert_eq!(parser.peek_event(), None);
+        }
+
+        // An error found while peeking is returned by the next `get_event`
+        let mut parser = BasicParser::new("a: [".to_string());
+        while parser.peek_event().is_some() {
+            parser.get_event().unwrap();
+        }
+        assert!(parser.check_event());
+        assert!(parser.get_event().is_err());
+    }
+
     #[test]
     fn test_basic_parsing() {
         let mut parser = BasicParser::new_eager("42".to_string());
@@ -1042,6 +1331,27 @@ mod tests {
         assert!(matches!(event.event_type, EventType::StreamEnd));
     }
 
+    #[test]
+    fn test_scanning_errors_are_reported() {
+        assert!(BasicParser::try_new_eager("&".to_string()).is_err());
+        assert!(BasicParser::try_new_eager("a: b".to_string()).is_ok());
+
+        // Errors found ahead of the events come before any of them
+        let mut parser = BasicParser::new_eager("key: &".to_string());
+        assert!(parser.get_event().is_err());
+
+        let limits = Limits {
+            max_document_size: 3,
+            ..Limits::default()
+        };
+        let mut parser = BasicParser::with_limits("a: b".to_string(), limits);
+        assert!(parser.check_event());
+        assert!(matches!(
+            parser.get_event(),
+            Err(Error::LimitExceeded { .. })
+        ));
+    }
+
     #[test]
     fn test_flow_sequence_parsing() {
         let mut parser = BasicParser::new_eager("[1, 2, 3]".to_string());
@@ -1095,4 +1405,75 @@ mod tests {
             panic!("Expected scalar 'key'");
         }
     }
+
+    #[test]
+    fn test_flow_collection_resumes_block_context() {
+        let collect = |input: &str| {
+            let mut parser = BasicParser::new_eager(input.to_string());
+            let mut events = Vec::new();
+            while let Ok(Some(event)) = parser.get_event() {
+                events.push(event.event_type);
+            }
+            events
+        };
+
+        // The nested mapping still closes at the dedent after the flow sequence
+        let events = collect("outer:\n  inner: [1]\nnext: 2\n");
+        let index = events
+            .iter()
+            .position(|event| matches!(event, EventType::SequenceEnd))
+            .unwrap();
+        assert!(matches!(events[index + 1], EventType::MappingEnd));
+
+        // Flow items stay siblings in the enclosing block sequence
+        let events = collect("- [1]\n- [2]\n");
+        let mut depth = 0;
+        let mut max_depth = 0;
+        for event in &events {
+            match event {
+                EventType::SequenceStart { .. } => depth += 1,
+                EventType::SequenceEnd => depth -= 1,
+                _ => {}
+            }
+            max_depth = max_depth.max(depth);
+        }
+        assert_eq!(max_depth, 2);
+    }
+
+    #[test]
+    fn test_compact_nested_sequences() {
+        let trace = |input: &str| {
+            let mut parser = BasicParser::new_eager(input.to_string());
+            let mut trace = String::new();
+            while let Some(event) = parser.get_event().unwrap() {
+                trace.push_str(&event.to_test_format());
+                trace.push('\n');
+            }
+            trace
+        };
+
+        // Block Sequence in Block Sequence (3ALJ)
+        assert_eq!(
+            trace("- - s1_i1\n  - s1_i2\n- s2\n"),
+            "+STR\n+DOC\n+SEQ\n+SEQ\n=VAL :s1_i1\n=VAL :s1_i2\n-SEQ\n=VAL :s2\n-SEQ\n-DOC\n-STR\n"
+        );
+        // Spec Example 8.15. Block Sequence Entry Types (W42U)
+        assert_eq!(
+            trace("- # Empty\n- |\n block node\n- - one # Compact\n  - two # sequence\n- one: two # Compact mapping\n"),
+            "+STR\n+DOC\n+SEQ\n=VAL :\n=VAL |block node\\n\n+SEQ\n=VAL :one\n=VAL :two\n-SEQ\n\
+             +MAP\n=VAL :one\n=VAL :two\n-MAP\n-SEQ\n-DOC\n-STR\n"
+        );
+        assert_eq!(
+            trace("- - - value\n"),
+            "+STR\n+DOC\n+SEQ\n+SEQ\n+SEQ\n=VAL :value\n-SEQ\n-SEQ\n-SEQ\n-DOC\n-STR\n"
+        );
+        assert_eq!(
+            trace("- - - a\n    - b\n  - c\n- d\n"),
+            "+STR\n+DOC\n+SEQ\n+SEQ\n+SEQ\n=VAL :a\n=VAL :b\n-SEQ\n=VAL :c\n-SEQ\n=VAL :d\n-SEQ\n-DOC\n-STR\n"
+        );
+        assert_eq!(
+            trace("- -\n  - x\n-\n"),
+            "+STR\n+DOC\n+SEQ\n+SEQ\n=VAL :\n=VAL :x\n-SEQ\n=VAL :\n-SEQ\n-DOC\n-STR\n"
+        );
+    }
 }
@@ -4,12 +4,13 @@
 //! reducing memory usage and improving performance for large documents.
 
 use crate::{
-    parser::{Event, Parser, ParserState},
+    composer::resolve_scalar,
+    parser::{BasicParser, Event, EventType, Parser},
+    profiling::Stopwatch,
     zerocopy::ScannerStats,
-    BasicScanner, Error, Position, Result, Scanner, Token, TokenType, ZeroScanner, ZeroToken,
-    ZeroTokenType,
+    BasicScanner, Error, Limits, Position, Result, Value, ZeroScanner,
 };
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 
 /// Configuration for streaming parser behavior
 #[derive(Debug, Clone)]
@@ -22,6 +23,14 @@ pub struct StreamingConfig {
     pub max_depth: usize,
     /// Enable streaming statistics collection
     pub collect_stats: bool,
+    /// Fail on a scalar key repeated within one mapping
+    pub reject_duplicate_keys: bool,
+    /// Fail on an invalid escape sequence in a double-quoted scalar instead
+    /// of keeping it literally
+    pub strict_escapes: bool,
+    /// Maximum length of a single scalar in bytes, see
+    /// [`Limits::max_scalar_len`]
+    pub max_scalar_len: usize,
 }
 
 impl Default for StreamingConfig {
@@ -31,6 +40,88 @@ impl Default for StreamingConfig {
             use_zero_copy: true,
             max_depth: 256,
             collect_stats: false,
+            reject_duplicate_keys: false,
+            strict_escapes: false,
+            max_scalar_len: Limits::default().max_scalar_len,
+        }
+    }
+}
+
+/// Detects scalar keys repeated within one mapping as the events of a
+/// stream go by, for parsers that never build the mapping
+///
+/// Keys are compared as the composer would load them, so `1` and `"1"`
+/// differ while `a` and `'a'` are the same key. Collection keys, aliases and
+/// merge keys (`<<`) are not checked.
+#[derive(Debug, Default)]
+pub(crate) struct DuplicateKeys {
+    /// Open collections, innermost last
+    frames: Vec<KeyFrame>,
+}
+
+/// An open collection seen by [`DuplicateKeys`]
+#[derive(Debug)]
+enum KeyFrame {
+    Sequence,
+    Mapping {
+        /// Whether the next node is a key
+        expecting_key: bool,
+        /// Keys seen so far and where each was seen
+        keys: HashMap<(Option<String>, Value), Position>,
+    },
+}
+
+impl DuplicateKeys {
+    /// Track `event`, failing if it repeats a key of the enclosing mapping
+    pub(crate) fn check(&mut self, event: &Event) -> Result<()> {
+        match &event.event_type {
+            EventType::Scalar {
+                tag, value, style, ..
+            } => {
+                if let Some(KeyFrame::Mapping {
+                    expecting_key: true,
+                    keys,
+                }) = self.frames.last_mut()
+                {
+                    let key = match tag {
+                        Some(tag) => (Some(tag.clone()), Value::String(value.clone())),
+                        None => (None, resolve_scalar(value.clone(), *style)),
+                    };
+                    let merge = key == (None, Value::String("<<".to_string()));
+                    let position = event.position;
+                    if let Some(previous) = keys.insert(key, position).filter(|_| !merge) {
+                        return Err(Error::construction(
+                            position,
+                            format!(
+                                "Duplicate key '{value}' at {position} repeats the key at {previous}"
+                            ),
+                        ));
+                    }
+                }
+                self.end_node();
+            }
+            EventType::Alias { .. } => self.end_node(),
+            EventType::SequenceStart { .. } => self.frames.push(KeyFrame::Sequence),
+            EventType::MappingStart { .. } => self.frames.push(KeyFrame::Mapping {
+                expecting_key: true,
+                keys: HashMap::new(),
+            }),
+            EventType::SequenceEnd | EventType::MappingEnd => {
+                self.frames.pop();
+                self.end_node();
+            }
+            EventType::StreamStart
+            | EventType::StreamEnd
+            | EventType::DocumentStart { .. }
+            | EventType::DocumentEnd { .. } => self.frames.clear(),
+        }
+        Ok(())
+    }
+
+    /// Alternate between key and value after a node of a mapping
+    fn end_node(&mut self) {
+        if let Some(KeyFrame::Mapping { expecting_key, .. }) = self.frames.last_mut() {
+            *expecting_key = !*expecting_key;
         }
     }
 }
@@ -53,113 +144,129 @@ pub struct StreamingStats {
 }
 
 /// Streaming YAML parser that processes events on demand
+///
+/// The events come from a [`BasicParser`] over either a [`BasicScanner`] or,
+/// for [`StreamingParser::new_zero_copy`], a [`ZeroScanner`] that borrows the
+/// input, so both modes parse the same events as [`Yaml`](crate::Yaml).
 pub struct StreamingParser<'a> {
-    /// Traditional scanner for compatibility
-    scanner: Option<BasicScanner>,
-    /// Zero-copy scanner for optimized parsing
-    zero_scanner: Option<ZeroScanner<'a>>,
+    /// Parser the events come from
+    source: EventSource<'a>,
     /// Configuration
     config: StreamingConfig,
     /// Event buffer for batched processing
     event_buffer: VecDeque<Event>,
-    /// Current parser state
-    state: ParserState,
-    /// State stack for nested structures
-    state_stack: Vec<ParserState>,
-    /// Current position
+    /// Position of the last event returned
     position: Position,
-    /// Current nesting depth
+    /// Nesting depth after the buffered events
     depth: usize,
-    /// Pending anchor for next node
-    pending_anchor: Option<String>,
-    /// Pending tag for next node
-    pending_tag: Option<String>,
     /// Statistics (if enabled)
     stats: Option<StreamingStats>,
     /// Start time for performance measurement
-    start_time: std::time::Instant,
-    /// Whether the stream has ended
+    start_time: Stopwatch,
+    /// Whether the stream end has been buffered or the source has failed
     stream_ended: bool,
+    /// Duplicate key detection, when enabled
+    duplicate_keys: Option<DuplicateKeys>,
+    /// An error found while parsing ahead, reported after the buffered events
+    pending_error: Option<Error>,
+}
+
+/// The parser a [`StreamingParser`] takes its events from
+enum EventSource<'a> {
+    /// Tokens from a [`BasicScanner`] owning the input
+    Traditional(Box<BasicParser>),
+    /// Tokens from a [`ZeroScanner`] borrowing the input
+    ZeroCopy(Box<BasicParser<ZeroScanner<'a>>>),
+}
+
+impl EventSource<'_> {
+    fn get_event(&mut self) -> Result<Option<Event>> {
+        match self {
+            Self::Traditional(parser) => parser.get_event(),
+            Self::ZeroCopy(parser) => parser.get_event(),
+        }
+    }
+
+    fn reset(&mut self) {
+        match self {
+            Self::Traditional(parser) => parser.reset(),
+            Self::ZeroCopy(parser) => parser.reset(),
+        }
+    }
+
+    fn tokens_scanned(&self) -> usize {
+        match self {
+            Self::Traditional(parser) => parser.tokens_scanned(),
+            Self::ZeroCopy(parser) => parser.scanner().tokens_scanned(),
+        }
+    }
 }
 
 impl<'a> StreamingParser<'a> {
     /// Create a new streaming parser with traditional scanner
     pub fn new(input: String, config: StreamingConfig) -> StreamingParser<'static> {
-        let scanner = BasicScanner::new(input);
-        let position = scanner.position();
-
-        StreamingParser {
-            scanner: Some(scanner),
-            zero_scanner: None,
-            config: config.clone(),
-            event_buffer: VecDeque::with_capacity(config.max_buffer_size),
-            state: ParserState::StreamStart,
-            state_stack: Vec::with_capacity(config.max_depth),
-            position,
-            depth: 0,
-            pending_anchor: None,
-            pending_tag: None,
-            stats: if config.collect_stats {
-                Some(StreamingStats {
-                    events_processed: 0,
-                    tokens_processed: 0,
-                    max_buffer_size: 0,
-                    max_depth: 0,
-                    scanner_stats: None,
-                    parse_time_ns: 0,
-                })
-            } else {
-                None
-            },
-            start_time: std::time::Instant::now(),
-            stream_ended: false,
-        }
+        let limits = Limits {
+            max_scalar_len: config.max_scalar_len,
+            ..Limits::default()
+        };
+        let mut scanner = BasicScanner::with_limits(input, limits);
+        scanner.set_strict_escapes(config.strict_escapes);
+        StreamingParser::with_source(
+            EventSource::Traditional(Box::new(BasicParser::with_scanner(scanner))),
+            config,
+        )
     }
 
     /// Create a new streaming parser with zero-copy scanner
+    ///
+    /// With [`StreamingConfig::use_zero_copy`] unset the input is copied and
+    /// scanned as by [`StreamingParser::new`].
     pub fn new_zero_copy(input: &'a str, config: StreamingConfig) -> Self {
-        let zero_scanner = ZeroScanner::new(input);
-        let position = zero_scanner.position;
+        if !config.use_zero_copy {
+            return StreamingParser::new(input.to_string(), config);
+        }
+        let mut scanner = ZeroScanner::new(input);
+        scanner.set_strict_escapes(config.strict_escapes);
+        scanner.set_max_scalar_len(config.max_scalar_len);
+        Self::with_source(
+            EventSource::ZeroCopy(Box::new(BasicParser::with_scanner(scanner))),
+            config,
+        )
+    }
 
+    fn with_source(source: EventSource<'a>, config: StreamingConfig) -> Self {
         Self {
-            scanner: None,
-            zero_scanner: Some(zero_scanner),
-            config: config.clone(),
+            source,
             event_buffer: VecDeque::with_capacity(config.max_buffer_size),
-            state: ParserState::StreamStart,
-            state_stack: Vec::with_capacity(config.max_depth),
-            position,
+            position: Position::start(),
             depth: 0,
-            pending_anchor: None,
-            pending_tag: None,
-            stats: if config.collect_stats {
-                Some(StreamingStats {
-                    events_processed: 0,
-                    tokens_processed: 0,
-                    max_buffer_size: 0,
-                    max_depth: 0,
-                    scanner_stats: None,
-                    parse_time_ns: 0,
-                })
-            } else {
-                None
-            },
-            start_time: std::time::Instant::now(),
+            stats: config.collect_stats.then_some(StreamingStats {
+                events_processed: 0,
+                tokens_processed: 0,
+                max_buffer_size: 0,
+                max_depth: 0,
+                scanner_stats: None,
+                parse_time_ns: 0,
+            }),
+            start_time: Stopwatch::start(),
             stream_ended: false,
+            duplicate_keys: config.reject_duplicate_keys.then(DuplicateKeys::default),
+            pending_error: None,
+            config,
         }
     }
 
     /// Get the next batch of events
     pub fn next_batch(&mut self) -> Result<Vec<Event>> {
-        if self.stream_ended {
+        if !self.has_more_events() {
             return Ok(Vec::new());
         }
 
         let mut events = Vec::new();
         let target_size = std::cmp::min(self.config.max_buffer_size / 2, 8);
 
-        while events.len() < target_size && !self.stream_ended {
-            if let Some(event) = self.next_event_internal()? {
+        while events.len() < target_size && self.has_more_events() {
+            if let Some(event) = self.get_event()? {
                 events.push(event);
             } else {
                 break;
@@ -171,428 +278,79 @@ impl<'a> StreamingParser<'a> {
 
     /// Get the next event from buffer or generate a new one
     fn next_event_internal(&mut self) -> Result<Option<Event>> {
-        // Return buffered event if available
-        if let Some(event) = self.event_buffer.pop_front() {
-            self.update_stats_for_event(&event);
-            return Ok(Some(event));
+        // Generate new event(s) unless some are buffered
+        if self.event_buffer.is_empty() {
+            self.generate_events()?;
         }
 
-        // Generate new event(s)
-        self.generate_events()?;
-
         // Return the first buffered event
-        if let Some(event) = self.event_buffer.pop_front() {
-            self.update_stats_for_event(&event);
-            Ok(Some(event))
-        } else {
-            Ok(None)
+        let Some(event) = self.event_buffer.pop_front() else {
+            return Ok(None);
+        };
+        self.position = event.position;
+        self.update_stats_for_event(&event);
+        if let Some(ref mut duplicate_keys) = self.duplicate_keys {
+            duplicate_keys.check(&event)?;
         }
+        Ok(Some(event))
     }
 
-    /// Generate events by processing tokens
+    /// Buffer the next events of the source, up to half the buffer size
+    ///
+    /// An error after some events is kept in `pending_error` and reported
+    /// once those events are taken.
     fn generate_events(&mut self) -> Result<()> {
-        if self.stream_ended {
-            return Ok(());
-        }
-
-        // Check depth limit
-        if self.depth > self.config.max_depth {
-            return Err(Error::parse(
-                self.position,
-                format!("Maximum nesting depth exceeded: {}", self.config.max_depth),
-            ));
-        }
-
-        if self.config.use_zero_copy && self.zero_scanner.is_some() {
-            self.generate_events_zero_copy()
-        } else {
-            self.generate_events_traditional()
-        }
-    }
-
-    /// Generate events using zero-copy scanner
-    fn generate_events_zero_copy(&mut self) -> Result<()> {
-        // Process a small batch of characters/tokens
-        let batch_size = 16;
-        let mut processed = 0;
-
-        while processed < batch_size {
-            // Get current character without borrowing the entire scanner
-            let current_char = if let Some(scanner) = &self.zero_scanner {
-                scanner.current_char()
-            } else {
-                None
-            };
-
-            if current_char.is_none() {
-                // End of stream
-                if !matches!(self.state, ParserState::StreamEnd) {
-                    self.event_buffer
-                        .push_back(Event::stream_end(self.position));
-                    self.stream_ended = true;
+        let target_size = (self.config.max_buffer_size / 2).max(1);
+        while !self.stream_ended && self.event_buffer.len() < target_size {
+            match self.next_source_event() {
+                Ok(Some(event)) => {
+                    self.stream_ended = matches!(event.event_type, EventType::StreamEnd);
+                    self.event_buffer.push_back(event);
                 }
-                break;
-            }
-
-            // Skip whitespace efficiently
-            if let Some(scanner) = &mut self.zero_scanner {
-                scanner.skip_whitespace();
-            }
-
-            let ch = current_char.unwrap();
-            match ch {
-                '-' if self.is_document_start_candidate_simple() => {
-                    self.handle_document_start();
-                    // Advance past the "---"
-                    if let Some(scanner) = &mut self.zero_scanner {
-                        scanner.advance();
-                        scanner.advance();
-                        scanner.advance();
-                    }
-                }
-                '.' if self.is_document_end_candidate_simple() => {
-                    self.handle_document_end();
-                    // Advance past the "..."
-                    if let Some(scanner) = &mut self.zero_scanner {
-                        scanner.advance();
-                        scanner.advance();
-                        scanner.advance();
-                    }
-                }
-                '[' => {
-                    self.handle_flow_sequence_start();
-                    if let Some(scanner) = &mut self.zero_scanner {
-                        scanner.advance();
-                    }
-                }
-                ']' => {
-                    self.handle_flow_sequence_end();
-                    if let Some(scanner) = &mut self.zero_scanner {
-                        scanner.advance();
-                    }
-                }
-                '{' => {
-                    self.handle_flow_mapping_start();
-                    if let Some(scanner) = &mut self.zero_scanner {
-                        scanner.advance();
-                    }
-                }
-                '}' => {
-                    self.handle_flow_mapping_end();
-                    if let Some(scanner) = &mut self.zero_scanner {
-                        scanner.advance();
-                    }
-                }
-                ':' if self.is_value_indicator_simple() => {
-                    self.handle_value_indicator();
-                    if let Some(scanner) = &mut self.zero_scanner {
-                        scanner.advance();
-                    }
-                }
-                ',' => {
-                    // Flow entry separator
-                    if let Some(scanner) = &mut self.zero_scanner {
-                        scanner.advance();
-                    }
-                }
-                '#' => {
-                    // Skip comments for now
-                    self.skip_comment_simple();
-                }
-                ch if ch.is_alphabetic() || ch.is_numeric() => {
-                    // Scan scalar using zero-copy
-                    let scalar_token = if let Some(scanner) = &mut self.zero_scanner {
-                        scanner.scan_plain_scalar_zero_copy()?
-                    } else {
-                        return Err(Error::parse(
-                            self.position,
-                            "No scanner available".to_string(),
-                        ));
-                    };
-                    self.handle_zero_copy_scalar(scalar_token)?;
-                }
-                '&' => {
-                    // Anchor
-                    if let Some(scanner) = &mut self.zero_scanner {
-                        scanner.advance(); // Skip '&'
-                        let anchor = scanner.scan_identifier_zero_copy()?;
-                        self.pending_anchor = Some(anchor.as_str().to_string());
-                    }
-                }
-                '*' => {
-                    // Alias
-                    if let Some(scanner) = &mut self.zero_scanner {
-                        scanner.advance(); // Skip '*'
-                        let alias = scanner.scan_identifier_zero_copy()?;
-                        self.event_buffer
-                            .push_back(Event::alias(self.position, alias.as_str().to_string()));
-                    }
-                }
-                _ => {
-                    // Unknown character, skip it
-                    if let Some(scanner) = &mut self.zero_scanner {
-                        scanner.advance();
+                Ok(None) => self.stream_ended = true,
+                Err(error) => {
+                    self.stream_ended = true;
+                    if self.event_buffer.is_empty() {
+                        return Err(error);
                     }
+                    self.pending_error = Some(error);
                 }
             }
-
-            processed += 1;
-            if let Some(scanner) = &self.zero_scanner {
-                self.position = scanner.position;
-            }
-
-            if let Some(ref mut stats) = self.stats {
-                stats.tokens_processed += 1;
-            }
         }
-
         Ok(())
     }
 
-    /// Generate events using traditional scanner
-    fn generate_events_traditional(&mut self) -> Result<()> {
-        // Process a few tokens at a time
-        for _ in 0..4 {
-            let has_token = if let Some(scanner) = &self.scanner {
-                scanner.check_token()
-            } else {
-                false
-            };
-
-            if !has_token {
-                if !matches!(self.state, ParserState::StreamEnd) {
-                    self.event_buffer
-                        .push_back(Event::stream_end(self.position));
-                    self.stream_ended = true;
+    /// Take the next event of the source, checking the depth limit
+    fn next_source_event(&mut self) -> Result<Option<Event>> {
+        let Some(event) = self.source.get_event()? else {
+            return Ok(None);
+        };
+        match event.event_type {
+            EventType::SequenceStart { .. } | EventType::MappingStart { .. } => {
+                self.depth += 1;
+                if self.depth > self.config.max_depth {
+                    return Err(Error::parse(
+                        event.position,
+                        format!("Maximum nesting depth exceeded: {}", self.config.max_depth),
+                    ));
                 }
-                break;
-            }
-
-            let token = if let Some(scanner) = &mut self.scanner {
-                scanner.get_token()?
-            } else {
-                None
-            };
-
-            if let Some(token) = token {
-                self.process_token(token)?;
-
                 if let Some(ref mut stats) = self.stats {
-                    stats.tokens_processed += 1;
+                    stats.max_depth = stats.max_depth.max(self.depth);
                 }
             }
-        }
-
-        Ok(())
-    }
-
-    /// Check if this position could be document start (---) - simplified version
-    fn is_document_start_candidate_simple(&self) -> bool {
-        if let Some(scanner) = &self.zero_scanner {
-            scanner.current_char() == Some('-')
-                && scanner.peek_char(1) == Some('-')
-                && scanner.peek_char(2) == Some('-')
-                && scanner.peek_char(3).map_or(true, |c| c.is_whitespace())
-        } else {
-            false
-        }
-    }
-
-    /// Check if this position could be document end (...) - simplified version
-    fn is_document_end_candidate_simple(&self) -> bool {
-        if let Some(scanner) = &self.zero_scanner {
-            scanner.current_char() == Some('.')
-                && scanner.peek_char(1) == Some('.')
-                && scanner.peek_char(2) == Some('.')
-                && scanner.peek_char(3).map_or(true, |c| c.is_whitespace())
-        } else {
-            false
-        }
-    }
-
-    /// Check if colon is a value indicator - simplified version
-    fn is_value_indicator_simple(&self) -> bool {
-        if let Some(scanner) = &self.zero_scanner {
-            scanner.current_char() == Some(':')
-                && scanner.peek_char(1).map_or(true, |c| c.is_whitespace())
-        } else {
-            false
-        }
-    }
-
-    /// Handle document start
-    fn handle_document_start(&mut self) {
-        self.event_buffer
-            .push_back(Event::document_start(self.position, None, vec![], false));
-        self.state = ParserState::DocumentStart;
-    }
-
-    /// Handle document end
-    fn handle_document_end(&mut self) {
-        self.event_buffer
-            .push_back(Event::document_end(self.position, false));
-        self.state = ParserState::DocumentEnd;
-    }
-
-    /// Handle flow sequence start
-    fn handle_flow_sequence_start(&mut self) {
-        self.push_state(ParserState::FlowSequence);
-        self.event_buffer.push_back(Event::sequence_start(
-            self.position,
-            self.pending_anchor.take(),
-            self.pending_tag.take(),
-            true,
-        ));
-    }
-
-    /// Handle flow sequence end
-    fn handle_flow_sequence_end(&mut self) {
-        self.event_buffer
-            .push_back(Event::sequence_end(self.position));
-        self.pop_state();
-    }
-
-    /// Handle flow mapping start
-    fn handle_flow_mapping_start(&mut self) {
-        self.push_state(ParserState::FlowMapping);
-        self.event_buffer.push_back(Event::mapping_start(
-            self.position,
-            self.pending_anchor.take(),
-            self.pending_tag.take(),
-            true,
-        ));
-    }
-
-    /// Handle flow mapping end
-    fn handle_flow_mapping_end(&mut self) {
-        self.event_buffer
-            .push_back(Event::mapping_end(self.position));
-        self.pop_state();
-    }
-
-    /// Handle value indicator (:)
-    fn handle_value_indicator(&mut self) {
-        match self.state {
-            ParserState::BlockMappingKey => {
-                self.state = ParserState::BlockMappingValue;
-            }
-            ParserState::FlowMapping => {
-                // In flow mapping, we don't change state on value indicator
-            }
-            _ => {
-                // Might need to start a new mapping
-                // This is simplified - full implementation would be more complex
-            }
-        }
-    }
-
-    /// Handle zero-copy scalar token
-    fn handle_zero_copy_scalar(&mut self, token: ZeroToken) -> Result<()> {
-        if let ZeroTokenType::Scalar(zero_string, quote_style) = token.token_type {
-            // Convert to regular scalar event
-            let style = match quote_style {
-                crate::scanner::QuoteStyle::Plain => crate::parser::ScalarStyle::Plain,
-                crate::scanner::QuoteStyle::Single => crate::parser::ScalarStyle::SingleQuoted,
-                crate::scanner::QuoteStyle::Double => crate::parser::ScalarStyle::DoubleQuoted,
-            };
-
-            // Avoid allocation if possible
-            let value = if zero_string.is_borrowed() {
-                zero_string.as_str().to_string()
-            } else {
-                zero_string.into_owned()
-            };
-
-            self.event_buffer.push_back(Event::scalar(
-                token.start_position,
-                self.pending_anchor.take(),
-                self.pending_tag.take(),
-                value,
-                style == crate::parser::ScalarStyle::Plain,
-                style != crate::parser::ScalarStyle::Plain,
-                style,
-            ));
-        }
-        Ok(())
-    }
-
-    /// Skip comment line - simplified version
-    fn skip_comment_simple(&mut self) {
-        if let Some(scanner) = &mut self.zero_scanner {
-            while let Some(ch) = scanner.current_char() {
-                scanner.advance();
-                if ch == '\n' || ch == '\r' {
-                    break;
-                }
-            }
-        }
-    }
-
-    /// Process a token from traditional scanner
-    fn process_token(&mut self, token: Token) -> Result<()> {
-        self.position = token.end_position;
-
-        match token.token_type {
-            TokenType::StreamStart => {
-                self.event_buffer
-                    .push_back(Event::stream_start(token.start_position));
-                self.state = ParserState::ImplicitDocumentStart;
-            }
-            TokenType::StreamEnd => {
-                self.event_buffer
-                    .push_back(Event::stream_end(token.start_position));
-                self.stream_ended = true;
-            }
-            TokenType::Scalar(value, quote_style) => {
-                let style = match quote_style {
-                    crate::scanner::QuoteStyle::Plain => crate::parser::ScalarStyle::Plain,
-                    crate::scanner::QuoteStyle::Single => crate::parser::ScalarStyle::SingleQuoted,
-                    crate::scanner::QuoteStyle::Double => crate::parser::ScalarStyle::DoubleQuoted,
-                };
-
-                self.event_buffer.push_back(Event::scalar(
-                    token.start_position,
-                    self.pending_anchor.take(),
-                    self.pending_tag.take(),
-                    value,
-                    style == crate::parser::ScalarStyle::Plain,
-                    style != crate::parser::ScalarStyle::Plain,
-                    style,
-                ));
-            }
-            // Add other token types as needed
-            _ => {
-                // Simplified implementation - full parser would handle all token types
+            EventType::SequenceEnd | EventType::MappingEnd => {
+                self.depth = self.depth.saturating_sub(1);
             }
+            _ => {}
         }
-
-        Ok(())
-    }
-
-    /// Push a new state onto the stack
-    fn push_state(&mut self, new_state: ParserState) {
-        self.state_stack.push(self.state);
-        self.state = new_state;
-        self.depth += 1;
-
-        if let Some(ref mut stats) = self.stats {
-            stats.max_depth = stats.max_depth.max(self.depth);
-        }
-    }
-
-    /// Pop state from the stack
-    fn pop_state(&mut self) {
-        if let Some(prev_state) = self.state_stack.pop() {
-            self.state = prev_state;
-            self.depth = self.depth.saturating_sub(1);
-        }
+        Ok(Some(event))
     }
 
     /// Update statistics for processed event
     fn update_stats_for_event(&mut self, _event: &Event) {
         if let Some(ref mut stats) = self.stats {
             stats.events_processed += 1;
-            stats.max_buffer_size = stats.max_buffer_size.max(self.event_buffer.len());
+            stats.max_buffer_size = stats.max_buffer_size.max(self.event_buffer.len() + 1);
         }
     }
 
@@ -600,9 +358,10 @@ impl<'a> StreamingParser<'a> {
     pub fn get_stats(&mut self) -> Option<StreamingStats> {
         if let Some(ref mut stats) = self.stats {
             stats.parse_time_ns = self.start_time.elapsed().as_nanos() as u64;
+            stats.tokens_processed = self.source.tokens_scanned();
 
-            if let Some(ref scanner) = self.zero_scanner {
-                stats.scanner_stats = Some(scanner.stats());
+            if let EventSource::ZeroCopy(ref parser) = self.source {
+                stats.scanner_stats = Some(parser.scanner().stats());
             }
 
             Some(stats.clone())
@@ -611,9 +370,9 @@ impl<'a> StreamingParser<'a> {
         }
     }
 
-    /// Check if more events are available
+    /// Check if more events are available, or an error to report
     pub fn has_more_events(&self) -> bool {
-        !self.stream_ended || !self.event_buffer.is_empty()
+        !self.stream_ended || !self.event_buffer.is_empty() || self.pending_error.is_some()
     }
 
     /// Get the current buffer size
@@ -623,33 +382,38 @@ impl<'a> StreamingParser<'a> {
 }
 
 impl<'a> Parser for StreamingParser<'a> {
-    fn check_event(&self) -> bool {
-        !self.event_buffer.is_empty() || !self.stream_ended
+    fn check_event(&mut self) -> bool {
+        self.peek_event().is_some() || self.has_more_events()
     }
 
-    fn peek_event(&self) -> Result<Option<&Event>> {
-        Ok(self.event_buffer.front())
+    fn peek_event(&mut self) -> Option<&Event> {
+        if self.event_buffer.is_empty() && self.pending_error.is_none() {
+            if let Err(error) = self.generate_events() {
+                self.pending_error = Some(error);
+            }
+        }
+        self.event_buffer.front()
     }
 
     fn get_event(&mut self) -> Result<Option<Event>> {
+        if self.event_buffer.is_empty() {
+            if let Some(error) = self.pending_error.take() {
+                return Err(error);
+            }
+        }
         self.next_event_internal()
     }
 
     fn reset(&mut self) {
+        self.source.reset();
         self.event_buffer.clear();
-        self.state = ParserState::StreamStart;
-        self.state_stack.clear();
+        self.position = Position::start();
         self.depth = 0;
-        self.pending_anchor = None;
-        self.pending_tag = None;
         self.stream_ended = false;
-        self.start_time = std::time::Instant::now();
-
-        if let Some(ref mut scanner) = self.scanner {
-            scanner.reset();
-        }
-        if let Some(ref mut scanner) = self.zero_scanner {
-            scanner.reset();
+        self.pending_error = None;
+        self.start_time = Stopwatch::start();
+        if self.duplicate_keys.is_some() {
+            self.duplicate_keys = Some(DuplicateKeys::default());
         }
     }
 
@@ -661,6 +425,7 @@ impl<'a> Parser for StreamingParser<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::ScalarStyle;
     use crate::EventType;
 
     #[test]
@@ -700,6 +465,37 @@ mod tests {
         assert!(has_scalar, "Should find scalar event with value '42'");
     }
 
+    #[test]
+    fn test_empty_values_streaming() {
+        let mut parser = StreamingParser::new(
+            "a:\nb: ''\nc: null\nd:\n  - x\ne:\n".to_string(),
+            StreamingConfig::default(),
+        );
+        let mut scalars = Vec::new();
+        while let Some(event) = parser.get_event().unwrap() {
+            if let EventType::Scalar { value, style, .. } = event.event_type {
+                scalars.push((value, style));
+            }
+        }
+
+        let plain = |value: &str| (value.to_string(), ScalarStyle::Plain);
+        assert_eq!(
+            scalars,
+            vec![
+                plain("a"),
+                plain(""),
+                plain("b"),
+                (String::new(), ScalarStyle::SingleQuoted),
+                plain("c"),
+                plain("null"),
+                plain("d"),
+                plain("x"),
+                plain("e"),
+                plain(""),
+            ]
+        );
+    }
+
     #[test]
     fn test_zero_copy_streaming() {
         let input = "key: value";
@@ -730,6 +526,9 @@ mod tests {
             use_zero_copy: false,
             max_depth: 10,
             collect_stats: true,
+            reject_duplicate_keys: false,
+            strict_escapes: false,
+            max_scalar_len: 1024,
         };
 
         let parser = StreamingParser::new("test".to_string(), config);
@@ -767,4 +566,102 @@ mod tests {
         });
         assert!(has_sequence_start, "Should find flow sequence start");
     }
+
+    #[test]
+    fn test_event_trace_matches_yaml() {
+        let trace = |mut parser: StreamingParser| {
+            let mut trace = String::new();
+            while let Some(event) = parser.get_event().unwrap() {
+                trace.push_str(&event.to_test_format());
+                trace.push('\n');
+            }
+            trace
+        };
+
+        for input in [
+            "a: 1\nb: [x, y]\n",
+            "- b: 1\n  c: 2\n- {d: e}\n",
+            "? [a]\n: 1\nk:\n- x\n- y\n",
+            "s: \"a\\tb\"\nt: 'it''s'\n\"q\": x\n",
+            "a: !!str 1\nb: !local x\n--- !!map\nc: d\n",
+            "%YAML 1.2\n%TAG !e! tag:example.com,2000:\n--- !e!thing\nx: 1\n",
+            "lit: |\n  line 1\n  line 2\nfold: >-\n  one\n  two\n",
+            "base: &b {x: 1}\ncopy: *b\n&k key: value\n",
+            "--- a\n...\n--- b\n",
+            "plain: one\n  two\nnull: ~\nbool: yes\n",
+        ] {
+            let expected = crate::Yaml::new().event_trace(input).unwrap();
+            let config = StreamingConfig::default();
+            assert_eq!(
+                trace(StreamingParser::new_zero_copy(input, config.clone())),
+                expected,
+                "zero-copy {input:?}"
+            );
+            assert_eq!(
+                trace(StreamingParser::new(input.to_string(), config)),
+                expected,
+                "traditional {input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        let check = |input: &str| {
+            let mut parser = crate::BasicParser::new_eager(input.to_string());
+            let mut duplicate_keys = DuplicateKeys::default();
+            while let Some(event) = parser.get_event()? {
+                duplicate_keys.check(&event)?;
+            }
+            Ok::<_, Error>(())
+        };
+
+        let error = check("a: 1\nb: 2\na: 3\n").unwrap_err();
+        assert!(error.to_string().contains("Duplicate key 'a'"), "{error}");
+        assert!(check("a: 1\n'a': 2\n").is_err());
+        assert!(check("{x: 1, x: 2}").is_err());
+        assert!(check("a:\n  - [1, 2]\n  - {b: 1}\na: 2\n").is_err());
+
+        // Keys only clash within one mapping, and 1 is not "1"
+        assert!(check("a: {b: 1}\nc: {b: 2}\n").is_ok());
+        assert!(check("1: a\n\"1\": b\n").is_ok());
+        assert!(check("a: 1\n---\na: 2\n").is_ok());
+        assert!(check("- a: 1\n- a: 2\n").is_ok());
+        assert!(check("a: a\nb: a\n").is_ok());
+        assert!(check("? [a]\n: 1\n? [a]\n: 2\n").is_ok());
+    }
+
+    #[test]
+    fn test_streaming_rejects_duplicate_keys() {
+        let config = StreamingConfig {
+            reject_duplicate_keys: true,
+            ..Default::default()
+        };
+        let drain = |mut parser: StreamingParser| {
+            while parser.get_event()?.is_some() {}
+            Ok::<_, Error>(())
+        };
+        let check = |input: &str| {
+            let zero_copy = drain(StreamingParser::new_zero_copy(input, config.clone()));
+            let traditional = drain(StreamingParser::new(input.to_string(), config.clone()));
+            assert_eq!(
+                zero_copy.is_ok(),
+                traditional.is_ok(),
+                "{input:?}: {zero_copy:?} {traditional:?}"
+            );
+            traditional
+        };
+
+        let error = check("a: 1\na: 2\n").unwrap_err();
+        assert!(error.to_string().contains("Duplicate key 'a'"), "{error}");
+        assert!(check("outer:\n  b: 1\n  c: 2\n  b: 3\n").is_err());
+        assert!(check("- x: 1\n  x: 2\n").is_err());
+        assert!(check("{a: 1, a: 2}").is_err());
+        assert!(check("k: {a: 1, b: {c: 1, c: 2}}\n").is_err());
+
+        assert!(check("a: 1\nb: 2\n").is_ok());
+        assert!(check("a: {b: 1}\nc: {b: 2}\n").is_ok());
+        assert!(check("- a: 1\n- a: 2\n").is_ok());
+        assert!(check("a: 1\n---\na: 2\n").is_ok());
+    }
 }
@@ -0,0 +1,574 @@
+//! JSON Merge Patch (RFC 7386) and JSON Patch (RFC 6902) support for YAML values
+
+use crate::{Error, Position, Result, Value};
+
+/// A single JSON Patch operation (RFC 6902)
+///
+/// Paths are JSON Pointers (RFC 6901), e.g. `/spec/containers/0/image`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchOp {
+    /// Add a value at the target location
+    Add {
+        /// Target location
+        path: String,
+        /// Value to add
+        value: Value,
+    },
+    /// Remove the value at the target location
+    Remove {
+        /// Target location
+        path: String,
+    },
+    /// Replace the value at the target location
+    Replace {
+        /// Target location
+        path: String,
+        /// Replacement value
+        value: Value,
+    },
+    /// Move a value from one location to another
+    Move {
+        /// Source location
+        from: String,
+        /// Target location
+        path: String,
+    },
+    /// Copy a value from one location to another
+    Copy {
+        /// Source location
+        from: String,
+        /// Target location
+        path: String,
+    },
+    /// Test that the value at the target location equals the given value
+    Test {
+        /// Target location
+        path: String,
+        /// Expected value
+        value: Value,
+    },
+}
+
+impl PatchOp {
+    /// Get the name of this operation as used in JSON Patch documents
+    pub const fn op_name(&self) -> &'static str {
+        match self {
+            Self::Add { .. } => "add",
+            Self::Remove { .. } => "remove",
+            Self::Replace { .. } => "replace",
+            Self::Move { .. } => "move",
+            Self::Copy { .. } => "copy",
+            Self::Test { .. } => "test",
+        }
+    }
+
+    /// Get the target path of this operation
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Add { path, .. }
+            | Self::Remove { path }
+            | Self::Replace { path, .. }
+            | Self::Move { path, .. }
+            | Self::Copy { path, .. }
+            | Self::Test { path, .. } => path,
+        }
+    }
+
+    /// Parse a single operation from its JSON Patch representation
+    ///
+    /// The value must be a mapping with an `op` key and the members required
+    /// by that operation (`path`, `value`, `from`).
+    pub fn from_value(value: &Value) -> Result<Self> {
+        let op = value
+            .get_str("op")
+            .and_then(Value::as_str)
+            .ok_or_else(|| patch_error("patch operation is missing 'op'"))?;
+        let member = |name: &str| -> Result<String> {
+            value
+                .get_str(name)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| patch_error(format!("'{}' operation is missing '{}'", op, name)))
+        };
+        let operand = || -> Result<Value> {
+            value
+                .get_str("value")
+                .cloned()
+                .ok_or_else(|| patch_error(format!("'{}' operation is missing 'value'", op)))
+        };
+
+        match op {
+            "add" => Ok(Self::Add {
+                path: member("path")?,
+                value: operand()?,
+            }),
+            "remove" => Ok(Self::Remove {
+                path: member("path")?,
+            }),
+            "replace" => Ok(Self::Replace {
+                path: member("path")?,
+                value: operand()?,
+            }),
+            "move" => Ok(Self::Move {
+                from: member("from")?,
+                path: member("path")?,
+            }),
+            "copy" => Ok(Self::Copy {
+                from: member("from")?,
+                path: member("path")?,
+            }),
+            "test" => Ok(Self::Test {
+                path: member("path")?,
+                value: operand()?,
+            }),
+            other => Err(patch_error(format!("unknown patch operation '{}'", other))),
+        }
+    }
+
+    /// Parse a JSON Patch document (a sequence of operations)
+    pub fn list_from_value(value: &Value) -> Result<Vec<Self>> {
+        match value {
+            Value::Sequence(ops) => ops.iter().map(Self::from_value).collect(),
+            _ => Err(patch_error("JSON Patch document must be a sequence")),
+        }
+    }
+
+    /// Convert this operation to its JSON Patch representation
+    pub fn to_value(&self) -> Value {
+        let mut pairs = vec![(Value::from("op"), Value::from(self.op_name()))];
+        match self {
+            Self::Move { from, .. } | Self::Copy { from, .. } => {
+                pairs.push((Value::from("from"), Value::from(from.as_str())));
+            }
+            _ => {}
+        }
+        pairs.push((Value::from("path"), Value::from(self.path())));
+        match self {
+            Self::Add { value, .. } | Self::Replace { value, .. } | Self::Test { value, .. } => {
+                pairs.push((Value::from("value"), value.clone()));
+            }
+            _ => {}
+        }
+        Value::mapping_with(pairs)
+    }
+}
+
+impl Value {
+    /// Apply a JSON Merge Patch (RFC 7386) to this value
+    ///
+    /// Mapping members of the patch are merged recursively, `null` members
+    /// remove the corresponding key, and any other patch value replaces the
+    /// target wholesale.
+    pub fn apply_merge_patch(&mut self, patch: &Self) {
+        let Self::Mapping(patch_map) = patch else {
+            *self = patch.clone();
+            return;
+        };
+
+        if !self.is_mapping() {
+            *self = Self::mapping();
+        }
+
+        if let Self::Mapping(target) = self {
+            for (key, patch_value) in patch_map {
+                if patch_value.is_null() {
+                    target.shift_remove(key);
+                } else {
+                    target
+                        .entry(key.clone())
+                        .or_insert(Self::Null)
+                        .apply_merge_patch(patch_value);
+                }
+            }
+        }
+    }
+
+    /// Apply a sequence of JSON Patch (RFC 6902) operations to this value
+    ///
+    /// The patch is applied atomically: if any operation fails, this value is
+    /// left unchanged and the error is returned.
+    pub fn apply_json_patch(&mut self, ops: &[PatchOp]) -> Result<()> {
+        let mut working = self.clone();
+        for op in ops {
+            working.apply_patch_op(op)?;
+        }
+        *self = working;
+        Ok(())
+    }
+
+    /// Generate a JSON Patch that transforms this value into `target`
+    ///
+    /// Applying the returned operations to a clone of `self` yields a value
+    /// equal to `target`. Mappings with non-string keys are replaced as a whole
+    /// since their keys cannot be addressed by a JSON Pointer.
+    pub fn diff_as_json_patch(&self, target: &Self) -> Vec<PatchOp> {
+        let mut ops = Vec::new();
+        diff_values(self, target, "", &mut ops);
+        ops
+    }
+
+    /// Resolve a JSON Pointer (RFC 6901) against this value
+    pub fn pointer(&self, pointer: &str) -> Option<&Self> {
+        let tokens = parse_pointer(pointer).ok()?;
+        let mut current = self;
+        for token in &tokens {
+            current = match current {
+                Self::Mapping(map) => map.get(&Self::String(token.clone()))?,
+                Self::Sequence(seq) => seq.get(parse_index(token, seq.len()).ok()?)?,
+                _ => return None,
+            };
+        }
+        Some(current)
+    }
+
+    /// Resolve a JSON Pointer (RFC 6901) against this value, mutably
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Self> {
+        let tokens = parse_pointer(pointer).ok()?;
+        let mut current = self;
+        for token in &tokens {
+            current = match current {
+                Self::Mapping(map) => map.get_mut(&Self::String(token.clone()))?,
+                Self::Sequence(seq) => {
+                    let index = parse_index(token, seq.len()).ok()?;
+                    seq.get_mut(index)?
+                }
+                _ => return None,
+            };
+        }
+        Some(current)
+    }
+
+    fn apply_patch_op(&mut self, op: &PatchOp) -> Result<()> {
+        match op {
+            PatchOp::Add { path, value } => self.patch_add(path, value.clone()),
+            PatchOp::Remove { path } => self.patch_remove(path).map(|_| ()),
+            PatchOp::Replace { path, value } => {
+                let target = self
+                    .pointer_mut(path)
+                    .ok_or_else(|| patch_error(format!("path '{}' does not exist", path)))?;
+                *target = value.clone();
+                Ok(())
+            }
+            PatchOp::Move { from, path } => {
+                if path.starts_with(from.as_str())
+                    && path.len() > from.len()
+                    && path[from.len()..].starts_with('/')
+                {
+                    return Err(patch_error(format!(
+                        "cannot move '{}' into one of its own children",
+                        from
+                    )));
+                }
+                let value = self.patch_remove(from)?;
+                self.patch_add(path, value)
+            }
+            PatchOp::Copy { from, path } => {
+                let value = self
+                    .pointer(from)
+                    .cloned()
+                    .ok_or_else(|| patch_error(format!("path '{}' does not exist", from)))?;
+                self.patch_add(path, value)
+            }
+            PatchOp::Test { path, value } => match self.pointer(path) {
+                Some(actual) if actual == value => Ok(()),
+                Some(_) => Err(patch_error(format!(
+                    "value at '{}' does not match expected value",
+                    path
+                ))),
+                None => Err(patch_error(format!("path '{}' does not exist", path))),
+            },
+        }
+    }
+
+    fn patch_add(&mut self, path: &str, value: Self) -> Result<()> {
+        let mut tokens = parse_pointer(path)?;
+        let Some(last) = tokens.pop() else {
+            *self = value;
+            return Ok(());
+        };
+        let parent = self.pointer_tokens_mut(&tokens, path)?;
+        match parent {
+            Self::Mapping(map) => {
+                map.insert(Self::String(last), value);
+                Ok(())
+            }
+            Self::Sequence(seq) => {
+                let index = if last == "-" {
+                    seq.len()
+                } else {
+                    parse_index(&last, seq.len() + 1)?
+                };
+                seq.insert(index, value);
+                Ok(())
+            }
+            _ => Err(patch_error(format!(
+                "parent of '{}' is not a mapping or sequence",
+                path
+            ))),
+        }
+    }
+
+    fn patch_remove(&mut self, path: &str) -> Result<Self> {
+        let mut tokens = parse_pointer(path)?;
+        let Some(last) = tokens.pop() else {
+            return Err(patch_error("cannot remove the document root"));
+        };
+        let parent = self.pointer_tokens_mut(&tokens, path)?;
+        match parent {
+            Self::Mapping(map) => map
+                .shift_remove(&Self::String(last))
+                .ok_or_else(|| patch_error(format!("path '{}' does not exist", path))),
+            Self::Sequence(seq) => {
+                let index = parse_index(&last, seq.len())?;
+                Ok(seq.remove(index))
+            }
+            _ => Err(patch_error(format!("path '{}' does not exist", path))),
+        }
+    }
+
+    fn pointer_tokens_mut(&mut self, tokens: &[String], path: &str) -> Result<&mut Self> {
+        let mut current = self;
+        for token in tokens {
+            let next = match current {
+                Self::Mapping(map) => map.get_mut(&Self::String(token.clone())),
+                Self::Sequence(seq) => {
+                    let index = parse_index(token, seq.len())?;
+                    seq.get_mut(index)
+                }
+                _ => None,
+            };
+            current = next.ok_or_else(|| patch_error(format!("path '{}' does not exist", path)))?;
+        }
+        Ok(current)
+    }
+}
+
+/// Build the error type used for patch failures
+fn patch_error(message: impl Into<String>) -> Error {
+    Error::value_error(Position::start(), message)
+}
+
+/// Split a JSON Pointer into unescaped reference tokens
+fn parse_pointer(pointer: &str) -> Result<Vec<String>> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(rest) = pointer.strip_prefix('/') else {
+        return Err(patch_error(format!(
+            "invalid JSON pointer '{}': must start with '/'",
+            pointer
+        )));
+    };
+    Ok(rest
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+/// Escape a mapping key for use as a JSON Pointer reference token
+fn escape_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+/// Parse a sequence index token, requiring it to be below `bound`
+fn parse_index(token: &str, bound: usize) -> Result<usize> {
+    let valid = !token.is_empty()
+        && token.bytes().all(|b| b.is_ascii_digit())
+        && (token == "0" || !token.starts_with('0'));
+    let index = if valid {
+        token.parse::<usize>().ok()
+    } else {
+        None
+    };
+    match index {
+        Some(index) if index < bound => Ok(index),
+        Some(index) => Err(patch_error(format!("index {} is out of bounds", index))),
+        None => Err(patch_error(format!("invalid sequence index '{}'", token))),
+    }
+}
+
+fn diff_values(source: &Value, target: &Value, path: &str, ops: &mut Vec<PatchOp>) {
+    if source == target {
+        return;
+    }
+
+    match (source, target) {
+        (Value::Mapping(src), Value::Mapping(dst))
+            if src.keys().chain(dst.keys()).all(Value::is_string) =>
+        {
+            for (key, src_value) in src {
+                let child = format!("{}/{}", path, escape_token(key.as_str().unwrap_or("")));
+                match dst.get(key) {
+                    Some(dst_value) => diff_values(src_value, dst_value, &child, ops),
+                    None => ops.push(PatchOp::Remove { path: child }),
+                }
+            }
+            for (key, dst_value) in dst {
+                if !src.contains_key(key) {
+                    let child = format!("{}/{}", path, escape_token(key.as_str().unwrap_or("")));
+                    ops.push(PatchOp::Add {
+                        path: child,
+                        value: dst_value.clone(),
+                    });
+                }
+            }
+        }
+        (Value::Sequence(src), Value::Sequence(dst)) => {
+            let common = src.len().min(dst.len());
+            for index in 0..common {
+                diff_values(
+                    &src[index],
+                    &dst[index],
+                    &format!("{}/{}", path, index),
+                    ops,
+                );
+            }
+            for index in (common..src.len()).rev() {
+                ops.push(PatchOp::Remove {
+                    path: format!("{}/{}", path, index),
+                });
+            }
+            for (index, value) in dst.iter().enumerate().skip(common) {
+                ops.push(PatchOp::Add {
+                    path: format!("{}/{}", path, index),
+                    value: value.clone(),
+                });
+            }
+        }
+        _ => ops.push(PatchOp::Replace {
+            path: path.to_string(),
+            value: target.clone(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Yaml;
+
+    fn load(input: &str) -> Value {
+        Yaml::new().load_str(input).unwrap()
+    }
+
+    #[test]
+    fn test_merge_patch_rfc7386() {
+        let mut target = load("title: Goodbye!\nauthor:\n  givenName: John\n  familyName: Doe\ntags: [example, sample]\ncontent: This will be unchanged\n");
+        let patch = load("title: Hello!\nphoneNumber: '+01-123-456-7890'\nauthor:\n  familyName: null\ntags: [example]\n");
+
+        target.apply_merge_patch(&patch);
+
+        let expected = load("title: Hello!\nauthor:\n  givenName: John\ntags: [example]\ncontent: This will be unchanged\nphoneNumber: '+01-123-456-7890'\n");
+        assert_eq!(target, expected);
+    }
+
+    #[test]
+    fn test_merge_patch_replaces_non_mappings() {
+        let mut target = load("a: [1, 2]");
+        target.apply_merge_patch(&load("a: {b: c}"));
+        assert_eq!(target, load("a: {b: c}"));
+
+        let mut scalar = Value::Int(1);
+        scalar.apply_merge_patch(&load("x: 1"));
+        assert_eq!(scalar, load("x: 1"));
+    }
+
+    #[test]
+    fn test_json_patch_operations() {
+        let mut doc = load("foo:\n  bar: baz\nlist: [a, b]\n");
+        let ops = vec![
+            PatchOp::Add {
+                path: "/foo/qux".to_string(),
+                value: Value::Int(1),
+            },
+            PatchOp::Add {
+                path: "/list/1".to_string(),
+                value: Value::from("x"),
+            },
+            PatchOp::Add {
+                path: "/list/-".to_string(),
+                value: Value::from("z"),
+            },
+            PatchOp::Replace {
+                path: "/foo/bar".to_string(),
+                value: Value::from("boo"),
+            },
+            PatchOp::Copy {
+                from: "/foo/bar".to_string(),
+                path: "/copied".to_string(),
+            },
+            PatchOp::Move {
+                from: "/foo/qux".to_string(),
+                path: "/moved".to_string(),
+            },
+            PatchOp::Remove {
+                path: "/list/0".to_string(),
+            },
+            PatchOp::Test {
+                path: "/list".to_string(),
+                value: load("[x, b, z]"),
+            },
+        ];
+
+        doc.apply_json_patch(&ops).unwrap();
+        assert_eq!(
+            doc,
+            load("foo:\n  bar: boo\nlist: [x, b, z]\ncopied: boo\nmoved: 1\n")
+        );
+    }
+
+    #[test]
+    fn test_json_patch_is_atomic() {
+        let original = load("a: 1\nb: 2\n");
+        let mut doc = original.clone();
+        let ops = vec![
+            PatchOp::Remove {
+                path: "/a".to_string(),
+            },
+            PatchOp::Test {
+                path: "/b".to_string(),
+                value: Value::Int(3),
+            },
+        ];
+
+        assert!(doc.apply_json_patch(&ops).is_err());
+        assert_eq!(doc, original);
+    }
+
+    #[test]
+    fn test_pointer_escaping() {
+        let doc = load("x: 0\n\"a/b\": 1\n\"m~n\": 2\n");
+        assert_eq!(doc.pointer("/a~1b"), Some(&Value::Int(1)));
+        assert_eq!(doc.pointer("/m~0n"), Some(&Value::Int(2)));
+        assert_eq!(doc.pointer(""), Some(&doc));
+        assert_eq!(doc.pointer("missing"), None);
+    }
+
+    #[test]
+    fn test_patch_op_value_round_trip() {
+        let patch = load(
+            "- op: add\n  path: /a\n  value: 1\n- op: move\n  from: /a\n  path: /b\n- op: remove\n  path: /c\n",
+        );
+        let ops = PatchOp::list_from_value(&patch).unwrap();
+        assert_eq!(ops.len(), 3);
+        let back = Value::Sequence(ops.iter().map(PatchOp::to_value).collect());
+        assert_eq!(PatchOp::list_from_value(&back).unwrap(), ops);
+
+        assert!(PatchOp::from_value(&load("{op: frobnicate, path: /a}")).is_err());
+    }
+
+    #[test]
+    fn test_diff_as_json_patch_round_trip() {
+        let source = load(
+            "name: app\nreplicas: 1\nports: [80, 443, 8080]\nold: true\nnested: {a: 1, b: 2}\n",
+        );
+        let target = load("name: app\nreplicas: 3\nports: [80]\nnested: {a: 1, c: 3}\nnew: [x]\n");
+
+        let ops = source.diff_as_json_patch(&target);
+        let mut patched = source.clone();
+        patched.apply_json_patch(&ops).unwrap();
+        assert_eq!(patched, target);
+
+        assert!(source.diff_as_json_patch(&source).is_empty());
+    }
+}
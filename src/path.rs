@@ -0,0 +1,175 @@
+//! Paths addressing nodes inside a YAML value
+//!
+//! A [`Path`] is the sequence of mapping keys and sequence indices leading
+//! from the document root to a node. It displays in the familiar dotted form,
+//! such as `spec.containers[0].image`, and parses back from it.
+
+use crate::composer::resolve_scalar;
+use crate::parser::ScalarStyle;
+use crate::{Error, Value};
+use std::fmt;
+use std::str::FromStr;
+
+/// One step of a [`Path`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    /// The value under a mapping key
+    Key(Value),
+    /// The element of a sequence at an index
+    Index(usize),
+}
+
+/// The location of a node inside a value, from the root down
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Path {
+    segments: Vec<PathSegment>,
+}
+
+impl Path {
+    /// The path of the root node
+    pub const fn new() -> Self {
+        Self {
+            segments: Vec::new(),
+        }
+    }
+
+    /// The steps of this path, from the root down
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// Check whether this is the path of the root node
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Add a step at the end of the path
+    pub fn push(&mut self, segment: PathSegment) {
+        self.segments.push(segment);
+    }
+
+    /// Remove and return the last step of the path
+    pub fn pop(&mut self) -> Option<PathSegment> {
+        self.segments.pop()
+    }
+
+    /// Find the node this path leads to inside `root`
+    pub fn resolve<'a>(&self, root: &'a Value) -> Option<&'a Value> {
+        self.segments
+            .iter()
+            .try_fold(root, |current, segment| match segment {
+                PathSegment::Key(key) => current.as_mapping()?.get(key),
+                PathSegment::Index(index) => current.get_index(*index),
+            })
+    }
+}
+
+impl From<Vec<PathSegment>> for Path {
+    fn from(segments: Vec<PathSegment>) -> Self {
+        Self { segments }
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                PathSegment::Key(key) => {
+                    if i > 0 {
+                        f.write_str(".")?;
+                    }
+                    match key {
+                        Value::String(key) => f.write_str(key)?,
+                        key => write!(f, "{key}")?,
+                    }
+                }
+                PathSegment::Index(index) => write!(f, "[{index}]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Path {
+    type Err = Error;
+
+    /// Parse the dotted form written by `Display`
+    ///
+    /// Keys are resolved as plain scalars, so `ports.8080` addresses the
+    /// integer key `8080`. The empty string is the root path. Keys containing
+    /// `.` or `[` cannot be written in this form.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            |reason: &str| Error::config_error(format!("Invalid path '{text}': {reason}"));
+        let mut path = Self::new();
+        if text.is_empty() {
+            return Ok(path);
+        }
+        for (i, part) in text.split('.').enumerate() {
+            let (key, mut indices) = part.split_at(part.find('[').unwrap_or(part.len()));
+            if !key.is_empty() {
+                path.push(PathSegment::Key(resolve_scalar(
+                    key.to_string(),
+                    ScalarStyle::Plain,
+                )));
+            } else if i > 0 || indices.is_empty() {
+                return Err(invalid("empty key"));
+            }
+            while !indices.is_empty() {
+                let (index, rest) = indices
+                    .strip_prefix('[')
+                    .and_then(|rest| rest.split_once(']'))
+                    .ok_or_else(|| invalid("malformed index"))?;
+                let index = index
+                    .parse()
+                    .map_err(|_| invalid(&format!("'{index}' is not an index")))?;
+                path.push(PathSegment::Index(index));
+                indices = rest;
+            }
+        }
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_and_resolve() {
+        let value = crate::yaml!({"spec": {"containers": [{"image": "nginx"}]}, 8080: true});
+        let path = Path::from(vec![
+            PathSegment::Key(Value::from("spec")),
+            PathSegment::Key(Value::from("containers")),
+            PathSegment::Index(0),
+            PathSegment::Key(Value::from("image")),
+        ]);
+        assert_eq!(path.to_string(), "spec.containers[0].image");
+        assert_eq!(path.resolve(&value), Some(&Value::from("nginx")));
+
+        let int_key = Path::from(vec![PathSegment::Key(Value::Int(8080))]);
+        assert_eq!(int_key.to_string(), "8080");
+        assert_eq!(int_key.resolve(&value), Some(&Value::Bool(true)));
+
+        assert!(Path::new().is_root());
+        assert_eq!(Path::new().resolve(&value), Some(&value));
+        assert_eq!(
+            Path::from(vec![PathSegment::Index(3)]).resolve(&value),
+            None
+        );
+    }
+
+    #[test]
+    fn test_from_str() {
+        for text in ["spec.containers[0].image", "[1][2].a", "8080", ""] {
+            assert_eq!(text.parse::<Path>().unwrap().to_string(), text);
+        }
+        assert_eq!(
+            "ports.8080".parse::<Path>().unwrap().segments()[1],
+            PathSegment::Key(Value::Int(8080))
+        );
+        for text in ["a..b", "a.", "a[x]", "a[1", "a[1]b"] {
+            assert!(text.parse::<Path>().is_err(), "{text}");
+        }
+    }
+}
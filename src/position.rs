@@ -38,18 +38,31 @@ impl Position {
     }
 
     /// Advance position by one character
+    ///
+    /// `\n` and a lone `\r` each end a line; a `\r\n` pair is a single line
+    /// break, which [`Position::advance_crlf`] steps over.
     pub const fn advance(self, ch: char) -> Self {
-        if ch == '\n' {
+        if ch == '\n' || ch == '\r' {
             Self::at(self.line + 1, 1, self.index + ch.len_utf8())
         } else {
             Self::at(self.line, self.column + 1, self.index + ch.len_utf8())
         }
     }
 
+    /// Advance position past a `\r\n` line break
+    pub const fn advance_crlf(self) -> Self {
+        Self::at(self.line + 1, 1, self.index + 2)
+    }
+
     /// Advance position by a string
     pub fn advance_str(mut self, s: &str) -> Self {
-        for ch in s.chars() {
-            self = self.advance(ch);
+        let mut chars = s.chars().peekable();
+        while let Some(ch) = chars.next() {
+            if ch == '\r' && chars.next_if_eq(&'\n').is_some() {
+                self = self.advance_crlf();
+            } else {
+                self = self.advance(ch);
+            }
         }
         self
     }
@@ -205,6 +218,18 @@ mod tests {
         assert_eq!(pos1.index, 11);
     }
 
+    #[test]
+    fn test_position_line_breaks() {
+        // `\r\n` is one line break and a lone `\r` is another
+        let pos = Position::start().advance_str("a\r\nb\rcd");
+        assert_eq!(pos, Position::at(3, 3, 7));
+        assert_eq!(Position::start().advance('\r'), Position::at(2, 1, 1));
+        assert_eq!(
+            Position::at(4, 9, 30).advance_crlf(),
+            Position::at(5, 1, 32)
+        );
+    }
+
     #[test]
     fn test_position_display() {
         let pos = Position::at(42, 13, 1000);
@@ -1,7 +1,89 @@
 //! Performance profiling utilities and optimization features
 
 use std::collections::HashMap;
-use std::time::{Duration, Instant};
+use std::time::Duration;
+#[cfg(not(feature = "wasm"))]
+use std::time::Instant;
+
+/// Elapsed-time measurement that also builds for `wasm32-unknown-unknown`
+///
+/// `std::time::Instant` panics on targets without a clock, so with the `wasm`
+/// feature enabled a stopwatch always reports zero elapsed time.
+#[derive(Debug, Clone, Copy)]
+pub struct Stopwatch {
+    #[cfg(not(feature = "wasm"))]
+    start: Instant,
+}
+
+impl Stopwatch {
+    /// Start measuring from now
+    pub fn start() -> Self {
+        Self {
+            #[cfg(not(feature = "wasm"))]
+            start: Instant::now(),
+        }
+    }
+
+    /// Time elapsed since the stopwatch was started
+    pub fn elapsed(&self) -> Duration {
+        #[cfg(not(feature = "wasm"))]
+        {
+            self.start.elapsed()
+        }
+        #[cfg(feature = "wasm")]
+        {
+            Duration::ZERO
+        }
+    }
+}
+
+impl Default for Stopwatch {
+    fn default() -> Self {
+        Self::start()
+    }
+}
+
+/// Counters and timing for one load, from [`Yaml::load_str_with_stats`] and
+/// [`Yaml::load_all_str_with_stats`]
+///
+/// [`Yaml::load_str_with_stats`]: crate::Yaml::load_str_with_stats
+/// [`Yaml::load_all_str_with_stats`]: crate::Yaml::load_all_str_with_stats
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseStats {
+    /// Size of the input in bytes
+    pub bytes: usize,
+    /// Tokens produced by the scanner
+    pub tokens: usize,
+    /// Events produced by the parser
+    pub events: usize,
+    /// Documents loaded
+    pub documents: usize,
+    /// Anchors defined
+    pub anchors: usize,
+    /// Aliases expanded
+    pub alias_expansions: usize,
+    /// Deepest collection nesting reached
+    pub max_depth: usize,
+    /// Complexity score the structure added up to
+    pub complexity_score: usize,
+    /// Wall-clock time spent loading
+    pub elapsed: Duration,
+}
+
+/// Counters and timing for one dump, from [`Yaml::dump_str_with_stats`] and
+/// [`Yaml::dump_all_str_with_stats`]
+///
+/// [`Yaml::dump_str_with_stats`]: crate::Yaml::dump_str_with_stats
+/// [`Yaml::dump_all_str_with_stats`]: crate::Yaml::dump_all_str_with_stats
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DumpStats {
+    /// Size of the output in bytes
+    pub bytes: usize,
+    /// Documents written
+    pub documents: usize,
+    /// Wall-clock time spent dumping
+    pub elapsed: Duration,
+}
 
 /// Performance profiler for YAML operations
 #[derive(Debug, Clone)]
@@ -45,7 +127,7 @@ impl YamlProfiler {
             return func();
         }
 
-        let start = Instant::now();
+        let start = Stopwatch::start();
         let result = func();
         let duration = start.elapsed();
 
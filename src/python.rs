@@ -0,0 +1,330 @@
+//! Python bindings (`python` feature)
+//!
+//! Builds a `rust_yaml` extension module with pyo3 whose functions follow
+//! the ruamel.yaml calling conventions, so existing tooling can swap it in:
+//!
+//! ```text
+//! import rust_yaml
+//!
+//! data = rust_yaml.load(open("config.yaml"))            # typ="safe"
+//! docs = rust_yaml.load_all(text, typ="rt")
+//! text = rust_yaml.dump(data, indent=4)
+//! rust_yaml.dump_all(docs, sys.stdout, typ="rt")
+//! ```
+//!
+//! `stream` arguments accept a `str` or any object with a `read()` (load) or
+//! `write()` (dump) method. `typ="safe"` uses the safe loader; `typ="rt"`
+//! uses the round-trip loader, which keeps merge keys (`<<`) as written
+//! rather than expanding them. Documents are converted to native Python
+//! objects: mappings become `dict` (sequence keys become `tuple`), sequences
+//! become `list`, and scalars become `None`, `bool`, `int`, `float` or `str`.
+
+// `#[pyfunction]` and `#[pymodule]` expand to `unsafe` FFI shims, and wrap a
+// `PyResult` return value in a conversion to `PyErr` it does not need.
+#![allow(unsafe_code, clippy::useless_conversion)]
+
+use crate::yaml::IndentConfig;
+use crate::{LoaderType, Value, Yaml, YamlConfig};
+use indexmap::IndexMap;
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple};
+
+// `create_exception!` expands to items gated on pyo3's own `gil-refs` feature,
+// which this crate does not declare.
+#[allow(unexpected_cfgs)]
+mod exception {
+    use pyo3::create_exception;
+    use pyo3::exceptions::PyValueError;
+
+    create_exception!(
+        rust_yaml,
+        YAMLError,
+        PyValueError,
+        "Raised when a document cannot be parsed or emitted"
+    );
+}
+use exception::YAMLError;
+
+/// Load the first document of `stream`
+#[pyfunction]
+#[pyo3(signature = (stream, *, typ = "safe"))]
+fn load(py: Python<'_>, stream: &Bound<'_, PyAny>, typ: &str) -> PyResult<PyObject> {
+    let yaml = yaml_for(typ, None)?;
+    let input = read_stream(stream)?;
+    let value = yaml.load_str(&input).map_err(to_py_error)?;
+    to_py(py, &value)
+}
+
+/// Load every document of `stream` into a list
+#[pyfunction]
+#[pyo3(signature = (stream, *, typ = "safe"))]
+fn load_all(py: Python<'_>, stream: &Bound<'_, PyAny>, typ: &str) -> PyResult<PyObject> {
+    let yaml = yaml_for(typ, None)?;
+    let input = read_stream(stream)?;
+    let documents = yaml.load_all_str(&input).map_err(to_py_error)?;
+    let list = PyList::empty_bound(py);
+    for document in &documents {
+        list.append(to_py(py, document)?)?;
+    }
+    Ok(list.into_any().unbind())
+}
+
+/// Dump `data` as YAML, returning a `str` when no `stream` is given
+#[pyfunction]
+#[pyo3(signature = (data, stream = None, *, typ = "safe", indent = None))]
+fn dump(
+    data: &Bound<'_, PyAny>,
+    stream: Option<&Bound<'_, PyAny>>,
+    typ: &str,
+    indent: Option<usize>,
+) -> PyResult<Option<String>> {
+    let yaml = yaml_for(typ, indent)?;
+    let value = from_py(data)?;
+    let output = yaml.dump_str(&value).map_err(to_py_error)?;
+    write_stream(stream, output)
+}
+
+/// Dump each item of `documents` as a separate YAML document
+#[pyfunction]
+#[pyo3(signature = (documents, stream = None, *, typ = "safe", indent = None))]
+fn dump_all(
+    documents: &Bound<'_, PyAny>,
+    stream: Option<&Bound<'_, PyAny>>,
+    typ: &str,
+    indent: Option<usize>,
+) -> PyResult<Option<String>> {
+    let yaml = yaml_for(typ, indent)?;
+    let values = documents
+        .iter()?
+        .map(|document| from_py(&document?))
+        .collect::<PyResult<Vec<_>>>()?;
+    let output = yaml.dump_all_str(&values).map_err(to_py_error)?;
+    write_stream(stream, output)
+}
+
+/// The `rust_yaml` Python module
+#[pymodule]
+fn rust_yaml(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("YAMLError", m.py().get_type_bound::<YAMLError>())?;
+    m.add_function(wrap_pyfunction!(load, m)?)?;
+    m.add_function(wrap_pyfunction!(load_all, m)?)?;
+    m.add_function(wrap_pyfunction!(dump, m)?)?;
+    m.add_function(wrap_pyfunction!(dump_all, m)?)?;
+    Ok(())
+}
+
+/// Build a processor for a ruamel-style `typ` name
+fn yaml_for(typ: &str, indent: Option<usize>) -> PyResult<Yaml> {
+    let mut config = match typ {
+        "safe" => YamlConfig {
+            loader_type: LoaderType::Safe,
+            ..YamlConfig::default()
+        },
+        "rt" => YamlConfig {
+            loader_type: LoaderType::RoundTrip,
+            preserve_merge_keys: true,
+            ..YamlConfig::default()
+        },
+        other => {
+            return Err(PyValueError::new_err(format!(
+                "unsupported typ '{other}', expected 'safe' or 'rt'"
+            )))
+        }
+    };
+    if let Some(indent) = indent {
+        if indent == 0 {
+            return Err(PyValueError::new_err("indent must be positive"));
+        }
+        config.indent = IndentConfig {
+            indent,
+            ..config.indent
+        };
+    }
+    Ok(Yaml::with_config(config))
+}
+
+fn read_stream(stream: &Bound<'_, PyAny>) -> PyResult<String> {
+    if let Ok(text) = stream.downcast::<PyString>() {
+        return Ok(text.to_str()?.to_owned());
+    }
+    if stream.hasattr("read")? {
+        return stream.call_method0("read")?.extract();
+    }
+    Err(PyTypeError::new_err(
+        "stream must be a str or have a read() method",
+    ))
+}
+
+fn write_stream(stream: Option<&Bound<'_, PyAny>>, output: String) -> PyResult<Option<String>> {
+    match stream {
+        None => Ok(Some(output)),
+        Some(stream) => {
+            stream.call_method1("write", (output,))?;
+            Ok(None)
+        }
+    }
+}
+
+fn to_py_error(error: crate::Error) -> PyErr {
+    YAMLError::new_err(error.to_string())
+}
+
+fn to_py(py: Python<'_>, value: &Value) -> PyResult<PyObject> {
+    Ok(match value {
+        Value::Null => py.None(),
+        Value::Bool(b) => b.into_py(py),
+        Value::Int(i) => i.into_py(py),
+        Value::Float(f) => f.into_py(py),
+        Value::String(s) => s.into_py(py),
+        Value::Sequence(seq) => {
+            let list = PyList::empty_bound(py);
+            for item in seq {
+                list.append(to_py(py, item)?)?;
+            }
+            list.into_any().unbind()
+        }
+        Value::Mapping(map) => {
+            let dict = PyDict::new_bound(py);
+            for (key, item) in map {
+                dict.set_item(to_py_key(py, key)?, to_py(py, item)?)?;
+            }
+            dict.into_any().unbind()
+        }
+        Value::Tagged(tagged) => to_py(py, &tagged.value)?,
+    })
+}
+
+/// Convert a mapping key, using tuples for sequence keys so they are hashable
+fn to_py_key(py: Python<'_>, key: &Value) -> PyResult<PyObject> {
+    match key {
+        Value::Sequence(seq) => {
+            let items = seq
+                .iter()
+                .map(|item| to_py_key(py, item))
+                .collect::<PyResult<Vec<_>>>()?;
+            Ok(PyTuple::new_bound(py, items).into_any().unbind())
+        }
+        Value::Mapping(_) => Err(YAMLError::new_err(
+            "mapping keys cannot be represented as Python dict keys",
+        )),
+        other => to_py(py, other),
+    }
+}
+
+fn from_py(object: &Bound<'_, PyAny>) -> PyResult<Value> {
+    if object.is_none() {
+        return Ok(Value::Null);
+    }
+    // `bool` is a subclass of `int`, so it must be checked first
+    if let Ok(b) = object.downcast::<PyBool>() {
+        return Ok(Value::Bool(b.is_true()));
+    }
+    if object.is_instance_of::<PyInt>() {
+        return object
+            .extract::<i64>()
+            .map(Value::Int)
+            .map_err(|_| PyValueError::new_err("integer does not fit in 64 bits"));
+    }
+    if let Ok(f) = object.downcast::<PyFloat>() {
+        return Ok(Value::Float(f.value()));
+    }
+    if let Ok(s) = object.downcast::<PyString>() {
+        return Ok(Value::String(s.to_str()?.to_owned()));
+    }
+    if let Ok(dict) = object.downcast::<PyDict>() {
+        let mut map = IndexMap::with_capacity(dict.len());
+        for (key, item) in dict.iter() {
+            map.insert(from_py(&key)?, from_py(&item)?);
+        }
+        return Ok(Value::Mapping(map));
+    }
+    if object.is_instance_of::<PyList>() || object.is_instance_of::<PyTuple>() {
+        return object
+            .iter()?
+            .map(|item| from_py(&item?))
+            .collect::<PyResult<Vec<_>>>()
+            .map(Value::Sequence);
+    }
+    Err(PyTypeError::new_err(format!(
+        "cannot represent an object of type '{}'",
+        object.get_type().name()?
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run `f` holding the GIL of an interpreter started for the tests
+    fn with_python<R>(f: impl FnOnce(Python<'_>) -> R) -> R {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(f)
+    }
+
+    fn load_str(py: Python<'_>, input: &str) -> PyResult<PyObject> {
+        load(py, PyString::new_bound(py, input).as_any(), "safe")
+    }
+
+    #[test]
+    fn test_bool_converts_before_int() {
+        with_python(|py| {
+            let value = from_py(PyBool::new_bound(py, true).as_any()).unwrap();
+            assert_eq!(value, Value::Bool(true));
+            let value = from_py(&1_i64.into_py(py).into_bound(py)).unwrap();
+            assert_eq!(value, Value::Int(1));
+
+            let loaded = load_str(py, "[true, 1]").unwrap().into_bound(py);
+            let items = loaded.downcast::<PyList>().unwrap();
+            assert!(items.get_item(0).unwrap().is_instance_of::<PyBool>());
+            assert!(!items.get_item(1).unwrap().is_instance_of::<PyBool>());
+        });
+    }
+
+    #[test]
+    fn test_sequence_keys_become_tuples() {
+        with_python(|py| {
+            let loaded = load_str(py, "? [a, [b, 1]]\n: x\n").unwrap().into_bound(py);
+            let dict = loaded.downcast::<PyDict>().unwrap();
+            let (key, item) = dict.iter().next().unwrap();
+            let key = key.downcast::<PyTuple>().unwrap();
+            assert_eq!(key.get_item(0).unwrap().extract::<String>().unwrap(), "a");
+            assert!(key.get_item(1).unwrap().is_instance_of::<PyTuple>());
+            assert_eq!(item.extract::<String>().unwrap(), "x");
+
+            // A tuple dumps back as a sequence
+            let value = from_py(&key).unwrap();
+            assert_eq!(value, crate::yaml!(["a", ["b", 1]]));
+        });
+    }
+
+    #[test]
+    fn test_mapping_key_is_an_error() {
+        with_python(|py| {
+            let error = load_str(py, "? {a: 1}\n: x\n").unwrap_err();
+            assert!(error.is_instance_of::<YAMLError>(py));
+            assert!(error
+                .to_string()
+                .contains("mapping keys cannot be represented"));
+        });
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        with_python(|py| {
+            let stream = PyString::new_bound(py, "a: 1");
+            let error = load(py, stream.as_any(), "unsafe").unwrap_err();
+            assert!(error.is_instance_of::<PyValueError>(py));
+            assert!(error.to_string().contains("unsupported typ 'unsafe'"));
+
+            let data = PyDict::new_bound(py);
+            let error = dump(data.as_any(), None, "safe", Some(0)).unwrap_err();
+            assert!(error.to_string().contains("indent must be positive"));
+            let output = dump(data.as_any(), None, "rt", Some(4)).unwrap();
+            assert_eq!(output.as_deref(), Some("{}\n"));
+
+            let error = load(py, 1_i64.into_py(py).bind(py), "safe").unwrap_err();
+            assert!(error.is_instance_of::<PyTypeError>(py));
+        });
+    }
+}
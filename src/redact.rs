@@ -0,0 +1,127 @@
+//! Hiding secrets in values before they are logged
+//!
+//! [`redact`] copies a value with whatever is stored under keys such as
+//! `password` replaced, and [`YamlConfig::redaction`](crate::YamlConfig)
+//! applies the same rules whenever a value is dumped:
+//!
+//! ```
+//! use rust_yaml::{redact, yaml, RedactionRules};
+//!
+//! let config = yaml!({"db": {"user": "app", "password": "hunter2"}, "api_token": "abc"});
+//! assert_eq!(
+//!     redact(&config, &RedactionRules::default()),
+//!     yaml!({"db": {"user": "app", "password": "***"}, "api_token": "***"})
+//! );
+//! ```
+
+use crate::{Path, PathSegment, Value, Visit, Visitor};
+
+/// Which values [`redact`] hides and what it writes in their place
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedactionRules {
+    /// Parts of the keys whose values are hidden: a string mapping key
+    /// containing any of them, ignoring case, matches; `password`, `token`,
+    /// `secret` and `key` by default
+    pub key_patterns: Vec<String>,
+    /// What a hidden value, scalar or collection, is replaced with
+    pub replacement: String,
+}
+
+impl Default for RedactionRules {
+    fn default() -> Self {
+        Self::new(["password", "token", "secret", "key"])
+    }
+}
+
+impl RedactionRules {
+    /// Rules hiding the values of keys containing any of `key_patterns`,
+    /// replacing them with `***`
+    pub fn new<I>(key_patterns: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Self {
+            key_patterns: key_patterns.into_iter().map(Into::into).collect(),
+            replacement: "***".to_string(),
+        }
+    }
+
+    /// Whether the value stored under `key` is hidden
+    pub fn matches_key(&self, key: &str) -> bool {
+        let key = key.to_lowercase();
+        self.key_patterns
+            .iter()
+            .any(|pattern| key.contains(&pattern.to_lowercase()))
+    }
+
+    /// Hide the values under matching keys in `value` itself
+    pub fn apply(&self, value: &mut Value) {
+        value.transform(&mut Redactor(self));
+    }
+}
+
+/// A copy of `value` with what is stored under keys matching `rules`
+/// replaced, for logging loaded configurations safely
+pub fn redact(value: &Value, rules: &RedactionRules) -> Value {
+    let mut redacted = value.clone();
+    rules.apply(&mut redacted);
+    redacted
+}
+
+/// Replaces the values under matching keys without visiting inside them
+struct Redactor<'a>(&'a RedactionRules);
+
+impl Visitor for Redactor<'_> {
+    fn enter(&mut self, path: &Path, value: &mut Value) -> Visit {
+        match path.segments().last() {
+            Some(PathSegment::Key(Value::String(key))) if self.0.matches_key(key) => {
+                *value = Value::String(self.0.replacement.clone());
+                Visit::Skip
+            }
+            _ => Visit::Continue,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::yaml;
+
+    #[test]
+    fn test_redact_default_patterns() {
+        let value = yaml!({
+            "name": "api",
+            "Password": "hunter2",
+            "auth": {"GITHUB_TOKEN": "ghp_x", "client_secret": ["a", "b"]},
+            "servers": [{"host": "a", "ssh_key": null}],
+        });
+        assert_eq!(
+            redact(&value, &RedactionRules::default()),
+            yaml!({
+                "name": "api",
+                "Password": "***",
+                "auth": {"GITHUB_TOKEN": "***", "client_secret": "***"},
+                "servers": [{"host": "a", "ssh_key": "***"}],
+            })
+        );
+        // The original is left as it was
+        assert_eq!(value["Password"], Value::from("hunter2"));
+    }
+
+    #[test]
+    fn test_redact_custom_rules() {
+        let mut rules = RedactionRules::new(["pin"]);
+        rules.replacement = "[hidden]".to_string();
+        assert!(rules.matches_key("PIN_CODE"));
+        assert!(!rules.matches_key("password"));
+
+        let mut value = yaml!({"pin": 1234, "password": "kept", "list": ["pin"]});
+        rules.apply(&mut value);
+        assert_eq!(
+            value,
+            yaml!({"pin": "[hidden]", "password": "kept", "list": ["pin"]})
+        );
+    }
+}
@@ -0,0 +1,411 @@
+//! Annotated error reports for command-line tools
+//!
+//! [`ErrorReport`] renders an [`Error`] against the input it was raised for,
+//! with a window of source lines around each position involved, optional
+//! terminal colors, and a JSON form for editors and CI annotations:
+//!
+//! ```
+//! use rust_yaml::{ErrorReport, Yaml};
+//!
+//! let input = "name: app\nport: *missing\n";
+//! let error = Yaml::new().load_str(input).unwrap_err();
+//! let report = ErrorReport::new(&error, input).with_source_name("app.yaml");
+//! let text = report.to_string();
+//! assert!(text.contains("--> app.yaml:2:"));
+//! assert!(text.contains("2 | port: *missing"));
+//! assert!(report.to_json().starts_with("{\"severity\":\"error\""));
+//! ```
+
+use crate::{Error, Position};
+use std::collections::BTreeSet;
+use std::fmt::{self, Write};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const ERROR: &str = "\x1b[1;31m";
+const GUTTER: &str = "\x1b[1;34m";
+const SECONDARY: &str = "\x1b[1;36m";
+
+/// A position of the source marked in a report
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Label {
+    position: Position,
+    message: String,
+    primary: bool,
+}
+
+/// An [`Error`] rendered against its source text
+///
+/// `Display` writes a report in the style of compiler diagnostics; with
+/// [`ErrorReport::with_color`] it includes ANSI colors, which callers usually
+/// enable when standard error is a terminal. Errors without a position are
+/// reported by their message alone, and each error of an [`Error::Multiple`]
+/// gets a report of its own.
+#[derive(Debug, Clone)]
+pub struct ErrorReport<'a> {
+    error: &'a Error,
+    source: &'a str,
+    source_name: Option<String>,
+    color: bool,
+    context_lines: usize,
+}
+
+impl<'a> ErrorReport<'a> {
+    /// Create a report of `error`, which was raised for the text `source`
+    pub const fn new(error: &'a Error, source: &'a str) -> Self {
+        Self {
+            error,
+            source,
+            source_name: None,
+            color: false,
+            context_lines: 1,
+        }
+    }
+
+    /// Name the source, usually its file path, in the location line
+    pub fn with_source_name(mut self, name: impl Into<String>) -> Self {
+        self.source_name = Some(name.into());
+        self
+    }
+
+    /// Set whether the report includes ANSI terminal colors
+    pub const fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Set the number of source lines shown before and after each marked
+    /// line (1 by default)
+    pub const fn with_context_lines(mut self, lines: usize) -> Self {
+        self.context_lines = lines;
+        self
+    }
+
+    /// The report as a single-line JSON object
+    ///
+    /// The object has the fields `severity`, `kind`, `message`, `source`,
+    /// `line`, `column`, `labels` (each with `line`, `column`, `message` and
+    /// `primary`), `suggestion` and `errors`, which holds the reports of the
+    /// errors of an [`Error::Multiple`]. Missing values are `null`.
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.write_json(self.error, &mut out);
+        out
+    }
+
+    fn write_json(&self, error: &Error, out: &mut String) {
+        out.push_str("{\"severity\":\"error\",\"kind\":");
+        write_json_string(kind(error), out);
+        out.push_str(",\"message\":");
+        write_json_string(&error.summary(), out);
+        out.push_str(",\"source\":");
+        match &self.source_name {
+            Some(name) => write_json_string(name, out),
+            None => out.push_str("null"),
+        }
+        match error.position() {
+            Some(position) => {
+                let _ = write!(
+                    out,
+                    ",\"line\":{},\"column\":{}",
+                    position.line, position.column
+                );
+            }
+            None => out.push_str(",\"line\":null,\"column\":null"),
+        }
+        out.push_str(",\"labels\":[");
+        for (i, label) in labels(error).iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let _ = write!(
+                out,
+                "{{\"line\":{},\"column\":{},\"message\":",
+                label.position.line, label.position.column
+            );
+            write_json_string(&label.message, out);
+            let _ = write!(out, ",\"primary\":{}}}", label.primary);
+        }
+        out.push_str("],\"suggestion\":");
+        match error
+            .context()
+            .and_then(|context| context.suggestion.as_ref())
+        {
+            Some(suggestion) => write_json_string(suggestion, out),
+            None => out.push_str("null"),
+        }
+        out.push_str(",\"errors\":[");
+        if let Error::Multiple { errors, .. } = error {
+            for (i, nested) in errors.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                self.write_json(nested, out);
+            }
+        }
+        out.push_str("]}");
+    }
+
+    /// The text of `style` when colors are on
+    fn paint(&self, style: &'static str) -> &'static str {
+        if self.color {
+            style
+        } else {
+            ""
+        }
+    }
+
+    fn render(&self, error: &Error, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reset = self.paint(RESET);
+        writeln!(
+            f,
+            "{}error{reset}{}: {}{reset}",
+            self.paint(ERROR),
+            self.paint(BOLD),
+            error.summary()
+        )?;
+
+        if let Error::Multiple { errors, .. } = error {
+            for nested in errors {
+                writeln!(f)?;
+                self.render(nested, f)?;
+            }
+            return Ok(());
+        }
+        let Some(primary) = error.position() else {
+            return Ok(());
+        };
+
+        let lines: Vec<&str> = self.source.lines().collect();
+        let labels = labels(error);
+        let shown: BTreeSet<usize> = labels
+            .iter()
+            .flat_map(|label| {
+                let line = label.position.line.max(1);
+                line.saturating_sub(self.context_lines).max(1)..=line + self.context_lines
+            })
+            .filter(|&line| line <= lines.len() || labels.iter().any(|l| l.position.line == line))
+            .collect();
+        let width = shown.last().copied().unwrap_or(1).to_string().len();
+        let gutter = self.paint(GUTTER);
+        let pad = " ".repeat(width);
+
+        writeln!(
+            f,
+            "{pad}{gutter}-->{reset} {}:{}:{}",
+            self.source_name.as_deref().unwrap_or("<input>"),
+            primary.line,
+            primary.column
+        )?;
+        writeln!(f, "{pad} {gutter}|{reset}")?;
+        let mut previous = None;
+        for &number in &shown {
+            if previous.is_some_and(|previous| number > previous + 1) {
+                writeln!(f, "{gutter}...{reset}")?;
+            }
+            previous = Some(number);
+            let text = lines.get(number - 1).copied().unwrap_or("");
+            writeln!(f, "{gutter}{number:>width$} |{reset} {text}")?;
+            let mut marked: Vec<&Label> = labels
+                .iter()
+                .filter(|label| label.position.line == number)
+                .collect();
+            marked.sort_by_key(|label| label.position.column);
+            for label in marked {
+                let color = self.paint(if label.primary { ERROR } else { SECONDARY });
+                writeln!(
+                    f,
+                    "{pad} {gutter}|{reset} {}{color}^ {}{reset}",
+                    indent_to(text, label.position.column),
+                    label.message
+                )?;
+            }
+        }
+        writeln!(f, "{pad} {gutter}|{reset}")?;
+
+        if let Some(suggestion) = error
+            .context()
+            .and_then(|context| context.suggestion.as_ref())
+        {
+            writeln!(
+                f,
+                "{pad} {gutter}={reset} {}help{reset}: {suggestion}",
+                self.paint(BOLD)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ErrorReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.render(self.error, f)
+    }
+}
+
+/// The positions an error marks, the one it is reported at first
+fn labels(error: &Error) -> Vec<Label> {
+    let label = |position: Position, message: String, primary: bool| Label {
+        position,
+        message,
+        primary,
+    };
+    match error {
+        Error::UnclosedDelimiter {
+            start_position,
+            current_position,
+            delimiter_type,
+            ..
+        } => vec![
+            label(*current_position, "not closed by here".to_string(), true),
+            label(
+                *start_position,
+                format!("{delimiter_type} opened here"),
+                false,
+            ),
+        ],
+        Error::Indentation {
+            position,
+            expected,
+            found,
+            ..
+        } => vec![label(
+            *position,
+            format!("expected {expected} spaces, found {found}"),
+            true,
+        )],
+        Error::InvalidCharacter {
+            position,
+            character,
+            ..
+        } => vec![label(*position, format!("unexpected {character:?}"), true)],
+        _ => error
+            .position()
+            .map(|position| label(*position, "here".to_string(), true))
+            .into_iter()
+            .collect(),
+    }
+}
+
+/// Whitespace reaching `column` (1-based) of `line`, keeping its tabs so
+/// the marker lines up under the character
+fn indent_to(line: &str, column: usize) -> String {
+    let before = column.saturating_sub(1);
+    let mut indent: String = line
+        .chars()
+        .take(before)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let missing = before.saturating_sub(indent.chars().count());
+    indent.extend(std::iter::repeat_n(' ', missing));
+    indent
+}
+
+/// The name of the kind of `error` used in the JSON form
+const fn kind(error: &Error) -> &'static str {
+    match error {
+        Error::Parse { .. } => "parse",
+        Error::Scan { .. } => "scan",
+        Error::Construction { .. } => "construction",
+        Error::Emission { .. } => "emission",
+        Error::Io { .. } => "io",
+        Error::Utf8 { .. } => "utf8",
+        Error::Type { .. } => "type",
+        Error::Value { .. } => "value",
+        Error::Config { .. } => "config",
+        Error::Multiple { .. } => "multiple",
+        Error::LimitExceeded { .. } => "limit_exceeded",
+        Error::Indentation { .. } => "indentation",
+        Error::InvalidCharacter { .. } => "invalid_character",
+        Error::UnclosedDelimiter { .. } => "unclosed_delimiter",
+    }
+}
+
+fn write_json_string(s: &str, out: &mut String) {
+    out.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let input = "a: 1\nb: [1, 2\nc: 3\nd: 4\n";
+        let error = Error::unclosed_delimiter(
+            Position::at(2, 4, 8),
+            Position::at(4, 5, 24),
+            "flow sequence",
+        );
+        let report = ErrorReport::new(&error, input).with_source_name("x.yaml");
+        assert_eq!(
+            report.to_string(),
+            "error: Unclosed flow sequence starting at line 2, column 4\n \
+             --> x.yaml:4:5\n  \
+             |\n\
+             1 | a: 1\n\
+             2 | b: [1, 2\n  \
+             |    ^ flow sequence opened here\n\
+             3 | c: 3\n\
+             4 | d: 4\n  \
+             |     ^ not closed by here\n  \
+             |\n"
+        );
+
+        let colored = report.clone().with_color(true).to_string();
+        assert!(colored.contains("\x1b[1;31merror\x1b[0m"));
+        assert_eq!(
+            colored
+                .replace(RESET, "")
+                .replace(ERROR, "")
+                .replace(BOLD, "")
+                .replace(GUTTER, "")
+                .replace(SECONDARY, ""),
+            report.to_string()
+        );
+
+        let tabbed = Error::parse(Position::at(1, 3, 2), "bad");
+        let text = ErrorReport::new(&tabbed, "\tx: y\n").to_string();
+        assert!(text.contains("1 | \tx: y\n  | \t ^ here\n"));
+        assert!(text.contains("<input>:1:3"));
+
+        let config = Error::config("no position");
+        assert_eq!(
+            ErrorReport::new(&config, "").to_string(),
+            "error: Configuration error: no position\n"
+        );
+    }
+
+    #[test]
+    fn test_to_json() {
+        let context = crate::error::ErrorContext::new("a: \"x".to_string(), 4)
+            .with_suggestion("close the \"quote\"".to_string());
+        let nested = Error::scan_with_context(Position::at(1, 4, 3), "unterminated", context);
+        let error = Error::multiple(vec![nested, Error::config("bad")], "2 problems");
+        let json = ErrorReport::new(&error, "a: \"x\n").to_json();
+        assert_eq!(
+            json,
+            "{\"severity\":\"error\",\"kind\":\"multiple\",\"message\":\"Multiple errors: 2 problems\",\
+             \"source\":null,\"line\":null,\"column\":null,\"labels\":[],\"suggestion\":null,\"errors\":[\
+             {\"severity\":\"error\",\"kind\":\"scan\",\"message\":\"Scan error: unterminated\",\"source\":null,\
+             \"line\":1,\"column\":4,\"labels\":[{\"line\":1,\"column\":4,\"message\":\"here\",\"primary\":true}],\
+             \"suggestion\":\"close the \\\"quote\\\"\",\"errors\":[]},\
+             {\"severity\":\"error\",\"kind\":\"config\",\"message\":\"Configuration error: bad\",\"source\":null,\
+             \"line\":null,\"column\":null,\"labels\":[],\"suggestion\":null,\"errors\":[]}]}"
+        );
+    }
+}
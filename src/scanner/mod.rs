@@ -1,6 +1,8 @@
 //! YAML scanner for tokenization
 
-use crate::{error::ErrorContext, Error, Limits, Position, ResourceTracker, Result};
+use crate::{
+    error::ErrorContext, value::Chomping, Error, Limits, Position, ResourceTracker, Result,
+};
 
 pub mod indentation;
 pub mod scalar_scanner;
@@ -31,6 +33,80 @@ pub trait Scanner {
 
     /// Get the input text for error reporting
     fn input(&self) -> &str;
+
+    /// Check for an error found while scanning ahead of the tokens returned
+    /// so far
+    fn has_scanning_error(&self) -> bool {
+        false
+    }
+
+    /// Take the error found while scanning ahead of the tokens returned so
+    /// far, which is reported before them
+    fn take_scanning_error(&mut self) -> Option<Error> {
+        None
+    }
+}
+
+/// Tokenize a YAML string, including comments and directives
+///
+/// Tokens are produced lazily with their start and end positions, so editors
+/// and highlighters can reuse the crate's scanner. Iteration stops after the
+/// first error.
+pub fn tokenize(input: &str) -> Tokens {
+    Tokens {
+        scanner: BasicScanner::new_with_comments(input.to_string()),
+        finished: false,
+    }
+}
+
+/// Iterator over the tokens of a YAML string, created by [`tokenize`]
+#[derive(Debug)]
+pub struct Tokens {
+    scanner: BasicScanner,
+    finished: bool,
+}
+
+impl Iterator for Tokens {
+    type Item = Result<Token>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        if let Some(error) = self.scanner.take_scanning_error() {
+            self.finished = true;
+            return Some(Err(error));
+        }
+        match self.scanner.get_token() {
+            Ok(Some(token)) => Some(Ok(token)),
+            Ok(None) => {
+                self.finished = true;
+                None
+            }
+            Err(error) => {
+                self.finished = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+impl std::iter::FusedIterator for Tokens {}
+
+/// What to do with a directive the YAML spec does not accept: an unknown
+/// directive, a second `%YAML` directive or `%TAG` directive for the same
+/// handle before one document, or a `%YAML` version other than 1.x
+///
+/// A 1.x version newer than 1.2 is loaded with a warning either way, as the
+/// spec asks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum InvalidDirective {
+    /// Fail to load the stream, pointing at the directive
+    #[default]
+    Error,
+    /// Ignore the directive and record a warning, reported in
+    /// [`Document::warnings`](crate::Document::warnings)
+    Warn,
 }
 
 /// A basic scanner implementation for YAML tokenization
@@ -54,7 +130,10 @@ pub struct BasicScanner {
     indent_samples: Vec<(usize, bool)>, // (size, is_tabs)
     previous_indent_level: usize,       // Track the previous indentation for style detection
     // Performance optimizations
-    buffer: String,                   // Reusable string buffer for token values
+    buffer: String, // Reusable string buffer for token values
+    // Whether the input is ASCII only, so characters are read straight from
+    // its bytes and the caches below are left empty
+    ascii: bool,
     char_cache: Vec<char>,            // Cached characters for faster access
     char_indices: Vec<(usize, char)>, // Cached character indices for O(1) lookups
     current_char_index: usize,        // Current index in char_cache
@@ -64,8 +143,17 @@ pub struct BasicScanner {
     // Resource tracking
     limits: Limits,
     resource_tracker: ResourceTracker,
-    // Track inline nested sequences that need closing
-    inline_sequence_depth: usize,
+    // Reject invalid escape sequences instead of keeping them literally
+    strict_escapes: bool,
+    // Keep plain scalars as written instead of normalizing booleans and nulls
+    keep_plain_scalars: bool,
+    // What to do with invalid directives, and the warnings about those
+    // ignored and about newer versions, with the position of each
+    invalid_directive: InvalidDirective,
+    warnings: Vec<(Position, String)>,
+    // Directives seen so far for the next document
+    yaml_directive_seen: bool,
+    tag_handles: Vec<String>,
 }
 
 impl BasicScanner {
@@ -76,9 +164,19 @@ impl BasicScanner {
 
     /// Create a new scanner with custom resource limits
     pub fn with_limits(input: String, limits: Limits) -> Self {
-        let char_cache: Vec<char> = input.chars().collect();
-        let char_indices: Vec<(usize, char)> = input.char_indices().collect();
-        let current_char = char_cache.first().copied();
+        // Most documents are ASCII only, and those need no decoding: every
+        // character is a byte at the index it would have in the cache
+        let ascii = input.is_ascii();
+        let (char_cache, char_indices) = if ascii {
+            (Vec::new(), Vec::new())
+        } else {
+            (input.chars().collect(), input.char_indices().collect())
+        };
+        let current_char = if ascii {
+            input.bytes().next().map(char::from)
+        } else {
+            char_cache.first().copied()
+        };
 
         // Track document size for resource limits
         let mut resource_tracker = ResourceTracker::new();
@@ -101,6 +199,7 @@ impl BasicScanner {
                 indent_samples: Vec::new(),
                 previous_indent_level: 0,
                 buffer: String::new(),
+                ascii,
                 char_cache: Vec::new(),
                 char_indices: Vec::new(),
                 current_char_index: 0,
@@ -108,11 +207,16 @@ impl BasicScanner {
                 scanning_error: Some(e),
                 limits,
                 resource_tracker,
-                inline_sequence_depth: 0,
+                strict_escapes: false,
+                keep_plain_scalars: false,
+                invalid_directive: InvalidDirective::default(),
+                warnings: Vec::new(),
+                yaml_directive_seen: false,
+                tag_handles: Vec::new(),
             };
         }
 
-        Self {
+        let mut scanner = Self {
             current_char,
             input,
             position: Position::start(),
@@ -129,6 +233,7 @@ impl BasicScanner {
             indent_samples: Vec::new(),
             previous_indent_level: 0,
             buffer: String::with_capacity(64), // Pre-allocate buffer
+            ascii,
             char_cache,
             char_indices,
             current_char_index: 0,
@@ -138,8 +243,15 @@ impl BasicScanner {
             scanning_error: None,
             limits,
             resource_tracker,
-            inline_sequence_depth: 0,
-        }
+            strict_escapes: false,
+            keep_plain_scalars: false,
+            invalid_directive: InvalidDirective::default(),
+            warnings: Vec::new(),
+            yaml_directive_seen: false,
+            tag_handles: Vec::new(),
+        };
+        scanner.skip_byte_order_mark();
+        scanner
     }
 
     /// Create a new scanner with eager token scanning (for compatibility)
@@ -150,13 +262,18 @@ impl BasicScanner {
     /// Create a new scanner with eager token scanning and custom limits
     pub fn new_eager_with_limits(input: String, limits: Limits) -> Self {
         let mut scanner = Self::with_limits(input, limits);
-        // Store any scanning errors for later retrieval
-        if let Err(error) = scanner.scan_all_tokens() {
-            scanner.scanning_error = Some(error);
-        }
+        scanner.scan_eagerly();
         scanner
     }
 
+    /// Scan all remaining tokens now, storing any error for
+    /// [`take_scanning_error`](Self::take_scanning_error)
+    pub(crate) fn scan_eagerly(&mut self) {
+        if let Err(error) = self.scan_all_tokens() {
+            self.scanning_error = Some(error);
+        }
+    }
+
     /// Create a new scanner with comment preservation enabled
     pub fn new_with_comments(input: String) -> Self {
         let mut scanner = Self::new(input);
@@ -174,7 +291,9 @@ impl BasicScanner {
     /// Create a new scanner with eager scanning and comment preservation
     pub fn new_eager_with_comments(input: String) -> Self {
         let mut scanner = Self::new_with_comments(input);
-        scanner.scan_all_tokens().unwrap_or(());
+        if let Err(error) = scanner.scan_all_tokens() {
+            scanner.scanning_error = Some(error);
+        }
         scanner
     }
 
@@ -194,22 +313,117 @@ impl BasicScanner {
         self.scanning_error.take()
     }
 
+    /// Report an error for an invalid escape sequence in a double-quoted
+    /// scalar, such as `\q` or a `\x` without two hex digits, instead of
+    /// keeping it literally
+    ///
+    /// Takes effect for scalars scanned after the call.
+    pub fn set_strict_escapes(&mut self, strict: bool) {
+        self.strict_escapes = strict;
+    }
+
+    /// Keep plain scalars as written instead of normalizing the YAML 1.1
+    /// boolean and null spellings such as `yes`, `Off` or `~` to `true`,
+    /// `false` and `null`, for loaders that type scalars by another schema
+    ///
+    /// Takes effect for scalars scanned after the call.
+    pub const fn set_keep_plain_scalars(&mut self, keep: bool) {
+        self.keep_plain_scalars = keep;
+    }
+
+    /// Set what to do with directives the YAML spec does not accept, such
+    /// as `%YAML 2.0` or an unknown `%FOO` directive
+    ///
+    /// Takes effect for directives scanned after the call.
+    pub const fn set_invalid_directive(&mut self, invalid_directive: InvalidDirective) {
+        self.invalid_directive = invalid_directive;
+    }
+
+    /// Take the warnings about directives scanned before `position`, which
+    /// belong to the document starting there
+    pub(crate) fn take_warnings_before(&mut self, position: Position) -> Vec<String> {
+        let split = self
+            .warnings
+            .iter()
+            .position(|(at, _)| at.index >= position.index)
+            .unwrap_or(self.warnings.len());
+        self.warnings
+            .drain(..split)
+            .map(|(_, warning)| warning)
+            .collect()
+    }
+
+    /// Number of tokens scanned so far
+    pub fn tokens_scanned(&self) -> usize {
+        self.tokens.len()
+    }
+
     /// Advance to the next character
     fn advance(&mut self) -> Option<char> {
         if let Some(ch) = self.current_char {
-            self.position = self.position.advance(ch);
             self.current_char_index += 1;
-
-            if self.current_char_index < self.char_cache.len() {
-                self.current_char = Some(self.char_cache[self.current_char_index]);
+            // A `\r\n` pair is one line break, so callers that step over a
+            // break see the next line rather than a stray `\n`
+            if ch == '\r' && self.char_at(self.current_char_index) == Some('\n') {
+                self.current_char_index += 1;
+                self.position = self.position.advance_crlf();
             } else {
-                self.current_char = None;
+                self.position = self.position.advance(ch);
             }
+
+            self.current_char = self.char_at(self.current_char_index);
         }
 
         self.current_char
     }
 
+    /// The character at `index` in the input, counted in characters
+    #[inline]
+    fn char_at(&self, index: usize) -> Option<char> {
+        if self.ascii {
+            self.input.as_bytes().get(index).copied().map(char::from)
+        } else {
+            self.char_cache.get(index).copied()
+        }
+    }
+
+    /// Number of characters in the input
+    fn char_count(&self) -> usize {
+        if self.ascii {
+            self.input.len()
+        } else {
+            self.char_cache.len()
+        }
+    }
+
+    /// Index in characters of the character starting at byte `index`
+    fn char_index_of(&self, index: usize) -> usize {
+        if self.ascii {
+            index
+        } else {
+            self.char_indices
+                .binary_search_by_key(&index, |(byte, _)| *byte)
+                .unwrap_or(0)
+        }
+    }
+
+    /// Move back to `position`, which was reached earlier
+    fn rewind_to(&mut self, position: Position) {
+        self.position = position;
+        self.current_char_index = self.char_index_of(position.index);
+        self.current_char = self.char_at(self.current_char_index);
+    }
+
+    /// Step over a byte order mark at the start of the stream, which is not
+    /// part of the content; columns on the first line start after it
+    fn skip_byte_order_mark(&mut self) {
+        if self.current_char_index == 0 && self.current_char == Some('\u{feff}') {
+            self.current_char_index = 1;
+            self.current_char = self.char_at(1);
+            self.position = Position::at(1, 1, '\u{feff}'.len_utf8());
+        }
+    }
+
     /// Skip whitespace characters (excluding newlines)
     fn skip_whitespace(&mut self) {
         while let Some(ch) = self.current_char {
@@ -221,6 +435,33 @@ impl BasicScanner {
         }
     }
 
+    /// Skip to the line break ending the current line, or the end of input
+    fn skip_to_line_end(&mut self) {
+        if self.ascii {
+            // No character before the break can be a multi-byte one, so the
+            // position moves by the bytes skipped
+            let start = self.current_char_index;
+            let len = self.input.as_bytes()[start..]
+                .iter()
+                .position(|byte| matches!(byte, b'\n' | b'\r'))
+                .unwrap_or(self.input.len() - start);
+            self.current_char_index += len;
+            self.current_char = self.char_at(self.current_char_index);
+            self.position = Position::at(
+                self.position.line,
+                self.position.column + len,
+                self.position.index + len,
+            );
+            return;
+        }
+        while let Some(ch) = self.current_char {
+            if ch == '\n' || ch == '\r' {
+                break;
+            }
+            self.advance();
+        }
+    }
+
     /// Handle indentation and produce block tokens if necessary
     fn handle_indentation(&mut self) -> Result<()> {
         // Only handle indentation in block context (flow_level == 0)
@@ -463,8 +704,13 @@ impl BasicScanner {
     /// Check if current position starts a plain scalar
     fn is_plain_scalar_start(&self) -> bool {
         self.current_char.map_or(false, |ch| match ch {
-            '-' | '?' | ':' | ',' | '[' | ']' | '{' | '}' | '#' | '&' | '*' | '!' | '|' | '>'
-            | '\'' | '"' | '%' | '@' | '`' => false,
+            // `-.inf` or `?x` are plain, as long as a safe character follows
+            '-' | '?' | ':' => self.peek_char(1).map_or(false, |next| {
+                !(next.is_whitespace()
+                    || self.flow_level > 0 && scalar_scanner::is_flow_indicator(next))
+            }),
+            ',' | '[' | ']' | '{' | '}' | '#' | '&' | '*' | '!' | '|' | '>' | '\'' | '"' | '%'
+            | '@' | '`' => false,
             _ => !ch.is_whitespace(),
         })
     }
@@ -500,7 +746,7 @@ impl BasicScanner {
     }
 
     /// Normalize a scalar value based on YAML rules
-    fn normalize_scalar(value: String) -> String {
+    pub(crate) fn normalize_scalar(value: String) -> String {
         if Self::is_yaml_bool(&value) {
             // Normalize booleans to lowercase
             match value.to_lowercase().as_str() {
@@ -516,82 +762,58 @@ impl BasicScanner {
         }
     }
 
-    /// Scan a number token
-    fn scan_number(&mut self) -> Result<Token> {
-        let start_pos = self.position;
-        let mut value = String::new();
-
-        // Handle negative numbers
-        if self.current_char == Some('-') {
-            value.push('-');
-            self.advance();
-        }
+    /// Scan a plain scalar (unquoted string)
+    fn scan_plain_scalar(&mut self) -> Result<Token> {
+        self.scan_plain_scalar_from(self.position, String::new())
+    }
 
-        // Scan digits
-        while let Some(ch) = self.current_char {
-            if ch.is_ascii_digit() {
-                value.push(ch);
-                self.advance();
-            } else if ch == '.' {
-                value.push(ch);
-                self.advance();
-                // Scan fractional part
-                while let Some(ch) = self.current_char {
-                    if ch.is_ascii_digit() {
-                        value.push(ch);
-                        self.advance();
-                    } else {
-                        break;
-                    }
-                }
-                break;
-            } else {
-                break;
-            }
+    /// Fail as soon as the scalar that started at `start_pos` has grown to
+    /// `len` bytes past [`Limits::max_scalar_len`], before the rest of it is
+    /// read into memory
+    fn check_scalar_len(&self, start_pos: Position, len: usize) -> Result<()> {
+        if len > self.limits.max_scalar_len {
+            return Err(Error::limit_exceeded(format!(
+                "Maximum scalar length {} exceeded by the scalar at {start_pos}",
+                self.limits.max_scalar_len
+            )));
         }
-
-        Ok(Token::new(
-            TokenType::Scalar(value, tokens::QuoteStyle::Plain),
-            start_pos,
-            self.position,
-        ))
+        Ok(())
     }
 
-    /// Scan a plain scalar (unquoted string)
-    fn scan_plain_scalar(&mut self) -> Result<Token> {
-        let start_pos = self.position;
-        let mut value = String::new();
-
+    /// Scan the rest of a plain scalar that started at `start_pos` with the
+    /// already consumed text `value`
+    fn scan_plain_scalar_from(&mut self, start_pos: Position, mut value: String) -> Result<Token> {
         while let Some(ch) = self.current_char {
-            // Stop at structural characters in block context
-            if self.flow_level == 0 {
-                match ch {
-                    '\n' | '\r' => break,
-                    ':' if self.peek_char(1).map_or(true, |c| c.is_whitespace()) => break,
-                    '#' if value.is_empty()
-                        || self.peek_char(-1).map_or(false, |c| c.is_whitespace()) =>
-                    {
-                        break;
-                    }
-                    _ => {}
+            self.check_scalar_len(start_pos, value.len())?;
+            if self.ascii && self.take_plain_ascii_run(&mut value) {
+                continue;
+            }
+            if matches!(ch, '\n' | '\r') {
+                if self.flow_level == 0 && !self.plain_scalar_continues() {
+                    break;
                 }
-            } else {
-                // In flow context, stop at flow indicators
-                match ch {
-                    ',' | '[' | ']' | '{' | '}' => break,
-                    ':' if self
-                        .peek_char(1)
-                        .map_or(true, |c| c.is_whitespace() || "]}".contains(c)) =>
-                    {
-                        break;
-                    }
-                    '#' if value.is_empty()
-                        || self.peek_char(-1).map_or(false, |c| c.is_whitespace()) =>
-                    {
-                        break;
-                    }
-                    _ => {}
+                // Inside a flow collection, or on a more indented line in block
+                // context, the scalar continues, folding the line break into a space
+                value.truncate(value.trim_end_matches([' ', '\t']).len());
+                let mut breaks = 0;
+                while matches!(self.current_char, Some('\n' | '\r')) {
+                    breaks += 1;
+                    self.advance();
+                    self.skip_whitespace();
                 }
+                match breaks {
+                    1 => value.push(' '),
+                    breaks => value.push_str(&"\n".repeat(breaks - 1)),
+                }
+                continue;
+            }
+            if scalar_scanner::ends_plain_scalar(
+                ch,
+                self.peek_char(-1),
+                self.peek_char(1),
+                self.flow_level > 0,
+            ) {
+                break;
             }
 
             value.push(ch);
@@ -599,12 +821,17 @@ impl BasicScanner {
         }
 
         // Check string length limit
+        self.check_scalar_len(start_pos, value.len())?;
         self.resource_tracker
             .check_string_length(&self.limits, value.len())?;
 
         // Trim trailing whitespace from plain scalars
-        let value = value.trim_end().to_string();
-        let normalized_value = Self::normalize_scalar(value);
+        value.truncate(value.trim_end().len());
+        let normalized_value = if self.keep_plain_scalars {
+            value
+        } else {
+            Self::normalize_scalar(value)
+        };
 
         Ok(Token::new(
             TokenType::Scalar(normalized_value, tokens::QuoteStyle::Plain),
@@ -613,7 +840,209 @@ impl BasicScanner {
         ))
     }
 
+    /// Append the run of ASCII input from here that cannot end a plain
+    /// scalar to `value` in one step, returning whether there was any
+    ///
+    /// Only characters that never end a scalar are taken: line breaks and
+    /// the indicators `ends_plain_scalar` looks at are left to the caller.
+    fn take_plain_ascii_run(&mut self, value: &mut String) -> bool {
+        let start = self.current_char_index;
+        let len = self.input.as_bytes()[start..]
+            .iter()
+            .position(|byte| {
+                matches!(
+                    byte,
+                    b'\n' | b'\r' | b':' | b'#' | b',' | b'[' | b']' | b'{' | b'}'
+                )
+            })
+            .unwrap_or(self.input.len() - start);
+        if len == 0 {
+            return false;
+        }
+        value.push_str(&self.input[start..start + len]);
+        self.current_char_index += len;
+        self.current_char = self.char_at(self.current_char_index);
+        self.position = Position::at(
+            self.position.line,
+            self.position.column + len,
+            self.position.index + len,
+        );
+        true
+    }
+
+    /// Whether a block plain scalar continues on the next non-empty line
+    ///
+    /// The line must be indented more than the enclosing block collection
+    /// and must not start a comment, a document marker, an indicator such as
+    /// `- ` or a mapping key, which all end the scalar instead.
+    fn plain_scalar_continues(&self) -> bool {
+        let min_indent = match self.indent_stack.as_slice() {
+            [_, .., last] => last + 1,
+            _ => 0,
+        };
+        let mut offset = 0;
+        let mut indent = 0;
+        let first = loop {
+            match self.peek_char(offset) {
+                Some('\n' | '\r') => indent = 0,
+                Some(' ') => indent += 1,
+                Some('\t') => {}
+                Some(ch) => break ch,
+                None => return false,
+            }
+            offset += 1;
+        };
+        let next = self.peek_char(offset + 1);
+        let is_indicator =
+            matches!(first, '-' | '?' | ':') && next.map_or(true, |next| next.is_whitespace());
+        let is_document_marker = indent == 0
+            && matches!(first, '-' | '.')
+            && next == Some(first)
+            && self.peek_char(offset + 2) == Some(first);
+        if indent < min_indent || first == '#' || is_indicator || is_document_marker {
+            return false;
+        }
+        // A `key: value` line starts a new entry rather than continuing the scalar
+        let mut prev = first;
+        while let Some(ch) = self.peek_char(offset + 1) {
+            if matches!(ch, '\n' | '\r') || ch == '#' && prev.is_whitespace() {
+                break;
+            }
+            if prev == ':' && ch.is_whitespace() {
+                return false;
+            }
+            prev = ch;
+            offset += 1;
+        }
+        prev != ':'
+    }
+
+    /// Read the hex digits of a `\x`, `\u` or `\U` escape
+    ///
+    /// Expects the current character to be the escape letter and leaves the
+    /// scanner on the last digit, like the single-character escapes. Returns
+    /// `None` without consuming anything when the digits are missing, so the
+    /// escape can be kept literally like other unknown escapes.
+    fn scan_hex_escape(&mut self, digits: usize) -> Result<Option<char>> {
+        let start_pos = self.position;
+        let mut code = 0u32;
+        for offset in 1..=digits {
+            match self
+                .peek_char(offset as isize)
+                .and_then(|ch| ch.to_digit(16))
+            {
+                Some(digit) => code = code * 16 + digit,
+                None => return Ok(None),
+            }
+        }
+        for _ in 0..digits {
+            self.advance();
+        }
+        char::from_u32(code).map(Some).ok_or_else(|| {
+            Error::scan(
+                start_pos,
+                format!("escape sequence U+{code:X} is not a valid Unicode scalar value"),
+            )
+        })
+    }
+
+    /// Decode the escape sequence of a double-quoted scalar whose backslash
+    /// is the current character, leaving the scanner after it
+    ///
+    /// An escaped line break joins the lines without a space. Unknown
+    /// escapes are kept as written unless `strict`; `\'` is accepted as a
+    /// quote for compatibility with older documents.
+    fn scan_escape(&mut self, value: &mut String, strict: bool) -> Result<()> {
+        let escape_pos = self.position;
+        let Some(escaped) = self.advance() else {
+            if strict {
+                return Err(scalar_scanner::invalid_escape(
+                    &self.input,
+                    escape_pos,
+                    "\\",
+                ));
+            }
+            value.push('\\');
+            return Ok(());
+        };
+
+        if matches!(escaped, '\n' | '\r') {
+            self.advance();
+            self.skip_whitespace();
+            while matches!(self.current_char, Some('\n' | '\r')) {
+                value.push('\n');
+                self.advance();
+                self.skip_whitespace();
+            }
+            return Ok(());
+        }
+
+        if let Some(decoded) = scalar_scanner::escaped_char(escaped) {
+            value.push(decoded);
+        } else if let Some(digits) = scalar_scanner::hex_escape_digits(escaped) {
+            match self.scan_hex_escape(digits)? {
+                Some(decoded) => value.push(decoded),
+                None if strict => {
+                    let written: String = (1..=digits as isize)
+                        .map_while(|offset| self.peek_char(offset))
+                        .take_while(char::is_ascii_hexdigit)
+                        .collect();
+                    return Err(scalar_scanner::invalid_escape(
+                        &self.input,
+                        escape_pos,
+                        &format!("\\{escaped}{written}"),
+                    ));
+                }
+                None => {
+                    value.push('\\');
+                    value.push(escaped);
+                }
+            }
+        } else if escaped == '\'' && !strict {
+            value.push('\'');
+        } else if strict {
+            return Err(scalar_scanner::invalid_escape(
+                &self.input,
+                escape_pos,
+                &format!("\\{escaped}"),
+            ));
+        } else {
+            value.push('\\');
+            value.push(escaped);
+        }
+        self.advance();
+        Ok(())
+    }
+
     /// Scan a quoted string
+    /// Check that only a `:`, a flow indicator, a comment or the end of the
+    /// line follows a closing quote on its line, as in `k: "a"b` it may not
+    fn check_after_closing_quote(&self) -> Result<()> {
+        let mut offset = 0;
+        while matches!(self.peek_char(offset), Some(' ' | '\t')) {
+            offset += 1;
+        }
+        let allowed = match self.peek_char(offset) {
+            None | Some('\n' | '\r' | ':' | ',' | '[' | ']' | '{' | '}') => true,
+            Some('#') => offset > 0,
+            Some(_) => false,
+        };
+        if allowed {
+            return Ok(());
+        }
+        // Blanks are one byte and one column each
+        let offset = offset as usize;
+        let position = Position {
+            line: self.position.line,
+            column: self.position.column + offset,
+            index: self.position.index + offset,
+        };
+        Err(Error::scan(
+            position,
+            "Unexpected content after a quoted scalar".to_string(),
+        ))
+    }
+
     fn scan_quoted_string(&mut self, quote_char: char) -> Result<Token> {
         let start_pos = self.position;
         let mut value = String::new();
@@ -628,41 +1057,25 @@ impl BasicScanner {
         self.advance(); // Skip opening quote
 
         while let Some(ch) = self.current_char {
-            if ch == quote_char {
+            self.check_scalar_len(start_pos, value.len())?;
+            if ch == '\'' && quote_char == '\'' && self.peek_char(1) == Some('\'') {
+                // A doubled quote is the only escape of a single-quoted scalar
+                value.push('\'');
+                self.advance();
+                self.advance();
+            } else if ch == quote_char {
                 self.advance(); // Skip closing quote
+                self.check_after_closing_quote()?;
                 break;
-            } else if ch == '\\' {
-                self.advance();
-                if let Some(escaped) = self.current_char {
-                    match escaped {
-                        // Standard C-style escapes
-                        'n' => value.push('\n'),  // newline
-                        't' => value.push('\t'),  // tab
-                        'r' => value.push('\r'),  // carriage return
-                        '\\' => value.push('\\'), // literal backslash
-                        '\'' => value.push('\''), // single quote
-                        '"' => value.push('"'),   // double quote
-
-                        // Additional YAML escapes
-                        '0' => value.push('\0'),   // null character
-                        'a' => value.push('\x07'), // bell character
-                        'b' => value.push('\x08'), // backspace
-                        'f' => value.push('\x0C'), // form feed
-                        'v' => value.push('\x0B'), // vertical tab
-                        'e' => value.push('\x1B'), // escape character
-                        ' ' => value.push(' '),    // literal space
-                        '/' => value.push('/'),    // literal forward slash
-
-                        // For unknown escapes, preserve them literally (YAML spec behavior)
-                        _ => {
-                            value.push('\\');
-                            value.push(escaped);
-                        }
-                    }
-                    self.advance();
-                }
+            } else if ch == '\\' && quote_char == '"' {
+                self.scan_escape(&mut value, self.strict_escapes)?;
+            } else if ch == '\\' && !matches!(self.peek_char(1), None | Some('\n' | '\r')) {
+                // Single-quoted scalars have always decoded backslash escapes
+                // here, and stay lenient whether or not escapes are strict
+                self.scan_escape(&mut value, false)?;
             } else {
-                value.push(ch);
+                // Line breaks in the content are normalized to `\n`
+                value.push(if ch == '\r' { '\n' } else { ch });
                 self.advance();
 
                 // Check string length periodically to fail fast
@@ -677,6 +1090,7 @@ impl BasicScanner {
         }
 
         // Check string length limit
+        self.check_scalar_len(start_pos, value.len())?;
         self.resource_tracker
             .check_string_length(&self.limits, value.len())?;
 
@@ -735,25 +1149,7 @@ impl BasicScanner {
             self.skip_whitespace();
 
             // Parse version number (e.g., "1.2")
-            let major = if let Some(ch) = self.current_char {
-                if ch.is_ascii_digit() {
-                    let digit = ch.to_digit(10).unwrap() as u8;
-                    self.advance();
-                    digit
-                } else {
-                    return Err(Error::scan(
-                        self.position,
-                        "Expected major version number after %YAML".to_string(),
-                    ));
-                }
-            } else {
-                return Err(Error::scan(
-                    self.position,
-                    "Expected version after %YAML directive".to_string(),
-                ));
-            };
-
-            // Expect '.'
+            let major = self.scan_version_number("major")?;
             if self.current_char != Some('.') {
                 return Err(Error::scan(
                     self.position,
@@ -761,25 +1157,22 @@ impl BasicScanner {
                 ));
             }
             self.advance();
+            let minor = self.scan_version_number("minor")?;
 
-            // Parse minor version
-            let minor = if let Some(ch) = self.current_char {
-                if ch.is_ascii_digit() {
-                    let digit = ch.to_digit(10).unwrap() as u8;
-                    self.advance();
-                    digit
-                } else {
-                    return Err(Error::scan(
-                        self.position,
-                        "Expected minor version number after '.'".to_string(),
-                    ));
-                }
-            } else {
+            // Only a comment may follow the version, after whitespace
+            let separated = self.current_char.map_or(true, char::is_whitespace);
+            self.skip_whitespace();
+            let line_ends = match self.current_char {
+                None | Some('\n' | '\r') => true,
+                Some('#') => separated,
+                Some(_) => false,
+            };
+            if !line_ends {
                 return Err(Error::scan(
                     self.position,
-                    "Expected minor version number".to_string(),
+                    "Expected end of line after YAML version".to_string(),
                 ));
-            };
+            }
 
             Ok(Some(Token::new(
                 TokenType::YamlDirective(major, minor),
@@ -788,23 +1181,95 @@ impl BasicScanner {
             )))
         } else {
             // Not a YAML directive, reset position
-            self.position = saved_position;
-            // Properly reset current_char based on saved position
-            self.current_char = self
-                .char_indices
-                .iter()
-                .find(|(i, _)| *i == saved_position.index)
-                .map(|(_, ch)| *ch);
-            // Reset the current_char_index
-            self.current_char_index = self
-                .char_indices
-                .iter()
-                .position(|(i, _)| *i == saved_position.index)
-                .unwrap_or(0);
+            self.rewind_to(saved_position);
             Ok(None)
         }
     }
 
+    /// Scan one number of a `%YAML` version
+    fn scan_version_number(&mut self, part: &str) -> Result<u8> {
+        let start = self.position;
+        let mut digits = String::new();
+        while let Some(ch) = self.current_char.filter(char::is_ascii_digit) {
+            digits.push(ch);
+            self.advance();
+        }
+        digits.parse().map_err(|_| {
+            Error::scan(
+                start,
+                format!("Expected {part} version number in %YAML directive"),
+            )
+        })
+    }
+
+    /// Check a directive against those seen before for the same document,
+    /// returning whether to keep it
+    fn check_directive(&mut self, token: &Token) -> Result<bool> {
+        let position = token.start_position;
+        match &token.token_type {
+            TokenType::YamlDirective(major, minor) => {
+                if self.yaml_directive_seen {
+                    self.reject_directive(
+                        position,
+                        "Duplicate %YAML directive".to_string(),
+                        "A document may have only one %YAML directive",
+                    )?;
+                    return Ok(false);
+                }
+                if *major != 1 {
+                    self.reject_directive(
+                        position,
+                        format!("Unsupported YAML version {major}.{minor}"),
+                        "Only YAML 1.x documents can be loaded; use %YAML 1.2",
+                    )?;
+                    return Ok(false);
+                }
+                self.yaml_directive_seen = true;
+                if *minor > 2 {
+                    self.warnings.push((
+                        position,
+                        format!("YAML version 1.{minor} at {position} is newer than 1.2 and is loaded as 1.2"),
+                    ));
+                }
+            }
+            TokenType::TagDirective(handle, _) => {
+                if self.tag_handles.contains(handle) {
+                    self.reject_directive(
+                        position,
+                        format!("Duplicate %TAG directive for handle '{handle}'"),
+                        "A document may define each tag handle only once",
+                    )?;
+                    return Ok(false);
+                }
+                self.tag_handles.push(handle.clone());
+            }
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    /// Fail on the invalid directive at `position`, or record a warning that
+    /// it is ignored, as set with [`Self::set_invalid_directive`]
+    fn reject_directive(
+        &mut self,
+        position: Position,
+        message: String,
+        suggestion: &str,
+    ) -> Result<()> {
+        match self.invalid_directive {
+            InvalidDirective::Error => {
+                let context = ErrorContext::from_input(&self.input, &position, 2)
+                    .with_suggestion(suggestion.to_string());
+                Err(Error::scan_with_context(position, message, context))
+            }
+            InvalidDirective::Warn => {
+                self.warnings
+                    .push((position, format!("{message} at {position} is ignored")));
+                Ok(())
+            }
+        }
+    }
+
     /// Scan TAG directive (%TAG)
     fn scan_tag_directive(&mut self) -> Result<Option<Token>> {
         if self.current_char != Some('%') {
@@ -844,19 +1309,7 @@ impl BasicScanner {
             )))
         } else {
             // Reset position if not a TAG directive
-            self.position = saved_position;
-            // Properly reset current_char based on saved position
-            self.current_char = self
-                .char_indices
-                .iter()
-                .find(|(i, _)| *i == saved_position.index)
-                .map(|(_, ch)| *ch);
-            // Reset the current_char_index
-            self.current_char_index = self
-                .char_indices
-                .iter()
-                .position(|(i, _)| *i == saved_position.index)
-                .unwrap_or(0);
+            self.rewind_to(saved_position);
             Ok(None)
         }
     }
@@ -992,34 +1445,50 @@ impl BasicScanner {
     fn process_line(&mut self) -> Result<()> {
         // Check for directives at start of line
         if self.position.column == 1 && self.current_char == Some('%') {
-            // Try to scan YAML directive
-            if let Some(token) = self.scan_yaml_directive()? {
-                self.tokens.push(token);
-                return Ok(());
+            let start_pos = self.position;
+            let token = match self.scan_yaml_directive()? {
+                Some(token) => Some(token),
+                None => self.scan_tag_directive()?,
+            };
+            match token {
+                Some(token) => {
+                    if self.check_directive(&token)? {
+                        self.tokens.push(token);
+                    }
+                }
+                None => {
+                    // Reserved directives are skipped to the end of the line
+                    let mut line = String::new();
+                    while let Some(ch) = self.current_char.filter(|ch| !matches!(ch, '\n' | '\r')) {
+                        line.push(ch);
+                        self.advance();
+                    }
+                    let name = line.split_whitespace().next().unwrap_or_default();
+                    self.reject_directive(
+                        start_pos,
+                        format!("Unknown directive '{name}'"),
+                        "Only the %YAML and %TAG directives are defined",
+                    )?;
+                }
             }
-
-            // Try to scan TAG directive
-            if let Some(token) = self.scan_tag_directive()? {
-                self.tokens.push(token);
-                return Ok(());
-            }
-
-            // If not a recognized directive, treat as error
-            if self.current_char == Some('%') {
-                return Err(Error::scan(self.position, "Unknown directive".to_string()));
-            }
-        }
+            return Ok(());
+        }
 
         // Check for document markers at start of line
         if self.position.column == 1 {
             // Check for document start marker
             if let Some(token) = self.scan_document_start()? {
+                // Directives before the next document may be repeated
+                self.yaml_directive_seen = false;
+                self.tag_handles.clear();
+                self.close_open_blocks();
                 self.tokens.push(token);
                 return Ok(());
             }
 
             // Check for document end marker
             if let Some(token) = self.scan_document_end()? {
+                self.close_open_blocks();
                 self.tokens.push(token);
                 return Ok(());
             }
@@ -1042,12 +1511,7 @@ impl BasicScanner {
                     self.tokens.push(comment_token);
                 } else {
                     // Skip comment lines
-                    while let Some(ch) = self.current_char {
-                        if ch == '\n' || ch == '\r' {
-                            break;
-                        }
-                        self.advance();
-                    }
+                    self.skip_to_line_end();
                 }
                 return Ok(());
             }
@@ -1072,12 +1536,7 @@ impl BasicScanner {
                         self.tokens.push(comment_token);
                     } else {
                         // Skip rest of line (comment)
-                        while let Some(ch) = self.current_char {
-                            if ch == '\n' || ch == '\r' {
-                                break;
-                            }
-                            self.advance();
-                        }
+                        self.skip_to_line_end();
                     }
                     break;
                 }
@@ -1128,23 +1587,45 @@ impl BasicScanner {
                         .push(Token::new(TokenType::FlowEntry, pos, self.position));
                 }
 
-                // Key-value separator
-                ':' => {
+                // Key-value separator, unless it starts a plain scalar such as
+                // `::vector`; in flow context a `:` right after a quoted key or a
+                // flow collection is always a separator, as in `{"a":1}`
+                ':' if !self.is_plain_scalar_start()
+                    || self.flow_level > 0
+                        && self.tokens.last().is_some_and(|token| {
+                            matches!(
+                                token.token_type,
+                                TokenType::Scalar(_, QuoteStyle::Single | QuoteStyle::Double)
+                                    | TokenType::FlowSequenceEnd
+                                    | TokenType::FlowMappingEnd
+                            )
+                        }) =>
+                {
                     let pos = self.position;
+                    // A `:` that starts its line is the value of an explicit `?` key
+                    let explicit_value =
+                        self.flow_level == 0 && self.position.column - 1 == self.current_indent;
                     self.advance();
                     self.tokens
                         .push(Token::new(TokenType::Value, pos, self.position));
+                    if explicit_value {
+                        self.skip_whitespace();
+                        self.indent_compact_collection();
+                    }
                 }
 
-                // Explicit key marker
+                // Explicit key marker, which starts a block mapping entry like an implicit key
                 '?' if self.flow_level == 0
                     && (self.peek_char(1).map_or(true, |c| c.is_whitespace())
                         || self.peek_char(1).is_none()) =>
                 {
+                    self.start_block_mapping_if_needed()?;
                     let pos = self.position;
                     self.advance();
                     self.tokens
                         .push(Token::new(TokenType::Key, pos, self.position));
+                    self.skip_whitespace();
+                    self.indent_compact_collection();
                 }
                 '?' if self.flow_level > 0
                     && (self
@@ -1195,6 +1676,8 @@ impl BasicScanner {
                             .any(|t| matches!(t.token_type, TokenType::BlockSequenceStart));
 
                         if !has_active_sequence {
+                            // Track the sequence like a root mapping so it is closed at the end
+                            self.indent_stack.push(self.current_indent);
                             // Check depth limit
                             self.resource_tracker.check_depth(
                                 &self.limits,
@@ -1208,32 +1691,18 @@ impl BasicScanner {
                     self.tokens
                         .push(Token::new(TokenType::BlockEntry, pos, self.position));
 
-                    // After emitting BlockEntry, check if the next token is another dash (nested sequence)
+                    // A compact mapping or nested sequence on the same line, as in
+                    // `- key: value` or `- - item`, is indented to its first character
                     self.skip_whitespace();
-                    if self.current_char == Some('-')
-                        && self.peek_char(1).map_or(true, |c| c.is_whitespace())
-                    {
-                        // We have a nested sequence on the same line!
-                        // Track this as an inline sequence
-                        self.inline_sequence_depth += 1;
-                        // Also push to indent_stack to track proper nesting
-                        self.indent_stack.push(self.position.column);
-                        // Check depth limit
-                        self.resource_tracker
-                            .check_depth(&self.limits, self.flow_level + self.indent_stack.len())?;
-                        self.tokens
-                            .push(Token::simple(TokenType::BlockSequenceStart, self.position));
-                        // Continue processing - the next iteration will handle the nested dash
-                    }
+                    self.indent_compact_collection();
                 }
 
-                // Quoted strings
-                '"' => {
-                    let token = self.scan_quoted_string('"')?;
-                    self.tokens.push(token);
-                }
-                '\'' => {
-                    let token = self.scan_quoted_string('\'')?;
+                // Quoted strings, which may be mapping keys too
+                '"' | '\'' => {
+                    if self.flow_level == 0 && self.check_for_quoted_key_ahead(ch) {
+                        self.start_block_mapping_if_needed()?;
+                    }
+                    let token = self.scan_quoted_string(ch)?;
                     self.tokens.push(token);
                 }
 
@@ -1244,6 +1713,9 @@ impl BasicScanner {
                     if let Some(token) = self.scan_document_start()? {
                         self.tokens.push(token);
                     } else if self.is_plain_scalar_start() {
+                        if self.flow_level == 0 && self.check_for_mapping_ahead() {
+                            self.start_block_mapping_if_needed()?;
+                        }
                         let token = self.scan_plain_scalar()?;
                         self.tokens.push(token);
                     }
@@ -1252,6 +1724,9 @@ impl BasicScanner {
                     if let Some(token) = self.scan_document_end()? {
                         self.tokens.push(token);
                     } else if self.is_plain_scalar_start() {
+                        if self.flow_level == 0 && self.check_for_mapping_ahead() {
+                            self.start_block_mapping_if_needed()?;
+                        }
                         let token = self.scan_plain_scalar()?;
                         self.tokens.push(token);
                     }
@@ -1261,7 +1736,12 @@ impl BasicScanner {
                 _ if ch.is_ascii_digit()
                     || (ch == '-' && self.peek_char(1).map_or(false, |c| c.is_ascii_digit())) =>
                 {
-                    let token = self.scan_number()?;
+                    // A number such as `1: one` may be a mapping key too; text such as
+                    // `0x1F`, `1.2.3` or `0 A` continues as a plain scalar
+                    if self.flow_level == 0 && self.check_for_mapping_ahead() {
+                        self.start_block_mapping_if_needed()?;
+                    }
+                    let token = self.scan_plain_scalar()?;
                     self.tokens.push(token);
                 }
 
@@ -1294,43 +1774,8 @@ impl BasicScanner {
                 // Plain scalars
                 _ if self.is_plain_scalar_start() => {
                     // Look ahead to see if this is a mapping key
-                    if self.flow_level == 0 {
-                        let should_start_mapping = self.check_for_mapping_ahead();
-                        if should_start_mapping {
-                            let last_indent = *self.indent_stack.last().unwrap();
-
-                            // Check if we should start a new mapping
-                            // Start a mapping if:
-                            // 1. No mapping is active at this indentation level, OR
-                            // 2. We're at a deeper indentation level (nested mapping)
-                            let should_start_new_mapping = if self.current_indent > last_indent {
-                                // Deeper indentation - start nested mapping
-                                true
-                            } else if self.current_indent == last_indent {
-                                // Same indentation - check if there's an active mapping at this level
-                                // We need to carefully track mapping contexts across BlockEnd tokens
-                                let has_active_mapping_at_this_level =
-                                    self.check_active_mapping_at_level(self.current_indent);
-                                !has_active_mapping_at_this_level
-                            } else {
-                                // Shallower indentation - should have been handled by handle_indentation
-                                false
-                            };
-
-                            if should_start_new_mapping {
-                                // Start mapping before processing the key
-                                self.indent_stack.push(self.current_indent);
-                                // Check depth limit
-                                self.resource_tracker.check_depth(
-                                    &self.limits,
-                                    self.flow_level + self.indent_stack.len(),
-                                )?;
-                                self.tokens.push(Token::simple(
-                                    TokenType::BlockMappingStart,
-                                    self.position,
-                                ));
-                            }
-                        }
+                    if self.flow_level == 0 && self.check_for_mapping_ahead() {
+                        self.start_block_mapping_if_needed()?;
                     }
 
                     let token = self.scan_plain_scalar()?;
@@ -1350,17 +1795,6 @@ impl BasicScanner {
             }
         }
 
-        // After processing the line, close any inline sequences
-        while self.inline_sequence_depth > 0 {
-            self.inline_sequence_depth -= 1;
-            // Also pop from indent_stack
-            if self.indent_stack.len() > 1 {
-                self.indent_stack.pop();
-            }
-            self.tokens
-                .push(Token::simple(TokenType::BlockEnd, self.position));
-        }
-
         Ok(())
     }
 
@@ -1407,6 +1841,10 @@ impl BasicScanner {
 
     /// Pre-scan all tokens (simplified approach for basic implementation)
     fn scan_all_tokens(&mut self) -> Result<()> {
+        // Typical documents hold a token for every eight characters or so;
+        // the estimate only saves regrowing the buffer
+        self.tokens.reserve(self.char_count() / 8);
+
         // Only add StreamStart if we don't have it yet
         if !self
             .tokens
@@ -1431,11 +1869,7 @@ impl BasicScanner {
         }
 
         // Close any remaining blocks
-        while self.indent_stack.len() > 1 {
-            self.indent_stack.pop();
-            self.tokens
-                .push(Token::simple(TokenType::BlockEnd, self.position));
-        }
+        self.close_open_blocks();
 
         self.tokens
             .push(Token::simple(TokenType::StreamEnd, self.position));
@@ -1443,23 +1877,21 @@ impl BasicScanner {
         Ok(())
     }
 
+    /// Emit a BlockEnd for every open block collection, e.g. before a document marker
+    fn close_open_blocks(&mut self) {
+        while self.indent_stack.len() > 1 {
+            self.indent_stack.pop();
+            self.tokens
+                .push(Token::simple(TokenType::BlockEnd, self.position));
+        }
+        self.current_indent = 0;
+    }
+
     /// Peek at a character at the given offset (can be negative)
     fn peek_char(&self, offset: isize) -> Option<char> {
-        if offset >= 0 {
-            let target_index = self.current_char_index + offset as usize;
-            if target_index < self.char_cache.len() {
-                Some(self.char_cache[target_index])
-            } else {
-                None
-            }
-        } else {
-            let offset_magnitude = (-offset) as usize;
-            if self.current_char_index >= offset_magnitude {
-                Some(self.char_cache[self.current_char_index - offset_magnitude])
-            } else {
-                None
-            }
-        }
+        self.current_char_index
+            .checked_add_signed(offset)
+            .and_then(|index| self.char_at(index))
     }
 
     /// Scan an anchor token (&name)
@@ -1467,7 +1899,7 @@ impl BasicScanner {
         let start_pos = self.position;
         self.advance(); // Skip '&'
 
-        let name = self.scan_identifier()?;
+        let name = self.scan_identifier("anchor")?;
         if name.is_empty() {
             let context = ErrorContext::from_input(&self.input, &self.position, 2).with_suggestion(
                 "Provide a valid anchor name after &, e.g., &anchor_name".to_string(),
@@ -1494,7 +1926,7 @@ impl BasicScanner {
         let start_pos = self.position;
         self.advance(); // Skip '*'
 
-        let name = self.scan_identifier()?;
+        let name = self.scan_identifier("alias")?;
         if name.is_empty() {
             let context = ErrorContext::from_input(&self.input, &self.position, 2).with_suggestion(
                 "Provide a valid alias name after *, e.g., *alias_name".to_string(),
@@ -1510,16 +1942,40 @@ impl BasicScanner {
     }
 
     /// Scan an identifier (used for anchor and alias names)
-    fn scan_identifier(&mut self) -> Result<String> {
+    ///
+    /// The name runs up to whitespace or a flow indicator; any other
+    /// character outside the spec's anchor character set is an error, as is
+    /// a name longer than [`Limits::max_anchor_name_len`].
+    fn scan_identifier(&mut self, kind: &str) -> Result<String> {
+        let start_pos = self.position;
         let mut identifier = String::new();
+        let mut length = 0;
 
         while let Some(ch) = self.current_char {
-            if ch.is_alphanumeric() || ch == '_' || ch == '-' {
-                identifier.push(ch);
-                self.advance();
-            } else {
+            if matches!(ch, ' ' | '\t' | '\n' | '\r') || scalar_scanner::is_flow_indicator(ch) {
                 break;
             }
+            if !scalar_scanner::is_anchor_char(ch) {
+                let suggestion = format!(
+                    "Use only printable characters other than spaces and ,[]{{}} in {kind} names"
+                );
+                let context = ErrorContext::from_input(&self.input, &self.position, 2)
+                    .with_suggestion(suggestion);
+                return Err(Error::scan_with_context(
+                    self.position,
+                    format!("Invalid character {ch:?} in {kind} name"),
+                    context,
+                ));
+            }
+            length += 1;
+            if length > self.limits.max_anchor_name_len {
+                return Err(Error::limit_exceeded(format!(
+                    "Maximum {kind} name length {} exceeded by the {kind} at {start_pos}",
+                    self.limits.max_anchor_name_len
+                )));
+            }
+            identifier.push(ch);
+            self.advance();
         }
 
         Ok(identifier)
@@ -1557,11 +2013,30 @@ impl BasicScanner {
             if self.current_char == Some('!') {
                 tag.push('!');
                 self.advance(); // Skip second '!'
+            } else {
+                // A named handle such as `!e!` is word characters closed by '!'
+                let mut length = 0;
+                while let Some(ch) = self.peek_char(length) {
+                    if ch.is_ascii_alphanumeric() || ch == '-' {
+                        length += 1;
+                    } else {
+                        break;
+                    }
+                }
+                if length > 0 && self.peek_char(length) == Some('!') {
+                    for _ in 0..=length {
+                        if let Some(ch) = self.current_char {
+                            tag.push(ch);
+                        }
+                        self.advance();
+                    }
+                }
             }
 
-            // Scan tag name/suffix
+            // Scan the suffix; characters a tag may not hold are reported
+            // when it is resolved
             while let Some(ch) = self.current_char {
-                if ch.is_alphanumeric() || "-./_:".contains(ch) {
+                if !ch.is_whitespace() && !",[]{}".contains(ch) {
                     tag.push(ch);
                     self.advance();
                 } else {
@@ -1578,23 +2053,7 @@ impl BasicScanner {
         let start_pos = self.position;
         self.advance(); // Skip '|'
 
-        // Parse block scalar header (indicators like +, -, explicit indent)
-        let (keep_trailing, explicit_indent) = self.scan_block_scalar_header()?;
-
-        // Skip to next line
-        self.skip_to_next_line()?;
-
-        // Determine indentation
-        let base_indent = self.current_indent;
-        let content_indent = if let Some(explicit) = explicit_indent {
-            base_indent + explicit
-        } else {
-            // Find the first non-empty content line to determine indentation
-            self.find_block_scalar_indent(base_indent)?
-        };
-
-        // Collect the literal block content
-        let content = self.collect_literal_block_content(content_indent, keep_trailing)?;
+        let content = self.scan_block_scalar_content(start_pos, false)?;
 
         Ok(Token::new(
             TokenType::BlockScalarLiteral(content),
@@ -1608,23 +2067,7 @@ impl BasicScanner {
         let start_pos = self.position;
         self.advance(); // Skip '>'
 
-        // Parse block scalar header (indicators like +, -, explicit indent)
-        let (keep_trailing, explicit_indent) = self.scan_block_scalar_header()?;
-
-        // Skip to next line
-        self.skip_to_next_line()?;
-
-        // Determine indentation
-        let base_indent = self.current_indent;
-        let content_indent = if let Some(explicit) = explicit_indent {
-            base_indent + explicit
-        } else {
-            // Find the first non-empty content line to determine indentation
-            self.find_block_scalar_indent(base_indent)?
-        };
-
-        // Collect the folded block content
-        let content = self.collect_folded_block_content(content_indent, keep_trailing)?;
+        let content = self.scan_block_scalar_content(start_pos, true)?;
 
         Ok(Token::new(
             TokenType::BlockScalarFolded(content),
@@ -1633,23 +2076,79 @@ impl BasicScanner {
         ))
     }
 
-    /// Parse block scalar header indicators (+, -, and explicit indent)
-    fn scan_block_scalar_header(&mut self) -> Result<(bool, Option<usize>)> {
-        let mut keep_trailing = false;
+    /// Scan the header and content of a block scalar whose indicator at
+    /// `start_pos` has been consumed, stopping at the line break before the
+    /// next construct so that its indentation is handled as usual
+    fn scan_block_scalar_content(&mut self, start_pos: Position, folded: bool) -> Result<String> {
+        let (chomping, explicit_indent) = self.scan_block_scalar_header()?;
+
+        // Content is indented past the parent node; a top-level scalar has no
+        // parent and may start in the first column
+        let parent_indent = if self.at_document_level() {
+            None
+        } else {
+            Some(self.current_indent)
+        };
+        let content_indent = match explicit_indent {
+            Some(increment) => parent_indent.map_or(increment, |indent| indent + increment),
+            None => self.find_block_scalar_indent(parent_indent.map_or(0, |indent| indent + 1)),
+        };
+
+        let (lines, terminated) = self.collect_block_scalar_lines(start_pos, content_indent)?;
+
+        // Lines after the last one with content only contribute line breaks
+        let body_len = lines
+            .iter()
+            .rposition(|line| !line.is_empty())
+            .map_or(0, |index| index + 1);
+        let body = &lines[..body_len];
+        let breaks = if lines.is_empty() {
+            0
+        } else {
+            lines.len() - body_len.saturating_sub(1) - usize::from(!terminated)
+        };
+
+        let mut content = if folded {
+            fold_block_lines(body)
+        } else {
+            body.join("\n")
+        };
+        match chomping {
+            Chomping::Strip => {}
+            Chomping::Clip => {
+                if body_len > 0 && breaks > 0 {
+                    content.push('\n');
+                }
+            }
+            Chomping::Keep => content.push_str(&"\n".repeat(breaks)),
+        }
+
+        // Check string length limit
+        self.check_scalar_len(start_pos, content.len())?;
+        self.resource_tracker
+            .check_string_length(&self.limits, content.len())?;
+
+        Ok(content)
+    }
+
+    /// Parse block scalar header indicators (+, -, and explicit indent),
+    /// leaving the scanner at the line break that ends the header
+    fn scan_block_scalar_header(&mut self) -> Result<(Chomping, Option<usize>)> {
+        let mut chomping = Chomping::Clip;
         let mut explicit_indent: Option<usize> = None;
 
         // Parse indicators in any order
         while let Some(ch) = self.current_char {
             match ch {
                 '+' => {
-                    keep_trailing = true;
+                    chomping = Chomping::Keep;
                     self.advance();
                 }
                 '-' => {
-                    keep_trailing = false; // Strip trailing newlines
+                    chomping = Chomping::Strip;
                     self.advance();
                 }
-                '0'..='9' => {
+                '1'..='9' => {
                     let digit = ch.to_digit(10).unwrap() as usize;
                     if explicit_indent.is_some() {
                         let context = ErrorContext::from_input(&self.input, &self.position, 2)
@@ -1669,11 +2168,17 @@ impl BasicScanner {
                     self.advance(); // Skip whitespace
                 }
                 '#' => {
-                    // Skip comment to end of line
-                    while let Some(ch) = self.current_char {
-                        self.advance();
-                        if ch == '\n' || ch == '\r' {
-                            break;
+                    if self.preserve_comments {
+                        // Kept ahead of the scalar, which is pushed once scanned
+                        let comment_token = self.scan_comment()?;
+                        self.tokens.push(comment_token);
+                    } else {
+                        // Skip comment to end of line
+                        while let Some(ch) = self.current_char {
+                            if ch == '\n' || ch == '\r' {
+                                break;
+                            }
+                            self.advance();
                         }
                     }
                     break;
@@ -1681,7 +2186,7 @@ impl BasicScanner {
                 '\n' | '\r' => break,
                 _ => {
                     let context = ErrorContext::from_input(&self.input, &self.position, 2)
-                        .with_suggestion("Use valid block scalar indicators: | (literal), > (folded), + (keep), - (strip), or digit (indent)".to_string());
+                        .with_suggestion("Use valid block scalar indicators: | (literal), > (folded), + (keep), - (strip), or digit 1-9 (indent)".to_string());
                     return Err(Error::invalid_character_with_context(
                         self.position,
                         ch,
@@ -1692,78 +2197,67 @@ impl BasicScanner {
             }
         }
 
-        Ok((keep_trailing, explicit_indent))
+        Ok((chomping, explicit_indent))
     }
 
-    /// Skip whitespace and comments to the next content line
-    fn skip_to_next_line(&mut self) -> Result<()> {
-        while let Some(ch) = self.current_char {
-            match ch {
-                '\n' | '\r' => {
-                    self.advance();
-                    break;
-                }
-                ' ' | '\t' => {
-                    self.advance();
-                }
-                _ => break,
-            }
-        }
-        Ok(())
+    /// Whether the node being scanned is the document's root node, rather
+    /// than a value or item of a block collection
+    fn at_document_level(&self) -> bool {
+        self.tokens
+            .iter()
+            .rev()
+            .find(|token| {
+                !matches!(
+                    token.token_type,
+                    TokenType::Anchor(_) | TokenType::Tag(_) | TokenType::Comment(_)
+                )
+            })
+            .map_or(true, |token| {
+                matches!(
+                    token.token_type,
+                    TokenType::StreamStart | TokenType::DocumentStart
+                )
+            })
     }
 
-    /// Find the content indentation for a block scalar
-    fn find_block_scalar_indent(&mut self, base_indent: usize) -> Result<usize> {
-        let saved_position = self.position;
-        let saved_char = self.current_char;
-        let saved_char_index = self.current_char_index;
-
-        let mut content_indent = base_indent + 1; // Default minimum indent
-
-        // Look ahead to find the first non-empty line
-        while let Some(ch) = self.current_char {
-            self.advance();
-            if ch == '\n' || ch == '\r' {
-                let line_indent = self.count_line_indent();
-
-                // If this line has content (not just whitespace)
-                if let Some(line_ch) = self.current_char {
-                    if line_ch != '\n' && line_ch != '\r' {
-                        if line_indent > base_indent {
-                            content_indent = line_indent;
-                            break;
-                        }
-                        // Content must be indented more than the block scalar indicator
-                        content_indent = base_indent + 1;
-                        break;
-                    }
-                }
-            }
+    /// Whether the scanner is at a `---` or `...` marker in the first column
+    fn at_document_marker(&self) -> bool {
+        if self.position.column != 1 {
+            return false;
         }
-
-        // Restore position
-        self.position = saved_position;
-        self.current_char = saved_char;
-        self.current_char_index = saved_char_index;
-
-        Ok(content_indent)
+        let index = self.current_char_index;
+        let marker = [
+            self.char_at(index),
+            self.char_at(index + 1),
+            self.char_at(index + 2),
+        ];
+        matches!(
+            marker,
+            [Some('-'), Some('-'), Some('-')] | [Some('.'), Some('.'), Some('.')]
+        ) && self.char_at(index + 3).map_or(true, char::is_whitespace)
     }
 
-    /// Count indentation at start of current line
-    fn count_line_indent(&mut self) -> usize {
-        let mut indent = 0;
+    /// Detect the content indentation of a block scalar from its first
+    /// non-empty line, starting at the line break that ends the header
+    fn find_block_scalar_indent(&mut self, min_indent: usize) -> usize {
         let saved_position = self.position;
         let saved_char = self.current_char;
         let saved_char_index = self.current_char_index;
 
-        while let Some(ch) = self.current_char {
-            if ch == ' ' {
-                indent += 1;
-                self.advance();
-            } else if ch == '\t' {
-                indent += 8; // Tab counts as 8 spaces
+        // Leading empty lines may be indented further than the first content line
+        let mut indent = min_indent;
+        while matches!(self.current_char, Some('\n' | '\r')) {
+            self.advance();
+            if self.at_document_marker() {
+                break;
+            }
+            let mut spaces = 0;
+            while self.current_char == Some(' ') {
+                spaces += 1;
                 self.advance();
-            } else {
+            }
+            indent = indent.max(spaces);
+            if !matches!(self.current_char, None | Some('\n' | '\r')) {
                 break;
             }
         }
@@ -1776,137 +2270,217 @@ impl BasicScanner {
         indent
     }
 
-    /// Collect content for a literal block scalar
-    fn collect_literal_block_content(
+    /// Collect the lines of a block scalar with their indentation removed,
+    /// starting at the line break that ends the header
+    ///
+    /// Empty lines are returned as empty strings. Scanning stops at the line
+    /// break before the first line indented less than `content_indent`, or at
+    /// a document marker. Also returns whether the last line ends in a line
+    /// break. Fails once the lines outgrow [`Limits::max_scalar_len`] for the
+    /// scalar at `start_pos`.
+    fn collect_block_scalar_lines(
         &mut self,
+        start_pos: Position,
         content_indent: usize,
-        _keep_trailing: bool,
-    ) -> Result<String> {
-        let mut content = String::new();
-
-        while let Some(_) = self.current_char {
-            let line_indent = self.count_line_indent();
-
-            // Skip indentation
-            for _ in 0..content_indent.min(line_indent) {
-                if let Some(' ' | '\t') = self.current_char {
-                    self.advance();
-                }
-            }
-
-            // Collect line content
-            let mut line = String::new();
-            while let Some(ch) = self.current_char {
-                if ch == '\n' || ch == '\r' {
-                    self.advance();
-                    break;
-                }
-                line.push(ch);
-                self.advance();
-            }
+    ) -> Result<(Vec<String>, bool)> {
+        let mut lines = Vec::new();
+        let mut terminated = false;
+        // Bytes collected so far, counting a line break per line
+        let mut len = 0;
+
+        while matches!(self.current_char, Some('\n' | '\r')) {
+            let saved_position = self.position;
+            let saved_char = self.current_char;
+            let saved_char_index = self.current_char_index;
+            self.advance();
+            terminated = true;
 
-            // Check if we've reached the end of the block scalar
-            if line_indent < content_indent && !line.trim().is_empty() {
-                // This line is part of the next construct
+            if self.current_char.is_none() {
                 break;
             }
-
-            // Add line to content (preserving literal newlines)
-            content.push_str(&line);
-            if self.current_char.is_some() {
-                content.push('\n');
-            }
-
-            // Check for end of input or document boundaries
-            if self.current_char.is_none() {
+            if self.at_document_marker() {
+                self.position = saved_position;
+                self.current_char = saved_char;
+                self.current_char_index = saved_char_index;
                 break;
             }
-        }
 
-        Ok(content)
-    }
-
-    /// Collect content for a folded block scalar
-    fn collect_folded_block_content(
-        &mut self,
-        content_indent: usize,
-        _keep_trailing: bool,
-    ) -> Result<String> {
-        let mut content = String::new();
-        let mut prev_was_empty = false;
-        let mut first_line = true;
-
-        while let Some(_) = self.current_char {
-            let line_indent = self.count_line_indent();
-
-            // Skip indentation
-            for _ in 0..content_indent.min(line_indent) {
-                if let Some(' ' | '\t') = self.current_char {
-                    self.advance();
-                }
+            let mut spaces = 0;
+            while spaces < content_indent && self.current_char == Some(' ') {
+                spaces += 1;
+                self.advance();
             }
-
-            // Collect line content
-            let mut line = String::new();
+            let mut text = String::new();
             while let Some(ch) = self.current_char {
                 if ch == '\n' || ch == '\r' {
-                    self.advance();
                     break;
                 }
-                line.push(ch);
+                self.check_scalar_len(start_pos, len + text.len())?;
+                text.push(ch);
                 self.advance();
             }
 
-            // Check if we've reached the end of the block scalar
-            if line_indent < content_indent && !line.trim().is_empty() {
-                break;
-            }
-
-            let line_is_empty = line.trim().is_empty();
-
-            if line_is_empty {
-                // Empty lines are preserved as-is
-                if !first_line && !prev_was_empty {
-                    content.push('\n');
-                }
-                prev_was_empty = true;
-            } else {
-                // Non-empty lines are folded (joined with spaces)
-                if !first_line && !prev_was_empty {
-                    content.push(' '); // Fold previous line break into space
+            if spaces < content_indent {
+                if !text.trim().is_empty() {
+                    // This line is part of the next construct
+                    self.position = saved_position;
+                    self.current_char = saved_char;
+                    self.current_char_index = saved_char_index;
+                    break;
                 }
-                content.push_str(line.trim());
-                prev_was_empty = false;
-            }
-
-            first_line = false;
-
-            if self.current_char.is_none() {
-                break;
+                text.clear();
             }
+            len += text.len() + 1;
+            self.check_scalar_len(start_pos, len)?;
+            lines.push(text);
+            terminated = false;
         }
 
-        Ok(content)
+        Ok((lines, terminated))
     }
 
     /// Check if the current position is the start of a mapping key by looking ahead for ':'
     fn check_for_mapping_ahead(&self) -> bool {
-        // Look ahead through the current line for a ':' character
-        for i in self.current_char_index..self.char_cache.len() {
-            let ch = self.char_cache[i];
+        // Look ahead through the current line for a ':' that ends the plain
+        // scalar, so `a:b: c` has the key `a:b` and `http://x` is no key
+        for i in self.current_char_index..self.char_count() {
+            let Some(ch) = self.char_at(i) else {
+                break;
+            };
+            let prev = i.checked_sub(1).and_then(|prev| self.char_at(prev));
+            let next = self.char_at(i + 1);
             match ch {
-                ':' => {
-                    // Found colon, check if it's followed by whitespace or end of line
-                    let next_char = self.char_cache.get(i + 1).copied();
-                    return next_char.map_or(true, |c| c.is_whitespace());
-                }
                 '\n' | '\r' => break, // End of line, no colon found
+                ':' | '#' if scalar_scanner::ends_plain_scalar(ch, prev, next, false) => {
+                    return ch == ':';
+                }
                 _ => {}
             }
         }
         false
     }
 
+    /// Check whether the quoted scalar starting here is followed by `:` on the
+    /// same line, making it a mapping key
+    fn check_for_quoted_key_ahead(&self, quote_char: char) -> bool {
+        let mut i = self.current_char_index + 1;
+        // Find the closing quote
+        loop {
+            match self.char_at(i) {
+                None | Some('\n' | '\r') => return false,
+                Some('\\') if quote_char == '"' => i += 2,
+                Some(ch) if ch == quote_char => {
+                    if quote_char == '\'' && self.char_at(i + 1) == Some('\'') {
+                        i += 2;
+                    } else {
+                        break;
+                    }
+                }
+                Some(_) => i += 1,
+            }
+        }
+        i += 1;
+        while matches!(self.char_at(i), Some(' ' | '\t')) {
+            i += 1;
+        }
+        self.char_at(i) == Some(':') && self.char_at(i + 1).map_or(true, char::is_whitespace)
+    }
+
+    /// Indent a collection that starts on the same line as a `-` entry, an
+    /// explicit `?` key or its `:` value, as in `- - a` or `? b: c`, to its
+    /// first character
+    fn indent_compact_collection(&mut self) {
+        let compact = match self.current_char {
+            Some('-' | '?') => self.peek_char(1).map_or(true, char::is_whitespace),
+            // A key with properties, as in `- &a key: value`
+            Some('&' | '!') => self.check_for_mapping_ahead(),
+            Some(quote @ ('"' | '\'')) => self.check_for_quoted_key_ahead(quote),
+            Some(_) if self.is_plain_scalar_start() => self.check_for_mapping_ahead(),
+            _ => false,
+        };
+        if compact {
+            self.current_indent = self.position.column - 1;
+            // Later lines are indented relative to this level, as in `- - a\n  - b`
+            self.previous_indent_level = self.current_indent;
+        }
+    }
+
+    /// Emit `BlockMappingStart` before a key unless a mapping is already
+    /// active at the current indentation
+    fn start_block_mapping_if_needed(&mut self) -> Result<()> {
+        let last_indent = *self.indent_stack.last().unwrap();
+
+        // Check if we should start a new mapping
+        // Start a mapping if:
+        // 1. No mapping is active at this indentation level, OR
+        // 2. We're at a deeper indentation level (nested mapping)
+        let should_start_new_mapping = if self.current_indent > last_indent {
+            // Deeper indentation - start nested mapping
+            true
+        } else if self.current_indent == last_indent {
+            // Same indentation - check if there's an active mapping at this level
+            // We need to carefully track mapping contexts across BlockEnd tokens
+            let has_active_mapping_at_this_level =
+                self.check_active_mapping_at_level(self.current_indent);
+            !has_active_mapping_at_this_level
+        } else {
+            // Shallower indentation - should have been handled by handle_indentation
+            false
+        };
+
+        if should_start_new_mapping {
+            // Start mapping before processing the key
+            self.indent_stack.push(self.current_indent);
+            // Check depth limit
+            self.resource_tracker
+                .check_depth(&self.limits, self.flow_level + self.indent_stack.len())?;
+            // An anchor or tag on the key's line, as in `&a key: value`, belongs
+            // to the key rather than the mapping
+            let key_properties = self
+                .tokens
+                .iter()
+                .rev()
+                .take_while(|token| {
+                    matches!(token.token_type, TokenType::Anchor(_) | TokenType::Tag(_))
+                        && token.start_position.line == self.position.line
+                })
+                .count();
+            let (index, position) = match key_properties {
+                0 => (self.tokens.len(), self.position),
+                count => {
+                    let index = self.tokens.len() - count;
+                    (index, self.tokens[index].start_position)
+                }
+            };
+            self.tokens
+                .insert(index, Token::simple(TokenType::BlockMappingStart, position));
+        } else if self.follows_value_on_same_line() {
+            // A key right after another key's `:`, as in `key: value: extra`
+            let context = ErrorContext::from_input(&self.input, &self.position, 2).with_suggestion(
+                "Quote the value if it contains ': ', as in key: \"value: extra\"".to_string(),
+            );
+            return Err(Error::scan_with_context(
+                self.position,
+                "mapping values are not allowed in this context",
+                context,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether the previous token, ignoring node properties, is a `:` on the
+    /// current line
+    fn follows_value_on_same_line(&self) -> bool {
+        self.tokens
+            .iter()
+            .rev()
+            .find(|token| !matches!(token.token_type, TokenType::Anchor(_) | TokenType::Tag(_)))
+            .is_some_and(|token| {
+                matches!(token.token_type, TokenType::Value)
+                    && token.start_position.line == self.position.line
+            })
+    }
+
     /// Check if there's an active mapping at the specified indentation level
     /// This method properly handles BlockEnd tokens by tracking mapping start/end pairs
     fn check_active_mapping_at_level(&self, _target_indent: usize) -> bool {
@@ -1925,6 +2499,13 @@ impl BasicScanner {
                     }
                     mapping_depth -= 1;
                 }
+                TokenType::BlockSequenceStart => {
+                    if mapping_depth == 0 {
+                        // The innermost open collection is a sequence
+                        return false;
+                    }
+                    mapping_depth -= 1;
+                }
                 TokenType::BlockEnd => {
                     mapping_depth += 1;
                 }
@@ -1940,6 +2521,41 @@ impl BasicScanner {
     }
 }
 
+/// Join the content lines of a folded block scalar: a line break between two
+/// text lines becomes a space, while empty lines and lines that start with
+/// whitespace keep their line breaks
+pub(crate) fn fold_block_lines<S: AsRef<str>>(lines: &[S]) -> String {
+    let mut content = String::new();
+    // Whether the previous text line started with whitespace
+    let mut previous: Option<bool> = None;
+    let mut empty_lines = 0;
+
+    for line in lines {
+        let line = line.as_ref();
+        if line.is_empty() {
+            empty_lines += 1;
+            continue;
+        }
+        let spaced = line.starts_with([' ', '\t']);
+        let breaks = match previous {
+            None => empty_lines,
+            Some(false) if !spaced => {
+                if empty_lines == 0 {
+                    content.push(' ');
+                }
+                empty_lines
+            }
+            Some(_) => empty_lines + 1,
+        };
+        content.push_str(&"\n".repeat(breaks));
+        content.push_str(line);
+        previous = Some(spaced);
+        empty_lines = 0;
+    }
+
+    content
+}
+
 impl Scanner for BasicScanner {
     fn check_token(&self) -> bool {
         // For lazy scanning: check if we have cached tokens or can generate more
@@ -1958,13 +2574,17 @@ impl Scanner for BasicScanner {
             self.scan_next_token()?;
         }
 
-        if self.token_index < self.tokens.len() {
-            let token = self.tokens[self.token_index].clone();
-            self.token_index += 1;
-            Ok(Some(token))
-        } else {
-            Ok(None)
-        }
+        // The scanner only looks back at the kinds of earlier tokens, so the
+        // text of a returned token is moved out rather than copied
+        let Some(token) = self.tokens.get_mut(self.token_index) else {
+            return Ok(None);
+        };
+        self.token_index += 1;
+        Ok(Some(Token::new(
+            token.token_type.take_text(),
+            token.start_position,
+            token.end_position,
+        )))
     }
 
     fn reset(&mut self) {
@@ -1980,7 +2600,11 @@ impl Scanner for BasicScanner {
         self.indent_samples.clear();
         self.previous_indent_level = 0;
         self.current_char_index = 0;
-        self.current_char = self.char_cache.first().copied();
+        self.current_char = self.char_at(0);
+        self.warnings.clear();
+        self.yaml_directive_seen = false;
+        self.tag_handles.clear();
+        self.skip_byte_order_mark();
     }
 
     fn position(&self) -> Position {
@@ -1990,6 +2614,14 @@ impl Scanner for BasicScanner {
     fn input(&self) -> &str {
         &self.input
     }
+
+    fn has_scanning_error(&self) -> bool {
+        Self::has_scanning_error(self)
+    }
+
+    fn take_scanning_error(&mut self) -> Option<Error> {
+        Self::take_scanning_error(self)
+    }
 }
 
 #[cfg(test)]
@@ -2113,6 +2745,36 @@ normal: value # This is a comment
             .any(|s| s.contains("This is a comment")));
     }
 
+    #[test]
+    fn test_number_prefixed_plain_scalars() {
+        let input = "a: 0x1F\nb: 1.2.3\nc: 2024-01-01\nd: [10, 0o17]\ne: 42\n";
+        let mut scanner = BasicScanner::new(input.to_string());
+
+        let mut scalar_values = Vec::new();
+        while let Ok(Some(token)) = scanner.get_token() {
+            if let TokenType::Scalar(value, _) = token.token_type {
+                scalar_values.push(value);
+            }
+        }
+
+        assert_eq!(
+            scalar_values,
+            [
+                "a",
+                "0x1F",
+                "b",
+                "1.2.3",
+                "c",
+                "2024-01-01",
+                "d",
+                "10",
+                "0o17",
+                "e",
+                "42"
+            ]
+        );
+    }
+
     #[test]
     fn test_escape_sequences() {
         // Test standard C-style escapes
@@ -2153,6 +2815,8 @@ normal: value # This is a comment
             (r#""\e""#, "\x1B"), // escape
             (r#""\ ""#, " "),    // literal space
             (r#""\/"#, "/"),     // literal forward slash
+            // hex escapes
+            (r#""\x41\u00e9\U0001F600""#, "A\u{e9}\u{1F600}"),
         ];
 
         for (input, expected) in test_cases {
@@ -2174,8 +2838,8 @@ normal: value # This is a comment
     #[test]
     fn test_unknown_escape_sequences() {
         // Test that unknown escape sequences are preserved literally
-        let input = r#""\z\q\8""#;
-        let expected = "\\z\\q\\8"; // Should preserve backslashes for unknown escapes
+        let input = r#""\z\q\8\xZ""#;
+        let expected = "\\z\\q\\8\\xZ"; // Should preserve backslashes for unknown escapes
 
         let mut scanner = BasicScanner::new(input.to_string());
         scanner.get_token().unwrap(); // Skip StreamStart
@@ -2190,4 +2854,284 @@ normal: value # This is a comment
             panic!("Failed to get token");
         }
     }
+
+    #[test]
+    fn test_strict_escapes() {
+        let scan = |input: &str, strict: bool| {
+            let mut scanner = BasicScanner::new(input.to_string());
+            scanner.set_strict_escapes(strict);
+            scanner.get_token().unwrap(); // Skip StreamStart
+            scanner
+                .get_token()
+                .map(|token| match token.unwrap().token_type {
+                    TokenType::Scalar(value, _) => value,
+                    other => panic!("Expected scalar token, got {other:?}"),
+                })
+        };
+
+        for strict in [false, true] {
+            assert_eq!(
+                scan(r#""\N\_\L\P""#, strict).unwrap(),
+                "\u{85}\u{A0}\u{2028}\u{2029}"
+            );
+            assert_eq!(scan("\"a\\\n  b\"", strict).unwrap(), "ab");
+            assert_eq!(scan("\"a\\\n\n  b\"", strict).unwrap(), "a\nb");
+        }
+
+        let error = scan(r#""\q""#, true).unwrap_err();
+        assert!(error.to_string().contains("invalid escape sequence '\\q'"));
+        assert_eq!(error.position().unwrap().column, 2);
+        let error = scan(r#""\x4""#, true).unwrap_err();
+        assert!(error.to_string().contains("'\\x4' needs 2 hex digits"));
+        assert!(scan(r#""\'""#, true).is_err());
+        assert_eq!(scan(r#""\'""#, false).unwrap(), "'");
+
+        // Single-quoted scalars stay lenient
+        assert_eq!(scan(r"'\q'", true).unwrap(), "\\q");
+    }
+
+    #[test]
+    fn test_tokenize_spans_and_categories() {
+        let tokens: Vec<Token> = tokenize("%YAML 1.2\n---\nkey: value # note\n")
+            .collect::<Result<_>>()
+            .unwrap();
+
+        let categories: Vec<TokenCategory> = tokens.iter().map(Token::category).collect();
+        assert!(categories.contains(&TokenCategory::Directive));
+        assert!(categories.contains(&TokenCategory::Document));
+        assert!(categories.contains(&TokenCategory::Comment));
+
+        let key = tokens
+            .iter()
+            .find(|token| token.as_scalar() == Some("key"))
+            .unwrap();
+        assert_eq!(key.start_position().line, 3);
+        assert!(key.end_position().index > key.start_position().index);
+    }
+
+    #[test]
+    fn test_crlf_and_cr_line_breaks() {
+        let lf = "a: 1\nb: |\n  x\n  y\nc: 'p q' # note\nd: [1, 2]\n";
+        let lf_tokens: Vec<Token> = tokenize(lf).collect::<Result<_>>().unwrap();
+
+        for text in [lf.replace('\n', "\r\n"), lf.replace('\n', "\r")] {
+            let tokens: Vec<Token> = tokenize(&text).collect::<Result<_>>().unwrap();
+            let types: Vec<_> = tokens.iter().map(|token| &token.token_type).collect();
+            let lf_types: Vec<_> = lf_tokens.iter().map(|token| &token.token_type).collect();
+            assert_eq!(types, lf_types, "{text:?}");
+
+            for (token, lf_token) in tokens.iter().zip(&lf_tokens) {
+                let (start, lf_start) = (token.start_position(), lf_token.start_position());
+                assert_eq!((start.line, start.column), (lf_start.line, lf_start.column));
+                // Each line break before the token is one byte longer in CRLF text
+                if text.contains('\n') {
+                    assert_eq!(start.index, lf_start.index + lf_start.line - 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_quoted_line_breaks_are_normalized() {
+        let tokens: Vec<Token> = tokenize("\"a\r\nb\"").collect::<Result<_>>().unwrap();
+        assert!(tokens.iter().any(|token| token.as_scalar() == Some("a\nb")));
+    }
+
+    #[test]
+    fn test_plain_scalars_starting_with_indicators() {
+        let scalars = |input: &str| -> Vec<String> {
+            tokenize(input)
+                .collect::<Result<Vec<_>>>()
+                .unwrap()
+                .iter()
+                .filter_map(|token| token.as_scalar().map(str::to_string))
+                .collect()
+        };
+        assert_eq!(scalars("-.inf\n"), ["-.inf"]);
+        assert_eq!(scalars("a: -b\nc: ?d\n"), ["a", "-b", "c", "?d"]);
+        assert_eq!(scalars("- -x\n"), ["-x"]);
+    }
+
+    #[test]
+    fn test_plain_scalars_in_flow_and_block_context() {
+        let scalars = |input: &str| -> Vec<String> {
+            tokenize(input)
+                .collect::<Result<Vec<_>>>()
+                .unwrap()
+                .iter()
+                .filter_map(|token| token.as_scalar().map(str::to_string))
+                .collect()
+        };
+        assert_eq!(
+            scalars("[a:b, http://x.com:80/p?q=1#f, c #note\n]"),
+            ["a:b", "http://x.com:80/p?q=1#f", "c"]
+        );
+        assert_eq!(scalars("{k:v, a: 1}"), ["k:v", "a", "1"]);
+        assert_eq!(scalars("[one\n  two, three]"), ["one two", "three"]);
+        assert_eq!(scalars("a: b,c[d]{e}\n"), ["a", "b,c[d]{e}"]);
+        assert_eq!(scalars("[1.5x, 2]"), ["1.5x", "2"]);
+    }
+
+    #[test]
+    fn test_multi_line_plain_scalars_in_block_context() {
+        let scalars = |input: &str| -> Vec<String> {
+            tokenize(input)
+                .collect::<Result<Vec<_>>>()
+                .unwrap()
+                .iter()
+                .filter_map(|token| token.as_scalar().map(str::to_string))
+                .collect()
+        };
+        assert_eq!(scalars("a: one\n  two\nb: 1\n"), ["a", "one two", "b", "1"]);
+        assert_eq!(scalars("a: one\n\n  two\n"), ["a", "one\ntwo"]);
+        assert_eq!(scalars("? one\n  two\n: v\n"), ["one two", "v"]);
+        assert_eq!(scalars("a: one\n  # note\n"), ["a", "one"]);
+        assert_eq!(
+            scalars("a:\n  b: one\n  c: two\n"),
+            ["a", "b", "one", "c", "two"]
+        );
+        assert_eq!(scalars("- one\n- two\n"), ["one", "two"]);
+    }
+
+    #[test]
+    fn test_colons_in_block_plain_scalars() {
+        let scalars = |input: &str| -> Vec<String> {
+            tokenize(input)
+                .collect::<Result<Vec<_>>>()
+                .unwrap()
+                .iter()
+                .filter_map(|token| token.as_scalar().map(str::to_string))
+                .collect()
+        };
+        assert_eq!(
+            scalars("url: http://host:8080/path\ntime: 12:30:45\n"),
+            ["url", "http://host:8080/path", "time", "12:30:45"]
+        );
+        assert_eq!(scalars("a:b: c\n"), ["a:b", "c"]);
+        assert_eq!(scalars("k: ::vector\n"), ["k", "::vector"]);
+        assert_eq!(scalars("0 A: v\n"), ["0 A", "v"]);
+
+        for input in ["key: value: extra\n", "- a: b: c\n", "a: &x 'b': c\n"] {
+            let error = tokenize(input).collect::<Result<Vec<_>>>().unwrap_err();
+            assert!(
+                error.to_string().contains("mapping values are not allowed"),
+                "input: {input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_properties_on_block_mapping_keys() {
+        let tokens = |input: &str| -> Vec<String> {
+            tokenize(input)
+                .map(|token| token.unwrap().token_type.to_string())
+                .collect()
+        };
+        assert_eq!(
+            tokens("&a !!str key: v\n"),
+            [
+                "StreamStart",
+                "BlockMappingStart",
+                "Anchor(a)",
+                "Tag(!!str)",
+                "Scalar(key, Plain)",
+                "Value",
+                "Scalar(v, Plain)",
+                "BlockEnd",
+                "StreamEnd"
+            ]
+        );
+        // Properties on their own line belong to the mapping
+        assert_eq!(
+            tokens("&m\nkey: v\n")[1..3],
+            ["Anchor(m)", "BlockMappingStart"]
+        );
+    }
+
+    #[test]
+    fn test_block_scalar_indicators() {
+        let block = |input: &str| -> String {
+            tokenize(input)
+                .collect::<Result<Vec<_>>>()
+                .unwrap()
+                .into_iter()
+                .find_map(|token| match token.token_type {
+                    TokenType::BlockScalarLiteral(value) | TokenType::BlockScalarFolded(value) => {
+                        Some(value)
+                    }
+                    _ => None,
+                })
+                .unwrap()
+        };
+        assert_eq!(block("a: |\n  x\n  y\n\n"), "x\ny\n");
+        assert_eq!(block("a: |-\n  x\n\n"), "x");
+        assert_eq!(block("a: |+\n  x\n\n"), "x\n\n");
+        assert_eq!(block("a: |2\n    x\n  y\n"), "  x\ny\n");
+        assert_eq!(block("a: >\n  x\n  y\n\n  z\n   w\n"), "x y\nz\n w\n");
+        assert_eq!(block("- >-\n  x\n  y\n"), "x y");
+        assert_eq!(block("--- |\nx\n"), "x\n");
+
+        // The scalar ends before the next, less indented key
+        let tokens: Vec<Token> = tokenize("a: |\n  x\nb: 1\n")
+            .collect::<Result<_>>()
+            .unwrap();
+        assert!(tokens.iter().any(|token| token.as_scalar() == Some("b")));
+        assert!(tokenize("a: |0\n x\n").any(|token| token.is_err()));
+    }
+
+    #[test]
+    fn test_anchor_names() {
+        let names = |input: &str| -> Vec<String> {
+            tokenize(input)
+                .filter_map(|token| match token.unwrap().token_type {
+                    TokenType::Anchor(name) | TokenType::Alias(name) => Some(name),
+                    _ => None,
+                })
+                .collect()
+        };
+        assert_eq!(names("a: &a.b/c 1\nb: *a.b/c\n"), ["a.b/c", "a.b/c"]);
+        assert_eq!(names("&a: key: *a:\n"), ["a:", "a:"]);
+        assert_eq!(names("[&x 1, *x]\n"), ["x", "x"]);
+        assert_eq!(names("{a: *ключ}\n"), ["ключ"]);
+
+        let error = tokenize("a: &x\u{7}y 1\n")
+            .collect::<Result<Vec<_>>>()
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Invalid character '\\u{7}' in anchor name"));
+        assert_eq!(error.position().map(|position| position.column), Some(6));
+    }
+
+    #[test]
+    fn test_ascii_and_unicode_paths_agree() {
+        let tokens = |input: String| -> Vec<Token> {
+            let mut scanner = BasicScanner::new(input);
+            let mut tokens = Vec::new();
+            while let Some(token) = scanner.get_token().unwrap() {
+                tokens.push(token);
+            }
+            tokens
+        };
+        let input = "%YAML 1.2\n%TAG !e! tag:example.com:\n--- # start\n\
+                     url: http://x:80/a#b # c\nlist: [a b, {c: d#e}]\n\
+                     text: 'it''s'\nfolded: one\n  two\n...\n";
+        let ascii = tokens(input.to_string());
+        // A trailing comment outside ASCII makes the scanner decode the
+        // input, leaving the tokens before it as they were
+        let mut unicode = tokens(format!("{input}# é\n"));
+        assert_eq!(
+            ascii.last().map(|token| &token.token_type),
+            Some(&TokenType::StreamEnd)
+        );
+        unicode.pop();
+        assert_eq!(ascii[..ascii.len() - 1], unicode[..]);
+        assert!(ascii
+            .iter()
+            .any(|token| token.as_scalar() == Some("http://x:80/a#b")));
+        assert!(ascii.iter().any(|token| token.as_scalar() == Some("d#e")));
+        assert!(ascii
+            .iter()
+            .any(|token| token.as_scalar() == Some("one two")));
+    }
 }
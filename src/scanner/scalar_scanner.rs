@@ -1,7 +1,7 @@
 //! Scalar scanning functionality for YAML scanner
 
 use super::{QuoteStyle, Token, TokenType};
-use crate::{Error, Position, Result};
+use crate::{value::Chomping, Error, Position, Result};
 
 /// Trait for scanning scalar values
 pub trait ScalarScanner {
@@ -62,6 +62,110 @@ pub(super) fn is_plain_scalar_char(ch: char) -> bool {
     )
 }
 
+/// Whether `ch` ends the plain scalar it follows, given the characters
+/// before and after it and whether the scalar is inside a flow collection
+///
+/// A `:` ends a plain scalar only before whitespace, or in flow context
+/// before a flow indicator, so `a:b` and URLs stay whole. `#` starts a
+/// comment only after whitespace, and `,`, `[`, `]`, `{` and `}` are
+/// indicators only in flow context. Line breaks are left to the caller.
+pub(crate) fn ends_plain_scalar(
+    ch: char,
+    prev: Option<char>,
+    next: Option<char>,
+    in_flow: bool,
+) -> bool {
+    match ch {
+        ':' => next.map_or(true, |next| {
+            next.is_whitespace() || in_flow && is_flow_indicator(next)
+        }),
+        '#' => prev.map_or(true, char::is_whitespace),
+        _ => in_flow && is_flow_indicator(ch),
+    }
+}
+
+/// Whether `ch` is one of the flow indicators `,`, `[`, `]`, `{` and `}`
+pub(crate) const fn is_flow_indicator(ch: char) -> bool {
+    matches!(ch, ',' | '[' | ']' | '{' | '}')
+}
+
+/// Whether `ch` may appear in an anchor or alias name
+///
+/// Names may hold any printable character other than a space, a byte order
+/// mark or a flow indicator, so `&a:b` and `&ключ` are valid anchors. Line
+/// breaks and tabs end a name before this is asked.
+pub(crate) fn is_anchor_char(ch: char) -> bool {
+    let printable = matches!(
+        ch,
+        '\u{21}'..='\u{7E}'
+            | '\u{85}'
+            | '\u{A0}'..='\u{D7FF}'
+            | '\u{E000}'..='\u{FFFD}'
+            | '\u{10000}'..
+    );
+    printable && ch != '\u{FEFF}' && !is_flow_indicator(ch)
+}
+
+/// The character a single-character escape of a double-quoted scalar
+/// stands for, such as a line feed for `\n`
+///
+/// The hex escapes `\x`, `\u` and `\U` are decoded by the scanners, see
+/// [`hex_escape_digits`].
+pub(crate) const fn escaped_char(ch: char) -> Option<char> {
+    Some(match ch {
+        '0' => '\0',
+        'a' => '\x07',
+        'b' => '\x08',
+        't' | '\t' => '\t',
+        'n' => '\n',
+        'v' => '\x0B',
+        'f' => '\x0C',
+        'r' => '\r',
+        'e' => '\x1B',
+        ' ' | '"' | '/' | '\\' => ch,
+        'N' => '\u{85}',
+        '_' => '\u{A0}',
+        'L' => '\u{2028}',
+        'P' => '\u{2029}',
+        _ => return None,
+    })
+}
+
+/// How many hex digits follow the letter of a `\x`, `\u` or `\U` escape
+pub(crate) const fn hex_escape_digits(ch: char) -> Option<usize> {
+    match ch {
+        'x' => Some(2),
+        'u' => Some(4),
+        'U' => Some(8),
+        _ => None,
+    }
+}
+
+/// The error a scanner with strict escapes reports for the escape sequence
+/// `escape` starting at `position`, showing the offending line of `input`
+pub(crate) fn invalid_escape(input: &str, position: Position, escape: &str) -> Error {
+    let (message, suggestion) = match escape.chars().nth(1).and_then(hex_escape_digits) {
+        Some(digits) => (
+            format!("escape sequence '{escape}' needs {digits} hex digits"),
+            format!(
+                "write exactly {digits} hex digits after '{}', padding with leading zeros",
+                &escape[..2]
+            ),
+        ),
+        None if escape == "\\" => (
+            "escape sequence at end of input".to_string(),
+            "close the double-quoted scalar with '\"'".to_string(),
+        ),
+        None => (
+            format!("invalid escape sequence '{escape}' in double-quoted scalar"),
+            "write '\\\\' for a literal backslash; valid escapes are \\0 \\a \\b \\t \\n \\v \\f \\r \\e \\\" \\/ \\\\ \\N \\_ \\L \\P, \\xXX, \\uXXXX and \\UXXXXXXXX".to_string(),
+        ),
+    };
+    let context =
+        crate::error::ErrorContext::from_input(input, &position, 2).with_suggestion(suggestion);
+    Error::scan_with_context(position, message, context)
+}
+
 pub(super) fn process_escape_sequence(ch: char) -> Result<String> {
     match ch {
         'n' => Ok("\n".to_string()),
@@ -95,42 +199,24 @@ impl ScalarScanner for super::BasicScanner {
         let mut value = String::new();
 
         while let Some(ch) = self.current_char {
-            // Stop at structural characters in block context
-            if self.flow_level == 0 {
-                match ch {
-                    '\n' | '\r' => break,
-                    ':' if self.peek_char(1).map_or(true, |c| c.is_whitespace()) => break,
-                    '#' if value.is_empty()
-                        || self.peek_char(-1).map_or(false, |c| c.is_whitespace()) =>
-                    {
-                        break;
-                    }
-                    _ => {}
-                }
-            } else {
-                // In flow context, stop at flow indicators
-                match ch {
-                    ',' | '[' | ']' | '{' | '}' => break,
-                    ':' if self
-                        .peek_char(1)
-                        .map_or(true, |c| c.is_whitespace() || "]}".contains(c)) =>
-                    {
-                        break;
-                    }
-                    '#' if value.is_empty()
-                        || self.peek_char(-1).map_or(false, |c| c.is_whitespace()) =>
-                    {
-                        break;
-                    }
-                    _ => {}
-                }
+            if matches!(ch, '\n' | '\r')
+                || ends_plain_scalar(
+                    ch,
+                    self.peek_char(-1),
+                    self.peek_char(1),
+                    self.flow_level > 0,
+                )
+            {
+                break;
             }
 
+            self.check_scalar_len(start_pos, value.len())?;
             value.push(ch);
             self.advance();
         }
 
         // Check string length limit
+        self.check_scalar_len(start_pos, value.len())?;
         self.resource_tracker
             .check_string_length(&self.limits, value.len())?;
 
@@ -153,6 +239,7 @@ impl ScalarScanner for super::BasicScanner {
         self.advance();
 
         while let Some(ch) = self.current_char {
+            self.check_scalar_len(start_pos, value.len())?;
             if ch == quote_char {
                 // End quote found
                 self.advance();
@@ -201,6 +288,7 @@ impl ScalarScanner for super::BasicScanner {
         }
 
         // Check string length limit
+        self.check_scalar_len(start_pos, value.len())?;
         self.resource_tracker
             .check_string_length(&self.limits, value.len())?;
 
@@ -258,226 +346,16 @@ impl ScalarScanner for super::BasicScanner {
     }
 
     fn scan_literal_block_scalar(&mut self) -> Result<Token> {
-        let start_pos = self.position;
-
-        // Skip the '|' character
-        self.advance();
-
-        // Scan block scalar header for chomping and indentation
-        let (keep_chomping, explicit_indent) = self.scan_block_scalar_header()?;
-
-        // Find the base indentation level
-        let mut base_indent = None;
-        let mut lines = Vec::new();
-        let mut current_line = String::new();
-
-        // Skip to end of header line
-        while let Some(ch) = self.current_char {
-            if ch == '\n' || ch == '\r' {
-                self.advance();
-                break;
-            }
-            self.advance();
-        }
-
-        // Collect lines
-        while let Some(ch) = self.current_char {
-            if ch == '\n' || ch == '\r' {
-                lines.push(current_line.clone());
-                current_line.clear();
-                self.advance();
-
-                // Check if next line has content to determine if we should continue
-                let mut temp_indent = 0usize;
-                let mut has_content = false;
-
-                while let Some(next_ch) = self.peek_char(temp_indent as isize) {
-                    if next_ch == ' ' || next_ch == '\t' {
-                        temp_indent += 1;
-                    } else if next_ch == '\n' || next_ch == '\r' {
-                        // Empty line, continue collecting
-                        break;
-                    } else {
-                        has_content = true;
-                        break;
-                    }
-                }
-
-                if !has_content {
-                    // No more content lines
-                    break;
-                }
-
-                // Set base indentation from first content line
-                if base_indent.is_none() && has_content {
-                    base_indent = Some(explicit_indent.unwrap_or(temp_indent));
-                }
-            } else {
-                current_line.push(ch);
-                self.advance();
-            }
-        }
-
-        // Add final line if not empty
-        if !current_line.is_empty() {
-            lines.push(current_line);
-        }
-
-        // Join lines with newlines (literal style preserves line breaks)
-        let mut value = lines.join("\n");
-
-        // Apply chomping rules
-        if !keep_chomping {
-            value = value.trim_end_matches('\n').to_string();
-        }
-
-        // Check string length limit
-        self.resource_tracker
-            .check_string_length(&self.limits, value.len())?;
-
-        Ok(Token::new(
-            TokenType::BlockScalarLiteral(value),
-            start_pos,
-            self.position,
-        ))
+        Self::scan_literal_block_scalar(self)
     }
 
     fn scan_folded_block_scalar(&mut self) -> Result<Token> {
-        let start_pos = self.position;
-
-        // Skip the '>' character
-        self.advance();
-
-        // Scan block scalar header for chomping and indentation
-        let (keep_chomping, explicit_indent) = self.scan_block_scalar_header()?;
-
-        // Similar to literal but fold newlines
-        let mut base_indent = None;
-        let mut lines = Vec::new();
-        let mut current_line = String::new();
-
-        // Skip to end of header line
-        while let Some(ch) = self.current_char {
-            if ch == '\n' || ch == '\r' {
-                self.advance();
-                break;
-            }
-            self.advance();
-        }
-
-        // Collect lines
-        while let Some(ch) = self.current_char {
-            if ch == '\n' || ch == '\r' {
-                lines.push(current_line.clone());
-                current_line.clear();
-                self.advance();
-
-                // Check if next line has content
-                let mut temp_indent = 0usize;
-                let mut has_content = false;
-
-                while let Some(next_ch) = self.peek_char(temp_indent as isize) {
-                    if next_ch == ' ' || next_ch == '\t' {
-                        temp_indent += 1;
-                    } else if next_ch == '\n' || next_ch == '\r' {
-                        break;
-                    } else {
-                        has_content = true;
-                        break;
-                    }
-                }
-
-                if !has_content {
-                    break;
-                }
-
-                if base_indent.is_none() && has_content {
-                    base_indent = Some(explicit_indent.unwrap_or(temp_indent));
-                }
-            } else {
-                current_line.push(ch);
-                self.advance();
-            }
-        }
-
-        if !current_line.is_empty() {
-            lines.push(current_line);
-        }
-
-        // Fold lines: join non-empty lines with spaces, preserve empty lines
-        let mut value = String::new();
-        let mut prev_was_empty = false;
-
-        for (i, line) in lines.iter().enumerate() {
-            if line.trim().is_empty() {
-                if !prev_was_empty && i > 0 {
-                    value.push('\n');
-                }
-                prev_was_empty = true;
-            } else {
-                if i > 0 && !prev_was_empty {
-                    value.push(' ');
-                } else if prev_was_empty && i > 0 {
-                    value.push('\n');
-                }
-                value.push_str(line.trim());
-                prev_was_empty = false;
-            }
-        }
-
-        // Apply chomping rules
-        if !keep_chomping {
-            value = value.trim_end_matches('\n').to_string();
-        }
-
-        // Check string length limit
-        self.resource_tracker
-            .check_string_length(&self.limits, value.len())?;
-
-        Ok(Token::new(
-            TokenType::BlockScalarFolded(value),
-            start_pos,
-            self.position,
-        ))
+        Self::scan_folded_block_scalar(self)
     }
 
     fn scan_block_scalar_header(&mut self) -> Result<(bool, Option<usize>)> {
-        let mut keep_chomping = true;
-        let mut explicit_indent = None;
-
-        // Skip whitespace after '|' or '>'
-        while let Some(ch) = self.current_char {
-            if ch == ' ' || ch == '\t' {
-                self.advance();
-            } else {
-                break;
-            }
-        }
-
-        // Check for explicit indentation indicator (digit)
-        if let Some(ch) = self.current_char {
-            if ch.is_ascii_digit() {
-                explicit_indent = Some(ch.to_digit(10).unwrap() as usize);
-                self.advance();
-            }
-        }
-
-        // Check for chomping indicator
-        if let Some(ch) = self.current_char {
-            match ch {
-                '-' => {
-                    keep_chomping = false; // Strip final newlines
-                    self.advance();
-                }
-                '+' => {
-                    keep_chomping = true; // Keep final newlines
-                    self.advance();
-                }
-                _ => {}
-            }
-        }
-
-        Ok((keep_chomping, explicit_indent))
+        let (chomping, explicit_indent) = Self::scan_block_scalar_header(self)?;
+        Ok((chomping == Chomping::Keep, explicit_indent))
     }
 
     // Helper trait methods
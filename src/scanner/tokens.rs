@@ -14,6 +14,29 @@ pub enum QuoteStyle {
     Double,
 }
 
+/// Broad classification of tokens, for syntax highlighting and editor tooling
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenCategory {
+    /// Stream boundaries and implicit block structure (zero-width)
+    Structure,
+    /// Document markers (`---`, `...`)
+    Document,
+    /// Directives (`%YAML`, `%TAG`)
+    Directive,
+    /// Flow and block indicators (`[`, `]`, `{`, `}`, `,`, `-`, `?`, `:`)
+    Indicator,
+    /// Plain, quoted and block scalars
+    Scalar,
+    /// Anchors (`&name`)
+    Anchor,
+    /// Aliases (`*name`)
+    Alias,
+    /// Tags (`!tag`, `!!tag`)
+    Tag,
+    /// Comments (`# text`)
+    Comment,
+}
+
 /// Represents a YAML token with position information
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Token {
@@ -133,6 +156,11 @@ impl Token {
         self.end_position
     }
 
+    /// Get the broad category of this token
+    pub const fn category(&self) -> TokenCategory {
+        self.token_type.category()
+    }
+
     /// Check if this is a scalar token
     pub const fn is_scalar(&self) -> bool {
         matches!(
@@ -231,6 +259,53 @@ impl fmt::Display for Token {
     }
 }
 
+impl TokenType {
+    /// Move the text out of this token type, leaving the same kind of token
+    /// with empty text behind without allocating
+    pub(crate) fn take_text(&mut self) -> Self {
+        use std::mem::take;
+        match self {
+            Self::TagDirective(handle, prefix) => Self::TagDirective(take(handle), take(prefix)),
+            Self::Scalar(value, style) => Self::Scalar(take(value), style.clone()),
+            Self::BlockScalarLiteral(value) => Self::BlockScalarLiteral(take(value)),
+            Self::BlockScalarFolded(value) => Self::BlockScalarFolded(take(value)),
+            Self::Alias(name) => Self::Alias(take(name)),
+            Self::Anchor(name) => Self::Anchor(take(name)),
+            Self::Tag(tag) => Self::Tag(take(tag)),
+            Self::Comment(text) => Self::Comment(take(text)),
+            other => other.clone(),
+        }
+    }
+
+    /// Get the broad category of this token type
+    pub const fn category(&self) -> TokenCategory {
+        match self {
+            Self::StreamStart
+            | Self::StreamEnd
+            | Self::BlockSequenceStart
+            | Self::BlockMappingStart
+            | Self::BlockEnd => TokenCategory::Structure,
+            Self::DocumentStart | Self::DocumentEnd => TokenCategory::Document,
+            Self::YamlDirective(..) | Self::TagDirective(..) => TokenCategory::Directive,
+            Self::FlowSequenceStart
+            | Self::FlowSequenceEnd
+            | Self::FlowMappingStart
+            | Self::FlowMappingEnd
+            | Self::BlockEntry
+            | Self::FlowEntry
+            | Self::Key
+            | Self::Value => TokenCategory::Indicator,
+            Self::Scalar(..) | Self::BlockScalarLiteral(_) | Self::BlockScalarFolded(_) => {
+                TokenCategory::Scalar
+            }
+            Self::Anchor(_) => TokenCategory::Anchor,
+            Self::Alias(_) => TokenCategory::Alias,
+            Self::Tag(_) => TokenCategory::Tag,
+            Self::Comment(_) => TokenCategory::Comment,
+        }
+    }
+}
+
 impl fmt::Display for TokenType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -283,6 +358,23 @@ mod tests {
         assert_eq!(token.as_scalar(), Some("hello"));
     }
 
+    #[test]
+    fn test_take_text() {
+        let mut token_type = TokenType::Scalar("hello".to_string(), QuoteStyle::Single);
+        assert_eq!(
+            token_type.take_text(),
+            TokenType::Scalar("hello".to_string(), QuoteStyle::Single)
+        );
+        assert_eq!(
+            token_type,
+            TokenType::Scalar(String::new(), QuoteStyle::Single)
+        );
+
+        let mut token_type = TokenType::BlockEntry;
+        assert_eq!(token_type.take_text(), TokenType::BlockEntry);
+        assert_eq!(token_type, TokenType::BlockEntry);
+    }
+
     #[test]
     fn test_token_type_checks() {
         let scalar_token = Token::simple(
@@ -302,6 +394,19 @@ mod tests {
         assert!(!doc_start.is_scalar());
     }
 
+    #[test]
+    fn test_token_category() {
+        let comment = Token::simple(TokenType::Comment("note".to_string()), Position::start());
+        assert_eq!(comment.category(), TokenCategory::Comment);
+        assert_eq!(
+            TokenType::TagDirective("!e!".to_string(), "tag:example.com,2000:".to_string())
+                .category(),
+            TokenCategory::Directive
+        );
+        assert_eq!(TokenType::Value.category(), TokenCategory::Indicator);
+        assert_eq!(TokenType::BlockEnd.category(), TokenCategory::Structure);
+    }
+
     #[test]
     fn test_token_display() {
         let scalar = Token::simple(
@@ -1,15 +1,22 @@
 //! YAML schema validation system
 
-use crate::{Error, Position, Result, Value};
+use crate::composer::resolve_scalar;
+use crate::parser::EventType;
+use crate::{BasicParser, Error, Parser, Path, PathSegment, Position, Result, Value};
 use regex::Regex;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::sync::OnceLock;
 
 /// Schema validation error with detailed context
 #[derive(Debug, Clone)]
 pub struct ValidationError {
     /// Path to the invalid value (e.g., "config.database.port")
     pub path: String,
+    /// The keys and indices leading to the invalid value, which `path` writes
+    /// in dotted form
+    pub location: Path,
     /// The validation rule that failed
     pub rule: String,
     /// Human-readable error message
@@ -20,10 +27,138 @@ pub struct ValidationError {
     pub position: Option<Position>,
 }
 
+impl ValidationError {
+    /// Fill in `position` from a map of node positions
+    ///
+    /// Paths that do not exist in the document (such as missing required
+    /// properties) fall back to the position of their closest ancestor.
+    pub fn locate(&mut self, positions: &HashMap<Path, Position>) {
+        let mut location = self.location.clone();
+        loop {
+            if let Some(position) = positions.get(&location) {
+                self.position = Some(*position);
+                return;
+            }
+            if location.pop().is_none() {
+                return;
+            }
+        }
+    }
+}
+
 impl fmt::Display for ValidationError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Validation error at '{}': {}", self.path, self.message)
+        write!(f, "Validation error at '{}': {}", self.path, self.message)?;
+        if let Some(position) = &self.position {
+            write!(f, " at {}", position)?;
+        }
+        Ok(())
+    }
+}
+
+/// A container being walked while collecting node positions
+enum PositionFrame {
+    /// Mapping at `path`; `key` holds the pending key once it has been read
+    Mapping {
+        path: Path,
+        key: Option<Value>,
+        expecting_key: bool,
+    },
+    /// Sequence at `path` with the index of the next item
+    Sequence { path: Path, index: usize },
+    /// Collection used as a mapping key, which has no validation path
+    ComplexKey,
+}
+
+/// Collect the start position of every node in each document of `input`
+///
+/// Positions are keyed by the same paths used in [`ValidationError::location`],
+/// with the empty path for the document root. Scalar keys are resolved as the
+/// composer resolves them, so `8080:` is keyed by the integer `8080`.
+pub fn node_positions(input: &str) -> Result<Vec<HashMap<Path, Position>>> {
+    let mut parser = BasicParser::new(input.to_string());
+    let mut documents = Vec::new();
+    let mut positions = HashMap::new();
+    let mut stack: Vec<PositionFrame> = Vec::new();
+
+    while let Some(event) = parser.get_event()? {
+        // `opens` is Some(is_mapping) for events that start a collection
+        let opens = match &event.event_type {
+            EventType::Scalar { .. } | EventType::Alias { .. } => None,
+            EventType::SequenceStart { .. } => Some(false),
+            EventType::MappingStart { .. } => Some(true),
+            EventType::SequenceEnd | EventType::MappingEnd => {
+                stack.pop();
+                continue;
+            }
+            EventType::DocumentEnd { .. } => {
+                documents.push(std::mem::take(&mut positions));
+                stack.clear();
+                continue;
+            }
+            _ => continue,
+        };
+
+        // Work out the path of this node from its parent container
+        let path = match stack.last_mut() {
+            None => Some(Path::new()),
+            Some(PositionFrame::ComplexKey) => None,
+            Some(PositionFrame::Mapping {
+                path,
+                key,
+                expecting_key,
+            }) => {
+                if *expecting_key {
+                    *expecting_key = false;
+                    *key = match &event.event_type {
+                        EventType::Scalar { value, style, .. } => {
+                            Some(resolve_scalar(value.clone(), *style))
+                        }
+                        _ => None,
+                    };
+                    if opens.is_some() {
+                        stack.push(PositionFrame::ComplexKey);
+                    }
+                    continue;
+                }
+                *expecting_key = true;
+                key.take().map(|key| child(path, PathSegment::Key(key)))
+            }
+            Some(PositionFrame::Sequence { path, index }) => {
+                let item_path = child(path, PathSegment::Index(*index));
+                *index += 1;
+                Some(item_path)
+            }
+        };
+
+        if let Some(path) = &path {
+            positions.entry(path.clone()).or_insert(event.position);
+        }
+
+        match (opens, path) {
+            (Some(true), Some(path)) => stack.push(PositionFrame::Mapping {
+                path,
+                key: None,
+                expecting_key: true,
+            }),
+            (Some(false), Some(path)) => stack.push(PositionFrame::Sequence { path, index: 0 }),
+            (Some(_), None) => stack.push(PositionFrame::ComplexKey),
+            (None, _) => {}
+        }
     }
+
+    if !positions.is_empty() {
+        documents.push(positions);
+    }
+
+    Ok(documents)
+}
+
+/// `path` extended by one more step
+fn child(path: &Path, segment: PathSegment) -> Path {
+    let mut path = path.clone();
+    path.push(segment);
+    path
 }
 
 impl std::error::Error for ValidationError {}
@@ -34,6 +169,84 @@ pub type ValidationResult<T> = std::result::Result<T, Vec<ValidationError>>;
 /// Custom validator function type
 pub type ValidatorFn = Box<dyn Fn(&Value, &str) -> Result<()> + Send + Sync>;
 
+/// State shared by every rule evaluated during a single validation pass
+#[derive(Clone, Copy, Default)]
+struct ValidationContext<'a> {
+    /// Custom validators available to `SchemaRule::Custom`
+    validators: Option<&'a HashMap<String, ValidatorFn>>,
+    /// Root schema definitions available to `SchemaRule::Ref`
+    definitions: Option<&'a HashMap<String, Schema>>,
+    /// Number of references followed to reach the current schema
+    ref_depth: usize,
+}
+
+/// Maximum number of nested `SchemaRule::Ref` hops in a single validation pass
+const MAX_REF_DEPTH: usize = 256;
+
+/// Resolves `$ref` targets while a schema document is being converted
+struct SchemaLoader<'a> {
+    /// The complete schema document references are resolved against
+    root: &'a Value,
+    /// Parsed definitions, keyed by reference; doubles as the resolver cache
+    definitions: HashMap<String, Schema>,
+    /// References currently being parsed, used to tie recursive schemas off
+    resolving: HashSet<String>,
+}
+
+impl<'a> SchemaLoader<'a> {
+    fn new(root: &'a Value) -> Self {
+        Self {
+            root,
+            definitions: HashMap::new(),
+            resolving: HashSet::new(),
+        }
+    }
+
+    /// Look up the document node a local reference points at
+    fn target(&self, reference: &str) -> Result<&'a Value> {
+        let pointer = reference.strip_prefix('#').ok_or_else(|| {
+            schema_error(format!(
+                "only local '$ref' values are supported, got '{}'",
+                reference
+            ))
+        })?;
+        self.root
+            .pointer(pointer)
+            .ok_or_else(|| schema_error(format!("unresolved '$ref' '{}'", reference)))
+    }
+
+    /// Make sure the definition behind `reference` is parsed and cached
+    fn resolve(&mut self, reference: &str) -> Result<()> {
+        if self.definitions.contains_key(reference) || self.resolving.contains(reference) {
+            return Ok(());
+        }
+
+        // A chain of references that never reaches a real schema cannot be resolved
+        let mut chain = HashSet::new();
+        let mut current = reference.to_string();
+        loop {
+            if !chain.insert(current.clone()) {
+                return Err(schema_error(format!("circular '$ref' '{}'", reference)));
+            }
+            match self
+                .target(&current)?
+                .get_str("$ref")
+                .and_then(Value::as_str)
+            {
+                Some(next) => current = next.to_string(),
+                None => break,
+            }
+        }
+
+        let target = self.target(reference)?;
+        self.resolving.insert(reference.to_string());
+        let schema = Schema::parse_json_schema(target, self)?;
+        self.resolving.remove(reference);
+        self.definitions.insert(reference.to_string(), schema);
+        Ok(())
+    }
+}
+
 /// Schema validation rules
 #[derive(Debug, Clone)]
 pub enum SchemaRule {
@@ -84,6 +297,93 @@ pub enum SchemaRule {
     AllOf(Vec<Schema>),
     /// Value must match exactly one of these schemas (XOR)
     OneOf(Vec<Schema>),
+    /// Properties whose names match a pattern must validate against its schema
+    PatternProperties(Vec<(Regex, Schema)>),
+    /// Every property name must validate against this schema
+    PropertyNames(Box<Schema>),
+    /// Value must match the root schema's definition registered under this reference
+    Ref(String),
+    /// Array items must be unique
+    UniqueItems,
+    /// Number must be a multiple of this value
+    MultipleOf(f64),
+    /// String must conform to a well-known format
+    Format(FormatKind),
+}
+
+/// Well-known string formats for `SchemaRule::Format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatKind {
+    /// Email address (`user@example.com`)
+    Email,
+    /// Absolute URI with a scheme (`https://example.com/path`)
+    Uri,
+    /// Dotted-quad IPv4 address
+    Ipv4,
+    /// IPv6 address
+    Ipv6,
+    /// RFC 3339 date-time (`2024-01-31T12:00:00Z`)
+    DateTime,
+    /// Hyphenated UUID
+    Uuid,
+}
+
+impl FormatKind {
+    /// Look up a format by its JSON Schema name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "email" => Some(Self::Email),
+            "uri" => Some(Self::Uri),
+            "ipv4" => Some(Self::Ipv4),
+            "ipv6" => Some(Self::Ipv6),
+            "date-time" => Some(Self::DateTime),
+            "uuid" => Some(Self::Uuid),
+            _ => None,
+        }
+    }
+
+    /// Check whether a string conforms to this format
+    pub fn matches(self, s: &str) -> bool {
+        static EMAIL: OnceLock<Regex> = OnceLock::new();
+        static URI: OnceLock<Regex> = OnceLock::new();
+        static DATE_TIME: OnceLock<Regex> = OnceLock::new();
+        static UUID: OnceLock<Regex> = OnceLock::new();
+
+        let regex = match self {
+            Self::Ipv4 => return s.parse::<Ipv4Addr>().is_ok(),
+            Self::Ipv6 => return s.parse::<Ipv6Addr>().is_ok(),
+            Self::Email => EMAIL.get_or_init(|| {
+                Regex::new(r"^[^@\s]+@[^@\s.]+(\.[^@\s.]+)+$").expect("valid email regex")
+            }),
+            Self::Uri => URI.get_or_init(|| {
+                Regex::new(r"^[A-Za-z][A-Za-z0-9+.-]*:[^\s]+$").expect("valid uri regex")
+            }),
+            Self::DateTime => DATE_TIME.get_or_init(|| {
+                Regex::new(
+                    r"^\d{4}-(0[1-9]|1[0-2])-(0[1-9]|[12]\d|3[01])[Tt ]([01]\d|2[0-3]):[0-5]\d:([0-5]\d|60)(\.\d+)?([Zz]|[+-]([01]\d|2[0-3]):[0-5]\d)$",
+                )
+                .expect("valid date-time regex")
+            }),
+            Self::Uuid => UUID.get_or_init(|| {
+                Regex::new(r"^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$")
+                    .expect("valid uuid regex")
+            }),
+        };
+        regex.is_match(s)
+    }
+}
+
+impl fmt::Display for FormatKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatKind::Email => write!(f, "email"),
+            FormatKind::Uri => write!(f, "uri"),
+            FormatKind::Ipv4 => write!(f, "ipv4"),
+            FormatKind::Ipv6 => write!(f, "ipv6"),
+            FormatKind::DateTime => write!(f, "date-time"),
+            FormatKind::Uuid => write!(f, "uuid"),
+        }
+    }
 }
 
 /// Supported value types for validation
@@ -132,6 +432,10 @@ pub struct Schema {
     pub optional: bool,
     /// Default value if not provided
     pub default: Option<Value>,
+    /// Shared subschemas targeted by `SchemaRule::Ref`, keyed by reference
+    ///
+    /// Only the definitions of the root schema are consulted during validation.
+    pub definitions: HashMap<String, Schema>,
 }
 
 impl Schema {
@@ -143,6 +447,7 @@ impl Schema {
             rules: Vec::new(),
             optional: false,
             default: None,
+            definitions: HashMap::new(),
         }
     }
 
@@ -154,6 +459,7 @@ impl Schema {
             rules: vec![SchemaRule::Type(value_type)],
             optional: false,
             default: None,
+            definitions: HashMap::new(),
         }
     }
 
@@ -188,13 +494,423 @@ impl Schema {
         self
     }
 
+    /// Register a shared subschema that `SchemaRule::Ref(reference)` resolves to
+    pub fn definition(mut self, reference: impl Into<String>, schema: Schema) -> Self {
+        self.definitions.insert(reference.into(), schema);
+        self
+    }
+
+    /// Build a schema from a JSON Schema document loaded as a YAML value
+    ///
+    /// Supports the draft-07 subset of keywords that map onto [`SchemaRule`]:
+    /// `type`, `properties`, `required`, `items`, `enum`, `const`, `pattern`,
+    /// `minimum`/`maximum`, `minLength`/`maxLength`, `minItems`/`maxItems`,
+    /// `anyOf`/`allOf`/`oneOf`/`not`, `if`/`then`/`else` and boolean
+    /// `additionalProperties`. Keywords that constrain one type of value, such
+    /// as `pattern` or `minimum`, pass values of other types. Other draft-07
+    /// validation keywords, and `additionalProperties` given a schema, are
+    /// rejected rather than skipped; unknown keywords are ignored.
+    ///
+    /// Local references such as `$ref: "#/definitions/container"` are resolved
+    /// against the document. Each referenced definition is parsed once and
+    /// stored in [`Schema::definitions`], so recursive schemas are supported;
+    /// references that only point at each other are rejected as circular.
+    pub fn from_value(value: &Value) -> Result<Self> {
+        let mut loader = SchemaLoader::new(value);
+        let mut schema = Self::parse_json_schema(value, &mut loader)?;
+        schema.definitions.extend(loader.definitions);
+        Ok(schema)
+    }
+
+    /// Build a schema from JSON Schema text (JSON or YAML syntax)
+    pub fn from_json_schema_str(input: &str) -> Result<Self> {
+        let value = crate::Yaml::new().load_str(input)?;
+        Self::from_value(&value)
+    }
+
+    fn parse_json_schema(value: &Value, loader: &mut SchemaLoader<'_>) -> Result<Self> {
+        match value {
+            Value::Bool(true) => Ok(Self::new()),
+            Value::Bool(false) => Ok(Self::new().rule(SchemaRule::Not(Box::default()))),
+            Value::Mapping(_) => {
+                // Keywords next to `$ref` are ignored, as in draft-07
+                if let Some(reference) = value.get_str("$ref") {
+                    let reference = reference
+                        .as_str()
+                        .ok_or_else(|| schema_error("'$ref' must be a string"))?;
+                    loader.resolve(reference)?;
+                    return Ok(Self::new().rule(SchemaRule::Ref(reference.to_string())));
+                }
+                Self::from_json_schema_object(value, loader)
+            }
+            other => Err(schema_error(format!(
+                "JSON Schema must be a mapping or boolean, got {}",
+                other.type_name()
+            ))),
+        }
+    }
+
+    fn from_json_schema_object(value: &Value, loader: &mut SchemaLoader<'_>) -> Result<Self> {
+        let mut schema = Self::new();
+
+        if let Some(title) = value.get_str("title").and_then(Value::as_str) {
+            schema.title = Some(title.to_string());
+        }
+        if let Some(description) = value.get_str("description").and_then(Value::as_str) {
+            schema.description = Some(description.to_string());
+        }
+        if let Some(default) = value.get_str("default") {
+            schema.default = Some(default.clone());
+        }
+
+        let mut declared_type = None;
+        if let Some(type_value) = value.get_str("type") {
+            let rule = parse_type_keyword(type_value)?;
+            if let SchemaRule::Type(value_type) = &rule {
+                declared_type = Some(value_type.clone());
+            }
+            schema.rules.push(rule);
+        }
+
+        if let Some(allowed) = value.get_str("enum") {
+            let allowed = allowed
+                .as_sequence()
+                .ok_or_else(|| schema_error("'enum' must be a sequence"))?;
+            schema.rules.push(SchemaRule::Enum(allowed.clone()));
+        }
+        if let Some(constant) = value.get_str("const") {
+            schema.rules.push(SchemaRule::Enum(vec![constant.clone()]));
+        }
+
+        for keyword in UNSUPPORTED_KEYWORDS {
+            if value.get_str(keyword).is_some() {
+                return Err(schema_error(format!("unsupported keyword '{}'", keyword)));
+            }
+        }
+
+        // Keywords that only constrain values of one type, grouped by that type
+        let mut strings = Vec::new();
+        let mut numbers = Vec::new();
+        let mut arrays = Vec::new();
+        let mut objects = Vec::new();
+
+        if let Some(pattern) = value.get_str("pattern") {
+            let pattern = pattern
+                .as_str()
+                .ok_or_else(|| schema_error("'pattern' must be a string"))?;
+            let regex = Regex::new(pattern)
+                .map_err(|e| schema_error(format!("invalid 'pattern' '{}': {}", pattern, e)))?;
+            strings.push(SchemaRule::Pattern(regex));
+        }
+
+        let min = optional_number(value, "minimum")?;
+        let max = optional_number(value, "maximum")?;
+        if min.is_some() || max.is_some() {
+            numbers.push(SchemaRule::Range { min, max });
+        }
+
+        if let Some(divisor) = optional_number(value, "multipleOf")? {
+            if divisor <= 0.0 {
+                return Err(schema_error("'multipleOf' must be greater than zero"));
+            }
+            numbers.push(SchemaRule::MultipleOf(divisor));
+        }
+
+        match value.get_str("uniqueItems") {
+            Some(Value::Bool(true)) => arrays.push(SchemaRule::UniqueItems),
+            Some(Value::Bool(false)) | None => {}
+            Some(_) => return Err(schema_error("'uniqueItems' must be a boolean")),
+        }
+
+        // Unknown formats are ignored, as JSON Schema treats them as annotations
+        if let Some(format) = value.get_str("format") {
+            let format = format
+                .as_str()
+                .ok_or_else(|| schema_error("'format' must be a string"))?;
+            if let Some(kind) = FormatKind::from_name(format) {
+                strings.push(SchemaRule::Format(kind));
+            }
+        }
+
+        for (min_key, max_key, rules) in [
+            ("minLength", "maxLength", &mut strings),
+            ("minItems", "maxItems", &mut arrays),
+        ] {
+            let min = optional_count(value, min_key)?;
+            let max = optional_count(value, max_key)?;
+            if min.is_some() || max.is_some() {
+                rules.push(SchemaRule::Length { min, max });
+            }
+        }
+
+        let required: Vec<String> = match value.get_str("required") {
+            Some(Value::Sequence(names)) => names
+                .iter()
+                .map(|name| {
+                    name.as_str()
+                        .map(str::to_string)
+                        .ok_or_else(|| schema_error("'required' entries must be strings"))
+                })
+                .collect::<Result<_>>()?,
+            Some(_) => return Err(schema_error("'required' must be a sequence")),
+            None => Vec::new(),
+        };
+
+        if let Some(properties) = value.get_str("properties") {
+            let map = properties
+                .as_mapping()
+                .ok_or_else(|| schema_error("'properties' must be a mapping"))?;
+            let mut parsed = HashMap::new();
+            for (name, prop_schema) in map {
+                let name = name
+                    .as_str()
+                    .ok_or_else(|| schema_error("property names must be strings"))?;
+                let mut prop = Self::parse_json_schema(prop_schema, loader)?;
+                // JSON Schema properties are optional unless listed in `required`
+                if !required.iter().any(|r| r == name) {
+                    prop.optional = true;
+                }
+                parsed.insert(name.to_string(), prop);
+            }
+            objects.push(SchemaRule::Properties(parsed));
+        }
+
+        // Required names covered by `properties` are already reported as missing
+        // there; only the remaining ones need a separate `Required` rule
+        let declared = value.get_str("properties").and_then(Value::as_mapping);
+        let undeclared: Vec<String> = required
+            .into_iter()
+            .filter(|name| {
+                declared.map_or(true, |props| {
+                    !props.contains_key(&Value::String(name.clone()))
+                })
+            })
+            .collect();
+        if !undeclared.is_empty() {
+            objects.push(SchemaRule::Required(undeclared));
+        }
+
+        if let Some(patterns) = value.get_str("patternProperties") {
+            let map = patterns
+                .as_mapping()
+                .ok_or_else(|| schema_error("'patternProperties' must be a mapping"))?;
+            let mut parsed = Vec::new();
+            for (pattern, prop_schema) in map {
+                let pattern = pattern
+                    .as_str()
+                    .ok_or_else(|| schema_error("'patternProperties' keys must be strings"))?;
+                let regex = Regex::new(pattern).map_err(|e| {
+                    schema_error(format!("invalid property pattern '{}': {}", pattern, e))
+                })?;
+                parsed.push((regex, Self::parse_json_schema(prop_schema, loader)?));
+            }
+            objects.push(SchemaRule::PatternProperties(parsed));
+        }
+
+        if let Some(names) = value.get_str("propertyNames") {
+            objects.push(SchemaRule::PropertyNames(Box::new(
+                Self::parse_json_schema(names, loader)?,
+            )));
+        }
+
+        if let Some(additional) = value.get_str("additionalProperties") {
+            let allowed = additional.as_bool().ok_or_else(|| {
+                schema_error("unsupported 'additionalProperties': only booleans are supported")
+            })?;
+            objects.push(SchemaRule::AdditionalProperties(allowed));
+        }
+
+        if let Some(items) = value.get_str("items") {
+            arrays.push(SchemaRule::Items(Box::new(Self::parse_json_schema(
+                items, loader,
+            )?)));
+        }
+
+        for (value_type, rules) in [
+            (ValueType::String, strings),
+            (ValueType::Number, numbers),
+            (ValueType::Array, arrays),
+            (ValueType::Object, objects),
+        ] {
+            schema.rules.extend(type_specific_rules(
+                value_type,
+                rules,
+                declared_type.as_ref(),
+            ));
+        }
+
+        for keyword in ["anyOf", "allOf", "oneOf"] {
+            if let Some(subschemas) = value.get_str(keyword) {
+                let subschemas = subschemas
+                    .as_sequence()
+                    .ok_or_else(|| schema_error(format!("'{}' must be a sequence", keyword)))?
+                    .iter()
+                    .map(|subschema| Self::parse_json_schema(subschema, loader))
+                    .collect::<Result<Vec<_>>>()?;
+                schema.rules.push(match keyword {
+                    "anyOf" => SchemaRule::AnyOf(subschemas),
+                    "allOf" => SchemaRule::AllOf(subschemas),
+                    _ => SchemaRule::OneOf(subschemas),
+                });
+            }
+        }
+
+        if let Some(negated) = value.get_str("not") {
+            schema
+                .rules
+                .push(SchemaRule::Not(Box::new(Self::parse_json_schema(
+                    negated, loader,
+                )?)));
+        }
+
+        if let Some(if_schema) = value.get_str("if") {
+            let if_schema = Box::new(Self::parse_json_schema(if_schema, loader)?);
+            let mut branch = |key: &str| -> Result<Option<Box<Self>>> {
+                value
+                    .get_str(key)
+                    .map(|s| Self::parse_json_schema(s, loader).map(Box::new))
+                    .transpose()
+            };
+            let then_schema = branch("then")?;
+            let else_schema = branch("else")?;
+            schema.rules.push(SchemaRule::Conditional {
+                if_schema,
+                then_schema,
+                else_schema,
+            });
+        }
+
+        Ok(schema)
+    }
+
+    /// Insert defaults for missing optional properties, recursively
+    ///
+    /// Paths of the properties that were filled in are appended to `filled`.
+    pub fn fill_defaults(&self, value: &mut Value, path: &str, filled: &mut Vec<String>) {
+        self.fill_defaults_in(value, path, filled, &self.definitions, 0);
+    }
+
+    fn fill_defaults_in(
+        &self,
+        value: &mut Value,
+        path: &str,
+        filled: &mut Vec<String>,
+        definitions: &HashMap<String, Schema>,
+        ref_depth: usize,
+    ) {
+        for rule in self.unguarded_rules() {
+            match rule {
+                SchemaRule::Properties(properties) => {
+                    let Value::Mapping(map) = value else {
+                        continue;
+                    };
+
+                    // Sort names so filled keys are inserted deterministically
+                    let mut names: Vec<&String> = properties.keys().collect();
+                    names.sort();
+
+                    for name in names {
+                        let prop_schema = &properties[name];
+                        let prop_path = if path.is_empty() {
+                            name.clone()
+                        } else {
+                            format!("{}.{}", path, name)
+                        };
+                        let key = Value::String(name.clone());
+
+                        if !map.contains_key(&key) && prop_schema.optional {
+                            if let Some(default) = &prop_schema.default {
+                                map.insert(key.clone(), default.clone());
+                                filled.push(prop_path.clone());
+                            }
+                        }
+
+                        if let Some(prop_value) = map.get_mut(&key) {
+                            prop_schema.fill_defaults_in(
+                                prop_value,
+                                &prop_path,
+                                filled,
+                                definitions,
+                                ref_depth,
+                            );
+                        }
+                    }
+                }
+                SchemaRule::Items(item_schema) => {
+                    if let Value::Sequence(seq) = value {
+                        for (index, item) in seq.iter_mut().enumerate() {
+                            let item_path = format!("{}[{}]", path, index);
+                            item_schema.fill_defaults_in(
+                                item,
+                                &item_path,
+                                filled,
+                                definitions,
+                                ref_depth,
+                            );
+                        }
+                    }
+                }
+                SchemaRule::AllOf(schemas) => {
+                    for schema in schemas {
+                        schema.fill_defaults_in(value, path, filled, definitions, ref_depth);
+                    }
+                }
+                SchemaRule::Ref(reference) if ref_depth < MAX_REF_DEPTH => {
+                    if let Some(target) = definitions.get(reference) {
+                        target.fill_defaults_in(value, path, filled, definitions, ref_depth + 1);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The rules of this schema, with the rules of a `SchemaRule::Conditional`
+    /// that only checks the value's type in place of the conditional
+    ///
+    /// [`Schema::from_value`] guards type-specific keywords this way, so the
+    /// guarded rules still describe the values of that type.
+    pub(crate) fn unguarded_rules(&self) -> impl Iterator<Item = &SchemaRule> {
+        self.rules.iter().flat_map(|rule| match rule {
+            SchemaRule::Conditional {
+                if_schema,
+                then_schema: Some(then_schema),
+                else_schema: None,
+            } if matches!(if_schema.rules.as_slice(), [SchemaRule::Type(_)]) => {
+                then_schema.rules.as_slice()
+            }
+            other => std::slice::from_ref(other),
+        })
+    }
+
     /// Validate a value against this schema
+    ///
+    /// `SchemaRule::Custom` rules fail here since no validators are registered;
+    /// use [`SchemaValidator::register_validator`] to supply them.
     pub fn validate(&self, value: &Value, path: &str) -> ValidationResult<()> {
+        let ctx = ValidationContext {
+            definitions: Some(&self.definitions),
+            ..ValidationContext::default()
+        };
+        // A prefix that is not in dotted form is kept whole as a single key
+        let root = path.parse().unwrap_or_else(|_| {
+            Path::from(vec![PathSegment::Key(Value::String(path.to_string()))])
+        });
+        self.validate_in(value, &root, ctx)
+    }
+
+    /// Validate a value against this schema within a validation pass
+    fn validate_in(
+        &self,
+        value: &Value,
+        path: &Path,
+        ctx: ValidationContext<'_>,
+    ) -> ValidationResult<()> {
         let mut errors = Vec::new();
 
         // Apply each validation rule
         for rule in &self.rules {
-            if let Err(rule_errors) = self.apply_rule(rule, value, path) {
+            if let Err(rule_errors) = self.apply_rule(rule, value, path, ctx) {
                 errors.extend(rule_errors);
             }
         }
@@ -207,22 +923,30 @@ impl Schema {
     }
 
     /// Apply a single validation rule
-    fn apply_rule(&self, rule: &SchemaRule, value: &Value, path: &str) -> ValidationResult<()> {
+    fn apply_rule(
+        &self,
+        rule: &SchemaRule,
+        value: &Value,
+        path: &Path,
+        ctx: ValidationContext<'_>,
+    ) -> ValidationResult<()> {
         match rule {
             SchemaRule::Type(expected_type) => self.validate_type(expected_type, value, path),
             SchemaRule::Pattern(regex) => self.validate_pattern(regex, value, path),
             SchemaRule::Length { min, max } => self.validate_length(*min, *max, value, path),
             SchemaRule::Range { min, max } => self.validate_range(*min, *max, value, path),
             SchemaRule::Enum(allowed_values) => self.validate_enum(allowed_values, value, path),
-            SchemaRule::Properties(properties) => self.validate_properties(properties, value, path),
-            SchemaRule::Items(item_schema) => self.validate_items(item_schema, value, path),
+            SchemaRule::Properties(properties) => {
+                self.validate_properties(properties, value, path, ctx)
+            }
+            SchemaRule::Items(item_schema) => self.validate_items(item_schema, value, path, ctx),
             SchemaRule::Required(required_props) => {
                 self.validate_required(required_props, value, path)
             }
             SchemaRule::AdditionalProperties(allowed) => {
                 self.validate_additional_properties(*allowed, value, path)
             }
-            SchemaRule::Custom(name) => self.validate_custom(name, value, path),
+            SchemaRule::Custom(name) => self.validate_custom(name, value, path, ctx),
             SchemaRule::Conditional {
                 if_schema,
                 then_schema,
@@ -233,11 +957,22 @@ impl Schema {
                 else_schema.as_ref().map(|v| &**v),
                 value,
                 path,
+                ctx,
             ),
-            SchemaRule::Not(schema) => self.validate_not(schema, value, path),
-            SchemaRule::AnyOf(schemas) => self.validate_any_of(schemas, value, path),
-            SchemaRule::AllOf(schemas) => self.validate_all_of(schemas, value, path),
-            SchemaRule::OneOf(schemas) => self.validate_one_of(schemas, value, path),
+            SchemaRule::Not(schema) => self.validate_not(schema, value, path, ctx),
+            SchemaRule::AnyOf(schemas) => self.validate_any_of(schemas, value, path, ctx),
+            SchemaRule::AllOf(schemas) => self.validate_all_of(schemas, value, path, ctx),
+            SchemaRule::OneOf(schemas) => self.validate_one_of(schemas, value, path, ctx),
+            SchemaRule::PatternProperties(patterns) => {
+                self.validate_pattern_properties(patterns, value, path, ctx)
+            }
+            SchemaRule::PropertyNames(name_schema) => {
+                self.validate_property_names(name_schema, value, path, ctx)
+            }
+            SchemaRule::Ref(reference) => self.validate_ref(reference, value, path, ctx),
+            SchemaRule::UniqueItems => self.validate_unique_items(value, path),
+            SchemaRule::MultipleOf(divisor) => self.validate_multiple_of(*divisor, value, path),
+            SchemaRule::Format(kind) => self.validate_format(*kind, value, path),
         }
     }
 
@@ -246,9 +981,10 @@ impl Schema {
         &self,
         expected_type: &ValueType,
         value: &Value,
-        path: &str,
+        path: &Path,
     ) -> ValidationResult<()> {
-        let actual_type = match value {
+        // A tagged value has the type of its node
+        let actual_type = match value.untagged() {
             Value::String(_) => ValueType::String,
             Value::Int(_) => ValueType::Integer,
             Value::Float(_) => ValueType::Number,
@@ -256,6 +992,7 @@ impl Schema {
             Value::Sequence(_) => ValueType::Array,
             Value::Mapping(_) => ValueType::Object,
             Value::Null => ValueType::Null,
+            Value::Tagged(_) => unreachable!("untagged values have no tags"),
         };
 
         // Allow integer to be considered as number
@@ -269,6 +1006,7 @@ impl Schema {
         } else {
             Err(vec![ValidationError {
                 path: path.to_string(),
+                location: path.clone(),
                 rule: "type".to_string(),
                 message: format!("Expected {}, got {}", expected_type, actual_type),
                 value: value.clone(),
@@ -278,13 +1016,14 @@ impl Schema {
     }
 
     /// Validate regex pattern
-    fn validate_pattern(&self, regex: &Regex, value: &Value, path: &str) -> ValidationResult<()> {
+    fn validate_pattern(&self, regex: &Regex, value: &Value, path: &Path) -> ValidationResult<()> {
         if let Value::String(s) = value {
             if regex.is_match(s) {
                 Ok(())
             } else {
                 Err(vec![ValidationError {
                     path: path.to_string(),
+                    location: path.clone(),
                     rule: "pattern".to_string(),
                     message: format!("String '{}' does not match required pattern", s),
                     value: value.clone(),
@@ -294,6 +1033,7 @@ impl Schema {
         } else {
             Err(vec![ValidationError {
                 path: path.to_string(),
+                location: path.clone(),
                 rule: "pattern".to_string(),
                 message: "Pattern validation can only be applied to strings".to_string(),
                 value: value.clone(),
@@ -308,7 +1048,7 @@ impl Schema {
         min: Option<usize>,
         max: Option<usize>,
         value: &Value,
-        path: &str,
+        path: &Path,
     ) -> ValidationResult<()> {
         let length = match value {
             Value::String(s) => s.len(),
@@ -316,6 +1056,7 @@ impl Schema {
             _ => {
                 return Err(vec![ValidationError {
                     path: path.to_string(),
+                    location: path.clone(),
                     rule: "length".to_string(),
                     message: "Length validation can only be applied to strings or arrays"
                         .to_string(),
@@ -331,6 +1072,7 @@ impl Schema {
             if length < min_len {
                 errors.push(ValidationError {
                     path: path.to_string(),
+                    location: path.clone(),
                     rule: "minLength".to_string(),
                     message: format!("Length {} is less than minimum {}", length, min_len),
                     value: value.clone(),
@@ -343,6 +1085,7 @@ impl Schema {
             if length > max_len {
                 errors.push(ValidationError {
                     path: path.to_string(),
+                    location: path.clone(),
                     rule: "maxLength".to_string(),
                     message: format!("Length {} is greater than maximum {}", length, max_len),
                     value: value.clone(),
@@ -364,7 +1107,7 @@ impl Schema {
         min: Option<f64>,
         max: Option<f64>,
         value: &Value,
-        path: &str,
+        path: &Path,
     ) -> ValidationResult<()> {
         let number = match value {
             Value::Int(i) => *i as f64,
@@ -372,6 +1115,7 @@ impl Schema {
             _ => {
                 return Err(vec![ValidationError {
                     path: path.to_string(),
+                    location: path.clone(),
                     rule: "range".to_string(),
                     message: "Range validation can only be applied to numbers".to_string(),
                     value: value.clone(),
@@ -386,6 +1130,7 @@ impl Schema {
             if number < min_val {
                 errors.push(ValidationError {
                     path: path.to_string(),
+                    location: path.clone(),
                     rule: "minimum".to_string(),
                     message: format!("Value {} is less than minimum {}", number, min_val),
                     value: value.clone(),
@@ -398,6 +1143,7 @@ impl Schema {
             if number > max_val {
                 errors.push(ValidationError {
                     path: path.to_string(),
+                    location: path.clone(),
                     rule: "maximum".to_string(),
                     message: format!("Value {} is greater than maximum {}", number, max_val),
                     value: value.clone(),
@@ -418,13 +1164,14 @@ impl Schema {
         &self,
         allowed_values: &[Value],
         value: &Value,
-        path: &str,
+        path: &Path,
     ) -> ValidationResult<()> {
         if allowed_values.contains(value) {
             Ok(())
         } else {
             Err(vec![ValidationError {
                 path: path.to_string(),
+                location: path.clone(),
                 rule: "enum".to_string(),
                 message: format!(
                     "Value is not one of the allowed values: {:?}",
@@ -441,17 +1188,14 @@ impl Schema {
         &self,
         properties: &HashMap<String, Schema>,
         value: &Value,
-        path: &str,
+        path: &Path,
+        ctx: ValidationContext<'_>,
     ) -> ValidationResult<()> {
         if let Value::Mapping(map) = value {
             let mut errors = Vec::new();
 
             for (prop_name, prop_schema) in properties {
-                let prop_path = if path.is_empty() {
-                    prop_name.clone()
-                } else {
-                    format!("{}.{}", path, prop_name)
-                };
+                let prop_path = child(path, PathSegment::Key(Value::String(prop_name.clone())));
 
                 // Find the property in the mapping
                 let prop_value = map
@@ -468,7 +1212,7 @@ impl Schema {
                 match prop_value {
                     Some(value) => {
                         // Validate the property
-                        if let Err(prop_errors) = prop_schema.validate(value, &prop_path) {
+                        if let Err(prop_errors) = prop_schema.validate_in(value, &prop_path, ctx) {
                             errors.extend(prop_errors);
                         }
                     }
@@ -476,7 +1220,8 @@ impl Schema {
                         // Property is missing
                         if !prop_schema.optional {
                             errors.push(ValidationError {
-                                path: prop_path,
+                                path: prop_path.to_string(),
+                                location: prop_path,
                                 rule: "required".to_string(),
                                 message: format!("Required property '{}' is missing", prop_name),
                                 value: Value::Null,
@@ -495,6 +1240,7 @@ impl Schema {
         } else {
             Err(vec![ValidationError {
                 path: path.to_string(),
+                location: path.clone(),
                 rule: "properties".to_string(),
                 message: "Properties validation can only be applied to objects".to_string(),
                 value: value.clone(),
@@ -508,14 +1254,15 @@ impl Schema {
         &self,
         item_schema: &Schema,
         value: &Value,
-        path: &str,
+        path: &Path,
+        ctx: ValidationContext<'_>,
     ) -> ValidationResult<()> {
         if let Value::Sequence(seq) = value {
             let mut errors = Vec::new();
 
             for (index, item) in seq.iter().enumerate() {
-                let item_path = format!("{}[{}]", path, index);
-                if let Err(item_errors) = item_schema.validate(item, &item_path) {
+                let item_path = child(path, PathSegment::Index(index));
+                if let Err(item_errors) = item_schema.validate_in(item, &item_path, ctx) {
                     errors.extend(item_errors);
                 }
             }
@@ -528,6 +1275,7 @@ impl Schema {
         } else {
             Err(vec![ValidationError {
                 path: path.to_string(),
+                location: path.clone(),
                 rule: "items".to_string(),
                 message: "Items validation can only be applied to arrays".to_string(),
                 value: value.clone(),
@@ -541,7 +1289,7 @@ impl Schema {
         &self,
         required_props: &[String],
         value: &Value,
-        path: &str,
+        path: &Path,
     ) -> ValidationResult<()> {
         if let Value::Mapping(map) = value {
             let mut errors = Vec::new();
@@ -556,14 +1304,12 @@ impl Schema {
                 });
 
                 if !has_property {
-                    let prop_path = if path.is_empty() {
-                        required_prop.clone()
-                    } else {
-                        format!("{}.{}", path, required_prop)
-                    };
+                    let prop_path =
+                        child(path, PathSegment::Key(Value::String(required_prop.clone())));
 
                     errors.push(ValidationError {
-                        path: prop_path,
+                        path: prop_path.to_string(),
+                        location: prop_path,
                         rule: "required".to_string(),
                         message: format!("Required property '{}' is missing", required_prop),
                         value: Value::Null,
@@ -580,6 +1326,7 @@ impl Schema {
         } else {
             Err(vec![ValidationError {
                 path: path.to_string(),
+                location: path.clone(),
                 rule: "required".to_string(),
                 message: "Required validation can only be applied to objects".to_string(),
                 value: value.clone(),
@@ -589,25 +1336,90 @@ impl Schema {
     }
 
     /// Validate additional properties
+    ///
+    /// Keys are considered declared when they appear in a sibling
+    /// `SchemaRule::Properties` rule or match a sibling
+    /// `SchemaRule::PatternProperties` pattern of this schema.
     fn validate_additional_properties(
         &self,
         allowed: bool,
         value: &Value,
-        _path: &str,
+        path: &Path,
     ) -> ValidationResult<()> {
-        // This would be implemented in conjunction with Properties validation
-        // For now, always allow additional properties
-        if !allowed {
-            // TODO: Check for additional properties not defined in schema
+        let map = match value {
+            Value::Mapping(map) if !allowed => map,
+            _ => return Ok(()),
+        };
+
+        let mut errors = Vec::new();
+
+        for key in map.keys() {
+            let declared = match key {
+                Value::String(name) => self.rules.iter().any(|rule| match rule {
+                    SchemaRule::Properties(properties) => properties.contains_key(name),
+                    SchemaRule::PatternProperties(patterns) => {
+                        patterns.iter().any(|(regex, _)| regex.is_match(name))
+                    }
+                    _ => false,
+                }),
+                _ => false,
+            };
+
+            if !declared {
+                let key_name = match key {
+                    Value::String(name) => name.clone(),
+                    other => other.to_string(),
+                };
+                let prop_path = child(path, PathSegment::Key(Value::String(key_name.clone())));
+
+                errors.push(ValidationError {
+                    path: prop_path.to_string(),
+                    location: prop_path,
+                    rule: "additionalProperties".to_string(),
+                    message: format!("Additional property '{}' is not allowed", key_name),
+                    value: map[key].clone(),
+                    position: None,
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
         }
-        Ok(())
     }
 
-    /// Validate using custom function
-    fn validate_custom(&self, name: &str, value: &Value, path: &str) -> ValidationResult<()> {
-        // For now, custom validation always passes
-        // In a full implementation, this would call user-provided functions
-        Ok(())
+    /// Validate using a registered custom function
+    fn validate_custom(
+        &self,
+        name: &str,
+        value: &Value,
+        path: &Path,
+        ctx: ValidationContext<'_>,
+    ) -> ValidationResult<()> {
+        let Some(validator) = ctx.validators.and_then(|validators| validators.get(name)) else {
+            // Unknown validators fail rather than pass silently
+            return Err(vec![ValidationError {
+                path: path.to_string(),
+                location: path.clone(),
+                rule: "custom".to_string(),
+                message: format!("Unknown custom validator '{}'", name),
+                value: value.clone(),
+                position: None,
+            }]);
+        };
+
+        validator(value, &path.to_string()).map_err(|err| {
+            vec![ValidationError {
+                path: path.to_string(),
+                location: path.clone(),
+                rule: format!("custom:{}", name),
+                message: err.to_string(),
+                value: value.clone(),
+                position: None,
+            }]
+        })
     }
 
     /// Validate conditional logic
@@ -617,29 +1429,37 @@ impl Schema {
         then_schema: Option<&Schema>,
         else_schema: Option<&Schema>,
         value: &Value,
-        path: &str,
+        path: &Path,
+        ctx: ValidationContext<'_>,
     ) -> ValidationResult<()> {
         // Check if the "if" condition matches
-        let if_matches = if_schema.validate(value, path).is_ok();
+        let if_matches = if_schema.validate_in(value, path, ctx).is_ok();
 
         if if_matches {
             if let Some(then_schema) = then_schema {
-                then_schema.validate(value, path)
+                then_schema.validate_in(value, path, ctx)
             } else {
                 Ok(())
             }
         } else if let Some(else_schema) = else_schema {
-            else_schema.validate(value, path)
+            else_schema.validate_in(value, path, ctx)
         } else {
             Ok(())
         }
     }
 
     /// Validate negation (NOT)
-    fn validate_not(&self, schema: &Schema, value: &Value, path: &str) -> ValidationResult<()> {
-        if schema.validate(value, path).is_ok() {
+    fn validate_not(
+        &self,
+        schema: &Schema,
+        value: &Value,
+        path: &Path,
+        ctx: ValidationContext<'_>,
+    ) -> ValidationResult<()> {
+        if schema.validate_in(value, path, ctx).is_ok() {
             Err(vec![ValidationError {
                 path: path.to_string(),
+                location: path.clone(),
                 rule: "not".to_string(),
                 message: "Value must not match the specified schema".to_string(),
                 value: value.clone(),
@@ -655,16 +1475,18 @@ impl Schema {
         &self,
         schemas: &[Schema],
         value: &Value,
-        path: &str,
+        path: &Path,
+        ctx: ValidationContext<'_>,
     ) -> ValidationResult<()> {
         for schema in schemas {
-            if schema.validate(value, path).is_ok() {
+            if schema.validate_in(value, path, ctx).is_ok() {
                 return Ok(());
             }
         }
 
         Err(vec![ValidationError {
             path: path.to_string(),
+            location: path.clone(),
             rule: "anyOf".to_string(),
             message: "Value must match at least one of the specified schemas".to_string(),
             value: value.clone(),
@@ -677,12 +1499,13 @@ impl Schema {
         &self,
         schemas: &[Schema],
         value: &Value,
-        path: &str,
+        path: &Path,
+        ctx: ValidationContext<'_>,
     ) -> ValidationResult<()> {
         let mut all_errors = Vec::new();
 
         for schema in schemas {
-            if let Err(errors) = schema.validate(value, path) {
+            if let Err(errors) = schema.validate_in(value, path, ctx) {
                 all_errors.extend(errors);
             }
         }
@@ -699,12 +1522,13 @@ impl Schema {
         &self,
         schemas: &[Schema],
         value: &Value,
-        path: &str,
+        path: &Path,
+        ctx: ValidationContext<'_>,
     ) -> ValidationResult<()> {
         let mut valid_count = 0;
 
         for schema in schemas {
-            if schema.validate(value, path).is_ok() {
+            if schema.validate_in(value, path, ctx).is_ok() {
                 valid_count += 1;
             }
         }
@@ -714,6 +1538,7 @@ impl Schema {
         } else if valid_count == 0 {
             Err(vec![ValidationError {
                 path: path.to_string(),
+                location: path.clone(),
                 rule: "oneOf".to_string(),
                 message: "Value must match exactly one of the specified schemas (matched none)"
                     .to_string(),
@@ -723,6 +1548,7 @@ impl Schema {
         } else {
             Err(vec![ValidationError {
                 path: path.to_string(),
+                location: path.clone(),
                 rule: "oneOf".to_string(),
                 message: format!(
                     "Value must match exactly one of the specified schemas (matched {})",
@@ -733,21 +1559,373 @@ impl Schema {
             }])
         }
     }
-}
 
-impl Default for Schema {
-    fn default() -> Self {
-        Self::new()
-    }
-}
+    /// Validate properties whose names match a pattern
+    fn validate_pattern_properties(
+        &self,
+        patterns: &[(Regex, Schema)],
+        value: &Value,
+        path: &Path,
+        ctx: ValidationContext<'_>,
+    ) -> ValidationResult<()> {
+        if let Value::Mapping(map) = value {
+            let mut errors = Vec::new();
 
-/// Schema validator for YAML documents
-#[derive(Debug)]
-pub struct SchemaValidator {
-    /// Root schema for validation
-    pub schema: Schema,
-    /// Whether to collect all errors or stop at first error
-    pub collect_all_errors: bool,
+            for (key, prop_value) in map {
+                let Value::String(name) = key else {
+                    continue;
+                };
+                let prop_path = child(path, PathSegment::Key(Value::String(name.clone())));
+
+                for (regex, prop_schema) in patterns {
+                    if regex.is_match(name) {
+                        if let Err(prop_errors) =
+                            prop_schema.validate_in(prop_value, &prop_path, ctx)
+                        {
+                            errors.extend(prop_errors);
+                        }
+                    }
+                }
+            }
+
+            if errors.is_empty() {
+                Ok(())
+            } else {
+                Err(errors)
+            }
+        } else {
+            Err(vec![ValidationError {
+                path: path.to_string(),
+                location: path.clone(),
+                rule: "patternProperties".to_string(),
+                message: "Pattern properties validation can only be applied to objects".to_string(),
+                value: value.clone(),
+                position: None,
+            }])
+        }
+    }
+
+    /// Validate the names of all properties
+    fn validate_property_names(
+        &self,
+        name_schema: &Schema,
+        value: &Value,
+        path: &Path,
+        ctx: ValidationContext<'_>,
+    ) -> ValidationResult<()> {
+        if let Value::Mapping(map) = value {
+            let mut errors = Vec::new();
+
+            for key in map.keys() {
+                let key_name = match key {
+                    Value::String(name) => name.clone(),
+                    other => other.to_string(),
+                };
+                let prop_path = child(path, PathSegment::Key(Value::String(key_name.clone())));
+
+                if let Err(name_errors) = name_schema.validate_in(key, &prop_path, ctx) {
+                    errors.extend(name_errors.into_iter().map(|error| ValidationError {
+                        rule: format!("propertyNames:{}", error.rule),
+                        message: format!("Property name '{}': {}", key_name, error.message),
+                        ..error
+                    }));
+                }
+            }
+
+            if errors.is_empty() {
+                Ok(())
+            } else {
+                Err(errors)
+            }
+        } else {
+            Err(vec![ValidationError {
+                path: path.to_string(),
+                location: path.clone(),
+                rule: "propertyNames".to_string(),
+                message: "Property names validation can only be applied to objects".to_string(),
+                value: value.clone(),
+                position: None,
+            }])
+        }
+    }
+
+    /// Validate against a referenced definition
+    fn validate_ref(
+        &self,
+        reference: &str,
+        value: &Value,
+        path: &Path,
+        ctx: ValidationContext<'_>,
+    ) -> ValidationResult<()> {
+        let error = |message: String| {
+            Err(vec![ValidationError {
+                path: path.to_string(),
+                location: path.clone(),
+                rule: "$ref".to_string(),
+                message,
+                value: value.clone(),
+                position: None,
+            }])
+        };
+
+        let Some(target) = ctx.definitions.and_then(|defs| defs.get(reference)) else {
+            return error(format!("Unresolved schema reference '{}'", reference));
+        };
+        if ctx.ref_depth >= MAX_REF_DEPTH {
+            return error(format!(
+                "Schema reference '{}' nested deeper than {} levels",
+                reference, MAX_REF_DEPTH
+            ));
+        }
+
+        let nested = ValidationContext {
+            ref_depth: ctx.ref_depth + 1,
+            ..ctx
+        };
+        target.validate_in(value, path, nested)
+    }
+
+    /// Validate that array items are unique
+    fn validate_unique_items(&self, value: &Value, path: &Path) -> ValidationResult<()> {
+        if let Value::Sequence(seq) = value {
+            let mut errors = Vec::new();
+
+            // Pairwise comparison keeps mapping equality independent of key order
+            for (index, item) in seq.iter().enumerate() {
+                if let Some(first) = seq[..index].iter().position(|other| other == item) {
+                    let item_path = child(path, PathSegment::Index(index));
+                    errors.push(ValidationError {
+                        path: item_path.to_string(),
+                        location: item_path,
+                        rule: "uniqueItems".to_string(),
+                        message: format!("Item duplicates the item at index {}", first),
+                        value: item.clone(),
+                        position: None,
+                    });
+                }
+            }
+
+            if errors.is_empty() {
+                Ok(())
+            } else {
+                Err(errors)
+            }
+        } else {
+            Err(vec![ValidationError {
+                path: path.to_string(),
+                location: path.clone(),
+                rule: "uniqueItems".to_string(),
+                message: "Unique items validation can only be applied to arrays".to_string(),
+                value: value.clone(),
+                position: None,
+            }])
+        }
+    }
+
+    /// Validate that a number is a multiple of the divisor
+    fn validate_multiple_of(
+        &self,
+        divisor: f64,
+        value: &Value,
+        path: &Path,
+    ) -> ValidationResult<()> {
+        let is_multiple = match value {
+            Value::Int(i) if divisor.fract() == 0.0 && divisor.abs() <= i64::MAX as f64 => {
+                i.checked_rem(divisor as i64) == Some(0)
+            }
+            Value::Int(i) => is_float_multiple(*i as f64, divisor),
+            Value::Float(f) => is_float_multiple(*f, divisor),
+            _ => {
+                return Err(vec![ValidationError {
+                    path: path.to_string(),
+                    location: path.clone(),
+                    rule: "multipleOf".to_string(),
+                    message: "Multiple-of validation can only be applied to numbers".to_string(),
+                    value: value.clone(),
+                    position: None,
+                }]);
+            }
+        };
+
+        if is_multiple {
+            Ok(())
+        } else {
+            Err(vec![ValidationError {
+                path: path.to_string(),
+                location: path.clone(),
+                rule: "multipleOf".to_string(),
+                message: format!("Value {} is not a multiple of {}", value, divisor),
+                value: value.clone(),
+                position: None,
+            }])
+        }
+    }
+
+    /// Validate string format
+    fn validate_format(
+        &self,
+        kind: FormatKind,
+        value: &Value,
+        path: &Path,
+    ) -> ValidationResult<()> {
+        if let Value::String(s) = value {
+            if kind.matches(s) {
+                Ok(())
+            } else {
+                Err(vec![ValidationError {
+                    path: path.to_string(),
+                    location: path.clone(),
+                    rule: "format".to_string(),
+                    message: format!("String '{}' is not a valid {}", s, kind),
+                    value: value.clone(),
+                    position: None,
+                }])
+            }
+        } else {
+            Err(vec![ValidationError {
+                path: path.to_string(),
+                location: path.clone(),
+                rule: "format".to_string(),
+                message: "Format validation can only be applied to strings".to_string(),
+                value: value.clone(),
+                position: None,
+            }])
+        }
+    }
+}
+
+/// Check whether `number` is a multiple of `divisor`, tolerating rounding error
+fn is_float_multiple(number: f64, divisor: f64) -> bool {
+    if divisor == 0.0 || !number.is_finite() {
+        return false;
+    }
+    let quotient = number / divisor;
+    (quotient - quotient.round()).abs() < 1e-9
+}
+
+impl Default for Schema {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Build the error returned for malformed JSON Schema documents
+fn schema_error(message: impl Into<String>) -> Error {
+    Error::config_error(format!("Invalid JSON Schema: {}", message.into()))
+}
+
+/// Map a JSON Schema type name onto a [`ValueType`]
+fn parse_value_type(name: &str) -> Result<ValueType> {
+    match name {
+        "string" => Ok(ValueType::String),
+        "number" => Ok(ValueType::Number),
+        "integer" => Ok(ValueType::Integer),
+        "boolean" => Ok(ValueType::Boolean),
+        "array" => Ok(ValueType::Array),
+        "object" => Ok(ValueType::Object),
+        "null" => Ok(ValueType::Null),
+        other => Err(schema_error(format!("unknown type '{}'", other))),
+    }
+}
+
+/// Convert the `type` keyword, which may be a single name or a list of names
+fn parse_type_keyword(value: &Value) -> Result<SchemaRule> {
+    match value {
+        Value::String(name) => Ok(SchemaRule::Type(parse_value_type(name)?)),
+        Value::Sequence(names) => {
+            let alternatives = names
+                .iter()
+                .map(|name| {
+                    name.as_str()
+                        .ok_or_else(|| schema_error("'type' entries must be strings"))
+                        .and_then(parse_value_type)
+                        .map(Schema::with_type)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(SchemaRule::AnyOf(alternatives))
+        }
+        _ => Err(schema_error("'type' must be a string or a sequence")),
+    }
+}
+
+/// Draft-07 validation keywords [`Schema::from_value`] cannot represent
+const UNSUPPORTED_KEYWORDS: [&str; 7] = [
+    "exclusiveMinimum",
+    "exclusiveMaximum",
+    "minProperties",
+    "maxProperties",
+    "additionalItems",
+    "contains",
+    "dependencies",
+];
+
+/// The rules of JSON Schema keywords that only constrain values of `value_type`
+///
+/// Unless the schema's `type` already limits values to that type, the rules
+/// are guarded by a type check so that values of other types pass them.
+fn type_specific_rules(
+    value_type: ValueType,
+    rules: Vec<SchemaRule>,
+    declared_type: Option<&ValueType>,
+) -> Vec<SchemaRule> {
+    let declared = match declared_type {
+        Some(ValueType::Integer) => value_type == ValueType::Number,
+        Some(declared_type) => *declared_type == value_type,
+        None => false,
+    };
+    if rules.is_empty() || declared {
+        return rules;
+    }
+    vec![SchemaRule::Conditional {
+        if_schema: Box::new(Schema::with_type(value_type)),
+        then_schema: Some(Box::new(Schema::new().rules(rules))),
+        else_schema: None,
+    }]
+}
+
+/// Read an optional numeric keyword
+fn optional_number(value: &Value, key: &str) -> Result<Option<f64>> {
+    match value.get_str(key) {
+        Some(number) => number
+            .as_float()
+            .map(Some)
+            .ok_or_else(|| schema_error(format!("'{}' must be a number", key))),
+        None => Ok(None),
+    }
+}
+
+/// Read an optional non-negative integer keyword
+fn optional_count(value: &Value, key: &str) -> Result<Option<usize>> {
+    match value.get_str(key) {
+        Some(Value::Int(count)) if *count >= 0 => Ok(Some(*count as usize)),
+        Some(_) => Err(schema_error(format!(
+            "'{}' must be a non-negative integer",
+            key
+        ))),
+        None => Ok(None),
+    }
+}
+
+/// Schema validator for YAML documents
+pub struct SchemaValidator {
+    /// Root schema for validation
+    pub schema: Schema,
+    /// Whether to collect all errors or stop at first error
+    pub collect_all_errors: bool,
+    /// Custom validators referenced by `SchemaRule::Custom`
+    validators: HashMap<String, ValidatorFn>,
+}
+
+impl fmt::Debug for SchemaValidator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names: Vec<&String> = self.validators.keys().collect();
+        names.sort();
+        f.debug_struct("SchemaValidator")
+            .field("schema", &self.schema)
+            .field("collect_all_errors", &self.collect_all_errors)
+            .field("validators", &names)
+            .finish()
+    }
 }
 
 impl SchemaValidator {
@@ -756,6 +1934,7 @@ impl SchemaValidator {
         Self {
             schema,
             collect_all_errors: true,
+            validators: HashMap::new(),
         }
     }
 
@@ -764,31 +1943,101 @@ impl SchemaValidator {
         Self {
             schema,
             collect_all_errors: false,
+            validators: HashMap::new(),
         }
     }
 
+    /// Register a custom validator invoked for `SchemaRule::Custom(name)`
+    ///
+    /// Registering a validator under an existing name replaces it.
+    pub fn register_validator(&mut self, name: impl Into<String>, validator: ValidatorFn) {
+        self.validators.insert(name.into(), validator);
+    }
+
+    /// Builder-style variant of [`register_validator`](Self::register_validator)
+    pub fn with_validator(mut self, name: impl Into<String>, validator: ValidatorFn) -> Self {
+        self.register_validator(name, validator);
+        self
+    }
+
+    /// Check whether a custom validator is registered under `name`
+    pub fn has_validator(&self, name: &str) -> bool {
+        self.validators.contains_key(name)
+    }
+
     /// Validate a YAML value against the schema
     pub fn validate(&self, value: &Value) -> ValidationResult<()> {
-        self.schema.validate(value, "")
+        self.schema.validate_in(value, &Path::new(), self.context())
+    }
+
+    /// Fill in schema defaults for missing optional properties, then validate
+    ///
+    /// Returns the paths of every property that received its default value, so
+    /// callers can tell which settings were not provided explicitly.
+    pub fn validate_and_fill(&self, value: &mut Value) -> ValidationResult<Vec<String>> {
+        let mut filled = Vec::new();
+        self.schema.fill_defaults(value, "", &mut filled);
+        self.validate(value)?;
+        Ok(filled)
+    }
+
+    /// Build the context shared by all rules during a validation pass
+    fn context(&self) -> ValidationContext<'_> {
+        ValidationContext {
+            validators: Some(&self.validators),
+            definitions: Some(&self.schema.definitions),
+            ref_depth: 0,
+        }
+    }
+
+    /// Validate a value, attaching source positions to the reported errors
+    ///
+    /// `positions` is the map for the value's document as returned by
+    /// [`node_positions`].
+    pub fn validate_located(
+        &self,
+        value: &Value,
+        positions: &HashMap<Path, Position>,
+    ) -> ValidationResult<()> {
+        self.validate(value).map_err(|mut errors| {
+            for error in &mut errors {
+                error.locate(positions);
+            }
+            errors
+        })
     }
 
     /// Validate and return a formatted error report
     pub fn validate_with_report(&self, value: &Value) -> Result<()> {
-        match self.validate(value) {
-            Ok(()) => Ok(()),
-            Err(errors) => {
-                let error_messages: Vec<String> =
-                    errors.iter().map(|e| format!("  - {}", e)).collect();
+        self.validate(value).map_err(|errors| Self::report(&errors))
+    }
 
-                let message = format!(
-                    "Schema validation failed with {} error(s):\n{}",
-                    errors.len(),
-                    error_messages.join("\n")
-                );
+    /// Validate against the document's node positions and return a formatted
+    /// error report whose entries carry line and column information
+    pub fn validate_located_with_report(
+        &self,
+        value: &Value,
+        positions: &HashMap<Path, Position>,
+    ) -> Result<()> {
+        self.validate_located(value, positions)
+            .map_err(|errors| Self::report(&errors))
+    }
 
-                Err(Error::parse(Position::start(), message))
-            }
-        }
+    /// Build the error returned for a failed validation
+    fn report(errors: &[ValidationError]) -> Error {
+        let error_messages: Vec<String> = errors.iter().map(|e| format!("  - {}", e)).collect();
+
+        let message = format!(
+            "Schema validation failed with {} error(s):\n{}",
+            errors.len(),
+            error_messages.join("\n")
+        );
+
+        let position = errors
+            .iter()
+            .find_map(|e| e.position)
+            .unwrap_or_else(Position::start);
+        Error::parse(position, message)
     }
 }
 
@@ -862,4 +2111,559 @@ mod tests {
             .validate(&Value::Mapping(invalid_map), "test")
             .is_err());
     }
+
+    #[test]
+    fn test_from_json_schema_str() {
+        let schema = Schema::from_json_schema_str(
+            r#"
+title: Server
+type: object
+properties:
+  host: {"type": "string", "pattern": "^[a-z.]+$"}
+  port: {"type": "integer", "minimum": 1, "maximum": 65535}
+  mode:
+    enum: [dev, prod]
+  tags:
+    type: array
+    items:
+      type: string
+    maxItems: 2
+required: [host, port]
+"#,
+        )
+        .unwrap();
+        assert_eq!(schema.title.as_deref(), Some("Server"));
+
+        let yaml = crate::Yaml::new();
+        let valid = yaml
+            .load_str("host: example.com\nport: 8080\ntags: [a, b]\n")
+            .unwrap();
+        assert!(schema.validate(&valid, "").is_ok());
+
+        let invalid = yaml
+            .load_str("host: Example.com\nport: 99999\nmode: test\ntags: [a, 1, c]\n")
+            .unwrap();
+        let errors = schema.validate(&invalid, "").unwrap_err();
+        let paths: Vec<&str> = errors.iter().map(|e| e.path.as_str()).collect();
+        assert!(paths.contains(&"host"));
+        assert!(paths.contains(&"port"));
+        assert!(paths.contains(&"mode"));
+        assert!(paths.contains(&"tags"));
+        assert!(paths.contains(&"tags[1]"));
+
+        let missing = yaml.load_str("host: example.com").unwrap();
+        assert!(schema.validate(&missing, "").is_err());
+    }
+
+    #[test]
+    fn test_from_value_combinators() {
+        let yaml = crate::Yaml::new();
+        let schema_doc = yaml
+            .load_str(
+                "anyOf:\n  - type: string\n  - type: [integer, \"null\"]\nnot:\n  const: forbidden\n",
+            )
+            .unwrap();
+        let schema = Schema::from_value(&schema_doc).unwrap();
+
+        assert!(schema.validate(&Value::String("ok".into()), "").is_ok());
+        assert!(schema.validate(&Value::Int(3), "").is_ok());
+        assert!(schema.validate(&Value::Null, "").is_ok());
+        assert!(schema.validate(&Value::Bool(true), "").is_err());
+        assert!(schema
+            .validate(&Value::String("forbidden".into()), "")
+            .is_err());
+
+        assert!(Schema::from_value(&Value::Bool(true))
+            .unwrap()
+            .validate(&Value::Int(1), "")
+            .is_ok());
+        assert!(Schema::from_value(&Value::Bool(false))
+            .unwrap()
+            .validate(&Value::Int(1), "")
+            .is_err());
+    }
+
+    #[test]
+    fn test_from_value_rejects_malformed_schemas() {
+        assert!(Schema::from_json_schema_str("type: bogus").is_err());
+        assert!(Schema::from_json_schema_str("required: name").is_err());
+        assert!(Schema::from_json_schema_str("pattern: '('").is_err());
+        assert!(Schema::from_json_schema_str("42").is_err());
+
+        // Keyword forms the subset cannot represent are not silently dropped
+        for unsupported in [
+            "additionalProperties: {type: string}",
+            "exclusiveMinimum: 0",
+            "contains: {type: integer}",
+            "uniqueItems: yes please",
+        ] {
+            assert!(
+                Schema::from_json_schema_str(unsupported).is_err(),
+                "{unsupported}"
+            );
+        }
+        let error = Schema::from_json_schema_str("minProperties: 1").unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("unsupported keyword 'minProperties'"));
+    }
+
+    #[test]
+    fn test_from_value_type_specific_keywords() {
+        let yaml = crate::Yaml::new();
+        let check = |schema: &Schema, input: &str| {
+            schema.validate(&yaml.load_str(input).unwrap(), "").is_ok()
+        };
+
+        // Keywords for one type pass values of the other types the schema allows
+        let nullable = Schema::from_json_schema_str(
+            "type: [string, \"null\"]\nmaxLength: 3\npattern: '^[a-z]*$'",
+        )
+        .unwrap();
+        assert!(check(&nullable, "null"));
+        assert!(check(&nullable, "abc"));
+        assert!(!check(&nullable, "abcd"));
+        assert!(!check(&nullable, "AB"));
+        assert!(!check(&nullable, "12"));
+
+        let untyped = Schema::from_json_schema_str(
+            "minLength: 2\nminItems: 3\nminimum: 10\nitems: {type: integer}",
+        )
+        .unwrap();
+        assert!(check(&untyped, "ab"));
+        assert!(!check(&untyped, "a"));
+        assert!(check(&untyped, "[1, 2, 3]"));
+        assert!(!check(&untyped, "[1, 2]"));
+        assert!(!check(&untyped, "[1, 2, x]"));
+        assert!(check(&untyped, "12"));
+        assert!(!check(&untyped, "9"));
+        assert!(check(&untyped, "true"));
+        assert!(check(&untyped, "{a: 1}"));
+
+        // Defaults of guarded properties are still filled in
+        let schema = Schema::from_json_schema_str(
+            "type: [object, \"null\"]\nproperties:\n  port: {type: integer, default: 80}",
+        )
+        .unwrap();
+        assert!(check(&schema, "null"));
+        let mut value = yaml.load_str("host: example.com").unwrap();
+        let mut filled = Vec::new();
+        schema.fill_defaults(&mut value, "", &mut filled);
+        assert_eq!(value["port"], Value::Int(80));
+        assert_eq!(filled, vec!["port".to_string()]);
+    }
+
+    #[test]
+    fn test_custom_validators() {
+        let schema = Schema::with_type(ValueType::Integer).rule(SchemaRule::Custom("even".into()));
+
+        // Without a registered validator the rule must not silently pass
+        let errors = schema.validate(&Value::Int(2), "n").unwrap_err();
+        assert_eq!(errors[0].rule, "custom");
+        assert!(errors[0].message.contains("even"));
+
+        let validator = SchemaValidator::new(schema).with_validator(
+            "even",
+            Box::new(|value, _path| match value {
+                Value::Int(i) if i % 2 == 0 => Ok(()),
+                _ => Err(Error::config("value must be even")),
+            }),
+        );
+        assert!(validator.has_validator("even"));
+        assert!(validator.validate(&Value::Int(4)).is_ok());
+
+        let errors = validator.validate(&Value::Int(3)).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].rule, "custom:even");
+        assert!(errors[0].message.contains("value must be even"));
+    }
+
+    #[test]
+    fn test_custom_validators_in_nested_schemas() {
+        let mut properties = HashMap::new();
+        properties.insert(
+            "name".to_string(),
+            Schema::with_type(ValueType::String).rule(SchemaRule::Custom("lowercase".into())),
+        );
+        let schema = Schema::with_type(ValueType::Object).rule(SchemaRule::Properties(properties));
+
+        let mut validator = SchemaValidator::new(schema);
+        validator.register_validator(
+            "lowercase",
+            Box::new(|value, path| match value.as_str() {
+                Some(s) if s.chars().all(|c| !c.is_uppercase()) => Ok(()),
+                _ => Err(Error::config(format!("{} must be lowercase", path))),
+            }),
+        );
+
+        let ok = Value::mapping_with(vec![(Value::from("name"), Value::from("alice"))]);
+        let bad = Value::mapping_with(vec![(Value::from("name"), Value::from("Alice"))]);
+        assert!(validator.validate(&ok).is_ok());
+
+        let errors = validator.validate(&bad).unwrap_err();
+        assert_eq!(errors[0].path, "name");
+        assert!(errors[0].message.contains("name must be lowercase"));
+    }
+
+    #[test]
+    fn test_additional_properties_rejected() {
+        let mut properties = HashMap::new();
+        properties.insert("host".to_string(), Schema::with_type(ValueType::String));
+        properties.insert(
+            "port".to_string(),
+            Schema::with_type(ValueType::Integer).optional(),
+        );
+        let schema = Schema::with_type(ValueType::Object)
+            .rule(SchemaRule::Properties(properties))
+            .rule(SchemaRule::AdditionalProperties(false));
+
+        let ok = Value::mapping_with(vec![
+            (Value::from("host"), Value::from("localhost")),
+            (Value::from("port"), Value::Int(80)),
+        ]);
+        assert!(schema.validate(&ok, "server").is_ok());
+
+        let typo = Value::mapping_with(vec![
+            (Value::from("host"), Value::from("localhost")),
+            (Value::from("prot"), Value::Int(80)),
+            (Value::from("debug"), Value::Bool(true)),
+        ]);
+        let errors = schema.validate(&typo, "server").unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().all(|e| e.rule == "additionalProperties"));
+        assert_eq!(errors[0].path, "server.prot");
+        assert_eq!(errors[0].value, Value::Int(80));
+        assert_eq!(errors[1].path, "server.debug");
+
+        // Allowing additional properties keeps the previous behavior
+        let permissive =
+            Schema::with_type(ValueType::Object).rule(SchemaRule::AdditionalProperties(true));
+        assert!(permissive.validate(&typo, "").is_ok());
+    }
+
+    #[test]
+    fn test_validate_and_fill_defaults() {
+        let mut pool = HashMap::new();
+        pool.insert(
+            "size".to_string(),
+            Schema::with_type(ValueType::Integer)
+                .optional()
+                .default_value(Value::Int(5)),
+        );
+        pool.insert(
+            "timeout".to_string(),
+            Schema::with_type(ValueType::Integer)
+                .optional()
+                .default_value(Value::Int(30)),
+        );
+
+        let mut database = HashMap::new();
+        database.insert("url".to_string(), Schema::with_type(ValueType::String));
+        database.insert(
+            "pool".to_string(),
+            Schema::with_type(ValueType::Object)
+                .rule(SchemaRule::Properties(pool))
+                .optional()
+                .default_value(Value::mapping()),
+        );
+
+        let mut root = HashMap::new();
+        root.insert(
+            "database".to_string(),
+            Schema::with_type(ValueType::Object).rule(SchemaRule::Properties(database)),
+        );
+        let validator = SchemaValidator::new(
+            Schema::with_type(ValueType::Object).rule(SchemaRule::Properties(root)),
+        );
+
+        let mut config = Value::mapping_with(vec![(
+            Value::from("database"),
+            Value::mapping_with(vec![(Value::from("url"), Value::from("postgres://db"))]),
+        )]);
+        let filled = validator.validate_and_fill(&mut config).unwrap();
+
+        assert_eq!(
+            filled,
+            vec![
+                "database.pool".to_string(),
+                "database.pool.size".to_string(),
+                "database.pool.timeout".to_string(),
+            ]
+        );
+        let pool = config.get_str("database").unwrap().get_str("pool").unwrap();
+        assert_eq!(pool.get_str("size"), Some(&Value::Int(5)));
+        assert_eq!(pool.get_str("timeout"), Some(&Value::Int(30)));
+
+        // Values that are present are left untouched
+        let mut explicit = Value::mapping_with(vec![(
+            Value::from("database"),
+            Value::mapping_with(vec![
+                (Value::from("url"), Value::from("postgres://db")),
+                (
+                    Value::from("pool"),
+                    Value::mapping_with(vec![(Value::from("size"), Value::Int(1))]),
+                ),
+            ]),
+        )]);
+        let filled = validator.validate_and_fill(&mut explicit).unwrap();
+        assert_eq!(filled, vec!["database.pool.timeout".to_string()]);
+    }
+
+    #[test]
+    fn test_node_positions() {
+        let input = "name: app\nserver:\n  port: 80\n  hosts:\n    - a\n    - b\n";
+        let documents = node_positions(input).unwrap();
+        assert_eq!(documents.len(), 1);
+        let positions = &documents[0];
+        let at = |path: &str| positions[&path.parse::<Path>().unwrap()];
+
+        assert_eq!(at("name").line, 1);
+        assert_eq!(at("server.port").line, 3);
+        assert_eq!(at("server.port").column, 9);
+        assert_eq!(at("server.hosts[1]").line, 6);
+
+        let documents = node_positions("a: 1\n---\nb: 2\n").unwrap();
+        assert_eq!(documents.len(), 2);
+        assert_eq!(documents[1][&"b".parse::<Path>().unwrap()].line, 3);
+
+        // Keys are segments of their own, whatever characters they contain
+        let input = "labels:\n  app.kubernetes.io/name: web\n8080: http\n";
+        let positions = node_positions(input).unwrap().remove(0);
+        let label = Path::from(vec![
+            PathSegment::Key(Value::from("labels")),
+            PathSegment::Key(Value::from("app.kubernetes.io/name")),
+        ]);
+        assert_eq!(positions[&label].line, 2);
+        let port = Path::from(vec![PathSegment::Key(Value::Int(8080))]);
+        assert_eq!(positions[&port].line, 3);
+    }
+
+    #[test]
+    fn test_validation_errors_carry_positions() {
+        let mut properties = HashMap::new();
+        properties.insert(
+            "port".to_string(),
+            Schema::with_type(ValueType::Integer).rule(SchemaRule::Range {
+                min: Some(1.0),
+                max: Some(65535.0),
+            }),
+        );
+        properties.insert("host".to_string(), Schema::with_type(ValueType::String));
+        let mut root = HashMap::new();
+        root.insert(
+            "server".to_string(),
+            Schema::with_type(ValueType::Object).rule(SchemaRule::Properties(properties)),
+        );
+        let validator = SchemaValidator::new(
+            Schema::with_type(ValueType::Object).rule(SchemaRule::Properties(root)),
+        );
+
+        let input = "# settings\nserver:\n  port: 99999\n";
+        let value = crate::Yaml::new().load_str(input).unwrap();
+        let positions = node_positions(input).unwrap().remove(0);
+        let errors = validator.validate_located(&value, &positions).unwrap_err();
+
+        let port = errors.iter().find(|e| e.path == "server.port").unwrap();
+        let position = port.position.unwrap();
+        assert_eq!((position.line, position.column), (3, 9));
+        assert!(port.to_string().ends_with("at line 3, column 9"));
+
+        // Missing properties point at their parent mapping
+        let host = errors.iter().find(|e| e.path == "server.host").unwrap();
+        assert_eq!(host.position.unwrap().line, 3);
+
+        // A dotted key is located as one key rather than as nested ones
+        let labels = HashMap::from([(
+            "app.kubernetes.io/name".to_string(),
+            Schema::with_type(ValueType::String),
+        )]);
+        let validator =
+            SchemaValidator::new(Schema::new().rule(SchemaRule::Properties(HashMap::from([(
+                "labels".to_string(),
+                Schema::new().rule(SchemaRule::Properties(labels)),
+            )]))));
+        let input = "labels:\n  app.kubernetes.io/name: 3\n";
+        let value = crate::Yaml::new().load_str(input).unwrap();
+        let positions = node_positions(input).unwrap().remove(0);
+        let errors = validator.validate_located(&value, &positions).unwrap_err();
+        assert_eq!(errors[0].path, "labels.app.kubernetes.io/name");
+        assert_eq!(errors[0].location.segments().len(), 2);
+        let position = errors[0].position.unwrap();
+        assert_eq!((position.line, position.column), (2, 27));
+    }
+
+    #[test]
+    fn test_unique_items_validation() {
+        let schema = Schema::with_type(ValueType::Array).rule(SchemaRule::UniqueItems);
+
+        let unique = Value::Sequence(vec![Value::Int(1), Value::Int(2), Value::from("1")]);
+        assert!(schema.validate(&unique, "ports").is_ok());
+
+        let duplicated = Value::Sequence(vec![Value::Int(1), Value::Int(2), Value::Int(1)]);
+        let errors = schema.validate(&duplicated, "ports").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "ports[2]");
+        assert_eq!(errors[0].rule, "uniqueItems");
+    }
+
+    #[test]
+    fn test_multiple_of_validation() {
+        let schema = Schema::new().rule(SchemaRule::MultipleOf(5.0));
+        assert!(schema.validate(&Value::Int(15), "n").is_ok());
+        assert!(schema.validate(&Value::Float(-10.0), "n").is_ok());
+        assert!(schema.validate(&Value::Int(7), "n").is_err());
+        assert!(schema.validate(&Value::from("15"), "n").is_err());
+
+        let fractional = Schema::new().rule(SchemaRule::MultipleOf(0.1));
+        assert!(fractional.validate(&Value::Float(0.3), "n").is_ok());
+        assert!(fractional.validate(&Value::Int(2), "n").is_ok());
+        assert!(fractional.validate(&Value::Float(0.25), "n").is_err());
+    }
+
+    #[test]
+    fn test_format_validation() {
+        let cases = [
+            (FormatKind::Email, "ops@example.com", "ops@example"),
+            (FormatKind::Uri, "https://example.com/a?b=c", "example.com"),
+            (FormatKind::Ipv4, "192.168.0.1", "256.1.1.1"),
+            (FormatKind::Ipv6, "::1", "12345::"),
+            (
+                FormatKind::DateTime,
+                "2024-02-29T23:59:60.5+05:30",
+                "2024-13-01T00:00:00Z",
+            ),
+            (
+                FormatKind::Uuid,
+                "123e4567-e89b-12d3-a456-426614174000",
+                "123e4567-e89b-12d3-a456",
+            ),
+        ];
+
+        for (kind, valid, invalid) in cases {
+            let schema = Schema::with_type(ValueType::String).rule(SchemaRule::Format(kind));
+            assert!(
+                schema.validate(&Value::from(valid), "v").is_ok(),
+                "{}",
+                kind
+            );
+            let errors = schema.validate(&Value::from(invalid), "v").unwrap_err();
+            assert_eq!(errors[0].rule, "format", "{}", kind);
+        }
+
+        let schema =
+            Schema::from_json_schema_str("type: array\nuniqueItems: true\nitems: {format: ipv4}\n")
+                .unwrap();
+        let value = crate::Yaml::new()
+            .load_str("[10.0.0.1, 10.0.0.1, nope]")
+            .unwrap();
+        let errors = schema.validate(&value, "").unwrap_err();
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn test_pattern_properties_and_property_names() {
+        let environment = Schema::with_type(ValueType::Object)
+            .rule(SchemaRule::Required(vec!["replicas".to_string()]));
+        let schema = Schema::with_type(ValueType::Object)
+            .rule(SchemaRule::PatternProperties(vec![(
+                Regex::new("^(prod|dev)-").unwrap(),
+                environment,
+            )]))
+            .rule(SchemaRule::PropertyNames(Box::new(
+                Schema::with_type(ValueType::String).rule(SchemaRule::Length {
+                    min: None,
+                    max: Some(12),
+                }),
+            )))
+            .rule(SchemaRule::AdditionalProperties(false));
+
+        let yaml = crate::Yaml::new();
+        let ok = yaml
+            .load_str("prod-eu:\n  replicas: 3\ndev-local:\n  replicas: 1\n")
+            .unwrap();
+        assert!(schema.validate(&ok, "").is_ok());
+
+        let bad = yaml
+            .load_str("prod-eu:\n  image: app\nstaging: {}\ndev-very-long-name: {replicas: 1}\n")
+            .unwrap();
+        let errors = schema.validate(&bad, "").unwrap_err();
+        let rules: Vec<(&str, &str)> = errors
+            .iter()
+            .map(|e| (e.path.as_str(), e.rule.as_str()))
+            .collect();
+        assert!(rules.contains(&("prod-eu.replicas", "required")));
+        assert!(rules.contains(&("staging", "additionalProperties")));
+        assert!(rules.contains(&("dev-very-long-name", "propertyNames:maxLength")));
+    }
+
+    #[test]
+    fn test_ref_resolution_with_definitions() {
+        let schema = Schema::from_json_schema_str(
+            r##"
+definitions:
+  port:
+    type: integer
+    minimum: 1
+    maximum: 65535
+  container:
+    type: object
+    required: [name]
+    properties:
+      name: {type: string}
+      port: {$ref: "#/definitions/port"}
+      sidecars:
+        type: array
+        items: {$ref: "#/definitions/container"}
+type: object
+properties:
+  main: {$ref: "#/definitions/container"}
+  metrics: {$ref: "#/definitions/port"}
+"##,
+        )
+        .unwrap();
+
+        // Each definition is parsed once and shared by every reference
+        assert_eq!(schema.definitions.len(), 2);
+
+        let yaml = crate::Yaml::new();
+        let valid = yaml
+            .load_str("main:\n  name: app\n  port: 80\n  sidecars:\n    - name: proxy\n      sidecars: []\nmetrics: 9090\n")
+            .unwrap();
+        assert!(schema.validate(&valid, "").is_ok());
+
+        let invalid = yaml
+            .load_str("main:\n  name: app\n  sidecars:\n    - port: 0\nmetrics: 70000\n")
+            .unwrap();
+        let errors = schema.validate(&invalid, "").unwrap_err();
+        let paths: Vec<&str> = errors.iter().map(|e| e.path.as_str()).collect();
+        assert!(paths.contains(&"main.sidecars[0].name"));
+        assert!(paths.contains(&"main.sidecars[0].port"));
+        assert!(paths.contains(&"metrics"));
+    }
+
+    #[test]
+    fn test_ref_errors() {
+        assert!(Schema::from_json_schema_str("$ref: '#/definitions/missing'").is_err());
+        assert!(Schema::from_json_schema_str("$ref: 'other.json#/a'").is_err());
+        assert!(Schema::from_json_schema_str(
+            "definitions:\n  a: {$ref: '#/definitions/b'}\n  b: {$ref: '#/definitions/a'}\n$ref: '#/definitions/a'\n"
+        )
+        .is_err());
+
+        let unresolved = Schema::new().rule(SchemaRule::Ref("#/nowhere".to_string()));
+        let errors = unresolved.validate(&Value::Null, "").unwrap_err();
+        assert_eq!(errors[0].rule, "$ref");
+
+        let builder = Schema::new()
+            .rule(SchemaRule::Ref("positive".to_string()))
+            .definition(
+                "positive",
+                Schema::with_type(ValueType::Integer).rule(SchemaRule::Range {
+                    min: Some(1.0),
+                    max: None,
+                }),
+            );
+        assert!(builder.validate(&Value::Int(3), "").is_ok());
+        assert!(builder.validate(&Value::Int(0), "").is_err());
+    }
 }
@@ -0,0 +1,369 @@
+//! Self-documenting output laid out by a schema
+//!
+//! With a schema set through [`BasicEmitter::set_schema`] or
+//! [`YamlConfig::dump_schema`](crate::YamlConfig), the properties of each
+//! mapping are written in the order the schema lists them, with the
+//! description of each property as a comment above its first occurrence.
+//! [`generate_template`] uses this to write a starting configuration:
+//!
+//! ```
+//! use rust_yaml::{generate_template, Schema, SchemaRule, ValueType};
+//! use std::collections::HashMap;
+//!
+//! let mut properties = HashMap::new();
+//! properties.insert(
+//!     "port".to_string(),
+//!     Schema::with_type(ValueType::Integer).info("Port", "Port the server listens on"),
+//! );
+//! let schema = Schema::with_type(ValueType::Object).rule(SchemaRule::Properties(properties));
+//! assert_eq!(
+//!     generate_template(&schema),
+//!     "# Port the server listens on\nport: 0\n"
+//! );
+//! ```
+//!
+//! Properties come in the order of the schema's `required` list, followed
+//! by the others sorted by name; keys the schema does not describe keep
+//! their place after them.
+
+use crate::{BasicEmitter, CommentedValue, Emitter, Schema, SchemaRule, Value, ValueType};
+use indexmap::IndexMap;
+use std::collections::HashSet;
+
+/// Write a YAML document with a value for every property `schema`
+/// describes, each under its description
+///
+/// Values are the schema's defaults where it has them, and otherwise the
+/// first allowed value of an enum or an empty value of the property's
+/// type. An array of objects is given one item showing their properties.
+pub fn generate_template(schema: &Schema) -> String {
+    let mut emitter = BasicEmitter::new();
+    emitter.set_schema(Some(schema.clone()));
+    let mut output = Vec::new();
+    emitter
+        .emit(&template_value(schema, schema, 0), &mut output)
+        .expect("writing to a Vec cannot fail");
+    String::from_utf8(output).expect("the emitter writes UTF-8")
+}
+
+/// Deepest nesting of schemas a template is written for, which stops
+/// schemas referring to themselves
+const MAX_TEMPLATE_DEPTH: usize = 32;
+
+/// The placeholder value written for `schema`
+fn template_value(schema: &Schema, root: &Schema, depth: usize) -> Value {
+    let schema = resolve(schema, root);
+    if let Some(default) = &schema.default {
+        return default.clone();
+    }
+    let value_type = schema.rules.iter().find_map(|rule| match rule {
+        SchemaRule::Type(value_type) => Some(value_type),
+        _ => None,
+    });
+    if let Some(first) = schema.rules.iter().find_map(|rule| match rule {
+        SchemaRule::Enum(values) => values.first(),
+        _ => None,
+    }) {
+        return first.clone();
+    }
+    let properties = properties(schema);
+    match value_type {
+        Some(ValueType::String) => Value::String(String::new()),
+        Some(ValueType::Integer) => Value::Int(0),
+        Some(ValueType::Number) => Value::Float(0.0),
+        Some(ValueType::Boolean) => Value::Bool(false),
+        Some(ValueType::Array) => match items(schema) {
+            Some(items) if depth < MAX_TEMPLATE_DEPTH && !properties_of(items, root).is_empty() => {
+                Value::Sequence(vec![template_value(items, root, depth + 1)])
+            }
+            _ => Value::Sequence(Vec::new()),
+        },
+        Some(ValueType::Object) | None if !properties.is_empty() => {
+            if depth >= MAX_TEMPLATE_DEPTH {
+                return Value::Mapping(IndexMap::new());
+            }
+            Value::Mapping(
+                properties
+                    .into_iter()
+                    .map(|(name, property)| {
+                        (Value::from(name), template_value(property, root, depth + 1))
+                    })
+                    .collect(),
+            )
+        }
+        Some(ValueType::Object) => Value::Mapping(IndexMap::new()),
+        Some(ValueType::Null) | None => Value::Null,
+    }
+}
+
+/// `commented` with its mappings ordered and described by `schema`
+///
+/// Comments already recorded on a key are kept in place of its
+/// description.
+pub(crate) fn document(commented: &CommentedValue, schema: &Schema) -> CommentedValue {
+    let mut documented = commented.clone();
+    if documented.comments.leading.is_empty() {
+        documented.comments.leading = description(schema);
+    }
+    let mut value = std::mem::replace(&mut documented.value, Value::Null);
+    Documenter {
+        root: schema,
+        described: HashSet::new(),
+    }
+    .document(&mut value, &mut documented, schema);
+    documented.value = value;
+    documented
+}
+
+/// Orders and describes the mappings of a value by a schema
+struct Documenter<'a> {
+    root: &'a Schema,
+    /// Addresses of the property schemas whose description was written
+    described: HashSet<usize>,
+}
+
+impl<'a> Documenter<'a> {
+    /// Order the mappings in `value` by `schema`, recording property
+    /// descriptions on the keys and items of `recorded`
+    fn document(&mut self, value: &mut Value, recorded: &mut CommentedValue, schema: &'a Schema) {
+        let schema = resolve(schema, self.root);
+        match value {
+            Value::Mapping(map) => {
+                let properties = properties(schema);
+                if properties.is_empty() {
+                    return;
+                }
+                let mut ordered = IndexMap::with_capacity(map.len());
+                for (name, _) in &properties {
+                    if let Some((key, item)) = map.shift_remove_entry(&Value::from(*name)) {
+                        ordered.insert(key, item);
+                    }
+                }
+                ordered.extend(map.drain(..));
+                *map = ordered;
+
+                for (name, property) in properties {
+                    let key = Value::from(name);
+                    let Some(item) = map.get_mut(&key) else {
+                        continue;
+                    };
+                    let entry = recorded
+                        .keys
+                        .entry(key.clone())
+                        .or_insert_with(|| CommentedValue::new(key));
+                    // Each property is described above its first occurrence
+                    if self.described.insert(std::ptr::from_ref(property) as usize)
+                        && entry.comments.leading.is_empty()
+                    {
+                        entry.comments.leading = description(resolve(property, self.root));
+                    }
+                    self.document(item, entry, property);
+                }
+            }
+            Value::Sequence(seq) => {
+                let Some(items) = items(schema) else {
+                    return;
+                };
+                for (index, item) in seq.iter_mut().enumerate() {
+                    // An item recorded for an earlier value is left unused
+                    let mut unused = CommentedValue::new(Value::Null);
+                    let entry = match recorded.items.get_mut(index) {
+                        Some(entry) if entry.value == *item => entry,
+                        Some(_) => &mut unused,
+                        None => {
+                            recorded.items.push(CommentedValue::new(Value::Null));
+                            &mut recorded.items[index]
+                        }
+                    };
+                    self.document(item, entry, items);
+                    entry.value = item.clone();
+                }
+            }
+            Value::Tagged(tagged) => self.document(&mut tagged.value, recorded, schema),
+            _ => {}
+        }
+    }
+}
+
+/// The comment lines describing `schema`: its description, or its title
+/// when it has none
+fn description(schema: &Schema) -> Vec<String> {
+    schema
+        .description
+        .as_deref()
+        .or(schema.title.as_deref())
+        .map(|text| {
+            text.lines()
+                .map(|line| line.trim_end().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The definition `schema` refers to in `root`, if it is a reference
+fn resolve<'a>(schema: &'a Schema, root: &'a Schema) -> &'a Schema {
+    let mut resolved = schema;
+    // A reference may lead to another, but never more often than there are
+    // definitions
+    for _ in 0..=root.definitions.len() {
+        let reference = resolved.rules.iter().find_map(|rule| match rule {
+            SchemaRule::Ref(reference) => root.definitions.get(reference),
+            _ => None,
+        });
+        match reference {
+            Some(definition) => resolved = definition,
+            None => break,
+        }
+    }
+    resolved
+}
+
+/// The properties of `schema` in the order they are written
+fn properties(schema: &Schema) -> Vec<(&str, &Schema)> {
+    let mut properties: Vec<(&str, &Schema)> = schema
+        .unguarded_rules()
+        .filter_map(|rule| match rule {
+            SchemaRule::Properties(properties) => Some(properties),
+            _ => None,
+        })
+        .flatten()
+        .map(|(name, property)| (name.as_str(), property))
+        .collect();
+    let required: Vec<&str> = schema
+        .unguarded_rules()
+        .filter_map(|rule| match rule {
+            SchemaRule::Required(names) => Some(names),
+            _ => None,
+        })
+        .flatten()
+        .map(String::as_str)
+        .collect();
+    properties.sort_by_key(|(name, _)| {
+        (
+            required
+                .iter()
+                .position(|required| required == name)
+                .unwrap_or(usize::MAX),
+            *name,
+        )
+    });
+    properties
+}
+
+/// The properties of the schema `schema` refers to
+fn properties_of<'a>(schema: &'a Schema, root: &'a Schema) -> Vec<(&'a str, &'a Schema)> {
+    properties(resolve(schema, root))
+}
+
+/// The schema the items of an array described by `schema` follow
+fn items(schema: &Schema) -> Option<&Schema> {
+    schema.unguarded_rules().find_map(|rule| match rule {
+        SchemaRule::Items(items) => Some(&**items),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{yaml, Yaml, YamlConfig};
+    use std::collections::HashMap;
+
+    fn service_schema() -> Schema {
+        let mut port = HashMap::new();
+        port.insert(
+            "port".to_string(),
+            Schema::with_type(ValueType::Integer).info("Port", "Port to listen on"),
+        );
+        port.insert(
+            "protocol".to_string(),
+            Schema::with_type(ValueType::String).rule(SchemaRule::Enum(vec![
+                Value::from("tcp"),
+                Value::from("udp"),
+            ])),
+        );
+        let mut properties = HashMap::new();
+        properties.insert(
+            "name".to_string(),
+            Schema::with_type(ValueType::String).info("Name", "Name of the service"),
+        );
+        properties.insert(
+            "replicas".to_string(),
+            Schema::with_type(ValueType::Integer)
+                .info("Replicas", "How many copies to run\nat once")
+                .default_value(Value::Int(1)),
+        );
+        properties.insert(
+            "ports".to_string(),
+            Schema::with_type(ValueType::Array).rule(SchemaRule::Items(Box::new(
+                Schema::with_type(ValueType::Object).rule(SchemaRule::Properties(port)),
+            ))),
+        );
+        properties.insert(
+            "debug".to_string(),
+            Schema::with_type(ValueType::Boolean).info("Debug", ""),
+        );
+        Schema::with_type(ValueType::Object)
+            .rule(SchemaRule::Properties(properties))
+            .rule(SchemaRule::Required(vec![
+                "name".to_string(),
+                "ports".to_string(),
+            ]))
+            .info("Service", "A service to deploy")
+    }
+
+    #[test]
+    fn test_generate_template() {
+        assert_eq!(
+            generate_template(&service_schema()),
+            "# A service to deploy\n\
+             # Name of the service\n\
+             name: \"\"\n\
+             ports:\n  \
+             - \n    \
+             # Port to listen on\n    \
+             port: 0\n    \
+             protocol: tcp\n\
+             debug: false\n\
+             # How many copies to run\n\
+             # at once\n\
+             replicas: 1\n"
+        );
+    }
+
+    #[test]
+    fn test_dump_schema_orders_and_describes_once() {
+        let mut yaml = Yaml::with_config(YamlConfig {
+            dump_schema: Some(service_schema()),
+            ..YamlConfig::default()
+        });
+        let value = yaml!({
+            "extra": true,
+            "replicas": 2,
+            "ports": [{"protocol": "udp", "port": 53}, {"port": 80}],
+            "name": "dns",
+        });
+        let dumped = yaml.dump_str(&value).unwrap();
+        assert_eq!(
+            dumped,
+            "# A service to deploy\n\
+             # Name of the service\n\
+             name: dns\n\
+             ports:\n  \
+             - \n    \
+             # Port to listen on\n    \
+             port: 53\n    \
+             protocol: udp\n  \
+             - \n    \
+             port: 80\n\
+             # How many copies to run\n\
+             # at once\n\
+             replicas: 2\n\
+             extra: true\n"
+        );
+        assert_eq!(yaml.load_str(&dumped).unwrap(), value);
+
+        yaml.config_mut().dump_schema = None;
+        assert!(yaml.dump_str(&value).unwrap().starts_with("extra: true"));
+    }
+}
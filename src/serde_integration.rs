@@ -1,17 +1,33 @@
 //! Serde integration for rust-yaml
 //!
-//! This module provides serde serialization and deserialization support
-
-// Placeholder for serde integration - imports will be used when feature is implemented
+//! [`from_value`] deserializes a loaded [`Value`] into any type implementing
+//! `Deserialize`, and [`Yaml::load_validated`](crate::Yaml::load_validated)
+//! checks a document against a schema before doing so:
+//!
+//! ```
+//! use rust_yaml::{from_value, yaml};
+//!
+//! #[derive(serde::Deserialize)]
+//! struct Server {
+//!     host: String,
+//!     ports: Vec<u16>,
+//! }
+//!
+//! let server: Server = from_value(&yaml!({"host": "db", "ports": [5432]})).unwrap();
+//! assert_eq!(server.ports, [5432]);
+//! ```
+//!
+//! Sequences and mappings deserialize as sequences and maps, and a tagged
+//! value as what it holds, except for enums, where `!Variant value` selects
+//! the variant like a single-entry mapping `{Variant: value}` does.
 
-// For now, this module is a placeholder for future serde integration
-// The feature is not yet implemented but the module exists to avoid compilation errors
+use crate::{Error, Path, PathSegment, Position, Value};
+use serde::de::{self, DeserializeOwned, IntoDeserializer};
+use std::fmt;
 
-#[cfg(feature = "serde")]
 /// Placeholder wrapper for future serde integration
 pub struct SerdeWrapper;
 
-#[cfg(feature = "serde")]
 impl SerdeWrapper {
     /// Create a new serde wrapper
     #[must_use]
@@ -20,9 +36,389 @@ impl SerdeWrapper {
     }
 }
 
-#[cfg(feature = "serde")]
 impl Default for SerdeWrapper {
     fn default() -> Self {
         Self::new()
     }
 }
+
+/// Deserialize `value` into a `T`
+///
+/// Errors name the path of the node that did not fit `T`; they have no
+/// source position, as the value may not have been loaded from text.
+pub fn from_value<T: DeserializeOwned>(value: &Value) -> crate::Result<T> {
+    deserialize_at_path(value).map_err(|(path, message)| {
+        Error::value_error(Position::start(), at_path_message(&path, &message))
+    })
+}
+
+/// Deserialize `value` into a `T`, failing with the path of the node that
+/// did not fit and the reason
+pub(crate) fn deserialize_at_path<T: DeserializeOwned>(value: &Value) -> Result<T, (Path, String)> {
+    T::deserialize(ValueDeserializer(value)).map_err(|error| {
+        let mut segments = error.segments;
+        segments.reverse();
+        (Path::from(segments), error.message)
+    })
+}
+
+/// The message of an error deserializing the node at `path`
+pub(crate) fn at_path_message(path: &Path, message: &str) -> String {
+    if path.is_root() {
+        format!("cannot deserialize document: {message}")
+    } else {
+        format!("cannot deserialize '{path}': {message}")
+    }
+}
+
+/// An error deserializing a value, with the steps to the node it is at
+#[derive(Debug)]
+struct DeError {
+    message: String,
+    /// Steps from the node up to the root, added as the error propagates
+    segments: Vec<PathSegment>,
+}
+
+impl DeError {
+    fn at(mut self, segment: PathSegment) -> Self {
+        self.segments.push(segment);
+        self
+    }
+}
+
+impl fmt::Display for DeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for DeError {}
+
+impl de::Error for DeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self {
+            message: msg.to_string(),
+            segments: Vec::new(),
+        }
+    }
+}
+
+/// What a value looks like to serde, for type errors
+fn unexpected(value: &Value) -> de::Unexpected<'_> {
+    match value {
+        Value::Null => de::Unexpected::Unit,
+        Value::Bool(b) => de::Unexpected::Bool(*b),
+        Value::Int(i) => de::Unexpected::Signed(*i),
+        Value::Float(f) => de::Unexpected::Float(*f),
+        Value::String(s) => de::Unexpected::Str(s),
+        Value::Sequence(_) => de::Unexpected::Seq,
+        Value::Mapping(_) => de::Unexpected::Map,
+        Value::Tagged(tagged) => unexpected(&tagged.value),
+    }
+}
+
+/// Deserializer reading a borrowed [`Value`]
+struct ValueDeserializer<'de>(&'de Value);
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = DeError;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.0 {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(b) => visitor.visit_bool(*b),
+            Value::Int(i) => visitor.visit_i64(*i),
+            Value::Float(f) => visitor.visit_f64(*f),
+            Value::String(s) => visitor.visit_borrowed_str(s),
+            Value::Sequence(seq) => {
+                let mut access = SeqAccess {
+                    items: seq.iter().enumerate(),
+                };
+                let value = visitor.visit_seq(&mut access)?;
+                match access.items.len() {
+                    0 => Ok(value),
+                    remaining => Err(de::Error::invalid_length(
+                        seq.len() - remaining,
+                        &"fewer elements in sequence",
+                    )),
+                }
+            }
+            Value::Mapping(map) => visitor.visit_map(MapAccess {
+                entries: map.iter(),
+                value: None,
+            }),
+            Value::Tagged(tagged) => ValueDeserializer(&tagged.value).deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.0 {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        let (variant, content) = match self.0 {
+            Value::String(variant) => (variant.as_str(), None),
+            Value::Tagged(tagged) => (
+                tagged.tag.strip_prefix('!').unwrap_or(&tagged.tag),
+                Some(&tagged.value),
+            ),
+            Value::Mapping(map) if map.len() == 1 => match map.iter().next() {
+                Some((Value::String(variant), content)) => (variant.as_str(), Some(content)),
+                _ => return Err(de::Error::invalid_type(unexpected(self.0), &"enum variant")),
+            },
+            other => return Err(de::Error::invalid_type(unexpected(other), &"enum variant")),
+        };
+        visitor.visit_enum(EnumAccess { variant, content })
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+/// The items of a sequence being deserialized
+struct SeqAccess<'de> {
+    items: std::iter::Enumerate<std::slice::Iter<'de, Value>>,
+}
+
+impl<'de> de::SeqAccess<'de> for &mut SeqAccess<'de> {
+    type Error = DeError;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, DeError> {
+        let Some((index, item)) = self.items.next() else {
+            return Ok(None);
+        };
+        seed.deserialize(ValueDeserializer(item))
+            .map(Some)
+            .map_err(|error| error.at(PathSegment::Index(index)))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+/// The entries of a mapping being deserialized, with the one whose key was
+/// just read
+struct MapAccess<'de> {
+    entries: indexmap::map::Iter<'de, Value, Value>,
+    value: Option<(&'de Value, &'de Value)>,
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess<'de> {
+    type Error = DeError;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, DeError> {
+        let Some((key, value)) = self.entries.next() else {
+            return Ok(None);
+        };
+        self.value = Some((key, value));
+        seed.deserialize(ValueDeserializer(key)).map(Some)
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, DeError> {
+        let Some((key, value)) = self.value.take() else {
+            return Err(de::Error::custom("value requested before its key"));
+        };
+        seed.deserialize(ValueDeserializer(value))
+            .map_err(|error| error.at(PathSegment::Key(key.clone())))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+/// The variant of an enum being deserialized and the node it holds
+struct EnumAccess<'de> {
+    variant: &'de str,
+    content: Option<&'de Value>,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess<'de> {
+    type Error = DeError;
+    type Variant = Self;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self), DeError> {
+        let variant = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> EnumAccess<'de> {
+    /// The node the variant holds, with errors in it placed under the
+    /// variant's key
+    fn content<T>(
+        &self,
+        deserialize: impl FnOnce(ValueDeserializer<'de>) -> Result<T, DeError>,
+    ) -> Result<T, DeError> {
+        let Some(content) = self.content else {
+            return Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"variant with content",
+            ));
+        };
+        deserialize(ValueDeserializer(content))
+            .map_err(|error| error.at(PathSegment::Key(Value::from(self.variant))))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for EnumAccess<'de> {
+    type Error = DeError;
+
+    fn unit_variant(self) -> Result<(), DeError> {
+        match self.content {
+            None | Some(Value::Null) => Ok(()),
+            Some(content) => Err(de::Error::invalid_type(
+                unexpected(content),
+                &"unit variant",
+            )),
+        }
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, DeError> {
+        self.content(|content| seed.deserialize(content))
+    }
+
+    fn tuple_variant<V: de::Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.content(|content| de::Deserializer::deserialize_seq(content, visitor))
+    }
+
+    fn struct_variant<V: de::Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.content(|content| de::Deserializer::deserialize_map(content, visitor))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::yaml;
+    use serde::Deserialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Config {
+        name: String,
+        replicas: u8,
+        ratio: Option<f64>,
+        labels: BTreeMap<String, String>,
+        ports: Vec<Port>,
+        mode: Mode,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Port {
+        port: u16,
+        #[serde(default)]
+        protocol: Protocol,
+    }
+
+    #[derive(Debug, Default, PartialEq, Deserialize)]
+    enum Protocol {
+        #[default]
+        Tcp,
+        Udp,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Mode {
+        Single(String),
+        Pool { size: u32 },
+    }
+
+    #[test]
+    fn test_from_value() {
+        let value = yaml!({
+            "name": "api",
+            "replicas": 3,
+            "ratio": null,
+            "labels": {"tier": "web"},
+            "ports": [{"port": 80}, {"port": 53, "protocol": "Udp"}],
+            "mode": {"Pool": {"size": 4}},
+        });
+        let config: Config = from_value(&value).unwrap();
+        assert_eq!(
+            config,
+            Config {
+                name: "api".to_string(),
+                replicas: 3,
+                ratio: None,
+                labels: BTreeMap::from([("tier".to_string(), "web".to_string())]),
+                ports: vec![
+                    Port {
+                        port: 80,
+                        protocol: Protocol::Tcp
+                    },
+                    Port {
+                        port: 53,
+                        protocol: Protocol::Udp
+                    },
+                ],
+                mode: Mode::Pool { size: 4 },
+            }
+        );
+
+        // A tagged value selects the variant of an enum
+        let mode = crate::Yaml::new().load_str("!Single primary").unwrap();
+        assert_eq!(
+            from_value::<Mode>(&mode).unwrap(),
+            Mode::Single("primary".to_string())
+        );
+    }
+
+    #[test]
+    fn test_from_value_error_paths() {
+        let value = yaml!({"name": "api", "replicas": 3, "labels": {}, "ports": [{"port": 70000}], "mode": {"Single": "a"}});
+        let (path, message) = deserialize_at_path::<Config>(&value).unwrap_err();
+        assert_eq!(path.to_string(), "ports[0].port");
+        assert!(message.contains("70000"), "{message}");
+
+        let (path, message) = deserialize_at_path::<Config>(&yaml!({"name": "api"})).unwrap_err();
+        assert!(path.is_root());
+        assert!(message.contains("missing field `replicas`"), "{message}");
+
+        let error = from_value::<Mode>(&yaml!({"Pool": {"size": "big"}})).unwrap_err();
+        assert!(error.to_string().contains("'Pool.size'"), "{error}");
+    }
+}
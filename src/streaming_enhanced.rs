@@ -7,8 +7,10 @@
 //! - Buffered reading with configurable chunk sizes
 
 use crate::{
-    parser::{Event, EventType},
-    Error, Limits, Position, ResourceTracker, Result,
+    parser::{DuplicateKeys, Event, EventType, ScalarStyle},
+    profiling::Stopwatch,
+    scanner::scalar_scanner::ends_plain_scalar,
+    Chomping, Error, Limits, Position, ResourceTracker, Result,
 };
 use std::collections::VecDeque;
 use std::io::{BufRead, BufReader};
@@ -27,6 +29,8 @@ pub struct StreamConfig {
     pub limits: Limits,
     /// Chunk size for reading (bytes)
     pub chunk_size: usize,
+    /// Fail on a scalar key repeated within one mapping
+    pub reject_duplicate_keys: bool,
 }
 
 impl Default for StreamConfig {
@@ -37,6 +41,7 @@ impl Default for StreamConfig {
             incremental: true,
             limits: Limits::default(),
             chunk_size: 8 * 1024, // 8KB chunks
+            reject_duplicate_keys: false,
         }
     }
 }
@@ -50,6 +55,7 @@ impl StreamConfig {
             incremental: true,
             limits: Limits::permissive(),
             chunk_size: 64 * 1024, // 64KB chunks
+            reject_duplicate_keys: false,
         }
     }
 
@@ -61,6 +67,7 @@ impl StreamConfig {
             incremental: true,
             limits: Limits::strict(),
             chunk_size: 1024, // 1KB chunks
+            reject_duplicate_keys: false,
         }
     }
 }
@@ -100,44 +107,85 @@ pub struct StreamingYamlParser<R: BufRead> {
     context: ParseContext,
     /// Statistics
     stats: StreamStats,
+    /// Whether the reader has no more data
+    exhausted: bool,
+    /// Duplicate key detection, when enabled
+    duplicate_keys: Option<DuplicateKeys>,
 }
 
 /// Parsing context for incremental parsing
 #[derive(Debug, Clone)]
 struct ParseContext {
-    /// Stack of collection types (true = mapping, false = sequence)
-    collection_stack: Vec<bool>,
-    /// Current indentation level
-    indent_level: usize,
+    /// Open block collections, innermost last
+    collection_stack: Vec<OpenCollection>,
+    /// Open flow collections, innermost last
+    flow_stack: Vec<FlowCollection>,
     /// Pending anchor
     pending_anchor: Option<String>,
+    /// Where the pending anchor starts, so an anchor on the line of a
+    /// mapping key belongs to the key, as in `&a key: value`
+    anchor_position: Option<Position>,
     /// Pending tag
     pending_tag: Option<String>,
-    /// In block scalar
-    in_block_scalar: bool,
-    /// Block scalar indent
-    block_scalar_indent: Option<usize>,
+    /// Style and chomping of a block scalar whose content is still to be read
+    block_scalar: Option<(ScalarStyle, Chomping)>,
+    /// Line of the last block mapping `:`, where no other key may start
+    value_indicator_line: Option<usize>,
+}
+
+/// A block collection that has not ended yet
+#[derive(Debug, Clone)]
+struct OpenCollection {
+    /// Whether it is a mapping rather than a sequence
+    mapping: bool,
+    /// Column its keys or `-` indicators start at, counted from 0
+    indent: usize,
+    /// Whether the last key or `-` is still waiting for its node
+    awaiting_node: bool,
+}
+
+/// A flow collection that has not ended yet
+#[derive(Debug, Clone)]
+struct FlowCollection {
+    /// Whether it is a mapping rather than a sequence
+    mapping: bool,
+    /// How far its current entry has been read
+    entry: FlowEntry,
+}
+
+/// How far the current entry of a flow collection has been read
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FlowEntry {
+    /// Nothing yet, or a mapping key still to come
+    Start,
+    /// A mapping key, whose `:` may follow
+    Key,
+    /// A mapping `:`, whose value may follow
+    Value,
+    /// The whole entry, so only `,` or the end of the collection may follow
+    Done,
 }
 
 impl ParseContext {
     fn new() -> Self {
         Self {
             collection_stack: Vec::new(),
-            indent_level: 0,
+            flow_stack: Vec::new(),
             pending_anchor: None,
+            anchor_position: None,
             pending_tag: None,
-            in_block_scalar: false,
-            block_scalar_indent: None,
+            block_scalar: None,
+            value_indicator_line: None,
         }
     }
 
     fn reset(&mut self) {
         self.collection_stack.clear();
-        self.indent_level = 0;
+        self.flow_stack.clear();
         self.pending_anchor = None;
+        self.anchor_position = None;
         self.pending_tag = None;
-        self.in_block_scalar = false;
-        self.block_scalar_indent = None;
+        self.block_scalar = None;
     }
 }
 
@@ -163,6 +211,7 @@ impl<R: BufRead> StreamingYamlParser<R> {
     pub fn new(reader: R, config: StreamConfig) -> Self {
         Self {
             reader,
+            duplicate_keys: config.reject_duplicate_keys.then(DuplicateKeys::default),
             config,
             state: StreamState::Initial,
             buffer: String::with_capacity(4096),
@@ -171,24 +220,33 @@ impl<R: BufRead> StreamingYamlParser<R> {
             resource_tracker: ResourceTracker::new(),
             context: ParseContext::new(),
             stats: StreamStats::default(),
+            exhausted: false,
         }
     }
 
     /// Parse the next chunk of data
     pub fn parse_next(&mut self) -> Result<bool> {
-        let start = std::time::Instant::now();
+        let start = Stopwatch::start();
 
         // Read next chunk
         let bytes_read = self.read_chunk()?;
-        if bytes_read == 0 && self.buffer.is_empty() {
-            self.state = StreamState::EndOfStream;
-            return Ok(false);
+        self.exhausted = bytes_read == 0;
+        if self.exhausted && self.buffer.is_empty() {
+            self.finish_stream()?;
+            return Ok(!self.events.is_empty());
         }
 
         self.stats.bytes_read += bytes_read;
 
         // Parse buffer content
+        let buffered = self.buffer.len();
         self.parse_buffer()?;
+        if self.exhausted && self.buffer.len() == buffered && self.state == StreamState::InDocument
+        {
+            self.state = StreamState::Error("unexpected end of stream".to_string());
+            self.stats.errors_encountered += 1;
+            return Err(Error::parse(self.position, "unexpected end of stream"));
+        }
 
         // Update statistics
         self.stats.parse_time_ms += start.elapsed().as_millis() as u64;
@@ -213,6 +271,9 @@ impl<R: BufRead> StreamingYamlParser<R> {
     /// Parse the current buffer content
     fn parse_buffer(&mut self) -> Result<()> {
         // Handle different states
+        if self.state != StreamState::Initial && self.needs_more_data() {
+            return Ok(());
+        }
         match self.state {
             StreamState::Initial => {
                 self.emit_stream_start()?;
@@ -235,20 +296,49 @@ impl<R: BufRead> StreamingYamlParser<R> {
         Ok(())
     }
 
+    /// Close the open document and emit the end of the stream
+    fn finish_stream(&mut self) -> Result<()> {
+        match self.state {
+            StreamState::EndOfStream | StreamState::Error(_) => return Ok(()),
+            StreamState::Initial => self.emit_stream_start()?,
+            StreamState::InDocument => self.end_document(true)?,
+            StreamState::BetweenDocuments => {}
+        }
+        self.emit_stream_end()?;
+        self.state = StreamState::EndOfStream;
+        Ok(())
+    }
+
     /// Parse document start markers
     fn parse_document_start(&mut self) -> Result<()> {
-        // Skip whitespace
-        self.skip_whitespace();
+        // Blank lines, comments and stray `...` markers belong to no document
+        loop {
+            self.skip_whitespace();
+            match self.buffer.chars().next() {
+                Some('\n' | '\r') => {
+                    self.buffer.remove(0);
+                    self.position.line += 1;
+                    self.position.column = 1;
+                }
+                Some('#') => self.skip_comment(),
+                Some('.') if self.at_marker("...") => {
+                    self.buffer.drain(..3);
+                    self.position.column += 3;
+                }
+                _ => break,
+            }
+        }
 
         // Check for document start marker (---)
-        if self.buffer.starts_with("---") {
+        if self.at_marker("---") {
             self.buffer.drain(..3);
             self.position.column += 3;
-            self.emit_document_start()?;
+            self.emit_document_start(false)?;
             self.state = StreamState::InDocument;
+            self.parse_document_content()?;
         } else if !self.buffer.is_empty() {
             // Implicit document start
-            self.emit_document_start()?;
+            self.emit_document_start(true)?;
             self.state = StreamState::InDocument;
             self.parse_document_content()?;
         }
@@ -256,28 +346,64 @@ impl<R: BufRead> StreamingYamlParser<R> {
         Ok(())
     }
 
+    /// Whether the buffer starts with a document marker
+    fn at_marker(&self, marker: &str) -> bool {
+        self.buffer.starts_with(marker)
+            && self.buffer[marker.len()..]
+                .chars()
+                .next()
+                .is_none_or(|ch| matches!(ch, ' ' | '\t' | '\n' | '\r'))
+    }
+
+    /// Close the open collections and emit the end of the document
+    fn end_document(&mut self, implicit: bool) -> Result<()> {
+        if let Some(flow) = self.context.flow_stack.last() {
+            let close = if flow.mapping { '}' } else { ']' };
+            return Err(self.fail(format!(
+                "unclosed flow collection, expected '{close}' before the end of the document"
+            )));
+        }
+        if let Some((style, _)) = self.context.block_scalar.take() {
+            self.emit_scalar(String::new(), style)?;
+        }
+        while !self.context.collection_stack.is_empty() {
+            self.close_collection()?;
+        }
+        self.emit_document_end(implicit)?;
+        self.state = StreamState::BetweenDocuments;
+        self.context.reset();
+        Ok(())
+    }
+
     /// Parse document content incrementally
     fn parse_document_content(&mut self) -> Result<()> {
-        while !self.buffer.is_empty() {
+        while !self.buffer.is_empty() && !self.needs_more_data() {
+            let line_start = self.position.column == 1 && self.context.block_scalar.is_none();
+
             // Check for document end marker (...)
-            if self.buffer.starts_with("...") {
+            if line_start && self.at_marker("...") {
                 self.buffer.drain(..3);
                 self.position.column += 3;
-                self.emit_document_end()?;
-                self.state = StreamState::BetweenDocuments;
-                self.context.reset();
+                self.end_document(false)?;
                 break;
             }
 
+            // A document start marker implicitly ends the current document
+            if line_start && self.at_marker("---") {
+                self.end_document(true)?;
+                return self.parse_document_start();
+            }
+
             // Parse based on context
-            if self.context.in_block_scalar {
+            let buffered = self.buffer.len();
+            if self.context.block_scalar.is_some() {
                 self.parse_block_scalar_content()?;
             } else {
                 self.parse_yaml_content()?;
             }
 
             // Break if we need more data
-            if self.needs_more_data() {
+            if self.buffer.len() == buffered {
                 break;
             }
         }
@@ -294,16 +420,16 @@ impl<R: BufRead> StreamingYamlParser<R> {
         }
 
         let first_char = self.buffer.chars().next().unwrap();
+        if !self.context.flow_stack.is_empty() {
+            return self.parse_flow_content(first_char);
+        }
 
         match first_char {
             '-' if self.is_sequence_item() => {
                 self.parse_sequence_item()?;
             }
-            '[' => {
-                self.parse_flow_sequence()?;
-            }
-            '{' => {
-                self.parse_flow_mapping()?;
+            '[' | '{' => {
+                self.parse_flow_start(first_char == '{')?;
             }
             '|' | '>' => {
                 self.parse_block_scalar_start(first_char)?;
@@ -314,6 +440,9 @@ impl<R: BufRead> StreamingYamlParser<R> {
             '*' => {
                 self.parse_alias()?;
             }
+            '"' | '\'' if self.is_quoted_key(first_char) => {
+                self.parse_mapping_entry()?;
+            }
             '"' | '\'' => {
                 self.parse_quoted_scalar(first_char)?;
             }
@@ -323,7 +452,15 @@ impl<R: BufRead> StreamingYamlParser<R> {
             '\n' => {
                 self.buffer.remove(0);
                 self.position.line += 1;
-                self.position.column = 0;
+                self.position.column = 1;
+            }
+            '\r' => {
+                // A `\r\n` pair counts once, at its `\n`
+                self.buffer.remove(0);
+                if !self.buffer.starts_with('\n') {
+                    self.position.line += 1;
+                    self.position.column = 1;
+                }
             }
             _ if self.is_mapping_key() => {
                 self.parse_mapping_entry()?;
@@ -336,64 +473,179 @@ impl<R: BufRead> StreamingYamlParser<R> {
         Ok(())
     }
 
-    /// Check if we need more data to continue parsing
+    /// Check if we need more data to continue parsing: the rest of the
+    /// current line has not been read yet
     fn needs_more_data(&self) -> bool {
-        // If buffer is small and doesn't contain a complete line
-        if self.buffer.len() < 100 && !self.buffer.contains('\n') {
-            return true;
+        !self.exhausted && !self.buffer.trim_end_matches('\r').contains(['\n', '\r'])
+    }
+
+    /// Column of the next character, counted from 0
+    fn current_indent(&self) -> usize {
+        self.position.column.saturating_sub(1)
+    }
+
+    /// Start a node that is not a block collection entry at the current
+    /// column, ending the collections it is less indented than
+    fn begin_node(&mut self) -> Result<()> {
+        if let Some(flow) = self.context.flow_stack.last_mut() {
+            flow.entry = match flow.entry {
+                FlowEntry::Start if flow.mapping => FlowEntry::Key,
+                FlowEntry::Start | FlowEntry::Value => FlowEntry::Done,
+                FlowEntry::Key | FlowEntry::Done => {
+                    return Err(self.fail("expected ',' between flow collection entries"));
+                }
+            };
+            return Ok(());
+        }
+        self.close_deeper(self.current_indent())?;
+        if let Some(collection) = self.context.collection_stack.last_mut() {
+            collection.awaiting_node = false;
+        }
+        Ok(())
+    }
+
+    /// Start a block mapping key or sequence entry at the current column,
+    /// ending and starting collections as its indentation calls for
+    fn begin_entry(&mut self, mapping: bool) -> Result<()> {
+        // A key starts at its anchor when both are on the same line
+        let key_anchor_column = self
+            .context
+            .anchor_position
+            .filter(|position| mapping && position.line == self.position.line)
+            .map(|position| position.column.saturating_sub(1));
+        let indent = key_anchor_column.unwrap_or_else(|| self.current_indent());
+        self.close_deeper(indent)?;
+
+        while let Some(collection) = self.context.collection_stack.last() {
+            if collection.indent < indent {
+                if !collection.awaiting_node {
+                    let entry = if mapping {
+                        "mapping key"
+                    } else {
+                        "sequence entry"
+                    };
+                    return Err(self.fail(format!("unexpected {entry} at this indentation")));
+                }
+                break;
+            }
+
+            // An entry of the innermost collection
+            if collection.mapping == mapping {
+                if collection.awaiting_node {
+                    self.emit_scalar(String::new(), ScalarStyle::Plain)?;
+                }
+                if let Some(collection) = self.context.collection_stack.last_mut() {
+                    collection.awaiting_node = true;
+                }
+                return Ok(());
+            }
+
+            // A sequence may sit at the indentation of the key it belongs to
+            if collection.mapping && collection.awaiting_node {
+                break;
+            }
+            let parent = self.context.collection_stack.iter().rev().nth(1);
+            if !parent.is_some_and(|parent| parent.mapping && parent.indent == indent) {
+                return Err(self.fail("mapping key and sequence entry at the same indentation"));
+            }
+            self.close_collection()?;
         }
 
-        // If we're in a block scalar and need more lines
-        if self.context.in_block_scalar && !self.has_complete_block_scalar() {
-            return true;
+        if let Some(collection) = self.context.collection_stack.last_mut() {
+            collection.awaiting_node = false;
         }
+        if mapping {
+            let key_anchor = key_anchor_column.and_then(|_| self.context.pending_anchor.take());
+            self.emit_mapping_start(false)?;
+            if key_anchor.is_some() {
+                self.context.pending_anchor = key_anchor;
+            }
+        } else {
+            self.emit_sequence_start(false)?;
+        }
+        self.context.collection_stack.push(OpenCollection {
+            mapping,
+            indent,
+            awaiting_node: true,
+        });
+        Ok(())
+    }
 
-        false
+    /// End the collections indented deeper than `indent`
+    fn close_deeper(&mut self, indent: usize) -> Result<()> {
+        while self
+            .context
+            .collection_stack
+            .last()
+            .is_some_and(|collection| collection.indent > indent)
+        {
+            self.close_collection()?;
+        }
+        Ok(())
     }
 
-    /// Check if buffer contains a complete block scalar
-    fn has_complete_block_scalar(&self) -> bool {
-        // Simplified check - in production, would be more sophisticated
-        self.buffer.contains("\n\n") || self.buffer.contains("\n...")
+    /// End the innermost open collection, giving a key or `-` still waiting
+    /// for its node an empty one
+    fn close_collection(&mut self) -> Result<()> {
+        let Some(collection) = self.context.collection_stack.pop() else {
+            return Ok(());
+        };
+        if collection.awaiting_node {
+            self.emit_scalar(String::new(), ScalarStyle::Plain)?;
+        }
+        if collection.mapping {
+            self.emit_mapping_end()
+        } else {
+            self.emit_sequence_end()
+        }
+    }
+
+    /// Stop parsing with an error at the current position
+    fn fail(&mut self, message: impl Into<String>) -> Error {
+        let error = Error::parse(self.position, message);
+        self.state = StreamState::Error(error.to_string());
+        self.stats.errors_encountered += 1;
+        error
     }
 
     /// Parse a sequence item
     fn parse_sequence_item(&mut self) -> Result<()> {
+        self.begin_entry(false)?;
         self.buffer.remove(0); // Remove '-'
         self.position.column += 1;
-
-        // Start sequence if needed
-        if !self.context.collection_stack.iter().any(|&x| !x) {
-            self.emit_sequence_start()?;
-            self.context.collection_stack.push(false);
-        }
-
         self.skip_whitespace();
         Ok(())
     }
 
     /// Parse a mapping entry
     fn parse_mapping_entry(&mut self) -> Result<()> {
-        // Parse key
-        let key_end = self.find_mapping_key_end();
-        if let Some(end) = key_end {
-            let key = self.buffer.drain(..end).collect::<String>();
-            self.position.column += key.len();
+        if self.context.value_indicator_line == Some(self.position.line) {
+            return Err(self.fail("mapping values are not allowed in this context"));
+        }
+        self.begin_entry(true)?;
 
-            // Skip ':' and whitespace
-            if self.buffer.starts_with(':') {
-                self.buffer.remove(0);
-                self.position.column += 1;
+        // Parse key
+        match self.buffer.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                if let Some((key, style)) = self.take_quoted(quote) {
+                    self.emit_scalar(key, style)?;
+                }
+                self.skip_whitespace();
             }
-
-            // Start mapping if needed
-            if !self.context.collection_stack.iter().any(|&x| x) {
-                self.emit_mapping_start()?;
-                self.context.collection_stack.push(true);
+            _ => {
+                if let Some(end) = self.find_mapping_key_end() {
+                    let key = self.buffer.drain(..end).collect::<String>();
+                    self.position.column += key.len();
+                    self.emit_scalar(key.trim().to_string(), ScalarStyle::Plain)?;
+                }
             }
+        }
 
-            // Emit key as scalar
-            self.emit_scalar(key.trim().to_string())?;
+        // Skip ':'
+        if self.buffer.starts_with(':') {
+            self.buffer.remove(0);
+            self.position.column += 1;
+            self.context.value_indicator_line = Some(self.position.line);
         }
 
         Ok(())
@@ -412,7 +664,7 @@ impl<R: BufRead> StreamingYamlParser<R> {
     }
 
     fn skip_comment(&mut self) {
-        if let Some(newline_pos) = self.buffer.find('\n') {
+        if let Some(newline_pos) = self.buffer.find(['\n', '\r']) {
             self.buffer.drain(..newline_pos);
             self.position.column = 0;
         } else {
@@ -421,65 +673,235 @@ impl<R: BufRead> StreamingYamlParser<R> {
     }
 
     fn is_sequence_item(&self) -> bool {
-        self.buffer.starts_with("- ")
+        self.buffer[1..]
+            .chars()
+            .next()
+            .is_none_or(|ch| matches!(ch, ' ' | '\t' | '\n' | '\r'))
     }
 
     fn is_mapping_key(&self) -> bool {
-        // Simplified check for mapping key
-        self.buffer.contains(':') && !self.buffer.starts_with(':')
+        self.find_mapping_key_end().is_some()
     }
 
+    /// Find the `:` value indicator ending a key on the current line, before
+    /// any comment
     fn find_mapping_key_end(&self) -> Option<usize> {
-        self.buffer.find(':')
+        let line_end = self.buffer.find(['\n', '\r']).unwrap_or(self.buffer.len());
+        let line = &self.buffer[..line_end];
+        let mut prev = None;
+        for (i, ch) in line.char_indices() {
+            let next = line[i + ch.len_utf8()..].chars().next();
+            if i > 0 && matches!(ch, ':' | '#') && ends_plain_scalar(ch, prev, next, false) {
+                return (ch == ':').then_some(i);
+            }
+            prev = Some(ch);
+        }
+        None
     }
 
-    fn parse_flow_sequence(&mut self) -> Result<()> {
-        // Simplified flow sequence parsing
-        if let Some(end) = self.buffer.find(']') {
-            let content = self.buffer.drain(..=end).collect::<String>();
-            self.emit_sequence_start()?;
-            // Parse content (simplified)
-            self.emit_sequence_end()?;
-            self.position.column += content.len();
+    /// Check if a quoted scalar on the current line is followed by `:`
+    fn is_quoted_key(&self, quote: char) -> bool {
+        let line_end = self.buffer.find(['\n', '\r']).unwrap_or(self.buffer.len());
+        let line = &self.buffer[..line_end];
+        let Some(close) = line[1..].find(quote) else {
+            return false;
+        };
+        let rest = line[close + 2..].trim_start();
+        rest.starts_with(':')
+            && rest[1..]
+                .chars()
+                .next()
+                .is_none_or(|ch| ch == ' ' || ch == '\t')
+    }
+
+    /// Parse the next token inside a flow collection, where line breaks
+    /// and indentation carry no structure
+    fn parse_flow_content(&mut self, first_char: char) -> Result<()> {
+        match first_char {
+            '[' | '{' => self.parse_flow_start(first_char == '{'),
+            ']' | '}' => self.parse_flow_end(first_char == '}'),
+            ',' => {
+                self.finish_flow_entry()?;
+                self.take_indicator();
+                Ok(())
+            }
+            ':' if ends_plain_scalar(':', None, self.buffer[1..].chars().next(), true) => {
+                self.parse_flow_value_indicator()
+            }
+            '"' | '\'' => self.parse_quoted_scalar(first_char),
+            '&' => self.parse_anchor(),
+            '*' => self.parse_alias(),
+            '#' => {
+                self.skip_comment();
+                Ok(())
+            }
+            '\n' | '\r' => {
+                self.buffer.remove(0);
+                if first_char == '\n' || !self.buffer.starts_with('\n') {
+                    self.position.line += 1;
+                    self.position.column = 1;
+                }
+                Ok(())
+            }
+            '|' | '>' => Err(self.fail("block scalars are not allowed inside flow collections")),
+            _ => self.parse_plain_scalar(),
+        }
+    }
+
+    /// Start a flow sequence at `[` or a flow mapping at `{`
+    fn parse_flow_start(&mut self, mapping: bool) -> Result<()> {
+        self.begin_node()?;
+        if mapping {
+            self.emit_mapping_start(true)?;
+        } else {
+            self.emit_sequence_start(true)?;
         }
+        self.context.flow_stack.push(FlowCollection {
+            mapping,
+            entry: FlowEntry::Start,
+        });
+        self.take_indicator();
         Ok(())
     }
 
-    fn parse_flow_mapping(&mut self) -> Result<()> {
-        // Simplified flow mapping parsing
-        if let Some(end) = self.buffer.find('}') {
-            let content = self.buffer.drain(..=end).collect::<String>();
-            self.emit_mapping_start()?;
-            // Parse content (simplified)
+    /// End the innermost flow collection at `]` or `}`
+    fn parse_flow_end(&mut self, mapping: bool) -> Result<()> {
+        match self.context.flow_stack.last() {
+            Some(flow) if flow.mapping == mapping => {}
+            Some(flow) => {
+                let (found, expected) = if flow.mapping { (']', '}') } else { ('}', ']') };
+                return Err(self.fail(format!(
+                    "unexpected '{found}', expected '{expected}' to close the flow collection"
+                )));
+            }
+            None => unreachable!("flow content is only parsed inside a flow collection"),
+        }
+        self.finish_flow_entry()?;
+        self.context.flow_stack.pop();
+        if mapping {
             self.emit_mapping_end()?;
-            self.position.column += content.len();
+        } else {
+            self.emit_sequence_end()?;
         }
+        self.take_indicator();
         Ok(())
     }
 
-    fn parse_block_scalar_start(&mut self, _indicator: char) -> Result<()> {
-        self.buffer.remove(0); // Remove indicator
-        self.context.in_block_scalar = true;
-        // Parse block scalar header (simplified)
+    /// Read the `:` between a key and its value in a flow mapping
+    fn parse_flow_value_indicator(&mut self) -> Result<()> {
+        let Some(flow) = self.context.flow_stack.last() else {
+            return Ok(());
+        };
+        match (flow.mapping, flow.entry) {
+            (true, FlowEntry::Start) => self.emit_scalar(String::new(), ScalarStyle::Plain)?,
+            (true, FlowEntry::Key) => {}
+            (true, _) => return Err(self.fail("unexpected ':' in flow mapping")),
+            (false, _) => {
+                return Err(self.fail(
+                    "single-pair mappings inside flow sequences are not supported, \
+                     write the pair as '{key: value}'",
+                ));
+            }
+        }
+        if let Some(flow) = self.context.flow_stack.last_mut() {
+            flow.entry = FlowEntry::Value;
+        }
+        self.take_indicator();
         Ok(())
     }
 
-    fn parse_block_scalar_content(&mut self) -> Result<()> {
-        // Simplified block scalar parsing
-        if let Some(end) = self.find_block_scalar_end() {
-            let content = self.buffer.drain(..end).collect::<String>();
-            self.emit_scalar(content)?;
-            self.context.in_block_scalar = false;
+    /// End the current entry of the innermost flow collection at `,` or the
+    /// end of the collection, giving a key without a value an empty one
+    fn finish_flow_entry(&mut self) -> Result<()> {
+        let Some(flow) = self.context.flow_stack.last_mut() else {
+            return Ok(());
+        };
+        let entry = std::mem::replace(&mut flow.entry, FlowEntry::Start);
+        if matches!(entry, FlowEntry::Key | FlowEntry::Value) {
+            self.emit_scalar(String::new(), ScalarStyle::Plain)?;
         }
         Ok(())
     }
 
-    fn find_block_scalar_end(&self) -> Option<usize> {
-        // Simplified - find dedent or document marker
-        self.buffer.find("\n\n").or(self.buffer.find("\n..."))
+    /// Consume the one-character indicator at the start of the buffer
+    fn take_indicator(&mut self) {
+        self.buffer.remove(0);
+        self.position.column += 1;
+    }
+
+    fn parse_block_scalar_start(&mut self, indicator: char) -> Result<()> {
+        self.begin_node()?;
+
+        // The header runs to the end of the line; its content indentation
+        // is always detected from the first non-empty line
+        let line_end = self.buffer.find(['\n', '\r']).unwrap_or(self.buffer.len());
+        let header = self.buffer.drain(..line_end).collect::<String>();
+        self.position.column += header.len();
+        let indicators = header[1..].split([' ', '\t', '#']).next().unwrap_or("");
+        let chomping = if indicators.contains('-') {
+            Chomping::Strip
+        } else if indicators.contains('+') {
+            Chomping::Keep
+        } else {
+            Chomping::Clip
+        };
+        let style = if indicator == '|' {
+            ScalarStyle::Literal
+        } else {
+            ScalarStyle::Folded
+        };
+        self.context.block_scalar = Some((style, chomping));
+        Ok(())
+    }
+
+    /// Read the lines of a block scalar once the line that ends it, or the
+    /// end of the input, is in the buffer
+    fn parse_block_scalar_content(&mut self) -> Result<()> {
+        let Some((style, chomping)) = self.context.block_scalar else {
+            return Ok(());
+        };
+        let min_indent = self
+            .context
+            .collection_stack
+            .last()
+            .map_or(0, |collection| collection.indent + 1);
+
+        // Skip the line break ending the header
+        let mut consumed = match self.buffer.as_bytes() {
+            [b'\r', b'\n', ..] => 2,
+            [b'\n' | b'\r', ..] => 1,
+            _ => 0,
+        };
+        let mut lines = Vec::new();
+        loop {
+            let rest = &self.buffer[consumed..];
+            let (line, next) = match rest.find('\n') {
+                Some(end) => (&rest[..end], consumed + end + 1),
+                None if self.exhausted => (rest, self.buffer.len()),
+                None => return Ok(()),
+            };
+            let line = line.trim_end_matches('\r');
+            let indent = line.len() - line.trim_start_matches(' ').len();
+            let blank = line.trim().is_empty();
+            let marker = indent == 0 && (line.starts_with("---") || line.starts_with("..."));
+            if rest.is_empty() || (!blank && (indent < min_indent || marker)) {
+                break;
+            }
+            lines.push(line);
+            consumed = next;
+        }
+
+        let value = block_scalar_value(&lines, style == ScalarStyle::Folded, chomping);
+        let breaks = self.buffer[..consumed].matches('\n').count();
+        self.buffer.drain(..consumed);
+        self.position.line += breaks;
+        self.position.column = 1;
+        self.context.block_scalar = None;
+        self.emit_scalar(value, style)
     }
 
     fn parse_anchor(&mut self) -> Result<()> {
+        self.context.anchor_position = Some(self.position);
         self.buffer.remove(0); // Remove '&'
         let end = self.find_identifier_end();
         if let Some(end) = end {
@@ -491,6 +913,7 @@ impl<R: BufRead> StreamingYamlParser<R> {
     }
 
     fn parse_alias(&mut self) -> Result<()> {
+        self.begin_node()?;
         self.buffer.remove(0); // Remove '*'
         let end = self.find_identifier_end();
         if let Some(end) = end {
@@ -502,24 +925,37 @@ impl<R: BufRead> StreamingYamlParser<R> {
     }
 
     fn parse_quoted_scalar(&mut self, quote: char) -> Result<()> {
-        self.buffer.remove(0); // Remove opening quote
-        if let Some(end) = self.buffer.find(quote) {
-            let content = self.buffer.drain(..end).collect::<String>();
-            self.buffer.remove(0); // Remove closing quote
-            let content_len = content.len();
-            self.emit_scalar(content)?;
-            self.position.column += content_len + 2;
+        self.begin_node()?;
+        if let Some((content, style)) = self.take_quoted(quote) {
+            self.emit_scalar(content, style)?;
         }
         Ok(())
     }
 
+    /// Take a quoted scalar from the buffer, with its style
+    fn take_quoted(&mut self, quote: char) -> Option<(String, ScalarStyle)> {
+        let end = self.buffer[1..].find(quote)? + 1;
+        let content = self.buffer[1..end].to_string();
+        self.buffer.drain(..=end);
+        self.position.column += content.len() + 2;
+        let style = if quote == '"' {
+            ScalarStyle::DoubleQuoted
+        } else {
+            ScalarStyle::SingleQuoted
+        };
+        Some((content, style))
+    }
+
     fn parse_plain_scalar(&mut self) -> Result<()> {
         let end = self.find_plain_scalar_end();
-        if let Some(end) = end {
-            let content = self.buffer.drain(..end).collect::<String>();
-            self.emit_scalar(content.trim().to_string())?;
-            self.position.column += end;
+        if end == 0 {
+            let ch = self.buffer.chars().next().unwrap_or_default();
+            return Err(self.fail(format!("unexpected '{ch}'")));
         }
+        self.begin_node()?;
+        let content = self.buffer.drain(..end).collect::<String>();
+        self.emit_scalar(content.trim().to_string(), ScalarStyle::Plain)?;
+        self.position.column += end;
         Ok(())
     }
 
@@ -532,134 +968,134 @@ impl<R: BufRead> StreamingYamlParser<R> {
         None
     }
 
-    fn find_plain_scalar_end(&self) -> Option<usize> {
-        // Find end of plain scalar (simplified)
-        for (i, ch) in self.buffer.char_indices() {
-            if ch == '\n' || ch == ':' || ch == '#' {
-                return Some(i);
+    /// Find where the plain scalar starting the buffer ends on its line
+    fn find_plain_scalar_end(&self) -> usize {
+        let in_flow = !self.context.flow_stack.is_empty();
+        let mut prev = None;
+        let mut chars = self.buffer.char_indices().peekable();
+        while let Some((i, ch)) = chars.next() {
+            let next = chars.peek().map(|&(_, next)| next);
+            if matches!(ch, '\n' | '\r') || ends_plain_scalar(ch, prev, next, in_flow) {
+                return i;
             }
+            prev = Some(ch);
         }
-        Some(self.buffer.len())
+        self.buffer.len()
+    }
+
+    /// Queue an event, checking it for a repeated key first
+    fn push_event(&mut self, event: Event) -> Result<()> {
+        if let Some(ref mut duplicate_keys) = self.duplicate_keys {
+            if let Err(error) = duplicate_keys.check(&event) {
+                self.state = StreamState::Error(error.to_string());
+                self.stats.errors_encountered += 1;
+                return Err(error);
+            }
+        }
+        self.events.push_back(event);
+        self.stats.events_generated += 1;
+        Ok(())
     }
 
     /// Event emission methods
     fn emit_stream_start(&mut self) -> Result<()> {
-        self.events.push_back(Event {
+        self.push_event(Event {
             event_type: EventType::StreamStart,
             position: self.position,
-        });
-        self.stats.events_generated += 1;
-        Ok(())
+        })
     }
 
     fn emit_stream_end(&mut self) -> Result<()> {
-        self.events.push_back(Event {
+        self.push_event(Event {
             event_type: EventType::StreamEnd,
             position: self.position,
-        });
-        self.stats.events_generated += 1;
-        Ok(())
+        })
     }
 
-    fn emit_document_start(&mut self) -> Result<()> {
-        self.events.push_back(Event {
+    fn emit_document_start(&mut self, implicit: bool) -> Result<()> {
+        self.push_event(Event {
             event_type: EventType::DocumentStart {
                 version: None,
                 tags: Vec::new(),
-                implicit: false,
+                implicit,
             },
             position: self.position,
-        });
-        self.stats.events_generated += 1;
+        })?;
         self.stats.documents_parsed += 1;
         Ok(())
     }
 
-    fn emit_document_end(&mut self) -> Result<()> {
-        self.events.push_back(Event {
-            event_type: EventType::DocumentEnd { implicit: false },
+    fn emit_document_end(&mut self, implicit: bool) -> Result<()> {
+        self.push_event(Event {
+            event_type: EventType::DocumentEnd { implicit },
             position: self.position,
-        });
-        self.stats.events_generated += 1;
-        Ok(())
+        })
     }
 
-    fn emit_sequence_start(&mut self) -> Result<()> {
+    fn emit_sequence_start(&mut self, flow_style: bool) -> Result<()> {
         let anchor = self.context.pending_anchor.take();
         let tag = self.context.pending_tag.take();
 
-        self.events.push_back(Event {
+        self.push_event(Event {
             event_type: EventType::SequenceStart {
                 anchor,
                 tag,
-                flow_style: false,
+                flow_style,
             },
             position: self.position,
-        });
-        self.stats.events_generated += 1;
-        Ok(())
+        })
     }
 
     fn emit_sequence_end(&mut self) -> Result<()> {
-        self.events.push_back(Event {
+        self.push_event(Event {
             event_type: EventType::SequenceEnd,
             position: self.position,
-        });
-        self.stats.events_generated += 1;
-        Ok(())
+        })
     }
 
-    fn emit_mapping_start(&mut self) -> Result<()> {
+    fn emit_mapping_start(&mut self, flow_style: bool) -> Result<()> {
         let anchor = self.context.pending_anchor.take();
         let tag = self.context.pending_tag.take();
 
-        self.events.push_back(Event {
+        self.push_event(Event {
             event_type: EventType::MappingStart {
                 anchor,
                 tag,
-                flow_style: false,
+                flow_style,
             },
             position: self.position,
-        });
-        self.stats.events_generated += 1;
-        Ok(())
+        })
     }
 
     fn emit_mapping_end(&mut self) -> Result<()> {
-        self.events.push_back(Event {
+        self.push_event(Event {
             event_type: EventType::MappingEnd,
             position: self.position,
-        });
-        self.stats.events_generated += 1;
-        Ok(())
+        })
     }
 
-    fn emit_scalar(&mut self, value: String) -> Result<()> {
+    fn emit_scalar(&mut self, value: String, style: ScalarStyle) -> Result<()> {
         let anchor = self.context.pending_anchor.take();
         let tag = self.context.pending_tag.take();
 
-        self.events.push_back(Event {
+        self.push_event(Event {
             event_type: EventType::Scalar {
                 value,
                 anchor,
                 tag,
-                style: crate::parser::ScalarStyle::Plain,
-                plain_implicit: true,
-                quoted_implicit: true,
+                style,
+                plain_implicit: style == ScalarStyle::Plain,
+                quoted_implicit: style != ScalarStyle::Plain,
             },
             position: self.position,
-        });
-        self.stats.events_generated += 1;
-        Ok(())
+        })
     }
 
     fn emit_alias(&mut self, anchor: String) -> Result<()> {
-        self.events.push_back(Event {
+        self.push_event(Event {
             event_type: EventType::Alias { anchor },
             position: self.position,
-        });
-        self.stats.events_generated += 1;
-        Ok(())
+        })
     }
 
     /// Get the next event if available
@@ -688,27 +1124,68 @@ impl<R: BufRead> Iterator for StreamingYamlParser<R> {
     type Item = Result<Event>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        // Try to get an event from the buffer
-        if let Some(event) = self.next_event() {
-            return Some(Ok(event));
-        }
-
-        // Parse more data if needed
-        match self.parse_next() {
-            Ok(true) => self.next_event().map(Ok),
-            Ok(false) if self.state == StreamState::EndOfStream => {
-                if !self.events.is_empty() {
-                    self.next_event().map(Ok)
-                } else {
-                    None
-                }
+        loop {
+            // Try to get an event from the buffer
+            if let Some(event) = self.next_event() {
+                return Some(Ok(event));
+            }
+
+            if matches!(self.state, StreamState::EndOfStream | StreamState::Error(_)) {
+                return None;
+            }
+
+            // Parse more data if needed; events queued before an error are
+            // dropped with the rest of the stream
+            if let Err(e) = self.parse_next() {
+                self.events.clear();
+                return Some(Err(e));
             }
-            Ok(false) => None,
-            Err(e) => Some(Err(e)),
         }
     }
 }
 
+/// The value of a block scalar from its content lines, folding line breaks
+/// between lines of text when `folded`
+fn block_scalar_value(lines: &[&str], folded: bool, chomping: Chomping) -> String {
+    let indent = lines
+        .iter()
+        .find(|line| !line.trim().is_empty())
+        .map_or(0, |line| line.len() - line.trim_start_matches(' ').len());
+
+    let mut value = String::new();
+    let mut empty_lines = 0;
+    let mut previous_more_indented = None;
+    for line in lines {
+        let line = line.get(indent..).unwrap_or("");
+        if line.trim().is_empty() {
+            empty_lines += 1;
+            continue;
+        }
+        let more_indented = line.starts_with([' ', '\t']);
+        match previous_more_indented {
+            Some(false) if folded && !more_indented && empty_lines == 0 => value.push(' '),
+            Some(false) if folded && !more_indented => {}
+            Some(_) => value.push('\n'),
+            None => {}
+        }
+        value.push_str(&"\n".repeat(empty_lines));
+        value.push_str(line);
+        empty_lines = 0;
+        previous_more_indented = Some(more_indented);
+    }
+
+    match chomping {
+        Chomping::Strip => {}
+        Chomping::Clip if previous_more_indented.is_some() => value.push('\n'),
+        Chomping::Clip => {}
+        Chomping::Keep => {
+            let breaks = empty_lines + usize::from(previous_more_indented.is_some());
+            value.push_str(&"\n".repeat(breaks));
+        }
+    }
+    value
+}
+
 /// Create a streaming parser from a file path
 pub fn stream_from_file<P: AsRef<Path>>(
     path: P,
@@ -783,4 +1260,240 @@ mod tests {
 
         assert!(events.len() > 0);
     }
+
+    #[test]
+    fn test_implicit_documents_match_basic_parser() {
+        let yaml = crate::Yaml::new();
+        for input in [
+            "",
+            "a: 1\n",
+            "a: 1\n---\nb: 2\n",
+            "a: 1\n...\n---\nb: 2",
+            "# leading\n\na: 1\n---\nb: 2\n...\n",
+            "---\na: 1\n---\nb: 2\n",
+        ] {
+            let trace: String = stream_from_string(input.to_string(), StreamConfig::default())
+                .map(|event| event.unwrap().to_test_format() + "\n")
+                .collect();
+            assert_eq!(trace, yaml.event_trace(input).unwrap(), "input: {input:?}");
+        }
+    }
+
+    #[test]
+    fn test_quoted_scalar_styles() {
+        let parser = stream_from_string("a: ''\nb: \"\"\n".to_string(), StreamConfig::default());
+        let scalars: Vec<_> = parser
+            .filter_map(|event| match event.unwrap().event_type {
+                EventType::Scalar { value, style, .. } => Some((value, style)),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            scalars,
+            vec![
+                ("a".to_string(), ScalarStyle::Plain),
+                (String::new(), ScalarStyle::SingleQuoted),
+                ("b".to_string(), ScalarStyle::Plain),
+                (String::new(), ScalarStyle::DoubleQuoted),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reject_duplicate_keys() {
+        let config = StreamConfig {
+            reject_duplicate_keys: true,
+            ..StreamConfig::default()
+        };
+        let drain = |input: &str| {
+            stream_from_string(input.to_string(), config.clone()).collect::<Result<Vec<_>>>()
+        };
+
+        let error = drain("a: 1\nb: 2\na: 3\n").unwrap_err();
+        assert!(error.to_string().contains("Duplicate key 'a'"), "{error}");
+        assert!(drain("a: 1\n'a': 2\n").is_err());
+        assert!(drain("a:\n  b: 1\n  b: 2\n").is_err());
+        assert!(drain("a:\n  b: 1\nc:\n  b: 2\n").is_ok());
+
+        assert!(drain("a: 1\nb: 1\n").is_ok());
+        assert!(drain("a: 1\n---\na: 2\n").is_ok());
+        assert!(
+            stream_from_string("a: 1\na: 2\n".to_string(), StreamConfig::default())
+                .collect::<Result<Vec<_>>>()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_nested_block_events_match_basic_parser() {
+        let yaml = crate::Yaml::new();
+        for input in [
+            "a:\n  b: 1\n  c: 2\nd: 3\n",
+            "a:\n  b:\n    c: 1\nd: 2\n",
+            "a:\n  - x\n  - y\nb: z\n",
+            "a:\nb:\n  c:\n",
+            "a: &x\n  b: 1\nc: *x\n",
+            "url: http://example.com # comment\nport: 80\n",
+            "a: |\n  line 1\n\n  line 2\nb: >-\n  folded\n  text\n\n  more\nc: 1\n",
+            "keep: |+\n  text\n\nnext:\n  - >\n    a\n     b\n    c\n",
+        ] {
+            let expected = yaml.event_trace(input).unwrap();
+            for chunk_size in [3, 8 * 1024] {
+                let config = StreamConfig {
+                    chunk_size,
+                    ..StreamConfig::default()
+                };
+                let trace: String = stream_from_string(input.to_string(), config)
+                    .map(|event| event.unwrap().to_test_format() + "\n")
+                    .collect();
+                assert_eq!(trace, expected, "input: {input:?}, chunk size {chunk_size}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_nested_sequence_events() {
+        for (input, expected) in [
+            (
+                "a:\n- x\n- y\nb: z\n",
+                "+MAP\n=VAL :a\n+SEQ\n=VAL :x\n=VAL :y\n-SEQ\n=VAL :b\n=VAL :z\n-MAP\n",
+            ),
+            (
+                "- a: 1\n  b: 2\n- c: 3\n",
+                "+SEQ\n+MAP\n=VAL :a\n=VAL :1\n=VAL :b\n=VAL :2\n-MAP\n\
+                 +MAP\n=VAL :c\n=VAL :3\n-MAP\n-SEQ\n",
+            ),
+            (
+                "- - x\n  - y\n- z\n",
+                "+SEQ\n+SEQ\n=VAL :x\n=VAL :y\n-SEQ\n=VAL :z\n-SEQ\n",
+            ),
+            (
+                "-\n  a: 1\n-\n",
+                "+SEQ\n+MAP\n=VAL :a\n=VAL :1\n-MAP\n=VAL :\n-SEQ\n",
+            ),
+            (
+                "- \"quoted\": 'value'\n  'other': 2\n",
+                "+SEQ\n+MAP\n=VAL \"quoted\n=VAL 'value\n=VAL 'other\n=VAL :2\n-MAP\n-SEQ\n",
+            ),
+        ] {
+            let trace: String = stream_from_string(input.to_string(), StreamConfig::default())
+                .map(|event| event.unwrap().to_test_format() + "\n")
+                .collect();
+            assert_eq!(
+                trace,
+                format!("+STR\n+DOC\n{expected}-DOC\n-STR\n"),
+                "input: {input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_flow_collection_events() {
+        for (input, expected) in [
+            (
+                "a: [b:c, http://x.com:80/p#f, 'q', d #note\n  ]\n",
+                "+MAP\n=VAL :a\n+SEQ []\n=VAL :b:c\n=VAL :http://x.com:80/p#f\n=VAL 'q\n\
+                 =VAL :d\n-SEQ\n-MAP\n",
+            ),
+            (
+                "{k:v, a: [1, 2], b: {c: d}, e, : f, g: }\n",
+                "+MAP {}\n=VAL :k:v\n=VAL :\n=VAL :a\n+SEQ []\n=VAL :1\n=VAL :2\n-SEQ\n\
+                 =VAL :b\n+MAP {}\n=VAL :c\n=VAL :d\n-MAP\n=VAL :e\n=VAL :\n=VAL :\n=VAL :f\n\
+                 =VAL :g\n=VAL :\n-MAP\n",
+            ),
+            (
+                "- [a, b]\n- {c: &x 1, d: *x}\n",
+                "+SEQ\n+SEQ []\n=VAL :a\n=VAL :b\n-SEQ\n+MAP {}\n=VAL :c\n=VAL &x :1\n\
+                 =VAL :d\n=ALI *x\n-MAP\n-SEQ\n",
+            ),
+            (
+                "a: [\n  1,\n  2,\n]\nb: c,d\n",
+                "+MAP\n=VAL :a\n+SEQ []\n=VAL :1\n=VAL :2\n-SEQ\n=VAL :b\n=VAL :c,d\n-MAP\n",
+            ),
+        ] {
+            for chunk_size in [3, 8 * 1024] {
+                let config = StreamConfig {
+                    chunk_size,
+                    ..StreamConfig::default()
+                };
+                let trace: String = stream_from_string(input.to_string(), config)
+                    .map(|event| event.unwrap().to_test_format() + "\n")
+                    .collect();
+                assert_eq!(
+                    trace,
+                    format!("+STR\n+DOC\n{expected}-DOC\n-STR\n"),
+                    "input: {input:?}, chunk size {chunk_size}"
+                );
+            }
+        }
+
+        let error = |input: &str| {
+            stream_from_string(input.to_string(), StreamConfig::default())
+                .collect::<Result<Vec<_>>>()
+                .unwrap_err()
+                .to_string()
+        };
+        assert!(error("a: [1, 2\n").contains("unclosed flow collection"));
+        assert!(error("a: [1}\n").contains("expected ']'"));
+        assert!(error("{a: 'x' b}\n").contains("expected ','"));
+    }
+
+    #[test]
+    fn test_anchors_on_keys() {
+        let yaml = crate::Yaml::new();
+        for input in [
+            "&a key: value\nother: *a\n",
+            "- &a x: 1\n  y: *a\n",
+            "&m\na: 1\n",
+            "a: &x\n  b: 1\nc: *x\n",
+            "{&k a: &v 1, b: *k}\n",
+        ] {
+            let trace: String = stream_from_string(input.to_string(), StreamConfig::default())
+                .map(|event| event.unwrap().to_test_format() + "\n")
+                .collect();
+            assert_eq!(trace, yaml.event_trace(input).unwrap(), "input: {input:?}");
+        }
+    }
+
+    #[test]
+    fn test_colons_in_plain_scalars() {
+        let yaml = crate::Yaml::new();
+        for input in [
+            "url: http://host:8080/path\n",
+            "time: 12:30:45\n",
+            "a:b: c\n",
+            "k: ::vector\n",
+            "a: b #c: d\n",
+        ] {
+            let trace: String = stream_from_string(input.to_string(), StreamConfig::default())
+                .map(|event| event.unwrap().to_test_format() + "\n")
+                .collect();
+            assert_eq!(trace, yaml.event_trace(input).unwrap(), "input: {input:?}");
+        }
+
+        for input in ["key: value: extra\n", "- a: 'b': c\n"] {
+            let error = stream_from_string(input.to_string(), StreamConfig::default())
+                .collect::<Result<Vec<_>>>()
+                .unwrap_err();
+            assert!(error.to_string().contains("mapping values are not allowed"));
+        }
+    }
+
+    #[test]
+    fn test_inconsistent_indentation() {
+        let events = |input: &str| {
+            stream_from_string(input.to_string(), StreamConfig::default())
+                .collect::<Result<Vec<_>>>()
+        };
+
+        assert!(events("a: 1\n  b: 2\n").is_err());
+        assert!(events("- x\nb: 2\n").is_err());
+
+        // Parsing stops at the first error
+        let mut parser =
+            stream_from_string("a: 1\n  b: 2\nc: 3\n".to_string(), StreamConfig::default());
+        assert!(parser.by_ref().any(|event| event.is_err()));
+        assert!(parser.next().is_none());
+    }
 }
@@ -0,0 +1,118 @@
+//! Emission hints for parts of a plain value tree
+//!
+//! A [`StyleMap`] attaches a style to the nodes at given paths, so a value
+//! built in code can be dumped with, say, one long string as a literal block
+//! and one mapping inline, without going through the round-trip loader:
+//!
+//! ```
+//! use rust_yaml::{yaml, FlowStyle, StyleMap, Yaml};
+//! use rust_yaml::parser::ScalarStyle;
+//!
+//! let value = yaml!({"ports": [80, 443], "script": "make\nmake install\n"});
+//! let mut styles = StyleMap::new();
+//! styles.set("ports", FlowStyle::Flow).unwrap();
+//! styles.set("script", ScalarStyle::Literal).unwrap();
+//!
+//! let dumped = Yaml::new().dump_str_with_styles(&value, &styles).unwrap();
+//! assert_eq!(dumped, "ports: [80, 443]\nscript: |\n  make\n  make install\n");
+//! ```
+
+use crate::parser::ScalarStyle;
+use crate::scanner::QuoteStyle;
+use crate::{BlockScalarStyle, FlowStyle, Path, Result, Style};
+use std::collections::HashMap;
+
+/// The style of one node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeStyle {
+    /// How a sequence or mapping is written; [`FlowStyle::Auto`] inlines it
+    /// when it holds only scalars and fits within the width
+    Collection(FlowStyle),
+    /// How a scalar is written; quotes are still added to a plain string that
+    /// would not read back as the same string, and a string a block scalar
+    /// cannot hold keeps its quotes
+    Scalar(ScalarStyle),
+}
+
+impl From<FlowStyle> for NodeStyle {
+    fn from(style: FlowStyle) -> Self {
+        Self::Collection(style)
+    }
+}
+
+impl From<ScalarStyle> for NodeStyle {
+    fn from(style: ScalarStyle) -> Self {
+        Self::Scalar(style)
+    }
+}
+
+/// Styles for the nodes of a value at given paths, used by
+/// [`Yaml::dump_str_with_styles`](crate::Yaml::dump_str_with_styles)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StyleMap {
+    styles: HashMap<Path, NodeStyle>,
+}
+
+impl StyleMap {
+    /// Create an empty style map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the style of the node at `path`, in the dotted form such as
+    /// `metadata.annotations` or `spec.containers[0].command`
+    pub fn set(&mut self, path: &str, style: impl Into<NodeStyle>) -> Result<()> {
+        self.set_path(path.parse()?, style);
+        Ok(())
+    }
+
+    /// Set the style of the node at `path`
+    pub fn set_path(&mut self, path: Path, style: impl Into<NodeStyle>) {
+        self.styles.insert(path, style.into());
+    }
+
+    /// The style set for the node at `path`
+    pub fn get(&self, path: &Path) -> Option<NodeStyle> {
+        self.styles.get(path).copied()
+    }
+
+    /// Remove the style of the node at `path`
+    pub fn remove(&mut self, path: &Path) -> Option<NodeStyle> {
+        self.styles.remove(path)
+    }
+
+    /// Number of nodes with a style
+    pub fn len(&self) -> usize {
+        self.styles.len()
+    }
+
+    /// Check if no styles are set
+    pub fn is_empty(&self) -> bool {
+        self.styles.is_empty()
+    }
+
+    /// The collection style set for the node at `path`
+    pub(crate) fn flow_style(&self, path: &Path) -> Option<FlowStyle> {
+        match self.get(path)? {
+            NodeStyle::Collection(style) => Some(style),
+            NodeStyle::Scalar(_) => None,
+        }
+    }
+
+    /// The scalar style set for the node at `path`, as the [`Style`] the
+    /// emitter writes scalars with
+    pub(crate) fn scalar_style(&self, path: &Path) -> Option<Style> {
+        let NodeStyle::Scalar(scalar) = self.get(path)? else {
+            return None;
+        };
+        let mut style = Style::new();
+        match scalar {
+            ScalarStyle::Plain => style.quote_style = Some(QuoteStyle::Plain),
+            ScalarStyle::SingleQuoted => style.quote_style = Some(QuoteStyle::Single),
+            ScalarStyle::DoubleQuoted => style.quote_style = Some(QuoteStyle::Double),
+            ScalarStyle::Literal => style.block_scalar = Some(BlockScalarStyle::literal()),
+            ScalarStyle::Folded => style.block_scalar = Some(BlockScalarStyle::folded()),
+        }
+        Some(style)
+    }
+}
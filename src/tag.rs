@@ -3,9 +3,10 @@
 //! This module implements the full YAML 1.2 tag resolution mechanism,
 //! including support for custom tag handlers and schema validation.
 
-use crate::{Error, Result, Value};
+use crate::{Error, Position, Result, Value};
 use std::collections::HashMap;
 use std::fmt;
+use std::sync::Arc;
 
 /// Tag handle types as defined in YAML 1.2 spec
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -65,12 +66,144 @@ pub enum TagKind {
     Custom(String),
 }
 
+/// Registry of custom tag handlers, keyed by tag URI
+///
+/// Clones share the registered handlers until one of them registers another,
+/// so a registry configured once can be handed to every load, from any thread.
+#[derive(Clone, Default)]
+pub struct TagHandlers {
+    handlers: Arc<HashMap<String, Arc<dyn TagHandler>>>,
+}
+
+impl TagHandlers {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a handler for values tagged with `tag_uri`
+    pub fn register(&mut self, tag_uri: impl Into<String>, handler: impl TagHandler + 'static) {
+        self.insert(tag_uri.into(), Arc::new(handler));
+    }
+
+    fn insert(&mut self, tag_uri: String, handler: Arc<dyn TagHandler>) {
+        Arc::make_mut(&mut self.handlers).insert(tag_uri, handler);
+    }
+
+    /// Get the handler registered for `tag_uri`
+    pub fn get(&self, tag_uri: &str) -> Option<&dyn TagHandler> {
+        self.handlers.get(tag_uri).map(Arc::as_ref)
+    }
+
+    /// Number of registered handlers
+    pub fn len(&self) -> usize {
+        self.handlers.len()
+    }
+
+    /// Check if no handlers are registered
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+}
+
+impl fmt::Debug for TagHandlers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut tags: Vec<_> = self.handlers.keys().collect();
+        tags.sort();
+        f.debug_set().entries(tags).finish()
+    }
+}
+
+/// Characters other than ASCII letters and digits allowed in the suffix of
+/// a shorthand tag such as `!e!suffix`
+const TAG_CHARS: &str = "-#;/?:@&=+$_.~*'()";
+
+/// Characters other than ASCII letters and digits allowed in a verbatim tag
+/// or a full tag URI
+const URI_CHARS: &str = "-#;/?:@&=+$,_.!~*'()[]";
+
+/// Whether `suffix` can follow a tag handle as written, without escapes
+pub(crate) fn is_shorthand_suffix(suffix: &str) -> bool {
+    !suffix.is_empty()
+        && suffix
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || TAG_CHARS.contains(c))
+}
+
+/// Split a shorthand tag into its handle (`!`, `!!` or `!name!`) and suffix
+fn split_shorthand(tag_str: &str) -> (&str, &str) {
+    if tag_str.starts_with("!!") {
+        return tag_str.split_at(2);
+    }
+    let name = tag_str[1..]
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+        .map_or(tag_str.len(), |n| n + 1);
+    if name > 1 && tag_str[name..].starts_with('!') {
+        tag_str.split_at(name + 1)
+    } else {
+        tag_str.split_at(1)
+    }
+}
+
+/// Decode the `%xx` escapes of `text`, part of the tag `tag`, checking that
+/// it only holds ASCII letters, digits and the characters of `allowed`
+fn decode_uri(text: &str, allowed: &str, tag: &str, position: Position) -> Result<String> {
+    let invalid = |message: String| Error::construction(position, message);
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut chars = text.char_indices();
+    while let Some((index, c)) = chars.next() {
+        if c == '%' {
+            let byte = text
+                .get(index + 1..index + 3)
+                .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| {
+                    invalid(format!(
+                        "invalid escape in tag '{}': '%' must be followed by two hex digits",
+                        tag
+                    ))
+                })?;
+            bytes.push(byte);
+            chars.nth(1);
+        } else if c.is_ascii_alphanumeric() || allowed.contains(c) {
+            bytes.push(c as u8);
+        } else {
+            return Err(invalid(format!(
+                "invalid character '{}' in tag '{}'; escape it as %xx",
+                c.escape_default(),
+                tag
+            )));
+        }
+    }
+    String::from_utf8(bytes)
+        .map_err(|_| invalid(format!("escapes in tag '{}' are not valid UTF-8", tag)))
+}
+
+/// How a named tag handle such as `!e!` without a matching `%TAG`
+/// directive is resolved
+///
+/// The YAML spec makes an undeclared named handle an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum UnknownHandle {
+    /// Fail to load the document
+    #[default]
+    Error,
+    /// Resolve the whole tag against the primary handle `!`, so `!e!foo`
+    /// becomes the primary prefix followed by `e!foo`
+    FallbackPrimary,
+    /// Keep the tag as written, such as `!e!foo`
+    KeepVerbatim,
+}
+
 /// Tag resolution context
+#[derive(Clone)]
 pub struct TagResolver {
     /// Tag directives (handle -> prefix)
     directives: HashMap<String, String>,
+    /// How named handles without a directive are resolved
+    unknown_handle: UnknownHandle,
     /// Custom tag handlers
-    handlers: HashMap<String, Box<dyn TagHandler>>,
+    handlers: TagHandlers,
     /// Default schema
     schema: Schema,
 }
@@ -81,6 +214,7 @@ impl fmt::Debug for TagResolver {
             .field("directives", &self.directives)
             .field("handlers_count", &self.handlers.len())
             .field("schema", &self.schema)
+            .field("unknown_handle", &self.unknown_handle)
             .finish()
     }
 }
@@ -95,7 +229,8 @@ impl TagResolver {
     pub fn with_schema(schema: Schema) -> Self {
         let mut resolver = Self {
             directives: HashMap::new(),
-            handlers: HashMap::new(),
+            unknown_handle: UnknownHandle::default(),
+            handlers: TagHandlers::new(),
             schema,
         };
 
@@ -122,57 +257,65 @@ impl TagResolver {
             .insert("!!".to_string(), "tag:yaml.org,2002:".to_string());
     }
 
+    /// Set how named handles without a `%TAG` directive are resolved
+    pub fn set_unknown_handle(&mut self, unknown_handle: UnknownHandle) {
+        self.unknown_handle = unknown_handle;
+    }
+
     /// Register a custom tag handler
     pub fn register_handler(&mut self, tag_uri: String, handler: Box<dyn TagHandler>) {
-        self.handlers.insert(tag_uri, handler);
+        self.handlers.insert(tag_uri, Arc::from(handler));
+    }
+
+    /// Whether a registered handler constructs values with `tag`
+    pub fn has_handler(&self, tag: &Tag) -> bool {
+        self.handlers.get(&tag.uri).is_some()
+    }
+
+    /// Replace the custom tag handlers with a shared registry
+    pub fn set_handlers(&mut self, handlers: TagHandlers) {
+        self.handlers = handlers;
     }
 
     /// Resolve a tag string to a full Tag
+    ///
+    /// `%xx` escapes in the suffix of a shorthand tag, a verbatim tag or a
+    /// full URI are decoded; a malformed escape or a character a tag may not
+    /// contain is an error.
     pub fn resolve(&self, tag_str: &str) -> Result<Tag> {
+        self.resolve_at(tag_str, Position::start())
+    }
+
+    /// Resolve a tag string to a full Tag, reporting errors at `position`
+    pub fn resolve_at(&self, tag_str: &str, position: Position) -> Result<Tag> {
         let (uri, original) = if tag_str.starts_with("tag:") {
             // Already a full URI
-            (tag_str.to_string(), tag_str.to_string())
+            (
+                decode_uri(tag_str, URI_CHARS, tag_str, position)?,
+                tag_str.to_string(),
+            )
         } else if tag_str.starts_with("!<") && tag_str.ends_with('>') {
             // Verbatim tag
-            let uri = tag_str[2..tag_str.len() - 1].to_string();
+            let uri = decode_uri(&tag_str[2..tag_str.len() - 1], URI_CHARS, tag_str, position)?;
             (uri, tag_str.to_string())
-        } else if tag_str.starts_with("!!") {
-            // Secondary handle
-            let suffix = &tag_str[2..];
-            let prefix = self
-                .directives
-                .get("!!")
-                .cloned()
-                .unwrap_or_else(|| "tag:yaml.org,2002:".to_string());
-            (format!("{}{}", prefix, suffix), tag_str.to_string())
         } else if tag_str.starts_with('!') {
-            // Check for named handle
-            if let Some(end) = tag_str[1..].find('!') {
-                let handle_name = &tag_str[1..end + 1];
-                let handle = format!("!{}!", handle_name);
-                let suffix = &tag_str[end + 2..];
-
-                if let Some(prefix) = self.directives.get(&handle) {
-                    (format!("{}{}", prefix, suffix), tag_str.to_string())
-                } else {
-                    // Unknown named handle, treat as primary
-                    let prefix = self
-                        .directives
-                        .get("!")
-                        .cloned()
-                        .unwrap_or_else(|| "!".to_string());
-                    (format!("{}{}", prefix, &tag_str[1..]), tag_str.to_string())
-                }
-            } else {
-                // Primary handle
-                let suffix = &tag_str[1..];
-                let prefix = self
-                    .directives
-                    .get("!")
-                    .cloned()
-                    .unwrap_or_else(|| "!".to_string());
-                (format!("{}{}", prefix, suffix), tag_str.to_string())
-            }
+            // Shorthand tag with a primary, secondary or named handle
+            let (handle, suffix) = split_shorthand(tag_str);
+            let suffix = decode_uri(suffix, TAG_CHARS, tag_str, position)?;
+            let uri = match self.directives.get(handle) {
+                Some(prefix) => format!("{}{}", prefix, suffix),
+                None => match self.unknown_handle {
+                    UnknownHandle::Error => {
+                        return Err(Self::unknown_handle_error(handle, tag_str, position));
+                    }
+                    UnknownHandle::FallbackPrimary => {
+                        let prefix = self.directives.get("!").map_or("!", String::as_str);
+                        format!("{}{}{}", prefix, &handle[1..], suffix)
+                    }
+                    UnknownHandle::KeepVerbatim => tag_str.to_string(),
+                },
+            };
+            (uri, tag_str.to_string())
         } else {
             // No tag prefix, use implicit tagging based on schema
             (
@@ -190,6 +333,39 @@ impl TagResolver {
         })
     }
 
+    /// Expand the handle of a tag as written, checking its suffix but
+    /// leaving `%xx` escapes in place so that [`TagResolver::resolve`] reads
+    /// the result back to the same tag
+    ///
+    /// Returns `None` for a named handle without a directive, which is left
+    /// to the unknown handle policy of whoever resolves the tag.
+    pub(crate) fn expand(&self, tag_str: &str, position: Position) -> Result<Option<String>> {
+        if tag_str.starts_with("!<") && tag_str.ends_with('>') {
+            let uri = &tag_str[2..tag_str.len() - 1];
+            decode_uri(uri, URI_CHARS, tag_str, position)?;
+            return Ok(Some(uri.to_string()));
+        }
+        if !tag_str.starts_with('!') {
+            return Ok(Some(tag_str.to_string()));
+        }
+        let (handle, suffix) = split_shorthand(tag_str);
+        decode_uri(suffix, TAG_CHARS, tag_str, position)?;
+        Ok(self
+            .directives
+            .get(handle)
+            .map(|prefix| format!("{}{}", prefix, suffix)))
+    }
+
+    fn unknown_handle_error(handle: &str, tag_str: &str, position: Position) -> Error {
+        Error::construction(
+            position,
+            format!(
+                "undefined tag handle '{}' in tag '{}'; declare it with a %TAG directive",
+                handle, tag_str
+            ),
+        )
+    }
+
     /// Identify the kind of tag from its URI
     fn identify_tag_kind(uri: &str) -> TagKind {
         match uri {
@@ -209,7 +385,10 @@ impl TagResolver {
         }
     }
 
-    /// Apply a tag to a value
+    /// Apply a tag to a scalar
+    ///
+    /// Application tags with no registered handler are kept by wrapping the
+    /// string in [`Value::Tagged`].
     pub fn apply_tag(&self, tag: &Tag, value: &str) -> Result<Value> {
         // Check for custom handler first
         if let Some(handler) = self.handlers.get(&tag.uri) {
@@ -225,10 +404,22 @@ impl TagResolver {
             TagKind::Str => Ok(Value::String(value.to_string())),
             TagKind::Binary => self.construct_binary(value),
             TagKind::Timestamp => self.construct_timestamp(value),
+            TagKind::Custom(uri) => Ok(Value::tagged(uri, Value::String(value.to_string()))),
             _ => Ok(Value::String(value.to_string())), // Default to string
         }
     }
 
+    /// Apply a tag to a composed sequence or mapping
+    ///
+    /// Application tags are kept by wrapping the collection in
+    /// [`Value::Tagged`]; the core collection tags leave it as it is.
+    pub fn apply_collection_tag(&self, tag: &Tag, value: Value) -> Value {
+        match &tag.kind {
+            TagKind::Custom(uri) => Value::tagged(uri, value),
+            _ => value,
+        }
+    }
+
     /// Construct a boolean from a tagged value
     fn construct_bool(&self, value: &str) -> Result<Value> {
         match value.to_lowercase().as_str() {
@@ -454,6 +645,59 @@ mod tests {
         assert_eq!(tag.uri, "tag:explicit.com,2024:type");
     }
 
+    #[test]
+    fn test_uri_escapes() {
+        let mut resolver = TagResolver::new();
+        resolver.add_directive("!e!".to_string(), "tag:example.com,2024:".to_string());
+        assert_eq!(
+            resolver.resolve("!e!a%2Fb%7E").unwrap().uri,
+            "tag:example.com,2024:a/b~"
+        );
+        assert_eq!(resolver.resolve("!caf%C3%A9").unwrap().uri, "!café");
+        assert_eq!(
+            resolver.resolve("!<tag:x.com,2024:a%21>").unwrap().uri,
+            "tag:x.com,2024:a!"
+        );
+        // Expanding keeps the escapes, so the result resolves to the same tag
+        let expanded = resolver
+            .expand("!e%21x", Position::start())
+            .unwrap()
+            .unwrap();
+        assert_eq!(expanded, "!e%21x");
+        assert_eq!(resolver.resolve(&expanded).unwrap().uri, "!e!x");
+
+        for (tag, message) in [
+            ("!bad%2", "'%' must be followed by two hex digits"),
+            ("!bad%zz", "'%' must be followed by two hex digits"),
+            ("!bad%FF", "not valid UTF-8"),
+            ("!b<ad", "invalid character '<' in tag '!b<ad'"),
+            ("!e!a!b", "invalid character '!'"),
+            ("!café", "invalid character"),
+            ("!<tag:x.com,2024:a b>", "invalid character ' '"),
+        ] {
+            let error = resolver.resolve(tag).unwrap_err();
+            assert!(error.to_string().contains(message), "{tag}: {error}");
+        }
+    }
+
+    #[test]
+    fn test_unknown_handle() {
+        let mut resolver = TagResolver::new();
+        resolver.add_directive("!".to_string(), "tag:example.com,2024:".to_string());
+        let error = resolver.resolve("!x!widget").unwrap_err();
+        assert!(error.to_string().contains("undefined tag handle '!x!'"));
+
+        resolver.set_unknown_handle(UnknownHandle::FallbackPrimary);
+        let tag = resolver.resolve("!x!widget").unwrap();
+        assert_eq!(tag.uri, "tag:example.com,2024:x!widget");
+
+        resolver.set_unknown_handle(UnknownHandle::KeepVerbatim);
+        let tag = resolver.resolve("!x!widget").unwrap();
+        assert_eq!(tag.uri, "!x!widget");
+        // Declared handles are unaffected
+        assert_eq!(resolver.resolve("!!str").unwrap().kind, TagKind::Str);
+    }
+
     #[test]
     fn test_tag_construction() {
         let resolver = TagResolver::new();
@@ -531,4 +775,21 @@ mod tests {
             Value::Sequence(vec![Value::Float(3.5), Value::Float(7.2)])
         );
     }
+
+    #[test]
+    fn test_tag_handlers_clone_on_register() {
+        let mut handlers = TagHandlers::new();
+        handlers.register("!point", PointTagHandler);
+        let mut copy = handlers.clone();
+        assert!(Arc::ptr_eq(&handlers.handlers, &copy.handlers));
+
+        copy.register("!other", PointTagHandler);
+        assert_eq!((handlers.len(), copy.len()), (1, 2));
+        assert!(handlers.get("!other").is_none());
+
+        let mut resolver = TagResolver::new();
+        resolver.set_handlers(handlers);
+        let tag = resolver.resolve("!point").unwrap();
+        assert!(resolver.apply_tag(&tag, "1, 2").unwrap().is_sequence());
+    }
 }
@@ -0,0 +1,343 @@
+//! Loading YAML that still holds template placeholders
+//!
+//! Helm charts and Jinja-rendered files contain spans such as
+//! `{{ .Values.image }}` or `{% if enabled %}` that are not valid YAML until
+//! the template is rendered. [`MaskedTemplate`] pre-scans such a file and
+//! replaces every span with text of the same byte length that YAML accepts,
+//! so error positions still point into the original file:
+//!
+//! - Lines holding nothing but template spans, such as `{{- if .Values.x }}`,
+//!   `{% endfor %}` or `{{ toYaml .Values.labels | nindent 4 }}`, are read as
+//!   comments.
+//! - Spans within a line are read as opaque scalar text, and
+//!   [`MaskedTemplate::restore`] puts the original text back into the loaded
+//!   value.
+//!
+//! With [`YamlConfig::template_placeholders`](crate::YamlConfig) set,
+//! [`Yaml::load_str`](crate::Yaml::load_str) and
+//! [`Yaml::load_all_str`](crate::Yaml::load_all_str) do both steps.
+
+use crate::{Position, Value};
+use indexmap::IndexMap;
+
+/// Opening and closing delimiters of template spans
+const DELIMITERS: [(&str, &str); 3] = [("{{", "}}"), ("{%", "%}"), ("{#", "#}")];
+
+/// Private use characters that stand for the index of a span within a line
+const MARKERS: [(u32, u32); 2] = [(0xE000, 0xF8FF), (0xF_0000, 0xF_FFFD)];
+
+/// A template span found in the input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateSpan {
+    /// Where the span starts
+    pub position: Position,
+    /// The span including its delimiters, such as `{{ .Values.image }}`
+    pub text: String,
+    /// Whether the span stands on lines of its own and was read as a comment
+    pub standalone: bool,
+}
+
+impl TemplateSpan {
+    /// The byte range of the span in the input
+    pub fn range(&self) -> std::ops::Range<usize> {
+        self.position.index..self.position.index + self.text.len()
+    }
+}
+
+/// A template with its placeholders masked so it loads as YAML
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaskedTemplate {
+    text: String,
+    spans: Vec<TemplateSpan>,
+}
+
+impl MaskedTemplate {
+    /// Find the template spans of `input` and mask them
+    ///
+    /// A span opening without a matching close is left as it is.
+    pub fn new(input: &str) -> Self {
+        let ranges = find_spans(input);
+        let mut text = String::with_capacity(input.len());
+        let mut spans = Vec::with_capacity(ranges.len());
+        let mut copied = 0;
+        let mut position = Position::new();
+        let mut group_start = 0;
+
+        for (i, &(start, end)) in ranges.iter().enumerate() {
+            // Spans separated by blanks on one line stand or fall together
+            let joined = i > 0 && is_blank(&input[ranges[i - 1].1..start]);
+            if !joined {
+                group_start = i;
+            }
+            let mut group_end = i;
+            while group_end + 1 < ranges.len()
+                && is_blank(&input[ranges[group_end].1..ranges[group_end + 1].0])
+            {
+                group_end += 1;
+            }
+            let line_start = input[..ranges[group_start].0]
+                .rfind('\n')
+                .map_or(0, |n| n + 1);
+            let after = &input[ranges[group_end].1..];
+            let after = &after[..after.find('\n').unwrap_or(after.len())];
+            let standalone = is_blank(&input[line_start..ranges[group_start].0])
+                && (is_blank(after) || after.trim_start().starts_with('#'));
+
+            text.push_str(&input[copied..start]);
+            advance(&mut position, &input[copied..start]);
+            let span = &input[start..end];
+            if standalone {
+                comment_out(span, !joined, &mut text);
+            } else {
+                mask(span, spans.len(), &mut text);
+            }
+            spans.push(TemplateSpan {
+                position,
+                text: span.to_string(),
+                standalone,
+            });
+            advance(&mut position, span);
+            copied = end;
+        }
+        text.push_str(&input[copied..]);
+        Self { text, spans }
+    }
+
+    /// The masked text to load as YAML, of the same byte length as the input
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The template spans found, in input order
+    pub fn spans(&self) -> &[TemplateSpan] {
+        &self.spans
+    }
+
+    /// Put the text of the masked spans back into the strings and keys of a
+    /// value loaded from [`MaskedTemplate::text`]
+    pub fn restore(&self, mut value: Value) -> Value {
+        match &mut value {
+            Value::String(text) => Value::String(self.restore_str(std::mem::take(text))),
+            Value::Sequence(seq) => Value::Sequence(
+                std::mem::take(seq)
+                    .into_iter()
+                    .map(|item| self.restore(item))
+                    .collect(),
+            ),
+            Value::Mapping(map) => Value::Mapping(
+                std::mem::take(map)
+                    .into_iter()
+                    .map(|(key, value)| (self.restore(key), self.restore(value)))
+                    .collect::<IndexMap<_, _>>(),
+            ),
+            Value::Tagged(tagged) => {
+                tagged.value = self.restore(tagged.value.take());
+                value
+            }
+            _ => value,
+        }
+    }
+
+    fn restore_str(&self, text: String) -> String {
+        if !text.chars().any(|c| marker_index(c).is_some()) {
+            return text;
+        }
+        let mut restored = String::with_capacity(text.len());
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            match marker_index(c).and_then(|index| self.spans.get(index)) {
+                Some(span) if !span.standalone => {
+                    restored.push_str(&span.text);
+                    let padding = span.text.len() - c.len_utf8();
+                    for _ in 0..padding {
+                        chars.next();
+                    }
+                }
+                _ => restored.push(c),
+            }
+        }
+        restored
+    }
+}
+
+/// The byte ranges of the template spans of `input`
+fn find_spans(input: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut from = 0;
+    while let Some(offset) = input[from..].find('{') {
+        let start = from + offset;
+        let close = DELIMITERS
+            .iter()
+            .find(|(open, _)| input[start..].starts_with(open))
+            .and_then(|(_, close)| {
+                input[start + 2..]
+                    .find(close)
+                    .map(|n| start + 2 + n + close.len())
+            });
+        match close {
+            Some(end) => {
+                spans.push((start, end));
+                from = end;
+            }
+            None => from = start + 1,
+        }
+    }
+    spans
+}
+
+fn is_blank(text: &str) -> bool {
+    text.chars().all(|c| c == ' ' || c == '\t' || c == '\r')
+}
+
+/// Move `position` past `text`
+fn advance(position: &mut Position, text: &str) {
+    for c in text.chars() {
+        position.index += c.len_utf8();
+        if c == '\n' {
+            position.line += 1;
+            position.column = 1;
+        } else {
+            position.column += 1;
+        }
+    }
+}
+
+/// Write a standalone span as comments, starting each of its lines with `#`
+fn comment_out(span: &str, starts_line: bool, out: &mut String) {
+    let mut line_start = starts_line;
+    for c in span.chars() {
+        if line_start && !c.is_whitespace() {
+            out.push('#');
+            out.extend(std::iter::repeat_n(' ', c.len_utf8() - 1));
+            line_start = false;
+        } else {
+            out.push(c);
+            line_start |= c == '\n';
+        }
+    }
+}
+
+/// Write an inline span as the marker of its index padded with `_` to the
+/// length of the span
+fn mask(span: &str, index: usize, out: &mut String) {
+    match marker(index) {
+        // Every span is at least 4 bytes long, as long as any marker
+        Some(marker) => {
+            out.push(marker);
+            out.extend(std::iter::repeat_n('_', span.len() - marker.len_utf8()));
+        }
+        // Too many spans to mark: the rest are left as unrestorable text
+        None => out.extend(std::iter::repeat_n('_', span.len())),
+    }
+}
+
+/// The marker character standing for a span index
+fn marker(index: usize) -> Option<char> {
+    let mut index = u32::try_from(index).ok()?;
+    for (first, last) in MARKERS {
+        if index <= last - first {
+            return char::from_u32(first + index);
+        }
+        index -= last - first + 1;
+    }
+    None
+}
+
+/// The span index a marker character stands for
+fn marker_index(c: char) -> Option<usize> {
+    let code = u32::from(c);
+    let mut base = 0;
+    for (first, last) in MARKERS {
+        if (first..=last).contains(&code) {
+            return Some((base + code - first) as usize);
+        }
+        base += last - first + 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Yaml, YamlConfig};
+
+    const CHART: &str = "apiVersion: apps/v1\n\
+                         metadata:\n  \
+                         name: {{ include \"app.fullname\" . }}\n  \
+                         labels:\n    \
+                         {{- include \"app.labels\" . | nindent 4 }}\n\
+                         spec:\n  \
+                         {{- if .Values.autoscaling }}{{/* keep */}}\n  \
+                         replicas: {{ .Values.replicaCount }}\n  \
+                         {{- end }}\n  \
+                         image: \"{{ .Values.image }}:{{ .Chart.AppVersion }}\"\n  \
+                         env: prod-{% raw %}x{% endraw %}\n";
+
+    #[test]
+    fn test_mask_and_restore() {
+        let masked = MaskedTemplate::new(CHART);
+        assert_eq!(masked.text().len(), CHART.len());
+        let texts: Vec<_> = masked
+            .spans()
+            .iter()
+            .map(|span| (span.text.as_str(), span.standalone))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                ("{{ include \"app.fullname\" . }}", false),
+                ("{{- include \"app.labels\" . | nindent 4 }}", true),
+                ("{{- if .Values.autoscaling }}", true),
+                ("{{/* keep */}}", true),
+                ("{{ .Values.replicaCount }}", false),
+                ("{{- end }}", true),
+                ("{{ .Values.image }}", false),
+                ("{{ .Chart.AppVersion }}", false),
+                ("{% raw %}", false),
+                ("{% endraw %}", false),
+            ]
+        );
+        let span = &masked.spans()[4];
+        assert_eq!(span.position.line, 8);
+        assert_eq!(&CHART[span.range()], span.text);
+
+        let value = masked.restore(Yaml::new().load_str(masked.text()).unwrap());
+        assert_eq!(
+            value,
+            crate::yaml!({
+                "apiVersion": "apps/v1",
+                "metadata": {"name": "{{ include \"app.fullname\" . }}", "labels": null},
+                "spec": {
+                    "replicas": "{{ .Values.replicaCount }}",
+                    "image": "{{ .Values.image }}:{{ .Chart.AppVersion }}",
+                    "env": "prod-{% raw %}x{% endraw %}",
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_template_placeholders_config() {
+        assert!(Yaml::new().load_str(CHART).is_err());
+        let yaml = Yaml::with_config(YamlConfig {
+            template_placeholders: true,
+            ..YamlConfig::default()
+        });
+        let value = yaml.load_str(CHART).unwrap();
+        assert_eq!(
+            value["spec"]["replicas"],
+            Value::from("{{ .Values.replicaCount }}")
+        );
+        let documents = yaml
+            .load_all_str("a: {{ .Values.a }}\n---\n{{- if .Values.b }}\nb: 1\n{{- end }}\n")
+            .unwrap();
+        assert_eq!(documents[0]["a"], Value::from("{{ .Values.a }}"));
+        assert_eq!(documents[1]["b"], Value::Int(1));
+
+        // Unclosed spans are left for the parser to report
+        assert_eq!(MaskedTemplate::new("a: {{ b\n").text(), "a: {{ b\n");
+        assert_eq!(marker_index('\u{F8FF}'), Some(6399));
+        assert_eq!(marker(6400), Some('\u{F0000}'));
+        assert_eq!(marker_index('\u{F0000}'), Some(6400));
+    }
+}
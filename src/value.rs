@@ -1,9 +1,15 @@
 //! YAML value representation
 
 use crate::scanner::QuoteStyle;
+use crate::{Error, Path, PathSegment, Position};
+use indexmap::map::Entry;
 use indexmap::IndexMap;
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
 use std::fmt;
 use std::hash::{Hash, Hasher};
+use std::ops::{Index, IndexMut};
 
 /// Comments associated with a YAML value
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -14,6 +20,12 @@ pub struct Comments {
     pub trailing: Option<String>,
     /// Comments that appear inside collections (between items)
     pub inner: Vec<String>,
+    /// Blank lines above this value and its leading comments, which group
+    /// the entries of a collection into sections
+    pub blank_lines: usize,
+    /// Column the trailing comment started at in the source, counted from 1,
+    /// which the emitter keeps when aligning trailing comments automatically
+    pub trailing_column: Option<usize>,
 }
 
 impl Comments {
@@ -23,10 +35,12 @@ impl Comments {
             leading: Vec::new(),
             trailing: None,
             inner: Vec::new(),
+            blank_lines: 0,
+            trailing_column: None,
         }
     }
 
-    /// Check if there are any comments
+    /// Check if there are any comments; blank lines are not comments
     pub const fn is_empty(&self) -> bool {
         self.leading.is_empty() && self.trailing.is_none() && self.inner.is_empty()
     }
@@ -68,6 +82,231 @@ impl Default for IndentStyle {
     }
 }
 
+/// How booleans are written
+///
+/// Only [`BoolStyle::TrueFalse`] is a boolean in YAML 1.2; the other forms
+/// are YAML 1.1 spellings that 1.2 parsers read as strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BoolStyle {
+    /// `true` / `false`
+    #[default]
+    TrueFalse,
+    /// `yes` / `no`
+    YesNo,
+    /// `on` / `off`
+    OnOff,
+}
+
+impl BoolStyle {
+    /// The literal written for `value` in this style
+    pub const fn literal(self, value: bool) -> &'static str {
+        match (self, value) {
+            (Self::TrueFalse, true) => "true",
+            (Self::TrueFalse, false) => "false",
+            (Self::YesNo, true) => "yes",
+            (Self::YesNo, false) => "no",
+            (Self::OnOff, true) => "on",
+            (Self::OnOff, false) => "off",
+        }
+    }
+}
+
+/// How null values are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum NullStyle {
+    /// `null`
+    #[default]
+    Null,
+    /// `~`
+    Tilde,
+    /// Nothing after `key:` or `-`; written as `null` where an empty node is
+    /// not allowed, such as inside flow collections or as a whole document
+    Empty,
+}
+
+impl NullStyle {
+    /// The literal written for a null node in this style
+    pub const fn literal(self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Tilde => "~",
+            Self::Empty => "",
+        }
+    }
+}
+
+/// How collections are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum FlowStyle {
+    /// Block style throughout, one entry per line
+    #[default]
+    Block,
+    /// Flow style throughout, such as `{ports: [80, 443]}`
+    Flow,
+    /// Block style, with non-empty collections of scalars written inline
+    /// (such as `ports: [80, 443]`) when the line fits within the width
+    Auto,
+}
+
+/// How a block scalar treats its final line break and trailing empty lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Chomping {
+    /// `-`: no final line break
+    Strip,
+    /// No indicator: a single final line break
+    #[default]
+    Clip,
+    /// `+`: the final line break and any trailing empty lines
+    Keep,
+}
+
+impl Chomping {
+    /// The chomping that writes a string ending in `breaks` line breaks
+    /// without an indicator where possible
+    pub const fn for_trailing_breaks(breaks: usize) -> Self {
+        match breaks {
+            0 => Self::Strip,
+            1 => Self::Clip,
+            _ => Self::Keep,
+        }
+    }
+
+    /// Whether a string ending in `breaks` line breaks can be written with
+    /// this chomping
+    pub const fn fits(self, breaks: usize) -> bool {
+        match self {
+            Self::Strip => breaks == 0,
+            Self::Clip => breaks == 1,
+            Self::Keep => breaks >= 1,
+        }
+    }
+
+    /// The header indicator for this chomping
+    pub const fn indicator(self) -> &'static str {
+        match self {
+            Self::Strip => "-",
+            Self::Clip => "",
+            Self::Keep => "+",
+        }
+    }
+}
+
+/// A literal (`|`) or folded (`>`) block scalar and its header indicators
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct BlockScalarStyle {
+    /// Whether the scalar is folded (`>`) rather than literal (`|`)
+    pub folded: bool,
+    /// Explicit indentation indicator (`1`-`9`); chosen on output when the
+    /// content starts with a space
+    pub indent: Option<u8>,
+    /// Explicit chomping indicator; chosen on output from the string's
+    /// trailing line breaks
+    pub chomping: Option<Chomping>,
+}
+
+impl BlockScalarStyle {
+    /// A literal block scalar with indicators chosen on output
+    pub const fn literal() -> Self {
+        Self {
+            folded: false,
+            indent: None,
+            chomping: None,
+        }
+    }
+
+    /// A folded block scalar with indicators chosen on output
+    pub const fn folded() -> Self {
+        Self {
+            folded: true,
+            indent: None,
+            chomping: None,
+        }
+    }
+}
+
+/// Line breaks written on output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum LineEnding {
+    /// `\n`
+    #[default]
+    Lf,
+    /// `\r\n`
+    Crlf,
+    /// Whatever the loaded document used, as recorded in [`Style::line_ending`];
+    /// `\n` for values that were not loaded in round-trip mode
+    Preserve,
+}
+
+impl LineEnding {
+    /// The line ending of `text`, judged by its first line break
+    pub fn detect(text: &str) -> Self {
+        match text.find('\n') {
+            Some(index) if text[..index].ends_with('\r') => Self::Crlf,
+            _ => Self::Lf,
+        }
+    }
+
+    /// The concrete line ending to write, given the one a document was
+    /// loaded with
+    pub fn resolve(self, detected: Option<Self>) -> Self {
+        match self {
+            Self::Preserve => match detected {
+                Some(Self::Crlf) => Self::Crlf,
+                _ => Self::Lf,
+            },
+            concrete => concrete,
+        }
+    }
+}
+
+/// How output ends after its last line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum FinalNewline {
+    /// A single line break
+    #[default]
+    Single,
+    /// No line break
+    Omit,
+    /// The line breaks, blank lines included, that ended the loaded document,
+    /// as recorded in [`Style::trailing_breaks`]; a single line break for
+    /// values that were not loaded in round-trip mode
+    Preserve,
+}
+
+impl FinalNewline {
+    /// The number of line breaks to end the output with, given the number
+    /// that ended the loaded document
+    pub fn resolve(self, recorded: Option<usize>) -> usize {
+        match self {
+            Self::Single => 1,
+            Self::Omit => 0,
+            Self::Preserve => recorded.unwrap_or(1),
+        }
+    }
+
+    /// The number of line breaks in the whitespace that ends `text`
+    pub fn count_trailing_breaks(text: &str) -> usize {
+        let content = text.trim_end_matches([' ', '\t', '\r', '\n']);
+        let trailing = &text[content.len()..];
+        trailing.matches('\n').count() + trailing.matches('\r').count()
+            - trailing.matches("\r\n").count()
+    }
+}
+
+/// Which scalars output is written with an explicit tag, such as `!!int 1`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TagEmission {
+    /// No tags; the type of each scalar follows from how it is written
+    #[default]
+    Never,
+    /// Tags on strings that would load as another type without quotes, such
+    /// as `!!str "123"`, and on scalars that were loaded in round-trip mode
+    /// with an explicit tag of their type (see [`Style::tag`])
+    NonDefault,
+    /// The core schema tag of its type on every scalar
+    Always,
+}
+
 /// Style information for YAML values to preserve formatting during round-trips
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Style {
@@ -75,6 +314,32 @@ pub struct Style {
     pub quote_style: Option<QuoteStyle>,
     /// Indentation style for the document
     pub indent_style: Option<IndentStyle>,
+    /// `%YAML` version directive of the document (set on the root value)
+    pub yaml_version: Option<(u8, u8)>,
+    /// `%TAG` directives of the document as `(handle, prefix)` (set on the root value)
+    pub tag_directives: Vec<(String, String)>,
+    /// Whether the document was closed with a `...` marker (set on the root
+    /// value)
+    pub explicit_end: bool,
+    /// How a null value was written
+    pub null_style: Option<NullStyle>,
+    /// The plain text of a scalar as written (such as `yes` for a boolean or
+    /// `0o755` for an integer), reused on output while it still denotes the
+    /// value
+    pub literal: Option<String>,
+    /// The line ending of the loaded text (set on the root value)
+    pub line_ending: Option<LineEnding>,
+    /// The number of line breaks ending the loaded text, blank lines
+    /// included (set on the root value)
+    pub trailing_breaks: Option<usize>,
+    /// Whether the loaded text began with a byte order mark (set on the root
+    /// value)
+    pub bom: bool,
+    /// Block scalar style of a string, with the header indicators as written
+    pub block_scalar: Option<BlockScalarStyle>,
+    /// Explicit tag of a scalar as a resolved URI, such as
+    /// `tag:yaml.org,2002:str` for `!!str`
+    pub tag: Option<String>,
 }
 
 impl Style {
@@ -83,6 +348,16 @@ impl Style {
         Self {
             quote_style: None,
             indent_style: None,
+            yaml_version: None,
+            tag_directives: Vec::new(),
+            explicit_end: false,
+            null_style: None,
+            literal: None,
+            line_ending: None,
+            trailing_breaks: None,
+            bom: false,
+            block_scalar: None,
+            tag: None,
         }
     }
 
@@ -91,6 +366,16 @@ impl Style {
         Self {
             quote_style: Some(quote_style),
             indent_style: None,
+            yaml_version: None,
+            tag_directives: Vec::new(),
+            explicit_end: false,
+            null_style: None,
+            literal: None,
+            line_ending: None,
+            trailing_breaks: None,
+            bom: false,
+            block_scalar: None,
+            tag: None,
         }
     }
 
@@ -99,6 +384,16 @@ impl Style {
         Self {
             quote_style: None,
             indent_style: Some(indent_style),
+            yaml_version: None,
+            tag_directives: Vec::new(),
+            explicit_end: false,
+            null_style: None,
+            literal: None,
+            line_ending: None,
+            trailing_breaks: None,
+            bom: false,
+            block_scalar: None,
+            tag: None,
         }
     }
 
@@ -107,12 +402,38 @@ impl Style {
         Self {
             quote_style: Some(quote_style),
             indent_style: Some(indent_style),
+            yaml_version: None,
+            tag_directives: Vec::new(),
+            explicit_end: false,
+            null_style: None,
+            literal: None,
+            line_ending: None,
+            trailing_breaks: None,
+            bom: false,
+            block_scalar: None,
+            tag: None,
         }
     }
 
     /// Check if there is any style information
-    pub const fn is_empty(&self) -> bool {
-        self.quote_style.is_none() && self.indent_style.is_none()
+    pub fn is_empty(&self) -> bool {
+        self.quote_style.is_none()
+            && self.indent_style.is_none()
+            && self.yaml_version.is_none()
+            && self.tag_directives.is_empty()
+            && !self.explicit_end
+            && self.null_style.is_none()
+            && self.literal.is_none()
+            && self.line_ending.is_none()
+            && self.trailing_breaks.is_none()
+            && !self.bom
+            && self.block_scalar.is_none()
+            && self.tag.is_none()
+    }
+
+    /// Check if there are any document directives
+    pub fn has_directives(&self) -> bool {
+        self.yaml_version.is_some() || !self.tag_directives.is_empty()
     }
 }
 
@@ -131,15 +452,35 @@ pub struct CommentedValue {
     pub comments: Comments,
     /// Style information for formatting preservation
     pub style: Style,
+    /// The keys of a mapping `value` as written, with their own comments and
+    /// style
+    ///
+    /// Each entry's `value` is the key itself, its `comments` are the comment
+    /// lines directly above the key and the comment at the end of the key's
+    /// line, and its `keys` describe the mapping stored under that key.
+    /// Entries are matched to the mapping by key, so editing values leaves
+    /// key comments in place; use [`CommentedValue::rename_key`] to rename.
+    ///
+    /// The comments and quote style of an entry describe the key; its other
+    /// fields (`keys`, `items`, `null_style`, `literal`) describe the value
+    /// under that key.
+    pub keys: IndexMap<Value, CommentedValue>,
+    /// The items of a sequence `value` as written, in order
+    ///
+    /// An item is only used on output while it still equals the sequence
+    /// element at the same index.
+    pub items: Vec<CommentedValue>,
 }
 
 impl CommentedValue {
     /// Create a new commented value
-    pub const fn new(value: Value) -> Self {
+    pub fn new(value: Value) -> Self {
         Self {
             value,
             comments: Comments::new(),
             style: Style::new(),
+            keys: IndexMap::new(),
+            items: Vec::new(),
         }
     }
 
@@ -173,11 +514,13 @@ impl CommentedValue {
     }
 
     /// Create a commented value with quote style
-    pub const fn with_quote_style(value: Value, quote_style: QuoteStyle) -> Self {
+    pub fn with_quote_style(value: Value, quote_style: QuoteStyle) -> Self {
         Self {
             value,
             comments: Comments::new(),
             style: Style::with_quote_style(quote_style),
+            keys: IndexMap::new(),
+            items: Vec::new(),
         }
     }
 
@@ -192,7 +535,7 @@ impl CommentedValue {
     }
 
     /// Check if this value has style information
-    pub const fn has_style(&self) -> bool {
+    pub fn has_style(&self) -> bool {
         !self.style.is_empty()
     }
 
@@ -206,12 +549,258 @@ impl CommentedValue {
         self.style.indent_style.as_ref()
     }
 
+    /// Set the null style
+    pub const fn set_null_style(&mut self, null_style: NullStyle) {
+        self.style.null_style = Some(null_style);
+    }
+
+    /// Get the null style
+    pub const fn null_style(&self) -> Option<NullStyle> {
+        self.style.null_style
+    }
+
     /// Create a commented value with indentation style
-    pub const fn with_indent_style(value: Value, indent_style: IndentStyle) -> Self {
+    pub fn with_indent_style(value: Value, indent_style: IndentStyle) -> Self {
         Self {
             value,
             comments: Comments::new(),
             style: Style::with_indent_style(indent_style),
+            keys: IndexMap::new(),
+            items: Vec::new(),
+        }
+    }
+
+    /// The recorded entry (comments and style) for a key of this mapping
+    pub fn key_entry(&self, key: &Value) -> Option<&CommentedValue> {
+        self.keys.get(key)
+    }
+
+    /// Mutable access to the recorded entry for a key of this mapping
+    pub fn key_entry_mut(&mut self, key: &Value) -> Option<&mut CommentedValue> {
+        self.keys.get_mut(key)
+    }
+
+    /// Rename a key of this mapping in place, keeping its position, value and
+    /// comments
+    ///
+    /// Returns `false` if `value` is not a mapping, `old` is missing, or `new`
+    /// is already present.
+    pub fn rename_key(&mut self, old: &Value, new: Value) -> bool {
+        let Value::Mapping(map) = &mut self.value else {
+            return false;
+        };
+        if map.contains_key(&new) {
+            return false;
+        }
+        let Some((index, _, value)) = map.shift_remove_full(old) else {
+            return false;
+        };
+        map.shift_insert(index, new.clone(), value);
+
+        if let Some((index, _, mut entry)) = self.keys.shift_remove_full(old) {
+            entry.value = new.clone();
+            self.keys.shift_insert(index, new, entry);
+        }
+        true
+    }
+
+    /// Remove a key of this mapping together with the comments attached to
+    /// it, keeping the order of the other keys
+    ///
+    /// The comments above the key, at the end of its line and inside its
+    /// value are dropped from this mapping's own comments too, so they are
+    /// not written out again. Returns the removed value and its recorded
+    /// entry (an empty one if none was recorded), or `None` if `value` is not
+    /// a mapping or `key` is missing.
+    pub fn remove_key_with_comments(&mut self, key: &Value) -> Option<(Value, CommentedValue)> {
+        let Value::Mapping(map) = &mut self.value else {
+            return None;
+        };
+        let value = map.shift_remove(key)?;
+        let entry = self
+            .keys
+            .shift_remove(key)
+            .unwrap_or_else(|| Self::new(key.clone()));
+
+        let mut removed = Vec::new();
+        entry.collect_comments(&mut removed);
+        if !removed.is_empty() {
+            self.comments
+                .leading
+                .retain(|comment| !removed.contains(comment));
+            self.comments
+                .inner
+                .retain(|comment| !removed.contains(comment));
+            if self
+                .comments
+                .trailing
+                .as_ref()
+                .is_some_and(|comment| removed.contains(comment))
+            {
+                self.comments.trailing = None;
+            }
+        }
+        Some((value, entry))
+    }
+
+    /// Insert a key into this mapping with the comments to write above it
+    /// and at the end of its line
+    ///
+    /// A new key is appended; an existing key keeps its position and style,
+    /// and its comments are replaced. Returns the previous value under `key`,
+    /// or `None` if there was none or `value` is not a mapping, in which case
+    /// nothing is inserted.
+    pub fn insert_with_comment(
+        &mut self,
+        key: Value,
+        value: Value,
+        leading: Vec<String>,
+        trailing: Option<String>,
+    ) -> Option<Value> {
+        let Value::Mapping(map) = &mut self.value else {
+            return None;
+        };
+        let previous = map.insert(key.clone(), value);
+        let entry = self
+            .keys
+            .entry(key.clone())
+            .or_insert_with(|| Self::new(key));
+        entry.comments.leading = leading;
+        entry.comments.trailing = trailing;
+        previous
+    }
+
+    /// The comments recorded for the node at `path`, such as the comments
+    /// above a mapping key and at the end of its line
+    ///
+    /// The root path gives this value's own comments. Returns `None` if
+    /// there is no node at `path` or nothing is recorded for it.
+    pub fn comments_at(&self, path: &Path) -> Option<&Comments> {
+        path.resolve(&self.value)?;
+        let mut entry = self;
+        for segment in path.segments() {
+            entry = match segment {
+                PathSegment::Key(key) => entry.keys.get(key)?,
+                PathSegment::Index(index) => entry.items.get(*index)?,
+            };
+        }
+        Some(&entry.comments)
+    }
+
+    /// Replace the comments above the node at `path` with `text`, one
+    /// comment line per line of `text`, written without the `#`
+    ///
+    /// Useful for license headers (at the root path) and for annotating
+    /// generated values. Returns `false`, changing nothing, if there is no
+    /// node at `path`.
+    pub fn set_leading_comment(&mut self, path: &Path, text: impl Into<String>) -> bool {
+        let text = text.into();
+        let Some(comments) = self.comments_at_mut(path) else {
+            return false;
+        };
+        let removed = std::mem::replace(
+            &mut comments.leading,
+            text.lines().map(str::to_string).collect(),
+        );
+        self.forget_comments(path, &removed);
+        true
+    }
+
+    /// Remove the comments above, at the end of and inside the node at
+    /// `path`, returning them
+    ///
+    /// Blank lines above the node are kept. Returns `None` if there is no
+    /// node at `path`.
+    pub fn remove_comments(&mut self, path: &Path) -> Option<Comments> {
+        let comments = self.comments_at_mut(path)?;
+        let mut removed = std::mem::take(comments);
+        comments.blank_lines = std::mem::take(&mut removed.blank_lines);
+        let texts: Vec<String> = removed
+            .leading
+            .iter()
+            .chain(&removed.trailing)
+            .chain(&removed.inner)
+            .cloned()
+            .collect();
+        self.forget_comments(path, &texts);
+        Some(removed)
+    }
+
+    /// The comments of the node at `path`, recording an entry for it and
+    /// the nodes above it where none is recorded yet
+    fn comments_at_mut(&mut self, path: &Path) -> Option<&mut Comments> {
+        path.resolve(&self.value)?;
+        let Self {
+            value,
+            comments,
+            keys,
+            items,
+            ..
+        } = self;
+        let (mut value, mut comments, mut keys, mut items) = (&*value, comments, keys, items);
+        for segment in path.segments() {
+            let entry = match segment {
+                PathSegment::Key(key) => {
+                    value = value.as_mapping()?.get(key)?;
+                    keys.entry(key.clone())
+                        .or_insert_with(|| Self::new(key.clone()))
+                }
+                PathSegment::Index(index) => {
+                    let seq = value.as_sequence()?;
+                    value = seq.get(*index)?;
+                    // Items are only used while they match the sequence
+                    items.truncate(seq.len());
+                    for (recorded, item) in items.iter_mut().zip(seq) {
+                        if recorded.value != *item {
+                            *recorded = Self::new(item.clone());
+                        }
+                    }
+                    items.extend(seq[items.len()..=*index].iter().cloned().map(Self::new));
+                    &mut items[*index]
+                }
+            };
+            (comments, keys, items) = (&mut entry.comments, &mut entry.keys, &mut entry.items);
+        }
+        Some(comments)
+    }
+
+    /// Drop `removed` from the comments of the nodes above `path`, which
+    /// the composer also files there, so they are not written out again
+    fn forget_comments(&mut self, path: &Path, removed: &[String]) {
+        if removed.is_empty() {
+            return;
+        }
+        let mut entry = Some(self);
+        for segment in path.segments() {
+            let Some(current) = entry else {
+                return;
+            };
+            let comments = &mut current.comments;
+            comments
+                .leading
+                .retain(|comment| !removed.contains(comment));
+            comments.inner.retain(|comment| !removed.contains(comment));
+            if comments
+                .trailing
+                .as_ref()
+                .is_some_and(|comment| removed.contains(comment))
+            {
+                comments.trailing = None;
+            }
+            entry = match segment {
+                PathSegment::Key(key) => current.keys.get_mut(key),
+                PathSegment::Index(index) => current.items.get_mut(*index),
+            };
+        }
+    }
+
+    /// Every comment of this value and of the keys and items recorded for it
+    fn collect_comments(&self, out: &mut Vec<String>) {
+        out.extend(self.comments.leading.iter().cloned());
+        out.extend(self.comments.trailing.iter().cloned());
+        out.extend(self.comments.inner.iter().cloned());
+        for entry in self.keys.values().chain(&self.items) {
+            entry.collect_comments(out);
         }
     }
 }
@@ -239,6 +828,18 @@ pub enum Value {
     Sequence(Vec<Value>),
     /// Mapping (dictionary/object) value
     Mapping(IndexMap<Value, Value>),
+    /// Node with an application tag that no handler constructs, such as
+    /// `!Deployment {...}`
+    Tagged(Box<TaggedValue>),
+}
+
+/// A node together with the application tag it was written with
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TaggedValue {
+    /// The resolved tag, such as `!Deployment` or `tag:example.com,2024:widget`
+    pub tag: String,
+    /// The node as it would load without the tag
+    pub value: Value,
 }
 
 impl Value {
@@ -291,6 +892,14 @@ impl Value {
         Self::Mapping(map)
     }
 
+    /// Create a tagged value
+    pub fn tagged(tag: impl Into<String>, value: Self) -> Self {
+        Self::Tagged(Box::new(TaggedValue {
+            tag: tag.into(),
+            value,
+        }))
+    }
+
     /// Get the type name of this value
     pub const fn type_name(&self) -> &'static str {
         match self {
@@ -301,6 +910,20 @@ impl Value {
             Self::String(_) => "string",
             Self::Sequence(_) => "sequence",
             Self::Mapping(_) => "mapping",
+            Self::Tagged(_) => "tagged",
+        }
+    }
+
+    /// Position of this value's type in the cross-type ordering of [`Ord`]
+    const fn type_rank(&self) -> u8 {
+        match self {
+            Self::Null => 0,
+            Self::Bool(_) => 1,
+            Self::Int(_) | Self::Float(_) => 2,
+            Self::String(_) => 3,
+            Self::Sequence(_) => 4,
+            Self::Mapping(_) => 5,
+            Self::Tagged(_) => 6,
         }
     }
 
@@ -339,6 +962,50 @@ impl Value {
         matches!(self, Self::Mapping(_))
     }
 
+    /// Take the value out, leaving null in its place
+    ///
+    /// Values cannot be moved out of by pattern, as they implement [`Drop`],
+    /// so taking the contents of a collection looks like
+    /// `if let Value::Sequence(seq) = &mut value { std::mem::take(seq) }`
+    /// and taking a tagged node like `tagged.value.take()`.
+    pub fn take(&mut self) -> Self {
+        std::mem::replace(self, Self::Null)
+    }
+
+    /// Check if this value carries an application tag
+    pub const fn is_tagged(&self) -> bool {
+        matches!(self, Self::Tagged(_))
+    }
+
+    /// Format this value like its `Display` and `Debug` output, showing
+    /// collections at most `max_depth` levels deep and at most `max_items`
+    /// items or entries of each
+    ///
+    /// The plain formats write out the whole tree, recursing once per level,
+    /// so printing an untrusted value with them can overflow the stack or
+    /// produce gigabytes. Elided parts are written as `...`:
+    ///
+    /// ```
+    /// use rust_yaml::yaml;
+    ///
+    /// let value = yaml!({"a": [1, 2, 3], "b": {"c": {"d": 1}}});
+    /// assert_eq!(
+    ///     value.display_truncated(2, 2).to_string(),
+    ///     "{\"a\": [1, 2, ...], \"b\": {\"c\": {...}}}"
+    /// );
+    /// ```
+    pub const fn display_truncated(
+        &self,
+        max_depth: usize,
+        max_items: usize,
+    ) -> TruncatedDisplay<'_> {
+        TruncatedDisplay {
+            value: self,
+            max_depth,
+            max_items,
+        }
+    }
+
     /// Check if this value is a number (int or float)
     pub const fn is_number(&self) -> bool {
         matches!(self, Self::Int(_) | Self::Float(_))
@@ -396,6 +1063,23 @@ impl Value {
         }
     }
 
+    /// Get the tag and tagged node of this value, if it has an application
+    /// tag
+    pub fn as_tagged(&self) -> Option<&TaggedValue> {
+        match self {
+            Self::Tagged(tagged) => Some(tagged),
+            _ => None,
+        }
+    }
+
+    /// This value without any application tags
+    pub fn untagged(&self) -> &Self {
+        match self {
+            Self::Tagged(tagged) => tagged.value.untagged(),
+            value => value,
+        }
+    }
+
     /// Get this value as a sequence reference, if possible
     pub const fn as_sequence(&self) -> Option<&Vec<Self>> {
         match self {
@@ -451,6 +1135,35 @@ impl Value {
         }
     }
 
+    /// Look `key` up in a mapping without regard to how its scalar keys are
+    /// typed
+    ///
+    /// An exact match is preferred; otherwise a scalar key matches when it
+    /// is the same number, boolean or null as `key`, with strings compared
+    /// as the core schema would type them if written plain. The keys `1`,
+    /// `1.0` and `"1"` all match each other:
+    ///
+    /// ```
+    /// use rust_yaml::Value;
+    ///
+    /// let value = Value::mapping_with(vec![(Value::Int(1), Value::from("one"))]);
+    /// assert_eq!(value.get_loose(&Value::from("1")), Some(&Value::from("one")));
+    /// assert_eq!(value.get_loose(&Value::Float(1.0)), Some(&Value::from("one")));
+    /// assert_eq!(value.get(&Value::from("1")), None);
+    /// ```
+    pub fn get_loose(&self, key: &Self) -> Option<&Self> {
+        let Self::Mapping(map) = self else {
+            return None;
+        };
+        if let Some(found) = map.get(key) {
+            return Some(found);
+        }
+        let wanted = LooseKey::of(key)?;
+        map.iter()
+            .find(|(candidate, _)| LooseKey::of(candidate).as_ref() == Some(&wanted))
+            .map(|(_, value)| value)
+    }
+
     /// Get a value by numeric index (for sequences)
     pub fn get_index(&self, index: usize) -> Option<&Self> {
         match self {
@@ -473,27 +1186,228 @@ impl Value {
             _ => None,
         }
     }
+
+    /// Get the entry for `key` for in-place insertion or update
+    ///
+    /// A null value is turned into an empty mapping first.
+    ///
+    /// ```
+    /// use rust_yaml::Value;
+    ///
+    /// let mut value = Value::Null;
+    /// value.entry("replicas")?.or_insert(Value::Int(1));
+    /// value.entry("replicas")?.and_modify(|n| *n = Value::Int(3));
+    /// assert_eq!(value["replicas"], Value::Int(3));
+    /// # Ok::<(), rust_yaml::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if `self` is neither a mapping nor null.
+    pub fn entry(&mut self, key: impl Into<Self>) -> crate::Result<Entry<'_, Self, Self>> {
+        if self.is_null() {
+            *self = Self::mapping();
+        }
+        match self {
+            Self::Mapping(map) => Ok(map.entry(key.into())),
+            other => Err(Error::value_error(
+                Position::start(),
+                format!("cannot take an entry of {}", other.type_name()),
+            )),
+        }
+    }
+
+    /// Set the value at a dot-separated path such as `"server.tls.enabled"`
+    ///
+    /// Missing or null intermediate values become mappings. A segment that
+    /// meets a sequence is read as an index into it.
+    ///
+    /// # Errors
+    /// Returns an error if the path has an empty segment, passes through a
+    /// scalar, or indexes a sequence out of bounds. `self` is left unchanged.
+    pub fn set_path(&mut self, path: &str, value: impl Into<Self>) -> crate::Result<()> {
+        let path_error = |message: String| {
+            Err(Error::value_error(
+                Position::start(),
+                format!("cannot set path '{path}': {message}"),
+            ))
+        };
+        if path.split('.').any(str::is_empty) {
+            return path_error("empty path segment".to_string());
+        }
+
+        // Validate before creating anything, so a failed call leaves no trace
+        let mut current = &*self;
+        for segment in path.split('.') {
+            current = match current {
+                Self::Mapping(map) => match map.get(&Self::from(segment)) {
+                    Some(next) => next,
+                    None => break,
+                },
+                Self::Sequence(seq) => match segment.parse::<usize>().ok().and_then(|i| seq.get(i))
+                {
+                    Some(next) => next,
+                    None => return path_error(format!("no index '{segment}' in sequence")),
+                },
+                Self::Null => break,
+                other => {
+                    return path_error(format!(
+                        "cannot descend into {} at '{segment}'",
+                        other.type_name()
+                    ))
+                }
+            };
+        }
+
+        let mut current = self;
+        for segment in path.split('.') {
+            if current.is_null() {
+                *current = Self::mapping();
+            }
+            current = match current {
+                Self::Mapping(map) => map.entry(Self::from(segment)).or_insert(Self::Null),
+                Self::Sequence(seq) => &mut seq[segment.parse::<usize>().unwrap_or_default()],
+                _ => unreachable!("path was validated above"),
+            };
+        }
+        *current = value.into();
+        Ok(())
+    }
+
+    /// Sort the keys of this mapping and every nested mapping by [`Ord`],
+    /// including mappings inside tagged nodes
+    pub fn sort_keys(&mut self) {
+        let mut stack = vec![self];
+        while let Some(value) = stack.pop() {
+            match value {
+                Self::Mapping(map) => {
+                    map.sort_keys();
+                    stack.extend(map.values_mut());
+                }
+                Self::Sequence(seq) => stack.extend(seq.iter_mut()),
+                Self::Tagged(tagged) => stack.push(&mut tagged.value),
+                _ => {}
+            }
+        }
+    }
+
+    /// A copy of this value with its keys sorted as
+    /// [`sort_keys`](Self::sort_keys) sorts them
+    ///
+    /// The copy is built from an explicit stack, so a deep value does not
+    /// overflow the call stack.
+    pub(crate) fn sorted(&self) -> Self {
+        let mut stack: Vec<SortedCopy<'_>> = Vec::new();
+        let mut node = self;
+        loop {
+            let mut copied = match node {
+                Self::Sequence(seq) => {
+                    stack.push(SortedCopy::Sequence(
+                        Vec::with_capacity(seq.len()),
+                        seq.iter(),
+                    ));
+                    None
+                }
+                Self::Mapping(map) => {
+                    let mut entries: Vec<_> = map.iter().collect();
+                    entries.sort_by_key(|(key, _)| *key);
+                    stack.push(SortedCopy::Mapping(
+                        IndexMap::with_capacity(map.len()),
+                        entries.into_iter(),
+                        None,
+                    ));
+                    None
+                }
+                Self::Tagged(tagged) => {
+                    stack.push(SortedCopy::Tagged(&tagged.tag));
+                    node = &tagged.value;
+                    continue;
+                }
+                leaf => Some(leaf.clone()),
+            };
+            // Complete the collections whose entries are all copied, until one
+            // has another entry to descend into
+            loop {
+                let Some(open) = stack.last_mut() else {
+                    return copied.unwrap_or(Self::Null);
+                };
+                match open {
+                    SortedCopy::Sequence(items, rest) => {
+                        items.extend(copied.take());
+                        if let Some(item) = rest.next() {
+                            node = item;
+                            break;
+                        }
+                        copied = Some(Self::Sequence(std::mem::take(items)));
+                    }
+                    SortedCopy::Mapping(map, rest, key) => {
+                        if let (Some(key), Some(value)) = (key.take(), copied.take()) {
+                            map.insert(key, value);
+                        }
+                        if let Some((next_key, value)) = rest.next() {
+                            *key = Some(next_key.clone());
+                            node = value;
+                            break;
+                        }
+                        copied = Some(Self::Mapping(std::mem::take(map)));
+                    }
+                    SortedCopy::Tagged(tag) => {
+                        copied = Some(Self::tagged(*tag, copied.take().unwrap_or(Self::Null)));
+                    }
+                }
+                stack.pop();
+            }
+        }
+    }
 }
 
-// Implement PartialEq manually to handle NaN in floats
+// Implement PartialEq manually to handle NaN in floats, comparing nested
+// collections from an explicit stack so deep values cannot overflow it
 impl PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (Value::Null, Value::Null) => true,
-            (Value::Bool(a), Value::Bool(b)) => a == b,
-            (Value::Int(a), Value::Int(b)) => a == b,
-            (Value::Float(a), Value::Float(b)) => {
-                // Special handling for NaN - all NaN values are considered equal for consistency with Hash
-                if a.is_nan() && b.is_nan() {
-                    true
-                } else {
-                    a == b
+        let mut pairs = Vec::new();
+        let (mut a, mut b) = (self, other);
+        loop {
+            let equal = match (a, b) {
+                (Value::Null, Value::Null) => true,
+                (Value::Bool(a), Value::Bool(b)) => a == b,
+                (Value::Int(a), Value::Int(b)) => a == b,
+                (Value::Float(a), Value::Float(b)) => {
+                    // Special handling for NaN - all NaN values are considered equal for consistency with Hash
+                    if a.is_nan() && b.is_nan() {
+                        true
+                    } else {
+                        a == b
+                    }
                 }
+                (Value::String(a), Value::String(b)) => a == b,
+                (Value::Sequence(a), Value::Sequence(b)) => {
+                    pairs.extend(a.iter().zip(b));
+                    a.len() == b.len()
+                }
+                // Mappings are equal regardless of key order
+                (Value::Mapping(a), Value::Mapping(b)) => {
+                    a.len() == b.len()
+                        && a.iter().all(|(key, value)| match b.get(key) {
+                            Some(other) => {
+                                pairs.push((value, other));
+                                true
+                            }
+                            None => false,
+                        })
+                }
+                (Value::Tagged(a), Value::Tagged(b)) => {
+                    pairs.push((&a.value, &b.value));
+                    a.tag == b.tag
+                }
+                _ => false,
+            };
+            if !equal {
+                return false;
+            }
+            match pairs.pop() {
+                Some(next) => (a, b) = next,
+                None => return true,
             }
-            (Value::String(a), Value::String(b)) => a == b,
-            (Value::Sequence(a), Value::Sequence(b)) => a == b,
-            (Value::Mapping(a), Value::Mapping(b)) => a == b,
-            _ => false,
         }
     }
 }
@@ -534,16 +1448,91 @@ impl Hash for Value {
             }
             Self::Mapping(map) => {
                 6u8.hash(state);
-                // Hash all key-value pairs
-                for (k, v) in map.iter() {
-                    k.hash(state);
-                    v.hash(state);
-                }
+                // Mappings are equal regardless of key order, so combine the
+                // entry hashes with an order-independent sum
+                map.len().hash(state);
+                let combined = map.iter().fold(0u64, |sum, entry| {
+                    let mut hasher = DefaultHasher::new();
+                    entry.hash(&mut hasher);
+                    sum.wrapping_add(hasher.finish())
+                });
+                combined.hash(state);
+            }
+            Self::Tagged(tagged) => {
+                7u8.hash(state);
+                tagged.hash(state);
             }
         }
     }
 }
 
+/// Total order across all values
+///
+/// Values of different types order as null < bool < number < string <
+/// sequence < mapping < tagged. Integers and floats are compared by numeric value,
+/// with an integer sorting just before an equal float (they are not equal
+/// values); NaN sorts above every other number. Sequences compare
+/// lexicographically and mappings by their entries in key order, so two
+/// mappings with the same entries in a different order are equal.
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Bool(a), Self::Bool(b)) => a.cmp(b),
+            (Self::Int(a), Self::Int(b)) => a.cmp(b),
+            (Self::Float(a), Self::Float(b)) => compare_floats(*a, *b),
+            (Self::Int(a), Self::Float(b)) => compare_int_float(*a, *b).then(Ordering::Less),
+            (Self::Float(a), Self::Int(b)) => {
+                compare_int_float(*b, *a).reverse().then(Ordering::Greater)
+            }
+            (Self::String(a), Self::String(b)) => a.cmp(b),
+            (Self::Sequence(a), Self::Sequence(b)) => a.cmp(b),
+            (Self::Mapping(a), Self::Mapping(b)) => sorted_entries(a).cmp(&sorted_entries(b)),
+            (Self::Tagged(a), Self::Tagged(b)) => a.cmp(b),
+            _ => self.type_rank().cmp(&other.type_rank()),
+        }
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Compare floats with NaN equal to itself and above every other float
+fn compare_floats(a: f64, b: f64) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+    }
+}
+
+/// Compare an integer with a float exactly, without rounding the integer
+fn compare_int_float(int: i64, float: f64) -> Ordering {
+    // 2^63, the first float above every i64
+    const LIMIT: f64 = 9_223_372_036_854_775_808.0;
+    if float.is_nan() || float >= LIMIT {
+        return Ordering::Less;
+    }
+    if float < -LIMIT {
+        return Ordering::Greater;
+    }
+    let whole = float.trunc();
+    int.cmp(&(whole as i64)).then_with(|| {
+        0.0_f64
+            .partial_cmp(&(float - whole))
+            .unwrap_or(Ordering::Equal)
+    })
+}
+
+fn sorted_entries(map: &IndexMap<Value, Value>) -> Vec<(&Value, &Value)> {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort();
+    entries
+}
+
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -572,6 +1561,180 @@ impl fmt::Display for Value {
                 }
                 write!(f, "}}")
             }
+            Self::Tagged(tagged) => write!(f, "{} {}", tagged.tag, tagged.value),
+        }
+    }
+}
+
+/// A collection [`Value::sorted`] is copying, with the entries still to copy
+enum SortedCopy<'a> {
+    Sequence(Vec<Value>, std::slice::Iter<'a, Value>),
+    /// A mapping, with the key whose value is copied next
+    Mapping(
+        IndexMap<Value, Value>,
+        std::vec::IntoIter<(&'a Value, &'a Value)>,
+        Option<Value>,
+    ),
+    Tagged(&'a str),
+}
+
+/// Values are dropped from an explicit stack rather than by recursing into
+/// their children, so dropping a maliciously deep tree cannot overflow the
+/// stack
+impl Drop for Value {
+    fn drop(&mut self) {
+        let mut stack = Vec::new();
+        take_nested(self, &mut stack);
+        while let Some(mut value) = stack.pop() {
+            take_nested(&mut value, &mut stack);
+        }
+    }
+}
+
+/// Move the children of `value` that hold further values onto `stack`,
+/// dropping the others
+fn take_nested(value: &mut Value, stack: &mut Vec<Value>) {
+    let nested = |value: &Value| {
+        matches!(
+            value,
+            Value::Sequence(_) | Value::Mapping(_) | Value::Tagged(_)
+        )
+    };
+    match value {
+        Value::Sequence(seq) => stack.extend(seq.drain(..).filter(nested)),
+        Value::Mapping(map) => stack.extend(
+            map.drain(..)
+                .flat_map(|(key, value)| [key, value])
+                .filter(nested),
+        ),
+        Value::Tagged(tagged) => stack.push(tagged.value.take()),
+        _ => {}
+    }
+}
+
+/// A [`Value`] formatted with bounded depth and length, created by
+/// [`Value::display_truncated`]
+#[derive(Clone, Copy)]
+pub struct TruncatedDisplay<'a> {
+    value: &'a Value,
+    max_depth: usize,
+    max_items: usize,
+}
+
+impl<'a> TruncatedDisplay<'a> {
+    /// The display of `value`, a child of the collection displayed
+    const fn child(&self, value: &'a Value) -> Self {
+        Self {
+            value,
+            max_depth: self.max_depth.saturating_sub(1),
+            max_items: self.max_items,
+        }
+    }
+
+    /// The display of the node a tag is on, at the same level as the tag
+    const fn tagged(&self, value: &'a Value) -> Self {
+        Self { value, ..*self }
+    }
+}
+
+impl fmt::Display for TruncatedDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value {
+            Value::Sequence(seq) => {
+                write!(f, "[")?;
+                if self.max_depth == 0 {
+                    return write!(f, "...]");
+                }
+                for (i, item) in seq.iter().take(self.max_items).enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", self.child(item))?;
+                }
+                if seq.len() > self.max_items {
+                    write!(f, "{}...", if self.max_items > 0 { ", " } else { "" })?;
+                }
+                write!(f, "]")
+            }
+            Value::Mapping(map) => {
+                write!(f, "{{")?;
+                if self.max_depth == 0 {
+                    return write!(f, "...}}");
+                }
+                for (i, (key, value)) in map.iter().take(self.max_items).enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", self.child(key), self.child(value))?;
+                }
+                if map.len() > self.max_items {
+                    write!(f, "{}...", if self.max_items > 0 { ", " } else { "" })?;
+                }
+                write!(f, "}}")
+            }
+            Value::Tagged(tagged) => write!(f, "{} {}", tagged.tag, self.tagged(&tagged.value)),
+            scalar => write!(f, "{scalar}"),
+        }
+    }
+}
+
+impl fmt::Debug for TruncatedDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.value {
+            Value::Sequence(_) => "Sequence",
+            Value::Mapping(_) => "Mapping",
+            Value::Tagged(_) => "Tagged",
+            scalar => return fmt::Debug::fmt(scalar, f),
+        };
+        let mut tuple = f.debug_tuple(name);
+        if self.max_depth == 0 {
+            tuple.finish_non_exhaustive()
+        } else {
+            tuple.field(&TruncatedContents(*self)).finish()
+        }
+    }
+}
+
+/// What the derived `Debug` of [`Value`] writes inside the variant name of
+/// a collection or tagged node, with the bounds of a [`TruncatedDisplay`]
+struct TruncatedContents<'a>(TruncatedDisplay<'a>);
+
+impl fmt::Debug for TruncatedContents<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let display = &self.0;
+        match display.value {
+            Value::Sequence(seq) => {
+                let mut list = f.debug_list();
+                list.entries(
+                    seq.iter()
+                        .take(display.max_items)
+                        .map(|item| display.child(item)),
+                );
+                if seq.len() > display.max_items {
+                    list.finish_non_exhaustive()
+                } else {
+                    list.finish()
+                }
+            }
+            Value::Mapping(map) => {
+                let mut entries = f.debug_map();
+                entries.entries(
+                    map.iter()
+                        .take(display.max_items)
+                        .map(|(key, value)| (display.child(key), display.child(value))),
+                );
+                if map.len() > display.max_items {
+                    entries.finish_non_exhaustive()
+                } else {
+                    entries.finish()
+                }
+            }
+            Value::Tagged(tagged) => f
+                .debug_struct("TaggedValue")
+                .field("tag", &tagged.tag)
+                .field("value", &display.tagged(&tagged.value))
+                .finish(),
+            _ => Ok(()),
         }
     }
 }
@@ -637,6 +1800,24 @@ impl From<IndexMap<Self, Self>> for Value {
     }
 }
 
+impl From<BTreeMap<Self, Self>> for Value {
+    fn from(map: BTreeMap<Self, Self>) -> Self {
+        Self::Mapping(map.into_iter().collect())
+    }
+}
+
+impl TryFrom<Value> for BTreeMap<Value, Value> {
+    type Error = Value;
+
+    /// Convert a mapping into a map sorted by key, giving back any other value
+    fn try_from(mut value: Value) -> std::result::Result<Self, Value> {
+        match &mut value {
+            Value::Mapping(map) => Ok(std::mem::take(map).into_iter().collect()),
+            _ => Err(value),
+        }
+    }
+}
+
 #[cfg(feature = "serde")]
 impl serde::Serialize for Value {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -651,6 +1832,184 @@ impl serde::Serialize for Value {
             Self::String(s) => serializer.serialize_str(s),
             Self::Sequence(seq) => seq.serialize(serializer),
             Self::Mapping(map) => map.serialize(serializer),
+            // Serde has no notion of tags, so only the node is serialized
+            Self::Tagged(tagged) => tagged.value.serialize(serializer),
+        }
+    }
+}
+
+/// Shared null returned when indexing finds nothing
+static NULL: Value = Value::Null;
+
+/// Look up a mapping key, returning null if `self` is not a mapping or has no
+/// such key (like `serde_json`)
+impl Index<&str> for Value {
+    type Output = Self;
+
+    fn index(&self, key: &str) -> &Self {
+        self.get_str(key).unwrap_or(&NULL)
+    }
+}
+
+/// Look up a sequence element, returning null if `self` is not a sequence or
+/// the index is out of bounds (like `serde_json`)
+impl Index<usize> for Value {
+    type Output = Self;
+
+    fn index(&self, index: usize) -> &Self {
+        self.get_index(index).unwrap_or(&NULL)
+    }
+}
+
+/// Mutably access a mapping key, inserting null if it is missing
+///
+/// A null value is turned into an empty mapping first.
+///
+/// # Panics
+///
+/// Panics if `self` is neither a mapping nor null.
+impl IndexMut<&str> for Value {
+    fn index_mut(&mut self, key: &str) -> &mut Self {
+        if self.is_null() {
+            *self = Self::mapping();
+        }
+        match self {
+            Self::Mapping(map) => map.entry(Self::from(key)).or_insert(Self::Null),
+            other => panic!("cannot index {} with key {:?}", other.type_name(), key),
+        }
+    }
+}
+
+/// Mutably access a sequence element
+///
+/// # Panics
+///
+/// Panics if `self` is not a sequence or the index is out of bounds.
+impl IndexMut<usize> for Value {
+    fn index_mut(&mut self, index: usize) -> &mut Self {
+        match self {
+            Self::Sequence(seq) => {
+                let len = seq.len();
+                seq.get_mut(index).unwrap_or_else(|| {
+                    panic!("index {index} out of bounds for sequence of length {len}")
+                })
+            }
+            other => panic!("cannot index {} with {}", other.type_name(), index),
+        }
+    }
+}
+
+/// Build a [`Value`] from JSON-like literal syntax
+///
+/// Sequences use `[...]`, mappings use `{key: value, ...}` and `null` is
+/// [`Value::Null`]; anything else is converted with `Value::from`. Mapping
+/// keys are single tokens (a literal or a parenthesized expression).
+///
+/// ```
+/// use rust_yaml::{yaml, Value};
+///
+/// let port = 8080;
+/// let value = yaml!({
+///     "name": "web",
+///     "ports": [port, -1, null],
+///     "tls": {"enabled": true},
+/// });
+/// assert_eq!(value["ports"][0], Value::Int(8080));
+/// assert_eq!(value["tls"]["enabled"], Value::Bool(true));
+/// ```
+#[macro_export]
+macro_rules! yaml {
+    // Sequence elements: collect the tokens of each element up to a comma
+    (@seq [$($done:expr,)*] ()) => {
+        vec![$($done),*]
+    };
+    (@seq [$($done:expr,)*] ($($current:tt)+)) => {
+        vec![$($done,)* $crate::yaml!($($current)+)]
+    };
+    (@seq [$($done:expr,)*] ($($current:tt)+) , $($rest:tt)*) => {
+        $crate::yaml!(@seq [$($done,)* $crate::yaml!($($current)+),] () $($rest)*)
+    };
+    (@seq [$($done:expr,)*] ($($current:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::yaml!(@seq [$($done,)*] ($($current)* $next) $($rest)*)
+    };
+
+    // Mapping entries: a single-token key, then the value's tokens up to a comma
+    (@map [$($done:expr,)*]) => {
+        vec![$($done),*]
+    };
+    (@map [$($done:expr,)*] $key:tt : $($rest:tt)*) => {
+        $crate::yaml!(@entry [$($done,)*] $key () $($rest)*)
+    };
+    (@entry [$($done:expr,)*] $key:tt ($($value:tt)+)) => {
+        vec![$($done,)* ($crate::Value::from($key), $crate::yaml!($($value)+))]
+    };
+    (@entry [$($done:expr,)*] $key:tt ($($value:tt)+) , $($rest:tt)*) => {
+        $crate::yaml!(
+            @map [$($done,)* ($crate::Value::from($key), $crate::yaml!($($value)+)),] $($rest)*
+        )
+    };
+    (@entry [$($done:expr,)*] $key:tt ($($value:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::yaml!(@entry [$($done,)*] $key ($($value)* $next) $($rest)*)
+    };
+
+    (null) => {
+        $crate::Value::Null
+    };
+    ([ $($tt:tt)* ]) => {
+        $crate::Value::Sequence($crate::yaml!(@seq [] () $($tt)*))
+    };
+    ({ $($tt:tt)* }) => {
+        $crate::Value::mapping_with($crate::yaml!(@map [] $($tt)*))
+    };
+    ($($value:tt)+) => {
+        $crate::Value::from($($value)+)
+    };
+}
+
+/// A scalar key as [`Value::get_loose`] compares it
+#[derive(Debug, PartialEq)]
+enum LooseKey<'a> {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(&'a str),
+}
+
+impl<'a> LooseKey<'a> {
+    /// The loose form of `value`, if it is a scalar
+    fn of(value: &'a Value) -> Option<Self> {
+        Some(match value {
+            Value::Null => Self::Null,
+            Value::Bool(b) => Self::Bool(*b),
+            Value::Int(i) => Self::Int(*i),
+            // Whole floats compare equal to the integer they hold
+            Value::Float(f) if f.fract() == 0.0 && f.abs() < i64::MAX as f64 => {
+                Self::Int(*f as i64)
+            }
+            Value::Float(f) => Self::Float(*f),
+            Value::String(s) if s.is_empty() => Self::Text(s),
+            Value::String(s) => match crate::composer::resolve_core_scalar(
+                s.clone(),
+                crate::parser::ScalarStyle::Plain,
+            ) {
+                Value::String(_) => Self::Text(s),
+                resolved => LooseKey::of(&resolved)?.into_owned_scalar()?,
+            },
+            Value::Tagged(tagged) => Self::of(&tagged.value)?,
+            _ => return None,
+        })
+    }
+
+    /// This key detached from the value it was read from, which only a
+    /// non-text key can be
+    const fn into_owned_scalar<'b>(self) -> Option<LooseKey<'b>> {
+        match self {
+            Self::Null => Some(LooseKey::Null),
+            Self::Bool(b) => Some(LooseKey::Bool(b)),
+            Self::Int(i) => Some(LooseKey::Int(i)),
+            Self::Float(f) => Some(LooseKey::Float(f)),
+            Self::Text(_) => None,
         }
     }
 }
@@ -659,6 +2018,34 @@ impl serde::Serialize for Value {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_get_loose() {
+        let value = Value::mapping_with(vec![
+            (Value::Int(1), Value::from("int")),
+            (Value::from("1"), Value::from("string")),
+            (Value::Float(2.5), Value::from("float")),
+            (Value::Bool(false), Value::from("bool")),
+            (Value::Null, Value::from("null")),
+            (Value::from("0x10"), Value::from("hex")),
+            (Value::from(""), Value::from("empty")),
+        ]);
+        let get = |key: Value| value.get_loose(&key).and_then(Value::as_str);
+
+        // Exact matches come first
+        assert_eq!(get(Value::Int(1)), Some("int"));
+        assert_eq!(get(Value::from("1")), Some("string"));
+        assert_eq!(get(Value::Float(1.0)), Some("int"));
+        assert_eq!(get(Value::from("1.0")), Some("int"));
+        assert_eq!(get(Value::from("2.5")), Some("float"));
+        assert_eq!(get(Value::from("false")), Some("bool"));
+        assert_eq!(get(Value::from("~")), Some("null"));
+        assert_eq!(get(Value::Int(16)), Some("hex"));
+        assert_eq!(get(Value::from("")), Some("empty"));
+        assert_eq!(get(Value::from("2")), None);
+        assert_eq!(get(Value::from("no")), None);
+        assert_eq!(Value::Int(1).get_loose(&Value::Int(1)), None);
+    }
+
     #[test]
     fn test_value_creation() {
         assert_eq!(Value::null(), Value::Null);
@@ -762,4 +2149,256 @@ mod tests {
             Value::String("hello".to_string())
         );
     }
+
+    #[test]
+    fn test_index_returns_null_when_missing() {
+        let value = Value::mapping_with(vec![(
+            Value::from("items"),
+            Value::Sequence(vec![Value::from(1), Value::from(2)]),
+        )]);
+        assert_eq!(value["items"][1], Value::Int(2));
+        assert_eq!(value["items"][5], Value::Null);
+        assert_eq!(value["missing"]["deeper"], Value::Null);
+        assert_eq!(value[0], Value::Null);
+    }
+
+    #[test]
+    fn test_index_mut_inserts_keys() {
+        let mut value = Value::Null;
+        value["server"]["port"] = Value::from(8080);
+        value["server"]["port"] = Value::from(9090);
+        assert_eq!(value["server"]["port"], Value::Int(9090));
+
+        let mut seq = Value::Sequence(vec![Value::Null]);
+        seq[0] = Value::from("x");
+        assert_eq!(seq[0], Value::from("x"));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot index int")]
+    fn test_index_mut_panics_on_scalar() {
+        let mut value = Value::Int(1);
+        value["key"] = Value::Null;
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn test_index_mut_panics_out_of_bounds() {
+        let mut value = Value::Sequence(Vec::new());
+        value[0] = Value::Null;
+    }
+
+    #[test]
+    fn test_yaml_macro() {
+        let name = String::from("web");
+        let value = crate::yaml!({
+            "a": [1, 2, {"b": true}],
+            "name": name,
+            "neg": -3,
+            "sum": 1 + 2,
+            "none": null,
+            "empty": {},
+            (String::from("computed")): [],
+        });
+
+        let expected = Value::mapping_with(vec![
+            (
+                Value::from("a"),
+                Value::Sequence(vec![
+                    Value::Int(1),
+                    Value::Int(2),
+                    Value::mapping_with(vec![(Value::from("b"), Value::Bool(true))]),
+                ]),
+            ),
+            (Value::from("name"), Value::from("web")),
+            (Value::from("neg"), Value::Int(-3)),
+            (Value::from("sum"), Value::Int(3)),
+            (Value::from("none"), Value::Null),
+            (Value::from("empty"), Value::mapping()),
+            (Value::from("computed"), Value::Sequence(Vec::new())),
+        ]);
+        assert_eq!(value, expected);
+        assert_eq!(
+            crate::yaml!([null, "x",]),
+            Value::Sequence(vec![Value::Null, Value::from("x")])
+        );
+        assert_eq!(crate::yaml!(2.5), Value::Float(2.5));
+    }
+
+    #[test]
+    fn test_entry_api() {
+        let mut value = Value::Null;
+        value.entry("count").unwrap().or_insert(Value::Int(0));
+        value
+            .entry("count")
+            .unwrap()
+            .and_modify(|count| *count = Value::Int(1))
+            .or_insert(Value::Int(0));
+        value
+            .entry("items")
+            .unwrap()
+            .or_insert_with(|| Value::Sequence(Vec::new()));
+        assert_eq!(value["count"], Value::Int(1));
+        assert_eq!(value["items"], Value::Sequence(Vec::new()));
+
+        assert!(Value::Int(1).entry("key").is_err());
+    }
+
+    #[test]
+    fn test_set_path() {
+        let mut value = Value::Null;
+        value.set_path("server.tls.enabled", true).unwrap();
+        value.set_path("server.port", 8080).unwrap();
+        value.set_path("server.port", 9090).unwrap();
+        assert_eq!(value["server"]["tls"]["enabled"], Value::Bool(true));
+        assert_eq!(value["server"]["port"], Value::Int(9090));
+
+        value["hosts"] = Value::Sequence(vec![Value::mapping()]);
+        value.set_path("hosts.0.name", "a").unwrap();
+        assert_eq!(value["hosts"][0]["name"], Value::from("a"));
+
+        let before = value.clone();
+        assert!(value.set_path("server.port.number", 1).is_err());
+        assert!(value.set_path("hosts.3.name", "b").is_err());
+        assert!(value.set_path("server..port", 1).is_err());
+        assert!(value.set_path("", 1).is_err());
+        assert_eq!(value, before);
+    }
+
+    #[test]
+    fn test_cross_type_ordering() {
+        let mut values = vec![
+            Value::mapping(),
+            Value::Sequence(Vec::new()),
+            Value::from("a"),
+            Value::Float(f64::NAN),
+            Value::Float(1.5),
+            Value::Int(1),
+            Value::Float(1.0),
+            Value::Int(-2),
+            Value::Float(f64::NEG_INFINITY),
+            Value::Bool(true),
+            Value::Bool(false),
+            Value::Null,
+        ];
+        values.sort();
+        assert_eq!(
+            values,
+            vec![
+                Value::Null,
+                Value::Bool(false),
+                Value::Bool(true),
+                Value::Float(f64::NEG_INFINITY),
+                Value::Int(-2),
+                Value::Int(1),
+                Value::Float(1.0),
+                Value::Float(1.5),
+                Value::Float(f64::NAN),
+                Value::from("a"),
+                Value::Sequence(Vec::new()),
+                Value::mapping(),
+            ]
+        );
+        assert!(Value::Int(i64::MAX) < Value::Float(9.3e18));
+        assert_eq!(Value::Float(0.0).cmp(&Value::Float(-0.0)), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_mapping_order_and_hash_ignore_key_order() {
+        use std::collections::BTreeSet;
+
+        let a = crate::yaml!({"x": 1, "y": 2});
+        let b = crate::yaml!({"y": 2, "x": 1});
+        assert_eq!(a, b);
+        assert_eq!(a.cmp(&b), Ordering::Equal);
+
+        let hash = |value: &Value| {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&a), hash(&b));
+
+        let set: BTreeSet<Value> = [a, b, crate::yaml!({"x": 0})].into_iter().collect();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn test_sort_keys() {
+        let mut value = crate::yaml!({"b": [{"z": 1, "a": 2}], "a": null, 3: true});
+        value.sort_keys();
+        let keys: Vec<_> = value.as_mapping().unwrap().keys().cloned().collect();
+        assert_eq!(
+            keys,
+            vec![Value::Int(3), Value::from("a"), Value::from("b")]
+        );
+        let inner: Vec<_> = value["b"][0]
+            .as_mapping()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect();
+        assert_eq!(inner, vec![Value::from("a"), Value::from("z")]);
+    }
+
+    #[test]
+    fn test_btree_map_conversions() {
+        let value = crate::yaml!({"b": 1, "a": 2});
+        let sorted = BTreeMap::try_from(value.clone()).unwrap();
+        assert_eq!(sorted.keys().next(), Some(&Value::from("a")));
+        assert_eq!(Value::from(sorted), value);
+        assert_eq!(BTreeMap::try_from(Value::Int(1)), Err(Value::Int(1)));
+    }
+
+    #[test]
+    fn test_display_truncated() {
+        let value = crate::yaml!({"a": [1, 2, 3], "b": {"c": {"d": 1}}});
+        assert_eq!(
+            value.display_truncated(usize::MAX, usize::MAX).to_string(),
+            value.to_string()
+        );
+        assert_eq!(value.display_truncated(0, 2).to_string(), "{...}");
+        assert_eq!(value.display_truncated(1, 0).to_string(), "{...}");
+        assert_eq!(
+            value.display_truncated(1, 1).to_string(),
+            "{\"a\": [...], ...}"
+        );
+        assert_eq!(Value::Int(1).display_truncated(0, 0).to_string(), "1");
+
+        let unbounded = value.display_truncated(usize::MAX, usize::MAX);
+        assert_eq!(format!("{unbounded:?}"), format!("{value:?}"));
+        assert_eq!(format!("{unbounded:#?}"), format!("{value:#?}"));
+        assert_eq!(
+            format!("{:?}", value.display_truncated(2, 1)),
+            "Mapping({String(\"a\"): Sequence([Int(1), ..]), ..})"
+        );
+        assert_eq!(
+            format!("{:?}", value.display_truncated(1, 2)),
+            "Mapping({String(\"a\"): Sequence(..), String(\"b\"): Mapping(..)})"
+        );
+
+        let tagged = Value::tagged("!set", crate::yaml!([1, 2]));
+        assert_eq!(
+            format!("{:?}", tagged.display_truncated(usize::MAX, usize::MAX)),
+            format!("{tagged:?}")
+        );
+    }
+
+    #[test]
+    fn test_drop_deep_value() {
+        // Built and dropped without recursion, so this would overflow the
+        // stack with a recursive drop
+        {
+            let mut value = Value::Null;
+            for _ in 0..1_000_000 {
+                value = Value::Sequence(vec![value]);
+            }
+            let _mapping = crate::yaml!({"k": value});
+        }
+
+        let mut value = crate::yaml!([1, {"a": "b"}]);
+        let taken = value.take();
+        assert_eq!(value, Value::Null);
+        assert_eq!(taken, crate::yaml!([1, {"a": "b"}]));
+    }
 }
@@ -313,19 +313,24 @@ impl<'a> Eq for BorrowedValue<'a> {}
 
 // Conversion from owned Value to BorrowedValue
 impl<'a> From<crate::Value> for BorrowedValue<'a> {
-    fn from(value: crate::Value) -> Self {
-        match value {
+    fn from(mut value: crate::Value) -> Self {
+        match &mut value {
             crate::Value::Null => Self::Null,
-            crate::Value::Bool(b) => Self::Bool(b),
-            crate::Value::Int(i) => Self::Int(i),
-            crate::Value::Float(f) => Self::Float(f),
-            crate::Value::String(s) => Self::String(Cow::Owned(s)),
+            crate::Value::Bool(b) => Self::Bool(*b),
+            crate::Value::Int(i) => Self::Int(*i),
+            crate::Value::Float(f) => Self::Float(*f),
+            crate::Value::String(s) => Self::String(Cow::Owned(std::mem::take(s))),
             crate::Value::Sequence(seq) => {
-                Self::Sequence(seq.into_iter().map(Into::into).collect())
-            }
-            crate::Value::Mapping(map) => {
-                Self::Mapping(map.into_iter().map(|(k, v)| (k.into(), v.into())).collect())
+                Self::Sequence(std::mem::take(seq).into_iter().map(Into::into).collect())
             }
+            crate::Value::Mapping(map) => Self::Mapping(
+                std::mem::take(map)
+                    .into_iter()
+                    .map(|(k, v)| (k.into(), v.into()))
+                    .collect(),
+            ),
+            // Borrowed values have no tags
+            crate::Value::Tagged(tagged) => tagged.value.take().into(),
         }
     }
 }
@@ -0,0 +1,422 @@
+//! Shared YAML value representation that preserves alias structure
+//!
+//! The regular [`Value`] tree deep-clones every alias into an independent
+//! copy. This module provides a reference-counted representation in which
+//! an alias and its anchor point at the same node, so mutations made through
+//! one are visible through the other and heavily-aliased documents are only
+//! stored once in memory.
+//!
+//! In [`AliasResolution::Graph`] mode the tree may contain cycles. The
+//! traversal helpers here ([`SharedNode::visit`], [`SharedNode::is_cyclic`],
+//! [`SharedNode::try_to_value`]) and the `Debug`/`PartialEq` impls visit each
+//! node at most once per path, so they are safe to use on cyclic graphs.
+
+use crate::{Error, Position, Result, Value};
+use indexmap::IndexMap;
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::rc::Rc;
+
+thread_local! {
+    /// Nodes currently being formatted, used to cut cycles in `Debug`
+    static DEBUG_PATH: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+}
+
+/// How aliases are materialized when composing a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AliasResolution {
+    /// Every alias becomes an independent deep copy of its anchored node
+    #[default]
+    Copy,
+    /// Every alias refers to the same node as its anchor
+    Shared,
+    /// Like `Shared`, but an anchor is visible inside its own node so that
+    /// self-referential documents load as cyclic graphs
+    Graph,
+}
+
+/// A YAML value whose collection children are shared nodes
+#[derive(Debug, Clone)]
+pub enum SharedValue {
+    /// Null value
+    Null,
+    /// Boolean value
+    Bool(bool),
+    /// Integer value
+    Int(i64),
+    /// Floating point value
+    Float(f64),
+    /// String value
+    String(String),
+    /// Sequence of shared nodes
+    Sequence(Vec<SharedNode>),
+    /// Mapping from plain keys to shared nodes
+    Mapping(IndexMap<Value, SharedNode>),
+}
+
+/// A reference-counted handle to a [`SharedValue`]
+///
+/// Cloning a `SharedNode` clones the handle, not the value: both handles
+/// observe the same underlying node.
+#[derive(Clone)]
+pub struct SharedNode(Rc<RefCell<SharedValue>>);
+
+impl SharedNode {
+    /// Create a new node owning the given value
+    pub fn new(value: SharedValue) -> Self {
+        Self(Rc::new(RefCell::new(value)))
+    }
+
+    /// Build a node tree from a plain value, with no sharing
+    pub fn from_value(value: &Value) -> Self {
+        Self::new(SharedValue::from_value(value))
+    }
+
+    /// Immutably borrow the node's value
+    ///
+    /// # Panics
+    /// Panics if the node is currently mutably borrowed.
+    pub fn borrow(&self) -> Ref<'_, SharedValue> {
+        self.0.borrow()
+    }
+
+    /// Mutably borrow the node's value
+    ///
+    /// # Panics
+    /// Panics if the node is currently borrowed.
+    pub fn borrow_mut(&self) -> RefMut<'_, SharedValue> {
+        self.0.borrow_mut()
+    }
+
+    /// Replace the node's value, returning the previous one
+    pub fn replace(&self, value: SharedValue) -> SharedValue {
+        self.0.replace(value)
+    }
+
+    /// Check whether two handles refer to the same node
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// Identity of the underlying node, stable for the node's lifetime
+    pub fn id(&self) -> usize {
+        Rc::as_ptr(&self.0) as usize
+    }
+
+    /// Number of handles currently referring to this node
+    pub fn handle_count(&self) -> usize {
+        Rc::strong_count(&self.0)
+    }
+
+    /// Check whether this node is referenced from more than one place
+    pub fn is_shared(&self) -> bool {
+        self.handle_count() > 1
+    }
+
+    /// Call `f` once for every distinct node reachable from this one,
+    /// in depth-first pre-order
+    pub fn visit<F: FnMut(&Self)>(&self, mut f: F) {
+        let mut seen = HashSet::new();
+        let mut stack = vec![self.clone()];
+        while let Some(node) = stack.pop() {
+            if !seen.insert(node.id()) {
+                continue;
+            }
+            f(&node);
+            match &*node.borrow() {
+                SharedValue::Sequence(seq) => stack.extend(seq.iter().rev().cloned()),
+                SharedValue::Mapping(map) => stack.extend(map.values().rev().cloned()),
+                _ => {}
+            }
+        }
+    }
+
+    /// Check whether any node reachable from this one refers back to
+    /// one of its own ancestors
+    pub fn is_cyclic(&self) -> bool {
+        fn walk(node: &SharedNode, path: &mut HashSet<usize>, done: &mut HashSet<usize>) -> bool {
+            let id = node.id();
+            if path.contains(&id) {
+                return true;
+            }
+            if !done.insert(id) {
+                return false;
+            }
+            path.insert(id);
+            let cyclic = match &*node.borrow() {
+                SharedValue::Sequence(seq) => seq.iter().any(|child| walk(child, path, done)),
+                SharedValue::Mapping(map) => map.values().any(|child| walk(child, path, done)),
+                _ => false,
+            };
+            path.remove(&id);
+            cyclic
+        }
+
+        walk(self, &mut HashSet::new(), &mut HashSet::new())
+    }
+
+    /// Create an independent copy of this node and all of its children
+    ///
+    /// Sharing and cycles inside the copied graph are reproduced among
+    /// the new nodes.
+    pub fn deep_copy(&self) -> Self {
+        fn copy(node: &SharedNode, copies: &mut HashMap<usize, SharedNode>) -> SharedNode {
+            if let Some(existing) = copies.get(&node.id()) {
+                return existing.clone();
+            }
+            let target = SharedNode::new(SharedValue::Null);
+            copies.insert(node.id(), target.clone());
+            let value = match &*node.borrow() {
+                SharedValue::Sequence(seq) => {
+                    SharedValue::Sequence(seq.iter().map(|child| copy(child, copies)).collect())
+                }
+                SharedValue::Mapping(map) => SharedValue::Mapping(
+                    map.iter()
+                        .map(|(k, v)| (k.clone(), copy(v, copies)))
+                        .collect(),
+                ),
+                scalar => scalar.clone(),
+            };
+            target.replace(value);
+            target
+        }
+
+        copy(self, &mut HashMap::new())
+    }
+
+    /// Materialize this node into a plain [`Value`], expanding shared nodes
+    ///
+    /// The graph must be acyclic; use [`SharedNode::try_to_value`] when it
+    /// may not be.
+    pub fn to_value(&self) -> Value {
+        self.borrow().to_value()
+    }
+
+    /// Materialize this node into a plain [`Value`]
+    ///
+    /// # Errors
+    /// Returns an error if the graph contains a cycle, which a plain value
+    /// cannot represent.
+    pub fn try_to_value(&self) -> Result<Value> {
+        if self.is_cyclic() {
+            return Err(Error::value_error(
+                Position::start(),
+                "Cyclic node graph cannot be converted to a plain value",
+            ));
+        }
+        Ok(self.to_value())
+    }
+}
+
+/// Structural equality that treats a revisited pair of nodes as equal
+fn nodes_eq(a: &SharedNode, b: &SharedNode, seen: &mut HashSet<(usize, usize)>) -> bool {
+    if a.ptr_eq(b) || !seen.insert((a.id(), b.id())) {
+        return true;
+    }
+    values_eq(&a.borrow(), &b.borrow(), seen)
+}
+
+fn values_eq(a: &SharedValue, b: &SharedValue, seen: &mut HashSet<(usize, usize)>) -> bool {
+    match (a, b) {
+        (SharedValue::Null, SharedValue::Null) => true,
+        (SharedValue::Bool(x), SharedValue::Bool(y)) => x == y,
+        (SharedValue::Int(x), SharedValue::Int(y)) => x == y,
+        (SharedValue::Float(x), SharedValue::Float(y)) => Value::Float(*x) == Value::Float(*y),
+        (SharedValue::String(x), SharedValue::String(y)) => x == y,
+        (SharedValue::Sequence(x), SharedValue::Sequence(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(a, b)| nodes_eq(a, b, seen))
+        }
+        (SharedValue::Mapping(x), SharedValue::Mapping(y)) => {
+            x.len() == y.len()
+                && x.iter()
+                    .all(|(k, a)| y.get(k).is_some_and(|b| nodes_eq(a, b, seen)))
+        }
+        _ => false,
+    }
+}
+
+impl PartialEq for SharedNode {
+    fn eq(&self, other: &Self) -> bool {
+        nodes_eq(self, other, &mut HashSet::new())
+    }
+}
+
+impl PartialEq for SharedValue {
+    fn eq(&self, other: &Self) -> bool {
+        values_eq(self, other, &mut HashSet::new())
+    }
+}
+
+impl fmt::Debug for SharedNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let id = self.id();
+        if DEBUG_PATH.with(|path| path.borrow().contains(&id)) {
+            return write!(f, "<cycle>");
+        }
+        DEBUG_PATH.with(|path| path.borrow_mut().push(id));
+        let result = fmt::Debug::fmt(&*self.borrow(), f);
+        DEBUG_PATH.with(|path| path.borrow_mut().pop());
+        result
+    }
+}
+
+impl From<SharedValue> for SharedNode {
+    fn from(value: SharedValue) -> Self {
+        Self::new(value)
+    }
+}
+
+impl SharedValue {
+    /// Build a shared value from a plain value, with no sharing
+    pub fn from_value(value: &Value) -> Self {
+        match value {
+            Value::Null => Self::Null,
+            Value::Bool(b) => Self::Bool(*b),
+            Value::Int(i) => Self::Int(*i),
+            Value::Float(f) => Self::Float(*f),
+            Value::String(s) => Self::String(s.clone()),
+            Value::Sequence(seq) => {
+                Self::Sequence(seq.iter().map(SharedNode::from_value).collect())
+            }
+            Value::Mapping(map) => Self::Mapping(
+                map.iter()
+                    .map(|(k, v)| (k.clone(), SharedNode::from_value(v)))
+                    .collect(),
+            ),
+            // Shared values have no tags
+            Value::Tagged(tagged) => Self::from_value(&tagged.value),
+        }
+    }
+
+    /// Materialize this value into a plain [`Value`], expanding shared nodes
+    pub fn to_value(&self) -> Value {
+        match self {
+            Self::Null => Value::Null,
+            Self::Bool(b) => Value::Bool(*b),
+            Self::Int(i) => Value::Int(*i),
+            Self::Float(f) => Value::Float(*f),
+            Self::String(s) => Value::String(s.clone()),
+            Self::Sequence(seq) => Value::Sequence(seq.iter().map(SharedNode::to_value).collect()),
+            Self::Mapping(map) => {
+                Value::Mapping(map.iter().map(|(k, v)| (k.clone(), v.to_value())).collect())
+            }
+        }
+    }
+
+    /// Create an independent copy of this value and all of its children
+    pub fn deep_copy(&self) -> Self {
+        match self {
+            Self::Sequence(seq) => Self::Sequence(seq.iter().map(SharedNode::deep_copy).collect()),
+            Self::Mapping(map) => Self::Mapping(
+                map.iter()
+                    .map(|(k, v)| (k.clone(), v.deep_copy()))
+                    .collect(),
+            ),
+            scalar => scalar.clone(),
+        }
+    }
+
+    /// Get the type name of this value
+    pub const fn type_name(&self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Bool(_) => "bool",
+            Self::Int(_) => "int",
+            Self::Float(_) => "float",
+            Self::String(_) => "string",
+            Self::Sequence(_) => "sequence",
+            Self::Mapping(_) => "mapping",
+        }
+    }
+
+    /// Get the node stored under a string key, if this is a mapping
+    pub fn get_str(&self, key: &str) -> Option<SharedNode> {
+        match self {
+            Self::Mapping(map) => map.get(&Value::String(key.to_string())).cloned(),
+            _ => None,
+        }
+    }
+
+    /// Get the node at a sequence index, if this is a sequence
+    pub fn get_index(&self, index: usize) -> Option<SharedNode> {
+        match self {
+            Self::Sequence(seq) => seq.get(index).cloned(),
+            _ => None,
+        }
+    }
+}
+
+impl From<&Value> for SharedValue {
+    fn from(value: &Value) -> Self {
+        Self::from_value(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_round_trip() {
+        let mut map = IndexMap::new();
+        map.insert(
+            Value::String("items".to_string()),
+            Value::Sequence(vec![Value::Int(1), Value::Float(2.5), Value::Null]),
+        );
+        let value = Value::Mapping(map);
+
+        let node = SharedNode::from_value(&value);
+        assert_eq!(node.to_value(), value);
+    }
+
+    #[test]
+    fn test_clone_shares_node() {
+        let node = SharedNode::new(SharedValue::Int(1));
+        let alias = node.clone();
+        assert!(node.ptr_eq(&alias));
+        assert!(node.is_shared());
+
+        *alias.borrow_mut() = SharedValue::Int(2);
+        assert_eq!(node.to_value(), Value::Int(2));
+    }
+
+    #[test]
+    fn test_deep_copy_is_independent() {
+        let node = SharedNode::from_value(&Value::Sequence(vec![Value::Int(1)]));
+        let copy = node.deep_copy();
+        assert!(!node.ptr_eq(&copy));
+
+        if let SharedValue::Sequence(seq) = &*copy.borrow() {
+            seq[0].replace(SharedValue::Int(9));
+        }
+        assert_eq!(node.to_value(), Value::Sequence(vec![Value::Int(1)]));
+        assert_eq!(copy.to_value(), Value::Sequence(vec![Value::Int(9)]));
+    }
+
+    fn cyclic_node() -> SharedNode {
+        let node = SharedNode::new(SharedValue::Mapping(IndexMap::new()));
+        if let SharedValue::Mapping(map) = &mut *node.borrow_mut() {
+            map.insert(Value::String("self".to_string()), node.clone());
+        }
+        node
+    }
+
+    #[test]
+    fn test_cycle_safe_helpers() {
+        let node = cyclic_node();
+        assert!(node.is_cyclic());
+        assert!(node.try_to_value().is_err());
+        assert_eq!(format!("{node:?}"), "Mapping({String(\"self\"): <cycle>})");
+
+        let mut visited = 0;
+        node.visit(|_| visited += 1);
+        assert_eq!(visited, 1);
+
+        let copy = node.deep_copy();
+        assert!(!copy.ptr_eq(&node));
+        assert!(copy.borrow().get_str("self").unwrap().ptr_eq(&copy));
+        assert_eq!(copy, node);
+        assert_eq!(cyclic_node(), node);
+    }
+}
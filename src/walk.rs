@@ -0,0 +1,396 @@
+//! Depth-first traversal of value trees
+//!
+//! [`Value::walk`] visits every node with its [`Path`], which the search
+//! helpers build on, and [`Value::transform`] changes nodes in place through
+//! a [`Visitor`]:
+//!
+//! ```
+//! use rust_yaml::{yaml, Value};
+//!
+//! let manifest = yaml!({"containers": [{"image": "nginx"}, {"image": "redis"}]});
+//! let images = manifest.values_at_key("image");
+//! assert_eq!(images, vec![&Value::from("nginx"), &Value::from("redis")]);
+//! ```
+
+use crate::{Path, PathSegment, Value};
+
+/// Iterator over the nodes of a value in depth-first order, created by
+/// [`Value::walk`]
+#[derive(Debug, Clone)]
+pub struct Walk<'a> {
+    stack: Vec<(Path, &'a Value)>,
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = (Path, &'a Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, value) = self.stack.pop()?;
+        // Push children in reverse so they come off the stack in order
+        match value {
+            Value::Sequence(seq) => {
+                for (index, item) in seq.iter().enumerate().rev() {
+                    let mut child = path.clone();
+                    child.push(PathSegment::Index(index));
+                    self.stack.push((child, item));
+                }
+            }
+            Value::Mapping(map) => {
+                for (key, item) in map.iter().rev() {
+                    let mut child = path.clone();
+                    child.push(PathSegment::Key(key.clone()));
+                    self.stack.push((child, item));
+                }
+            }
+            _ => {}
+        }
+        Some((path, value))
+    }
+}
+
+/// What [`Value::transform`] does after [`Visitor::enter`] returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visit {
+    /// Visit the nodes below the entered one
+    Continue,
+    /// Leave the nodes below the entered one as they are
+    Skip,
+}
+
+/// Callbacks for [`Value::transform`], called with every node and its path
+///
+/// Both do nothing by default, so a visitor implements the ones it needs.
+pub trait Visitor {
+    /// Called with a node before the nodes below it; the node may be changed
+    /// or replaced, and the nodes below what it then is are visited next
+    fn enter(&mut self, _path: &Path, _value: &mut Value) -> Visit {
+        Visit::Continue
+    }
+
+    /// Called with a node after the nodes below it
+    fn exit(&mut self, _path: &Path, _value: &mut Value) {}
+}
+
+/// A collection whose children [`Value::transform`] is visiting, with the
+/// position of the next one
+#[derive(Debug)]
+struct Transforming {
+    node: Value,
+    next: usize,
+}
+
+impl Transforming {
+    /// Take out the next child to visit, stepping `path` into it
+    fn next_child(&mut self, path: &mut Path) -> Option<Value> {
+        let child = match &mut self.node {
+            Value::Sequence(items) => {
+                let item = items.get_mut(self.next)?;
+                path.push(PathSegment::Index(self.next));
+                item
+            }
+            Value::Mapping(map) => {
+                let (key, value) = map.get_index_mut(self.next)?;
+                path.push(PathSegment::Key(key.clone()));
+                value
+            }
+            // The node of a tagged value has the path of the tagged value
+            Value::Tagged(tagged) if self.next == 0 => &mut tagged.value,
+            _ => return None,
+        };
+        self.next += 1;
+        Some(child.take())
+    }
+
+    /// Put back the child last taken out, stepping `path` out of it
+    fn put_back(&mut self, child: Value, path: &mut Path) {
+        let slot = match &mut self.node {
+            Value::Sequence(items) => items.get_mut(self.next - 1),
+            Value::Mapping(map) => map.get_index_mut(self.next - 1).map(|(_, value)| value),
+            Value::Tagged(tagged) => {
+                tagged.value = child;
+                return;
+            }
+            _ => None,
+        };
+        if let Some(slot) = slot {
+            *slot = child;
+        }
+        path.pop();
+    }
+}
+
+impl Value {
+    /// Iterate over this value and every node below it, parents before
+    /// children, together with their paths
+    ///
+    /// Mapping keys are not visited themselves; they appear in the paths of
+    /// the values under them.
+    pub fn walk(&self) -> Walk<'_> {
+        Walk {
+            stack: vec![(Path::new(), self)],
+        }
+    }
+
+    /// Pass this value and every node below it to `visitor`, parents before
+    /// children, together with their paths
+    ///
+    /// As with [`Value::walk`], mapping keys are only seen in paths. The
+    /// node of a tagged value is visited after it, with the same path. Nodes
+    /// are visited from an explicit stack, so values of any depth can be
+    /// transformed.
+    ///
+    /// ```
+    /// use rust_yaml::{yaml, Path, PathSegment, Value, Visit, Visitor};
+    ///
+    /// struct Redact;
+    ///
+    /// impl Visitor for Redact {
+    ///     fn enter(&mut self, path: &Path, value: &mut Value) -> Visit {
+    ///         match path.segments().last() {
+    ///             Some(PathSegment::Key(Value::String(key))) if key == "password" => {
+    ///                 *value = Value::from("***");
+    ///                 Visit::Skip
+    ///             }
+    ///             _ => Visit::Continue,
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let mut config = yaml!({"db": {"user": "app", "password": "hunter2"}});
+    /// config.transform(&mut Redact);
+    /// assert_eq!(config, yaml!({"db": {"user": "app", "password": "***"}}));
+    /// ```
+    pub fn transform<V: Visitor + ?Sized>(&mut self, visitor: &mut V) {
+        let mut path = Path::new();
+        let mut stack = Vec::new();
+        let mut node = self.take();
+        loop {
+            let visit = visitor.enter(&path, &mut node);
+            let mut finished = match node {
+                Self::Sequence(_) | Self::Mapping(_) | Self::Tagged(_)
+                    if visit == Visit::Continue =>
+                {
+                    stack.push(Transforming { node, next: 0 });
+                    None
+                }
+                mut node => {
+                    visitor.exit(&path, &mut node);
+                    Some(node)
+                }
+            };
+
+            // Put finished nodes back into their collections, finishing
+            // those with no children left, until one has a child to visit
+            node = loop {
+                let Some(collection) = stack.last_mut() else {
+                    if let Some(root) = finished {
+                        *self = root;
+                    }
+                    return;
+                };
+                if let Some(child) = finished.take() {
+                    collection.put_back(child, &mut path);
+                }
+                if let Some(child) = collection.next_child(&mut path) {
+                    break child;
+                }
+                if let Some(Transforming { mut node, .. }) = stack.pop() {
+                    visitor.exit(&path, &mut node);
+                    finished = Some(node);
+                }
+            };
+        }
+    }
+
+    /// Every node matching `predicate`, in depth-first order
+    pub fn find_all<F>(&self, mut predicate: F) -> Vec<(Path, &Self)>
+    where
+        F: FnMut(&Self) -> bool,
+    {
+        self.walk().filter(|(_, value)| predicate(value)).collect()
+    }
+
+    /// Every value stored under the string key `key` at any depth, in
+    /// depth-first order
+    pub fn values_at_key(&self, key: &str) -> Vec<&Self> {
+        self.walk()
+            .filter(|(path, _)| {
+                matches!(
+                    path.segments().last(),
+                    Some(PathSegment::Key(Self::String(name))) if name == key
+                )
+            })
+            .map(|(_, value)| value)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_walk_order_and_paths() {
+        let value = crate::yaml!({"a": [1, {"b": 2}], "c": 3});
+        let visited: Vec<_> = value
+            .walk()
+            .map(|(path, node)| (path.to_string(), node.type_name()))
+            .collect();
+        assert_eq!(
+            visited,
+            vec![
+                (String::new(), "mapping"),
+                ("a".to_string(), "sequence"),
+                ("a[0]".to_string(), "int"),
+                ("a[1]".to_string(), "mapping"),
+                ("a[1].b".to_string(), "int"),
+                ("c".to_string(), "int"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_all_and_values_at_key() {
+        let value = crate::yaml!({
+            "image": "top",
+            "spec": {"containers": [{"image": "nginx"}, {"name": "sidecar", "image": null}]},
+        });
+
+        let found = value.find_all(|node| node.as_str().is_some_and(|s| s.contains('i')));
+        let paths: Vec<_> = found.iter().map(|(path, _)| path.to_string()).collect();
+        assert_eq!(
+            paths,
+            vec!["spec.containers[0].image", "spec.containers[1].name"]
+        );
+
+        assert_eq!(
+            value.values_at_key("image"),
+            vec![&Value::from("top"), &Value::from("nginx"), &Value::Null]
+        );
+        assert!(value.values_at_key("missing").is_empty());
+    }
+
+    /// Records the nodes it is called with
+    #[derive(Default)]
+    struct Trace(Vec<String>);
+
+    impl Visitor for Trace {
+        fn enter(&mut self, path: &Path, value: &mut Value) -> Visit {
+            self.0.push(format!("enter {path} {}", value.type_name()));
+            Visit::Continue
+        }
+
+        fn exit(&mut self, path: &Path, value: &mut Value) {
+            self.0.push(format!("exit {path} {}", value.type_name()));
+        }
+    }
+
+    #[test]
+    fn test_transform_order_and_paths() {
+        let mut value = crate::yaml!({"a": [1, {"b": 2}], "c": 3});
+        let original = value.clone();
+        let mut trace = Trace::default();
+        value.transform(&mut trace);
+        assert_eq!(value, original);
+        assert_eq!(
+            trace.0,
+            vec![
+                "enter  mapping",
+                "enter a sequence",
+                "enter a[0] int",
+                "exit a[0] int",
+                "enter a[1] mapping",
+                "enter a[1].b int",
+                "exit a[1].b int",
+                "exit a[1] mapping",
+                "exit a sequence",
+                "enter c int",
+                "exit c int",
+                "exit  mapping",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_transform_replaces_and_skips() {
+        /// Expands `expand` into a sequence, leaves `keep` alone and reads
+        /// numeric strings as integers everywhere else
+        struct Coerce;
+
+        impl Visitor for Coerce {
+            fn enter(&mut self, path: &Path, value: &mut Value) -> Visit {
+                match path.segments().last() {
+                    Some(PathSegment::Key(Value::String(key))) if key == "keep" => Visit::Skip,
+                    Some(PathSegment::Key(Value::String(key))) if key == "expand" => {
+                        *value = crate::yaml!(["1", "2"]);
+                        Visit::Continue
+                    }
+                    _ => Visit::Continue,
+                }
+            }
+
+            fn exit(&mut self, _path: &Path, value: &mut Value) {
+                if let Some(number) = value.as_str().and_then(|s| s.parse().ok()) {
+                    *value = Value::Int(number);
+                }
+            }
+        }
+
+        let mut value = crate::yaml!({"port": "80", "keep": ["443"], "expand": null});
+        value.transform(&mut Coerce);
+        assert_eq!(
+            value,
+            crate::yaml!({"port": 80, "keep": ["443"], "expand": [1, 2]})
+        );
+
+        // The node of a tagged value is visited at the same path
+        let mut value = Value::Tagged(Box::new(crate::TaggedValue {
+            tag: "!port".to_string(),
+            value: Value::from("8080"),
+        }));
+        let mut trace = Trace::default();
+        value.transform(&mut trace);
+        assert_eq!(
+            trace.0,
+            vec![
+                "enter  tagged",
+                "enter  string",
+                "exit  string",
+                "exit  tagged"
+            ]
+        );
+        value.transform(&mut Coerce);
+        assert_eq!(value.untagged(), &Value::Int(8080));
+    }
+
+    #[test]
+    fn test_transform_deep_value() {
+        /// Counts the nodes it enters and doubles integers
+        struct Double(usize);
+
+        impl Visitor for Double {
+            fn enter(&mut self, _path: &Path, value: &mut Value) -> Visit {
+                self.0 += 1;
+                if let Value::Int(i) = value {
+                    *i *= 2;
+                }
+                Visit::Continue
+            }
+        }
+
+        let depth = 100_000;
+        let mut value = Value::Int(21);
+        for _ in 0..depth {
+            value = Value::Sequence(vec![value]);
+        }
+        let mut double = Double(0);
+        value.transform(&mut double);
+        assert_eq!(double.0, depth + 1);
+
+        let mut node = &value;
+        while let Value::Sequence(seq) = node {
+            node = &seq[0];
+        }
+        assert_eq!(node, &Value::Int(42));
+    }
+}
@@ -0,0 +1,169 @@
+//! WebAssembly bindings (`wasm` feature)
+//!
+//! Exposes a small js-yaml style API through `wasm-bindgen`:
+//!
+//! ```text
+//! import { parse, parseAll, stringify } from "rust-yaml";
+//!
+//! parse("a: 1\nb: [true, null]");   // { a: 1, b: [true, null] }
+//! stringify({ a: 1 }, { indent: 4 }); // "a: 1\n"
+//! ```
+//!
+//! Values map the same way js-yaml maps them: mappings become plain objects
+//! (non-string keys are stringified), sequences become arrays, integers and
+//! floats become numbers and null becomes `null`.
+//!
+//! Build for the web with `--no-default-features --features wasm`, since the
+//! default `mmap` feature is not available on `wasm32-unknown-unknown`, and
+//! as a `cdylib` for `wasm-bindgen` to process:
+//!
+//! ```text
+//! cargo rustc --lib --crate-type cdylib --release --no-default-features \
+//!     --features wasm --target wasm32-unknown-unknown
+//! wasm-bindgen --target web --out-dir pkg \
+//!     target/wasm32-unknown-unknown/release/rust_yaml.wasm
+//! ```
+
+// `#[wasm_bindgen]` expands to `unsafe` ABI shims.
+#![allow(unsafe_code)]
+
+use crate::yaml::IndentConfig;
+use crate::{Value, Yaml, YamlConfig};
+use indexmap::IndexMap;
+use js_sys::{Array, Object, Reflect};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Largest integer a JS number represents exactly (`Number.MAX_SAFE_INTEGER`)
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+/// Parse a single YAML document into a JavaScript value
+#[wasm_bindgen]
+pub fn parse(input: &str) -> Result<JsValue, JsError> {
+    let value = Yaml::new().load_str(input).map_err(to_js_error)?;
+    to_js(&value)
+}
+
+/// Parse every document of a YAML stream into an array of JavaScript values
+#[wasm_bindgen(js_name = parseAll)]
+pub fn parse_all(input: &str) -> Result<JsValue, JsError> {
+    let documents = Yaml::new().load_all_str(input).map_err(to_js_error)?;
+    let array = Array::new();
+    for document in &documents {
+        array.push(&to_js(document)?);
+    }
+    Ok(array.into())
+}
+
+/// Serialize a JavaScript value to YAML
+///
+/// `options` may be `undefined` or an object with an `indent` number.
+#[wasm_bindgen]
+pub fn stringify(value: JsValue, options: JsValue) -> Result<String, JsError> {
+    let mut config = YamlConfig::default();
+    if options.is_object() {
+        let indent = Reflect::get(&options, &JsValue::from_str("indent"))
+            .map_err(|_| JsError::new("invalid options object"))?;
+        if let Some(indent) = indent.as_f64() {
+            if indent < 1.0 || indent.fract() != 0.0 {
+                return Err(JsError::new("indent must be a positive integer"));
+            }
+            config.indent = IndentConfig {
+                indent: indent as usize,
+                ..config.indent
+            };
+        }
+    }
+
+    let value = from_js(&value)?;
+    Yaml::with_config(config)
+        .dump_str(&value)
+        .map_err(to_js_error)
+}
+
+fn to_js_error(error: crate::Error) -> JsError {
+    JsError::new(&error.to_string())
+}
+
+fn to_js(value: &Value) -> Result<JsValue, JsError> {
+    Ok(match value {
+        Value::Null => JsValue::NULL,
+        Value::Bool(b) => JsValue::from_bool(*b),
+        Value::Int(i) => JsValue::from_f64(*i as f64),
+        Value::Float(f) => JsValue::from_f64(*f),
+        Value::String(s) => JsValue::from_str(s),
+        Value::Sequence(seq) => {
+            let array = Array::new();
+            for item in seq {
+                array.push(&to_js(item)?);
+            }
+            array.into()
+        }
+        Value::Mapping(map) => {
+            let object = Object::new();
+            for (key, item) in map {
+                let key = match key {
+                    Value::String(s) => JsValue::from_str(s),
+                    other => JsValue::from_str(&other.to_string()),
+                };
+                Reflect::set(&object, &key, &to_js(item)?)
+                    .map_err(|_| JsError::new("failed to set object property"))?;
+            }
+            object.into()
+        }
+        Value::Tagged(tagged) => to_js(&tagged.value)?,
+    })
+}
+
+fn from_js(value: &JsValue) -> Result<Value, JsError> {
+    if value.is_null() || value.is_undefined() {
+        return Ok(Value::Null);
+    }
+    if let Some(b) = value.as_bool() {
+        return Ok(Value::Bool(b));
+    }
+    if let Some(n) = value.as_f64() {
+        return Ok(if n.fract() == 0.0 && n.abs() <= MAX_SAFE_INTEGER {
+            Value::Int(n as i64)
+        } else {
+            Value::Float(n)
+        });
+    }
+    if value.is_bigint() {
+        let text = String::from(
+            value
+                .dyn_ref::<js_sys::BigInt>()
+                .and_then(|big| big.to_string(10).ok())
+                .ok_or_else(|| JsError::new("invalid BigInt"))?,
+        );
+        return text
+            .parse::<i64>()
+            .map(Value::Int)
+            .map_err(|_| JsError::new("BigInt out of 64-bit range"));
+    }
+    if let Some(s) = value.as_string() {
+        return Ok(Value::String(s));
+    }
+    if Array::is_array(value) {
+        let array: &Array = value.unchecked_ref();
+        return array
+            .iter()
+            .map(|item| from_js(&item))
+            .collect::<Result<_, _>>()
+            .map(Value::Sequence);
+    }
+    if value.is_object() {
+        let object: &Object = value.unchecked_ref();
+        let mut map = IndexMap::new();
+        for entry in Object::entries(object).iter() {
+            let entry: Array = entry.unchecked_into();
+            let key = entry
+                .get(0)
+                .as_string()
+                .ok_or_else(|| JsError::new("object key is not a string"))?;
+            map.insert(Value::String(key), from_js(&entry.get(1))?);
+        }
+        return Ok(Value::Mapping(map));
+    }
+    Err(JsError::new("value cannot be represented in YAML"))
+}
@@ -1,10 +1,22 @@
 //! Main YAML API interface
 
+use crate::fingerprint::Fingerprint;
+use crate::handler::{HandlerDriver, PathProbe};
+use crate::profiling::Stopwatch;
+use crate::schema::node_positions;
+use crate::tag::{TagHandlers, TagResolver, UnknownHandle};
 use crate::{
-    BasicEmitter, CommentPreservingConstructor, CommentedValue, Constructor, Emitter, Limits,
-    Result, RoundTripConstructor, SafeConstructor, Schema, SchemaValidator, Value,
+    AliasResolution, AnchorNamer, AnchorRedefinition, AnchorScope, BasicComposer, BasicEmitter,
+    BasicParser, BasicScanner, BoolStyle, CommentPreservingComposer, CommentPreservingConstructor,
+    CommentedValue, Constructor, Document, DocumentHandler, DumpStats, Emitter, EmptyScalar,
+    FinalNewline, FlowStyle, InvalidDirective, KeyCoercion, Limits, LineEnding, MaskedTemplate,
+    MergePrecedence, NullStyle, ParseStats, Parser, Path, RedactionRules, ResourceHook, Result,
+    RoundTripConstructor, SafeConstructor, Schema, SchemaValidator, SharedComposer, SharedNode,
+    SharedValue, StyleMap, TagEmission, TagHandleNamer, Value,
 };
-use std::io::{Read, Write};
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::sync::Arc;
 
 /// Configuration for YAML processing
 #[derive(Debug, Clone)]
@@ -15,7 +27,9 @@ pub struct YamlConfig {
     pub pure: bool,
     /// Whether to preserve quote styles during round-trip
     pub preserve_quotes: bool,
-    /// Default flow style for output
+    /// Default flow style for output; when set, `Some(true)` and
+    /// `Some(false)` take precedence over `flow_style` as [`FlowStyle::Flow`]
+    /// and [`FlowStyle::Block`]
     pub default_flow_style: Option<bool>,
     /// Whether to allow duplicate keys
     pub allow_duplicate_keys: bool,
@@ -37,21 +51,138 @@ pub struct YamlConfig {
     pub limits: Limits,
     /// Enable safe mode (restricts dangerous features)
     pub safe_mode: bool,
-    /// Enable strict mode (fail on ambiguous constructs)
+    /// Enable strict mode (fail on ambiguous constructs, such as an invalid
+    /// escape sequence in a double-quoted scalar)
     pub strict_mode: bool,
+    /// How aliases are materialized by the shared loading API
+    pub alias_resolution: AliasResolution,
+    /// Keep merge keys (`<<`) unresolved in round-trip mode so they are re-emitted
+    pub preserve_merge_keys: bool,
+    /// Override order used when resolving merge keys with several sources
+    pub merge_precedence: MergePrecedence,
+    /// How a document that defines the same anchor twice is handled; warnings
+    /// are reported in [`Document::warnings`]
+    pub anchor_redefinition: AnchorRedefinition,
+    /// Whether anchors defined in one document of a stream can be aliased
+    /// from the following documents
+    pub anchor_scope: AnchorScope,
+    /// Read Helm/Jinja template spans such as `{{ .Values.x }}` as opaque
+    /// text instead of failing on them (see [`MaskedTemplate`])
+    pub template_placeholders: bool,
+    /// How null values are written; round-trip values keep their original style
+    pub null_style: NullStyle,
+    /// How booleans are written; round-trip values keep their original literal
+    pub bool_style: BoolStyle,
+    /// Whether `dump` writes mapping keys in sorted order (see the `Ord` impl
+    /// of [`Value`] for how keys of different types are ordered); on by
+    /// default with the `sort-keys` feature
+    pub sort_keys: bool,
+    /// Line breaks written by `dump`; [`LineEnding::Preserve`] reuses those
+    /// recorded when a round-trip value was loaded
+    pub line_ending: LineEnding,
+    /// How `dump` ends its output; [`FinalNewline::Preserve`] reuses the
+    /// trailing line breaks recorded when a round-trip value was loaded
+    pub final_newline: FinalNewline,
+    /// Whether `dump` starts its output with a byte order mark; round-trip
+    /// values record whether their source had one in [`crate::Style::bom`]
+    pub emit_bom: bool,
+    /// How `dump` writes collections; [`FlowStyle::Auto`] inlines small
+    /// collections that fit within `width`
+    pub flow_style: FlowStyle,
+    /// YAML version that `dump` targets; when set, a `%YAML` directive is
+    /// written and plain scalars that parsers of that version would not read
+    /// as strings are quoted
+    pub yaml_version: Option<(u8, u8)>,
+    /// Fewest nodes a repeated collection needs for `dump` to write it once
+    /// with an anchor and then as aliases; `None` never adds aliases
+    pub alias_threshold: Option<usize>,
+    /// How `dump` names the anchors it adds for repeated collections; `None`
+    /// numbers them, [`PathAnchorNamer`](crate::PathAnchorNamer) names them
+    /// after their key paths
+    pub anchor_namer: Option<Arc<dyn AnchorNamer>>,
+    /// Fewest tags that must share a URI prefix for `dump` to add a `%TAG`
+    /// directive for it and write them in short form; `None` adds none
+    pub tag_prefix_threshold: Option<usize>,
+    /// How `dump` names the handles of the `%TAG` directives it adds; `None`
+    /// names them after their prefix, such as `!example!`
+    pub tag_handle_namer: Option<Arc<dyn TagHandleNamer>>,
+    /// What an empty plain scalar, such as the value of `key:`, is loaded as;
+    /// [`EmptyScalar::EmptyString`] restores the behavior of loaders that
+    /// read it as `""`
+    pub empty_scalar: EmptyScalar,
+    /// How the scalar keys of mappings are canonicalized when loading;
+    /// [`KeyCoercion::StringifyScalars`] gives data read as JSON string
+    /// keys only
+    pub key_coercion: KeyCoercion,
+    /// Hook called with the resource usage counters as they grow while
+    /// loading, for accounting beyond [`YamlConfig::limits`]
+    pub resource_hook: Option<Arc<dyn ResourceHook>>,
+    /// Handlers that construct values with custom tags when loading
+    pub tag_handlers: TagHandlers,
+    /// How a named tag handle such as `!e!` without a matching `%TAG`
+    /// directive is resolved; an error by default, as the spec requires
+    pub unknown_handle: UnknownHandle,
+    /// Whether an application tag without a registered handler, such as
+    /// `!Ref`, fails the load instead of being kept on a
+    /// [`Value::Tagged`]; ignored by [`LoaderType::Full`]
+    pub reject_unknown_tags: bool,
+    /// Whether a sequence or mapping used as a mapping key, such as
+    /// `? [a, b]`, fails the load; ignored by [`LoaderType::Full`]
+    pub reject_complex_keys: bool,
+    /// Which scalars `dump` writes with an explicit core schema tag such as
+    /// `!!int`
+    pub tag_emission: TagEmission,
+    /// Column, counted from 1, that `dump` lines trailing comments up at;
+    /// `None` writes them one space after their value and `Some(0)` keeps
+    /// the column recorded when a round-trip value was loaded
+    pub align_trailing_comments: Option<usize>,
+    /// What to do with directives the YAML spec does not accept, such as
+    /// `%YAML 2.0`; warnings are reported in [`Document::warnings`]
+    pub invalid_directive: InvalidDirective,
+    /// Rules hiding the values of keys such as `password` whenever a value
+    /// is dumped, for logging configurations safely; `None` dumps values as
+    /// they are
+    pub redaction: Option<RedactionRules>,
+    /// Schema whose property order and descriptions mappings are dumped
+    /// with, for self-documenting configuration files; `None` dumps
+    /// mappings as they are, and keys the schema does not list keep the
+    /// order set by [`sort_keys`](Self::sort_keys)
+    pub dump_schema: Option<Schema>,
 }
 
 /// Type of YAML loader/dumper
+///
+/// Selects how [`Yaml::load_str`] and the other loads building a [`Value`]
+/// type scalars and which tags and keys they accept. No loader runs code
+/// named by a document: an application tag is only constructed by a
+/// handler registered in [`YamlConfig::tag_handlers`], and is otherwise
+/// kept on a [`Value::Tagged`] or rejected.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LoaderType {
-    /// Safe loader - only basic YAML types, no code execution
+    /// Plain data: untagged scalars are typed as YAML 1.1 and 1.2 documents
+    /// commonly expect (`yes` and `off` are booleans), and application tags
+    /// are kept; [`YamlConfig::reject_unknown_tags`] and
+    /// [`YamlConfig::reject_complex_keys`] make such tags and collection
+    /// keys errors
     Safe,
-    /// Base loader - minimal type set
+    /// No implicit typing: every untagged scalar, including an empty one,
+    /// loads as a string, as in the YAML failsafe schema; explicit tags
+    /// such as `!!int` are still applied
     Base,
-    /// Round-trip loader - preserves formatting and comments (future)
+    /// As [`Safe`](Self::Safe), and the loader
+    /// [`Yaml::load_str_with_comments`] requires to keep comments and
+    /// styles for writing a document back out
     RoundTrip,
-    /// Full loader - all features including potentially unsafe operations
+    /// As [`Safe`](Self::Safe), but application tags and collection keys
+    /// are always accepted, whatever the `reject_` options say
     Full,
+    /// YAML 1.2.2 core schema typing only: `true`/`false` and `null`/`~`
+    /// in lower, title or upper case, and numbers matching the core
+    /// patterns, with `yes`, `0b1` or `1_000` loading as strings; tags
+    /// other than the core `!!null`, `!!bool`, `!!int`, `!!float`, `!!str`,
+    /// `!!seq` and `!!map` are errors unless a handler constructs them, and
+    /// a core tag's scalar must match it, so `!!bool yes` is an error
+    Strict,
 }
 
 /// Indentation configuration
@@ -85,6 +216,36 @@ impl Default for YamlConfig {
             limits: Limits::default(),
             safe_mode: false,
             strict_mode: false,
+            alias_resolution: AliasResolution::Copy,
+            preserve_merge_keys: false,
+            merge_precedence: MergePrecedence::Spec,
+            anchor_redefinition: AnchorRedefinition::Allow,
+            anchor_scope: AnchorScope::Document,
+            template_placeholders: false,
+            null_style: NullStyle::Null,
+            bool_style: BoolStyle::TrueFalse,
+            sort_keys: cfg!(feature = "sort-keys"),
+            line_ending: LineEnding::Lf,
+            final_newline: FinalNewline::Single,
+            emit_bom: false,
+            flow_style: FlowStyle::Block,
+            yaml_version: None,
+            alias_threshold: Some(2),
+            anchor_namer: None,
+            tag_prefix_threshold: None,
+            tag_handle_namer: None,
+            empty_scalar: EmptyScalar::Null,
+            key_coercion: KeyCoercion::None,
+            resource_hook: None,
+            tag_handlers: TagHandlers::new(),
+            unknown_handle: UnknownHandle::Error,
+            reject_unknown_tags: false,
+            reject_complex_keys: false,
+            tag_emission: TagEmission::Never,
+            align_trailing_comments: None,
+            invalid_directive: InvalidDirective::Error,
+            redaction: None,
+            dump_schema: None,
         }
     }
 }
@@ -108,8 +269,46 @@ impl YamlConfig {
             limits: Limits::strict(),
             safe_mode: true,
             strict_mode: true,
+            alias_resolution: AliasResolution::Copy,
+            preserve_merge_keys: false,
+            merge_precedence: MergePrecedence::Spec,
+            anchor_redefinition: AnchorRedefinition::Allow,
+            anchor_scope: AnchorScope::Document,
+            template_placeholders: false,
+            null_style: NullStyle::Null,
+            bool_style: BoolStyle::TrueFalse,
+            sort_keys: cfg!(feature = "sort-keys"),
+            line_ending: LineEnding::Lf,
+            final_newline: FinalNewline::Single,
+            emit_bom: false,
+            flow_style: FlowStyle::Block,
+            yaml_version: None,
+            alias_threshold: Some(2),
+            anchor_namer: None,
+            tag_prefix_threshold: None,
+            tag_handle_namer: None,
+            empty_scalar: EmptyScalar::Null,
+            key_coercion: KeyCoercion::None,
+            resource_hook: None,
+            tag_handlers: TagHandlers::new(),
+            unknown_handle: UnknownHandle::Error,
+            reject_unknown_tags: true,
+            reject_complex_keys: true,
+            tag_emission: TagEmission::Never,
+            align_trailing_comments: None,
+            invalid_directive: InvalidDirective::Error,
+            redaction: None,
+            dump_schema: None,
         }
     }
+
+    /// Use `limits` for loading, e.g. [`Limits::strict`],
+    /// [`Limits::permissive`] or [`Limits::untrusted_input`]
+    #[must_use]
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
 }
 
 impl Default for IndentConfig {
@@ -169,8 +368,10 @@ impl Yaml {
         let mut buffer = String::new();
         reader.read_to_string(&mut buffer)?;
 
-        // For now, return a placeholder implementation
-        // This will be replaced with the actual parser implementation
+        if self.config.template_placeholders {
+            let masked = MaskedTemplate::new(&buffer);
+            return Ok(masked.restore(self.parse_yaml_string(masked.text())?));
+        }
         self.parse_yaml_string(&buffer)
     }
 
@@ -184,23 +385,102 @@ impl Yaml {
         let mut buffer = String::new();
         reader.read_to_string(&mut buffer)?;
 
-        // For now, return a placeholder implementation
-        // This will be replaced with the actual parser implementation
+        if self.config.template_placeholders {
+            let masked = MaskedTemplate::new(&buffer);
+            let documents = self.parse_yaml_documents(masked.text())?;
+            return Ok(documents
+                .into_iter()
+                .map(|value| masked.restore(value))
+                .collect());
+        }
         self.parse_yaml_documents(&buffer)
     }
 
+    /// Load YAML from bytes in any encoding the YAML spec allows
+    ///
+    /// UTF-8, UTF-16 and UTF-32 are detected from the byte order mark or the
+    /// leading null bytes. Error positions index into `input` itself rather
+    /// than the decoded text.
+    pub fn load_bytes(&self, input: &[u8]) -> Result<Value> {
+        let decoded = crate::encoding::decode(input)?;
+        self.load_str(&decoded.text)
+            .map_err(|error| error.map_positions(&|position| decoded.original_position(position)))
+    }
+
+    /// Load all YAML documents from bytes in any encoding the YAML spec allows
+    pub fn load_all_bytes(&self, input: &[u8]) -> Result<Vec<Value>> {
+        let decoded = crate::encoding::decode(input)?;
+        self.load_all_str(&decoded.text)
+            .map_err(|error| error.map_positions(&|position| decoded.original_position(position)))
+    }
+
+    /// Load the first document of a string with its `%YAML`/`%TAG`
+    /// directives and the paths of its anchors
+    pub fn load_document(&self, input: &str) -> Result<Document> {
+        let mut constructor = self.safe_constructor(input);
+        Ok(constructor
+            .construct_document()?
+            .unwrap_or_else(|| Document::new(Value::Null)))
+    }
+
+    /// Parse a string into the event notation of the YAML test suite, one
+    /// event per line (see [`crate::Event::to_test_format`])
+    ///
+    /// Useful for comparing the parser with other implementations and for
+    /// reporting parser discrepancies.
+    pub fn event_trace(&self, input: &str) -> Result<String> {
+        let mut parser =
+            BasicParser::try_new_eager_with_limits(input.to_string(), self.config.limits.clone())?;
+        let mut trace = String::new();
+        while let Some(event) = parser.get_event()? {
+            trace.push_str(&event.to_test_format());
+            trace.push('\n');
+        }
+        Ok(trace)
+    }
+
+    /// Load YAML from a string, also returning counters and timing for the load
+    pub fn load_str_with_stats(&self, input: &str) -> Result<(Value, ParseStats)> {
+        let stopwatch = Stopwatch::start();
+        let mut constructor = self.safe_constructor(input);
+        let value = constructor.construct()?;
+        let documents = usize::from(value.is_some());
+        let stats = Self::parse_stats(input, &constructor, documents, stopwatch);
+        Ok((value.unwrap_or(Value::Null), stats))
+    }
+
+    /// Load all YAML documents from a string, also returning counters and
+    /// timing for the load
+    pub fn load_all_str_with_stats(&self, input: &str) -> Result<(Vec<Value>, ParseStats)> {
+        let stopwatch = Stopwatch::start();
+        let mut constructor = self.safe_constructor(input);
+        let documents = Self::construct_documents(&mut constructor)?;
+        let stats = Self::parse_stats(input, &constructor, documents.len(), stopwatch);
+        Ok((documents, stats))
+    }
+
     /// Dump a YAML value to a string
     pub fn dump_str(&self, value: &Value) -> Result<String> {
-        let mut buffer = Vec::new();
-        self.dump(value, &mut buffer)?;
-        Ok(String::from_utf8(buffer)?)
+        let mut output = String::new();
+        self.dump_fmt(value, &mut output)?;
+        Ok(output)
     }
 
     /// Dump a YAML value to a writer
     pub fn dump<W: Write>(&self, value: &Value, writer: W) -> Result<()> {
-        // For now, return a placeholder implementation
-        // This will be replaced with the actual emitter implementation
-        self.emit_yaml_value(value, writer)
+        self.write_formatted(None, writer, |writer| self.emit_yaml_value(value, writer))
+    }
+
+    /// Dump a YAML value to a [`fmt::Write`], such as a `String` or a
+    /// [`fmt::Formatter`]
+    ///
+    /// The output streams into `writer` as it is emitted.
+    pub fn dump_fmt<W: fmt::Write + ?Sized>(&self, value: &Value, writer: &mut W) -> Result<()> {
+        let mut writer = FmtWriter {
+            inner: writer,
+            partial: Vec::new(),
+        };
+        self.dump(value, &mut writer)
     }
 
     /// Dump all YAML documents to a string
@@ -211,25 +491,265 @@ impl Yaml {
     }
 
     /// Dump all YAML documents to a writer
-    pub fn dump_all<W: Write>(&self, values: &[Value], mut writer: W) -> Result<()> {
-        for (i, value) in values.iter().enumerate() {
-            if i > 0 {
-                writeln!(writer, "---")?;
+    pub fn dump_all<W: Write>(&self, values: &[Value], writer: W) -> Result<()> {
+        self.write_formatted(None, writer, |writer| {
+            self.emit_yaml_documents(values.iter().map(Ok), writer)
+        })
+    }
+
+    /// Dump the documents `values` produces to a writer one at a time, as
+    /// [`dump_all`](Self::dump_all) would write them
+    ///
+    /// Each document is written and the writer flushed before the next one
+    /// is taken, so a stream of any length is never held in memory. The
+    /// first error, from `values` or from writing, stops the dump with the
+    /// documents before it already written.
+    ///
+    /// ```
+    /// use rust_yaml::{yaml, Yaml};
+    ///
+    /// let rows = (1..=2).map(|id| Ok(yaml!({"id": id})));
+    /// let mut output = Vec::new();
+    /// Yaml::new().dump_stream(rows, &mut output).unwrap();
+    /// assert_eq!(String::from_utf8(output).unwrap(), "id: 1\n---\nid: 2\n");
+    /// ```
+    pub fn dump_stream<I, W>(&self, values: I, writer: W) -> Result<()>
+    where
+        I: IntoIterator<Item = Result<Value>>,
+        W: Write,
+    {
+        self.write_formatted(None, writer, |writer| {
+            self.emit_yaml_documents(values, writer)
+        })
+    }
+
+    /// Dump a YAML value to a string, also returning counters and timing for
+    /// the dump
+    pub fn dump_str_with_stats(&self, value: &Value) -> Result<(String, DumpStats)> {
+        self.dump_all_str_with_stats(std::slice::from_ref(value))
+    }
+
+    /// Dump all YAML documents to a string, also returning counters and timing
+    /// for the dump
+    pub fn dump_all_str_with_stats(&self, values: &[Value]) -> Result<(String, DumpStats)> {
+        let stopwatch = Stopwatch::start();
+        let output = self.dump_all_str(values)?;
+        let stats = DumpStats {
+            bytes: output.len(),
+            documents: values.len(),
+            elapsed: stopwatch.elapsed(),
+        };
+        Ok((output, stats))
+    }
+
+    /// Load YAML from a string into a shared node tree
+    ///
+    /// Aliases are resolved according to [`YamlConfig::alias_resolution`]; with
+    /// [`AliasResolution::Shared`] every alias refers to its anchored node.
+    pub fn load_str_shared(&self, input: &str) -> Result<SharedNode> {
+        let mut composer = SharedComposer::with_limits(
+            input.to_string(),
+            self.config.limits.clone(),
+            self.config.alias_resolution,
+        );
+        composer.set_merge_precedence(self.config.merge_precedence);
+        composer.set_empty_scalar(self.config.empty_scalar);
+        composer.set_key_coercion(self.config.key_coercion);
+        composer.set_resource_hook(self.config.resource_hook.clone());
+        composer.set_tag_handlers(self.config.tag_handlers.clone());
+        composer.set_unknown_handle(self.config.unknown_handle);
+        Ok(composer
+            .compose_document()?
+            .unwrap_or_else(|| SharedNode::new(SharedValue::Null)))
+    }
+
+    /// Load all YAML documents from a string into shared node trees
+    pub fn load_all_str_shared(&self, input: &str) -> Result<Vec<SharedNode>> {
+        let mut composer = SharedComposer::with_limits(
+            input.to_string(),
+            self.config.limits.clone(),
+            self.config.alias_resolution,
+        );
+        composer.set_merge_precedence(self.config.merge_precedence);
+        composer.set_empty_scalar(self.config.empty_scalar);
+        composer.set_key_coercion(self.config.key_coercion);
+        composer.set_resource_hook(self.config.resource_hook.clone());
+        composer.set_tag_handlers(self.config.tag_handlers.clone());
+        composer.set_unknown_handle(self.config.unknown_handle);
+        composer.set_anchor_scope(self.config.anchor_scope);
+        let mut documents = Vec::new();
+        while composer.check_document() {
+            match composer.compose_document()? {
+                Some(document) => documents.push(document),
+                None => break,
             }
-            self.dump(value, &mut writer)?;
         }
-        Ok(())
+        if documents.is_empty() {
+            documents.push(SharedNode::new(SharedValue::Null));
+        }
+        Ok(documents)
+    }
+
+    /// Report every node of a stream to `handler` depth-first, with its path,
+    /// without building any value tree
+    ///
+    /// Scalars are resolved as [`Yaml::load_str`] would. Loading stops early,
+    /// without an error, when a callback returns [`ControlFlow::Break`]; see
+    /// [`DocumentHandler`] for what is reported.
+    ///
+    /// [`ControlFlow::Break`]: std::ops::ControlFlow::Break
+    pub fn load_with_handler<H: DocumentHandler + ?Sized>(
+        &self,
+        input: &str,
+        handler: &mut H,
+    ) -> Result<()> {
+        let parser =
+            BasicParser::new_eager_with_limits(input.to_string(), self.config.limits.clone());
+        let mut tag_resolver = TagResolver::new();
+        tag_resolver.set_handlers(self.config.tag_handlers.clone());
+        tag_resolver.set_unknown_handle(self.config.unknown_handle);
+        HandlerDriver::new(parser, tag_resolver, self.config.empty_scalar).run(handler)
+    }
+
+    /// Read the node at `path`, in the dotted form such as
+    /// `spec.containers[0].image`, from the first document of `input`
+    ///
+    /// Reading stops as soon as the node has been read, and nothing outside it
+    /// is composed, so probing a large file is cheap. Returns `None` if the
+    /// document has no node at the path. A node reached through a merge key
+    /// or containing an alias of a collection is found by loading the whole
+    /// document instead.
+    pub fn get_path(&self, input: &str, path: &str) -> Result<Option<Value>> {
+        let path: Path = path.parse()?;
+        let mut probe = PathProbe::new(&path);
+        self.load_with_handler(input, &mut probe)?;
+        if probe.needs_document() {
+            return Ok(path.resolve(&self.load_str(input)?).cloned());
+        }
+        Ok(probe.into_found())
+    }
+
+    /// Hash the documents of `input` without composing them, so that two
+    /// texts hash the same when they load the same, whatever their layout
+    ///
+    /// Comments, quoting, indentation, flow or block style and the order of
+    /// mapping keys do not change the fingerprint. It is the
+    /// [`Value::content_hash_unordered`] of the sequence of documents
+    /// [`Yaml::load_all_str`] returns, except that an alias of a collection
+    /// is hashed by its anchor name, and merge keys and collection tags are
+    /// hashed as written, as [`Yaml::load_with_handler`] reports them.
+    pub fn fingerprint(&self, input: &str) -> Result<u64> {
+        let mut fingerprint = Fingerprint::new();
+        self.load_with_handler(input, &mut fingerprint)?;
+        Ok(fingerprint.finish())
+    }
+
+    /// Dump a shared node graph to a string
+    ///
+    /// Nodes referenced more than once, including cyclic references, are
+    /// written with anchors and aliases.
+    pub fn dump_str_shared(&self, node: &SharedNode) -> Result<String> {
+        let mut buffer = Vec::new();
+        self.write_formatted(None, &mut buffer, |writer| {
+            self.emitter().emit_shared(node, writer)
+        })?;
+        Ok(String::from_utf8(buffer)?)
     }
 
     /// Load YAML from a string with comment preservation (RoundTrip mode only)
     pub fn load_str_with_comments(&self, input: &str) -> Result<CommentedValue> {
-        if !self.config.preserve_comments || self.config.loader_type != LoaderType::RoundTrip {
-            // If not in round-trip mode, parse normally and wrap in CommentedValue
-            let value = self.load_str(input)?;
-            return Ok(CommentedValue::new(value));
+        let mut commented =
+            if !self.config.preserve_comments || self.config.loader_type != LoaderType::RoundTrip {
+                // If not in round-trip mode, parse normally and wrap in CommentedValue
+                CommentedValue::new(self.load_str(input)?)
+            } else {
+                self.parse_yaml_string_with_comments(input)?
+            };
+        commented.style.line_ending = Some(LineEnding::detect(input));
+        commented.style.trailing_breaks = Some(FinalNewline::count_trailing_breaks(input));
+        commented.style.bom = input.starts_with('\u{feff}');
+        Ok(commented)
+    }
+
+    /// Load all YAML documents from a string with comment preservation
+    /// (RoundTrip mode only)
+    ///
+    /// The line ending of the input is recorded on every document, a byte
+    /// order mark on the first and the trailing line breaks on the last, so
+    /// [`dump_all_str_with_comments`](Self::dump_all_str_with_comments) writes
+    /// the stream back as it was.
+    pub fn load_all_str_with_comments(&self, input: &str) -> Result<Vec<CommentedValue>> {
+        let mut documents =
+            if !self.config.preserve_comments || self.config.loader_type != LoaderType::RoundTrip {
+                self.load_all_str(input)?
+                    .into_iter()
+                    .map(CommentedValue::new)
+                    .collect()
+            } else {
+                let mut constructor = self.commented_constructor(input);
+                let mut documents = Vec::new();
+                while let Some(document) = constructor.construct_commented()? {
+                    documents.push(document);
+                }
+                documents
+            };
+        let line_ending = LineEnding::detect(input);
+        for document in &mut documents {
+            document.style.line_ending = Some(line_ending);
+        }
+        if let Some(first) = documents.first_mut() {
+            first.style.bom = input.starts_with('\u{feff}');
         }
+        if let Some(last) = documents.last_mut() {
+            last.style.trailing_breaks = Some(FinalNewline::count_trailing_breaks(input));
+        }
+        Ok(documents)
+    }
+
+    /// Dump CommentedValues to a string as a stream of documents, preserving
+    /// comments
+    pub fn dump_all_str_with_comments(&self, values: &[CommentedValue]) -> Result<String> {
+        let mut buffer = Vec::new();
+        self.dump_all_with_comments(values, &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
 
-        self.parse_yaml_string_with_comments(input)
+    /// Dump CommentedValues to a writer as a stream of documents, preserving
+    /// comments
+    pub fn dump_all_with_comments<W: Write>(
+        &self,
+        values: &[CommentedValue],
+        writer: W,
+    ) -> Result<()> {
+        let recorded = values.last().map(|value| &value.style);
+        self.write_formatted(recorded, writer, |writer| {
+            let mut previous: Option<&CommentedValue> = None;
+            // Whether the last document left its final line open
+            let mut open = false;
+            for value in values {
+                if let Some(previous) = previous {
+                    if open {
+                        writeln!(writer)?;
+                    }
+                    // Directives may only follow a document end marker, and
+                    // the emitter writes the start marker after them
+                    let directives = self.config.yaml_version.is_some()
+                        || value.style.yaml_version.is_some()
+                        || !value.style.tag_directives.is_empty();
+                    if !directives {
+                        writeln!(writer, "---")?;
+                    } else if !previous.style.explicit_end {
+                        writeln!(writer, "...")?;
+                    }
+                }
+                let mut document = Vec::new();
+                self.emit_commented_value(value, &mut document)?;
+                writer.write_all(&document)?;
+                open = !document.ends_with(b"\n");
+                previous = Some(value);
+            }
+            Ok(())
+        })
     }
 
     /// Dump a CommentedValue to a string, preserving comments
@@ -241,7 +761,32 @@ impl Yaml {
 
     /// Dump a CommentedValue to a writer, preserving comments
     pub fn dump_with_comments<W: Write>(&self, value: &CommentedValue, writer: W) -> Result<()> {
-        self.emit_commented_value(value, writer)
+        self.write_formatted(Some(&value.style), writer, |writer| {
+            self.emit_commented_value(value, writer)
+        })
+    }
+
+    /// Dump a YAML value to a string, writing the nodes set in `styles` in
+    /// their given style
+    pub fn dump_str_with_styles(&self, value: &Value, styles: &StyleMap) -> Result<String> {
+        let mut buffer = Vec::new();
+        self.dump_with_styles(value, styles, &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    /// Dump a YAML value to a writer, writing the nodes set in `styles` in
+    /// their given style
+    pub fn dump_with_styles<W: Write>(
+        &self,
+        value: &Value,
+        styles: &StyleMap,
+        writer: W,
+    ) -> Result<()> {
+        self.write_formatted(None, writer, |writer| {
+            let mut emitter = self.emitter();
+            emitter.set_styles(styles.clone());
+            self.emit_with(emitter, value, writer)
+        })
     }
 
     /// Validate a YAML value against a schema
@@ -251,45 +796,120 @@ impl Yaml {
     }
 
     /// Load and validate YAML from a string with schema validation
+    ///
+    /// Validation errors report the line and column of the failing node.
+    /// Schemas with `SchemaRule::Custom` rules need their validators, which
+    /// [`load_str_with_validator`](Self::load_str_with_validator) takes.
     pub fn load_str_with_schema(&self, input: &str, schema: &Schema) -> Result<Value> {
+        self.load_str_with_validator(input, &SchemaValidator::new(schema.clone()))
+    }
+
+    /// Load YAML from a string and validate it with a [`SchemaValidator`],
+    /// whose registered validators check the schema's custom rules
+    ///
+    /// Validation errors report the line and column of the failing node.
+    pub fn load_str_with_validator(
+        &self,
+        input: &str,
+        validator: &SchemaValidator,
+    ) -> Result<Value> {
         let value = self.load_str(input)?;
-        self.validate_with_schema(&value, schema)?;
+        if validator.validate(&value).is_err() {
+            let positions = node_positions(input)?
+                .into_iter()
+                .next()
+                .unwrap_or_default();
+            validator.validate_located_with_report(&value, &positions)?;
+        }
         Ok(value)
     }
 
     /// Load and validate all YAML documents from a string with schema validation
     pub fn load_all_str_with_schema(&self, input: &str, schema: &Schema) -> Result<Vec<Value>> {
+        self.load_all_str_with_validator(input, &SchemaValidator::new(schema.clone()))
+    }
+
+    /// Load all YAML documents from a string and validate each with a
+    /// [`SchemaValidator`]
+    pub fn load_all_str_with_validator(
+        &self,
+        input: &str,
+        validator: &SchemaValidator,
+    ) -> Result<Vec<Value>> {
         let values = self.load_all_str(input)?;
-        for value in &values {
-            self.validate_with_schema(value, schema)?;
+        if values
+            .iter()
+            .any(|value| validator.validate(value).is_err())
+        {
+            let positions = node_positions(input)?;
+            for (index, value) in values.iter().enumerate() {
+                let document_positions = positions.get(index).cloned().unwrap_or_default();
+                validator.validate_located_with_report(value, &document_positions)?;
+            }
         }
         Ok(values)
     }
 
+    /// Load YAML from a string, validate it against a schema and
+    /// deserialize it into a `T`
+    ///
+    /// Schema violations are reported as by
+    /// [`load_str_with_schema`](Self::load_str_with_schema); a document that
+    /// passes the schema but does not fit `T` fails with the path, line and
+    /// column of the node that did not fit.
+    #[cfg(feature = "serde")]
+    pub fn load_validated<T: serde::de::DeserializeOwned>(
+        &self,
+        input: &str,
+        schema: &Schema,
+    ) -> Result<T> {
+        let value = self.load_str_with_schema(input, schema)?;
+        crate::serde_integration::deserialize_at_path(&value).map_err(|(path, message)| {
+            let position = node_positions(input)
+                .ok()
+                .and_then(|positions| positions.into_iter().next())
+                .and_then(|positions| positions.get(&path).copied())
+                .unwrap_or_else(crate::Position::start);
+            crate::Error::value_error_with_context(
+                position,
+                crate::serde_integration::at_path_message(&path, &message),
+                crate::error::ErrorContext::from_input(input, &position, 2),
+            )
+        })
+    }
+
     // Placeholder implementations - will be replaced with actual parser/emitter
 
     fn parse_yaml_string(&self, input: &str) -> Result<Value> {
         // Use our complete parsing pipeline: Scanner -> Parser -> Composer -> Constructor
-        match self.config.loader_type {
-            LoaderType::Safe => {
-                let mut constructor =
-                    SafeConstructor::with_limits(input.to_string(), self.config.limits.clone());
-                (constructor.construct()?).map_or_else(|| Ok(Value::Null), Ok)
-            }
-            _ => {
-                // For now, all loader types use SafeConstructor
-                // Future versions will implement different constructors
-                let mut constructor =
-                    SafeConstructor::with_limits(input.to_string(), self.config.limits.clone());
-                (constructor.construct()?).map_or_else(|| Ok(Value::Null), Ok)
-            }
-        }
+        // The composer types scalars and checks tags by the loader type
+        let mut constructor = self.safe_constructor(input);
+        (constructor.construct()?).map_or_else(|| Ok(Value::Null), Ok)
+    }
+
+    /// Load YAML from a string with `limits` in place of the configured ones
+    ///
+    /// Lets one shared `Yaml` handle both trusted large files and small
+    /// untrusted payloads, e.g. with [`Limits::untrusted_input`].
+    pub fn load_str_with_limits(&self, input: &str, limits: &Limits) -> Result<Value> {
+        let mut constructor = self.safe_constructor_with_limits(input, limits);
+        Ok(constructor.construct()?.unwrap_or(Value::Null))
+    }
+
+    /// Load all YAML documents from a string with `limits` in place of the
+    /// configured ones
+    pub fn load_all_str_with_limits(&self, input: &str, limits: &Limits) -> Result<Vec<Value>> {
+        let mut constructor = self.safe_constructor_with_limits(input, limits);
+        Self::construct_documents(&mut constructor)
     }
 
     fn parse_yaml_documents(&self, input: &str) -> Result<Vec<Value>> {
         // Use the proper parsing pipeline to handle multi-document streams
-        let mut constructor =
-            SafeConstructor::with_limits(input.to_string(), self.config.limits.clone());
+        let mut constructor = self.safe_constructor(input);
+        Self::construct_documents(&mut constructor)
+    }
+
+    fn construct_documents(constructor: &mut SafeConstructor) -> Result<Vec<Value>> {
         let mut documents = Vec::new();
 
         // Try to construct documents until no more are available
@@ -308,42 +928,280 @@ impl Yaml {
         Ok(documents)
     }
 
+    fn safe_constructor(&self, input: &str) -> SafeConstructor {
+        self.safe_constructor_with_limits(input, &self.config.limits)
+    }
+
+    fn safe_constructor_with_limits(&self, input: &str, limits: &Limits) -> SafeConstructor {
+        let mut scanner = BasicScanner::with_limits(input.to_string(), limits.clone());
+        scanner.set_strict_escapes(self.config.strict_mode);
+        scanner.set_invalid_directive(self.config.invalid_directive);
+        scanner.set_keep_plain_scalars(matches!(
+            self.config.loader_type,
+            LoaderType::Base | LoaderType::Strict
+        ));
+        let mut composer =
+            BasicComposer::from_parser(BasicParser::from_scanner_eager(scanner), limits.clone());
+        composer.set_merge_precedence(self.config.merge_precedence);
+        composer.set_empty_scalar(self.config.empty_scalar);
+        composer.set_key_coercion(self.config.key_coercion);
+        composer.set_resource_hook(self.config.resource_hook.clone());
+        composer.set_tag_handlers(self.config.tag_handlers.clone());
+        composer.set_unknown_handle(self.config.unknown_handle);
+        composer.set_anchor_redefinition(self.config.anchor_redefinition);
+        composer.set_anchor_scope(self.config.anchor_scope);
+        composer.set_loader_type(self.config.loader_type);
+        composer.set_reject_unknown_tags(self.config.reject_unknown_tags);
+        composer.set_reject_complex_keys(self.config.reject_complex_keys);
+        SafeConstructor::from_composer_with_limits(composer, limits.clone())
+    }
+
+    fn parse_stats(
+        input: &str,
+        constructor: &SafeConstructor,
+        documents: usize,
+        stopwatch: Stopwatch,
+    ) -> ParseStats {
+        let composer = constructor.composer();
+        let resources = composer.resource_stats();
+        ParseStats {
+            bytes: input.len(),
+            tokens: composer.parser().tokens_scanned(),
+            events: composer.parser().events_parsed(),
+            documents,
+            anchors: resources.anchor_count,
+            alias_expansions: resources.alias_expansions,
+            max_depth: resources.max_depth,
+            complexity_score: resources.complexity_score,
+            elapsed: stopwatch.elapsed(),
+        }
+    }
+
+    /// An emitter set up with the output options of the configuration
+    fn emitter(&self) -> BasicEmitter {
+        let mut emitter = BasicEmitter::with_indent(self.config.indent.indent);
+        emitter.set_null_style(self.config.null_style);
+        emitter.set_bool_style(self.config.bool_style);
+        emitter.set_flow_style(match self.config.default_flow_style {
+            Some(true) => FlowStyle::Flow,
+            Some(false) => FlowStyle::Block,
+            None => self.config.flow_style,
+        });
+        emitter.set_width(self.config.width);
+        emitter.set_alias_threshold(self.config.alias_threshold);
+        emitter.set_anchor_namer(self.config.anchor_namer.clone());
+        emitter.set_tag_prefix_threshold(self.config.tag_prefix_threshold);
+        emitter.set_tag_handle_namer(self.config.tag_handle_namer.clone());
+        emitter.set_tag_emission(self.config.tag_emission);
+        emitter.set_align_trailing_comments(self.config.align_trailing_comments);
+        emitter.set_redaction(self.config.redaction.clone());
+        emitter.set_schema(self.config.dump_schema.clone());
+        if let Some((major, minor)) = self.config.yaml_version {
+            emitter.set_yaml_version(major, minor);
+        }
+        emitter
+    }
+
+    /// Run `emit` with the configured byte order mark, final newline and
+    /// line endings applied to what it writes, taking what a round-trip
+    /// value recorded about its source from `recorded`
+    fn write_formatted<W: Write>(
+        &self,
+        recorded: Option<&crate::Style>,
+        mut writer: W,
+        emit: impl FnOnce(&mut dyn Write) -> Result<()>,
+    ) -> Result<()> {
+        if self.config.emit_bom {
+            writer.write_all("\u{feff}".as_bytes())?;
+        }
+        let detected = recorded.and_then(|style| style.line_ending);
+        let mut writer = FormattedWriter {
+            inner: writer,
+            crlf: self.config.line_ending.resolve(detected) == LineEnding::Crlf,
+            pending_breaks: 0,
+        };
+        emit(&mut writer)?;
+
+        let breaks = self
+            .config
+            .final_newline
+            .resolve(recorded.and_then(|style| style.trailing_breaks));
+        writer.finish(breaks)?;
+        Ok(())
+    }
+
     fn emit_yaml_value<W: Write>(&self, value: &Value, writer: W) -> Result<()> {
         // Use the proper emitter implementation
-        let mut emitter = BasicEmitter::with_indent(self.config.indent.indent);
-        emitter.emit(value, writer)?;
+        self.emit_with(self.emitter(), value, writer)
+    }
+
+    fn emit_with<W: Write>(
+        &self,
+        mut emitter: BasicEmitter,
+        value: &Value,
+        writer: W,
+    ) -> Result<()> {
+        if self.config.sort_keys {
+            emitter.emit(&value.sorted(), writer)?;
+        } else {
+            emitter.emit(value, writer)?;
+        }
         Ok(())
     }
 
     fn parse_yaml_string_with_comments(&self, input: &str) -> Result<CommentedValue> {
-        // Use the round-trip constructor for comment preservation
-        let mut constructor =
-            RoundTripConstructor::with_limits(input.to_string(), self.config.limits.clone());
-
-        match constructor.construct_commented()? {
+        match self.commented_constructor(input).construct_commented()? {
             Some(commented_value) => Ok(commented_value),
             None => Ok(CommentedValue::new(Value::Null)),
         }
     }
 
+    /// The round-trip constructor composing the documents of `input` with
+    /// their comments
+    fn commented_constructor(&self, input: &str) -> RoundTripConstructor {
+        let mut composer =
+            CommentPreservingComposer::with_limits(input.to_string(), self.config.limits.clone());
+        composer.set_preserve_merge_keys(self.config.preserve_merge_keys);
+        composer.set_merge_precedence(self.config.merge_precedence);
+        composer.set_empty_scalar(self.config.empty_scalar);
+        composer.set_key_coercion(self.config.key_coercion);
+        composer.set_resource_hook(self.config.resource_hook.clone());
+        composer.set_tag_handlers(self.config.tag_handlers.clone());
+        composer.set_unknown_handle(self.config.unknown_handle);
+        composer.set_reject_unknown_tags(self.config.reject_unknown_tags);
+        composer.set_reject_complex_keys(self.config.reject_complex_keys);
+        RoundTripConstructor::from_composer_with_limits(composer, self.config.limits.clone())
+    }
+
     fn emit_commented_value<W: Write>(&self, value: &CommentedValue, writer: W) -> Result<()> {
         // Use the proper emitter implementation with comment support
-        let mut emitter = BasicEmitter::with_indent(self.config.indent.indent);
+        let mut emitter = self.emitter();
         emitter.emit_commented_value_public(value, writer)?;
         Ok(())
     }
 
-    fn emit_yaml_documents<W: Write>(&self, values: &[Value], mut writer: W) -> Result<()> {
-        for (i, value) in values.iter().enumerate() {
+    fn emit_yaml_documents<V, W>(
+        &self,
+        values: impl IntoIterator<Item = Result<V>>,
+        mut writer: W,
+    ) -> Result<()>
+    where
+        V: std::borrow::Borrow<Value>,
+        W: Write,
+    {
+        for (i, value) in values.into_iter().enumerate() {
+            let value = value?;
             if i > 0 {
-                writeln!(writer, "---")?;
+                // Directives may only follow a document end marker
+                let separator = if self.config.yaml_version.is_some() {
+                    "..."
+                } else {
+                    "---"
+                };
+                writeln!(writer, "{separator}")?;
             }
-            self.emit_yaml_value(value, &mut writer)?;
+            self.emit_yaml_value(value.borrow(), &mut writer)?;
+            // The line break ending the document is held back until the
+            // next one shows it is not the end of the output
+            writer.flush()?;
         }
         Ok(())
     }
 }
 
+/// Writer applying the final newline and line ending settings to the output
+/// as the emitter streams it
+struct FormattedWriter<W: Write> {
+    inner: W,
+    crlf: bool,
+    /// Line breaks held back until more content shows they do not end the
+    /// output
+    pending_breaks: usize,
+}
+
+impl<W: Write> FormattedWriter<W> {
+    fn write_breaks(&mut self, count: usize) -> io::Result<()> {
+        let line_break: &[u8] = if self.crlf { b"\r\n" } else { b"\n" };
+        for _ in 0..count {
+            self.inner.write_all(line_break)?;
+        }
+        Ok(())
+    }
+
+    /// End the output with `breaks` line breaks in place of the last line's
+    /// own one
+    fn finish(mut self, breaks: usize) -> io::Result<()> {
+        let count = self.pending_breaks.saturating_sub(1) + breaks;
+        self.write_breaks(count)?;
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Write for FormattedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for chunk in buf.split_inclusive(|&byte| byte == b'\n') {
+            let (text, ends_line) = match chunk.strip_suffix(b"\n") {
+                Some(text) => (text, true),
+                None => (chunk, false),
+            };
+            if !text.is_empty() {
+                let pending = std::mem::take(&mut self.pending_breaks);
+                self.write_breaks(pending)?;
+                self.inner.write_all(text)?;
+            }
+            if ends_line {
+                self.pending_breaks += 1;
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Adapter passing the UTF-8 output of the emitter on to a [`fmt::Write`]
+struct FmtWriter<'a, W: fmt::Write + ?Sized> {
+    inner: &'a mut W,
+    /// The start of a character split across writes
+    partial: Vec<u8>,
+}
+
+impl<W: fmt::Write + ?Sized> Write for FmtWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let bytes = if self.partial.is_empty() {
+            buf
+        } else {
+            self.partial.extend_from_slice(buf);
+            &self.partial
+        };
+        let (text, rest) = match std::str::from_utf8(bytes) {
+            Ok(text) => (text, 0),
+            Err(error) if error.error_len().is_none() => {
+                let (valid, rest) = bytes.split_at(error.valid_up_to());
+                (std::str::from_utf8(valid).unwrap_or_default(), rest.len())
+            }
+            Err(error) => return Err(io::Error::new(io::ErrorKind::InvalidData, error)),
+        };
+        self.inner.write_str(text).map_err(io::Error::other)?;
+        let rest = bytes[bytes.len() - rest..].to_vec();
+        self.partial = rest;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.partial.is_empty() {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "output ends inside a UTF-8 character",
+            ))
+        }
+    }
+}
+
 impl Default for Yaml {
     fn default() -> Self {
         Self::new()
@@ -419,4 +1277,1057 @@ mod tests {
         assert_eq!(yaml.config().loader_type, LoaderType::Full);
         assert!(!yaml.config().allow_unicode);
     }
+
+    #[test]
+    fn test_load_str_shared() {
+        let input = "base: &b [1, 2]\nalias: *b\n";
+        let mut yaml = Yaml::new();
+
+        let copied = yaml.load_str_shared(input).unwrap();
+        let base = copied.borrow().get_str("base").unwrap();
+        assert!(!base.ptr_eq(&copied.borrow().get_str("alias").unwrap()));
+        assert_eq!(copied.to_value(), yaml.load_str(input).unwrap());
+
+        yaml.config_mut().alias_resolution = AliasResolution::Shared;
+        let shared = yaml.load_str_shared(input).unwrap();
+        let base = shared.borrow().get_str("base").unwrap();
+        assert!(base.ptr_eq(&shared.borrow().get_str("alias").unwrap()));
+    }
+
+    #[test]
+    fn test_graph_round_trip() {
+        let mut yaml = Yaml::new();
+        yaml.config_mut().alias_resolution = AliasResolution::Graph;
+
+        let root = yaml.load_str_shared("a: &x\n  b: *x\n").unwrap();
+        let dumped = yaml.dump_str_shared(&root).unwrap();
+        assert_eq!(dumped, "a: &anchor0\n  b: *anchor0\n");
+
+        let reloaded = yaml.load_str_shared(&dumped).unwrap();
+        assert_eq!(reloaded, root);
+    }
+
+    #[test]
+    fn test_preserve_merge_keys_round_trip() {
+        let input = "base: &base\n  a: 1\nitem:\n  <<: *base\n  b: 2\n";
+        let mut yaml = Yaml::with_config(YamlConfig {
+            loader_type: LoaderType::RoundTrip,
+            preserve_comments: true,
+            preserve_merge_keys: true,
+            ..YamlConfig::default()
+        });
+
+        let loaded = yaml.load_str_with_comments(input).unwrap();
+        let dumped = yaml.dump_str_with_comments(&loaded).unwrap();
+        assert!(dumped.contains("<<:"));
+
+        yaml.config_mut().loader_type = LoaderType::Safe;
+        let reloaded = yaml.load_str(&dumped).unwrap();
+        assert_eq!(reloaded, yaml.load_str(input).unwrap());
+    }
+
+    #[test]
+    fn test_tag_directives_round_trip() {
+        let input = "%TAG !e! tag:example.com,2024:\n---\nkey: value\n";
+        let yaml = Yaml::with_config(YamlConfig {
+            loader_type: LoaderType::RoundTrip,
+            preserve_comments: true,
+            ..YamlConfig::default()
+        });
+
+        let loaded = yaml.load_str_with_comments(input).unwrap();
+        let dumped = yaml.dump_str_with_comments(&loaded).unwrap();
+        assert!(dumped.starts_with("%TAG !e! tag:example.com,2024:\n---\n"));
+    }
+
+    #[test]
+    fn test_all_documents_round_trip_with_comments() {
+        let yaml = Yaml::with_config(YamlConfig {
+            loader_type: LoaderType::RoundTrip,
+            preserve_comments: true,
+            ..YamlConfig::default()
+        });
+
+        for input in [
+            "# first\na: 1 # one\n---\n# second\nb: 2 # two\n",
+            "a: |\n  text\n---\nb: 1\n",
+            "a: 1\n...\n%YAML 1.2\n---\nb: 2\n",
+            "%TAG !e! tag:example.com,2024:\n---\na: 1\n---\nb: 2 # two\n",
+        ] {
+            let loaded = yaml.load_all_str_with_comments(input).unwrap();
+            assert_eq!(loaded.len(), 2, "{input:?}");
+            assert_eq!(yaml.dump_all_str_with_comments(&loaded).unwrap(), input);
+        }
+    }
+
+    #[test]
+    fn test_blank_lines_round_trip() {
+        let yaml = Yaml::with_config(YamlConfig {
+            loader_type: LoaderType::RoundTrip,
+            preserve_comments: true,
+            ..YamlConfig::default()
+        });
+
+        for input in [
+            "# App\nname: web\nport: 80\n\n# Database\ndb:\n  host: x\n\n  user: y\n\n\nlist:\n  - a\n\n  - b\n",
+            "a: |\n  x\n\nb: 1\n",
+            "a: |+\n  x\n\nb: 1\n",
+        ] {
+            let loaded = yaml.load_str_with_comments(input).unwrap();
+            assert_eq!(yaml.dump_str_with_comments(&loaded).unwrap(), input);
+        }
+
+        let loaded = yaml
+            .load_str_with_comments("a: 1\n\n\n# note\nb: 2\n")
+            .unwrap();
+        let b = loaded.key_entry(&Value::from("b")).unwrap();
+        assert_eq!(b.comments.blank_lines, 2);
+        assert_eq!(b.comments.leading, vec!["note"]);
+        assert!(!loaded.key_entry(&Value::from("a")).unwrap().has_comments());
+    }
+
+    #[test]
+    fn test_document_end_marker_round_trip() {
+        let yaml = Yaml::with_config(YamlConfig {
+            loader_type: LoaderType::RoundTrip,
+            preserve_comments: true,
+            ..YamlConfig::default()
+        });
+
+        for input in [
+            "key: value # note\n...\n",
+            "- |\n  text\n...\n",
+            "scalar\n...\n",
+        ] {
+            let loaded = yaml.load_str_with_comments(input).unwrap();
+            assert!(loaded.style.explicit_end, "{input:?}");
+            assert_eq!(yaml.dump_str_with_comments(&loaded).unwrap(), input);
+        }
+
+        let loaded = yaml.load_str_with_comments("key: value\n").unwrap();
+        assert!(!loaded.style.explicit_end);
+        assert_eq!(
+            yaml.dump_str_with_comments(&loaded).unwrap(),
+            "key: value\n"
+        );
+    }
+
+    #[test]
+    fn test_yaml_version_dump() {
+        let mut yaml = Yaml::new();
+        let value = crate::yaml!({"answer": "y", "count": "1_000", "name": "yes"});
+        assert_eq!(
+            yaml.dump_str(&value).unwrap(),
+            "answer: y\ncount: 1_000\nname: \"yes\"\n"
+        );
+
+        yaml.config_mut().yaml_version = Some((1, 1));
+        let dumped = yaml.dump_str(&value).unwrap();
+        assert_eq!(
+            dumped,
+            "%YAML 1.1\n---\nanswer: \"y\"\ncount: \"1_000\"\nname: \"yes\"\n"
+        );
+        assert_eq!(yaml.load_str(&dumped).unwrap(), value);
+
+        yaml.config_mut().yaml_version = Some((1, 2));
+        let dumped = yaml
+            .dump_all_str(&[Value::Int(1), Value::from("y")])
+            .unwrap();
+        assert_eq!(dumped, "%YAML 1.2\n---\n1\n...\n%YAML 1.2\n---\ny\n");
+        assert_eq!(
+            yaml.load_all_str(&dumped).unwrap(),
+            vec![Value::Int(1), Value::from("y")]
+        );
+    }
+
+    #[test]
+    fn test_alias_threshold_dump() {
+        let mut yaml = Yaml::new();
+        let value = yaml
+            .load_str("a: {x: [1, 2]}\nb: {x: [1, 2]}\nl:\n  - [1, 2]\n  - []\n  - []\n")
+            .unwrap();
+
+        let dumped = yaml.dump_str(&value).unwrap();
+        assert_eq!(
+            dumped,
+            "a: &anchor0\n  x: &anchor1\n    - 1\n    - 2\nb: *anchor0\nl: \n  - *anchor1\n  - []\n  - []\n"
+        );
+        assert_eq!(yaml.load_str(&dumped).unwrap(), value);
+
+        yaml.config_mut().alias_threshold = None;
+        let dumped = yaml.dump_str(&value).unwrap();
+        assert!(!dumped.contains('&'));
+    }
+
+    // Loading under the `sort-keys` feature reorders the keys these compare
+    #[cfg(not(feature = "sort-keys"))]
+    #[test]
+    fn test_redaction_dump() {
+        let input = "db:\n  user: app\n  password: hunter2 # rotated monthly\napi_token: abc\n";
+        let mut yaml = Yaml::with_config(YamlConfig {
+            loader_type: LoaderType::RoundTrip,
+            preserve_comments: true,
+            ..YamlConfig::default()
+        });
+        let value = yaml.load_str(input).unwrap();
+        let commented = yaml.load_str_with_comments(input).unwrap();
+
+        yaml.config_mut().redaction = Some(RedactionRules::default());
+        assert_eq!(
+            yaml.dump_str(&value).unwrap(),
+            "db: \n  user: app\n  password: \"***\"\napi_token: \"***\"\n"
+        );
+        assert_eq!(
+            yaml.dump_str_with_comments(&commented).unwrap(),
+            "db:\n  user: app\n  password: \"***\" # rotated monthly\napi_token: \"***\"\n"
+        );
+        // The dumped values themselves are left as they were
+        assert_eq!(value["api_token"], Value::from("abc"));
+    }
+
+    #[test]
+    fn test_anchor_namer_dump() {
+        let mut yaml = Yaml::new();
+        yaml.config_mut().anchor_namer = Some(Arc::new(crate::PathAnchorNamer));
+        let value = yaml
+            .load_str("a: {x: [1, 2]}\nb: {x: [1, 2]}\nl:\n  - [1, 2]\n")
+            .unwrap();
+
+        let dumped = yaml.dump_str(&value).unwrap();
+        assert_eq!(
+            dumped,
+            "a: &a\n  x: &a_x\n    - 1\n    - 2\nb: *a\nl: \n  - *a_x\n"
+        );
+        assert_eq!(yaml.load_str(&dumped).unwrap(), value);
+    }
+
+    #[test]
+    fn test_tag_prefix_dump() {
+        #[derive(Debug)]
+        struct Short;
+        impl TagHandleNamer for Short {
+            fn handle_name(&self, _prefix: &str, index: usize) -> Option<String> {
+                Some(format!("t{index}"))
+            }
+        }
+
+        let mut yaml = Yaml::new();
+        let value = yaml
+            .load_str(
+                "a: !<tag:example.com,2024:app/widget> x\n\
+                 b: !<tag:example.com,2024:app/button> {}\n\
+                 c: !<tag:other.org,2024:one> 1\n\
+                 d: !local y\n",
+            )
+            .unwrap();
+        let verbatim = yaml.dump_str(&value).unwrap();
+        assert!(verbatim.contains("a: !<tag:example.com,2024:app/widget> x"));
+
+        yaml.config_mut().tag_prefix_threshold = Some(2);
+        let dumped = yaml.dump_str(&value).unwrap();
+        assert_eq!(
+            dumped,
+            "%TAG !example! tag:example.com,2024:app/\n---\n\
+             a: !example!widget x\nb: !example!button {}\n\
+             c: !<tag:other.org,2024:one> \"1\"\nd: !local y\n"
+        );
+        assert_eq!(yaml.load_str(&dumped).unwrap(), value);
+
+        yaml.config_mut().tag_prefix_threshold = Some(1);
+        yaml.config_mut().tag_handle_namer = Some(Arc::new(Short));
+        let dumped = yaml.dump_str(&value).unwrap();
+        assert!(dumped.starts_with(
+            "%TAG !t0! tag:example.com,2024:app/\n%TAG !t1! tag:other.org,2024:\n---\n"
+        ));
+        assert!(dumped.contains("c: !t1!one \"1\""));
+        assert_eq!(yaml.load_str(&dumped).unwrap(), value);
+    }
+
+    #[test]
+    fn test_event_trace() {
+        let yaml = Yaml::new();
+        let trace = yaml
+            .event_trace("a: [1, 'b']\nc: &x !!str d\n---\ne: *x\n")
+            .unwrap();
+        assert_eq!(
+            trace,
+            "+STR\n+DOC\n+MAP\n=VAL :a\n+SEQ []\n=VAL :1\n=VAL 'b\n-SEQ\n=VAL :c\n\
+             =VAL &x <tag:yaml.org,2002:str> :d\n-MAP\n-DOC\n+DOC ---\n+MAP\n=VAL :e\n=ALI *x\n\
+             -MAP\n-DOC\n-STR\n"
+        );
+
+        assert!(yaml.event_trace("a: &").is_err());
+    }
+
+    #[test]
+    fn test_empty_scalar_policy() {
+        let input = "a:\nb: \"\"\nc: ''\nd: null\n";
+        let mut yaml = Yaml::with_config(YamlConfig {
+            loader_type: LoaderType::RoundTrip,
+            preserve_comments: true,
+            ..YamlConfig::default()
+        });
+        let assert_loads = |yaml: &Yaml, empty: Value| {
+            let expected = crate::yaml!({"a": empty, "b": "", "c": "", "d": null});
+            assert_eq!(yaml.load_str(input).unwrap(), expected);
+            assert_eq!(yaml.load_str_with_comments(input).unwrap().value, expected);
+            assert_eq!(yaml.load_str_shared(input).unwrap().to_value(), expected);
+        };
+
+        assert_loads(&yaml, Value::Null);
+        yaml.config_mut().empty_scalar = EmptyScalar::EmptyString;
+        assert_loads(&yaml, Value::from(""));
+    }
+
+    #[test]
+    fn test_key_coercion_policy() {
+        let input = "1: a\n1.0: b\ntrue: c\n~: d\n'2': e\n!!str 3: f\n";
+        let mut yaml = Yaml::with_config(YamlConfig {
+            loader_type: LoaderType::RoundTrip,
+            preserve_comments: true,
+            ..YamlConfig::default()
+        });
+        let assert_loads = |yaml: &Yaml, expected: Value| {
+            assert_eq!(yaml.load_str(input).unwrap(), expected);
+            assert_eq!(yaml.load_str_with_comments(input).unwrap().value, expected);
+            assert_eq!(yaml.load_str_shared(input).unwrap().to_value(), expected);
+        };
+
+        let typed = Value::mapping_with(vec![
+            (Value::Int(1), Value::from("a")),
+            (Value::Float(1.0), Value::from("b")),
+            (Value::Bool(true), Value::from("c")),
+            (Value::Null, Value::from("d")),
+            (Value::from("2"), Value::from("e")),
+            (Value::from("3"), Value::from("f")),
+        ]);
+        assert_loads(&yaml, typed.clone());
+        assert_eq!(typed.get_loose(&Value::from("1")), Some(&Value::from("a")));
+        assert_eq!(typed.get_loose(&Value::Int(2)), Some(&Value::from("e")));
+
+        yaml.config_mut().key_coercion = KeyCoercion::StringifyScalars;
+        assert_loads(
+            &yaml,
+            crate::yaml!({"1": "a", "1.0": "b", "true": "c", "null": "d", "2": "e", "3": "f"}),
+        );
+    }
+
+    #[test]
+    fn test_dump_stream() {
+        /// Records what had been flushed when each flush happened
+        #[derive(Default)]
+        struct Recorder {
+            written: Vec<u8>,
+            flushed: Vec<String>,
+        }
+        impl Write for &mut Recorder {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.written.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                self.flushed
+                    .push(String::from_utf8(self.written.clone()).unwrap());
+                Ok(())
+            }
+        }
+
+        let yaml = Yaml::new();
+        let mut recorder = Recorder::default();
+        let rows = (1..=3).map(|id| Ok(crate::yaml!({"id": id, "name": "row"})));
+        yaml.dump_stream(rows, &mut recorder).unwrap();
+        let expected = "id: 1\nname: row\n---\nid: 2\nname: row\n---\nid: 3\nname: row\n";
+        assert_eq!(recorder.flushed[0], "id: 1\nname: row");
+        assert_eq!(recorder.flushed.last().unwrap(), expected);
+        assert_eq!(
+            yaml.dump_all_str(&[
+                crate::yaml!({"id": 1, "name": "row"}),
+                crate::yaml!({"id": 2, "name": "row"}),
+                crate::yaml!({"id": 3, "name": "row"}),
+            ])
+            .unwrap(),
+            expected
+        );
+
+        // An error stops the dump, leaving the documents before it written
+        let rows = vec![
+            Ok(Value::Int(1)),
+            Err(crate::Error::emission("row 2 could not be read")),
+            Ok(Value::Int(3)),
+        ];
+        let mut output = Vec::new();
+        let error = yaml.dump_stream(rows, &mut output).unwrap_err();
+        assert!(error.to_string().contains("row 2 could not be read"));
+        assert_eq!(output, b"1");
+
+        let mut output = Vec::new();
+        yaml.dump_stream(std::iter::empty(), &mut output).unwrap();
+        assert_eq!(output, yaml.dump_all_str(&[]).unwrap().as_bytes());
+    }
+
+    #[test]
+    fn test_sort_keys_dump() {
+        let mut yaml = Yaml::new();
+        let value = crate::yaml!({"b": 1, "a": {"d": 2, "c": 3}, 10: "x"});
+        yaml.config_mut().sort_keys = false;
+        assert!(yaml.dump_str(&value).unwrap().starts_with("b: 1"));
+
+        yaml.config_mut().sort_keys = true;
+        let dumped = yaml.dump_str(&value).unwrap();
+        assert_eq!(dumped, "10: x\na: \n  c: 3\n  d: 2\nb: 1\n");
+
+        // Mappings inside tagged nodes are sorted too
+        let mut tagged = Value::mapping_with(vec![(
+            Value::from("items"),
+            Value::Sequence(vec![Value::tagged(
+                "!point",
+                crate::yaml!({"y": 2, "x": {"b": 1, "a": 0}}),
+            )]),
+        )]);
+        let dumped = yaml.dump_str(&tagged).unwrap();
+        tagged.sort_keys();
+        let sorted = &tagged["items"][0];
+        let Value::Tagged(point) = sorted else {
+            panic!("expected a tagged node, found {sorted:?}");
+        };
+        let keys: Vec<_> = point.value.as_mapping().unwrap().keys().collect();
+        assert_eq!(keys, [&Value::from("x"), &Value::from("y")]);
+        let keys: Vec<_> = point.value["x"].as_mapping().unwrap().keys().collect();
+        assert_eq!(keys, [&Value::from("a"), &Value::from("b")]);
+        assert_eq!(dumped, yaml.dump_str(&tagged).unwrap());
+        assert!(dumped.find("x:").unwrap() < dumped.find("y:").unwrap());
+    }
+
+    #[cfg(feature = "sort-keys")]
+    #[test]
+    fn test_sort_keys_feature_loads_sorted_keys() {
+        let value = Yaml::new().load_str("b: 1\na: {d: 2, c: 3}\n").unwrap();
+        let keys: Vec<_> = value["a"].as_mapping().unwrap().keys().cloned().collect();
+        assert_eq!(keys, vec![Value::from("c"), Value::from("d")]);
+        assert_eq!(
+            Yaml::new().dump_str(&value).unwrap(),
+            "a: \n  c: 3\n  d: 2\nb: 1\n"
+        );
+    }
+
+    #[test]
+    fn test_load_document_metadata() {
+        let input = "%YAML 1.2\n%TAG !e! tag:example.com,2024:\n---\nbase: &base\n  port: 80\nlist:\n  - &first a\n  - b\nother: *base\n";
+        let document = Yaml::new().load_document(input).unwrap();
+
+        assert_eq!(document.value, Yaml::new().load_str(input).unwrap());
+        assert_eq!(document.version, Some((1, 2)));
+        assert_eq!(
+            document.directives,
+            vec![crate::TagDirective {
+                handle: "!e!".to_string(),
+                prefix: "tag:example.com,2024:".to_string(),
+            }]
+        );
+        assert_eq!(document.anchors["base"].to_string(), "base");
+        assert_eq!(document.anchors["first"].to_string(), "list[0]");
+        assert_eq!(document.anchored("first"), Some(&Value::from("a")));
+
+        let plain = Yaml::new().load_document("a: 1\n").unwrap();
+        assert_eq!(plain.version, None);
+        assert!(plain.directives.is_empty() && plain.anchors.is_empty());
+        assert!(!plain.explicit_end);
+        assert!(
+            Yaml::new()
+                .load_document("a: 1\n...\n")
+                .unwrap()
+                .explicit_end
+        );
+    }
+
+    #[test]
+    fn test_anchor_redefinition_policy() {
+        let input = "a: &x 1\nb: &x 2\nc: *x\n";
+        let mut yaml = Yaml::new();
+        let document = yaml.load_document(input).unwrap();
+        assert_eq!(document.value["c"], Value::Int(2));
+        assert!(document.warnings.is_empty());
+
+        yaml.config_mut().anchor_redefinition = AnchorRedefinition::Warn;
+        let document = yaml.load_document(input).unwrap();
+        assert_eq!(document.value["c"], Value::Int(2));
+        assert_eq!(document.warnings.len(), 1);
+        assert!(document.warnings[0].contains("line 2"));
+        assert!(document.warnings[0].contains("line 1"));
+
+        yaml.config_mut().anchor_redefinition = AnchorRedefinition::Error;
+        assert!(yaml.load_str(input).is_err());
+        // Anchors are scoped to their document
+        assert!(yaml.load_all_str("a: &x 1\n---\nb: &x 2\n").is_ok());
+    }
+
+    #[test]
+    fn test_invalid_directive() {
+        let mut yaml = Yaml::new();
+        for (input, line) in [
+            ("%YAML 1.2\n%YAML 1.2\n---\na: 1\n", 2),
+            ("%YAML 2.0\n---\na: 1\n", 1),
+            ("%TAG !e! tag:e.com:\n%TAG !e! tag:f.com:\n---\na: 1\n", 2),
+            ("%YAML 1.2\n---\na: 1\n...\n%FOO bar\n---\nb: 2\n", 5),
+        ] {
+            let error = yaml.load_all_str(input).unwrap_err();
+            assert_eq!(error.position().map(|p| p.line), Some(line), "{input:?}");
+        }
+        // Directives may be repeated for each document, and a newer 1.x
+        // version is loaded with a warning
+        assert!(yaml
+            .load_all_str("%YAML 1.2\n---\na: 1\n...\n%YAML 1.2\n---\nb: 2\n")
+            .is_ok());
+        let document = yaml.load_document("%YAML 1.3\n---\na: 1\n").unwrap();
+        assert_eq!(document.version, Some((1, 3)));
+        assert_eq!(document.warnings.len(), 1);
+        assert!(yaml.load_str("%YAML 1.2x\n---\na: 1\n").is_err());
+        assert!(yaml.load_str("%YAML 1.2 x\n---\na: 1\n").is_err());
+        assert!(yaml.load_str("%YAML 1.2 # comment\n---\na: 1\n").is_ok());
+
+        yaml.config_mut().invalid_directive = InvalidDirective::Warn;
+        let document = yaml
+            .load_document("%YAML 2.0\n%FOO bar\n---\na: 1\n")
+            .unwrap();
+        assert_eq!(document.value["a"], Value::Int(1));
+        assert_eq!(document.version, None);
+        assert_eq!(document.warnings.len(), 2);
+        assert!(document.warnings[0].contains("2.0 at line 1, column 1"));
+        assert!(document.warnings[1].contains("'%FOO' at line 2, column 1"));
+    }
+
+    #[test]
+    fn test_get_path() {
+        let yaml = Yaml::new();
+        let input = "base: &base {image: nginx, port: 80}\nspec:\n  containers:\n    - name: web\n      \
+                     image: nginx:1.25\n      ports: [80, 443]\n    - <<: *base\n      name: sidecar\n\
+                     copy: *base\n8080: open\n---\nother: 1\n";
+        let get = |path: &str| yaml.get_path(input, path).unwrap();
+        assert_eq!(
+            get("spec.containers[0].image"),
+            Some(Value::from("nginx:1.25"))
+        );
+        assert_eq!(
+            get("spec.containers[0].ports"),
+            Some(crate::yaml!([80, 443]))
+        );
+        assert_eq!(
+            get("base"),
+            Some(crate::yaml!({"image": "nginx", "port": 80}))
+        );
+        assert_eq!(get("8080"), Some(Value::from("open")));
+        assert_eq!(get("spec.containers[2]"), None);
+        assert_eq!(get("other"), None);
+        // Merge keys and aliased collections fall back to the whole document
+        assert_eq!(get("spec.containers[1].port"), Some(Value::Int(80)));
+        assert_eq!(get("copy.image"), Some(Value::from("nginx")));
+        assert!(yaml.get_path(input, "spec..image").is_err());
+    }
+
+    #[test]
+    fn test_anchor_scope() {
+        let input = "a: &x 1\n---\nb: *x\n";
+        let mut yaml = Yaml::new();
+        let error = yaml.load_all_str(input).unwrap_err();
+        assert!(error.to_string().contains("Unknown anchor"));
+        assert!(yaml.load_all_str_shared(input).is_err());
+
+        yaml.config_mut().anchor_scope = AnchorScope::Stream;
+        let documents = yaml.load_all_str(input).unwrap();
+        assert_eq!(documents[1]["b"], Value::Int(1));
+        let documents = yaml.load_all_str_shared(input).unwrap();
+        assert_eq!(documents[1].to_value()["b"], Value::Int(1));
+    }
+
+    #[test]
+    fn test_unknown_handle() {
+        let input = "%TAG ! tag:example.com,2024:\n---\nitem: !x!widget w\n";
+        let mut yaml = Yaml::new();
+        let error = yaml.load_str(input).unwrap_err();
+        assert!(error.to_string().contains("undefined tag handle '!x!'"));
+        assert!(yaml.load_str_with_comments(input).is_err());
+        assert!(yaml.load_str_shared(input).is_err());
+        let declared = "%TAG !x! tag:example.com,2024:\n---\nitem: !x!widget w\n";
+        assert!(yaml.load_str(declared).is_ok());
+
+        yaml.config_mut().unknown_handle = UnknownHandle::KeepVerbatim;
+        let value = yaml.load_str(input).unwrap();
+        assert_eq!(value["item"], Value::tagged("!x!widget", Value::from("w")));
+
+        yaml.config_mut().unknown_handle = UnknownHandle::FallbackPrimary;
+        let value = yaml.load_str(input).unwrap();
+        assert_eq!(
+            value["item"],
+            Value::tagged("tag:example.com,2024:x!widget", Value::from("w"))
+        );
+    }
+
+    #[test]
+    fn test_load_bytes_detects_encoding() {
+        use crate::encoding::Encoding;
+
+        let yaml = Yaml::new();
+        let text = "name: café\nitems: [1, 2]\n---\nnext: true\n";
+        let expected = yaml.load_all_str(text).unwrap();
+        for encoding in [Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf32Be] {
+            let mut bytes = encoding.bom().to_vec();
+            bytes.extend(encoding.encode(text));
+            assert_eq!(yaml.load_all_bytes(&bytes).unwrap(), expected);
+            assert_eq!(yaml.load_bytes(&bytes).unwrap(), expected[0]);
+        }
+
+        // Error positions index into the UTF-16 bytes, BOM included
+        let invalid = "a: é\nb: *missing\n";
+        let utf8_position = *yaml.load_str(invalid).unwrap_err().position().unwrap();
+        let mut bytes = Encoding::Utf16Be.bom().to_vec();
+        bytes.extend(Encoding::Utf16Be.encode(invalid));
+        let error = yaml.load_bytes(&bytes).unwrap_err();
+        let position = error.position().unwrap();
+        assert_eq!(
+            (position.line, position.column),
+            (utf8_position.line, utf8_position.column)
+        );
+        let units = invalid[..utf8_position.index].encode_utf16().count();
+        assert_eq!(position.index, 2 + units * 2);
+    }
+
+    #[test]
+    fn test_line_ending_and_bom_round_trip() {
+        let mut yaml = Yaml::with_config(YamlConfig {
+            loader_type: LoaderType::RoundTrip,
+            preserve_comments: true,
+            line_ending: LineEnding::Preserve,
+            ..YamlConfig::default()
+        });
+        let input = "\u{feff}# settings\r\nname: app\r\nports:\r\n  - 80\r\n";
+
+        let loaded = yaml.load_str_with_comments(input).unwrap();
+        assert_eq!(loaded.style.line_ending, Some(LineEnding::Crlf));
+        assert!(loaded.style.bom);
+        assert_eq!(loaded.value["name"], Value::from("app"));
+
+        yaml.config_mut().emit_bom = loaded.style.bom;
+        let output = yaml.dump_str_with_comments(&loaded).unwrap();
+        assert!(output.starts_with('\u{feff}'));
+        assert!(output.contains("name: app\r\n"));
+        assert!(!output.replace("\r\n", "").contains('\n'));
+
+        // Plain values have no recorded line ending, so Preserve writes `\n`
+        let output = yaml.dump_str(&loaded.value).unwrap();
+        assert!(output.starts_with('\u{feff}'));
+        assert!(!output.contains('\r'));
+
+        yaml.config_mut().line_ending = LineEnding::Crlf;
+        yaml.config_mut().emit_bom = false;
+        let output = yaml.dump_all_str(&[Value::Int(1), Value::Int(2)]).unwrap();
+        assert!(output.starts_with("1\r\n---\r\n2"));
+
+        assert_eq!(yaml.load_str("\u{feff}a: 1").unwrap()["a"], Value::Int(1));
+    }
+
+    #[test]
+    fn test_tag_emission() {
+        let mut yaml = Yaml::with_config(YamlConfig {
+            loader_type: LoaderType::RoundTrip,
+            preserve_comments: true,
+            ..YamlConfig::default()
+        });
+        let input = "a: !!str 123\nb: !!float 1\nc: !point 1,2\nd: '7'\ne: 8\n";
+        let value = yaml.load_str_with_comments(input).unwrap();
+        assert_eq!(value.value["a"], Value::from("123"));
+        assert_eq!(value.value["b"], Value::Float(1.0));
+        assert_eq!(value.value["d"], Value::from("7"));
+
+        assert_eq!(
+            yaml.dump_str_with_comments(&value).unwrap(),
+            "a: \"123\"\nb: 1.0\nc: !point \"1,2\"\nd: \"7\"\ne: 8\n"
+        );
+
+        yaml.config_mut().tag_emission = TagEmission::NonDefault;
+        assert_eq!(
+            yaml.dump_str_with_comments(&value).unwrap(),
+            "a: !!str \"123\"\nb: !!float 1.0\nc: !point \"1,2\"\nd: !!str \"7\"\ne: 8\n"
+        );
+
+        yaml.config_mut().tag_emission = TagEmission::Always;
+        let dumped = yaml.dump_str(&value.value).unwrap();
+        assert_eq!(
+            dumped,
+            "!!str a: !!str \"123\"\n!!str b: !!float 1.0\n!!str c: !point \"1,2\"\n\
+             !!str d: !!str \"7\"\n!!str e: !!int 8\n"
+        );
+        assert_eq!(yaml.load_str(&dumped).unwrap(), value.value);
+    }
+
+    #[test]
+    fn test_dump_fmt() {
+        let mut yaml = Yaml::new();
+        let value = crate::yaml!({"name": "café", "tags": ["a", "b"]});
+        let mut output = String::from("# header\n");
+        yaml.dump_fmt(&value, &mut output).unwrap();
+        assert_eq!(
+            output,
+            format!("# header\n{}", yaml.dump_str(&value).unwrap())
+        );
+
+        yaml.config_mut().line_ending = LineEnding::Crlf;
+        yaml.config_mut().final_newline = FinalNewline::Omit;
+        let mut output = String::new();
+        yaml.dump_fmt(&value, &mut output).unwrap();
+        assert!(output.starts_with("name: café\r\n"));
+        assert!(output.ends_with("- b"));
+
+        // A character split across writes reaches the formatter whole
+        let mut output = String::new();
+        let mut writer = FmtWriter {
+            inner: &mut output,
+            partial: Vec::new(),
+        };
+        let bytes = "é".as_bytes();
+        writer.write_all(&bytes[..1]).unwrap();
+        assert!(writer.flush().is_err());
+        writer.write_all(&bytes[1..]).unwrap();
+        writer.flush().unwrap();
+        assert_eq!(output, "é");
+    }
+
+    #[test]
+    fn test_final_newline() {
+        let mut yaml = Yaml::with_config(YamlConfig {
+            loader_type: LoaderType::RoundTrip,
+            preserve_comments: true,
+            ..YamlConfig::default()
+        });
+        let value = yaml.load_str("a: 1").unwrap();
+        let ends_without = yaml.load_str_with_comments("a: 1").unwrap();
+        let ends_blank = yaml.load_str_with_comments("a: 1\r\n\r\n\r\n").unwrap();
+        assert_eq!(ends_without.style.trailing_breaks, Some(0));
+        assert_eq!(ends_blank.style.trailing_breaks, Some(3));
+
+        // Every dump ends the same way
+        assert_eq!(yaml.dump_str(&value).unwrap(), "a: 1\n");
+        assert_eq!(
+            yaml.dump_str_with_comments(&ends_without).unwrap(),
+            "a: 1\n"
+        );
+        assert_eq!(
+            yaml.dump_all_str(std::slice::from_ref(&value)).unwrap(),
+            "a: 1\n"
+        );
+        let node = yaml.load_str_shared("a: 1").unwrap();
+        assert_eq!(yaml.dump_str_shared(&node).unwrap(), "a: 1\n");
+
+        yaml.config_mut().final_newline = FinalNewline::Omit;
+        assert_eq!(yaml.dump_str(&value).unwrap(), "a: 1");
+        assert_eq!(yaml.dump_str_with_comments(&ends_blank).unwrap(), "a: 1");
+
+        yaml.config_mut().final_newline = FinalNewline::Preserve;
+        yaml.config_mut().line_ending = LineEnding::Preserve;
+        assert_eq!(yaml.dump_str(&value).unwrap(), "a: 1\n");
+        assert_eq!(yaml.dump_str_with_comments(&ends_without).unwrap(), "a: 1");
+        assert_eq!(
+            yaml.dump_str_with_comments(&ends_blank).unwrap(),
+            "a: 1\r\n\r\n\r\n"
+        );
+    }
+
+    #[test]
+    fn test_load_and_dump_stats() {
+        let yaml = Yaml::new();
+        let input = "base: &b {x: [1, 2]}\nother: *b\n---\n- a\n";
+
+        let (value, stats) = yaml.load_str_with_stats(input).unwrap();
+        assert_eq!(value, yaml.load_str(input).unwrap());
+        assert_eq!(stats.bytes, input.len());
+        assert_eq!(stats.documents, 1);
+        assert_eq!(stats.anchors, 1);
+        assert_eq!(stats.alias_expansions, 1);
+        assert_eq!(stats.max_depth, 3);
+        assert!(stats.complexity_score > 0);
+        assert!(stats.tokens > 0 && stats.events > 0);
+
+        let (documents, stats) = yaml.load_all_str_with_stats(input).unwrap();
+        assert_eq!(documents.len(), 2);
+        assert_eq!(stats.documents, 2);
+
+        let (output, stats) = yaml.dump_all_str_with_stats(&documents).unwrap();
+        assert_eq!(output, yaml.dump_all_str(&documents).unwrap());
+        assert_eq!(stats.bytes, output.len());
+        assert_eq!(stats.documents, 2);
+    }
+
+    #[test]
+    fn test_per_call_limits() {
+        let yaml = Yaml::with_config(YamlConfig::default().with_limits(Limits::permissive()));
+        let deep = format!("{}1{}", "[".repeat(40), "]".repeat(40));
+
+        assert!(yaml.load_str(&deep).is_ok());
+        assert!(yaml
+            .load_str_with_limits(&deep, &Limits::untrusted_input())
+            .is_err());
+        assert!(yaml.load_str_with_limits(&deep, &Limits::strict()).is_ok());
+
+        let documents = yaml
+            .load_all_str_with_limits("a: 1\n---\nb: 2\n", &Limits::untrusted_input())
+            .unwrap();
+        assert_eq!(documents, yaml.load_all_str("a: 1\n---\nb: 2\n").unwrap());
+    }
+
+    #[test]
+    fn test_load_deeply_nested() {
+        let depth = 9_000;
+        let deep = format!("{}1{}", "[".repeat(depth), "]".repeat(depth));
+        let yaml = Yaml::new();
+        assert!(yaml
+            .load_str_with_limits(&deep, &Limits::permissive())
+            .unwrap()
+            .is_sequence());
+        assert!(yaml.load_str(&deep).is_err());
+    }
+
+    #[test]
+    fn test_dump_deeply_nested() {
+        let depth = 100_000;
+        let mut deep = Value::Int(1);
+        for _ in 0..depth {
+            deep = Value::Sequence(vec![deep]);
+        }
+        let limits = Limits {
+            max_depth: 2 * depth,
+            ..Limits::permissive()
+        };
+
+        let flow = Yaml::with_config(YamlConfig {
+            flow_style: FlowStyle::Flow,
+            ..YamlConfig::default()
+        });
+        let output = flow.dump_str(&deep).unwrap();
+        assert_eq!(
+            output,
+            format!("{}1{}\n", "[".repeat(depth), "]".repeat(depth))
+        );
+        assert_eq!(flow.load_str_with_limits(&output, &limits).unwrap(), deep);
+
+        // Block output indents every level, so its size grows with the square
+        // of the depth and a shallower tree keeps it small
+        let block_depth = 500;
+        let nested = || {
+            let mut deep = Value::Int(1);
+            for _ in 0..block_depth {
+                let mut map = indexmap::IndexMap::new();
+                map.insert(Value::String("k".to_string()), deep);
+                deep = Value::Mapping(map);
+            }
+            deep
+        };
+        let deep = nested();
+        let yaml = Yaml::new();
+        let output = yaml.dump_str(&deep).unwrap();
+        // Each `k: ` line is indented by two spaces per level above it
+        assert_eq!(
+            output.len(),
+            block_depth * (block_depth - 1) + 4 * block_depth + 1
+        );
+        assert_eq!(yaml.load_str_with_limits(&output, &limits).unwrap(), deep);
+
+        // A repeated copy is found and written as an alias
+        let output = yaml
+            .dump_str(&Value::Sequence(vec![nested(), nested()]))
+            .unwrap();
+        assert!(output.ends_with("- *anchor0\n"));
+    }
+
+    #[test]
+    fn test_shared_across_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Yaml>();
+        assert_send_sync::<YamlConfig>();
+
+        let mut config = YamlConfig::default();
+        config
+            .tag_handlers
+            .register("!point", crate::tag::PointTagHandler);
+        let yaml = Arc::new(Yaml::with_config(config));
+
+        let threads: Vec<_> = (0..4)
+            .map(|i| {
+                let yaml = Arc::clone(&yaml);
+                std::thread::spawn(move || yaml.load_str(&format!("at: !point {i}, 2\n")))
+            })
+            .collect();
+        for (i, thread) in threads.into_iter().enumerate() {
+            let value = thread.join().unwrap().unwrap();
+            assert_eq!(
+                value.get_str("at"),
+                Some(&Value::Sequence(vec![
+                    Value::Float(i as f64),
+                    Value::Float(2.0)
+                ]))
+            );
+        }
+        assert!(yaml.load_str_shared("!point 1, 2").is_ok());
+    }
+
+    #[test]
+    fn test_resource_hook() {
+        /// Rejects loads that expand more aliases than a shared budget allows
+        #[derive(Debug)]
+        struct AliasBudget(usize);
+
+        impl ResourceHook for AliasBudget {
+            fn on_alias(&self, stats: &crate::ResourceStats) -> Result<()> {
+                if stats.alias_expansions > self.0 {
+                    return Err(crate::Error::limit_exceeded("alias budget exceeded"));
+                }
+                Ok(())
+            }
+        }
+
+        let mut yaml = Yaml::new();
+        yaml.config_mut().resource_hook = Some(Arc::new(AliasBudget(1)));
+
+        assert!(yaml.load_str("a: &a 1\nb: *a\n").is_ok());
+        let error = yaml.load_str("a: &a 1\nb: *a\nc: *a\n").unwrap_err();
+        assert!(error.to_string().contains("alias budget exceeded"));
+        assert!(yaml.load_str_shared("a: &a 1\nb: *a\nc: *a\n").is_err());
+    }
+
+    // Loading under the `sort-keys` feature reorders the keys these compare
+    #[cfg(not(feature = "sort-keys"))]
+    #[test]
+    fn test_dump_with_styles() {
+        use crate::parser::ScalarStyle;
+
+        let yaml = Yaml::new();
+        let value = yaml
+            .load_str("name: web\nscript: \"make\\nmake install\\n\"\nports: [80, 443]\n")
+            .unwrap();
+
+        let mut styles = StyleMap::new();
+        styles.set("script", ScalarStyle::Literal).unwrap();
+        styles.set("ports", FlowStyle::Flow).unwrap();
+        styles.set("name", ScalarStyle::DoubleQuoted).unwrap();
+        let dumped = yaml.dump_str_with_styles(&value, &styles).unwrap();
+        assert_eq!(
+            dumped,
+            "name: \"web\"\nscript: |\n  make\n  make install\nports: [80, 443]\n"
+        );
+        assert_eq!(yaml.load_str(&dumped).unwrap(), value);
+
+        // Without styles the dump matches a plain dump
+        let plain = yaml.dump_str_with_styles(&value, &StyleMap::new()).unwrap();
+        assert_eq!(plain, yaml.dump_str(&value).unwrap());
+
+        // A flow hint on the root writes the whole document in flow style
+        let mut styles = StyleMap::new();
+        styles.set_path(Path::new(), FlowStyle::Flow);
+        let dumped = yaml.dump_str_with_styles(&value, &styles).unwrap();
+        assert_eq!(yaml.load_str(&dumped).unwrap(), value);
+        assert!(dumped.starts_with('{'));
+    }
+
+    #[test]
+    fn test_strict_mode_rejects_invalid_escapes() {
+        let input = "a: \"\\q\"\n";
+        assert_eq!(
+            Yaml::new().load_str(input).unwrap(),
+            Value::mapping_with(vec![(Value::from("a"), Value::from("\\q"))])
+        );
+        let error = Yaml::with_config(YamlConfig::secure())
+            .load_str(input)
+            .unwrap_err();
+        assert!(error.to_string().contains("invalid escape sequence"));
+    }
+
+    #[test]
+    fn test_loader_type_semantics() {
+        let load = |loader_type, input: &str| Yaml::with_loader(loader_type).load_str(input);
+        let input = "a: yes\nb: 0b11\nc: 1_000\nd: .NaN\ne: Null\nf:\ng: 0o17\nh: -1.5e3\n";
+
+        // Safe, RoundTrip and Full type the YAML 1.1 spellings too
+        for loader_type in [LoaderType::Safe, LoaderType::RoundTrip, LoaderType::Full] {
+            let value = load(loader_type, input).unwrap();
+            assert_eq!(value["a"], Value::Bool(true), "{loader_type:?}");
+            assert_eq!(value["g"], Value::Int(15), "{loader_type:?}");
+        }
+
+        // Strict types by the core schema alone
+        let value = load(LoaderType::Strict, input).unwrap();
+        assert_eq!(value["a"], Value::from("yes"));
+        assert_eq!(value["b"], Value::from("0b11"));
+        assert_eq!(value["c"], Value::from("1_000"));
+        assert!(value["d"].as_float().is_some_and(f64::is_nan));
+        assert_eq!(value["e"], Value::Null);
+        assert_eq!(value["f"], Value::Null);
+        assert_eq!(value["g"], Value::Int(15));
+        assert_eq!(value["h"], Value::Float(-1500.0));
+        assert_eq!(
+            load(
+                LoaderType::Strict,
+                "[!!float 1, !!str 0x10, !!int '7', !!null '']"
+            )
+            .unwrap(),
+            crate::yaml!([1.0, "0x10", 7, null])
+        );
+        for input in [
+            "!!bool yes",
+            "!!int 1.5",
+            "!Ref x",
+            "!!binary aGk=",
+            "!!str [a]",
+        ] {
+            let error = load(LoaderType::Strict, input).unwrap_err();
+            assert!(
+                error.to_string().contains("core schema"),
+                "{input}: {error}"
+            );
+        }
+
+        // Base loads every untagged scalar as a string
+        let value = load(LoaderType::Base, "a: 1\nb: true\nc:\nd: !!int 2\n").unwrap();
+        assert_eq!(
+            value,
+            crate::yaml!({"a": "1", "b": "true", "c": "", "d": 2})
+        );
+
+        // Application tags are kept unless rejected, which Full ignores
+        let tagged = "key: !Ref name\n? [a, b]\n: pair\n";
+        assert!(load(LoaderType::Safe, tagged).unwrap()["key"].is_tagged());
+        let mut yaml = Yaml::new();
+        yaml.config_mut().reject_unknown_tags = true;
+        let error = yaml.load_str(tagged).unwrap_err();
+        assert!(error.to_string().contains("Unknown tag '!Ref'"), "{error}");
+        assert_eq!(error.position().map(|p| p.line), Some(1));
+
+        yaml.config_mut().reject_unknown_tags = false;
+        yaml.config_mut().reject_complex_keys = true;
+        let error = yaml.load_str(tagged).unwrap_err();
+        assert!(error.to_string().contains("Complex mapping key"), "{error}");
+
+        yaml.config_mut().reject_unknown_tags = true;
+        yaml.config_mut().loader_type = LoaderType::Full;
+        assert!(yaml.load_str(tagged).is_ok());
+
+        // A registered handler constructs the tag whatever the loader type
+        yaml.config_mut().loader_type = LoaderType::Strict;
+        yaml.config_mut().reject_complex_keys = false;
+        yaml.config_mut()
+            .tag_handlers
+            .register("!point", crate::tag::PointTagHandler);
+        assert_eq!(
+            yaml.load_str("at: !point 1, 2").unwrap()["at"],
+            crate::yaml!([1.0, 2.0])
+        );
+
+        // The commented round-trip load applies the same options
+        let mut round_trip = Yaml::with_config(YamlConfig {
+            loader_type: LoaderType::RoundTrip,
+            preserve_comments: true,
+            reject_unknown_tags: true,
+            ..YamlConfig::default()
+        });
+        assert!(round_trip.load_str_with_comments(tagged).is_err());
+        round_trip.config_mut().reject_unknown_tags = false;
+        assert!(round_trip.load_str_with_comments(tagged).is_ok());
+    }
 }
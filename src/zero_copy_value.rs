@@ -77,6 +77,103 @@ impl OptimizedValue {
         Self::Mapping(Rc::new(map))
     }
 
+    /// Get the type name of this value
+    pub const fn type_name(&self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Bool(_) => "bool",
+            Self::Int(_) => "int",
+            Self::Float(_) => "float",
+            Self::String(_) => "string",
+            Self::Sequence(_) => "sequence",
+            Self::Mapping(_) => "mapping",
+        }
+    }
+
+    /// Check if this value is null
+    pub const fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
+    /// Check if this value is a boolean
+    pub const fn is_bool(&self) -> bool {
+        matches!(self, Self::Bool(_))
+    }
+
+    /// Check if this value is an integer
+    pub const fn is_int(&self) -> bool {
+        matches!(self, Self::Int(_))
+    }
+
+    /// Check if this value is a float
+    pub const fn is_float(&self) -> bool {
+        matches!(self, Self::Float(_))
+    }
+
+    /// Check if this value is a string
+    pub const fn is_string(&self) -> bool {
+        matches!(self, Self::String(_))
+    }
+
+    /// Check if this value is a sequence
+    pub const fn is_sequence(&self) -> bool {
+        matches!(self, Self::Sequence(_))
+    }
+
+    /// Check if this value is a mapping
+    pub const fn is_mapping(&self) -> bool {
+        matches!(self, Self::Mapping(_))
+    }
+
+    /// Check if this value is a number (int or float)
+    pub const fn is_number(&self) -> bool {
+        matches!(self, Self::Int(_) | Self::Float(_))
+    }
+
+    /// Get the length of sequences and mappings, None for scalars
+    pub fn len(&self) -> Option<usize> {
+        match self {
+            Self::Sequence(seq) => Some(seq.len()),
+            Self::Mapping(map) => Some(map.len()),
+            _ => None,
+        }
+    }
+
+    /// Check if sequences, mappings, or strings are empty
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Self::Sequence(seq) => seq.is_empty(),
+            Self::Mapping(map) => map.is_empty(),
+            Self::String(s) => s.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Get this value as a boolean, if possible
+    pub const fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Get this value as an integer, if possible
+    pub const fn as_int(&self) -> Option<i64> {
+        match self {
+            Self::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// Get this value as a float, if possible
+    pub const fn as_float(&self) -> Option<f64> {
+        match self {
+            Self::Float(f) => Some(*f),
+            Self::Int(i) => Some(*i as f64),
+            _ => None,
+        }
+    }
+
     /// Get a reference to the string if this is a string value
     pub fn as_str(&self) -> Option<&str> {
         if let Self::String(s) = self {
@@ -104,22 +201,55 @@ impl OptimizedValue {
         }
     }
 
+    /// Index into a sequence or mapping
+    pub fn get(&self, index: &Self) -> Option<&Self> {
+        match (self, index) {
+            (Self::Sequence(seq), Self::Int(i)) => {
+                usize::try_from(*i).ok().and_then(|i| seq.get(i))
+            }
+            (Self::Mapping(map), key) => map.get(key),
+            _ => None,
+        }
+    }
+
+    /// Convenience method to get a value by string key
+    pub fn get_str(&self, key: &str) -> Option<&Self> {
+        match self {
+            Self::Mapping(map) => map.get(&Self::string(key)),
+            _ => None,
+        }
+    }
+
+    /// Get a value by numeric index (for sequences)
+    pub fn get_index(&self, index: usize) -> Option<&Self> {
+        match self {
+            Self::Sequence(seq) => seq.get(index),
+            _ => None,
+        }
+    }
+
     /// Convert from regular Value
-    pub fn from_value(value: crate::Value) -> Self {
-        match value {
+    pub fn from_value(mut value: crate::Value) -> Self {
+        match &mut value {
             crate::Value::Null => Self::Null,
-            crate::Value::Bool(b) => Self::Bool(b),
-            crate::Value::Int(i) => Self::Int(i),
-            crate::Value::Float(f) => Self::Float(f),
-            crate::Value::String(s) => Self::String(Rc::new(s)),
-            crate::Value::Sequence(seq) => {
-                Self::Sequence(Rc::new(seq.into_iter().map(Self::from_value).collect()))
-            }
+            crate::Value::Bool(b) => Self::Bool(*b),
+            crate::Value::Int(i) => Self::Int(*i),
+            crate::Value::Float(f) => Self::Float(*f),
+            crate::Value::String(s) => Self::String(Rc::new(std::mem::take(s))),
+            crate::Value::Sequence(seq) => Self::Sequence(Rc::new(
+                std::mem::take(seq)
+                    .into_iter()
+                    .map(Self::from_value)
+                    .collect(),
+            )),
             crate::Value::Mapping(map) => Self::Mapping(Rc::new(
-                map.into_iter()
+                std::mem::take(map)
+                    .into_iter()
                     .map(|(k, v)| (Self::from_value(k), Self::from_value(v)))
                     .collect(),
             )),
+            // Optimized values have no tags
+            crate::Value::Tagged(tagged) => Self::from_value(tagged.value.take()),
         }
     }
 
@@ -150,7 +280,7 @@ impl fmt::Display for OptimizedValue {
             Self::Bool(b) => write!(f, "{}", b),
             Self::Int(i) => write!(f, "{}", i),
             Self::Float(fl) => write!(f, "{}", fl),
-            Self::String(s) => write!(f, "{}", s),
+            Self::String(s) => write!(f, "\"{}\"", s),
             Self::Sequence(seq) => {
                 write!(f, "[")?;
                 for (i, item) in seq.iter().enumerate() {
@@ -175,6 +305,48 @@ impl fmt::Display for OptimizedValue {
     }
 }
 
+impl From<crate::Value> for OptimizedValue {
+    fn from(value: crate::Value) -> Self {
+        Self::from_value(value)
+    }
+}
+
+impl From<&crate::Value> for OptimizedValue {
+    fn from(value: &crate::Value) -> Self {
+        Self::from_value(value.clone())
+    }
+}
+
+impl From<OptimizedValue> for crate::Value {
+    fn from(value: OptimizedValue) -> Self {
+        value.to_value()
+    }
+}
+
+impl From<&OptimizedValue> for crate::Value {
+    fn from(value: &OptimizedValue) -> Self {
+        value.to_value()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for OptimizedValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Self::Null => serializer.serialize_none(),
+            Self::Bool(b) => serializer.serialize_bool(*b),
+            Self::Int(i) => serializer.serialize_i64(*i),
+            Self::Float(f) => serializer.serialize_f64(*f),
+            Self::String(s) => serializer.serialize_str(s),
+            Self::Sequence(seq) => serde::Serialize::serialize(seq.as_slice(), serializer),
+            Self::Mapping(map) => serde::Serialize::serialize(map.as_ref(), serializer),
+        }
+    }
+}
+
 impl std::hash::Hash for OptimizedValue {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         match self {
@@ -250,4 +422,46 @@ mod tests {
 
         assert_eq!(original, converted_back);
     }
+
+    #[test]
+    fn test_accessor_parity() {
+        let value = crate::Value::mapping_with(vec![
+            (crate::Value::string("name"), crate::Value::string("app")),
+            (
+                crate::Value::string("ports"),
+                crate::Value::sequence_with(vec![crate::Value::int(80), crate::Value::float(1.5)]),
+            ),
+            (crate::Value::string("debug"), crate::Value::Null),
+        ]);
+        let optimized = OptimizedValue::from(&value);
+
+        assert_eq!(optimized.type_name(), value.type_name());
+        assert_eq!(optimized.len(), value.len());
+        assert!(optimized.is_mapping() && !optimized.is_empty());
+        assert_eq!(
+            optimized.get_str("name").and_then(OptimizedValue::as_str),
+            Some("app")
+        );
+        assert!(optimized
+            .get_str("debug")
+            .is_some_and(OptimizedValue::is_null));
+        assert!(optimized.get_str("missing").is_none());
+
+        let ports = optimized.get_str("ports").unwrap();
+        assert_eq!(ports.len(), Some(2));
+        assert_eq!(
+            ports.get_index(0).and_then(OptimizedValue::as_int),
+            Some(80)
+        );
+        assert_eq!(
+            ports
+                .get(&OptimizedValue::int(1))
+                .and_then(OptimizedValue::as_float),
+            Some(1.5)
+        );
+        assert!(ports.get(&OptimizedValue::int(-1)).is_none());
+        assert_eq!(optimized.to_string(), value.to_string());
+
+        assert_eq!(crate::Value::from(optimized), value);
+    }
 }
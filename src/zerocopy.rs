@@ -3,8 +3,14 @@
 //! This module provides data structures and utilities for minimizing allocations
 //! during YAML parsing by using string slices where possible instead of owned strings.
 
-use crate::{Position, Result};
+use crate::scanner::scalar_scanner::{
+    ends_plain_scalar, escaped_char, hex_escape_digits, invalid_escape, is_anchor_char,
+    is_flow_indicator,
+};
+use crate::scanner::{fold_block_lines, QuoteStyle, Scanner, Token, TokenType};
+use crate::{BasicScanner, Chomping, Error, Limits, Position, Result};
 use std::borrow::Cow;
+use std::collections::VecDeque;
 
 /// A zero-copy string that can either borrow from the input or own its data
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -82,6 +88,10 @@ pub enum ZeroTokenType<'a> {
     DocumentStart,
     /// Document end marker (...)
     DocumentEnd,
+    /// YAML version directive (%YAML)
+    YamlDirective(u8, u8),
+    /// Tag directive (%TAG) with its handle and prefix
+    TagDirective(ZeroString<'a>, ZeroString<'a>),
     /// Block sequence start ([)
     BlockSequenceStart,
     /// Block mapping start ({)
@@ -159,6 +169,10 @@ impl<'a> ZeroToken<'a> {
             ZeroTokenType::StreamEnd => TokenType::StreamEnd,
             ZeroTokenType::DocumentStart => TokenType::DocumentStart,
             ZeroTokenType::DocumentEnd => TokenType::DocumentEnd,
+            ZeroTokenType::YamlDirective(major, minor) => TokenType::YamlDirective(major, minor),
+            ZeroTokenType::TagDirective(handle, prefix) => {
+                TokenType::TagDirective(handle.into_owned(), prefix.into_owned())
+            }
             ZeroTokenType::BlockSequenceStart => TokenType::BlockSequenceStart,
             ZeroTokenType::BlockMappingStart => TokenType::BlockMappingStart,
             ZeroTokenType::BlockEnd => TokenType::BlockEnd,
@@ -184,6 +198,7 @@ impl<'a> ZeroToken<'a> {
 }
 
 /// Memory pool for token allocation to reduce heap allocations
+#[derive(Debug)]
 pub struct TokenPool<'a> {
     /// Pool of reusable tokens
     tokens: Vec<ZeroToken<'a>>,
@@ -232,6 +247,12 @@ impl<'a> TokenPool<'a> {
 }
 
 /// Zero-copy string scanner that operates on slices
+///
+/// Besides scanning single scalars, tags and directives, the scanner
+/// produces the tokens of a whole stream through [`Scanner`], a line at a
+/// time, in the same form as the [`BasicScanner`] so that a
+/// [`BasicParser`](crate::BasicParser) can parse them.
+#[derive(Debug)]
 pub struct ZeroScanner<'a> {
     /// Reference to the input string
     input: &'a str,
@@ -243,6 +264,41 @@ pub struct ZeroScanner<'a> {
     char_indices: Vec<(usize, char)>,
     /// Token pool for allocation optimization
     token_pool: TokenPool<'a>,
+    /// Reject invalid escape sequences instead of keeping them literally
+    strict_escapes: bool,
+    /// Flow collections the scanner is inside of
+    flow_level: usize,
+    /// Longest scalar in bytes the scanner reads before failing
+    max_scalar_len: usize,
+    /// Tokens scanned by [`Scanner::get_token`] and not returned yet
+    tokens: VecDeque<Token>,
+    /// Number of tokens scanned so far
+    tokens_scanned: usize,
+    /// Whether the stream start token has been scanned
+    stream_started: bool,
+    /// Whether the stream end token has been scanned
+    stream_ended: bool,
+    /// Open block collections, innermost last, with the indentation of
+    /// their entries
+    blocks: Vec<(usize, BlockKind)>,
+    /// Indentation of the node being scanned
+    current_indent: usize,
+    /// Line of the last `:` value indicator while no node has followed it
+    value_line: Option<usize>,
+    /// Whether the last token was a quoted scalar or the end of a flow
+    /// collection, after which `:` is a value indicator in flow context
+    adjacent_value: bool,
+    /// Whether the next document has a `%YAML` directive already
+    yaml_directive_seen: bool,
+    /// Tag handles the next document's `%TAG` directives define already
+    tag_handles: Vec<String>,
+}
+
+/// The kind of a block collection open in the [`ZeroScanner`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockKind {
+    Sequence,
+    Mapping,
 }
 
 impl<'a> ZeroScanner<'a> {
@@ -256,7 +312,52 @@ impl<'a> ZeroScanner<'a> {
             char_index: 0,
             char_indices,
             token_pool: TokenPool::with_capacity(128), // Start with reasonable capacity
+            strict_escapes: false,
+            flow_level: 0,
+            max_scalar_len: Limits::default().max_scalar_len,
+            tokens: VecDeque::new(),
+            tokens_scanned: 0,
+            stream_started: false,
+            stream_ended: false,
+            blocks: Vec::new(),
+            current_indent: 0,
+            value_line: None,
+            adjacent_value: false,
+            yaml_directive_seen: false,
+            tag_handles: Vec::new(),
+        }
+    }
+
+    /// Report an error for an invalid escape sequence in a double-quoted
+    /// scalar instead of keeping it literally, like
+    /// [`BasicScanner::set_strict_escapes`](crate::BasicScanner::set_strict_escapes)
+    pub fn set_strict_escapes(&mut self, strict: bool) {
+        self.strict_escapes = strict;
+    }
+
+    /// Set how many flow collections the next tokens are inside of, which
+    /// makes `,`, `[`, `]`, `{` and `}` end plain scalars when not zero
+    pub fn set_flow_level(&mut self, flow_level: usize) {
+        self.flow_level = flow_level;
+    }
+
+    /// Fail on a scalar longer than `max_scalar_len` bytes as soon as it is
+    /// read that far, like [`Limits::max_scalar_len`] does for the
+    /// [`BasicScanner`](crate::BasicScanner)
+    pub fn set_max_scalar_len(&mut self, max_scalar_len: usize) {
+        self.max_scalar_len = max_scalar_len;
+    }
+
+    /// Fail once the scalar that started at `start_pos` has grown to `len`
+    /// bytes past the maximum scalar length
+    fn check_scalar_len(&self, start_pos: Position, len: usize) -> Result<()> {
+        if len > self.max_scalar_len {
+            return Err(Error::limit_exceeded(format!(
+                "Maximum scalar length {} exceeded by the scalar at {start_pos}",
+                self.max_scalar_len
+            )));
         }
+        Ok(())
     }
 
     /// Get the current character
@@ -266,9 +367,15 @@ impl<'a> ZeroScanner<'a> {
 
     /// Advance to the next character
     pub fn advance(&mut self) -> Option<char> {
-        if let Some((_byte_index, ch)) = self.char_indices.get(self.char_index) {
-            self.position = self.position.advance(*ch);
+        if let Some(&(_byte_index, ch)) = self.char_indices.get(self.char_index) {
             self.char_index += 1;
+            // A `\r\n` pair is one line break
+            if ch == '\r' && self.current_char() == Some('\n') {
+                self.char_index += 1;
+                self.position = self.position.advance_crlf();
+            } else {
+                self.position = self.position.advance(ch);
+            }
             self.char_indices.get(self.char_index).map(|(_, ch)| *ch)
         } else {
             None
@@ -326,6 +433,22 @@ impl<'a> ZeroScanner<'a> {
         self.position = Position::start();
         self.char_index = 0;
         self.token_pool.reset();
+        self.flow_level = 0;
+        self.tokens.clear();
+        self.tokens_scanned = 0;
+        self.stream_started = false;
+        self.stream_ended = false;
+        self.blocks.clear();
+        self.current_indent = 0;
+        self.value_line = None;
+        self.adjacent_value = false;
+        self.yaml_directive_seen = false;
+        self.tag_handles.clear();
+    }
+
+    /// Number of tokens scanned so far by [`Scanner::get_token`]
+    pub fn tokens_scanned(&self) -> usize {
+        self.tokens_scanned
     }
 
     /// Get scanner statistics for performance monitoring
@@ -345,24 +468,23 @@ impl<'a> ZeroScanner<'a> {
 
         // Find the end of the scalar without allocating
         while let Some(ch) = self.current_char() {
-            // Stop at structural characters (same logic as regular scanner)
-            match ch {
-                '\n' | '\r' => break,
-                ':' if self.peek_char(1).map_or(true, |c| c.is_whitespace()) => break,
-                '#' if self.char_index == 0
-                    || self.peek_char(-1).map_or(false, |c| c.is_whitespace()) =>
-                {
-                    break;
-                }
-                ',' | '[' | ']' | '{' | '}' => break,
-                _ => {
-                    self.advance();
-                }
+            self.check_scalar_len(start_pos, self.position.index - start_pos.index)?;
+            if matches!(ch, '\n' | '\r')
+                || ends_plain_scalar(
+                    ch,
+                    self.peek_char(-1),
+                    self.peek_char(1),
+                    self.flow_level > 0,
+                )
+            {
+                break;
             }
+            self.advance();
         }
 
         // Get the slice without allocation
         let slice = self.slice_from(start_pos)?;
+        self.check_scalar_len(start_pos, slice.len())?;
         let trimmed_slice = slice.trim_end();
 
         // Use borrowed string if possible
@@ -398,6 +520,462 @@ impl<'a> ZeroScanner<'a> {
         Ok(ZeroString::borrowed(slice))
     }
 
+    /// Scan a single or double quoted scalar, borrowing its content when it
+    /// holds no escapes, doubled quotes or line breaks
+    pub fn scan_quoted_scalar_zero_copy(&mut self) -> Result<ZeroToken<'a>> {
+        let start_pos = self.position;
+        let quote = self.current_char().unwrap_or('"');
+        let style = if quote == '\'' {
+            QuoteStyle::Single
+        } else {
+            QuoteStyle::Double
+        };
+        self.advance(); // Skip opening quote
+
+        let content_start = self.position;
+        // The decoded content, once it differs from the input
+        let mut owned: Option<String> = None;
+        loop {
+            let len = owned
+                .as_ref()
+                .map_or(self.position.index - content_start.index, String::len);
+            self.check_scalar_len(start_pos, len)?;
+            let Some(ch) = self.current_char() else {
+                return Err(Error::scan(start_pos, "unclosed quoted scalar"));
+            };
+            match ch {
+                '\'' if quote == '\'' && self.peek_char(1) == Some('\'') => {
+                    self.owned_content(&mut owned, content_start)?.push('\'');
+                    self.advance();
+                    self.advance();
+                }
+                _ if ch == quote => break,
+                '\\' if quote == '"' => {
+                    let escape_pos = self.position;
+                    let value = self.owned_content(&mut owned, content_start)?;
+                    self.advance();
+                    if matches!(self.current_char(), Some('\n' | '\r')) {
+                        // An escaped line break joins the lines without a space
+                        let breaks = self.skip_line_folding();
+                        value.push_str(&"\n".repeat(breaks.saturating_sub(1)));
+                    } else {
+                        self.scan_escape(value, escape_pos)?;
+                    }
+                }
+                '\n' | '\r' => {
+                    let value = self.owned_content(&mut owned, content_start)?;
+                    value.truncate(value.trim_end_matches([' ', '\t']).len());
+                    match self.skip_line_folding() {
+                        1 => value.push(' '),
+                        breaks => value.push_str(&"\n".repeat(breaks - 1)),
+                    }
+                }
+                _ => {
+                    if let Some(value) = owned.as_mut() {
+                        value.push(ch);
+                    }
+                    self.advance();
+                }
+            }
+        }
+
+        let value = match owned {
+            Some(value) => ZeroString::owned(value),
+            None => ZeroString::borrowed(self.slice_from(content_start)?),
+        };
+        self.advance(); // Skip closing quote
+
+        Ok(ZeroToken::new(
+            ZeroTokenType::Scalar(value, style),
+            start_pos,
+            self.position,
+        ))
+    }
+
+    /// The decoded content of a quoted scalar, copied from the input read
+    /// since `content_start` the first time it is needed
+    fn owned_content<'v>(
+        &self,
+        owned: &'v mut Option<String>,
+        content_start: Position,
+    ) -> Result<&'v mut String> {
+        if owned.is_none() {
+            *owned = Some(self.slice_from(content_start)?.to_string());
+        }
+        Ok(owned.get_or_insert_with(String::new))
+    }
+
+    /// Skip the line breaks and indentation folded inside a quoted scalar,
+    /// starting at a line break, and return how many line breaks there were
+    fn skip_line_folding(&mut self) -> usize {
+        let mut breaks = 0;
+        while matches!(self.current_char(), Some('\n' | '\r')) {
+            breaks += 1;
+            self.advance();
+            self.skip_whitespace();
+        }
+        breaks
+    }
+
+    /// Decode the escape sequence whose letter is the current character and
+    /// whose backslash is at `escape_pos`, keeping an unknown escape as
+    /// written unless escapes are strict
+    fn scan_escape(&mut self, value: &mut String, escape_pos: Position) -> Result<()> {
+        let Some(escaped) = self.current_char() else {
+            if self.strict_escapes {
+                return Err(invalid_escape(self.input, escape_pos, "\\"));
+            }
+            value.push('\\');
+            return Ok(());
+        };
+        let decoded = if let Some(decoded) = escaped_char(escaped) {
+            decoded
+        } else if let Some(digits) = hex_escape_digits(escaped) {
+            let code = (1..=digits)
+                .map(|offset| {
+                    self.peek_char(offset as isize)
+                        .and_then(|ch| ch.to_digit(16))
+                })
+                .try_fold(0u32, |code, digit| Some(code * 16 + digit?));
+            let Some(code) = code else {
+                if self.strict_escapes {
+                    let written: String = (1..=digits)
+                        .map_while(|offset| self.peek_char(offset as isize))
+                        .take_while(char::is_ascii_hexdigit)
+                        .collect();
+                    return Err(invalid_escape(
+                        self.input,
+                        escape_pos,
+                        &format!("\\{escaped}{written}"),
+                    ));
+                }
+                value.push('\\');
+                value.push(escaped);
+                self.advance();
+                return Ok(());
+            };
+            let position = self.position;
+            for _ in 0..digits {
+                self.advance();
+            }
+            char::from_u32(code).ok_or_else(|| {
+                Error::scan(
+                    position,
+                    format!("escape sequence U+{code:X} is not a valid Unicode scalar value"),
+                )
+            })?
+        } else if escaped == '\'' && !self.strict_escapes {
+            escaped
+        } else if self.strict_escapes {
+            return Err(invalid_escape(
+                self.input,
+                escape_pos,
+                &format!("\\{escaped}"),
+            ));
+        } else {
+            value.push('\\');
+            escaped
+        };
+        value.push(decoded);
+        self.advance();
+        Ok(())
+    }
+
+    /// Scan a tag such as `!local`, `!!str`, `!e!suffix` or `!<tag:uri>`,
+    /// always borrowed from the input
+    pub fn scan_tag_zero_copy(&mut self) -> Result<ZeroToken<'a>> {
+        let start_pos = self.position;
+        self.advance(); // Skip '!'
+
+        if self.current_char() == Some('<') {
+            // Verbatim tag, up to the closing '>'
+            loop {
+                match self.advance() {
+                    Some('>') => {
+                        self.advance();
+                        break;
+                    }
+                    Some(ch) if ch.is_control() || ch.is_whitespace() => {
+                        return Err(Error::scan(
+                            self.position,
+                            "Invalid character in verbatim tag",
+                        ));
+                    }
+                    Some(_) => {}
+                    None => return Err(Error::scan(start_pos, "unclosed verbatim tag")),
+                }
+            }
+        } else {
+            // The suffix runs to whitespace or a flow indicator, and takes in
+            // the `!` closing a named handle such as `!e!`
+            while let Some(ch) = self.current_char() {
+                if ch.is_whitespace() || ",[]{}".contains(ch) {
+                    break;
+                }
+                self.advance();
+            }
+        }
+
+        Ok(ZeroToken::new(
+            ZeroTokenType::Tag(ZeroString::borrowed(self.slice_from(start_pos)?)),
+            start_pos,
+            self.position,
+        ))
+    }
+
+    /// Scan a `%YAML` or `%TAG` directive, borrowing the handle and prefix of
+    /// a tag directive
+    pub fn scan_directive_zero_copy(&mut self) -> Result<ZeroToken<'a>> {
+        let start_pos = self.position;
+        self.advance(); // Skip '%'
+        let name = self.scan_word();
+        self.skip_whitespace();
+
+        let token_type = match name {
+            "YAML" => {
+                let major = self.scan_version_number("major")?;
+                if self.current_char() != Some('.') {
+                    return Err(Error::scan(self.position, "Expected '.' in YAML version"));
+                }
+                self.advance();
+                let minor = self.scan_version_number("minor")?;
+                ZeroTokenType::YamlDirective(major, minor)
+            }
+            "TAG" => {
+                let handle = self.scan_word();
+                if !(handle.starts_with('!') && handle.ends_with('!')) {
+                    return Err(Error::scan(
+                        self.position,
+                        "Expected '!' at start of tag handle",
+                    ));
+                }
+                self.skip_whitespace();
+                let prefix = self.scan_word();
+                if prefix.is_empty() {
+                    return Err(Error::scan(
+                        self.position,
+                        "Expected tag prefix after tag handle",
+                    ));
+                }
+                ZeroTokenType::TagDirective(
+                    ZeroString::borrowed(handle),
+                    ZeroString::borrowed(prefix),
+                )
+            }
+            _ => return Err(Error::scan(start_pos, "Unknown directive")),
+        };
+
+        Ok(ZeroToken::new(token_type, start_pos, self.position))
+    }
+
+    /// Scan the characters up to the next whitespace
+    fn scan_word(&mut self) -> &'a str {
+        let start = self.position;
+        while self.current_char().is_some_and(|ch| !ch.is_whitespace()) {
+            self.advance();
+        }
+        self.slice_from(start).unwrap_or_default()
+    }
+
+    /// Scan one number of a `%YAML` version
+    fn scan_version_number(&mut self, part: &str) -> Result<u8> {
+        let start = self.position;
+        while self.current_char().is_some_and(|ch| ch.is_ascii_digit()) {
+            self.advance();
+        }
+        self.slice_from(start)?.parse().map_err(|_| {
+            Error::scan(
+                self.position,
+                format!("Expected {part} version number in %YAML directive"),
+            )
+        })
+    }
+
+    /// Scan a literal (`|`) or folded (`>`) block scalar, stopping at the line
+    /// break before the next construct
+    ///
+    /// `parent_indent` is the indentation of the collection holding the
+    /// scalar, or `None` for a document's root node. The content is borrowed
+    /// when it appears in the input as is, such as a single line kept with its
+    /// line break.
+    pub fn scan_block_scalar_zero_copy(
+        &mut self,
+        parent_indent: Option<usize>,
+    ) -> Result<ZeroToken<'a>> {
+        let start_pos = self.position;
+        let folded = self.current_char() == Some('>');
+        self.advance(); // Skip indicator
+
+        // Header indicators, up to the line break
+        let mut chomping = Chomping::Clip;
+        let mut explicit_indent = None;
+        while let Some(ch) = self.current_char() {
+            match ch {
+                '+' => chomping = Chomping::Keep,
+                '-' => chomping = Chomping::Strip,
+                '1'..='9' if explicit_indent.is_none() => {
+                    explicit_indent = ch.to_digit(10).map(|digit| digit as usize);
+                }
+                '1'..='9' => {
+                    return Err(Error::scan(
+                        self.position,
+                        "Multiple indent indicators in block scalar",
+                    ));
+                }
+                ' ' | '\t' => {}
+                '#' => {
+                    while self
+                        .current_char()
+                        .is_some_and(|ch| ch != '\n' && ch != '\r')
+                    {
+                        self.advance();
+                    }
+                    break;
+                }
+                '\n' | '\r' => break,
+                _ => {
+                    return Err(Error::scan(
+                        self.position,
+                        format!("Invalid character '{ch}' in block scalar header"),
+                    ));
+                }
+            }
+            self.advance();
+        }
+
+        let content_indent = match explicit_indent {
+            Some(increment) => parent_indent.map_or(increment, |indent| indent + increment),
+            None => self.find_block_scalar_indent(parent_indent.map_or(0, |indent| indent + 1)),
+        };
+
+        // Content lines with their indentation removed; empty lines are empty
+        let mut lines: Vec<&'a str> = Vec::new();
+        let mut first_line_start = None;
+        let mut terminated = false;
+        // Bytes collected so far, counting a line break per line
+        let mut len = 0;
+        while matches!(self.current_char(), Some('\n' | '\r')) {
+            let saved = (self.position, self.char_index);
+            self.advance();
+            terminated = true;
+            if self.current_char().is_none() {
+                break;
+            }
+            if self.at_document_marker() {
+                (self.position, self.char_index) = saved;
+                break;
+            }
+
+            let mut spaces = 0;
+            while spaces < content_indent && self.current_char() == Some(' ') {
+                spaces += 1;
+                self.advance();
+            }
+            let text_start = self.position;
+            while self
+                .current_char()
+                .is_some_and(|ch| ch != '\n' && ch != '\r')
+            {
+                self.check_scalar_len(start_pos, len + self.position.index - text_start.index)?;
+                self.advance();
+            }
+            let mut text = self.slice_from(text_start)?;
+            if spaces < content_indent {
+                if !text.trim().is_empty() {
+                    // This line is part of the next construct
+                    (self.position, self.char_index) = saved;
+                    break;
+                }
+                text = "";
+            }
+            if !text.is_empty() {
+                first_line_start.get_or_insert(text_start.index);
+            }
+            len += text.len() + 1;
+            self.check_scalar_len(start_pos, len)?;
+            lines.push(text);
+            terminated = false;
+        }
+
+        // Lines after the last one with content only contribute line breaks
+        let body_len = lines
+            .iter()
+            .rposition(|line| !line.is_empty())
+            .map_or(0, |index| index + 1);
+        let body = &lines[..body_len];
+        let breaks = if lines.is_empty() {
+            0
+        } else {
+            lines.len() - body_len.saturating_sub(1) - usize::from(!terminated)
+        };
+
+        let mut content = if folded {
+            fold_block_lines(body)
+        } else {
+            body.join("\n")
+        };
+        match chomping {
+            Chomping::Strip => {}
+            Chomping::Clip => {
+                if body_len > 0 && breaks > 0 {
+                    content.push('\n');
+                }
+            }
+            Chomping::Keep => content.push_str(&"\n".repeat(breaks)),
+        }
+        self.check_scalar_len(start_pos, content.len())?;
+
+        let borrowed = first_line_start
+            .and_then(|start| self.input.get(start..start + content.len()))
+            .filter(|slice| *slice == content);
+        let content = match borrowed {
+            Some(slice) => ZeroString::borrowed(slice),
+            None if content.is_empty() => ZeroString::borrowed(""),
+            None => ZeroString::owned(content),
+        };
+        let token_type = if folded {
+            ZeroTokenType::BlockScalarFolded(content)
+        } else {
+            ZeroTokenType::BlockScalarLiteral(content)
+        };
+        Ok(ZeroToken::new(token_type, start_pos, self.position))
+    }
+
+    /// Detect the content indentation of a block scalar from its first
+    /// non-empty line, starting at the line break that ends the header
+    fn find_block_scalar_indent(&mut self, min_indent: usize) -> usize {
+        let saved = (self.position, self.char_index);
+
+        // Leading empty lines may be indented further than the first content line
+        let mut indent = min_indent;
+        while matches!(self.current_char(), Some('\n' | '\r')) {
+            self.advance();
+            if self.at_document_marker() {
+                break;
+            }
+            let mut spaces = 0;
+            while self.current_char() == Some(' ') {
+                spaces += 1;
+                self.advance();
+            }
+            indent = indent.max(spaces);
+            if !matches!(self.current_char(), None | Some('\n' | '\r')) {
+                break;
+            }
+        }
+
+        (self.position, self.char_index) = saved;
+        indent
+    }
+
+    /// Whether the scanner is at a `---` or `...` marker in the first column
+    fn at_document_marker(&self) -> bool {
+        let marker = &self.input[self.position.index..];
+        self.position.column == 1
+            && (marker.starts_with("---") || marker.starts_with("..."))
+            && marker[3..].chars().next().is_none_or(char::is_whitespace)
+    }
+
     /// Skip whitespace efficiently
     pub fn skip_whitespace(&mut self) {
         while let Some(ch) = self.current_char() {
@@ -408,6 +986,581 @@ impl<'a> ZeroScanner<'a> {
             }
         }
     }
+
+    /// Queue the tokens of the next line, or the stream start or end
+    fn fetch_line(&mut self) -> Result<()> {
+        if !self.stream_started {
+            self.stream_started = true;
+            self.queue(TokenType::StreamStart, self.position, self.position);
+            return Ok(());
+        }
+        if self.current_char().is_none() {
+            self.close_blocks(self.position);
+            self.queue(TokenType::StreamEnd, self.position, self.position);
+            self.stream_ended = true;
+            return Ok(());
+        }
+
+        if self.position.column == 1 && self.current_char() == Some('%') {
+            let token = self.scan_directive_zero_copy()?.into_owned();
+            self.check_directive(&token)?;
+            self.queue(token.token_type, token.start_position, token.end_position);
+        } else if self.at_document_marker() {
+            let start = self.position;
+            let token_type = if self.current_char() == Some('-') {
+                TokenType::DocumentStart
+            } else {
+                TokenType::DocumentEnd
+            };
+            for _ in 0..3 {
+                self.advance();
+            }
+            if token_type == TokenType::DocumentStart {
+                // Directives before the next document may be repeated
+                self.yaml_directive_seen = false;
+                self.tag_handles.clear();
+            }
+            self.close_blocks(start);
+            self.current_indent = 0;
+            self.queue(token_type, start, self.position);
+        } else if self.position.column == 1 && self.flow_level == 0 {
+            self.scan_indentation()?;
+        }
+        self.scan_line()?;
+
+        // The line break, unless the input ends without one
+        self.advance();
+        Ok(())
+    }
+
+    /// Check that a directive ends its line and is the first of its kind in
+    /// the document
+    fn check_directive(&mut self, token: &Token) -> Result<()> {
+        // Only a comment may follow, after whitespace
+        let separated = self.current_char().is_none_or(char::is_whitespace);
+        self.skip_whitespace();
+        let line_ends = match self.current_char() {
+            None | Some('\n' | '\r') => true,
+            Some('#') => separated,
+            Some(_) => false,
+        };
+        if !line_ends {
+            return Err(Error::scan(
+                self.position,
+                "Expected end of line after directive",
+            ));
+        }
+
+        let position = token.start_position;
+        match &token.token_type {
+            TokenType::YamlDirective(major, minor) => {
+                if self.yaml_directive_seen {
+                    return Err(Error::scan(position, "Duplicate %YAML directive"));
+                }
+                if *major != 1 {
+                    return Err(Error::scan(
+                        position,
+                        format!("Unsupported YAML version {major}.{minor}"),
+                    ));
+                }
+                self.yaml_directive_seen = true;
+            }
+            TokenType::TagDirective(handle, _) => {
+                if self.tag_handles.contains(handle) {
+                    return Err(Error::scan(
+                        position,
+                        format!("Duplicate %TAG directive for handle '{handle}'"),
+                    ));
+                }
+                self.tag_handles.push(handle.clone());
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Read the indentation of a line in block context and close the block
+    /// collections that end before it, unless the line holds no node
+    fn scan_indentation(&mut self) -> Result<()> {
+        let line_start = self.position;
+        while self.current_char() == Some(' ') {
+            self.advance();
+        }
+        let indent = self.position.column - 1;
+        let mut offset = 0;
+        while matches!(self.peek_char(offset), Some(' ' | '\t')) {
+            offset += 1;
+        }
+        if matches!(self.peek_char(offset), None | Some('#' | '\n' | '\r')) {
+            return Ok(());
+        }
+        // Tabs may separate tokens but never indent them
+        if self.current_char() == Some('\t') {
+            return Err(Error::invalid_character(self.position, '\t', "indentation"));
+        }
+
+        self.current_indent = indent;
+        let entry =
+            self.current_char() == Some('-') && self.peek_char(1).is_none_or(char::is_whitespace);
+        while let Some(&(block_indent, kind)) = self.blocks.last() {
+            // A sequence at the indentation of its mapping ends at the next key
+            let ends = block_indent > indent
+                || block_indent == indent && kind == BlockKind::Sequence && !entry;
+            if !ends {
+                break;
+            }
+            if self.blocks.len() == 1 {
+                return Err(Error::scan(
+                    self.position,
+                    "Expected the end of the document after its root collection",
+                ));
+            }
+            self.blocks.pop();
+            self.queue(TokenType::BlockEnd, line_start, line_start);
+        }
+        Ok(())
+    }
+
+    /// Queue the tokens up to the end of the current line
+    fn scan_line(&mut self) -> Result<()> {
+        while let Some(ch) = self.current_char() {
+            let start = self.position;
+            match ch {
+                '\n' | '\r' => break,
+                ' ' | '\t' => self.skip_whitespace(),
+                '#' => {
+                    while self
+                        .current_char()
+                        .is_some_and(|ch| ch != '\n' && ch != '\r')
+                    {
+                        self.advance();
+                    }
+                }
+                '[' | '{' => {
+                    self.advance();
+                    self.flow_level += 1;
+                    let token_type = if ch == '[' {
+                        TokenType::FlowSequenceStart
+                    } else {
+                        TokenType::FlowMappingStart
+                    };
+                    self.queue(token_type, start, self.position);
+                }
+                ']' | '}' => {
+                    self.advance();
+                    self.flow_level = self.flow_level.saturating_sub(1);
+                    let token_type = if ch == ']' {
+                        TokenType::FlowSequenceEnd
+                    } else {
+                        TokenType::FlowMappingEnd
+                    };
+                    self.queue(token_type, start, self.position);
+                }
+                ',' => {
+                    self.advance();
+                    self.queue(TokenType::FlowEntry, start, self.position);
+                }
+                ':' if !self.is_plain_scalar_start()
+                    || self.flow_level > 0 && self.adjacent_value =>
+                {
+                    // A `:` that starts its line is the value of an explicit `?` key
+                    let explicit_value =
+                        self.flow_level == 0 && start.column - 1 == self.current_indent;
+                    self.advance();
+                    self.queue(TokenType::Value, start, self.position);
+                    if explicit_value {
+                        self.skip_whitespace();
+                        self.indent_compact_collection();
+                    }
+                }
+                '?' if self.peek_char(1).is_none_or(|next| {
+                    next.is_whitespace() || self.flow_level > 0 && ",:]}".contains(next)
+                }) =>
+                {
+                    if self.flow_level == 0 {
+                        self.start_block_mapping()?;
+                    }
+                    self.advance();
+                    self.queue(TokenType::Key, start, self.position);
+                    if self.flow_level == 0 {
+                        self.skip_whitespace();
+                        self.indent_compact_collection();
+                    }
+                }
+                '-' if self.flow_level == 0
+                    && self.peek_char(1).is_none_or(char::is_whitespace) =>
+                {
+                    self.start_block_sequence()?;
+                    self.advance();
+                    self.queue(TokenType::BlockEntry, start, self.position);
+                    // A compact collection on the same line, as in `- key: value`
+                    // or `- - item`, is indented to its first character
+                    self.skip_whitespace();
+                    self.indent_compact_collection();
+                }
+                '"' | '\'' => {
+                    if self.flow_level == 0 && self.quoted_key_ahead(ch) {
+                        self.start_block_mapping()?;
+                    }
+                    let token = self.scan_quoted_scalar_zero_copy()?;
+                    self.queue_token(token);
+                    self.check_after_closing_quote()?;
+                }
+                '&' | '*' => {
+                    self.advance();
+                    let token_type = if ch == '&' {
+                        TokenType::Anchor(self.scan_anchor_name("anchor")?.to_string())
+                    } else {
+                        TokenType::Alias(self.scan_anchor_name("alias")?.to_string())
+                    };
+                    self.queue(token_type, start, self.position);
+                }
+                '!' => {
+                    let token = self.scan_tag_zero_copy()?;
+                    self.queue_token(token);
+                }
+                '|' | '>' => {
+                    // Content is indented past the parent node; a root scalar has
+                    // no parent and may start in the first column
+                    let parent_indent = (!self.blocks.is_empty()).then_some(self.current_indent);
+                    let token = self.scan_block_scalar_zero_copy(parent_indent)?;
+                    self.queue_token(token);
+                }
+                _ if self.is_plain_scalar_start() => {
+                    if self.flow_level == 0 && self.mapping_key_ahead() {
+                        self.start_block_mapping()?;
+                    }
+                    let token = self.scan_plain_scalar_token()?;
+                    self.queue(token.token_type, token.start_position, token.end_position);
+                }
+                _ => return Err(Error::invalid_character(start, ch, "YAML document")),
+            }
+        }
+        Ok(())
+    }
+
+    /// Queue a `BlockMappingStart` before a key unless the key continues the
+    /// mapping open at its indentation
+    fn start_block_mapping(&mut self) -> Result<()> {
+        let starts = self.blocks.last().is_none_or(|&(indent, kind)| {
+            self.current_indent > indent
+                || self.current_indent == indent && kind == BlockKind::Sequence
+        });
+        if starts {
+            self.blocks.push((self.current_indent, BlockKind::Mapping));
+            // An anchor or tag on the key's line, as in `&a key: value`,
+            // belongs to the key rather than the mapping
+            let line = self.position.line;
+            let properties = self
+                .tokens
+                .iter()
+                .rev()
+                .take_while(|token| {
+                    matches!(token.token_type, TokenType::Anchor(_) | TokenType::Tag(_))
+                        && token.start_position.line == line
+                })
+                .count();
+            let index = self.tokens.len() - properties;
+            let position = self
+                .tokens
+                .get(index)
+                .map_or(self.position, |token| token.start_position);
+            self.tokens
+                .insert(index, Token::simple(TokenType::BlockMappingStart, position));
+            self.tokens_scanned += 1;
+        } else if self.value_line == Some(self.position.line) {
+            return Err(Error::scan(
+                self.position,
+                "mapping values are not allowed in this context",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Queue a `BlockSequenceStart` before an entry unless the entry
+    /// continues the sequence open at its indentation
+    fn start_block_sequence(&mut self) -> Result<()> {
+        let Some(&(indent, kind)) = self.blocks.last() else {
+            self.blocks.push((self.current_indent, BlockKind::Sequence));
+            self.queue(TokenType::BlockSequenceStart, self.position, self.position);
+            return Ok(());
+        };
+        if self.current_indent < indent
+            || self.current_indent == indent && kind == BlockKind::Sequence
+        {
+            return Ok(());
+        }
+        // A sequence may share its mapping's indentation, as in `key:\n- a`,
+        // but not the line of the key
+        if self.current_indent == indent && self.value_line == Some(self.position.line) {
+            return Err(Error::scan(
+                self.position,
+                "block sequence entries are not allowed in this context",
+            ));
+        }
+        self.blocks.push((self.current_indent, BlockKind::Sequence));
+        self.queue(TokenType::BlockSequenceStart, self.position, self.position);
+        Ok(())
+    }
+
+    /// Check that only a `:`, a flow indicator, a comment or the end of the
+    /// line follows a closing quote on its line, as in `k: "a"b` it may not
+    fn check_after_closing_quote(&self) -> Result<()> {
+        let mut offset = 0;
+        while matches!(self.peek_char(offset), Some(' ' | '\t')) {
+            offset += 1;
+        }
+        let allowed = match self.peek_char(offset) {
+            None | Some('\n' | '\r' | ':' | ',' | '[' | ']' | '{' | '}') => true,
+            Some('#') => offset > 0,
+            Some(_) => false,
+        };
+        if allowed {
+            return Ok(());
+        }
+        Err(Error::scan(
+            self.position,
+            "Unexpected content after a quoted scalar",
+        ))
+    }
+
+    /// Close every open block collection, as at a document marker
+    fn close_blocks(&mut self, position: Position) {
+        while self.blocks.pop().is_some() {
+            self.queue(TokenType::BlockEnd, position, position);
+        }
+    }
+
+    /// Indent a collection that starts on the same line as a `-` entry, an
+    /// explicit `?` key or its `:` value, as in `- - a` or `? b: c`, to its
+    /// first character
+    fn indent_compact_collection(&mut self) {
+        let compact = match self.current_char() {
+            Some('-' | '?') => self.peek_char(1).is_none_or(char::is_whitespace),
+            // A key with properties, as in `- &a key: value`
+            Some('&' | '!') => self.mapping_key_ahead(),
+            Some(quote @ ('"' | '\'')) => self.quoted_key_ahead(quote),
+            Some(_) if self.is_plain_scalar_start() => self.mapping_key_ahead(),
+            _ => false,
+        };
+        if compact {
+            self.current_indent = self.position.column - 1;
+        }
+    }
+
+    /// Whether a plain scalar starts here
+    fn is_plain_scalar_start(&self) -> bool {
+        self.current_char().is_some_and(|ch| match ch {
+            // `-.inf` or `?x` are plain, as long as a safe character follows
+            '-' | '?' | ':' => self.peek_char(1).is_some_and(|next| {
+                !(next.is_whitespace() || self.flow_level > 0 && is_flow_indicator(next))
+            }),
+            ',' | '[' | ']' | '{' | '}' | '#' | '&' | '*' | '!' | '|' | '>' | '\'' | '"' | '%'
+            | '@' | '`' => false,
+            _ => !ch.is_whitespace(),
+        })
+    }
+
+    /// Whether a `:` ending a plain scalar follows on this line, making the
+    /// node here a mapping key
+    fn mapping_key_ahead(&self) -> bool {
+        let mut offset = 0;
+        while let Some(ch) = self.peek_char(offset) {
+            let prev = self.peek_char(offset - 1);
+            match ch {
+                '\n' | '\r' => break,
+                ':' | '#' if ends_plain_scalar(ch, prev, self.peek_char(offset + 1), false) => {
+                    return ch == ':';
+                }
+                _ => {}
+            }
+            offset += 1;
+        }
+        false
+    }
+
+    /// Whether the quoted scalar starting here is followed by `:` on the
+    /// same line, making it a mapping key
+    fn quoted_key_ahead(&self, quote: char) -> bool {
+        let mut offset = 1;
+        loop {
+            match self.peek_char(offset) {
+                None | Some('\n' | '\r') => return false,
+                Some('\\') if quote == '"' => offset += 2,
+                Some('\'') if quote == '\'' && self.peek_char(offset + 1) == Some('\'') => {
+                    offset += 2;
+                }
+                Some(ch) if ch == quote => break,
+                Some(_) => offset += 1,
+            }
+        }
+        offset += 1;
+        while matches!(self.peek_char(offset), Some(' ' | '\t')) {
+            offset += 1;
+        }
+        self.peek_char(offset) == Some(':')
+            && self.peek_char(offset + 1).is_none_or(char::is_whitespace)
+    }
+
+    /// Scan the name of an anchor or alias after its `&` or `*`, up to
+    /// whitespace or a flow indicator
+    fn scan_anchor_name(&mut self, kind: &str) -> Result<&'a str> {
+        let start = self.position;
+        while let Some(ch) = self.current_char() {
+            if ch.is_whitespace() || is_flow_indicator(ch) {
+                break;
+            }
+            if !is_anchor_char(ch) {
+                return Err(Error::scan(
+                    self.position,
+                    format!("Invalid character {ch:?} in {kind} name"),
+                ));
+            }
+            self.advance();
+        }
+        let name = self.slice_from(start)?;
+        if name.is_empty() {
+            return Err(Error::scan(
+                self.position,
+                format!("{kind} name cannot be empty"),
+            ));
+        }
+        Ok(name)
+    }
+
+    /// Scan a plain scalar token, which continues on the following lines
+    /// inside a flow collection or on more indented lines in block context,
+    /// with booleans and nulls normalized like the [`BasicScanner`] does
+    fn scan_plain_scalar_token(&mut self) -> Result<Token> {
+        let first = self.scan_plain_scalar_zero_copy()?;
+        let start_pos = first.start_position;
+        let mut value = match first.token_type {
+            ZeroTokenType::Scalar(text, _) => text.into_owned(),
+            _ => String::new(),
+        };
+        while matches!(self.current_char(), Some('\n' | '\r'))
+            && (self.flow_level > 0 || self.plain_scalar_continues())
+        {
+            value.truncate(value.trim_end_matches([' ', '\t']).len());
+            match self.skip_line_folding() {
+                1 => value.push(' '),
+                breaks => value.push_str(&"\n".repeat(breaks - 1)),
+            }
+            if let ZeroTokenType::Scalar(text, _) = self.scan_plain_scalar_zero_copy()?.token_type {
+                value.push_str(text.as_str());
+            }
+            self.check_scalar_len(start_pos, value.len())?;
+        }
+        value.truncate(value.trim_end().len());
+
+        Ok(Token::new(
+            TokenType::Scalar(BasicScanner::normalize_scalar(value), QuoteStyle::Plain),
+            start_pos,
+            self.position,
+        ))
+    }
+
+    /// Whether a block plain scalar continues on the next non-empty line
+    ///
+    /// The line must be indented more than the enclosing block collection
+    /// and must not start a comment, a document marker, an indicator such as
+    /// `- ` or a mapping key, which all end the scalar instead.
+    fn plain_scalar_continues(&self) -> bool {
+        let min_indent = self.blocks.last().map_or(0, |&(indent, _)| indent + 1);
+        let mut offset = 0;
+        let mut indent = 0;
+        let first = loop {
+            match self.peek_char(offset) {
+                Some('\n' | '\r') => indent = 0,
+                Some(' ') => indent += 1,
+                Some('\t') => {}
+                Some(ch) => break ch,
+                None => return false,
+            }
+            offset += 1;
+        };
+        let next = self.peek_char(offset + 1);
+        let is_indicator = matches!(first, '-' | '?' | ':') && next.is_none_or(char::is_whitespace);
+        let is_document_marker = indent == 0
+            && matches!(first, '-' | '.')
+            && next == Some(first)
+            && self.peek_char(offset + 2) == Some(first);
+        if indent < min_indent || first == '#' || is_indicator || is_document_marker {
+            return false;
+        }
+        // A `key: value` line starts a new entry rather than continuing the scalar
+        let mut prev = first;
+        while let Some(ch) = self.peek_char(offset + 1) {
+            if matches!(ch, '\n' | '\r') || ch == '#' && prev.is_whitespace() {
+                break;
+            }
+            if prev == ':' && ch.is_whitespace() {
+                return false;
+            }
+            prev = ch;
+            offset += 1;
+        }
+        prev != ':'
+    }
+
+    /// Queue an owned copy of `token`
+    fn queue_token(&mut self, token: ZeroToken<'a>) {
+        let token = token.into_owned();
+        self.queue(token.token_type, token.start_position, token.end_position);
+    }
+
+    /// Queue a token, tracking what the scanning of later tokens depends on
+    fn queue(&mut self, token_type: TokenType, start: Position, end: Position) {
+        match token_type {
+            TokenType::Value => self.value_line = Some(start.line),
+            TokenType::Anchor(_) | TokenType::Tag(_) => {}
+            _ => self.value_line = None,
+        }
+        self.adjacent_value = matches!(
+            token_type,
+            TokenType::Scalar(_, QuoteStyle::Single | QuoteStyle::Double)
+                | TokenType::FlowSequenceEnd
+                | TokenType::FlowMappingEnd
+        );
+        self.tokens.push_back(Token::new(token_type, start, end));
+        self.tokens_scanned += 1;
+    }
+}
+
+/// The tokens of the whole stream, scanned a line at a time
+impl<'a> Scanner for ZeroScanner<'a> {
+    fn check_token(&self) -> bool {
+        !self.tokens.is_empty() || !self.stream_ended
+    }
+
+    fn peek_token(&self) -> Result<Option<&Token>> {
+        Ok(self.tokens.front())
+    }
+
+    fn get_token(&mut self) -> Result<Option<Token>> {
+        // The token after the one returned is scanned too, so the parser can
+        // peek at it
+        while self.tokens.len() < 2 && !self.stream_ended {
+            if let Err(error) = self.fetch_line() {
+                self.tokens.clear();
+                self.stream_ended = true;
+                return Err(error);
+            }
+        }
+        Ok(self.tokens.pop_front())
+    }
+
+    fn reset(&mut self) {
+        ZeroScanner::reset(self);
+    }
+
+    fn position(&self) -> Position {
+        self.position
+    }
+
+    fn input(&self) -> &str {
+        self.input
+    }
 }
 
 /// Statistics about scanner performance
@@ -515,6 +1668,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_zero_copy_plain_scalar_context() {
+        let scan = |input: &str, flow_level: usize| {
+            let mut scanner = ZeroScanner::new(input);
+            scanner.set_flow_level(flow_level);
+            match scanner.scan_plain_scalar_zero_copy().unwrap().token_type {
+                ZeroTokenType::Scalar(value, _) => value.into_owned(),
+                other => panic!("expected a scalar, got {other:?}"),
+            }
+        };
+        assert_eq!(scan("a,b[c] # note", 0), "a,b[c]");
+        assert_eq!(scan("a,b]", 1), "a");
+        assert_eq!(scan("http://x.com:80/p#f, b]", 1), "http://x.com:80/p#f");
+        assert_eq!(scan("k:v}", 1), "k:v");
+        assert_eq!(scan("k:}", 1), "k");
+    }
+
     #[test]
     fn test_zero_copy_identifier_scanning() {
         let input = "my_anchor_123 ";
@@ -540,4 +1710,271 @@ mod tests {
             panic!("Expected scalar token");
         }
     }
+
+    /// The first token BasicScanner produces for `input` after `skip` tokens
+    /// that are not part of the stream or block structure
+    fn basic_token(input: &str, skip: usize) -> crate::scanner::TokenType {
+        use crate::scanner::{BasicScanner, Scanner, TokenType};
+
+        let mut scanner = BasicScanner::new(input.to_string());
+        let mut tokens = Vec::new();
+        while let Some(token) = scanner.get_token().unwrap() {
+            if !matches!(
+                token.token_type,
+                TokenType::StreamStart
+                    | TokenType::StreamEnd
+                    | TokenType::BlockMappingStart
+                    | TokenType::BlockSequenceStart
+                    | TokenType::BlockEnd
+                    | TokenType::BlockEntry
+                    | TokenType::Value
+                    | TokenType::DocumentStart
+            ) {
+                tokens.push(token.token_type);
+            }
+        }
+        tokens.swap_remove(skip)
+    }
+
+    /// Scan the construct starting `offset` characters into `input` with
+    /// `scan`, checking it matches BasicScanner and whether it borrows
+    fn assert_matches_basic<'a>(
+        input: &'a str,
+        offset: usize,
+        skip: usize,
+        borrowed: bool,
+        scan: impl FnOnce(&mut ZeroScanner<'a>) -> Result<ZeroToken<'a>>,
+    ) {
+        let mut scanner = ZeroScanner::new(input);
+        for _ in 0..offset {
+            scanner.advance();
+        }
+        let token = scan(&mut scanner).unwrap();
+        let is_borrowed = match &token.token_type {
+            ZeroTokenType::Scalar(value, _)
+            | ZeroTokenType::BlockScalarLiteral(value)
+            | ZeroTokenType::BlockScalarFolded(value)
+            | ZeroTokenType::Tag(value) => value.is_borrowed(),
+            ZeroTokenType::TagDirective(handle, prefix) => {
+                handle.is_borrowed() && prefix.is_borrowed()
+            }
+            _ => true,
+        };
+        assert_eq!(
+            token.into_owned().token_type,
+            basic_token(input, skip),
+            "input: {input:?}"
+        );
+        assert_eq!(is_borrowed, borrowed, "input: {input:?}");
+    }
+
+    #[test]
+    fn test_zero_copy_quoted_scalars() {
+        assert_matches_basic(
+            "\"simple\"",
+            0,
+            0,
+            true,
+            ZeroScanner::scan_quoted_scalar_zero_copy,
+        );
+        assert_matches_basic(
+            "'single'",
+            0,
+            0,
+            true,
+            ZeroScanner::scan_quoted_scalar_zero_copy,
+        );
+        assert_matches_basic(
+            "'back\\slash'",
+            0,
+            0,
+            true,
+            ZeroScanner::scan_quoted_scalar_zero_copy,
+        );
+        assert_matches_basic(
+            "\"tab\\there\"",
+            0,
+            0,
+            false,
+            ZeroScanner::scan_quoted_scalar_zero_copy,
+        );
+        assert_matches_basic(
+            "\"\\x41\\u00e9\\U0001F600 \\\"q\\\"\"",
+            0,
+            0,
+            false,
+            ZeroScanner::scan_quoted_scalar_zero_copy,
+        );
+        assert_matches_basic(
+            "key: \"value\"",
+            5,
+            1,
+            true,
+            ZeroScanner::scan_quoted_scalar_zero_copy,
+        );
+
+        let scan = |input: &str| {
+            let token = ZeroScanner::new(input)
+                .scan_quoted_scalar_zero_copy()
+                .unwrap();
+            match token.token_type {
+                ZeroTokenType::Scalar(value, _) => value.into_owned(),
+                other => panic!("expected a scalar, got {other:?}"),
+            }
+        };
+        assert_eq!(scan("'it''s'"), "it's");
+        assert_eq!(scan("\"multi  \n  line\""), "multi line");
+        assert_eq!(scan("'x\n\n  y'"), "x\ny");
+        assert_eq!(scan("\"a\\\n  b\""), "ab");
+        assert_eq!(scan("\"\\N\\_\\L\\P\""), "\u{85}\u{A0}\u{2028}\u{2029}");
+        assert!(ZeroScanner::new("\"unclosed")
+            .scan_quoted_scalar_zero_copy()
+            .is_err());
+        assert!(ZeroScanner::new("\"\\UFFFFFFFF\"")
+            .scan_quoted_scalar_zero_copy()
+            .is_err());
+    }
+
+    #[test]
+    fn test_zero_copy_strict_escapes() {
+        use crate::scanner::{BasicScanner, Scanner};
+
+        let scan = |input: &str| {
+            let mut scanner = ZeroScanner::new(input);
+            scanner.set_strict_escapes(true);
+            scanner.scan_quoted_scalar_zero_copy().map(|_| ())
+        };
+        assert!(scan("\"\\x41\\N\\_\"").is_ok());
+        assert!(scan("\"a\\\n  b\"").is_ok());
+        for input in ["\"\\q\"", "\"\\x4\"", "\"\\u12G4\"", "\"\\'\""] {
+            assert_eq!(
+                scan(input).unwrap_err().to_string(),
+                {
+                    let mut scanner = BasicScanner::new(input.to_string());
+                    scanner.set_strict_escapes(true);
+                    scanner.get_token().unwrap();
+                    scanner.get_token().unwrap_err().to_string()
+                },
+                "{input}"
+            );
+        }
+        assert!(ZeroScanner::new("\"\\q\"")
+            .scan_quoted_scalar_zero_copy()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_zero_copy_tags_and_directives() {
+        for input in [
+            "!!str a",
+            "!local a",
+            "!e!x a",
+            "!<tag:example.com,2000:x> a",
+        ] {
+            assert_matches_basic(input, 0, 0, true, ZeroScanner::scan_tag_zero_copy);
+        }
+        assert!(ZeroScanner::new("!<tag a>").scan_tag_zero_copy().is_err());
+        assert_matches_basic(
+            "%YAML 1.2\n---\na",
+            0,
+            0,
+            true,
+            ZeroScanner::scan_directive_zero_copy,
+        );
+        assert_matches_basic(
+            "%TAG !e! tag:e.com,2000:\n---\na",
+            0,
+            0,
+            true,
+            ZeroScanner::scan_directive_zero_copy,
+        );
+        assert_matches_basic(
+            "%TAG !! tag:e.com,2000:\n---\na",
+            0,
+            0,
+            true,
+            ZeroScanner::scan_directive_zero_copy,
+        );
+        assert!(ZeroScanner::new("%FOO bar")
+            .scan_directive_zero_copy()
+            .is_err());
+        assert!(ZeroScanner::new("%YAML x.2")
+            .scan_directive_zero_copy()
+            .is_err());
+    }
+
+    #[test]
+    fn test_zero_copy_max_scalar_len() {
+        let scan = |input: &str| {
+            let mut scanner = ZeroScanner::new(input);
+            scanner.set_max_scalar_len(4);
+            match scanner.current_char() {
+                Some('|' | '>') => scanner.scan_block_scalar_zero_copy(None),
+                Some('\'' | '"') => scanner.scan_quoted_scalar_zero_copy(),
+                _ => scanner.scan_plain_scalar_zero_copy(),
+            }
+            .map(|_| ())
+        };
+        assert!(scan("abcd").is_ok());
+        assert!(scan("'ab''c'").is_ok());
+        assert!(scan("|\n  abc\n").is_ok());
+
+        for input in [
+            "abcde",
+            "\"abcde\"",
+            "\"abcdefgh",
+            "|\n  abc\n  d\n",
+            "|+\n\n\n\n\n\n",
+        ] {
+            let error = scan(input).unwrap_err().to_string();
+            assert!(
+                error.contains("Maximum scalar length 4 exceeded"),
+                "{input:?}: {error}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_zero_copy_block_scalars() {
+        assert_matches_basic("|\nline\n", 0, 0, true, |s| {
+            s.scan_block_scalar_zero_copy(None)
+        });
+        assert_matches_basic("|\n  one\n  two\n", 0, 0, false, |s| {
+            s.scan_block_scalar_zero_copy(None)
+        });
+        assert_matches_basic("|+\n  x\n\n", 0, 0, true, |s| {
+            s.scan_block_scalar_zero_copy(None)
+        });
+        assert_matches_basic(">\n  a\n  b\n\n  c\n   d\n", 0, 0, false, |s| {
+            s.scan_block_scalar_zero_copy(None)
+        });
+        assert_matches_basic("a: |\n  x\nb: 1\n", 3, 1, true, |s| {
+            s.scan_block_scalar_zero_copy(Some(0))
+        });
+        assert_matches_basic("a: >- # note\n  folded\n  text\nb: 1\n", 3, 1, false, |s| {
+            s.scan_block_scalar_zero_copy(Some(0))
+        });
+        assert_matches_basic("a: |2\n    indented\nb: 1\n", 3, 1, true, |s| {
+            s.scan_block_scalar_zero_copy(Some(0))
+        });
+        assert_matches_basic("a: |\n\n  after empty\n", 3, 1, false, |s| {
+            s.scan_block_scalar_zero_copy(Some(0))
+        });
+        assert_matches_basic("a: |\nb: 1\n", 3, 1, true, |s| {
+            s.scan_block_scalar_zero_copy(Some(0))
+        });
+
+        // The scanner stops at the line break before the next key
+        let mut scanner = ZeroScanner::new("a: |\n  x\nb: 1\n");
+        for _ in 0..3 {
+            scanner.advance();
+        }
+        scanner.scan_block_scalar_zero_copy(Some(0)).unwrap();
+        assert_eq!(scanner.current_char(), Some('\n'));
+        assert_eq!(scanner.position.line, 2);
+
+        assert!(ZeroScanner::new("|x\n  a\n")
+            .scan_block_scalar_zero_copy(None)
+            .is_err());
+    }
 }
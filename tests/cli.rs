@@ -0,0 +1,208 @@
+//! Tests for the `rust-yaml` command line tool, run as a process
+
+#![cfg(feature = "cli")]
+
+use rust_yaml::{yaml, Yaml};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Exit code, standard output and standard error of a run of the tool
+struct Output {
+    code: i32,
+    stdout: String,
+    stderr: String,
+}
+
+/// Run the tool with `args`, writing `stdin` to its standard input
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rust-yaml"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    Output {
+        code: output.status.code().unwrap(),
+        stdout: String::from_utf8(output.stdout).unwrap(),
+        stderr: String::from_utf8(output.stderr).unwrap(),
+    }
+}
+
+/// A temporary file holding `content`
+fn file(content: &str) -> tempfile::NamedTempFile {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    file.write_all(content.as_bytes()).unwrap();
+    file
+}
+
+fn path(file: &tempfile::NamedTempFile) -> &str {
+    file.path().to_str().unwrap()
+}
+
+#[test]
+fn test_usage_errors() {
+    let output = run(&[], "");
+    assert_eq!(output.code, 2);
+    assert!(output.stderr.starts_with("Usage: rust-yaml"));
+
+    let output = run(&["frobnicate"], "");
+    assert_eq!(output.code, 2);
+    assert!(output.stderr.contains("unknown command 'frobnicate'"));
+
+    let output = run(&["--help"], "");
+    assert_eq!(output.code, 0);
+    assert!(output.stdout.starts_with("Usage: rust-yaml"));
+}
+
+#[test]
+fn test_validate() {
+    let valid = file("a: 1\n---\nb: 2\n");
+    let output = run(&["validate", path(&valid)], "");
+    assert_eq!(output.code, 0);
+    assert_eq!(output.stdout, format!("{}: ok\n", path(&valid)));
+
+    let invalid = file("a: [1, 2\n");
+    let output = run(&["validate", path(&invalid)], "");
+    assert_eq!(output.code, 1);
+    assert!(output.stdout.starts_with(path(&invalid)));
+
+    // Standard input is read for `-`
+    assert_eq!(run(&["validate", "-"], "a: 1\n").code, 0);
+
+    let schema = file("type: object\nproperties:\n  a: {type: integer}\n");
+    let output = run(&["validate", path(&valid), "--schema", path(&schema)], "");
+    assert_eq!(output.code, 0);
+    let output = run(&["validate", "-", "--schema", path(&schema)], "a: x\n");
+    assert_eq!(output.code, 1);
+
+    let output = run(&["validate"], "");
+    assert_eq!(output.code, 2);
+    assert!(output.stderr.contains("validate expects exactly one file"));
+    assert_eq!(run(&["validate", "/nonexistent/file.yaml"], "").code, 2);
+}
+
+#[test]
+fn test_convert() {
+    let output = run(&["convert"], "a: 1\nb: [x, true]\n---\nc: ~\n");
+    assert_eq!(output.code, 0);
+    assert_eq!(
+        output.stdout,
+        "{\n  \"a\": 1,\n  \"b\": [\n    \"x\",\n    true\n  ]\n}\n{\n  \"c\": null\n}\n"
+    );
+
+    let output = run(&["convert", "--to", "yaml"], "a:   1\n---\nb: {c: 2}\n");
+    assert_eq!(output.code, 0);
+    assert_eq!(output.stdout, "a: 1\n---\nb: \n  c: 2\n");
+
+    let output = run(&["convert", "--to", "toml"], "a: 1\n");
+    assert_eq!(output.code, 2);
+    assert!(output.stderr.contains("unsupported output format 'toml'"));
+
+    let output = run(&["convert", "--to"], "a: 1\n");
+    assert_eq!(output.code, 2);
+    assert!(output.stderr.contains("option '--to' requires a value"));
+
+    assert_eq!(run(&["convert"], "a: [1\n").code, 2);
+}
+
+#[test]
+fn test_format_keeps_comments_of_every_document() {
+    let input = "# first\na:   1 # one\n---\n# second\nb:    2 # two\n";
+    let output = run(&["format"], input);
+    assert_eq!(output.code, 0);
+    assert_eq!(
+        output.stdout,
+        "# first\na: 1 # one\n---\n# second\nb: 2 # two\n"
+    );
+
+    // Formatting is stable
+    assert_eq!(run(&["format"], &output.stdout).stdout, output.stdout);
+}
+
+#[test]
+fn test_format_check() {
+    let output = run(&["format", "--check"], "a: 1 # one\n---\nb: 2\n");
+    assert_eq!(output.code, 0);
+    assert_eq!(output.stdout, "");
+
+    let output = run(&["format", "--check"], "a:    1\n");
+    assert_eq!(output.code, 1);
+    assert_eq!(output.stdout, "-: would be reformatted\n");
+}
+
+#[test]
+fn test_format_in_place() {
+    let document = file("# keep\na:    1\n---\nb:   [1,   2]   # list\n");
+    let output = run(&["format", "--in-place", path(&document)], "");
+    assert_eq!(output.code, 0);
+    assert_eq!(output.stdout, "");
+    let formatted = std::fs::read_to_string(document.path()).unwrap();
+    assert!(formatted.starts_with("# keep\na: 1\n---\n"), "{formatted}");
+    assert!(formatted.contains("# list"), "{formatted}");
+    assert_eq!(run(&["format", "--check", path(&document)], "").code, 0);
+
+    // Standard input has no file to write back to
+    let output = run(&["format", "--in-place"], "a: 1\n");
+    assert_eq!(output.code, 2);
+    assert!(output.stderr.contains("--in-place requires a file"));
+    let output = run(&["format", "--in-place", "-"], "a: 1\n");
+    assert_eq!(output.code, 2);
+}
+
+#[test]
+fn test_lint() {
+    let output = run(&["lint"], "a: 1\nb: 2\n");
+    assert_eq!(output.code, 0);
+    assert_eq!(output.stdout, "");
+
+    let output = run(
+        &["lint", "--max-line-length", "10"],
+        "a: 1 \nb: 12345678901\n\tc: 3",
+    );
+    assert_eq!(output.code, 1);
+    let lines: Vec<_> = output.stdout.lines().collect();
+    assert!(lines.contains(&"-:1:5: trailing whitespace"), "{lines:?}");
+    assert!(
+        lines.contains(&"-:2:11: line too long (14 > 10 characters)"),
+        "{lines:?}"
+    );
+    assert!(
+        lines.contains(&"-:3:1: tab character in indentation"),
+        "{lines:?}"
+    );
+    assert!(
+        lines.contains(&"-:3:1: missing newline at end of file"),
+        "{lines:?}"
+    );
+
+    let output = run(&["lint", "--max-line-length", "wide"], "a: 1\n");
+    assert_eq!(output.code, 2);
+    assert!(output.stderr.contains("invalid line length 'wide'"));
+}
+
+#[test]
+fn test_diff() {
+    let old = file("a: 1\nb: [x]\n");
+    let same = file("b: [x]\na: 1\n");
+    let output = run(&["diff", path(&old), path(&same)], "");
+    assert_eq!(output.code, 0);
+    assert_eq!(output.stdout, "");
+
+    let new = file("a: 2\nb: [x]\n");
+    let output = run(&["diff", path(&old), path(&new)], "");
+    assert_eq!(output.code, 1);
+    let patch = Yaml::new().load_str(&output.stdout).unwrap();
+    assert_eq!(patch, yaml!([{"op": "replace", "path": "/a", "value": 2}]));
+
+    let output = run(&["diff", path(&old)], "");
+    assert_eq!(output.code, 2);
+    assert!(output.stderr.contains("diff expects two files"));
+}
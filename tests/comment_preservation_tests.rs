@@ -1,6 +1,9 @@
 //! Comprehensive integration tests for comment preservation
 
-use rust_yaml::{CommentedValue, Comments, LoaderType, Style, Value, Yaml, YamlConfig};
+use rust_yaml::{
+    BoolStyle, CommentedValue, Comments, LoaderType, NullStyle, Path, Style, Value, Yaml,
+    YamlConfig,
+};
 
 #[test]
 fn test_comment_preservation_basic() {
@@ -370,7 +373,7 @@ number: 42
 
     // Verify structure is still correct
     let value = result.unwrap();
-    if let Value::Mapping(map) = value {
+    if let Value::Mapping(map) = &value {
         assert_eq!(
             map.get(&Value::String("key".to_string())),
             Some(&Value::String("value".to_string()))
@@ -515,6 +518,8 @@ fn test_commented_value_construction() {
         value,
         comments,
         style: Style::default(),
+        keys: Default::default(),
+        items: Vec::new(),
     };
 
     assert!(
@@ -580,3 +585,288 @@ special_chars: "line1\nline2" # Escaped characters
         "Round-trip with quotes and comments should be valid"
     );
 }
+
+#[test]
+fn test_key_comments_survive_value_edits_and_renames() {
+    let config = YamlConfig {
+        preserve_comments: true,
+        loader_type: LoaderType::RoundTrip,
+        ..Default::default()
+    };
+    let yaml = Yaml::with_config(config);
+
+    let input = "server:\n  # Listen port\n  port: 8080  # default\n  host: localhost\n";
+    let mut document = yaml.load_str_with_comments(input).unwrap();
+
+    // Edit a nested value through the plain value tree
+    if let Some(Value::Mapping(server)) = document.value.get_mut(&Value::from("server")) {
+        server.insert(Value::from("port"), Value::Int(9090));
+    }
+    // Rename a top-level key
+    assert!(document.rename_key(&Value::from("server"), Value::from("listener")));
+
+    let output = yaml.dump_str_with_comments(&document).unwrap();
+    assert!(
+        output.contains("listener:\n  # Listen port\n  port: 9090 # default\n"),
+        "Key comments should stay with their key, got:\n{output}"
+    );
+    assert_eq!(output.matches("Listen port").count(), 1);
+    assert!(!output.contains("server"));
+}
+
+#[test]
+fn test_remove_and_insert_keys_with_comments() {
+    let config = YamlConfig {
+        preserve_comments: true,
+        loader_type: LoaderType::RoundTrip,
+        ..Default::default()
+    };
+    let yaml = Yaml::with_config(config);
+
+    let input = "# About a\na: 1  # one\n# About b\nb:\n  # Inside b\n  x: 2\nc: 3\n";
+    let mut document = yaml.load_str_with_comments(input).unwrap();
+
+    let (value, entry) = document
+        .remove_key_with_comments(&Value::from("b"))
+        .unwrap();
+    assert_eq!(value["x"], Value::Int(2));
+    assert_eq!(entry.comments.leading, vec!["About b"]);
+    assert!(document
+        .remove_key_with_comments(&Value::from("b"))
+        .is_none());
+
+    let output = yaml.dump_str_with_comments(&document).unwrap();
+    assert_eq!(output.trim_end(), "# About a\na: 1 # one\nc: 3");
+
+    let previous = document.insert_with_comment(
+        Value::from("d"),
+        Value::Int(4),
+        vec!["About d".to_string()],
+        Some("four".to_string()),
+    );
+    assert!(previous.is_none());
+    document.remove_key_with_comments(&Value::from("a"));
+
+    let output = yaml.dump_str_with_comments(&document).unwrap();
+    assert_eq!(output.trim_end(), "c: 3\n# About d\nd: 4 # four");
+    assert!(!output.contains("one"));
+}
+
+#[test]
+fn test_edit_comments_by_path() {
+    let config = YamlConfig {
+        preserve_comments: true,
+        loader_type: LoaderType::RoundTrip,
+        ..Default::default()
+    };
+    let yaml = Yaml::with_config(config);
+
+    let input = "name: web  # the name\nserver:\n  # Listen port\n  port: 80\n";
+    let mut document = yaml.load_str_with_comments(input).unwrap();
+    let path = |text: &str| text.parse::<Path>().unwrap();
+
+    let port = document.comments_at(&path("server.port")).unwrap();
+    assert_eq!(port.leading, vec!["Listen port"]);
+    assert_eq!(
+        document
+            .comments_at(&path("name"))
+            .unwrap()
+            .trailing
+            .as_deref(),
+        Some("the name")
+    );
+    assert!(document.comments_at(&path("missing")).is_none());
+
+    assert!(document.set_leading_comment(
+        &path(""),
+        "Copyright 2026 Example\nSPDX-License-Identifier: MIT"
+    ));
+    assert!(document.set_leading_comment(&path("server.port"), "generated, do not edit"));
+    let removed = document.remove_comments(&path("name")).unwrap();
+    assert_eq!(removed.trailing.as_deref(), Some("the name"));
+    assert!(!document.set_leading_comment(&path("server.host"), "missing"));
+    assert!(document.remove_comments(&path("server.host")).is_none());
+
+    // A key added through the value tree gets an entry on first use
+    if let Some(Value::Mapping(server)) = document.value.get_mut(&Value::from("server")) {
+        server.insert(Value::from("host"), Value::from("localhost"));
+    }
+    assert!(document.comments_at(&path("server.host")).is_none());
+    assert!(document.set_leading_comment(&path("server.host"), "added"));
+
+    let output = yaml.dump_str_with_comments(&document).unwrap();
+    assert_eq!(
+        output,
+        "# Copyright 2026 Example\n# SPDX-License-Identifier: MIT\nname: web\nserver:\n  # generated, do not edit\n  port: 80\n  # added\n  host: localhost\n"
+    );
+}
+
+#[test]
+fn test_null_styles_round_trip() {
+    let config = YamlConfig {
+        preserve_comments: true,
+        loader_type: LoaderType::RoundTrip,
+        ..Default::default()
+    };
+    let yaml = Yaml::with_config(config);
+
+    let input = "tilde: ~\nword: null\nempty:\nlist:\n  - ~\n  - null\n";
+    let mut document = yaml.load_str_with_comments(input).unwrap();
+    if let Value::Mapping(map) = &mut document.value {
+        map.insert(Value::from("added"), Value::Null);
+    }
+
+    let output = yaml.dump_str_with_comments(&document).unwrap();
+    assert!(
+        output.starts_with("tilde: ~\nword: null\nempty:\nlist:\n  - ~\n  - null\n"),
+        "Null styles should be preserved, got:\n{output}"
+    );
+    // Nulls without a recorded style use the configured one
+    assert!(output.contains("added: null"));
+}
+
+#[test]
+fn test_null_style_config() {
+    let yaml = Yaml::with_config(YamlConfig {
+        null_style: NullStyle::Tilde,
+        ..Default::default()
+    });
+    let value = yaml.load_str("a:\nb: [null]\n").unwrap();
+    assert_eq!(yaml.dump_str(&value).unwrap(), "a: ~\nb: \n  - ~\n");
+}
+
+#[test]
+fn test_bool_literals_round_trip() {
+    let config = YamlConfig {
+        preserve_comments: true,
+        loader_type: LoaderType::RoundTrip,
+        bool_style: BoolStyle::OnOff,
+        ..Default::default()
+    };
+    let yaml = Yaml::with_config(config);
+
+    let input = "enabled: yes\nverbose: NO\nflags:\n  - true\n";
+    let mut document = yaml.load_str_with_comments(input).unwrap();
+    if let Value::Mapping(map) = &mut document.value {
+        // A changed value no longer matches its literal
+        map.insert(Value::from("verbose"), Value::Bool(true));
+        map.insert(Value::from("added"), Value::Bool(false));
+    }
+
+    let output = yaml.dump_str_with_comments(&document).unwrap();
+    assert!(
+        output.starts_with("enabled: yes\nverbose: on\nflags:\n  - true\nadded: off"),
+        "Boolean literals should be preserved, got:\n{output}"
+    );
+}
+
+#[test]
+fn test_integer_notation_round_trip() {
+    let config = YamlConfig {
+        preserve_comments: true,
+        loader_type: LoaderType::RoundTrip,
+        ..Default::default()
+    };
+    let yaml = Yaml::with_config(config);
+
+    let input = "mode: 0o755  # rwxr-xr-x\nmask: 0xFF\nretries: 0x03\n";
+    let mut document = yaml.load_str_with_comments(input).unwrap();
+    if let Value::Mapping(map) = &mut document.value {
+        map.insert(Value::from("retries"), Value::Int(5));
+    }
+
+    let output = yaml.dump_str_with_comments(&document).unwrap();
+    assert!(
+        output.starts_with("mode: 0o755 # rwxr-xr-x\nmask: 0xFF\nretries: 5"),
+        "Integer notation should be kept for unchanged values, got:\n{output}"
+    );
+}
+
+#[test]
+fn test_block_scalar_indicators_round_trip() {
+    let config = YamlConfig {
+        preserve_comments: true,
+        loader_type: LoaderType::RoundTrip,
+        ..Default::default()
+    };
+    let yaml = Yaml::with_config(config);
+
+    let input = "script: |-\n  set -e\n  make\nbanner: |2\n    indented\n  text\nnotes: |+\n  kept\n\nsummary: >\n  folded\n  text\n";
+    let mut document = yaml.load_str_with_comments(input).unwrap();
+    let output = yaml.dump_str_with_comments(&document).unwrap();
+    assert_eq!(
+        output,
+        "script: |-\n  set -e\n  make\nbanner: |2\n    indented\n  text\nnotes: |+\n  kept\n\nsummary: >\n  folded text\n"
+    );
+    assert_eq!(
+        yaml.load_str(&output).unwrap(),
+        yaml.load_str(input).unwrap()
+    );
+
+    // Indicators follow the new content when a value changes
+    if let Value::Mapping(map) = &mut document.value {
+        map.insert(Value::from("script"), Value::from("make\n"));
+    }
+    let output = yaml.dump_str_with_comments(&document).unwrap();
+    assert!(
+        output.starts_with("script: |\n  make\nbanner:"),
+        "Chomping should follow the new value, got:\n{output}"
+    );
+}
+
+#[test]
+fn test_nested_comments_round_trip() {
+    let config = YamlConfig {
+        preserve_comments: true,
+        loader_type: LoaderType::RoundTrip,
+        ..Default::default()
+    };
+    let yaml = Yaml::with_config(config);
+
+    let input = "list:\n  # first\n  - a\n  - b # bee\n  # third\n  - c\nitems:\n  # an item\n  - name: x # the x\n    # about y\n    y: 1\n  - name: z\nscripts:\n  - | # lit\n    text\n  - [1, 2] # pair\na:\n  b:\n    # deep\n    c: 1 # cee\n";
+    let document = yaml.load_str_with_comments(input).unwrap();
+    let output = yaml.dump_str_with_comments(&document).unwrap();
+    assert_eq!(
+        output,
+        "list:\n  # first\n  - a\n  - b # bee\n  # third\n  - c\nitems:\n  # an item\n  - \n    name: x # the x\n    # about y\n    y: 1\n  - \n    name: z\nscripts:\n  - | # lit\n    text\n  - \n    - 1 # pair\n    - 2\na:\n  b:\n    # deep\n    c: 1 # cee\n"
+    );
+    assert_eq!(
+        yaml.load_str(&output).unwrap(),
+        yaml.load_str(input).unwrap()
+    );
+
+    // A comment after a flow collection is written once, even when the
+    // collection is written in block style
+    let output = yaml
+        .dump_str_with_comments(&yaml.load_str_with_comments("flags: {a: 1} # f\n").unwrap())
+        .unwrap();
+    assert_eq!(output.matches("# f").count(), 1, "got:\n{output}");
+}
+
+#[test]
+fn test_align_trailing_comments() {
+    let input = "host: db        # database host\nport: 5432      # port\nlist:\n  - a   # first\n  - bb  # second\nscript: |  # run\n  make\n";
+    let dump = |align_trailing_comments| {
+        let yaml = Yaml::with_config(YamlConfig {
+            preserve_comments: true,
+            loader_type: LoaderType::RoundTrip,
+            align_trailing_comments,
+            ..Default::default()
+        });
+        let document = yaml.load_str_with_comments(input).unwrap();
+        yaml.dump_str_with_comments(&document).unwrap()
+    };
+
+    assert_eq!(
+        dump(None),
+        "host: db # database host\nport: 5432 # port\nlist:\n  - a # first\n  - bb # second\nscript: | # run\n  make\n"
+    );
+    // Auto mode keeps the columns of the source
+    assert_eq!(dump(Some(0)), input);
+    assert_eq!(
+        dump(Some(14)),
+        "host: db     # database host\nport: 5432   # port\nlist:\n  - a        # first\n  - bb       # second\nscript: |    # run\n  make\n"
+    );
+    // Values past the column are followed by a single space
+    assert_eq!(dump(Some(3)), dump(None));
+}
@@ -366,7 +366,7 @@ fn test_duplicate_complex_keys_error() {
     // or fail with a duplicate key error, depending on implementation
     let result = yaml.load_str(yaml_content);
 
-    if let Ok(Value::Mapping(map)) = result {
+    if let Ok(Value::Mapping(map)) = &result {
         // If parsing succeeds, should have only one entry (last one wins)
         assert_eq!(map.len(), 1);
 
@@ -392,3 +392,106 @@ fn test_duplicate_complex_keys_error() {
         );
     }
 }
+
+fn mapping(entries: Vec<(Value, Value)>) -> Value {
+    Value::Mapping(entries.into_iter().collect())
+}
+
+fn string(value: &str) -> Value {
+    Value::String(value.to_string())
+}
+
+#[test]
+fn test_block_collection_complex_keys() {
+    let yaml = Yaml::new();
+
+    let result = yaml.load_str("? - a\n  - b\n: x\n").unwrap();
+    assert_eq!(
+        result,
+        mapping(vec![(
+            Value::Sequence(vec![string("a"), string("b")]),
+            string("x")
+        )])
+    );
+
+    let result = yaml.load_str("? a: 1\n: b: 2\n  c: 3\n").unwrap();
+    assert_eq!(
+        result,
+        mapping(vec![(
+            mapping(vec![(string("a"), Value::Int(1))]),
+            mapping(vec![
+                (string("b"), Value::Int(2)),
+                (string("c"), Value::Int(3))
+            ])
+        )])
+    );
+
+    let result = yaml.load_str("? a\n: - 1\n  - 2\n").unwrap();
+    assert_eq!(
+        result,
+        mapping(vec![(
+            string("a"),
+            Value::Sequence(vec![Value::Int(1), Value::Int(2)])
+        )])
+    );
+}
+
+#[test]
+fn test_complex_keys_in_nested_collections() {
+    let yaml = Yaml::new();
+
+    let result = yaml.load_str("x:\n  ? a\n  : 1\ny: 2\n").unwrap();
+    assert_eq!(
+        result,
+        mapping(vec![
+            (string("x"), mapping(vec![(string("a"), Value::Int(1))])),
+            (string("y"), Value::Int(2)),
+        ])
+    );
+
+    let result = yaml.load_str("- ? a\n  : b\n- c\n").unwrap();
+    assert_eq!(
+        result,
+        Value::Sequence(vec![mapping(vec![(string("a"), string("b"))]), string("c")])
+    );
+}
+
+#[test]
+fn test_complex_keys_with_empty_nodes() {
+    let yaml = Yaml::new();
+
+    let result = yaml.load_str("? a\nb: 2\n").unwrap();
+    assert_eq!(
+        result,
+        mapping(vec![
+            (string("a"), Value::Null),
+            (string("b"), Value::Int(2))
+        ])
+    );
+
+    let result = yaml.load_str("? a\n? b\n: c\n").unwrap();
+    assert_eq!(
+        result,
+        mapping(vec![(string("a"), Value::Null), (string("b"), string("c"))])
+    );
+
+    let result = yaml.load_str("?\n: v\n").unwrap();
+    assert_eq!(result, mapping(vec![(Value::Null, string("v"))]));
+
+    let result = yaml.load_str("? incomplete_key").unwrap();
+    assert_eq!(
+        result,
+        mapping(vec![(string("incomplete_key"), Value::Null)])
+    );
+}
+
+#[test]
+fn test_multi_line_complex_key() {
+    let yaml = Yaml::new();
+
+    let result = yaml.load_str("? multi\n  line\n: v\n").unwrap();
+    assert_eq!(result, mapping(vec![(string("multi line"), string("v"))]));
+
+    let result = yaml.load_str("? |\n  block\n: v\n").unwrap();
+    assert_eq!(result, mapping(vec![(string("block\n"), string("v"))]));
+}
@@ -13,7 +13,7 @@ foo: bar
     let result = yaml.load_str(yaml_input).unwrap();
 
     // Should parse the document content correctly
-    if let Value::Mapping(map) = result {
+    if let Value::Mapping(map) = &result {
         assert_eq!(
             map.get(&Value::String("foo".to_string())),
             Some(&Value::String("bar".to_string()))
@@ -106,7 +106,7 @@ key: value
     let yaml = Yaml::new();
     let result = yaml.load_str(yaml_input).unwrap();
 
-    if let Value::Mapping(map) = result {
+    if let Value::Mapping(map) = &result {
         assert_eq!(
             map.get(&Value::String("key".to_string())),
             Some(&Value::String("value".to_string()))
@@ -3,7 +3,7 @@
 #![allow(clippy::expect_fun_call)]
 #![allow(clippy::unreadable_literal)] // Test data with large numbers
 
-use rust_yaml::{Value, Yaml};
+use rust_yaml::{yaml, Value, Yaml};
 
 #[test]
 fn test_empty_input() {
@@ -78,9 +78,6 @@ fn test_ambiguous_yaml_structures() {
     let yaml = Yaml::new();
     // Test some ambiguous cases that might parse differently
     let ambiguous_inputs = [
-        "key: value: extra",     // Multiple colons - might be parsed as string
-        "- - - value",           // Multiple dashes - might be nested sequences
-        "? incomplete_key",      // Incomplete complex key - might be treated as string
         "key:\n invalid_indent", // Invalid indentation - might be lenient
     ];
 
@@ -99,6 +96,67 @@ fn test_ambiguous_yaml_structures() {
     }
 }
 
+#[test]
+fn test_mapping_value_after_value_on_same_line() {
+    let yaml = Yaml::new();
+
+    let error = yaml.load_str("key: value: extra").unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("mapping values are not allowed in this context"));
+
+    let mut expected = indexmap::IndexMap::new();
+    expected.insert(
+        Value::String("key".to_string()),
+        Value::String("value: extra".to_string()),
+    );
+    assert_eq!(
+        yaml.load_str("key: 'value: extra'").unwrap(),
+        Value::Mapping(expected)
+    );
+
+    // Nothing but `:`, a flow indicator, a comment or the line end may follow
+    // a closing quote
+    for input in [
+        "k: \"a\"b",
+        "k: 'a' b",
+        "\"a\"b: 1",
+        "k: \"a\"#c",
+        "[\"a\"b]",
+    ] {
+        let error = yaml.load_str(input).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("Unexpected content after a quoted scalar"),
+            "{input:?}: {error}"
+        );
+    }
+    for input in ["k: \"a\" # c", "\"a\" : 1", "{\"a\":1, 'b': [\"c\"]}"] {
+        assert!(yaml.load_str(input).is_ok(), "{input:?}");
+    }
+}
+
+#[test]
+fn test_compact_nested_sequences() {
+    let yaml = Yaml::new();
+    let nested = Value::Sequence;
+    let string = |value: &str| Value::String(value.to_string());
+
+    assert_eq!(
+        yaml.load_str("- - - value").unwrap(),
+        nested(vec![nested(vec![nested(vec![string("value")])])])
+    );
+    assert_eq!(
+        yaml.load_str("- - a\n  - b\n- c\n").unwrap(),
+        nested(vec![nested(vec![string("a"), string("b")]), string("c")])
+    );
+    assert_eq!(
+        yaml.load_str("-\n  - null\n- null\n").unwrap(),
+        nested(vec![nested(vec![Value::Null]), Value::Null])
+    );
+}
+
 #[test]
 fn test_quoted_string_edge_cases() {
     let yaml = Yaml::new();
@@ -549,6 +607,46 @@ fn test_error_message_quality() {
     }
 }
 
+#[test]
+fn test_unclosed_flow_collection_reports_its_opening() {
+    let yaml = Yaml::new();
+    for (input, kind, line, column) in [
+        ("a: [1, 2", "sequence", 1, 4),
+        ("a: 1\nb: [1, 2\n", "sequence", 2, 4),
+        ("x:\n  y: {a: 1, b: [1, 2]\n", "mapping", 2, 6),
+        ("[1, {a: [2\n", "sequence", 1, 9),
+    ] {
+        let message = yaml.load_str(input).unwrap_err().to_string();
+        let expected = format!("Unclosed {kind} starting at line {line}, column {column}");
+        assert!(
+            message.contains(&expected),
+            "{input:?}: expected {expected:?} in {message}"
+        );
+    }
+}
+
+#[test]
+fn test_block_context_resumes_after_flow_collections() {
+    let yaml = Yaml::new();
+    for (input, expected) in [
+        (
+            "outer:\n  inner: [1]\nnext: 2\n",
+            yaml!({"outer": {"inner": [1]}, "next": 2}),
+        ),
+        (
+            "outer:\n  inner: {a: 1}\n  more: x\nnext: 2\n",
+            yaml!({"outer": {"inner": {"a": 1}, "more": "x"}, "next": 2}),
+        ),
+        ("- [1]\n- [2]\n", yaml!([[1], [2]])),
+        (
+            "- [a, b]\n- key: value\n- {c: d}\n- e\n",
+            yaml!([["a", "b"], {"key": "value"}, {"c": "d"}, "e"]),
+        ),
+    ] {
+        assert_eq!(yaml.load_str(input).unwrap(), expected, "{input:?}");
+    }
+}
+
 #[test]
 fn test_float_edge_cases() {
     let yaml = Yaml::new();
@@ -637,3 +735,96 @@ fn test_sequence_edge_cases() {
         }
     }
 }
+
+#[test]
+fn test_crlf_documents_load_like_lf() {
+    let yaml = Yaml::new();
+    let lf = "name: app\nscript: |\n  make\n  make test\nfolded: >\n  one\n  two\n# comment\nlist:\n  - 1\n  - 2\n";
+    let expected = yaml.load_str(lf).unwrap();
+
+    let crlf = lf.replace('\n', "\r\n");
+    assert_eq!(yaml.load_str(&crlf).unwrap(), expected);
+    let cr = lf.replace('\n', "\r");
+    assert_eq!(yaml.load_str(&cr).unwrap(), expected);
+}
+
+#[test]
+fn test_crlf_error_positions() {
+    let yaml = Yaml::new();
+    let lf = "a: 1\nb: 2\nc: *missing\n";
+    let lf_error = yaml.load_str(lf).unwrap_err();
+    let crlf_error = yaml.load_str(&lf.replace('\n', "\r\n")).unwrap_err();
+    let (lf_position, crlf_position) = (lf_error.position(), crlf_error.position());
+    assert_eq!(
+        lf_position.map(|p| (p.line, p.column)),
+        crlf_position.map(|p| (p.line, p.column))
+    );
+    assert_eq!(crlf_position.map(|p| p.line), Some(3));
+}
+
+#[test]
+fn test_empty_values_before_sibling_keys() {
+    let yaml = Yaml::new();
+    let value = yaml
+        .load_str("a:\nb: 1\nnested:\n  c:\n  d: 2\nanchored: &x\nlast:\n")
+        .unwrap();
+
+    assert_eq!(value.get_str("a"), Some(&Value::Null));
+    assert_eq!(value.get_str("b"), Some(&Value::Int(1)));
+    let nested = value.get_str("nested").unwrap();
+    assert_eq!(nested.get_str("c"), Some(&Value::Null));
+    assert_eq!(nested.get_str("d"), Some(&Value::Int(2)));
+    assert_eq!(value.get_str("anchored"), Some(&Value::Null));
+    assert_eq!(value.get_str("last"), Some(&Value::Null));
+
+    // The empty value ends its mapping when the next key is less indented
+    let value = yaml.load_str("outer:\n  inner:\nnext: 1\n").unwrap();
+    assert_eq!(value["outer"]["inner"], Value::Null);
+    assert_eq!(value.get_str("next"), Some(&Value::Int(1)));
+}
+
+#[test]
+fn test_quoted_keys_start_mappings() {
+    let yaml = Yaml::new();
+    let value = yaml
+        .load_str("\"a/b\": 1\n'c': \"x: y\"\nplain: 3\n")
+        .unwrap();
+
+    assert_eq!(value.get_str("a/b"), Some(&Value::Int(1)));
+    assert_eq!(value.get_str("c"), Some(&Value::String("x: y".to_string())));
+    assert_eq!(value.get_str("plain"), Some(&Value::Int(3)));
+    assert_eq!(
+        yaml.load_str("\"just: text\"\n").unwrap(),
+        Value::String("just: text".to_string())
+    );
+}
+
+#[test]
+fn test_sequences_of_mappings_inside_mappings() {
+    let yaml = Yaml::new();
+    let value = yaml
+        .load_str("a:\n  - x: 1\n    y: 2\n  - z: 3\n  - 4\nb: 5\n")
+        .unwrap();
+
+    let items = value.get_str("a").unwrap().as_sequence().unwrap();
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[0].get_str("x"), Some(&Value::Int(1)));
+    assert_eq!(items[0].get_str("y"), Some(&Value::Int(2)));
+    assert_eq!(items[1].get_str("z"), Some(&Value::Int(3)));
+    assert_eq!(items[2], Value::Int(4));
+    assert_eq!(value.get_str("b"), Some(&Value::Int(5)));
+}
+
+#[test]
+fn test_document_markers_close_open_mappings() {
+    let yaml = Yaml::new();
+    let documents = yaml.load_all_str("a:\n  b: 1\n---\nc: 2\n").unwrap();
+
+    assert_eq!(documents.len(), 2);
+    assert_eq!(
+        documents[0].get_str("a").unwrap().get_str("b"),
+        Some(&Value::Int(1))
+    );
+    assert_eq!(documents[1].get_str("c"), Some(&Value::Int(2)));
+    assert_eq!(documents[0].get_str("c"), None);
+}
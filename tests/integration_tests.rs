@@ -1,7 +1,7 @@
 #![allow(clippy::approx_constant)]
 #![allow(clippy::needless_raw_string_hashes)]
 
-use rust_yaml::{LoaderType, Value, Yaml, YamlConfig};
+use rust_yaml::{FlowStyle, LoaderType, Value, Yaml, YamlConfig};
 
 #[test]
 fn test_basic_scalar_parsing() {
@@ -369,19 +369,14 @@ mixed_array:
                 panic!("Expected nested sequence");
             }
 
-            // Check nested sequence containing a mapping (this is correct YAML behavior)
-            if let Value::Sequence(ref nested_seq) = seq[5] {
-                assert_eq!(nested_seq.len(), 1);
-                if let Value::Mapping(ref nested_map) = nested_seq[0] {
-                    assert_eq!(
-                        nested_map.get(&Value::String("key".to_string())),
-                        Some(&Value::String("nested_value".to_string()))
-                    );
-                } else {
-                    panic!("Expected mapping inside nested sequence");
-                }
+            // Check the mapping item after the flow sequence
+            if let Value::Mapping(ref nested_map) = seq[5] {
+                assert_eq!(
+                    nested_map.get(&Value::String("key".to_string())),
+                    Some(&Value::String("nested_value".to_string()))
+                );
             } else {
-                panic!("Expected nested sequence containing mapping");
+                panic!("Expected mapping");
             }
         } else {
             panic!("Expected sequence");
@@ -390,3 +385,36 @@ mixed_array:
         panic!("Expected mapping");
     }
 }
+
+#[test]
+fn test_flow_style_config() {
+    let input = "name: web\nports:\n  - 80\n  - 443\n";
+    let dump = |config: YamlConfig| {
+        let yaml = Yaml::with_config(config);
+        yaml.dump_str(&yaml.load_str(input).unwrap()).unwrap()
+    };
+
+    let auto = dump(YamlConfig {
+        flow_style: FlowStyle::Auto,
+        ..Default::default()
+    });
+    assert_eq!(auto, "name: web\nports: [80, 443]\n");
+    assert_eq!(
+        Yaml::new().load_str(&auto).unwrap(),
+        Yaml::new().load_str(input).unwrap()
+    );
+
+    let flow = dump(YamlConfig {
+        flow_style: FlowStyle::Flow,
+        ..Default::default()
+    });
+    assert_eq!(flow, "{name: web, ports: [80, 443]}\n");
+
+    // An explicit default_flow_style takes precedence over flow_style
+    let block = dump(YamlConfig {
+        flow_style: FlowStyle::Auto,
+        default_flow_style: Some(false),
+        ..Default::default()
+    });
+    assert_eq!(block, "name: web\nports: \n  - 80\n  - 443\n");
+}
@@ -18,7 +18,7 @@ development:
     let yaml = Yaml::new();
     let result = yaml.load_str(yaml_str).expect("Failed to parse YAML");
 
-    if let Value::Mapping(root) = result {
+    if let Value::Mapping(root) = &result {
         let dev = root
             .get(&Value::String("development".to_string()))
             .expect("Missing 'development' key");
@@ -64,7 +64,7 @@ custom:
     let yaml = Yaml::new();
     let result = yaml.load_str(yaml_str).expect("Failed to parse YAML");
 
-    if let Value::Mapping(root) = result {
+    if let Value::Mapping(root) = &result {
         let custom = root
             .get(&Value::String("custom".to_string()))
             .expect("Missing 'custom' key");
@@ -103,7 +103,7 @@ merged:
     let yaml = Yaml::new();
     let result = yaml.load_str(yaml_str).expect("Failed to parse YAML");
 
-    if let Value::Mapping(root) = result {
+    if let Value::Mapping(root) = &result {
         let merged = root
             .get(&Value::String("merged".to_string()))
             .expect("Missing 'merged' key");
@@ -153,7 +153,7 @@ final:
     let yaml = Yaml::new();
     let result = yaml.load_str(yaml_str).expect("Failed to parse YAML");
 
-    if let Value::Mapping(root) = result {
+    if let Value::Mapping(root) = &result {
         // Check extended first
         let extended = root
             .get(&Value::String("extended".to_string()))
@@ -210,7 +210,7 @@ merged:
     let yaml = Yaml::new();
     let result = yaml.load_str(yaml_str).expect("Failed to parse YAML");
 
-    if let Value::Mapping(root) = result {
+    if let Value::Mapping(root) = &result {
         let merged = root
             .get(&Value::String("merged".to_string()))
             .expect("Missing 'merged' key");
@@ -249,7 +249,7 @@ override:
     let yaml = Yaml::new();
     let result = yaml.load_str(yaml_str).expect("Failed to parse YAML");
 
-    if let Value::Mapping(root) = result {
+    if let Value::Mapping(root) = &result {
         let override_node = root
             .get(&Value::String("override".to_string()))
             .expect("Missing 'override' key");
@@ -288,7 +288,7 @@ merged:
     let yaml = Yaml::new();
     let result = yaml.load_str(yaml_str).expect("Failed to parse YAML");
 
-    if let Value::Mapping(root) = result {
+    if let Value::Mapping(root) = &result {
         let merged = root
             .get(&Value::String("merged".to_string()))
             .expect("Missing 'merged' key");
@@ -364,7 +364,7 @@ list:
     let yaml = Yaml::new();
     let result = yaml.load_str(yaml_str).expect("Failed to parse YAML");
 
-    if let Value::Mapping(root) = result {
+    if let Value::Mapping(root) = &result {
         if let Some(Value::Sequence(list)) = root.get(&Value::String("list".to_string())) {
             // First item should be a mapping with the merge
             if let Value::Mapping(first) = &list[0] {
@@ -0,0 +1,117 @@
+//! Property-based round-trip tests: `load(dump(v)) == v` for generated values
+
+use indexmap::IndexMap;
+use proptest::prelude::*;
+use rust_yaml::{roundtrip_check, roundtrip_check_value, Value};
+
+/// Strings the resolver or scanner treat specially when left unquoted
+const TRICKY_STRINGS: &[&str] = &[
+    "",
+    " ",
+    "null",
+    "Null",
+    "~",
+    "true",
+    "False",
+    "yes",
+    "NO",
+    "on",
+    "Off",
+    "123",
+    "-7",
+    "+1",
+    "0.5",
+    "1e3",
+    ".inf",
+    "-.Inf",
+    ".NaN",
+    "inf",
+    "NaN",
+    "2.1.0",
+    "- item",
+    "key: value",
+    "# comment",
+    "a #b",
+    "[x]",
+    "{y}",
+    "a, b",
+    "*alias",
+    "&anchor",
+    "!tag",
+    "|",
+    ">",
+    "%YAML",
+    "@at",
+    "`tick",
+    "? key",
+    "'single'",
+    "\"double\"",
+    "tab\there",
+    "line\nbreak",
+    "\r",
+    "\u{1}",
+    "\u{7f}",
+    "\u{feff}bom",
+    "ünïcödé",
+    "🚀",
+];
+
+fn arb_string() -> impl Strategy<Value = String> {
+    prop_oneof![
+        prop::sample::select(TRICKY_STRINGS).prop_map(str::to_string),
+        "[a-zA-Z0-9 _.:#,\\-]{0,12}",
+        "\\PC{0,8}",
+    ]
+}
+
+fn arb_scalar() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        any::<i64>().prop_map(Value::Int),
+        prop_oneof![
+            any::<f64>(),
+            Just(f64::INFINITY),
+            Just(f64::NEG_INFINITY),
+            Just(f64::NAN),
+        ]
+        .prop_map(Value::Float),
+        arb_string().prop_map(Value::String),
+    ]
+}
+
+/// Mapping keys are scalars; `<<` is excluded since a merge key cannot
+/// round-trip as an ordinary key
+fn arb_key() -> impl Strategy<Value = Value> {
+    arb_scalar().prop_filter("merge key", |key| key.as_str() != Some("<<"))
+}
+
+fn arb_value() -> impl Strategy<Value = Value> {
+    arb_scalar().prop_recursive(4, 48, 6, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..6).prop_map(Value::Sequence),
+            prop::collection::vec((arb_key(), inner), 0..6).prop_map(|entries| Value::Mapping(
+                entries.into_iter().collect::<IndexMap<_, _>>()
+            )),
+        ]
+    })
+}
+
+proptest! {
+    #[test]
+    fn prop_scalar_roundtrip(value in arb_scalar()) {
+        prop_assert!(roundtrip_check_value(&value).is_ok(), "{:?}", roundtrip_check_value(&value));
+    }
+
+    #[test]
+    fn prop_value_roundtrip(value in arb_value()) {
+        prop_assert!(roundtrip_check_value(&value).is_ok(), "{:?}", roundtrip_check_value(&value));
+    }
+}
+
+#[test]
+fn test_roundtrip_check_documents() {
+    roundtrip_check("a: 1\nb:\n  - x\n  - 'yes'\n---\n[1, 2]\n").unwrap();
+    roundtrip_check("0: null\n1:\n  2: x\n-1: y\n-a: z\n.inf: w\n0 A: v\n").unwrap();
+    assert!(roundtrip_check("a: [").is_err());
+}
@@ -51,7 +51,7 @@ active: true
     assert!(result.is_ok(), "Valid user YAML should pass validation");
 
     let user_value = result.unwrap();
-    if let Value::Mapping(map) = user_value {
+    if let Value::Mapping(map) = &user_value {
         assert_eq!(
             map.get(&Value::String("name".to_string())),
             Some(&Value::String("Alice Johnson".to_string()))
@@ -675,3 +675,87 @@ spec:
     let result = yaml.load_str_with_schema(invalid_deployment, &deployment_schema);
     assert!(result.is_err(), "Invalid apiVersion should fail validation");
 }
+
+// Custom validators return the crate's `Result`, whose error is large
+#[allow(clippy::result_large_err)]
+#[test]
+fn test_load_with_custom_validators() {
+    let mut properties = HashMap::new();
+    properties.insert(
+        "replicas".to_string(),
+        Schema::with_type(ValueType::Integer).rule(SchemaRule::Custom("odd".to_string())),
+    );
+    let schema = Schema::with_type(ValueType::Object).rule(SchemaRule::Properties(properties));
+    let validator = SchemaValidator::new(schema.clone()).with_validator(
+        "odd",
+        Box::new(|value, path| match value {
+            Value::Int(n) if n % 2 == 1 => Ok(()),
+            _ => Err(rust_yaml::Error::config(format!("{path} must be odd"))),
+        }),
+    );
+    let yaml = Yaml::new();
+
+    // Without the validator the custom rule cannot be checked
+    let error = yaml
+        .load_str_with_schema("replicas: 3\n", &schema)
+        .unwrap_err();
+    assert!(error.to_string().contains("Unknown custom validator 'odd'"));
+
+    let value = yaml
+        .load_str_with_validator("replicas: 3\n", &validator)
+        .unwrap();
+    assert_eq!(value["replicas"], Value::Int(3));
+
+    let error = yaml
+        .load_all_str_with_validator("replicas: 3\n---\nreplicas: 4\n", &validator)
+        .unwrap_err();
+    let message = error.to_string();
+    assert!(message.contains("replicas must be odd"), "{message}");
+    assert!(message.contains("line 3"), "{message}");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_load_validated_into_struct() {
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Service {
+        name: String,
+        port: u16,
+    }
+
+    let mut properties = HashMap::new();
+    properties.insert("name".to_string(), Schema::with_type(ValueType::String));
+    properties.insert("port".to_string(), Schema::with_type(ValueType::Integer));
+    let schema = Schema::with_type(ValueType::Object)
+        .rule(SchemaRule::Properties(properties))
+        .rule(SchemaRule::Required(vec!["name".to_string()]));
+    let yaml = Yaml::new();
+
+    let service: Service = yaml
+        .load_validated("name: api\nport: 8080\n", &schema)
+        .unwrap();
+    assert_eq!(
+        service,
+        Service {
+            name: "api".to_string(),
+            port: 8080
+        }
+    );
+
+    // Schema violations are reported before deserializing
+    let error = yaml
+        .load_validated::<Service>("port: 8080\n", &schema)
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("Schema validation failed"),
+        "{error}"
+    );
+
+    // A document the schema accepts but the type does not points at the node
+    let error = yaml
+        .load_validated::<Service>("name: api\nport: 70000\n", &schema)
+        .unwrap_err();
+    let message = error.to_string();
+    assert!(message.contains("cannot deserialize 'port'"), "{message}");
+    assert_eq!(error.position().map(|p| (p.line, p.column)), Some((2, 7)));
+}
@@ -90,6 +90,85 @@ fn test_max_anchor_limit() {
     }
 }
 
+#[test]
+fn test_max_anchor_name_length_limit() {
+    let config = YamlConfig {
+        limits: Limits {
+            max_anchor_name_len: 8,
+            ..Limits::default()
+        },
+        loader_type: LoaderType::Safe,
+        ..YamlConfig::default()
+    };
+    let yaml = Yaml::with_config(config);
+
+    assert!(yaml.load_str("a: &abcdefgh 1\nb: *abcdefgh\n").is_ok());
+    for input in ["a: &abcdefghi 1\n", "a: 1\nb: *abcdefghi\n"] {
+        let error = yaml.load_str(input).unwrap_err().to_string();
+        assert!(
+            error.contains("Maximum") && error.contains("name length 8 exceeded"),
+            "Expected anchor name length error, got: {}",
+            error
+        );
+        assert!(
+            error.contains("line"),
+            "Expected a position, got: {}",
+            error
+        );
+    }
+}
+
+#[test]
+fn test_max_scalar_length_limit() {
+    let config = YamlConfig {
+        limits: Limits {
+            max_scalar_len: 16,
+            ..Limits::default()
+        },
+        loader_type: LoaderType::Safe,
+        ..YamlConfig::default()
+    };
+    let yaml = Yaml::with_config(config);
+
+    let fits = "x".repeat(16);
+    let long = "x".repeat(17);
+    assert!(yaml
+        .load_str(&format!(
+            "a: {fits}
+b: \"{fits}\"
+"
+        ))
+        .is_ok());
+    assert!(yaml
+        .load_str(&format!("a: |\n  {}\n", "x".repeat(15)))
+        .is_ok());
+
+    let inputs = [
+        format!("a: {long}\n"),
+        format!("a: \"{long}\"\n"),
+        format!("a: '{long}'\n"),
+        format!("a: |\n  {long}\n"),
+        format!("a: >\n  {fits}\n  {fits}\n"),
+        format!("a: |+\n{}", "\n".repeat(20)),
+        // The limit is hit before the missing closing quote is noticed
+        format!("a: \"{}", "x".repeat(1000)),
+    ];
+    for input in &inputs {
+        let error = yaml.load_str(input).unwrap_err().to_string();
+        assert!(
+            error.contains("Maximum scalar length 16 exceeded"),
+            "Expected scalar length error for {:?}, got: {}",
+            input,
+            error
+        );
+        assert!(
+            error.contains("line 1"),
+            "Expected the scalar's start position, got: {}",
+            error
+        );
+    }
+}
+
 #[test]
 fn test_max_document_size_limit() {
     // Create a document that exceeds size limit
@@ -278,6 +357,23 @@ fn test_unlimited_config() {
     assert!(result.is_ok());
 }
 
+#[test]
+fn test_unlimited_depth_drops_normally() {
+    // Far deeper than a recursive drop of the loaded value allows
+    let depth = 200_000;
+    let input = format!("{}1{}", "[{k: ".repeat(depth), "}]".repeat(depth));
+    let config = YamlConfig {
+        limits: Limits::unlimited(),
+        ..YamlConfig::default()
+    };
+    let yaml = Yaml::with_config(config);
+
+    let value = yaml.load_str(&input).unwrap();
+    assert!(value.is_sequence());
+    let documents = yaml.load_all_str(&input).unwrap();
+    assert_eq!(documents.len(), 1);
+}
+
 #[test]
 fn test_permissive_config() {
     // Test permissive configuration
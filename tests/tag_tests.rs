@@ -28,7 +28,7 @@ fn test_tag_with_mapping() {
     let result = yaml.load_str(yaml_input).unwrap();
 
     // Should create a mapping
-    if let Value::Mapping(map) = result {
+    if let Value::Mapping(map) = &result {
         assert_eq!(
             map.get(&Value::String("key".to_string())),
             Some(&Value::String("value".to_string()))
@@ -55,7 +55,7 @@ fn test_tag_with_sequence() {
     let result = yaml.load_str(yaml_input).unwrap();
 
     // Should create a sequence
-    if let Value::Sequence(seq) = result {
+    if let Value::Sequence(seq) = &result {
         assert_eq!(seq.len(), 3);
         assert_eq!(seq[0], Value::String("item1".to_string()));
         assert_eq!(seq[1], Value::String("item2".to_string()));
@@ -92,7 +92,7 @@ null: !!null "anything"
     let yaml = Yaml::new();
     let result = yaml.load_str(yaml_input).unwrap();
 
-    if let Value::Mapping(map) = result {
+    if let Value::Mapping(map) = &result {
         assert_eq!(
             map.get(&Value::String("string".to_string())),
             Some(&Value::String("123".to_string()))
@@ -190,7 +190,7 @@ fn test_mixed_tags_in_collection() {
     let yaml = Yaml::new();
     let result = yaml.load_str(yaml_input).unwrap();
 
-    if let Value::Sequence(seq) = result {
+    if let Value::Sequence(seq) = &result {
         assert_eq!(seq[0], Value::String("123".to_string()));
         assert_eq!(seq[1], Value::Int(456));
         assert_eq!(seq[2], Value::Float(3.15));
@@ -210,7 +210,7 @@ ref: *anchor
     let yaml = Yaml::new();
     let result = yaml.load_str(yaml_input).unwrap();
 
-    if let Value::Mapping(map) = result {
+    if let Value::Mapping(map) = &result {
         // Both should be strings due to the !!str tag
         assert_eq!(
             map.get(&Value::String("base".to_string())),
@@ -240,6 +240,28 @@ fn test_invalid_tag_error() {
     assert!(result.is_ok() || result.is_err());
 }
 
+#[test]
+fn test_tag_uri_escapes() {
+    let yaml = Yaml::new();
+    let result = yaml
+        .load_str("%TAG !e! tag:example.com,2024:app/\n---\nid: !e!x%7Ey 1\nlocal: !a%21b 2\n")
+        .unwrap();
+    assert_eq!(
+        result["id"],
+        Value::tagged("tag:example.com,2024:app/x~y", Value::from("1"))
+    );
+    assert_eq!(result["local"], Value::tagged("!a!b", Value::from("2")));
+
+    // Malformed tags are reported where they are written
+    let error = yaml.load_str("a: 1\nb: !bad%zz 2\n").unwrap_err();
+    assert!(error.to_string().contains("line 2, column 4"), "{error}");
+    assert!(error
+        .to_string()
+        .contains("invalid escape in tag '!bad%zz'"));
+    let error = yaml.load_str("b: !b<ad 2\n").unwrap_err();
+    assert!(error.to_string().contains("invalid character '<'"));
+}
+
 #[test]
 fn test_schema_validation() {
     // Test Core schema vs JSON schema differences
@@ -275,3 +297,55 @@ fn test_multiple_documents_with_different_tags() {
     assert_eq!(documents.len(), 2);
     // Both documents should parse successfully with their respective tag directives
 }
+
+// Loading under the `sort-keys` feature reorders the keys this compares
+#[cfg(not(feature = "sort-keys"))]
+#[test]
+fn test_tagged_collections() {
+    let yaml_input = r"
+deployment: !Deployment
+  name: web
+  replicas: 2
+ports: !Ports [80, 443]
+empty: !Empty {}
+id: !Id 42
+";
+
+    let yaml = Yaml::new();
+    let result = yaml.load_str(yaml_input).unwrap();
+
+    let deployment = result["deployment"].as_tagged().unwrap();
+    assert_eq!(deployment.tag, "!Deployment");
+    assert_eq!(deployment.value["name"], Value::from("web"));
+    assert_eq!(deployment.value["replicas"], Value::Int(2));
+    assert_eq!(
+        result["ports"],
+        Value::tagged("!Ports", Value::from(vec![Value::Int(80), Value::Int(443)]))
+    );
+    assert_eq!(result["empty"], Value::tagged("!Empty", Value::mapping()));
+    assert_eq!(result["id"], Value::tagged("!Id", Value::from("42")));
+
+    let dumped = yaml.dump_str(&result).unwrap();
+    assert_eq!(
+        dumped,
+        "deployment: !Deployment\n  name: web\n  replicas: 2\nports: !Ports\n  - 80\n  - 443\n\
+         empty: !Empty {}\nid: !Id \"42\"\n"
+    );
+    assert_eq!(yaml.load_str(&dumped).unwrap(), result);
+}
+
+#[test]
+fn test_tagged_root_collection() {
+    let yaml = Yaml::new();
+    let result = yaml
+        .load_str("!<tag:example.com,2024:widget>\n- id: 1\n")
+        .unwrap();
+    assert_eq!(
+        result.as_tagged().map(|tagged| tagged.tag.as_str()),
+        Some("tag:example.com,2024:widget")
+    );
+
+    let dumped = yaml.dump_str(&result).unwrap();
+    assert_eq!(dumped, "!<tag:example.com,2024:widget>\n- \n  id: 1\n");
+    assert_eq!(yaml.load_str(&dumped).unwrap(), result);
+}